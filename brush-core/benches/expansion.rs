@@ -0,0 +1,146 @@
+#[cfg(unix)]
+mod unix {
+    use criterion::Criterion;
+
+    /// Number of fields produced by the field-splitting and array expansion benchmarks.
+    const MANY_FIELDS_COUNT: usize = 1000;
+
+    /// Number of directories, and of files in each, in the tree used for globbing benchmarks.
+    const GLOB_TREE_DIR_COUNT: usize = 50;
+    const GLOB_TREE_FILES_PER_DIR: usize = 40;
+
+    async fn instantiate_shell() -> brush_core::Shell {
+        let options = brush_core::CreateOptions::default();
+        brush_core::Shell::new(&options).await.unwrap()
+    }
+
+    /// Returns a shell that has first run the given setup script.
+    async fn instantiate_shell_with_setup(setup: &str) -> brush_core::Shell {
+        let mut shell = instantiate_shell().await;
+        run_one_command(&mut shell, setup).await;
+        shell
+    }
+
+    async fn run_one_command(shell: &mut brush_core::Shell, command: &str) {
+        let _ = shell
+            .run_string(command.to_owned(), &shell.default_exec_params())
+            .await
+            .unwrap();
+    }
+
+    /// Creates a directory tree of text (and other) files for globbing over; returns its root.
+    fn create_glob_tree() -> std::path::PathBuf {
+        let root = std::env::temp_dir().join(format!("brush-bench-glob-{}", std::process::id()));
+        for i in 0..GLOB_TREE_DIR_COUNT {
+            let dir = root.join(format!("dir-{i}")).join("nested");
+            std::fs::create_dir_all(&dir).unwrap();
+            for j in 0..GLOB_TREE_FILES_PER_DIR {
+                let extension = if j % 2 == 0 { "txt" } else { "log" };
+                std::fs::write(dir.join(format!("file-{j}.{extension}")), "").unwrap();
+            }
+        }
+
+        root
+    }
+
+    /// Runs the given command in clones of the given shell.
+    fn bench_command(
+        c: &mut Criterion,
+        rt: &tokio::runtime::Runtime,
+        name: &str,
+        shell: &brush_core::Shell,
+        command: &str,
+    ) {
+        c.bench_function(name, |b| {
+            b.iter_batched_ref(
+                || shell.clone(),
+                |s| rt.block_on(run_one_command(s, command)),
+                criterion::BatchSize::SmallInput,
+            );
+        });
+    }
+
+    /// This function defines benchmarks of expansion and interpreter hot paths.
+    pub(crate) fn criterion_benchmark(c: &mut Criterion) {
+        // Construct a runtime for us to run async code on.
+        let rt = tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+
+        // Benchmark: expanding words into many fields, via field splitting and via arrays.
+        let words: Vec<_> = (0..MANY_FIELDS_COUNT).map(|i| format!("word{i}")).collect();
+        let shell = rt.block_on(instantiate_shell_with_setup(
+            format!("WORDS='{}'; ARRAY=({})", words.join(" "), words.join(" ")).as_str(),
+        ));
+        bench_command(
+            c,
+            &rt,
+            "expand_many_fields_by_splitting",
+            &shell,
+            "set -- $WORDS",
+        );
+        bench_command(
+            c,
+            &rt,
+            "expand_many_fields_from_array",
+            &shell,
+            r#"set -- "${ARRAY[@]/#/prefix-}""#,
+        );
+
+        // Benchmark: large brace expansions, both sequences and cross products.
+        let shell = rt.block_on(instantiate_shell());
+        bench_command(c, &rt, "expand_brace_sequence", &shell, ": {1..10000}");
+        bench_command(
+            c,
+            &rt,
+            "expand_brace_cross_product",
+            &shell,
+            ": {a..z}{a..z}{0..9}",
+        );
+
+        // Benchmark: globbing over a large directory tree, with and without globstar.
+        let glob_tree_root = create_glob_tree();
+        let mut shell = rt.block_on(instantiate_shell());
+        shell.set_working_dir(glob_tree_root.as_path()).unwrap();
+        shell.options.enable_star_star_glob = true;
+        bench_command(c, &rt, "expand_glob_in_tree", &shell, ": */nested/*.txt");
+        bench_command(c, &rt, "expand_globstar_in_tree", &shell, ": **/*.txt");
+        let _ = std::fs::remove_dir_all(glob_tree_root);
+
+        // Benchmark: arithmetic in a loop.
+        let shell = rt.block_on(instantiate_shell());
+        bench_command(
+            c,
+            &rt,
+            "arithmetic_loop",
+            &shell,
+            "x=0; for ((i = 0; i < 1000; i++)); do (( x += i * 2 % 7 )); done",
+        );
+
+        // Benchmark: function call overhead, for calls with arguments and for recursion.
+        let shell = rt.block_on(instantiate_shell_with_setup(
+            "f() { :; }; r() { (( $1 > 0 )) && r $(( $1 - 1 )); }",
+        ));
+        bench_command(
+            c,
+            &rt,
+            "function_calls_with_args",
+            &shell,
+            "for ((i = 0; i < 100; i++)); do f a b c; done",
+        );
+        bench_command(c, &rt, "recursive_function_calls", &shell, "r 100");
+    }
+}
+
+#[cfg(unix)]
+criterion::criterion_group! {
+    name = benches;
+    config = criterion::Criterion::default().with_profiler(pprof::criterion::PProfProfiler::new(100, pprof::criterion::Output::Flamegraph(None)));
+    targets = unix::criterion_benchmark
+}
+#[cfg(unix)]
+criterion::criterion_main!(benches);
+
+#[cfg(not(unix))]
+fn main() -> () {}
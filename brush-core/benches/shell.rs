@@ -18,6 +18,67 @@ mod unix {
         brush_core::Shell::new(&options).await.unwrap()
     }
 
+    /// Number of variables defined in shells used to benchmark large environments.
+    const LARGE_ENV_VAR_COUNT: usize = 5000;
+
+    /// Script that runs command substitutions in a loop, used to compare the cost of
+    /// forking a shell against bash.
+    const SUBSHELL_LOOP_SCRIPT: &str = "for ((i = 0; i < 100; i++)); do x=$(:); done";
+
+    async fn instantiate_shell_with_large_env() -> brush_core::Shell {
+        let mut shell = instantiate_shell().await;
+        for i in 0..LARGE_ENV_VAR_COUNT {
+            shell
+                .set_var_str(format!("VAR_{i}"), format!("value of variable {i}"))
+                .unwrap();
+        }
+        shell
+    }
+
+    /// Returns a bash script that defines a large environment and then runs the subshell
+    /// loop script.
+    fn bash_large_env_subshell_loop_script() -> String {
+        let mut script: String = (0..LARGE_ENV_VAR_COUNT)
+            .map(|i| format!("VAR_{i}='value of variable {i}'\n"))
+            .collect();
+        script.push_str(SUBSHELL_LOOP_SCRIPT);
+        script
+    }
+
+    /// Number of directories in the search path used to benchmark command lookup.
+    const LARGE_SEARCH_PATH_DIR_COUNT: usize = 200;
+
+    /// Creates directories, each containing a few executables, making up a large search path
+    /// (reminiscent of those on NixOS); returns the root directory and the search path.
+    fn create_large_search_path() -> (std::path::PathBuf, String) {
+        use std::os::unix::fs::PermissionsExt;
+
+        let root = std::env::temp_dir().join(format!("brush-bench-path-{}", std::process::id()));
+        let mut dirs = vec![];
+        for i in 0..LARGE_SEARCH_PATH_DIR_COUNT {
+            let dir = root.join(format!("pkg-{i}")).join("bin");
+            std::fs::create_dir_all(&dir).unwrap();
+            for j in 0..10 {
+                let file = dir.join(format!("cmd-{i}-{j}"));
+                std::fs::write(&file, "#!/bin/sh\n").unwrap();
+                std::fs::set_permissions(&file, std::fs::Permissions::from_mode(0o755)).unwrap();
+            }
+            dirs.push(dir.to_string_lossy().to_string());
+        }
+
+        (root, dirs.join(":"))
+    }
+
+    async fn instantiate_shell_with_search_path(
+        search_path: &str,
+        cached: bool,
+    ) -> brush_core::Shell {
+        let mut shell = instantiate_shell().await;
+        shell.set_var_str("PATH", search_path).unwrap();
+        shell.options.cache_path_search = cached;
+        shell
+    }
+
     async fn run_one_command(shell: &mut brush_core::Shell, command: &str) {
         let _ = shell
             .run_string(command.to_owned(), &shell.default_exec_params())
@@ -57,6 +118,64 @@ mod unix {
             b.iter(|| black_box(shell.clone()));
         });
 
+        // Benchmark: cloning (i.e., forking) and checkpointing a shell with a large environment.
+        let shell = rt.block_on(instantiate_shell_with_large_env());
+        c.bench_function("clone_shell_object_with_large_env", |b| {
+            b.iter(|| black_box(shell.clone()));
+        });
+        c.bench_function("checkpoint_and_rollback_with_large_env", |b| {
+            b.iter_batched_ref(
+                || shell.clone(),
+                |s| {
+                    let checkpoint = s.checkpoint();
+                    s.set_var_str("VAR_0", "updated").unwrap();
+                    s.rollback(checkpoint);
+                },
+                criterion::BatchSize::SmallInput,
+            );
+        });
+
+        // Benchmark: command substitutions in a shell with a large environment, compared
+        // against bash (if available). N.B. The bash measurement includes process startup
+        // and defining the environment, which are small relative to the loop's forks.
+        c.bench_function("subshell_loop_with_large_env", |b| {
+            b.iter_batched_ref(
+                || shell.clone(),
+                |s| rt.block_on(run_one_command(s, SUBSHELL_LOOP_SCRIPT)),
+                criterion::BatchSize::SmallInput,
+            );
+        });
+        let bash_script = bash_large_env_subshell_loop_script();
+        if std::process::Command::new("bash")
+            .args(["-c", ":"])
+            .status()
+            .is_ok_and(|status| status.success())
+        {
+            c.bench_function("bash_subshell_loop_with_large_env", |b| {
+                b.iter(|| {
+                    std::process::Command::new("bash")
+                        .args(["--norc", "--noprofile", "-c", bash_script.as_str()])
+                        .status()
+                        .unwrap()
+                });
+            });
+        }
+
+        // Benchmark: looking up a command that doesn't exist (e.g., as when highlighting
+        // a partially typed command line) in a large search path, with and without caching
+        // the contents of the search path.
+        let (search_path_root, search_path) = create_large_search_path();
+        for (name, cached) in [
+            ("lookup_missing_command_in_large_path", false),
+            ("lookup_missing_command_in_large_path_with_path_cache", true),
+        ] {
+            let shell = rt.block_on(instantiate_shell_with_search_path(&search_path, cached));
+            c.bench_function(name, |b| {
+                b.iter(|| black_box(shell.find_first_executable_in_path("no-such-command")));
+            });
+        }
+        let _ = std::fs::remove_dir_all(search_path_root);
+
         // Benchmark: parsing and evaluating an arithmetic expression..
         let shell = rt.block_on(instantiate_shell());
         c.bench_function("eval_arithmetic", |b| {
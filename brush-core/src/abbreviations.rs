@@ -0,0 +1,148 @@
+//! Abbreviations, which (in the manner of fish) are expanded in place as they're typed at an
+//! interactive prompt. Unlike an alias, the expansion of an abbreviation is visible to the
+//! user, who may edit it before the command is executed.
+
+use std::collections::HashMap;
+use std::ops::Range;
+
+/// Where in a command line an abbreviation is expanded.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, clap::ValueEnum)]
+pub enum AbbreviationPosition {
+    /// Only where a command name is expected.
+    #[default]
+    #[clap(name = "command")]
+    Command,
+    /// Anywhere in the command line.
+    #[clap(name = "anywhere")]
+    Anywhere,
+}
+
+/// An abbreviation.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Abbreviation {
+    /// The text that the abbreviation expands to.
+    pub expansion: String,
+    /// Where the abbreviation is expanded.
+    pub position: AbbreviationPosition,
+}
+
+/// Words after which a command name is expected.
+const COMMAND_PRECEDING_WORDS: [&str; 10] = [
+    "!", "do", "elif", "else", "if", "then", "time", "until", "while", "{",
+];
+
+/// Characters after which a command name is expected.
+const COMMAND_PRECEDING_CHARS: [char; 5] = [';', '|', '&', '(', '\n'];
+
+/// Finds the abbreviation, if any, to expand for the word that ends at the cursor, which is
+/// about to be completed (e.g., by a space being typed, or by the line being accepted).
+/// Returns the range of the input line occupied by the word along with the abbreviation.
+///
+/// # Arguments
+///
+/// * `abbreviations` - The defined abbreviations, keyed by name.
+/// * `line` - The input line.
+/// * `cursor` - The byte offset of the cursor in the input line.
+pub fn find_expandable<'a>(
+    abbreviations: &'a HashMap<String, Abbreviation>,
+    line: &str,
+    cursor: usize,
+) -> Option<(Range<usize>, &'a Abbreviation)> {
+    let before_cursor = line.get(..cursor)?;
+
+    // The cursor must be at the end of the word.
+    if line[cursor..]
+        .chars()
+        .next()
+        .is_some_and(|c| !c.is_whitespace())
+    {
+        return None;
+    }
+
+    let start = before_cursor
+        .char_indices()
+        .rev()
+        .find(|(_, c)| is_word_boundary(*c))
+        .map_or(0, |(index, c)| index + c.len_utf8());
+
+    let abbreviation = abbreviations.get(&before_cursor[start..])?;
+    if abbreviation.position == AbbreviationPosition::Command
+        && !is_command_position(&before_cursor[..start])
+    {
+        return None;
+    }
+
+    Some((start..cursor, abbreviation))
+}
+
+/// Returns whether a word following the given text would be in command position.
+fn is_command_position(preceding: &str) -> bool {
+    let preceding = preceding.trim_end_matches([' ', '\t']);
+    if preceding.is_empty() || preceding.ends_with(COMMAND_PRECEDING_CHARS) {
+        return true;
+    }
+
+    let last_word = preceding
+        .rsplit(is_word_boundary)
+        .next()
+        .unwrap_or_default();
+
+    let preceding_last_word = &preceding[..preceding.len() - last_word.len()];
+    COMMAND_PRECEDING_WORDS.contains(&last_word) && is_command_position(preceding_last_word)
+}
+
+fn is_word_boundary(c: char) -> bool {
+    c.is_whitespace() || COMMAND_PRECEDING_CHARS.contains(&c)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn abbreviations() -> HashMap<String, Abbreviation> {
+        HashMap::from([
+            (
+                "gco".to_owned(),
+                Abbreviation {
+                    expansion: "git checkout".to_owned(),
+                    position: AbbreviationPosition::Command,
+                },
+            ),
+            (
+                "L".to_owned(),
+                Abbreviation {
+                    expansion: "| less".to_owned(),
+                    position: AbbreviationPosition::Anywhere,
+                },
+            ),
+        ])
+    }
+
+    fn find(line: &str, cursor: usize) -> Option<(Range<usize>, String)> {
+        let abbreviations = abbreviations();
+        find_expandable(&abbreviations, line, cursor)
+            .map(|(range, abbreviation)| (range, abbreviation.expansion.clone()))
+    }
+
+    #[test]
+    fn command_position() {
+        assert_eq!(find("gco", 3), Some((0..3, "git checkout".to_owned())));
+        assert_eq!(find("  gco", 5), Some((2..5, "git checkout".to_owned())));
+        assert_eq!(find("ls;gco", 6), Some((3..6, "git checkout".to_owned())));
+        assert_eq!(
+            find("if true; then gco", 17),
+            Some((14..17, "git checkout".to_owned()))
+        );
+
+        assert_eq!(find("echo gco", 8), None);
+        assert_eq!(find("echo then gco", 13), None);
+        assert_eq!(find("gco", 2), None);
+        assert_eq!(find("gcox", 4), None);
+    }
+
+    #[test]
+    fn anywhere() {
+        assert_eq!(find("cat file L", 10), Some((9..10, "| less".to_owned())));
+        assert_eq!(find("L", 1), Some((0..1, "| less".to_owned())));
+    }
+}
@@ -65,6 +65,7 @@ impl ExpandAndEvaluate for ast::UnexpandedArithmeticExpr {
         if trace_if_needed && shell.options.print_commands_and_arguments {
             shell
                 .trace_command(std::format!("(( {expr} ))"))
+                .await
                 .map_err(|_err| EvalError::TraceError)?;
         }
 
@@ -73,6 +74,45 @@ impl ExpandAndEvaluate for ast::UnexpandedArithmeticExpr {
     }
 }
 
+/// Evaluates a value being assigned to an integer variable (i.e., one declared with
+/// `declare -i`); each already-expanded string in the value is evaluated as an arithmetic
+/// expression. Array keys are left as-is.
+///
+/// # Arguments
+///
+/// * `shell` - The shell to use for evaluation.
+/// * `value` - The value being assigned.
+pub(crate) fn eval_integer_assignment(
+    shell: &mut Shell,
+    value: variables::ShellValueLiteral,
+) -> Result<variables::ShellValueLiteral, EvalError> {
+    fn eval_str(shell: &mut Shell, s: String) -> Result<String, EvalError> {
+        // N.B. An empty value evaluates to 0.
+        if s.trim().is_empty() {
+            return Ok(String::from("0"));
+        }
+
+        let expr =
+            brush_parser::arithmetic::parse(s.as_str()).map_err(|_e| EvalError::ParseError(s))?;
+        Ok(expr.eval(shell)?.to_string())
+    }
+
+    match value {
+        variables::ShellValueLiteral::Scalar(s) => {
+            Ok(variables::ShellValueLiteral::Scalar(eval_str(shell, s)?))
+        }
+        variables::ShellValueLiteral::Array(variables::ArrayLiteral(elements)) => {
+            let elements = elements
+                .into_iter()
+                .map(|(key, value)| Ok((key, eval_str(shell, value)?)))
+                .collect::<Result<Vec<_>, EvalError>>()?;
+            Ok(variables::ShellValueLiteral::Array(
+                variables::ArrayLiteral(elements),
+            ))
+        }
+    }
+}
+
 /// Trait implemented by evaluatable arithmetic expressions.
 pub trait Evaluatable {
     /// Evaluate the given arithmetic expression, returning the resulting numeric value.
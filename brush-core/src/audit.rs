@@ -0,0 +1,235 @@
+//! Auditing of the commands executed by a shell. Unlike history, which is controlled by
+//! the shell's user, audit records are delivered to sinks provided by the embedder of the
+//! shell; see [`AuditSink`].
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use serde::Serialize;
+
+use crate::{error, sys, Shell};
+
+/// A record of a command executed by a shell.
+#[derive(Clone, Debug, Serialize)]
+pub struct AuditRecord {
+    /// When the command was started, in RFC 3339 format.
+    pub timestamp: String,
+    /// The process ID of the shell that executed the command.
+    pub shell_pid: u32,
+    /// The name of the user the shell runs as, if known.
+    pub user: Option<String>,
+    /// The working directory of the shell when the command was started.
+    pub working_dir: PathBuf,
+    /// The command's name followed by its (expanded) arguments.
+    pub argv: Vec<String>,
+    /// The exit status of the command.
+    pub exit_status: u8,
+}
+
+/// A destination for audit records.
+pub trait AuditSink: std::fmt::Debug + Send + Sync {
+    /// Delivers a record of a command that completed. Errors are reported by the shell, but
+    /// don't affect the execution of any commands.
+    ///
+    /// # Arguments
+    ///
+    /// * `record` - The record to deliver.
+    fn record(&self, record: &AuditRecord) -> Result<(), error::Error>;
+}
+
+/// A record of a command that's still executing.
+pub(crate) struct PendingAuditRecord(AuditRecord);
+
+impl PendingAuditRecord {
+    /// Captures the details of a command that's about to be executed.
+    ///
+    /// # Arguments
+    ///
+    /// * `shell` - The shell executing the command.
+    /// * `argv` - The command's name followed by its arguments.
+    pub fn capture(shell: &Shell, argv: Vec<String>) -> Self {
+        Self(AuditRecord {
+            timestamp: chrono::Local::now().to_rfc3339(),
+            shell_pid: std::process::id(),
+            user: sys::users::get_current_username().ok(),
+            working_dir: shell.working_dir.clone(),
+            argv,
+            exit_status: 0,
+        })
+    }
+
+    /// Delivers the record, now that the command has completed, to all of the shell's sinks.
+    ///
+    /// # Arguments
+    ///
+    /// * `shell` - The shell that executed the command.
+    /// * `exit_status` - The exit status of the command.
+    pub fn complete(self, shell: &Shell, exit_status: u8) {
+        let record = AuditRecord {
+            exit_status,
+            ..self.0
+        };
+
+        for sink in &shell.audit_sinks {
+            if let Err(e) = sink.record(&record) {
+                tracing::warn!("failed to deliver audit record: {e}");
+            }
+        }
+    }
+}
+
+/// An [`AuditSink`] that appends records to a file, one JSON object per line. Each record
+/// is given a sequence number (starting at 1 for each sink), so that removed records can be
+/// detected. For the log to be tamper-evident, the file should be one the shell's user can
+/// append to but not otherwise modify (e.g., via `chattr +a` on Linux).
+#[derive(Debug)]
+pub struct JsonLinesFileSink {
+    /// The file, and the sequence number of the last record written to it.
+    state: Mutex<(std::fs::File, u64)>,
+}
+
+impl JsonLinesFileSink {
+    /// Opens the file at the given path for appending records, creating it if needed.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the file.
+    pub fn open(path: &Path) -> Result<Self, error::Error> {
+        let file = std::fs::File::options()
+            .create(true)
+            .append(true)
+            .open(path)?;
+
+        Ok(Self {
+            state: Mutex::new((file, 0)),
+        })
+    }
+}
+
+impl AuditSink for JsonLinesFileSink {
+    fn record(&self, record: &AuditRecord) -> Result<(), error::Error> {
+        #[derive(Serialize)]
+        struct SequencedRecord<'a> {
+            sequence: u64,
+            #[serde(flatten)]
+            record: &'a AuditRecord,
+        }
+
+        let mut state = self
+            .state
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        let (file, sequence) = &mut *state;
+
+        *sequence += 1;
+        let mut line = serde_json::to_vec(&SequencedRecord {
+            sequence: *sequence,
+            record,
+        })?;
+        line.push(b'\n');
+
+        // N.B. The line is written with a single call so that records from shells sharing
+        // the file aren't interleaved.
+        file.write_all(line.as_slice())?;
+
+        Ok(())
+    }
+}
+
+/// An [`AuditSink`] that sends records, formatted as JSON, to the local syslog daemon with
+/// the `authpriv` facility.
+#[cfg(unix)]
+#[derive(Debug)]
+pub struct SyslogSink {
+    /// Tag identifying the source of the messages.
+    tag: String,
+    /// Socket connected to the syslog daemon.
+    socket: std::os::unix::net::UnixDatagram,
+}
+
+#[cfg(unix)]
+impl SyslogSink {
+    /// Paths of the syslog daemon's socket on the supported platforms.
+    const SOCKET_PATHS: [&'static str; 3] = ["/dev/log", "/var/run/syslog", "/var/run/log"];
+
+    /// Connects to the local syslog daemon.
+    ///
+    /// # Arguments
+    ///
+    /// * `tag` - Tag identifying the source of the messages (e.g., `brush`).
+    pub fn connect(tag: &str) -> Result<Self, error::Error> {
+        let socket = std::os::unix::net::UnixDatagram::unbound()?;
+
+        let mut result = Err(std::io::Error::from(std::io::ErrorKind::NotFound));
+        for path in Self::SOCKET_PATHS {
+            result = socket.connect(path);
+            if result.is_ok() {
+                break;
+            }
+        }
+        result?;
+
+        Ok(Self {
+            tag: tag.to_owned(),
+            socket,
+        })
+    }
+}
+
+#[cfg(unix)]
+impl AuditSink for SyslogSink {
+    fn record(&self, record: &AuditRecord) -> Result<(), error::Error> {
+        // Priority is facility `authpriv` (10) * 8 + severity `info` (6).
+        const PRIORITY: u32 = 86;
+
+        let message = std::format!(
+            "<{PRIORITY}>{}[{}]: {}",
+            self.tag,
+            record.shell_pid,
+            serde_json::to_string(record)?
+        );
+
+        self.socket.send(message.as_bytes())?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_lines_file_sink() -> Result<(), error::Error> {
+        let path =
+            std::env::temp_dir().join(std::format!("brush-audit-{}.log", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let sink = JsonLinesFileSink::open(path.as_path())?;
+        for exit_status in [0, 1] {
+            sink.record(&AuditRecord {
+                timestamp: String::from("2025-01-01T00:00:00+00:00"),
+                shell_pid: 1,
+                user: Some(String::from("user")),
+                working_dir: PathBuf::from("/"),
+                argv: vec![String::from("false")],
+                exit_status,
+            })?;
+        }
+
+        let contents = std::fs::read_to_string(&path)?;
+        std::fs::remove_file(&path)?;
+
+        let lines: Vec<_> = contents.lines().collect();
+        assert_eq!(
+            lines,
+            [
+                r#"{"sequence":1,"timestamp":"2025-01-01T00:00:00+00:00","shell_pid":1,"user":"user","working_dir":"/","argv":["false"],"exit_status":0}"#,
+                r#"{"sequence":2,"timestamp":"2025-01-01T00:00:00+00:00","shell_pid":1,"user":"user","working_dir":"/","argv":["false"],"exit_status":1}"#,
+            ]
+        );
+
+        Ok(())
+    }
+}
@@ -5,15 +5,18 @@ use clap::Parser;
 use futures::future::BoxFuture;
 
 use crate::commands;
+use crate::completion;
 use crate::error;
 use crate::ExecutionResult;
 
+mod abbr;
 mod alias;
 mod bg;
 mod bind;
 mod break_;
 mod brushinfo;
 mod builtin_;
+mod caller;
 mod cd;
 mod colon;
 mod command;
@@ -36,6 +39,7 @@ mod getopts;
 mod hash;
 mod help;
 mod jobs;
+mod jump;
 #[cfg(unix)]
 mod kill;
 mod let_;
@@ -45,7 +49,9 @@ mod printf;
 mod pushd;
 mod pwd;
 mod read;
+mod restore;
 mod return_;
+mod save;
 mod set;
 mod shift;
 mod shopt;
@@ -62,9 +68,11 @@ mod unalias;
 mod unimp;
 mod unset;
 mod wait;
+mod whence;
+mod wslpath;
 
 pub(crate) use factory::get_default_builtins;
-pub use factory::{builtin, simple_builtin, SimpleCommand};
+pub use factory::{builtin, default_builtin_names, simple_builtin, SimpleCommand};
 
 /// Macro to define a struct that represents a shell built-in flag argument that can be
 /// enabled or disabled by specifying an option with a leading '+' or '-' character.
@@ -175,6 +183,14 @@ pub type CommandExecuteFunc = fn(
 /// * `content_type` - The type of content to retrieve.
 pub type CommandContentFunc = fn(&str, ContentType) -> Result<String, error::Error>;
 
+/// Type of a function to retrieve the completion spec for an argument to a built-in command.
+///
+/// # Arguments
+///
+/// * `preceding_args` - The arguments preceding the one being completed.
+/// * `token` - The (partial) argument being completed.
+pub type CommandCompletionSpecFunc = fn(&[&str], &str) -> completion::Spec;
+
 /// Trait implemented by built-in shell commands.
 pub trait Command: Parser {
     /// Instantiates the built-in command with the given arguments.
@@ -238,6 +254,39 @@ pub trait Command: Parser {
 
         Ok(s)
     }
+
+    /// Returns the action used to complete values of the given argument, for arguments
+    /// whose values can't be completed based on their clap definition alone.
+    ///
+    /// # Arguments
+    ///
+    /// * `arg_id` - The ID of the argument; `None` for arguments not defined via clap
+    ///   (e.g., declarations).
+    /// * `preceding_args` - The arguments preceding the one being completed.
+    fn get_completion_action(
+        arg_id: Option<&str>,
+        preceding_args: &[&str],
+    ) -> Option<completion::CompleteAction> {
+        let _ = (arg_id, preceding_args);
+        None
+    }
+
+    /// Returns the completion spec for an argument to the command, derived from the
+    /// command's clap definition.
+    ///
+    /// # Arguments
+    ///
+    /// * `preceding_args` - The arguments preceding the one being completed.
+    /// * `token` - The (partial) argument being completed.
+    fn get_completion_spec(preceding_args: &[&str], token: &str) -> completion::Spec {
+        get_builtin_completion_spec(
+            Self::command(),
+            Self::takes_plus_options(),
+            preceding_args,
+            token,
+            Self::get_completion_action,
+        )
+    }
 }
 
 /// Trait implemented by built-in shell commands that take specially handled declarations
@@ -272,6 +321,9 @@ pub struct Registration {
     /// Function to retrieve the builtin's content/help text.
     pub content_func: CommandContentFunc,
 
+    /// Optionally, function to retrieve the completion spec for the builtin's arguments.
+    pub completion_spec_func: Option<CommandCompletionSpecFunc>,
+
     /// Has this registration been disabled?
     pub disabled: bool,
 
@@ -291,6 +343,227 @@ impl Registration {
             ..self
         }
     }
+
+    /// Updates the given registration to mark it as disabled by default.
+    #[must_use]
+    pub fn disabled(self) -> Self {
+        Self {
+            disabled: true,
+            ..self
+        }
+    }
+}
+
+/// Returns whether the given short flag (e.g., `f` for `-f`) appears among the given
+/// arguments, either on its own or as part of a group of short flags (e.g., `-xf`).
+///
+/// # Arguments
+///
+/// * `args` - The arguments to search.
+/// * `flag` - The flag to look for.
+pub(crate) fn args_contain_short_flag(args: &[&str], flag: char) -> bool {
+    args.iter().any(|arg| {
+        arg.strip_prefix('-')
+            .is_some_and(|shorts| !shorts.starts_with('-') && shorts.contains(flag))
+    })
+}
+
+fn get_builtin_completion_spec(
+    mut command: clap::Command,
+    takes_plus_options: bool,
+    preceding_args: &[&str],
+    token: &str,
+    get_completion_action: fn(Option<&str>, &[&str]) -> Option<completion::CompleteAction>,
+) -> completion::Spec {
+    // Make sure implicitly defined arguments (e.g., `--help`) are present.
+    command.build();
+
+    // Find which (sub)command the token is an argument to, and which of its arguments it is.
+    let mut command = &command;
+    let mut positional_index = 0;
+    let mut options_ended = false;
+    let mut option_awaiting_value = None;
+    for arg in preceding_args {
+        if option_awaiting_value.take().is_some() {
+            continue;
+        }
+
+        if !options_ended {
+            if *arg == "--" {
+                options_ended = true;
+                continue;
+            } else if is_option(arg, takes_plus_options) {
+                option_awaiting_value = get_option_awaiting_value(command, arg);
+                continue;
+            }
+        }
+
+        if let Some(subcommand) = command.find_subcommand(arg) {
+            command = subcommand;
+            positional_index = 0;
+        } else {
+            positional_index += 1;
+        }
+    }
+
+    let mut spec = completion::Spec::default();
+
+    if let Some(option) = option_awaiting_value {
+        set_completion_for_arg(
+            &mut spec,
+            Some(option),
+            preceding_args,
+            get_completion_action,
+        );
+    } else if !options_ended
+        && (token.starts_with('-') || (takes_plus_options && token.starts_with('+')))
+    {
+        spec.word_list = Some(get_option_names(command, token.starts_with('+')).join(" "));
+    } else if command.has_subcommands() {
+        let names: Vec<_> = command
+            .get_subcommands()
+            .filter(|subcommand| !subcommand.is_hide_set())
+            .map(clap::Command::get_name)
+            .collect();
+        spec.word_list = Some(names.join(" "));
+    } else {
+        // Positional arguments taking multiple values absorb all remaining arguments.
+        let positionals: Vec<_> = command.get_positionals().collect();
+        let positional = positionals.get(positional_index).copied().or_else(|| {
+            positionals.last().copied().filter(|arg| {
+                arg.get_num_args()
+                    .is_some_and(|num_args| num_args.max_values() > 1)
+            })
+        });
+
+        set_completion_for_arg(&mut spec, positional, preceding_args, get_completion_action);
+    }
+
+    spec
+}
+
+fn is_option(arg: &str, takes_plus_options: bool) -> bool {
+    arg.len() > 1 && (arg.starts_with('-') || (takes_plus_options && arg.starts_with('+')))
+}
+
+/// Returns the option that takes the argument following the given one as its value, if any.
+fn get_option_awaiting_value<'a>(command: &'a clap::Command, arg: &str) -> Option<&'a clap::Arg> {
+    let option = if let Some(long) = arg.strip_prefix("--") {
+        // The value may be provided in the same argument (e.g., `--name=value`).
+        if long.contains('=') {
+            return None;
+        }
+        command.get_arguments().find(|a| a.get_long() == Some(long))
+    } else if let Some(shorts) = arg.strip_prefix('+') {
+        // N.B. Options with a leading '+' are defined as long options (e.g., `--+o`).
+        let last = std::format!("+{}", shorts.chars().last()?);
+        command
+            .get_arguments()
+            .find(|a| a.get_long() == Some(last.as_str()))
+    } else {
+        // In a group of short options (e.g., `-xf`), only the last may take a value
+        // from the following argument; any earlier one takes the rest of the group.
+        let shorts = arg.strip_prefix('-')?;
+        let mut option = None;
+        for (i, c) in shorts.char_indices() {
+            let Some(a) = command.get_arguments().find(|a| a.get_short() == Some(c)) else {
+                continue;
+            };
+
+            if a.get_action().takes_values() {
+                if i + c.len_utf8() == shorts.len() {
+                    option = Some(a);
+                }
+                break;
+            }
+        }
+        option
+    };
+
+    option.filter(|a| a.get_action().takes_values())
+}
+
+/// Returns the names of the options of the given command; if `plus` is set, only options
+/// with a leading '+' are returned.
+fn get_option_names(command: &clap::Command, plus: bool) -> Vec<String> {
+    let mut names = vec![];
+
+    for arg in command.get_arguments() {
+        if arg.is_positional() {
+            continue;
+        }
+
+        if let Some(plus_name) = arg.get_long().filter(|long| long.starts_with('+')) {
+            if plus {
+                names.push(plus_name.to_owned());
+            }
+            continue;
+        }
+
+        if plus || arg.is_hide_set() {
+            continue;
+        }
+
+        if let Some(short) = arg.get_short() {
+            names.push(std::format!("-{short}"));
+        }
+        if let Some(long) = arg.get_long() {
+            names.push(std::format!("--{long}"));
+        }
+    }
+
+    names
+}
+
+fn set_completion_for_arg(
+    spec: &mut completion::Spec,
+    arg: Option<&clap::Arg>,
+    preceding_args: &[&str],
+    get_completion_action: fn(Option<&str>, &[&str]) -> Option<completion::CompleteAction>,
+) {
+    if let Some(action) =
+        get_completion_action(arg.map(|arg| arg.get_id().as_str()), preceding_args)
+    {
+        spec.actions.push(action);
+        return;
+    }
+
+    if let Some(arg) = arg {
+        let possible_values: Vec<_> = arg
+            .get_possible_values()
+            .into_iter()
+            .filter(|value| !value.is_hide_set())
+            .map(|value| value.get_name().to_owned())
+            .collect();
+        if !possible_values.is_empty() {
+            spec.word_list = Some(possible_values.join(" "));
+            return;
+        }
+
+        let action = match arg.get_value_hint() {
+            clap::ValueHint::AnyPath
+            | clap::ValueHint::FilePath
+            | clap::ValueHint::ExecutablePath => Some(completion::CompleteAction::File),
+            clap::ValueHint::DirPath => Some(completion::CompleteAction::Directory),
+            clap::ValueHint::CommandName => Some(completion::CompleteAction::Command),
+            clap::ValueHint::Username => Some(completion::CompleteAction::User),
+            clap::ValueHint::Hostname => Some(completion::CompleteAction::HostName),
+            _ => None,
+        };
+
+        if let Some(action) = action {
+            spec.options.file_names = matches!(
+                action,
+                completion::CompleteAction::File | completion::CompleteAction::Directory
+            );
+            spec.actions.push(action);
+            return;
+        }
+    }
+
+    // Otherwise, fall back to completing file names, as for commands without a spec.
+    spec.options.default = true;
+    spec.options.file_names = true;
 }
 
 fn get_builtin_man_page(_name: &str, _command: &clap::Command) -> Result<String, error::Error> {
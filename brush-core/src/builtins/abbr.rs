@@ -0,0 +1,146 @@
+use clap::Parser;
+use itertools::Itertools;
+use std::io::Write;
+
+use crate::abbreviations::{Abbreviation, AbbreviationPosition};
+use crate::escape::{self, QuoteMode};
+use crate::{builtins, commands};
+
+/// Manage abbreviations, which are expanded in place as they're typed at an interactive
+/// prompt (experimental).
+#[derive(Parser)]
+pub(crate) struct AbbrCommand {
+    /// Add an abbreviation; this is the default if a name and expansion are given.
+    #[arg(short = 'a', long = "add", conflicts_with_all = ["erase", "list", "query", "show"])]
+    add: bool,
+
+    /// Erase the named abbreviations.
+    #[arg(short = 'e', long = "erase", conflicts_with_all = ["list", "query", "show"])]
+    erase: bool,
+
+    /// List the names of all abbreviations.
+    #[arg(short = 'l', long = "list", conflicts_with_all = ["query", "show"])]
+    list: bool,
+
+    /// Check whether the named abbreviations are defined.
+    #[arg(short = 'q', long = "query", conflicts_with = "show")]
+    query: bool,
+
+    /// Display all abbreviations in a reusable format; this is the default if no names are
+    /// given.
+    #[arg(short = 's', long = "show")]
+    show: bool,
+
+    /// Where an added abbreviation is expanded.
+    #[arg(long = "position", value_name = "POSITION", default_value = "command")]
+    position: AbbreviationPosition,
+
+    /// When adding, the abbreviation's name followed by the words of its expansion;
+    /// otherwise, names of abbreviations to operate on.
+    #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+    args: Vec<String>,
+}
+
+impl builtins::Command for AbbrCommand {
+    async fn execute(
+        &self,
+        context: commands::ExecutionContext<'_>,
+    ) -> Result<crate::builtins::ExitCode, crate::error::Error> {
+        if self.erase {
+            return self.erase_abbreviations(context);
+        } else if self.list {
+            for name in context.shell.abbreviations.keys().sorted() {
+                writeln!(context.stdout(), "{name}")?;
+            }
+        } else if self.query {
+            let all_defined = self
+                .args
+                .iter()
+                .all(|name| context.shell.abbreviations.contains_key(name));
+            return Ok(if all_defined {
+                builtins::ExitCode::Success
+            } else {
+                builtins::ExitCode::Custom(1)
+            });
+        } else if self.show || (!self.add && self.args.is_empty()) {
+            for (name, abbreviation) in context
+                .shell
+                .abbreviations
+                .iter()
+                .sorted_by_key(|(name, _)| *name)
+            {
+                let position = match abbreviation.position {
+                    AbbreviationPosition::Command => "",
+                    AbbreviationPosition::Anywhere => "--position anywhere ",
+                };
+                writeln!(
+                    context.stdout(),
+                    "abbr -a {position}-- {} {}",
+                    escape::quote_if_needed(name, QuoteMode::Quote),
+                    escape::quote_if_needed(abbreviation.expansion.as_str(), QuoteMode::Quote)
+                )?;
+            }
+        } else {
+            return self.add_abbreviation(context);
+        }
+
+        Ok(builtins::ExitCode::Success)
+    }
+}
+
+impl AbbrCommand {
+    fn add_abbreviation(
+        &self,
+        context: commands::ExecutionContext<'_>,
+    ) -> Result<builtins::ExitCode, crate::error::Error> {
+        let Some((name, words)) = self.args.split_first() else {
+            return Ok(builtins::ExitCode::InvalidUsage);
+        };
+
+        if name.is_empty() || name.contains(char::is_whitespace) {
+            writeln!(
+                context.stderr(),
+                "{}: {name}: invalid abbreviation name",
+                context.command_name
+            )?;
+            return Ok(builtins::ExitCode::InvalidUsage);
+        } else if words.is_empty() {
+            writeln!(
+                context.stderr(),
+                "{}: {name}: missing expansion",
+                context.command_name
+            )?;
+            return Ok(builtins::ExitCode::InvalidUsage);
+        }
+
+        context.shell.abbreviations.insert(
+            name.clone(),
+            Abbreviation {
+                expansion: words.join(" "),
+                position: self.position,
+            },
+        );
+
+        Ok(builtins::ExitCode::Success)
+    }
+
+    fn erase_abbreviations(
+        &self,
+        context: commands::ExecutionContext<'_>,
+    ) -> Result<builtins::ExitCode, crate::error::Error> {
+        let mut exit_code = builtins::ExitCode::Success;
+
+        for name in &self.args {
+            if context.shell.abbreviations.remove(name).is_none() {
+                writeln!(
+                    context.stderr(),
+                    "{}: {name}: not found",
+                    context.command_name
+                )?;
+                exit_code = builtins::ExitCode::Custom(1);
+            }
+        }
+
+        Ok(exit_code)
+    }
+}
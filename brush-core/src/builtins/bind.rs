@@ -48,6 +48,8 @@ pub(crate) struct BindCommand {
     /// List key sequence bindings.
     #[arg(short = 'X')]
     list_key_seq_bindings: bool,
+    /// Key sequences to bind to named functions, each in the form `"keyseq": function-name`.
+    bindings: Vec<String>,
 }
 
 impl builtins::Command for BindCommand {
@@ -113,6 +115,94 @@ impl builtins::Command for BindCommand {
             return error::unimp("bind -X is not yet implemented");
         }
 
-        Ok(builtins::ExitCode::Success)
+        let mut result = builtins::ExitCode::Success;
+        for binding in &self.bindings {
+            match parse_binding(binding) {
+                Some((_, target)) if target.starts_with(['"', '\'']) => {
+                    return error::unimp("binding key sequences to macros is not yet implemented");
+                }
+                Some((key_seq, function)) => {
+                    let key_bindings = &mut context.shell.key_bindings;
+                    key_bindings.retain(|(existing, _)| *existing != key_seq);
+                    key_bindings.push((key_seq, function));
+                }
+                None => {
+                    writeln!(
+                        context.stderr(),
+                        "{}: {binding}: invalid key binding",
+                        context.command_name
+                    )?;
+                    result = builtins::ExitCode::Custom(1);
+                }
+            }
+        }
+
+        Ok(result)
+    }
+}
+
+/// Parses a binding of the form `"keyseq": function-name` (or `keyname: function-name`),
+/// returning the key sequence (without quotes, with its escapes left as written) and the
+/// name of the function it's bound to.
+///
+/// # Arguments
+///
+/// * `binding` - The binding to parse.
+fn parse_binding(binding: &str) -> Option<(String, String)> {
+    let binding = binding.trim_start();
+
+    let (key_seq, rest) = if let Some(quoted) = binding.strip_prefix('"') {
+        let mut escaped = false;
+        let end = quoted.char_indices().find_map(|(i, c)| {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == '"' {
+                return Some(i);
+            }
+            None
+        })?;
+        (&quoted[..end], &quoted[end + 1..])
+    } else {
+        let end = binding.find(':')?;
+        (binding[..end].trim_end(), &binding[end..])
+    };
+
+    let target = rest.trim_start().strip_prefix(':')?.trim();
+    if key_seq.is_empty() || target.is_empty() {
+        return None;
+    }
+
+    Some((key_seq.to_owned(), target.to_owned()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn binding_parsing() {
+        assert_eq!(
+            parse_binding(r#""\C-x\C-c": copy-line-to-clipboard"#),
+            Some((
+                String::from(r"\C-x\C-c"),
+                String::from("copy-line-to-clipboard")
+            ))
+        );
+        assert_eq!(
+            parse_binding(r#""\"":paste-from-clipboard"#),
+            Some((String::from(r#"\""#), String::from("paste-from-clipboard")))
+        );
+        assert_eq!(
+            parse_binding("Control-y : paste-from-clipboard"),
+            Some((
+                String::from("Control-y"),
+                String::from("paste-from-clipboard")
+            ))
+        );
+        assert_eq!(parse_binding(r#""\C-x" paste-from-clipboard"#), None);
+        assert_eq!(parse_binding(r#""\C-x: paste-from-clipboard"#), None);
+        assert_eq!(parse_binding(r#""\C-x":"#), None);
     }
 }
@@ -1,7 +1,7 @@
 use clap::Parser;
 use std::io::Write;
 
-use crate::{builtins, commands};
+use crate::{builtins, commands, i18n};
 
 /// Directly invokes a built-in, without going through typical search order.
 #[derive(Parser)]
@@ -20,7 +20,13 @@ impl builtins::Command for BuiltinCommand {
         mut context: commands::ExecutionContext<'_>,
     ) -> Result<crate::builtins::ExitCode, crate::error::Error> {
         if let Some(builtin_name) = &self.builtin_name {
-            if let Some(builtin) = context.shell.builtins.get(builtin_name) {
+            // N.B. Builtins disabled with `enable -n` can't be invoked this way either.
+            if let Some(builtin) = context
+                .shell
+                .builtins
+                .get(builtin_name)
+                .filter(|b| !b.disabled)
+            {
                 context.command_name.clone_from(builtin_name);
 
                 let args: Vec<commands::CommandArg> = std::iter::once(builtin_name.into())
@@ -31,7 +37,14 @@ impl builtins::Command for BuiltinCommand {
                     .await
                     .map(|res: builtins::BuiltinResult| res.exit_code)
             } else {
-                writeln!(context.stderr(), "{builtin_name}: command not found")?;
+                writeln!(
+                    context.stderr(),
+                    "{}: {}",
+                    context.command_name,
+                    context
+                        .shell
+                        .localize(&i18n::NOT_A_SHELL_BUILTIN, &[("builtin", builtin_name)])
+                )?;
                 Ok(builtins::ExitCode::Custom(1))
             }
         } else {
@@ -0,0 +1,70 @@
+use clap::Parser;
+use std::io::Write;
+
+use crate::{builtins, commands};
+
+/// Return the context of the current subroutine call.
+#[derive(Parser)]
+pub(crate) struct CallerCommand {
+    /// Index of the call frame to describe, counting outward from the current frame.
+    expr: Option<usize>,
+}
+
+/// A frame on the call stack, as reported by `caller`.
+struct CallerFrame<'a> {
+    name: &'a str,
+    source: &'a str,
+    call_line_number: u32,
+}
+
+impl builtins::Command for CallerCommand {
+    async fn execute(
+        &self,
+        context: commands::ExecutionContext<'_>,
+    ) -> Result<crate::builtins::ExitCode, crate::error::Error> {
+        let mut frames: Vec<_> = context
+            .shell
+            .function_call_stack
+            .iter()
+            .map(|call| CallerFrame {
+                name: call.function_name(),
+                source: call.source(),
+                call_line_number: call.call_line_number(),
+            })
+            .collect();
+
+        // When running a script, its top level is reported as an additional `main` frame.
+        if let Some(script) = context.shell.script_call_stack.back() {
+            frames.push(CallerFrame {
+                name: "main",
+                source: script.as_str(),
+                call_line_number: 0,
+            });
+        }
+
+        let output = if let Some(index) = self.expr {
+            let (Some(frame), Some(calling_frame)) = (frames.get(index), frames.get(index + 1))
+            else {
+                return Ok(builtins::ExitCode::Custom(1));
+            };
+
+            std::format!(
+                "{} {} {}",
+                frame.call_line_number,
+                calling_frame.name,
+                calling_frame.source
+            )
+        } else {
+            let Some(frame) = frames.first() else {
+                return Ok(builtins::ExitCode::Custom(1));
+            };
+
+            let calling_source = frames.get(1).map_or("NULL", |f| f.source);
+            std::format!("{} {calling_source}", frame.call_line_number)
+        };
+
+        writeln!(context.stdout(), "{output}")?;
+
+        Ok(builtins::ExitCode::Success)
+    }
+}
@@ -1,5 +1,5 @@
 use std::io::Write;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use clap::Parser;
 
@@ -26,7 +26,9 @@ pub(crate) struct CdCommand {
     file_with_xattr_as_dir: bool,
 
     /// By default it is the value of the HOME shell variable. If `TARGET_DIR` is "-", it is
-    /// converted to $OLDPWD.
+    /// converted to $OLDPWD. If it's "-N", the directory N entries back in the shell's
+    /// directory history is changed to (a brush extension).
+    #[arg(value_hint = clap::ValueHint::DirPath, allow_negative_numbers = true)]
     target_dir: Option<PathBuf>,
 }
 
@@ -44,6 +46,14 @@ impl builtins::Command for CdCommand {
             return crate::error::unimp("options to cd");
         }
 
+        if let Some(steps) = self
+            .target_dir
+            .as_deref()
+            .and_then(Self::try_get_history_steps)
+        {
+            return Self::navigate_directory_history(context, steps);
+        }
+
         let mut should_print = false;
         let target_dir = if let Some(target_dir) = &self.target_dir {
             // `cd -', equivalent to `cd $OLDPWD'
@@ -55,6 +65,9 @@ impl builtins::Command for CdCommand {
                     writeln!(context.stderr(), "OLDPWD not set")?;
                     return Ok(builtins::ExitCode::Custom(1));
                 }
+            } else if let Some(value) = Self::try_get_cdable_var(&context, target_dir) {
+                should_print = true;
+                PathBuf::from(value)
             } else {
                 // TODO: remove clone, and use temporary lifetime extension after rust 1.75
                 target_dir.clone()
@@ -86,3 +99,59 @@ impl builtins::Command for CdCommand {
         Ok(builtins::ExitCode::Success)
     }
 }
+
+impl CdCommand {
+    /// Parses an argument of the form `-N`, yielding N.
+    fn try_get_history_steps(target_dir: &Path) -> Option<isize> {
+        let steps = target_dir.to_str()?.strip_prefix('-')?;
+        if !steps.starts_with(|c: char| c.is_ascii_digit()) {
+            return None;
+        }
+
+        steps.parse().ok()
+    }
+
+    /// Changes to the directory the given number of entries back in the directory history,
+    /// displaying it (as with `cd -`).
+    fn navigate_directory_history(
+        context: commands::ExecutionContext<'_>,
+        steps: isize,
+    ) -> Result<builtins::ExitCode, crate::error::Error> {
+        match context.shell.navigate_directory_history(-steps) {
+            Ok(Some(dir)) => {
+                writeln!(context.stdout(), "{}", dir.display())?;
+                Ok(builtins::ExitCode::Success)
+            }
+            Ok(None) => {
+                writeln!(
+                    context.stderr(),
+                    "cd: -{steps}: directory history has fewer entries"
+                )?;
+                Ok(builtins::ExitCode::Custom(1))
+            }
+            Err(e) => {
+                writeln!(context.stderr(), "cd: {e}")?;
+                Ok(builtins::ExitCode::Custom(1))
+            }
+        }
+    }
+
+    /// With `cdable_vars` enabled, an argument that isn't a directory may instead name
+    /// a variable whose value is the directory to change to.
+    fn try_get_cdable_var(
+        context: &commands::ExecutionContext<'_>,
+        target_dir: &Path,
+    ) -> Option<String> {
+        if !context.shell.options.cdable_vars
+            || context.shell.get_absolute_path(target_dir).is_dir()
+        {
+            return None;
+        }
+
+        context
+            .shell
+            .env
+            .get_str(target_dir.to_string_lossy())
+            .map(|value| value.to_string())
+    }
+}
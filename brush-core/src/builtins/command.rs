@@ -1,7 +1,8 @@
 use clap::Parser;
-use std::{fmt::Display, io::Write, path::Path};
+use std::io::Write;
 
-use crate::{builtins, commands, error, shell, sys::fs::PathExt, ExecutionResult};
+use crate::commandresolution::{self, ResolvedType};
+use crate::{builtins, commands, error, ExecutionResult};
 
 /// Directly invokes an external command, without going through typical search order.
 #[derive(Parser)]
@@ -36,78 +37,57 @@ impl builtins::Command for CommandCommand {
         }
 
         if self.print_description || self.print_verbose_description {
-            if let Some(found_cmd) = self.try_find_command(context.shell) {
-                if self.print_description {
-                    writeln!(context.stdout(), "{found_cmd}")?;
-                } else {
-                    match found_cmd {
-                        FoundCommand::Builtin(_name) => {
-                            writeln!(context.stdout(), "{} is a shell builtin", self.command_name)?;
-                        }
-                        FoundCommand::External(path) => {
-                            writeln!(context.stdout(), "{} is {path}", self.command_name)?;
-                        }
-                    }
-                }
-                Ok(builtins::ExitCode::Success)
-            } else {
-                if self.print_verbose_description {
-                    writeln!(
-                        context.stderr(),
-                        "command: {}: not found",
-                        self.command_name
-                    )?;
-                }
-                Ok(builtins::ExitCode::Custom(1))
-            }
+            self.describe_commands(&context)
         } else {
             self.execute_command(context).await
         }
     }
 }
 
-enum FoundCommand {
-    Builtin(String),
-    External(String),
-}
-
-impl Display for FoundCommand {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            FoundCommand::Builtin(name) => write!(f, "{name}"),
-            FoundCommand::External(path) => write!(f, "{path}"),
-        }
-    }
-}
-
 impl CommandCommand {
-    #[allow(clippy::unwrap_in_result)]
-    fn try_find_command(&self, shell: &mut shell::Shell) -> Option<FoundCommand> {
-        // Look in path.
-        if self.command_name.contains(std::path::MAIN_SEPARATOR) {
-            let candidate_path = shell.get_absolute_path(Path::new(&self.command_name));
-            if candidate_path.executable() {
-                Some(FoundCommand::External(
-                    candidate_path
-                        .canonicalize()
-                        .unwrap()
-                        .to_string_lossy()
-                        .to_string(),
-                ))
+    /// Describes how each of the given names would be resolved as a command, in the
+    /// same order that the shell would consider them.
+    fn describe_commands(
+        &self,
+        context: &commands::ExecutionContext<'_>,
+    ) -> Result<builtins::ExitCode, error::Error> {
+        let mut result = builtins::ExitCode::Success;
+
+        for name in std::iter::once(&self.command_name).chain(self.args.iter()) {
+            let Some(resolved_type) =
+                commandresolution::resolve_types(context.shell, name, false, false)
+                    .into_iter()
+                    .next()
+            else {
+                if self.print_verbose_description {
+                    writeln!(context.stderr(), "command: {name}: not found")?;
+                }
+                result = builtins::ExitCode::Custom(1);
+                continue;
+            };
+
+            if self.print_verbose_description {
+                writeln!(
+                    context.stdout(),
+                    "{}",
+                    resolved_type.describe(context.shell, name)
+                )?;
             } else {
-                None
-            }
-        } else {
-            if let Some(builtin_cmd) = shell.builtins.get(self.command_name.as_str()) {
-                if !builtin_cmd.disabled {
-                    return Some(FoundCommand::Builtin(self.command_name.clone()));
+                match resolved_type {
+                    ResolvedType::Alias(value) => {
+                        writeln!(context.stdout(), "alias {name}='{value}'")?;
+                    }
+                    ResolvedType::Keyword | ResolvedType::Function(_) | ResolvedType::Builtin => {
+                        writeln!(context.stdout(), "{name}")?;
+                    }
+                    ResolvedType::File { path, .. } => {
+                        writeln!(context.stdout(), "{}", path.to_string_lossy())?;
+                    }
                 }
             }
-
-            shell
-                .find_first_executable_in_path_using_cache(&self.command_name)
-                .map(|path| FoundCommand::External(path.to_string_lossy().to_string()))
         }
+
+        Ok(result)
     }
 
     async fn execute_command(
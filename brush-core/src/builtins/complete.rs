@@ -1,11 +1,11 @@
-use clap::{arg, Parser};
+use clap::{arg, Parser, ValueEnum};
 use std::collections::HashMap;
 use std::fmt::Write as _;
 use std::io::Write;
 
 use crate::builtins;
 use crate::commands;
-use crate::completion::{self, CompleteAction, CompleteOption, Spec};
+use crate::completion::{self, CompleteAction, CompleteOption, ExternalCompletionProtocol, Spec};
 use crate::error;
 use crate::escape;
 
@@ -35,6 +35,10 @@ pub(crate) struct CommonCompleteCommandArgs {
     #[arg(short = 'C', allow_hyphen_values = true)]
     command: Option<String>,
 
+    /// Protocol spoken by the command given with -C.
+    #[arg(long = "protocol", value_name = "PROTOCOL", default_value = "bash")]
+    command_protocol: ExternalCompletionProtocol,
+
     #[arg(short = 'X', allow_hyphen_values = true)]
     filter_pattern: Option<String>,
 
@@ -123,6 +127,7 @@ impl CommonCompleteCommandArgs {
             word_list: self.word_list.clone(),
             function_name: self.function_name.clone(),
             command: self.command.clone(),
+            command_protocol: self.command_protocol,
             filter_pattern,
             filter_pattern_excludes,
             prefix: self.prefix.clone(),
@@ -347,6 +352,7 @@ impl CompleteCommand {
                 CompleteAction::HostName => "-A hostname",
                 CompleteAction::Job => "-j",
                 CompleteAction::Keyword => "-k",
+                CompleteAction::RankedDirectory => "-A rankeddir",
                 CompleteAction::Running => "-A running",
                 CompleteAction::Service => "-s",
                 CompleteAction::SetOpt => "-A setopt",
@@ -408,6 +414,12 @@ impl CompleteCommand {
                 " -C {}",
                 escape::force_quote(command, escape::QuoteMode::Quote)
             )?;
+
+            if spec.command_protocol != ExternalCompletionProtocol::Bash {
+                if let Some(protocol) = spec.command_protocol.to_possible_value() {
+                    write!(s, " --protocol {}", protocol.get_name())?;
+                }
+            }
         }
         if let Some(filter_pattern) = &spec.filter_pattern {
             write!(
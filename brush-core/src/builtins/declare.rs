@@ -3,9 +3,9 @@ use itertools::Itertools;
 use std::io::Write;
 
 use crate::{
-    builtins, commands,
+    builtins, commands, completion,
     env::{EnvironmentLookup, EnvironmentScope},
-    error,
+    error, functions,
     variables::{
         self, ArrayLiteral, ShellValue, ShellValueLiteral, ShellValueUnsetType, ShellVariable,
         ShellVariableUpdateTransform,
@@ -115,6 +115,22 @@ impl builtins::Command for DeclareCommand {
         true
     }
 
+    fn get_completion_action(
+        arg_id: Option<&str>,
+        preceding_args: &[&str],
+    ) -> Option<completion::CompleteAction> {
+        // Declarations aren't defined via clap.
+        if arg_id.is_some() {
+            None
+        } else if builtins::args_contain_short_flag(preceding_args, 'f')
+            || builtins::args_contain_short_flag(preceding_args, 'F')
+        {
+            Some(completion::CompleteAction::Function)
+        } else {
+            Some(completion::CompleteAction::Variable)
+        }
+    }
+
     async fn execute(
         &self,
         mut context: commands::ExecutionContext<'_>,
@@ -125,6 +141,15 @@ impl builtins::Command for DeclareCommand {
             _ => DeclareVerb::Declare,
         };
 
+        if matches!(verb, DeclareVerb::Local) && !context.shell.in_function() {
+            writeln!(
+                context.stderr(),
+                "{}: can only be used in a function",
+                context.command_name
+            )?;
+            return Ok(builtins::ExitCode::Custom(1));
+        }
+
         // TODO: implement declare -I
         if self.locals_inherit_from_prev_scope {
             writeln!(
@@ -188,14 +213,10 @@ impl DeclareCommand {
 
         if self.function_names_only || self.function_names_or_defs_only {
             if let Some(func_registration) = context.shell.funcs.get(name) {
-                if self.function_names_only {
-                    if self.print {
-                        writeln!(context.stdout(), "declare -f {name}")?;
-                    } else {
-                        writeln!(context.stdout(), "{name}")?;
-                    }
+                if self.function_names_only && !self.print {
+                    writeln!(context.stdout(), "{name}")?;
                 } else {
-                    writeln!(context.stdout(), "{}", func_registration.definition)?;
+                    self.display_function(context, name, func_registration, self.print)?;
                 }
                 Ok(true)
             } else {
@@ -235,10 +256,28 @@ impl DeclareCommand {
         declaration: &commands::CommandArg,
         verb: DeclareVerb,
     ) -> Result<bool, error::Error> {
+        // `local -` makes the shell's `set` options local to the calling function.
+        if matches!(declaration, commands::CommandArg::String(s) if s == "-") {
+            if matches!(verb, DeclareVerb::Local) {
+                return Ok(context.shell.save_set_options_until_function_return());
+            }
+
+            writeln!(
+                context.stderr(),
+                "{}: `-': not a valid identifier",
+                context.command_name
+            )?;
+            return Ok(false);
+        }
+
         let create_var_local = matches!(verb, DeclareVerb::Local)
             || (context.shell.in_function() && !self.create_global);
 
         if self.function_names_or_defs_only || self.function_names_only {
+            if let Some(export) = self.make_exported.to_bool() {
+                return self.update_function_export(context, declaration, export);
+            }
+
             return self.try_display_declaration(context, declaration, verb);
         }
 
@@ -253,6 +292,22 @@ impl DeclareCommand {
             EnvironmentLookup::Anywhere
         };
 
+        // Values assigned to integer variables are evaluated arithmetically.
+        let becomes_integer = self.make_integer.to_bool().unwrap_or_else(|| {
+            context
+                .shell
+                .env
+                .get_using_policy(name.as_str(), lookup)
+                .is_some_and(|var| var.is_treated_as_integer())
+        });
+
+        let initial_value = match initial_value {
+            Some(initial_value) if becomes_integer => Some(
+                crate::arithmetic::eval_integer_assignment(context.shell, initial_value)?,
+            ),
+            initial_value => initial_value,
+        };
+
         // Look up the variable.
         if let Some(var) = context
             .shell
@@ -275,6 +330,22 @@ impl DeclareCommand {
 
             self.apply_attributes_after_update(var, verb)?;
         } else {
+            // A local variable may not shadow a readonly one.
+            if create_var_local
+                && context
+                    .shell
+                    .env
+                    .get_using_policy(name.as_str(), EnvironmentLookup::Anywhere)
+                    .is_some_and(|var| var.is_readonly())
+            {
+                writeln!(
+                    context.stderr(),
+                    "{}: {name}: readonly variable",
+                    context.command_name
+                )?;
+                return Ok(false);
+            }
+
             let unset_type = if self.make_indexed_array.is_some() {
                 ShellValueUnsetType::IndexedArray
             } else if self.make_associative_array.is_some() {
@@ -285,7 +356,23 @@ impl DeclareCommand {
                 ShellValueUnsetType::Untyped
             };
 
-            let mut var = ShellVariable::new(ShellValue::Unset(unset_type));
+            let inherited = if create_var_local {
+                Self::inherited_local(context, name.as_str())
+            } else {
+                None
+            };
+
+            let mut var = if let Some(mut inherited) = inherited {
+                if self.make_associative_array.is_some() {
+                    inherited.convert_to_associative_array()?;
+                }
+                if self.make_indexed_array.is_some() {
+                    inherited.convert_to_indexed_array()?;
+                }
+                inherited
+            } else {
+                ShellVariable::new(ShellValue::Unset(unset_type))
+            };
 
             self.apply_attributes_before_update(&mut var)?;
 
@@ -307,6 +394,25 @@ impl DeclareCommand {
         Ok(true)
     }
 
+    /// Returns the variable a new local should start out as a copy of, if any. Like bash, we
+    /// inherit from a command's temporary environment (e.g., `v=1 func`), or from any enclosing
+    /// scope with `localvar_inherit` enabled.
+    fn inherited_local(
+        context: &crate::commands::ExecutionContext<'_>,
+        name: &str,
+    ) -> Option<ShellVariable> {
+        let (scope, var) = context.shell.env.get(name)?;
+        if scope != EnvironmentScope::Command
+            && !context.shell.options.local_vars_inherit_value_and_attrs
+        {
+            return None;
+        }
+
+        let mut var = var.clone();
+        var.unset_treat_as_nameref();
+        Some(var)
+    }
+
     #[allow(clippy::unwrap_in_result)]
     fn declaration_to_name_and_value(
         declaration: &commands::CommandArg,
@@ -498,16 +604,64 @@ impl DeclareCommand {
         context: &mut crate::commands::ExecutionContext<'_>,
     ) -> Result<(), error::Error> {
         for (name, registration) in context.shell.funcs.iter().sorted_by_key(|v| v.0) {
-            if self.function_names_only {
-                writeln!(context.stdout(), "declare -f {name}")?;
-            } else {
-                writeln!(context.stdout(), "{}", registration.definition)?;
+            self.display_function(context, name, registration, true)?;
+        }
+
+        Ok(())
+    }
+
+    /// Displays the given function: just its attributes if only names were requested;
+    /// otherwise, its definition, followed by its attributes if `show_attributes` is set
+    /// and it has any.
+    fn display_function(
+        &self,
+        context: &crate::commands::ExecutionContext<'_>,
+        name: &str,
+        registration: &functions::FunctionRegistration,
+        show_attributes: bool,
+    ) -> Result<(), error::Error> {
+        let flags = if registration.is_exported() {
+            "fx"
+        } else {
+            "f"
+        };
+
+        if self.function_names_only {
+            writeln!(context.stdout(), "declare -{flags} {name}")?;
+        } else {
+            writeln!(context.stdout(), "{}", registration.definition)?;
+            if show_attributes && registration.is_exported() {
+                writeln!(context.stdout(), "declare -{flags} {name}")?;
             }
         }
 
         Ok(())
     }
 
+    fn update_function_export(
+        &self,
+        context: &mut crate::commands::ExecutionContext<'_>,
+        declaration: &commands::CommandArg,
+        export: bool,
+    ) -> Result<bool, error::Error> {
+        let registration = match declaration {
+            commands::CommandArg::String(name) => context.shell.funcs.get_mut(name),
+            commands::CommandArg::Assignment(_) => None,
+        };
+
+        if let Some(registration) = registration {
+            if export {
+                registration.export();
+            } else {
+                registration.unexport();
+            }
+            Ok(true)
+        } else {
+            writeln!(context.stderr(), "declare: {declaration}: not found")?;
+            Ok(false)
+        }
+    }
+
     #[allow(clippy::unnecessary_wraps)]
     fn apply_attributes_before_update(&self, var: &mut ShellVariable) -> Result<(), error::Error> {
         if let Some(value) = self.make_integer.to_bool() {
@@ -1,4 +1,5 @@
-use std::path::Path;
+use std::io::Write;
+use std::path::{Path, PathBuf};
 
 use clap::Parser;
 
@@ -23,16 +24,38 @@ impl builtins::Command for DotCommand {
         // TODO: Handle trap inheritance.
         let script_args: Vec<_> = self.script_args.iter().map(|a| a.as_str()).collect();
 
+        // With `sourcepath` enabled, a name without a slash is first looked for in PATH. Outside
+        // of POSIX mode, we fall back to the current directory if it's not found there.
+        let script_path = if context.shell.options.source_builtin_searches_path
+            && !self.script_path.contains('/')
+        {
+            match find_in_path(&context, self.script_path.as_str()) {
+                Some(path) => path,
+                None if context.shell.options.posix_mode => {
+                    writeln!(
+                        context.stderr(),
+                        "{}: {}: file not found",
+                        context.command_name,
+                        self.script_path
+                    )?;
+                    return Ok(builtins::ExitCode::Custom(1));
+                }
+                None => PathBuf::from(&self.script_path),
+            }
+        } else {
+            PathBuf::from(&self.script_path)
+        };
+
         let params = context.params.clone();
         let result = context
             .shell
-            .source(
-                Path::new(&self.script_path),
-                script_args.as_slice(),
-                &params,
-            )
+            .source_in_current_context(script_path.as_path(), script_args.as_slice(), &params)
             .await?;
 
+        if result.exit_shell {
+            return Ok(builtins::ExitCode::ExitShell(result.exit_code));
+        }
+
         if result.exit_code != 0 {
             return Ok(builtins::ExitCode::Custom(result.exit_code));
         }
@@ -40,3 +63,15 @@ impl builtins::Command for DotCommand {
         Ok(builtins::ExitCode::Success)
     }
 }
+
+fn find_in_path(context: &commands::ExecutionContext<'_>, name: &str) -> Option<PathBuf> {
+    context
+        .shell
+        .env
+        .get_str("PATH")
+        .unwrap_or_default()
+        .split(':')
+        .filter(|dir| !dir.is_empty())
+        .map(|dir| context.shell.get_absolute_path(&Path::new(dir).join(name)))
+        .find(|candidate| candidate.is_file())
+}
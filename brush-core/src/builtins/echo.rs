@@ -22,20 +22,61 @@ pub(crate) struct EchoCommand {
     /// Tokens to echo to standard output.
     #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
     args: Vec<String>,
+
+    /// All arguments given, including any options.
+    #[arg(skip)]
+    raw_args: Vec<String>,
 }
 
 impl builtins::Command for EchoCommand {
-    /// Override the default [`builtins::Command::new`] function to handle clap's limitation related
-    /// to `--`. See [`builtins::parse_known`] for more information
-    /// TODO: we can safely remove this after the issue is resolved
+    /// Override the default [`builtins::Command::new`] function to parse options as bash
+    /// does: they're only recognized up until the first argument that isn't made up
+    /// entirely of option letters, so `--` and (e.g.) `-x` are echoed as-is.
     fn new<I>(args: I) -> Result<Self, clap::Error>
     where
         I: IntoIterator<Item = String>,
     {
-        let (mut this, rest_args) = crate::builtins::try_parse_known::<EchoCommand>(args)?;
-        if let Some(args) = rest_args {
-            this.args.extend(args);
+        // N.B. The first argument is the name of the command.
+        let raw_args: Vec<String> = args.into_iter().skip(1).collect();
+
+        let mut this = Self {
+            no_trailing_newline: false,
+            interpret_backslash_escapes: false,
+            no_interpret_backslash_escapes: false,
+            args: vec![],
+            raw_args,
+        };
+
+        let option_count = this
+            .raw_args
+            .iter()
+            .take_while(|arg| {
+                arg.strip_prefix('-').is_some_and(|letters| {
+                    !letters.is_empty() && letters.chars().all(|c| matches!(c, 'n' | 'e' | 'E'))
+                })
+            })
+            .count();
+
+        // Later options take precedence over earlier ones.
+        for letter in this.raw_args[..option_count]
+            .iter()
+            .flat_map(|arg| arg.chars().skip(1))
+        {
+            match letter {
+                'n' => this.no_trailing_newline = true,
+                'e' => {
+                    this.interpret_backslash_escapes = true;
+                    this.no_interpret_backslash_escapes = false;
+                }
+                _ => {
+                    this.interpret_backslash_escapes = false;
+                    this.no_interpret_backslash_escapes = true;
+                }
+            }
         }
+
+        this.args = this.raw_args[option_count..].to_vec();
+
         Ok(this)
     }
 
@@ -43,35 +84,53 @@ impl builtins::Command for EchoCommand {
         &self,
         context: commands::ExecutionContext<'_>,
     ) -> Result<crate::builtins::ExitCode, crate::error::Error> {
-        let mut trailing_newline = !self.no_trailing_newline;
-        let mut s;
-        if self.interpret_backslash_escapes {
-            s = String::new();
-            for (i, arg) in self.args.iter().enumerate() {
-                if i > 0 {
-                    s.push(' ');
-                }
+        let xpg_echo = context.shell.options.echo_builtin_expands_escape_sequences;
+
+        // As in bash, with both `xpg_echo` and POSIX mode enabled, no options are recognized
+        // and escapes are always interpreted. Otherwise, with `xpg_echo` enabled, escapes are
+        // interpreted unless explicitly disabled.
+        let (args, interpret_backslash_escapes, mut trailing_newline) =
+            if xpg_echo && context.shell.options.posix_mode {
+                (&self.raw_args, true, true)
+            } else {
+                (
+                    &self.args,
+                    self.interpret_backslash_escapes
+                        || (xpg_echo && !self.no_interpret_backslash_escapes),
+                    !self.no_trailing_newline,
+                )
+            };
+
+        // N.B. Escapes may produce arbitrary bytes (e.g., `\0377`), so we assemble the
+        // output as bytes rather than as a string.
+        let mut output: Vec<u8> = vec![];
+        for (i, arg) in args.iter().enumerate() {
+            if i > 0 {
+                output.push(b' ');
+            }
 
-                let (expanded_arg, keep_going) = escape::expand_backslash_escapes(
+            if interpret_backslash_escapes {
+                let (mut expanded_arg, keep_going) = escape::expand_backslash_escapes(
                     arg.as_str(),
                     escape::EscapeExpansionMode::EchoBuiltin,
                 )?;
-                s.push_str(&String::from_utf8_lossy(expanded_arg.as_slice()));
+                output.append(&mut expanded_arg);
 
+                // `\c` suppresses all further output, including the trailing newline.
                 if !keep_going {
                     trailing_newline = false;
                     break;
                 }
+            } else {
+                output.extend_from_slice(arg.as_bytes());
             }
-        } else {
-            s = self.args.join(" ");
         }
 
         if trailing_newline {
-            s.push('\n');
+            output.push(b'\n');
         }
 
-        write!(context.stdout(), "{s}")?;
+        context.stdout().write_all(output.as_slice())?;
         context.stdout().flush()?;
 
         Ok(builtins::ExitCode::Success)
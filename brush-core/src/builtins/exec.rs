@@ -1,5 +1,5 @@
 use clap::Parser;
-use std::{borrow::Cow, os::unix::process::CommandExt};
+use std::{borrow::Cow, io::Write, os::unix::process::CommandExt, path::Path};
 
 use crate::{builtins, commands, error};
 
@@ -32,27 +32,75 @@ impl builtins::Command for ExecCommand {
             return Ok(builtins::ExitCode::Success);
         }
 
-        let mut argv0 = Cow::Borrowed(self.name_for_argv0.as_ref().unwrap_or(&self.args[0]));
+        let command_name = self.args[0].as_str();
+
+        let mut argv0 = Cow::Borrowed(self.name_for_argv0.as_deref().unwrap_or(command_name));
 
         if self.exec_as_login {
             argv0 = Cow::Owned(std::format!("-{argv0}"));
         }
 
-        let mut cmd = commands::compose_std_command(
+        // N.B. The command is resolved using the shell's own PATH (and cache), even if it's
+        // to be run with an empty environment.
+        let command_path = if command_name.contains(std::path::MAIN_SEPARATOR) {
+            Some(context.shell.get_absolute_path(Path::new(command_name)))
+        } else {
+            context
+                .shell
+                .find_first_executable_in_path_using_cache(command_name)
+        };
+
+        let Some(command_path) = command_path else {
+            writeln!(
+                context.stderr(),
+                "{}: {command_name}: not found",
+                context.command_name
+            )?;
+            return Ok(exec_failed(&context, 127));
+        };
+
+        let mut cmd = match commands::compose_std_command(
             context.shell,
-            &self.args[0],
-            argv0.as_str(),
+            command_path.to_string_lossy().as_ref(),
+            &argv0,
             &self.args[1..],
             context.params.open_files.clone(),
             self.empty_environment,
-        )?;
+        ) {
+            Ok(cmd) => cmd,
+            Err(e @ error::Error::CommandDeniedByPolicy(_)) => {
+                writeln!(context.stderr(), "{e}")?;
+                return Ok(builtins::ExitCode::Custom(126));
+            }
+            Err(e) => return Err(e),
+        };
 
         let exec_error = cmd.exec();
 
         if exec_error.kind() == std::io::ErrorKind::NotFound {
-            Ok(builtins::ExitCode::Custom(127))
+            writeln!(
+                context.stderr(),
+                "{}: {command_name}: not found",
+                context.command_name
+            )?;
+            Ok(exec_failed(&context, 127))
         } else {
-            Err(error::Error::from(exec_error))
+            writeln!(
+                context.stderr(),
+                "{}: {command_name}: cannot execute: {exec_error}",
+                context.command_name
+            )?;
+            Ok(exec_failed(&context, 126))
         }
     }
 }
+
+/// Returns the exit code for a failed exec. Unless `execfail` is enabled, a failure to exec
+/// causes non-interactive shells to exit.
+fn exec_failed(context: &commands::ExecutionContext<'_>, exit_code: u8) -> builtins::ExitCode {
+    if context.shell.options.interactive || context.shell.options.exit_on_exec_fail {
+        builtins::ExitCode::Custom(exit_code)
+    } else {
+        builtins::ExitCode::ExitShell(exit_code)
+    }
+}
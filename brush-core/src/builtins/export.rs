@@ -3,7 +3,7 @@ use itertools::Itertools;
 use std::io::Write;
 
 use crate::{
-    builtins, commands,
+    builtins, commands, completion,
     env::{EnvironmentLookup, EnvironmentScope},
     variables,
 };
@@ -38,18 +38,50 @@ impl builtins::DeclarationCommand for ExportCommand {
 }
 
 impl builtins::Command for ExportCommand {
+    fn get_completion_action(
+        arg_id: Option<&str>,
+        preceding_args: &[&str],
+    ) -> Option<completion::CompleteAction> {
+        // Declarations aren't defined via clap.
+        if arg_id.is_some() {
+            None
+        } else if builtins::args_contain_short_flag(preceding_args, 'f') {
+            Some(completion::CompleteAction::Function)
+        } else {
+            Some(completion::CompleteAction::Variable)
+        }
+    }
+
     async fn execute(
         &self,
         context: commands::ExecutionContext<'_>,
     ) -> Result<crate::builtins::ExitCode, crate::error::Error> {
+        if self.names_are_functions {
+            return self.execute_for_functions(context);
+        }
+
+        let mut result = builtins::ExitCode::Success;
+
         if !self.declarations.is_empty() {
             for decl in &self.declarations {
                 match decl {
+                    commands::CommandArg::String(s) if s == "-" => {
+                        writeln!(
+                            context.stderr(),
+                            "{}: `-': not a valid identifier",
+                            context.command_name
+                        )?;
+                        result = builtins::ExitCode::Custom(1);
+                    }
                     commands::CommandArg::String(s) => {
                         // Try to find the variable already present; if we find it, then mark it
-                        // exported.
+                        // exported (or un-exported, as requested).
                         if let Some((_, variable)) = context.shell.env.get_mut(s) {
-                            variable.export();
+                            if self.unexport {
+                                variable.unexport();
+                            } else {
+                                variable.export();
+                            }
                         }
                     }
                     commands::CommandArg::Assignment(assignment) => {
@@ -76,12 +108,17 @@ impl builtins::Command for ExportCommand {
                             }
                         };
 
-                        // Update the variable with the provided value and then mark it exported.
+                        // Update the variable with the provided value and then mark it exported
+                        // (or un-exported, as requested).
                         context.shell.env.update_or_add(
                             name,
                             value,
                             |var| {
-                                var.export();
+                                if self.unexport {
+                                    var.unexport();
+                                } else {
+                                    var.export();
+                                }
                                 Ok(())
                             },
                             EnvironmentLookup::Anywhere,
@@ -104,6 +141,51 @@ impl builtins::Command for ExportCommand {
             }
         }
 
-        Ok(builtins::ExitCode::Success)
+        Ok(result)
+    }
+}
+
+impl ExportCommand {
+    fn execute_for_functions(
+        &self,
+        context: commands::ExecutionContext<'_>,
+    ) -> Result<builtins::ExitCode, crate::error::Error> {
+        if self.declarations.is_empty() {
+            // Display exported functions, sorted by name.
+            for (name, registration) in context.shell.funcs.iter().sorted_by_key(|v| v.0) {
+                if registration.is_exported() {
+                    writeln!(context.stdout(), "{}", registration.definition)?;
+                    writeln!(context.stdout(), "declare -fx {name}")?;
+                }
+            }
+
+            return Ok(builtins::ExitCode::Success);
+        }
+
+        let mut result = builtins::ExitCode::Success;
+
+        for decl in &self.declarations {
+            let registration = match decl {
+                commands::CommandArg::String(name) => context.shell.funcs.get_mut(name),
+                commands::CommandArg::Assignment(_) => None,
+            };
+
+            if let Some(registration) = registration {
+                if self.unexport {
+                    registration.unexport();
+                } else {
+                    registration.export();
+                }
+            } else {
+                writeln!(
+                    context.stderr(),
+                    "{}: {decl}: not a function",
+                    context.command_name
+                )?;
+                result = builtins::ExitCode::Custom(1);
+            }
+        }
+
+        Ok(result)
     }
 }
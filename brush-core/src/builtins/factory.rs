@@ -28,6 +28,7 @@ pub fn simple_builtin<B: SimpleCommand + Send + Sync>() -> builtins::Registratio
     builtins::Registration {
         execute_func: exec_simple_builtin::<B>,
         content_func: B::get_content,
+        completion_spec_func: None,
         disabled: false,
         special_builtin: false,
         declaration_builtin: false,
@@ -40,6 +41,7 @@ pub fn builtin<B: builtins::Command + Send + Sync>() -> builtins::Registration {
     builtins::Registration {
         execute_func: exec_builtin::<B>,
         content_func: get_builtin_content::<B>,
+        completion_spec_func: Some(B::get_completion_spec),
         disabled: false,
         special_builtin: false,
         declaration_builtin: false,
@@ -50,6 +52,7 @@ fn decl_builtin<B: builtins::DeclarationCommand + Send + Sync>() -> builtins::Re
     builtins::Registration {
         execute_func: exec_declaration_builtin::<B>,
         content_func: get_builtin_content::<B>,
+        completion_spec_func: Some(B::get_completion_spec),
         disabled: false,
         special_builtin: false,
         declaration_builtin: true,
@@ -136,7 +139,10 @@ async fn exec_declaration_builtin_impl<T: builtins::DeclarationCommand + Send +
 
     for (i, arg) in args.into_iter().enumerate() {
         match arg {
-            CommandArg::String(s) if i == 0 || s.starts_with('-') || s.starts_with('+') => {
+            // N.B. A lone `-` is a declaration (e.g., as in `local -`), not an option.
+            CommandArg::String(s)
+                if i == 0 || (s != "-" && (s.starts_with('-') || s.starts_with('+'))) =>
+            {
                 options.push(s);
             }
             _ => declarations.push(arg),
@@ -221,7 +227,6 @@ pub(crate) fn get_default_builtins(
     m.insert("hash".into(), builtin::<hash::HashCommand>());
     m.insert("help".into(), builtin::<help::HelpCommand>());
     m.insert("jobs".into(), builtin::<jobs::JobsCommand>());
-    #[cfg(unix)]
     m.insert("kill".into(), builtin::<kill::KillCommand>());
     m.insert("local".into(), decl_builtin::<declare::DeclareCommand>());
     m.insert("pwd".into(), builtin::<pwd::PwdCommand>());
@@ -239,11 +244,13 @@ pub(crate) fn get_default_builtins(
 
     if !options.sh_mode {
         m.insert("builtin".into(), builtin::<builtin_::BuiltinCommand>());
+        m.insert("caller".into(), builtin::<caller::CallerCommand>());
         m.insert("declare".into(), decl_builtin::<declare::DeclareCommand>());
         m.insert("echo".into(), builtin::<echo::EchoCommand>());
         m.insert("enable".into(), builtin::<enable::EnableCommand>());
         m.insert("let".into(), builtin::<let_::LetCommand>());
         m.insert("mapfile".into(), builtin::<mapfile::MapFileCommand>());
+        m.insert("readarray".into(), builtin::<mapfile::MapFileCommand>());
         m.insert("printf".into(), builtin::<printf::PrintfCommand>());
         m.insert("shopt".into(), builtin::<shopt::ShoptCommand>());
         m.insert("source".into(), builtin::<dot::DotCommand>().special());
@@ -267,17 +274,44 @@ pub(crate) fn get_default_builtins(
         m.insert("bind".into(), builtin::<bind::BindCommand>());
 
         // TODO: Unimplemented builtins
-        m.insert("caller".into(), builtin::<unimp::UnimplementedCommand>());
         m.insert("disown".into(), builtin::<unimp::UnimplementedCommand>());
         m.insert("history".into(), builtin::<unimp::UnimplementedCommand>());
         m.insert("logout".into(), builtin::<unimp::UnimplementedCommand>());
-        m.insert("readarray".into(), builtin::<unimp::UnimplementedCommand>());
     }
 
     //
     // Brush-specific builtins.
     //
+    m.insert("abbr".into(), builtin::<abbr::AbbrCommand>().disabled());
     m.insert("brushinfo".into(), builtin::<brushinfo::BrushInfoCommand>());
+    m.insert("j".into(), builtin::<jump::JumpCommand>().disabled());
+    m.insert(
+        "restore".into(),
+        builtin::<restore::RestoreCommand>().disabled(),
+    );
+    m.insert("save".into(), builtin::<save::SaveCommand>().disabled());
+    m.insert("whence".into(), builtin::<whence::WhenceCommand>());
+    m.insert(
+        "wslpath".into(),
+        builtin::<wslpath::WslPathCommand>().disabled(),
+    );
 
     m
 }
+
+/// Returns the names of the builtins enabled by default in a shell created with the given
+/// options, in sorted order.
+///
+/// # Arguments
+///
+/// * `options` - The options the shell would be created with.
+pub fn default_builtin_names(options: &crate::CreateOptions) -> Vec<String> {
+    let mut names: Vec<_> = get_default_builtins(options)
+        .into_iter()
+        .filter(|(_, registration)| !registration.disabled)
+        .map(|(name, _)| name)
+        .collect();
+
+    names.sort();
+    names
+}
@@ -2,7 +2,7 @@ use std::{borrow::Cow, collections::HashMap, io::Write};
 
 use clap::Parser;
 
-use crate::{builtins, commands, variables};
+use crate::{builtins, commands, error, variables, Shell};
 
 /// Parse command options.
 #[derive(Parser)]
@@ -40,29 +40,14 @@ impl builtins::Command for GetOptsCommand {
         &self,
         context: commands::ExecutionContext<'_>,
     ) -> Result<crate::builtins::ExitCode, crate::error::Error> {
-        let mut args = HashMap::<char, bool>::new();
-        let mut treat_unknown_options_as_failure = true;
+        let spec = OptionsSpec::parse(self.options_string.as_str());
 
-        // Build the args map
-        let mut last_char = None;
-        for c in self.options_string.chars() {
-            if c == ':' {
-                if let Some(last_char) = last_char {
-                    args.insert(last_char, true);
-                    continue;
-                } else if args.is_empty() {
-                    // This is the first character of the options string.
-                    // Its presence indicates a request for unknown
-                    // options *not* to be treated as failures.
-                    treat_unknown_options_as_failure = false;
-                } else {
-                    return Ok(builtins::ExitCode::InvalidUsage);
-                }
-            }
-
-            args.insert(c, false);
-            last_char = Some(c);
-        }
+        // With no arguments given, the positional parameters are parsed.
+        let args = if self.args.is_empty() {
+            context.shell.positional_parameters.clone()
+        } else {
+            self.args.clone()
+        };
 
         // If unset, assume OPTIND is 1.
         let mut next_index: usize = context
@@ -76,149 +61,220 @@ impl builtins::Command for GetOptsCommand {
             return Ok(builtins::ExitCode::InvalidUsage);
         }
 
-        let mut new_optarg = None;
-        let new_optind;
-        let mut variable_value;
-        let exit_code;
-
-        // See if there are any args left to parse.
-        let mut next_index_zero_based = next_index - 1;
-        if next_index_zero_based < self.args.len() {
-            let arg = self.args[next_index_zero_based].as_str();
-
-            // See if this is an option.
-            if arg.starts_with('-') && arg != "--" {
-                // Figure out how far into this option we are.
-                const DEFAULT_NEXT_CHAR_INDEX: usize = 1;
-                let next_char_index = context
-                    .shell
-                    .env
-                    .get_str(VAR_GETOPTS_NEXT_CHAR_INDEX)
-                    .map_or(DEFAULT_NEXT_CHAR_INDEX, |s| {
-                        s.parse().unwrap_or(DEFAULT_NEXT_CHAR_INDEX)
-                    });
-
-                // Find the char.
-                let c = arg.chars().nth(next_char_index).unwrap();
-                let is_last_char_in_option = next_char_index == arg.len() - 1;
-
-                // Look up the char.
-                let mut is_error = false;
-                if let Some(takes_arg) = args.get(&c) {
-                    variable_value = String::from(c);
+        // Figure out how far into the current argument we are. What we recorded is only
+        // meaningful if OPTIND hasn't since been changed (e.g., reset to 1 to parse a new
+        // argument list) and the argument is the one we recorded it for.
+        let next_char_index = context
+            .shell
+            .env
+            .get_str(VAR_GETOPTS_NEXT_CHAR_INDEX)
+            .and_then(|s| parse_next_char_index(s.as_ref()))
+            .filter(|(index, char_index, arg)| {
+                *index == next_index
+                    && args.get(next_index - 1).is_some_and(|a| a == arg)
+                    && *char_index < arg.chars().count()
+            })
+            .map_or(1, |(_, char_index, _)| char_index);
 
-                    if *takes_arg {
-                        // If the option takes a value but it's not the last option in this
-                        // argument, then this is an error.
-                        if is_last_char_in_option {
-                            next_index += 1;
-                            next_index_zero_based += 1;
-
-                            if next_index_zero_based >= self.args.len() {
-                                return Ok(builtins::ExitCode::Custom(1));
-                            }
-
-                            new_optarg = Some(self.args[next_index_zero_based].clone());
-                        } else {
-                            is_error = true;
-                        }
-                    } else {
-                        new_optarg = None;
-                    }
-                } else {
-                    // Set to satisfy compiler.
-                    variable_value = String::from("?");
+        // See if there are any options left to parse.
+        let Some(arg) = args.get(next_index - 1) else {
+            return finish_parsing(context.shell, self.variable_name.as_str(), next_index);
+        };
 
-                    is_error = true;
-                }
+        if next_char_index == 1 {
+            if arg == "--" {
+                return finish_parsing(context.shell, self.variable_name.as_str(), next_index + 1);
+            } else if !arg.starts_with('-') || arg == "-" {
+                return finish_parsing(context.shell, self.variable_name.as_str(), next_index);
+            }
+        }
 
-                if is_error {
-                    // Unknown option; set variable to '?' and OPTARG to the unknown option (sans
-                    // hyphen).
-                    variable_value = String::from("?");
-                    if !treat_unknown_options_as_failure {
-                        new_optarg = Some(String::from(c));
-                    } else {
-                        new_optarg = None;
-                    }
-
-                    // TODO: honor OPTERR=0 indicating suppression of error messages
-                    if treat_unknown_options_as_failure {
-                        writeln!(context.stderr(), "getopts: illegal option -- {c}")?;
-                    }
-                }
+        let mut remaining_chars = arg.chars().skip(next_char_index);
+        let Some(c) = remaining_chars.next() else {
+            return finish_parsing(context.shell, self.variable_name.as_str(), next_index);
+        };
+        let rest_of_arg: String = remaining_chars.collect();
 
-                if is_last_char_in_option {
-                    // We're done with this argument, so unset the internal char index variable
-                    // and request an update to OPTIND.
-                    new_optind = next_index + 1;
-                    context.shell.env.unset(VAR_GETOPTS_NEXT_CHAR_INDEX)?;
-                } else {
-                    // We have more to go in this argument, so update the internal char index
-                    // and request that OPTIND not be updated.
-                    new_optind = next_index;
-                    context.shell.env.update_or_add(
-                        VAR_GETOPTS_NEXT_CHAR_INDEX,
-                        variables::ShellValueLiteral::Scalar((next_char_index + 1).to_string()),
-                        |_| Ok(()),
-                        crate::env::EnvironmentLookup::Anywhere,
-                        crate::env::EnvironmentScope::Global,
-                    )?;
-                }
+        // Move on to the next character, or the next argument if this was the last.
+        let mut next_char_index = Some(next_char_index + 1);
+        if rest_of_arg.is_empty() {
+            next_index += 1;
+            next_char_index = None;
+        }
 
-                exit_code = builtins::ExitCode::Success;
-            } else {
-                variable_value = String::from("?");
-                new_optarg = None;
+        let report_errors = !spec.silent
+            && context
+                .shell
+                .env
+                .get_str("OPTERR")
+                .map_or(true, |value| value != "0");
+
+        let variable_value;
+        let mut new_optarg = None;
+        let mut error_message = None;
 
-                // If it was "--" we encountered, then skip past it.
-                if arg == "--" {
-                    new_optind = next_index + 1;
+        match spec.options.get(&c) {
+            None => {
+                // Unknown option; in silent mode, OPTARG receives the option (sans hyphen).
+                variable_value = String::from("?");
+                if spec.silent {
+                    new_optarg = Some(String::from(c));
                 } else {
-                    new_optind = next_index;
+                    error_message = Some(std::format!("illegal option -- {c}"));
+                }
+            }
+            Some(false) => {
+                variable_value = String::from(c);
+            }
+            Some(true) => {
+                // The option's argument is either the rest of this argument or the whole of
+                // the next.
+                if !rest_of_arg.is_empty() {
+                    variable_value = String::from(c);
+                    new_optarg = Some(rest_of_arg);
+                    next_index += 1;
+                    next_char_index = None;
+                } else if let Some(value) = args.get(next_index - 1) {
+                    variable_value = String::from(c);
+                    new_optarg = Some(value.clone());
+                    next_index += 1;
+                } else if spec.silent {
+                    // Missing argument; in silent mode, this is reported with ':' rather
+                    // than '?', and OPTARG receives the option.
+                    variable_value = String::from(":");
+                    new_optarg = Some(String::from(c));
+                } else {
+                    variable_value = String::from("?");
+                    error_message = Some(std::format!("option requires an argument -- {c}"));
                 }
-
-                // Note that we're done parsing options.
-                exit_code = builtins::ExitCode::Custom(1);
             }
-        } else {
-            variable_value = String::from("?");
-            new_optarg = None;
-            new_optind = next_index;
-            exit_code = builtins::ExitCode::Custom(1);
         }
 
-        // Update variable value.
-        context.shell.env.update_or_add(
-            self.variable_name.as_str(),
-            variables::ShellValueLiteral::Scalar(variable_value),
-            |_| Ok(()),
-            crate::env::EnvironmentLookup::Anywhere,
-            crate::env::EnvironmentScope::Global,
-        )?;
+        if let Some(error_message) = error_message {
+            if report_errors {
+                let shell_name = context.shell.shell_name.as_deref().unwrap_or("getopts");
+                writeln!(context.stderr(), "{shell_name}: {error_message}")?;
+            }
+        }
 
-        // Update OPTARG
-        if let Some(new_optarg) = new_optarg {
+        // Record how far into the current argument we are, if we're not done with it.
+        if let Some(next_char_index) = next_char_index {
             context.shell.env.update_or_add(
-                "OPTARG",
-                variables::ShellValueLiteral::Scalar(new_optarg),
+                VAR_GETOPTS_NEXT_CHAR_INDEX,
+                variables::ShellValueLiteral::Scalar(std::format!(
+                    "{next_index}:{next_char_index}:{arg}"
+                )),
                 |_| Ok(()),
                 crate::env::EnvironmentLookup::Anywhere,
                 crate::env::EnvironmentScope::Global,
             )?;
         } else {
-            let _ = context.shell.env.unset("OPTARG")?;
+            context.shell.env.unset(VAR_GETOPTS_NEXT_CHAR_INDEX)?;
         }
 
-        // Update OPTIND
-        context.shell.env.update_or_add(
-            "OPTIND",
-            variables::ShellValueLiteral::Scalar(new_optind.to_string()),
+        update_vars(
+            context.shell,
+            self.variable_name.as_str(),
+            variable_value,
+            new_optarg,
+            next_index,
+        )?;
+
+        // N.B. Errors in the options parsed are reported via the variable, not the result.
+        Ok(builtins::ExitCode::Success)
+    }
+}
+
+/// Parsed form of the option specification given to `getopts`.
+struct OptionsSpec {
+    /// Maps each recognized option to whether it takes an argument.
+    options: HashMap<char, bool>,
+    /// Whether errors are reported silently (i.e., via the variable and OPTARG), as
+    /// requested by a leading ':'.
+    silent: bool,
+}
+
+impl OptionsSpec {
+    fn parse(spec: &str) -> Self {
+        let (silent, spec) = match spec.strip_prefix(':') {
+            Some(spec) => (true, spec),
+            None => (false, spec),
+        };
+
+        let mut options = HashMap::new();
+        let mut chars = spec.chars().peekable();
+        while let Some(c) = chars.next() {
+            if c == ':' {
+                continue;
+            }
+
+            let takes_arg = chars.next_if_eq(&':').is_some();
+            options.insert(c, takes_arg);
+        }
+
+        Self { options, silent }
+    }
+}
+
+/// Parses the recorded position within an argument being parsed: the value of OPTIND at
+/// the time, the index of the next character, and the argument itself.
+fn parse_next_char_index(value: &str) -> Option<(usize, usize, String)> {
+    let mut parts = value.splitn(3, ':');
+    let index = parts.next()?.parse().ok()?;
+    let char_index = parts.next()?.parse().ok()?;
+    let arg = parts.next()?.to_owned();
+    Some((index, char_index, arg))
+}
+
+/// Completes parsing once no options remain, leaving OPTIND at the given index (i.e., that
+/// of the first non-option argument).
+fn finish_parsing(
+    shell: &mut Shell,
+    variable_name: &str,
+    next_index: usize,
+) -> Result<builtins::ExitCode, error::Error> {
+    shell.env.unset(VAR_GETOPTS_NEXT_CHAR_INDEX)?;
+    update_vars(shell, variable_name, String::from("?"), None, next_index)?;
+    Ok(builtins::ExitCode::Custom(1))
+}
+
+fn update_vars(
+    shell: &mut Shell,
+    variable_name: &str,
+    variable_value: String,
+    new_optarg: Option<String>,
+    new_optind: usize,
+) -> Result<(), error::Error> {
+    // Update variable value.
+    shell.env.update_or_add(
+        variable_name,
+        variables::ShellValueLiteral::Scalar(variable_value),
+        |_| Ok(()),
+        crate::env::EnvironmentLookup::Anywhere,
+        crate::env::EnvironmentScope::Global,
+    )?;
+
+    // Update OPTARG
+    if let Some(new_optarg) = new_optarg {
+        shell.env.update_or_add(
+            "OPTARG",
+            variables::ShellValueLiteral::Scalar(new_optarg),
             |_| Ok(()),
             crate::env::EnvironmentLookup::Anywhere,
             crate::env::EnvironmentScope::Global,
         )?;
-
-        Ok(exit_code)
+    } else {
+        let _ = shell.env.unset("OPTARG")?;
     }
+
+    // Update OPTIND
+    shell.env.update_or_add(
+        "OPTIND",
+        variables::ShellValueLiteral::Scalar(new_optind.to_string()),
+        |_| Ok(()),
+        crate::env::EnvironmentLookup::Anywhere,
+        crate::env::EnvironmentScope::Global,
+    )?;
+
+    Ok(())
 }
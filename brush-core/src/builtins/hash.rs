@@ -1,8 +1,9 @@
 use clap::Parser;
 use std::{io::Write, path::PathBuf};
 
-use crate::{builtins, commands};
+use crate::{builtins, commands, error};
 
+/// Manage the table of remembered command locations.
 #[derive(Parser)]
 pub(crate) struct HashCommand {
     /// Remove entries associated with the given names.
@@ -36,6 +37,9 @@ impl builtins::Command for HashCommand {
     ) -> Result<crate::builtins::ExitCode, crate::error::Error> {
         let mut result = builtins::ExitCode::Success;
 
+        // Make sure we don't operate on locations remembered for a previous PATH.
+        context.shell.sync_program_location_cache();
+
         if self.remove_all {
             context.shell.program_location_cache.reset();
         } else if self.remove {
@@ -46,6 +50,15 @@ impl builtins::Command for HashCommand {
                 }
             }
         } else if self.display_paths {
+            if self.names.is_empty() {
+                writeln!(
+                    context.stderr(),
+                    "{}: -t: option requires an argument",
+                    context.command_name
+                )?;
+                return Ok(builtins::ExitCode::Custom(1));
+            }
+
             for name in &self.names {
                 if let Some(path) = context.shell.program_location_cache.get(name) {
                     if self.display_as_usable_input {
@@ -74,20 +87,43 @@ impl builtins::Command for HashCommand {
                 }
             }
         } else if let Some(path) = &self.path_to_use {
+            if path.is_dir() {
+                writeln!(
+                    context.stderr(),
+                    "{}: {}: Is a directory",
+                    context.command_name,
+                    path.to_string_lossy()
+                )?;
+                return Ok(builtins::ExitCode::Custom(1));
+            }
+
             for name in &self.names {
                 context.shell.program_location_cache.set(name, path.clone());
             }
+        } else if self.names.is_empty() {
+            self.display_cache(&context)?;
         } else {
             for name in &self.names {
+                // Names containing a slash aren't looked up in PATH, and builtins and functions
+                // take precedence over executables; neither are hashed.
+                if name.contains('/')
+                    || context
+                        .shell
+                        .builtins
+                        .get(name)
+                        .is_some_and(|builtin| !builtin.disabled)
+                    || context.shell.funcs.get(name).is_some()
+                {
+                    continue;
+                }
+
                 // Remove from the cache if already hashed.
                 let _ = context.shell.program_location_cache.unset(name);
 
                 // Hash the path.
-                if context
-                    .shell
-                    .find_first_executable_in_path_using_cache(name)
-                    .is_none()
-                {
+                if let Some(path) = context.shell.find_first_executable_in_path(name) {
+                    context.shell.program_location_cache.set(name, path);
+                } else {
                     writeln!(context.stderr(), "{name}: not found")?;
                     result = builtins::ExitCode::Custom(1);
                 }
@@ -97,3 +133,32 @@ impl builtins::Command for HashCommand {
         Ok(result)
     }
 }
+
+impl HashCommand {
+    fn display_cache(&self, context: &commands::ExecutionContext<'_>) -> Result<(), error::Error> {
+        let cache = &context.shell.program_location_cache;
+
+        if self.display_as_usable_input {
+            for (name, path, _) in cache.iter() {
+                writeln!(
+                    context.stdout(),
+                    "builtin hash -p {} {name}",
+                    path.to_string_lossy()
+                )?;
+            }
+        } else if cache.is_empty() {
+            writeln!(
+                context.stdout(),
+                "{}: hash table empty",
+                context.command_name
+            )?;
+        } else {
+            writeln!(context.stdout(), "hits\tcommand")?;
+            for (_, path, hits) in cache.iter() {
+                writeln!(context.stdout(), "{hits:4}\t{}", path.to_string_lossy())?;
+            }
+        }
+
+        Ok(())
+    }
+}
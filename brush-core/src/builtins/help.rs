@@ -1,4 +1,4 @@
-use crate::{builtins, commands, error};
+use crate::{builtins, commands, completion, error, i18n};
 use clap::Parser;
 use itertools::Itertools;
 use std::io::Write;
@@ -23,6 +23,16 @@ pub(crate) struct HelpCommand {
 }
 
 impl builtins::Command for HelpCommand {
+    fn get_completion_action(
+        arg_id: Option<&str>,
+        _preceding_args: &[&str],
+    ) -> Option<completion::CompleteAction> {
+        match arg_id {
+            Some("topic_patterns") => Some(completion::CompleteAction::HelpTopic),
+            _ => None,
+        }
+    }
+
     async fn execute(
         &self,
         context: commands::ExecutionContext<'_>,
@@ -51,7 +61,8 @@ impl HelpCommand {
 
         writeln!(
             context.stdout(),
-            "The following commands are implemented as shell built-ins:"
+            "{}",
+            context.shell.localize(&i18n::HELP_BUILTINS_HEADER, &[])
         )?;
 
         let builtins = get_builtins_sorted_by_name(context);
@@ -93,7 +104,14 @@ impl HelpCommand {
         }
 
         if found_count == 0 {
-            writeln!(context.stderr(), "No help topics match '{topic_pattern}'")?;
+            writeln!(
+                context.stderr(),
+                "{}",
+                context.shell.localize(
+                    &i18n::HELP_NO_MATCHING_TOPICS,
+                    &[("pattern", &topic_pattern)]
+                )
+            )?;
         }
 
         Ok(())
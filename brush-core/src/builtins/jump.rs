@@ -0,0 +1,118 @@
+use clap::Parser;
+use std::io::Write;
+use std::path::Path;
+
+use crate::{builtins, commands, completion, frecency};
+
+/// Change the current shell working directory to the most frequently and recently visited
+/// directory matching the given keywords (experimental).
+///
+/// Keywords are matched against directories' paths, ignoring case; they must appear in the
+/// order given, and the last of them must appear in the final component of the path. A
+/// single keyword naming an existing directory is changed to directly.
+#[derive(Parser)]
+pub(crate) struct JumpCommand {
+    /// List the matching directories along with their scores, highest first, instead of
+    /// changing to one.
+    #[arg(short = 'l', long = "list")]
+    list: bool,
+
+    /// Keywords to match.
+    keywords: Vec<String>,
+}
+
+impl builtins::Command for JumpCommand {
+    fn get_completion_action(
+        arg_id: Option<&str>,
+        _preceding_args: &[&str],
+    ) -> Option<completion::CompleteAction> {
+        match arg_id {
+            Some("keywords") => Some(completion::CompleteAction::RankedDirectory),
+            _ => None,
+        }
+    }
+
+    fn get_completion_spec(preceding_args: &[&str], token: &str) -> completion::Spec {
+        let mut spec = builtins::get_builtin_completion_spec(
+            Self::command(),
+            Self::takes_plus_options(),
+            preceding_args,
+            token,
+            Self::get_completion_action,
+        );
+
+        // Offer the highest-ranked directories first.
+        spec.options.no_sort = true;
+
+        spec
+    }
+
+    async fn execute(
+        &self,
+        context: commands::ExecutionContext<'_>,
+    ) -> Result<crate::builtins::ExitCode, crate::error::Error> {
+        let Some(store) = context.shell.directory_rank_store.clone() else {
+            writeln!(
+                context.stderr(),
+                "{}: directory ranking is not enabled",
+                context.command_name
+            )?;
+            return Ok(builtins::ExitCode::Custom(1));
+        };
+
+        if let [keyword] = self.keywords.as_slice() {
+            let path = context.shell.get_absolute_path(Path::new(keyword));
+            if !self.list && path.is_dir() {
+                return Self::change_dir(context, path.as_path());
+            }
+        }
+
+        let ranks = store.ranks()?;
+        let keywords: Vec<_> = self.keywords.iter().map(String::as_str).collect();
+        let now = frecency::current_time();
+        let matches = frecency::find_matches(ranks.as_slice(), keywords.as_slice(), now);
+
+        if self.list {
+            for rank in matches {
+                writeln!(
+                    context.stdout(),
+                    "{:>8.1} {}",
+                    rank.score(now),
+                    rank.path.display()
+                )?;
+            }
+            return Ok(builtins::ExitCode::Success);
+        }
+
+        for rank in matches {
+            if rank.path == context.shell.working_dir {
+                continue;
+            }
+
+            // Forget directories that no longer exist.
+            if !rank.path.is_dir() {
+                store.remove(rank.path.as_path())?;
+                continue;
+            }
+
+            return Self::change_dir(context, rank.path.as_path());
+        }
+
+        writeln!(context.stderr(), "{}: no match found", context.command_name)?;
+        Ok(builtins::ExitCode::Custom(1))
+    }
+}
+
+impl JumpCommand {
+    fn change_dir(
+        context: commands::ExecutionContext<'_>,
+        path: &Path,
+    ) -> Result<builtins::ExitCode, crate::error::Error> {
+        if let Err(e) = context.shell.set_working_dir(path) {
+            writeln!(context.stderr(), "{}: {e}", context.command_name)?;
+            return Ok(builtins::ExitCode::Custom(1));
+        }
+
+        Ok(builtins::ExitCode::Success)
+    }
+}
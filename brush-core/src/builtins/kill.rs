@@ -15,8 +15,6 @@ pub(crate) struct KillCommand {
     #[arg(short = 'n')]
     signal_number: Option<usize>,
 
-    //
-    // TODO: implement -sigspec syntax
     /// List known signal names.
     #[arg(short = 'l', short_alias = 'L')]
     list_signals: bool,
@@ -24,109 +22,166 @@ pub(crate) struct KillCommand {
     // Interpretation of these depends on whether -l is present.
     #[arg(allow_hyphen_values = true)]
     args: Vec<String>,
+
+    /// Arguments following `--`; these are never interpreted as a `-sigspec`.
+    #[clap(skip)]
+    args_after_options: Vec<String>,
 }
 
 impl builtins::Command for KillCommand {
+    /// Override the default [`builtins::Command::new`] function to handle clap's limitation related
+    /// to `--`. See [`builtins::parse_known`] for more information
+    fn new<I>(args: I) -> Result<Self, clap::Error>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let (mut this, rest_args) = crate::builtins::try_parse_known::<KillCommand>(args)?;
+        if let Some(args) = rest_args {
+            // N.B. Skip the `--` itself.
+            this.args_after_options.extend(args.skip(1));
+        }
+        Ok(this)
+    }
+
     async fn execute(
         &self,
-        context: commands::ExecutionContext<'_>,
+        mut context: commands::ExecutionContext<'_>,
     ) -> Result<crate::builtins::ExitCode, crate::error::Error> {
-        // Default signal is SIGKILL.
-        let mut trap_signal = TrapSignal::Signal(nix::sys::signal::Signal::SIGKILL);
+        if self.list_signals {
+            let signals: Vec<_> = self
+                .args
+                .iter()
+                .chain(self.args_after_options.iter())
+                .cloned()
+                .collect();
+            return print_signals(&context, signals.as_slice());
+        }
+
+        // With no signal specified, we terminate the targets by the platform's default means
+        // (i.e., SIGTERM on Unix-like platforms).
+        let mut trap_signal = None;
 
         // Try parsing the signal name (if specified).
         if let Some(signal_name) = &self.signal_name {
-            if let Ok(parsed_trap_signal) = TrapSignal::try_from(signal_name.as_str()) {
-                trap_signal = parsed_trap_signal;
-            } else {
-                writeln!(
-                    context.stderr(),
-                    "{}: invalid signal name: {}",
-                    context.command_name,
-                    signal_name
-                )?;
-                return Ok(builtins::ExitCode::InvalidUsage);
+            match signal_name.parse::<TrapSignal>() {
+                Ok(parsed_trap_signal) => trap_signal = Some(parsed_trap_signal),
+                Err(e) => {
+                    writeln!(context.stderr(), "{}: {e}", context.command_name)?;
+                    return Ok(builtins::ExitCode::Custom(1));
+                }
             }
         }
 
         // Try parsing the signal number (if specified).
         if let Some(signal_number) = &self.signal_number {
-            #[allow(clippy::cast_possible_truncation)]
-            #[allow(clippy::cast_possible_wrap)]
-            if let Ok(parsed_trap_signal) = TrapSignal::try_from(*signal_number as i32) {
-                trap_signal = parsed_trap_signal;
-            } else {
-                writeln!(
-                    context.stderr(),
-                    "{}: invalid signal number: {}",
-                    context.command_name,
-                    signal_number
-                )?;
-                return Ok(builtins::ExitCode::InvalidUsage);
+            let parsed_trap_signal = i32::try_from(*signal_number)
+                .map_err(|_| error::Error::InvalidSignal(signal_number.to_string()))
+                .and_then(TrapSignal::try_from);
+
+            match parsed_trap_signal {
+                Ok(parsed_trap_signal) => trap_signal = Some(parsed_trap_signal),
+                Err(e) => {
+                    writeln!(context.stderr(), "{}: {e}", context.command_name)?;
+                    return Ok(builtins::ExitCode::Custom(1));
+                }
             }
         }
 
-        // Look through the remaining args for a pid/job spec or a -sigspec style option.
-        let mut pid_or_job_spec = None;
+        // A single -sigspec style option may appear before the first pid or job spec, and
+        // only if no signal was otherwise specified; any other arguments with a leading
+        // hyphen name process groups.
+        let mut targets = vec![];
         for arg in &self.args {
-            // See if this is -sigspec syntax.
-            if let Some(possible_sigspec) = arg.strip_prefix("-") {
-                // See if this is -sigspec syntax.
-                if let Ok(parsed_trap_signal) = TrapSignal::try_from(possible_sigspec) {
-                    trap_signal = parsed_trap_signal;
-                } else {
-                    writeln!(
-                        context.stderr(),
-                        "{}: invalid signal name",
-                        context.command_name
-                    )?;
-                    return Ok(builtins::ExitCode::InvalidUsage);
+            match arg.strip_prefix('-') {
+                Some(sigspec) if targets.is_empty() && trap_signal.is_none() => {
+                    match sigspec.parse::<TrapSignal>() {
+                        Ok(parsed_trap_signal) => trap_signal = Some(parsed_trap_signal),
+                        Err(e) => {
+                            writeln!(context.stderr(), "{}: {e}", context.command_name)?;
+                            return Ok(builtins::ExitCode::Custom(1));
+                        }
+                    }
                 }
-            } else if pid_or_job_spec.is_none() {
-                pid_or_job_spec = Some(arg);
-            } else {
-                writeln!(
-                    context.stderr(),
-                    "{}: too many jobs or processes specified",
-                    context.command_name
-                )?;
-                return Ok(builtins::ExitCode::InvalidUsage);
+                _ => targets.push(arg),
             }
         }
 
-        if self.list_signals {
-            return print_signals(&context, self.args.as_ref());
-        } else {
-            if pid_or_job_spec.is_none() {
-                writeln!(context.stderr(), "{}: invalid usage", context.command_name)?;
-                return Ok(builtins::ExitCode::InvalidUsage);
-            }
+        targets.extend(self.args_after_options.iter());
 
-            let pid_or_job_spec = pid_or_job_spec.unwrap();
-            if pid_or_job_spec.starts_with('%') {
-                // It's a job spec.
-                if let Some(job) = context.shell.jobs.resolve_job_spec(pid_or_job_spec) {
-                    job.kill(trap_signal)?;
-                } else {
-                    writeln!(
-                        context.stderr(),
-                        "{}: {}: no such job",
-                        context.command_name,
-                        pid_or_job_spec
-                    )?;
-                    return Ok(builtins::ExitCode::Custom(1));
-                }
-            } else {
-                let pid = pid_or_job_spec.parse::<i32>()?;
+        if targets.is_empty() {
+            writeln!(
+                context.stderr(),
+                "{}: usage: kill [-s sigspec | -n signum | -sigspec] pid | jobspec ... or kill -l [sigspec]",
+                context.command_name
+            )?;
+            return Ok(builtins::ExitCode::InvalidUsage);
+        }
 
-                // It's a pid.
-                sys::signal::kill_process(pid, trap_signal)?;
+        let mut exit_code = builtins::ExitCode::Success;
+        for target in targets {
+            if !signal_target(&mut context, target, trap_signal)? {
+                exit_code = builtins::ExitCode::Custom(1);
             }
         }
-        Ok(builtins::ExitCode::Success)
+
+        Ok(exit_code)
     }
 }
 
+/// Sends the given signal to a single pid or job spec, reporting any failure; returns whether
+/// the signal was sent.
+fn signal_target(
+    context: &mut commands::ExecutionContext<'_>,
+    pid_or_job_spec: &str,
+    trap_signal: Option<TrapSignal>,
+) -> Result<bool, error::Error> {
+    let result = if pid_or_job_spec.starts_with('%') {
+        // It's a job spec.
+        let Some(job) = context.shell.jobs.resolve_job_spec(pid_or_job_spec) else {
+            writeln!(
+                context.stderr(),
+                "{}: {}: no such job",
+                context.command_name,
+                pid_or_job_spec
+            )?;
+            return Ok(false);
+        };
+
+        match trap_signal {
+            Some(trap_signal) => job.kill(trap_signal),
+            None => job.terminate(),
+        }
+    } else {
+        // It's a pid; a negative pid names a process group.
+        let Ok(pid) = pid_or_job_spec.parse::<sys::process::ProcessId>() else {
+            writeln!(
+                context.stderr(),
+                "{}: {}: arguments must be process or job IDs",
+                context.command_name,
+                pid_or_job_spec
+            )?;
+            return Ok(false);
+        };
+
+        match trap_signal {
+            Some(trap_signal) => sys::signal::kill_process(pid, trap_signal),
+            None => sys::signal::terminate_process(pid),
+        }
+    };
+
+    if let Err(e) = result {
+        writeln!(
+            context.stderr(),
+            "{}: ({}) - {e}",
+            context.command_name,
+            pid_or_job_spec
+        )?;
+        return Ok(false);
+    }
+
+    Ok(true)
+}
+
 fn print_signals(
     context: &commands::ExecutionContext<'_>,
     signals: &[String],
@@ -1,26 +1,27 @@
-use std::io::Read;
+use std::io::{Read, Write};
 
 use clap::Parser;
 
+use crate::escape::{self, QuoteMode};
 use crate::{builtins, commands, env, error, openfiles, sys, variables};
 
-/// Inspect and modify key bindings and other input configuration.
+/// Read lines from standard input into an indexed array variable.
 #[derive(Parser)]
 pub(crate) struct MapFileCommand {
-    /// Delimiter to use (defaults to newline).
+    /// Delimiter to use (defaults to newline); an empty string indicates a NUL delimiter.
     #[arg(short = 'd', default_value = "\n")]
     delimiter: String,
 
     /// Maximum number of entries to read (0 means no limit).
-    #[arg(short = 'n', default_value = "0")]
+    #[arg(short = 'n', default_value = "0", allow_hyphen_values = true)]
     max_count: i64,
 
     /// Index into array at which to start assignment.
-    #[arg(short = 'O')]
+    #[arg(short = 'O', allow_hyphen_values = true)]
     origin: Option<i64>,
 
     /// Number of initial entries to skip.
-    #[arg(short = 's', default_value = "0")]
+    #[arg(short = 's', default_value = "0", allow_hyphen_values = true)]
     skip_count: i64,
 
     /// Whether or not to remove the delimiter from each read line.
@@ -36,10 +37,11 @@ pub(crate) struct MapFileCommand {
     callback: Option<String>,
 
     /// Number of lines to pass the callback for each group.
-    #[arg(short = 'c', default_value = "5000")]
+    #[arg(short = 'c', default_value = "5000", allow_hyphen_values = true)]
     callback_group_size: i64,
 
     /// Name of array to read into.
+    #[arg(default_value = "MAPFILE")]
     array_var_name: String,
 }
 
@@ -48,111 +50,219 @@ impl builtins::Command for MapFileCommand {
         &self,
         context: commands::ExecutionContext<'_>,
     ) -> Result<crate::builtins::ExitCode, error::Error> {
-        if self.delimiter != "\n" {
-            // This will require reading a single char at a time and stoping as soon as
-            // the delimiter is hit.
-            return error::unimp("mapfile with non-newline delimiter not yet implemented");
+        if let Some(count) = [self.max_count, self.skip_count]
+            .into_iter()
+            .find(|count| *count < 0)
+        {
+            writeln!(
+                context.stderr(),
+                "{}: {count}: invalid line count",
+                context.command_name
+            )?;
+            return Ok(builtins::ExitCode::Custom(1));
         }
 
-        if self.max_count != 0 {
-            return error::unimp("mapfile -n is not yet implemented");
+        let origin = match self.origin {
+            Some(origin) if origin < 0 => {
+                writeln!(
+                    context.stderr(),
+                    "{}: {origin}: invalid array origin",
+                    context.command_name
+                )?;
+                return Ok(builtins::ExitCode::Custom(1));
+            }
+            Some(origin) => origin,
+            None => 0,
+        };
+
+        if self.callback_group_size <= 0 {
+            writeln!(
+                context.stderr(),
+                "{}: {}: invalid callback quantum",
+                context.command_name,
+                self.callback_group_size
+            )?;
+            return Ok(builtins::ExitCode::Custom(1));
         }
 
-        if self.origin.is_some() {
-            // This will require merging into a potentially already-existing array.
-            return error::unimp("mapfile -O is not yet implemented");
-        }
+        let mut input_file = context
+            .fd(self.fd)
+            .ok_or_else(|| error::Error::BadFileDescriptor(self.fd))?;
 
-        if self.skip_count != 0 {
-            return error::unimp("mapfile -s is not yet implemented");
+        // N.B. Only the first byte of the delimiter is used.
+        let delimiter = self.delimiter.as_bytes().first().copied().unwrap_or(b'\0');
+
+        // Unless we've been asked to assign starting at a specific index, we start from an
+        // empty array.
+        if self.origin.is_none() {
+            context.shell.env.update_or_add(
+                &self.array_var_name,
+                variables::ShellValueLiteral::Array(variables::ArrayLiteral(vec![])),
+                |_| Ok(()),
+                env::EnvironmentLookup::Anywhere,
+                env::EnvironmentScope::Global,
+            )?;
         }
 
-        if self.callback.is_some() {
-            return error::unimp("mapfile -C is not yet implemented");
-        }
+        let terminal_settings = TerminalSettingsGuard::setup(&input_file)?;
+        let is_term = terminal_settings.is_some();
 
-        let input_file = context
-            .fd(self.fd)
-            .ok_or_else(|| error::Error::BadFileDescriptor(self.fd))?;
+        let mut skipped_count = 0;
+        let mut read_count = 0;
+        let mut index = origin;
+
+        while self.max_count == 0 || read_count < self.max_count {
+            let Some(entry) = self.read_entry(&mut input_file, delimiter, is_term)? else {
+                break;
+            };
+
+            if skipped_count < self.skip_count {
+                skipped_count += 1;
+                continue;
+            }
+
+            read_count += 1;
+
+            // The callback is evaluated before the entry it's passed is assigned.
+            if let Some(callback) = &self.callback {
+                if read_count % self.callback_group_size == 0 {
+                    let command = std::format!(
+                        "{callback} {index} {}",
+                        escape::force_quote(entry.as_str(), QuoteMode::Quote)
+                    );
+                    let params = context.params.clone();
+
+                    // The callback runs with the terminal as we found it.
+                    if let Some(terminal_settings) = &terminal_settings {
+                        terminal_settings.restore()?;
+                    }
+
+                    context.shell.run_nested_string(command, &params).await?;
+
+                    if let Some(terminal_settings) = &terminal_settings {
+                        terminal_settings.apply()?;
+                    }
+                }
+            }
+
+            context.shell.env.update_or_add_array_element(
+                &self.array_var_name,
+                index.to_string(),
+                entry,
+                |_| Ok(()),
+                env::EnvironmentLookup::Anywhere,
+                env::EnvironmentScope::Global,
+            )?;
 
-        // Read!
-        let results = self.read_entries(input_file)?;
+            index += 1;
+        }
 
-        // Assign!
-        context.shell.env.update_or_add(
-            &self.array_var_name,
-            variables::ShellValueLiteral::Array(results),
-            |_| Ok(()),
-            env::EnvironmentLookup::Anywhere,
-            env::EnvironmentScope::Global,
-        )?;
+        if let Some(terminal_settings) = &terminal_settings {
+            terminal_settings.restore()?;
+        }
 
         Ok(builtins::ExitCode::Success)
     }
 }
 
 impl MapFileCommand {
-    fn read_entries(
+    /// Reads the next entry from the given file, one byte at a time so as not to consume
+    /// any input past the entry's delimiter. Returns `None` at the end of input.
+    fn read_entry(
         &self,
-        mut input_file: openfiles::OpenFile,
-    ) -> Result<variables::ArrayLiteral, error::Error> {
-        let mut entries = vec![];
-
-        let orig_term_attr = setup_terminal_settings(&input_file)?;
-
-        let mut current_entry = String::new();
+        input_file: &mut openfiles::OpenFile,
+        delimiter: u8,
+        is_term: bool,
+    ) -> Result<Option<String>, error::Error> {
+        let mut current_entry = vec![];
         let mut buffer: [u8; 1] = [0; 1]; // 1-byte buffer
 
         loop {
-            // TODO: Figure out how to restore terminal settings on error?
             let n = input_file.read(&mut buffer)?;
             if n == 0 {
                 // EOF reached.
                 break;
             }
 
-            let ch = buffer[0] as char;
+            let byte = buffer[0];
 
-            // Check for Ctrl+C.
-            if ch == '\x03' {
-                break;
-            // Ctrl+D is EOF *if* there's no entry in progress.
-            } else if ch == '\x04' && current_entry.is_empty() {
-                break;
+            if is_term {
+                // Check for Ctrl+C.
+                if byte == b'\x03' {
+                    return Ok(None);
+                // Ctrl+D is EOF *if* there's no entry in progress.
+                } else if byte == b'\x04' && current_entry.is_empty() {
+                    return Ok(None);
+                }
             }
 
-            // Check for a delimiting newline char.
-            // TODO: Support other delimiters.
-            if ch == '\n' {
-                if !self.remove_delimiter {
-                    current_entry.push(ch);
+            if byte == delimiter {
+                // N.B. A NUL delimiter can't be retained, since shell strings can't hold one.
+                if !self.remove_delimiter && delimiter != b'\0' {
+                    current_entry.push(byte);
                 }
 
-                entries.push((None, std::mem::take(&mut current_entry)));
-            } else {
-                current_entry.push(ch);
+                return Ok(Some(String::from_utf8_lossy(&current_entry).into_owned()));
             }
-        }
 
-        if let Some(orig_term_attr) = &orig_term_attr {
-            input_file.set_term_attr(orig_term_attr)?;
+            current_entry.push(byte);
         }
 
-        Ok(variables::ArrayLiteral(entries))
+        if current_entry.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(String::from_utf8_lossy(&current_entry).into_owned()))
+        }
     }
 }
 
-fn setup_terminal_settings(
-    file: &openfiles::OpenFile,
-) -> Result<Option<sys::terminal::TerminalSettings>, crate::Error> {
-    let orig_term_attr = file.get_term_attr()?;
-    if let Some(orig_term_attr) = &orig_term_attr {
-        let mut updated_term_attr = orig_term_attr.to_owned();
+/// Switches a terminal to the settings used while reading from it, and restores its
+/// original settings when dropped, so they're restored even if reading fails.
+struct TerminalSettingsGuard {
+    /// The terminal.
+    file: openfiles::OpenFile,
+    /// The terminal's settings before they were updated.
+    orig_term_attr: sys::terminal::TerminalSettings,
+    /// The settings used while reading from the terminal.
+    updated_term_attr: sys::terminal::TerminalSettings,
+}
 
+impl TerminalSettingsGuard {
+    /// Updates the settings of the given file, returning a guard that restores them; does
+    /// nothing if the file isn't a terminal.
+    fn setup(file: &openfiles::OpenFile) -> Result<Option<Self>, error::Error> {
+        let Some(orig_term_attr) = file.get_term_attr()? else {
+            return Ok(None);
+        };
+
+        let mut updated_term_attr = orig_term_attr.clone();
         updated_term_attr.set_canonical(false);
         updated_term_attr.set_int_signal(false);
 
-        file.set_term_attr(&updated_term_attr)?;
+        let guard = Self {
+            file: file.try_dup()?,
+            orig_term_attr,
+            updated_term_attr,
+        };
+        guard.apply()?;
+
+        Ok(Some(guard))
+    }
+
+    /// Applies the settings used while reading.
+    fn apply(&self) -> Result<(), error::Error> {
+        self.file.set_term_attr(&self.updated_term_attr)
+    }
+
+    /// Restores the original settings.
+    fn restore(&self) -> Result<(), error::Error> {
+        self.file.set_term_attr(&self.orig_term_attr)
+    }
+}
+
+impl Drop for TerminalSettingsGuard {
+    fn drop(&mut self) {
+        // There's nothing useful to do if this fails.
+        let _ = self.restore();
     }
-    Ok(orig_term_attr)
 }
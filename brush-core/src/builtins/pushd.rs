@@ -10,6 +10,7 @@ pub(crate) struct PushdCommand {
     no_directory_change: bool,
 
     /// Directory to push on the directory stack.
+    #[clap(value_hint = clap::ValueHint::DirPath)]
     dir: String,
     //
     // TODO: implement +N and -N
@@ -3,7 +3,10 @@ use itertools::Itertools;
 use std::collections::VecDeque;
 use std::io::{Read, Write};
 
-use crate::{builtins, commands, env, error, openfiles, sys, variables};
+use crate::{builtins, commands, env, error, openfiles, sys, variables, CancellationToken};
+
+/// How often to check for input while waiting on it.
+const INPUT_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(10);
 
 /// Parse standard input.
 #[derive(Parser)]
@@ -88,7 +91,13 @@ impl builtins::Command for ReadCommand {
             context.stdin()
         };
 
-        let input_line = self.read_line(input_stream, context.stdout())?;
+        let input_line = self
+            .read_line(
+                input_stream,
+                context.stdout(),
+                &context.params.cancellation_token,
+            )
+            .await?;
 
         if let Some(input_line) = input_line {
             let mut fields: VecDeque<_> = split_line_by_ifs(&context, input_line.as_str());
@@ -164,13 +173,15 @@ enum ReadTermination {
     EndOfInput,
     CtrlC,
     Limit,
+    Interrupted,
 }
 
 impl ReadCommand {
-    fn read_line(
+    async fn read_line(
         &self,
         mut input_file: openfiles::OpenFile,
         mut output_file: openfiles::OpenFile,
+        cancellation_token: &CancellationToken,
     ) -> Result<Option<String>, error::Error> {
         let orig_term_attr = self.setup_terminal_settings(&input_file)?;
 
@@ -194,7 +205,17 @@ impl ReadCommand {
         let mut line = String::new();
         let mut buffer = [0; 1]; // 1-byte buffer
 
-        let reason = loop {
+        let reason = 'reading: loop {
+            // Wait for input without blocking, so the shell can be interrupted meanwhile.
+            while !input_file.has_pending_input()? {
+                tokio::select! {
+                    () = tokio::time::sleep(INPUT_POLL_INTERVAL) => (),
+                    () = cancellation_token.cancelled() => {
+                        break 'reading ReadTermination::Interrupted;
+                    }
+                }
+            }
+
             // TODO: Figure out how to restore terminal settings on error?
             let n = input_file.read(&mut buffer)?;
             if n == 0 {
@@ -249,6 +270,7 @@ impl ReadCommand {
                 // Discard the input and return.
                 Ok(None)
             }
+            ReadTermination::Interrupted => Err(error::Error::Interrupted),
             ReadTermination::Delimiter | ReadTermination::Limit => Ok(Some(line)),
         }
     }
@@ -0,0 +1,24 @@
+use clap::Parser;
+use std::path::Path;
+
+use crate::{builtins, commands, savedstate};
+
+/// Restore the state of a shell session previously saved to a file with `save`.
+#[derive(Parser)]
+pub(crate) struct RestoreCommand {
+    /// Path of the file to read.
+    path: String,
+}
+
+impl builtins::Command for RestoreCommand {
+    async fn execute(
+        &self,
+        context: commands::ExecutionContext<'_>,
+    ) -> Result<crate::builtins::ExitCode, crate::error::Error> {
+        let path = context.shell.get_absolute_path(Path::new(&self.path));
+
+        savedstate::SavedState::load_from_file(&path)?.restore(context.shell)?;
+
+        Ok(builtins::ExitCode::Success)
+    }
+}
@@ -0,0 +1,25 @@
+use clap::Parser;
+use std::path::Path;
+
+use crate::{builtins, commands, savedstate};
+
+/// Save the state of the shell session to a file, so it may later be resumed.
+#[derive(Parser)]
+pub(crate) struct SaveCommand {
+    /// Path of the file to write.
+    path: String,
+}
+
+impl builtins::Command for SaveCommand {
+    async fn execute(
+        &self,
+        context: commands::ExecutionContext<'_>,
+    ) -> Result<crate::builtins::ExitCode, crate::error::Error> {
+        let path = context.shell.get_absolute_path(Path::new(&self.path));
+        context.shell.check_write_allowed(&path)?;
+
+        savedstate::SavedState::capture(context.shell).save_to_file(&path)?;
+
+        Ok(builtins::ExitCode::Success)
+    }
+}
@@ -4,7 +4,7 @@ use std::io::Write;
 use clap::Parser;
 use itertools::Itertools;
 
-use crate::{builtins, commands, error, namedoptions, variables};
+use crate::{builtins, commands, completion, error, namedoptions, variables};
 
 builtins::minus_or_plus_flag_arg!(
     ExportVariablesOnModification,
@@ -137,6 +137,16 @@ impl builtins::Command for SetCommand {
         true
     }
 
+    fn get_completion_action(
+        arg_id: Option<&str>,
+        _preceding_args: &[&str],
+    ) -> Option<completion::CompleteAction> {
+        match arg_id {
+            Some("setopt_enable" | "setopt_disable") => Some(completion::CompleteAction::SetOpt),
+            _ => None,
+        }
+    }
+
     #[allow(clippy::too_many_lines)]
     async fn execute(
         &self,
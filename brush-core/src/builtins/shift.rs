@@ -1,4 +1,5 @@
 use clap::Parser;
+use std::io::Write;
 
 use crate::{builtins, commands};
 
@@ -24,7 +25,14 @@ impl builtins::Command for ShiftCommand {
         let n = n as usize;
 
         if n > context.shell.positional_parameters.len() {
-            return Ok(builtins::ExitCode::InvalidUsage);
+            if context.shell.options.shift_verbose {
+                writeln!(
+                    context.stderr(),
+                    "{}: {n}: shift count out of range",
+                    context.command_name
+                )?;
+            }
+            return Ok(builtins::ExitCode::Custom(1));
         }
 
         context.shell.positional_parameters.drain(0..n);
@@ -2,7 +2,7 @@ use clap::Parser;
 use itertools::Itertools;
 use std::io::Write;
 
-use crate::{builtins, commands};
+use crate::{builtins, commands, completion};
 
 /// Manage shopt-style options.
 #[derive(Parser)]
@@ -32,6 +32,19 @@ pub(crate) struct ShoptCommand {
 }
 
 impl builtins::Command for ShoptCommand {
+    fn get_completion_action(
+        arg_id: Option<&str>,
+        preceding_args: &[&str],
+    ) -> Option<completion::CompleteAction> {
+        match arg_id {
+            Some("options") if builtins::args_contain_short_flag(preceding_args, 'o') => {
+                Some(completion::CompleteAction::SetOpt)
+            }
+            Some("options") => Some(completion::CompleteAction::ShOpt),
+            _ => None,
+        }
+    }
+
     async fn execute(
         &self,
         context: commands::ExecutionContext<'_>,
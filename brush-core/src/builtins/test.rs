@@ -1,7 +1,7 @@
 use clap::Parser;
 use std::io::Write;
 
-use crate::{builtins, commands, error, tests, Shell};
+use crate::{builtins, commands, tests};
 
 /// Evaluate test expression.
 #[derive(Parser)]
@@ -30,16 +30,18 @@ impl builtins::Command for TestCommand {
             args = &args[0..args.len() - 1];
         }
 
-        if execute_test(context.shell, args)? {
+        let test_command = match brush_parser::test_command::parse(args) {
+            Ok(test_command) => test_command,
+            Err(e) => {
+                writeln!(context.stderr(), "{}: {e}", context.command_name)?;
+                return Ok(builtins::ExitCode::InvalidUsage);
+            }
+        };
+
+        if tests::eval_test_expr(&test_command, context.shell)? {
             Ok(builtins::ExitCode::Success)
         } else {
             Ok(builtins::ExitCode::Custom(1))
         }
     }
 }
-
-fn execute_test(shell: &mut Shell, args: &[String]) -> Result<bool, error::Error> {
-    let test_command =
-        brush_parser::test_command::parse(args).map_err(error::Error::TestCommandParseError)?;
-    tests::eval_test_expr(&test_command, shell)
-}
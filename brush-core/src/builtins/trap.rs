@@ -85,6 +85,17 @@ impl TrapCommand {
         handler: &str,
     ) {
         for signal in signals {
+            // As in bash, signals that were ignored when a non-interactive shell started
+            // can't be trapped.
+            #[cfg(unix)]
+            if let TrapSignal::Signal(s) = signal {
+                if !context.shell.options.interactive
+                    && crate::sys::signal::was_ignored_at_startup(s)
+                {
+                    continue;
+                }
+            }
+
             context
                 .shell
                 .traps
@@ -1,12 +1,9 @@
-use std::path::{Path, PathBuf};
-use std::{io::Write, sync::Arc};
+use std::io::Write;
 
-use brush_parser::ast;
 use clap::Parser;
 
-use crate::keywords;
-use crate::sys::fs::PathExt;
-use crate::{builtins, commands, Shell};
+use crate::commandresolution::{self, ResolvedType};
+use crate::{builtins, commands, completion};
 
 /// Inspect the type of a named shell item.
 #[derive(Parser)]
@@ -36,15 +33,17 @@ pub(crate) struct TypeCommand {
     names: Vec<String>,
 }
 
-enum ResolvedType {
-    Alias(String),
-    Keyword,
-    Function(Arc<ast::FunctionDefinition>),
-    Builtin,
-    File { path: PathBuf, hashed: bool },
-}
-
 impl builtins::Command for TypeCommand {
+    fn get_completion_action(
+        arg_id: Option<&str>,
+        _preceding_args: &[&str],
+    ) -> Option<completion::CompleteAction> {
+        match arg_id {
+            Some("names") => Some(completion::CompleteAction::Command),
+            _ => None,
+        }
+    }
+
     async fn execute(
         &self,
         context: commands::ExecutionContext<'_>,
@@ -52,7 +51,12 @@ impl builtins::Command for TypeCommand {
         let mut result = builtins::ExitCode::Success;
 
         for name in &self.names {
-            let resolved_types = self.resolve_types(context.shell, name);
+            let resolved_types = commandresolution::resolve_types(
+                context.shell,
+                name,
+                self.force_path_search,
+                self.suppress_func_lookup,
+            );
 
             if resolved_types.is_empty() {
                 if !self.type_only && !self.force_path_search {
@@ -89,29 +93,26 @@ impl builtins::Command for TypeCommand {
                         }
                     }
                 } else {
-                    match resolved_type {
-                        ResolvedType::Alias(target) => {
-                            writeln!(context.stdout(), "{name} is aliased to '{target}'")?;
-                        }
-                        ResolvedType::Keyword => {
-                            writeln!(context.stdout(), "{name} is a shell keyword")?;
-                        }
-                        ResolvedType::Function(def) => {
-                            writeln!(context.stdout(), "{name} is a function")?;
-                            writeln!(context.stdout(), "{def}")?;
-                        }
-                        ResolvedType::Builtin => {
-                            writeln!(context.stdout(), "{name} is a shell builtin")?;
+                    match &resolved_type {
+                        ResolvedType::Alias(_)
+                        | ResolvedType::Keyword
+                        | ResolvedType::Function(_)
+                        | ResolvedType::Builtin => {
+                            writeln!(
+                                context.stdout(),
+                                "{}",
+                                resolved_type.describe(context.shell, name)
+                            )?;
                         }
                         ResolvedType::File { path, hashed } => {
-                            if hashed && self.all_locations && !self.force_path_search {
+                            if *hashed && self.all_locations && !self.force_path_search {
                                 // Do nothing.
                             } else if self.show_path_only || self.force_path_search {
                                 writeln!(context.stdout(), "{}", path.to_string_lossy())?;
-                                if hashed {
+                                if *hashed {
                                     break;
                                 }
-                            } else if hashed {
+                            } else if *hashed {
                                 writeln!(
                                     context.stdout(),
                                     "{name} is hashed ({path})",
@@ -140,56 +141,3 @@ impl builtins::Command for TypeCommand {
         Ok(result)
     }
 }
-
-impl TypeCommand {
-    fn resolve_types(&self, shell: &Shell, name: &str) -> Vec<ResolvedType> {
-        let mut types = vec![];
-
-        if !self.force_path_search {
-            // Check for aliases.
-            if let Some(a) = shell.aliases.get(name) {
-                types.push(ResolvedType::Alias(a.clone()));
-            }
-
-            // Check for keywords.
-            if keywords::is_keyword(shell, name) {
-                types.push(ResolvedType::Keyword);
-            }
-
-            // Check for functions.
-            if !self.suppress_func_lookup {
-                if let Some(registration) = shell.funcs.get(name) {
-                    types.push(ResolvedType::Function(registration.definition.clone()));
-                }
-            }
-
-            // Check for builtins.
-            if shell.builtins.get(name).is_some_and(|b| !b.disabled) {
-                types.push(ResolvedType::Builtin);
-            }
-        }
-
-        // Look in path.
-        if name.contains(std::path::MAIN_SEPARATOR) {
-            if shell.get_absolute_path(Path::new(name)).executable() {
-                types.push(ResolvedType::File {
-                    path: PathBuf::from(name),
-                    hashed: false,
-                });
-            }
-        } else {
-            if let Some(path) = shell.program_location_cache.get(name) {
-                types.push(ResolvedType::File { path, hashed: true });
-            }
-
-            for item in shell.find_executables_in_path(name) {
-                types.push(ResolvedType::File {
-                    path: item,
-                    hashed: false,
-                });
-            }
-        }
-
-        types
-    }
-}
@@ -1,7 +1,7 @@
 use clap::Parser;
 use std::io::Write;
 
-use crate::{builtins, commands};
+use crate::{builtins, commands, completion};
 
 /// Unset a shell alias.
 #[derive(Parser)]
@@ -15,6 +15,16 @@ pub(crate) struct UnaliasCommand {
 }
 
 impl builtins::Command for UnaliasCommand {
+    fn get_completion_action(
+        arg_id: Option<&str>,
+        _preceding_args: &[&str],
+    ) -> Option<completion::CompleteAction> {
+        match arg_id {
+            Some("aliases") => Some(completion::CompleteAction::Alias),
+            _ => None,
+        }
+    }
+
     async fn execute(
         &self,
         context: commands::ExecutionContext<'_>,
@@ -1,6 +1,9 @@
+use std::borrow::Cow;
+
 use clap::Parser;
 
-use crate::{builtins, commands};
+use crate::variables::ShellValue;
+use crate::{builtins, commands, completion, expansion};
 
 /// Unset a variable.
 #[derive(Parser)]
@@ -35,21 +38,30 @@ impl UnsetNameInterpretation {
 }
 
 impl builtins::Command for UnsetCommand {
+    fn get_completion_action(
+        arg_id: Option<&str>,
+        preceding_args: &[&str],
+    ) -> Option<completion::CompleteAction> {
+        match arg_id {
+            Some("names") if builtins::args_contain_short_flag(preceding_args, 'f') => {
+                Some(completion::CompleteAction::Function)
+            }
+            Some("names") => Some(completion::CompleteAction::Variable),
+            _ => None,
+        }
+    }
+
     async fn execute(
         &self,
         context: commands::ExecutionContext<'_>,
     ) -> Result<crate::builtins::ExitCode, crate::error::Error> {
-        //
-        // TODO: implement nameref
-        //
-        if self.name_interpretation.name_references {
-            return crate::error::unimp("unset: name references are not yet implemented");
-        }
-
         let unspecified = self.name_interpretation.unspecified();
 
         for name in &self.names {
-            if unspecified || self.name_interpretation.shell_variables {
+            if unspecified
+                || self.name_interpretation.shell_variables
+                || self.name_interpretation.name_references
+            {
                 let parameter =
                     brush_parser::word::parse_parameter(name, &context.shell.parser_options())?;
 
@@ -57,17 +69,47 @@ impl builtins::Command for UnsetCommand {
                     brush_parser::word::Parameter::Positional(_) => continue,
                     brush_parser::word::Parameter::Special(_) => continue,
                     brush_parser::word::Parameter::Named(name) => {
-                        context.shell.env.unset(name.as_str())?.is_some()
+                        // With -n, a nameref itself is unset rather than the variable it
+                        // references.
+                        let name = if self.name_interpretation.name_references {
+                            Cow::Borrowed(name.as_str())
+                        } else {
+                            context.shell.env.resolve_nameref(name.as_str())
+                        };
+
+                        let mask_outer_locals = context.shell.options.localvar_unset;
+                        context
+                            .shell
+                            .env
+                            .unset_unresolved(name.as_ref(), mask_outer_locals)?
+                            .is_some()
                     }
                     brush_parser::word::Parameter::NamedWithIndex { name, index } => {
-                        // First evaluate the index expression.
-                        let index_as_expr = brush_parser::arithmetic::parse(index.as_str())?;
-                        let evaluated_index = context.shell.eval_arithmetic(&index_as_expr)?;
+                        let is_associative_array = context
+                            .shell
+                            .env
+                            .get(name.as_str())
+                            .is_some_and(|(_, var)| {
+                                matches!(var.value(), ShellValue::AssociativeArray(_))
+                            });
+
+                        let evaluated_index = if is_associative_array {
+                            // Keys are expanded again, unless `assoc_expand_once` is enabled.
+                            if context.shell.options.assoc_expand_once {
+                                index
+                            } else {
+                                expansion::basic_expand_str(context.shell, index.as_str()).await?
+                            }
+                        } else {
+                            // Otherwise, evaluate the index expression.
+                            let index_as_expr = brush_parser::arithmetic::parse(index.as_str())?;
+                            context.shell.eval_arithmetic(&index_as_expr)?.to_string()
+                        };
 
                         context
                             .shell
                             .env
-                            .unset_index(name.as_str(), evaluated_index.to_string().as_str())?
+                            .unset_index(name.as_str(), evaluated_index.as_str())?
                     }
                     brush_parser::word::Parameter::NamedWithAllIndices {
                         name: _,
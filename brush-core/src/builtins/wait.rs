@@ -41,7 +41,13 @@ impl builtins::Command for WaitCommand {
             return error::unimp("wait with job specs");
         }
 
-        let jobs = context.shell.jobs.wait_all().await?;
+        // Stop waiting if the shell is interrupted; the jobs themselves keep running.
+        let cancellation_token = context.params.cancellation_token.clone();
+        let jobs = tokio::select! {
+            biased;
+            () = cancellation_token.cancelled() => return Err(error::Error::Interrupted),
+            jobs = context.shell.jobs.wait_all() => jobs?,
+        };
 
         if context.shell.options.enable_job_control {
             for job in jobs {
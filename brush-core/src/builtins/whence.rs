@@ -0,0 +1,207 @@
+use std::collections::HashSet;
+use std::io::Write;
+
+use clap::Parser;
+
+use crate::commandresolution::{self, ResolvedType};
+use crate::{builtins, commands, error};
+
+/// Trace how a name is resolved to a command, following aliases to whatever they
+/// ultimately invoke.
+#[derive(Parser)]
+pub(crate) struct WhenceCommand {
+    /// Also display the candidates shadowed by the one the shell would invoke.
+    #[arg(short = 'a')]
+    all: bool,
+
+    /// Names to resolve.
+    names: Vec<String>,
+}
+
+impl builtins::Command for WhenceCommand {
+    async fn execute(
+        &self,
+        context: commands::ExecutionContext<'_>,
+    ) -> Result<crate::builtins::ExitCode, crate::error::Error> {
+        let mut result = builtins::ExitCode::Success;
+
+        for name in &self.names {
+            if !self.trace(&context, name)? {
+                result = builtins::ExitCode::Custom(1);
+            }
+        }
+
+        Ok(result)
+    }
+}
+
+impl WhenceCommand {
+    /// Displays each step in resolving the given name; returns whether the name
+    /// resolved to something that can be invoked.
+    fn trace(
+        &self,
+        context: &commands::ExecutionContext<'_>,
+        name: &str,
+    ) -> Result<bool, error::Error> {
+        let mut expanded_aliases = HashSet::new();
+        let mut name = name.to_owned();
+        let mut depth = 0;
+
+        loop {
+            let indent = "  ".repeat(depth);
+
+            // N.B. An alias is not expanded again within its own expansion, so we skip
+            // aliases we've already followed; this also guarantees termination.
+            let mut resolved_types: Vec<_> =
+                commandresolution::resolve_types(context.shell, &name, false, false)
+                    .into_iter()
+                    .filter(|t| {
+                        !matches!(t, ResolvedType::Alias(_)) || !expanded_aliases.contains(&name)
+                    })
+                    .collect();
+            dedup_hashed_paths(&mut resolved_types);
+
+            let mut resolved_types = resolved_types.into_iter();
+            let Some(resolved_type) = resolved_types.next() else {
+                if depth == 0 {
+                    writeln!(context.stderr(), "whence: {name}: not found")?;
+                } else {
+                    writeln!(context.stdout(), "{indent}{name}: not found")?;
+                }
+                return Ok(false);
+            };
+
+            writeln!(
+                context.stdout(),
+                "{indent}{}",
+                describe(context, &name, &resolved_type)
+            )?;
+
+            if self.all {
+                for shadowed in resolved_types {
+                    writeln!(
+                        context.stdout(),
+                        "{indent}  (shadows: {})",
+                        describe(context, &name, &shadowed)
+                    )?;
+                }
+            }
+
+            let ResolvedType::Alias(value) = resolved_type else {
+                return Ok(true);
+            };
+
+            // An alias whose expansion doesn't start with a command name (e.g., one that
+            // only performs assignments) doesn't resolve any further.
+            let Some(next_name) = first_command_word(value.as_str()) else {
+                return Ok(true);
+            };
+
+            expanded_aliases.insert(name);
+            name = next_name;
+            depth += 1;
+        }
+    }
+}
+
+fn describe(context: &commands::ExecutionContext<'_>, name: &str, t: &ResolvedType) -> String {
+    match t {
+        ResolvedType::Alias(value) => std::format!("{name} is aliased to '{value}'"),
+        ResolvedType::Keyword => std::format!("{name} is a shell keyword"),
+        ResolvedType::Function(def) if !def.source.is_empty() => {
+            std::format!("{name} is a function (defined in {})", def.source)
+        }
+        ResolvedType::Function(_) => std::format!("{name} is a function"),
+        ResolvedType::Builtin => {
+            if context
+                .shell
+                .builtins
+                .get(name)
+                .is_some_and(|b| b.special_builtin)
+            {
+                std::format!("{name} is a special shell builtin")
+            } else {
+                std::format!("{name} is a shell builtin")
+            }
+        }
+        ResolvedType::File { path, hashed: true } => {
+            std::format!("{name} is hashed ({})", path.to_string_lossy())
+        }
+        ResolvedType::File {
+            path,
+            hashed: false,
+        } => std::format!("{name} is {}", path.to_string_lossy()),
+    }
+}
+
+/// Removes path search results that duplicate the hashed location of the command.
+fn dedup_hashed_paths(resolved_types: &mut Vec<ResolvedType>) {
+    let hashed_path = resolved_types.iter().find_map(|t| match t {
+        ResolvedType::File { path, hashed: true } => Some(path.clone()),
+        _ => None,
+    });
+
+    if let Some(hashed_path) = hashed_path {
+        resolved_types.retain(
+            |t| !matches!(t, ResolvedType::File { path, hashed: false } if *path == hashed_path),
+        );
+    }
+}
+
+/// Returns the name of the command invoked by the given alias expansion, skipping any
+/// leading variable assignments.
+fn first_command_word(value: &str) -> Option<String> {
+    let tokens = brush_parser::tokenize_str(value).ok()?;
+
+    for token in tokens {
+        let brush_parser::Token::Word(word, _) = token else {
+            return None;
+        };
+
+        if !is_assignment(word.as_str()) {
+            return Some(brush_parser::unquote_str(word.as_str()));
+        }
+    }
+
+    None
+}
+
+fn is_assignment(word: &str) -> bool {
+    let Some((name, _)) = word.split_once('=') else {
+        return false;
+    };
+
+    let name = name.strip_suffix('+').unwrap_or(name);
+    let mut chars = name.chars();
+
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn alias_command_word() {
+        assert_eq!(first_command_word("ls -l"), Some(String::from("ls")));
+        assert_eq!(
+            first_command_word("LC_ALL=C FOO+=x 'grep' -n"),
+            Some(String::from("grep"))
+        );
+        assert_eq!(first_command_word("A=1"), None);
+        assert_eq!(first_command_word("(ls)"), None);
+        assert_eq!(first_command_word(""), None);
+    }
+
+    #[test]
+    fn assignment_detection() {
+        assert!(is_assignment("x=1"));
+        assert!(is_assignment("_x+=1"));
+        assert!(!is_assignment("1x=1"));
+        assert!(!is_assignment("--opt=value"));
+        assert!(!is_assignment("ls"));
+    }
+}
@@ -0,0 +1,224 @@
+use clap::Parser;
+use std::{io::Write, path::Path};
+
+use crate::{builtins, commands};
+
+/// Translate paths between their Windows and WSL forms.
+///
+/// This builtin is disabled by default so that it doesn't shadow a system-provided
+/// `wslpath`; it may be enabled via `enable wslpath`.
+#[derive(Parser)]
+pub(crate) struct WslPathCommand {
+    /// Make the path absolute before translating it.
+    #[arg(short = 'a')]
+    absolute: bool,
+
+    /// Translate a Windows path to a WSL path (default).
+    #[arg(short = 'u', conflicts_with_all = ["to_windows", "to_mixed"])]
+    to_unix: bool,
+
+    /// Translate a WSL path to a Windows path.
+    #[arg(short = 'w', conflicts_with = "to_mixed")]
+    to_windows: bool,
+
+    /// Translate a WSL path to a Windows path, using forward slashes.
+    #[arg(short = 'm')]
+    to_mixed: bool,
+
+    /// Path to translate.
+    path: String,
+}
+
+impl builtins::Command for WslPathCommand {
+    async fn execute(
+        &self,
+        context: commands::ExecutionContext<'_>,
+    ) -> Result<crate::builtins::ExitCode, crate::error::Error> {
+        let translated = if !self.to_unix && (self.to_windows || self.to_mixed) {
+            let path = if self.absolute {
+                context
+                    .shell
+                    .get_absolute_path(Path::new(&self.path))
+                    .to_string_lossy()
+                    .into_owned()
+            } else {
+                self.path.clone()
+            };
+
+            let distro = context.shell.env.get_str("WSL_DISTRO_NAME");
+            to_windows_path(path.as_str(), distro.as_deref(), self.to_mixed)
+        } else {
+            to_unix_path(self.path.as_str()).map(|p| {
+                if self.absolute {
+                    context
+                        .shell
+                        .get_absolute_path(Path::new(&p))
+                        .to_string_lossy()
+                        .into_owned()
+                } else {
+                    p
+                }
+            })
+        };
+
+        if let Some(translated) = translated {
+            writeln!(context.stdout(), "{translated}")?;
+            Ok(builtins::ExitCode::Success)
+        } else {
+            writeln!(
+                context.stderr(),
+                "{}: {}: cannot translate path",
+                context.command_name,
+                self.path
+            )?;
+            Ok(builtins::ExitCode::Custom(1))
+        }
+    }
+}
+
+const WSL_UNC_PREFIXES: [&str; 2] = [r"\\wsl$\", r"\\wsl.localhost\"];
+
+/// Translates a Windows-style path (e.g., `C:\Users`) to its WSL-mounted equivalent
+/// (e.g., `/mnt/c/Users`). Returns `None` if the path refers to a location that has
+/// no WSL equivalent (e.g., a non-WSL network share).
+///
+/// # Arguments
+///
+/// * `path` - The path to translate.
+pub(crate) fn to_unix_path(path: &str) -> Option<String> {
+    if let Some((drive, rest)) = split_drive(path) {
+        return Some(std::format!(
+            "/mnt/{}{}",
+            drive.to_ascii_lowercase(),
+            rest.replace('\\', "/")
+        ));
+    }
+
+    let normalized = path.replace('/', "\\");
+    for prefix in WSL_UNC_PREFIXES {
+        if normalized
+            .get(..prefix.len())
+            .is_some_and(|p| p.eq_ignore_ascii_case(prefix))
+        {
+            // Skip past the distro name; everything after it is rooted at '/'.
+            let after_prefix = &normalized[prefix.len()..];
+            let rest = after_prefix
+                .find('\\')
+                .map_or("", |index| &after_prefix[index + 1..]);
+
+            return Some(std::format!("/{}", rest.replace('\\', "/")));
+        }
+    }
+
+    if normalized.starts_with(r"\\") {
+        return None;
+    }
+
+    Some(path.replace('\\', "/"))
+}
+
+/// Translates a WSL path (e.g., `/mnt/c/Users`) to its Windows equivalent (e.g.,
+/// `C:\Users`). Paths outside of the WSL drive mounts are translated to UNC paths
+/// under `\\wsl.localhost\`, which requires knowing the name of the current distro.
+///
+/// # Arguments
+///
+/// * `path` - The path to translate.
+/// * `distro` - The name of the current WSL distro, if known.
+/// * `forward_slashes` - Whether to use forward slashes as separators.
+pub(crate) fn to_windows_path(
+    path: &str,
+    distro: Option<&str>,
+    forward_slashes: bool,
+) -> Option<String> {
+    let translated = if let Some(rest) = path.strip_prefix("/mnt/") {
+        let mut chars = rest.chars();
+        match (chars.next(), chars.next()) {
+            (Some(drive), None | Some('/')) if drive.is_ascii_alphabetic() => {
+                let drive_rest = &rest[1..];
+                let drive_rest = if drive_rest.is_empty() {
+                    "/"
+                } else {
+                    drive_rest
+                };
+                std::format!("{}:{drive_rest}", drive.to_ascii_uppercase())
+            }
+            _ => std::format!("//wsl.localhost/{}{path}", distro?),
+        }
+    } else if path.starts_with('/') {
+        std::format!("//wsl.localhost/{}{path}", distro?)
+    } else {
+        path.to_owned()
+    };
+
+    if forward_slashes {
+        Some(translated)
+    } else {
+        Some(translated.replace('/', "\\"))
+    }
+}
+
+fn split_drive(path: &str) -> Option<(char, &str)> {
+    let mut chars = path.chars();
+    let drive = chars.next()?;
+    if !drive.is_ascii_alphabetic() || chars.next()? != ':' {
+        return None;
+    }
+
+    let rest = &path[2..];
+    if rest.is_empty() || rest.starts_with(['\\', '/']) {
+        Some((drive, rest))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_unix_path() {
+        assert_eq!(
+            to_unix_path(r"C:\Users\me").as_deref(),
+            Some("/mnt/c/Users/me")
+        );
+        assert_eq!(to_unix_path("d:/data").as_deref(), Some("/mnt/d/data"));
+        assert_eq!(to_unix_path("C:").as_deref(), Some("/mnt/c"));
+        assert_eq!(
+            to_unix_path(r"\\wsl$\Ubuntu\home\me").as_deref(),
+            Some("/home/me")
+        );
+        assert_eq!(
+            to_unix_path(r"\\wsl.localhost\Ubuntu").as_deref(),
+            Some("/")
+        );
+        assert_eq!(to_unix_path(r"some\dir").as_deref(), Some("some/dir"));
+        assert_eq!(to_unix_path(r"\\server\share"), None);
+    }
+
+    #[test]
+    fn test_to_windows_path() {
+        assert_eq!(
+            to_windows_path("/mnt/c/Users/me", None, false).as_deref(),
+            Some(r"C:\Users\me")
+        );
+        assert_eq!(
+            to_windows_path("/mnt/c", None, false).as_deref(),
+            Some(r"C:\")
+        );
+        assert_eq!(
+            to_windows_path("/mnt/c/Users", None, true).as_deref(),
+            Some("C:/Users")
+        );
+        assert_eq!(
+            to_windows_path("/home/me", Some("Ubuntu"), false).as_deref(),
+            Some(r"\\wsl.localhost\Ubuntu\home\me")
+        );
+        assert_eq!(to_windows_path("/home/me", None, false), None);
+        assert_eq!(
+            to_windows_path("some/dir", None, false).as_deref(),
+            Some(r"some\dir")
+        );
+    }
+}
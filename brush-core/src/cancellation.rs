@@ -0,0 +1,63 @@
+//! Cooperative cancellation of running commands. Commands run within the shell itself
+//! (e.g., loops, builtins, and expansions) can't be interrupted by a signal the way an
+//! external process can, so instead they check a shared token at regular points and stop
+//! early once cancellation has been requested. Anything that instead waits (e.g., on an
+//! external process or for input) awaits the token alongside, so it can stop waiting.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use crate::error;
+
+/// A token used to request that the commands being run by a shell stop at the next
+/// opportunity, as when the user presses Ctrl+C. Clones of a token share its state.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken(Arc<TokenState>);
+
+#[derive(Debug, Default)]
+struct TokenState {
+    /// Whether cancellation has been requested.
+    cancelled: AtomicBool,
+    /// Wakes anything awaiting cancellation.
+    notify: tokio::sync::Notify,
+}
+
+impl CancellationToken {
+    /// Requests cancellation.
+    pub fn cancel(&self) {
+        self.0.cancelled.store(true, Ordering::SeqCst);
+        self.0.notify.notify_waiters();
+    }
+
+    /// Returns whether cancellation has been requested.
+    pub fn is_cancelled(&self) -> bool {
+        self.0.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Withdraws any request for cancellation, e.g., before running a new command.
+    pub(crate) fn reset(&self) {
+        self.0.cancelled.store(false, Ordering::SeqCst);
+    }
+
+    /// Completes once cancellation has been requested (immediately, if it already has been).
+    pub(crate) async fn cancelled(&self) {
+        loop {
+            // N.B. We start listening before checking, so a request made in between isn't
+            // missed.
+            let notified = self.0.notify.notified();
+            if self.is_cancelled() {
+                return;
+            }
+            notified.await;
+        }
+    }
+
+    /// Returns [`error::Error::Interrupted`] if cancellation has been requested.
+    pub(crate) fn check(&self) -> Result<(), error::Error> {
+        if self.is_cancelled() {
+            Err(error::Error::Interrupted)
+        } else {
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,197 @@
+//! Resolution of command names to what the shell would invoke for them.
+//!
+//! When executing a simple command, the shell resolves its name by considering, in order:
+//!
+//! 1. If the name contains a path separator, the file at that path; nothing else is
+//!    considered.
+//! 2. In POSIX mode, special builtins (e.g., `:` or `eval`).
+//! 3. Shell functions, unless function lookup is suppressed (e.g., by `command`).
+//! 4. Builtins, excluding any disabled with `enable -n`.
+//! 5. Executables in `PATH`, starting with any remembered (i.e., hashed) location.
+//!
+//! Aliases and keywords are handled before a command is executed, but are reported (ahead
+//! of everything else) by the builtins that describe how names are resolved (e.g., `type`).
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use brush_parser::ast;
+
+use crate::sys::fs::PathExt;
+use crate::{builtins, functions, keywords, Shell};
+
+/// A way in which a name may be resolved as a command.
+pub(crate) enum ResolvedType {
+    Alias(String),
+    Keyword,
+    Function(Arc<ast::FunctionDefinition>),
+    Builtin,
+    File { path: PathBuf, hashed: bool },
+}
+
+impl ResolvedType {
+    /// Returns a description of the resolution, in the style of `type`.
+    ///
+    /// # Arguments
+    ///
+    /// * `shell` - The shell the name was resolved in.
+    /// * `name` - The name that was resolved.
+    pub(crate) fn describe(&self, shell: &Shell, name: &str) -> String {
+        match self {
+            ResolvedType::Alias(target) => std::format!("{name} is aliased to '{target}'"),
+            ResolvedType::Keyword => std::format!("{name} is a shell keyword"),
+            ResolvedType::Function(def) => std::format!("{name} is a function\n{def}"),
+            ResolvedType::Builtin => {
+                if shell.options.posix_mode && is_special_builtin(shell, name) {
+                    std::format!("{name} is a special shell builtin")
+                } else {
+                    std::format!("{name} is a shell builtin")
+                }
+            }
+            ResolvedType::File { path, hashed: true } => {
+                std::format!("{name} is hashed ({})", path.to_string_lossy())
+            }
+            ResolvedType::File {
+                path,
+                hashed: false,
+            } => std::format!("{name} is {}", path.to_string_lossy()),
+        }
+    }
+}
+
+/// What a command name resolves to for execution.
+pub(crate) enum ResolvedCommand {
+    Function(functions::FunctionRegistration),
+    Builtin(builtins::Registration),
+    External(PathBuf),
+}
+
+/// Returns all the ways the given name may be resolved as a command, in the order
+/// in which the shell considers them.
+///
+/// # Arguments
+///
+/// * `shell` - The shell to resolve the name in.
+/// * `name` - The name to resolve.
+/// * `force_path_search` - Whether to only search by file path.
+/// * `suppress_func_lookup` - Whether to skip looking up shell functions.
+pub(crate) fn resolve_types(
+    shell: &Shell,
+    name: &str,
+    force_path_search: bool,
+    suppress_func_lookup: bool,
+) -> Vec<ResolvedType> {
+    let mut types = vec![];
+
+    if !force_path_search {
+        // Check for aliases.
+        if let Some(a) = shell.aliases.get(name) {
+            types.push(ResolvedType::Alias(a.clone()));
+        }
+
+        // Check for keywords.
+        if keywords::is_keyword(shell, name) {
+            types.push(ResolvedType::Keyword);
+        }
+
+        // Check for special builtins that take precedence over functions.
+        let special_builtin_first = special_builtin_precedes_functions(shell, name);
+        if special_builtin_first {
+            types.push(ResolvedType::Builtin);
+        }
+
+        // Check for functions.
+        if !suppress_func_lookup {
+            if let Some(registration) = shell.funcs.get(name) {
+                types.push(ResolvedType::Function(registration.definition.clone()));
+            }
+        }
+
+        // Check for builtins.
+        if !special_builtin_first && shell.builtins.get(name).is_some_and(|b| !b.disabled) {
+            types.push(ResolvedType::Builtin);
+        }
+    }
+
+    // Look in path.
+    if name.contains(std::path::MAIN_SEPARATOR) {
+        if shell.get_absolute_path(Path::new(name)).executable() {
+            types.push(ResolvedType::File {
+                path: PathBuf::from(name),
+                hashed: false,
+            });
+        }
+    } else {
+        if let Some(path) = shell.program_location_cache.get(name) {
+            types.push(ResolvedType::File { path, hashed: true });
+        }
+
+        for item in shell.find_executables_in_path(name) {
+            types.push(ResolvedType::File {
+                path: item,
+                hashed: false,
+            });
+        }
+    }
+
+    types
+}
+
+/// Resolves the given command name to what should be invoked to execute it. Returns `None`
+/// if nothing was found.
+///
+/// # Arguments
+///
+/// * `shell` - The shell to resolve the name in; any executable found by searching `PATH`
+///   is remembered in its cache.
+/// * `name` - The name to resolve.
+/// * `use_functions` - Whether to consider shell functions.
+pub(crate) fn resolve_command(
+    shell: &mut Shell,
+    name: &str,
+    use_functions: bool,
+) -> Option<ResolvedCommand> {
+    if name.contains(std::path::MAIN_SEPARATOR) {
+        return Some(ResolvedCommand::External(PathBuf::from(name)));
+    }
+
+    let builtin = shell.builtins.get(name).filter(|b| !b.disabled).cloned();
+
+    if special_builtin_precedes_functions(shell, name) {
+        return builtin.map(ResolvedCommand::Builtin);
+    }
+
+    if use_functions {
+        if let Some(registration) = shell.funcs.get(name) {
+            return Some(ResolvedCommand::Function(registration.clone()));
+        }
+    }
+
+    if let Some(builtin) = builtin {
+        return Some(ResolvedCommand::Builtin(builtin));
+    }
+
+    shell
+        .find_first_executable_in_path_using_cache(name)
+        .map(ResolvedCommand::External)
+}
+
+/// Returns whether the given name is that of an enabled special builtin.
+///
+/// # Arguments
+///
+/// * `shell` - The shell to look up the name in.
+/// * `name` - The name to check.
+pub(crate) fn is_special_builtin(shell: &Shell, name: &str) -> bool {
+    !name.contains(std::path::MAIN_SEPARATOR)
+        && shell
+            .builtins
+            .get(name)
+            .is_some_and(|b| !b.disabled && b.special_builtin)
+}
+
+/// Returns whether the given name is that of a special builtin that's found before any
+/// function of the same name, as is the case in POSIX mode.
+fn special_builtin_precedes_functions(shell: &Shell, name: &str) -> bool {
+    shell.options.posix_mode && is_special_builtin(shell, name)
+}
@@ -9,12 +9,21 @@ use command_fds::{CommandFdExt, FdMapping};
 use itertools::Itertools;
 
 use crate::{
-    builtins, error, escape,
+    builtins, commandresolution, commandstats, error, escape, i18n,
     interp::{self, Execute, ProcessGroupPolicy},
     openfiles::{self, OpenFile, OpenFiles},
-    processes, sys, trace_categories, ExecutionParameters, ExecutionResult, Shell,
+    processes, sys, trace_categories, CancellationToken, ExecutionParameters, ExecutionResult,
+    Shell,
 };
 
+/// Text substituted for the output of a command substitution that's abandoned because it
+/// didn't complete in time.
+const TIMED_OUT_COMMAND_SUBSTITUTION_PLACEHOLDER: &str = "...";
+
+/// Exit status of a command substitution that's abandoned because it didn't complete in time
+/// (as with the `timeout` utility).
+const TIMED_OUT_COMMAND_SUBSTITUTION_EXIT_CODE: u8 = 124;
+
 /// Represents the result of spawning a command.
 pub(crate) enum CommandSpawnResult {
     /// The child process was spawned.
@@ -230,6 +239,18 @@ pub(crate) fn compose_std_command<S: AsRef<OsStr>>(
                 cmd.env(name, value_as_str.as_ref());
             }
         }
+
+        // Add in exported functions.
+        for (name, value) in shell.funcs.exported_env_vars().iter() {
+            cmd.env(name, value);
+        }
+    }
+
+    // Make sure the command may be executed.
+    if let Some(policy) = &shell.sandbox_policy {
+        if !policy.allows_command(&cmd) {
+            return Err(error::Error::CommandDeniedByPolicy(command_name.to_owned()));
+        }
     }
 
     // Redirect stdin, if applicable.
@@ -280,6 +301,10 @@ pub(crate) fn compose_std_command<S: AsRef<OsStr>>(
         }
     }
 
+    if let Some(policy) = &shell.sandbox_policy {
+        policy.prepare_command(&mut cmd)?;
+    }
+
     Ok(cmd)
 }
 
@@ -289,68 +314,48 @@ pub(crate) async fn execute(
     args: Vec<CommandArg>,
     use_functions: bool,
 ) -> Result<CommandSpawnResult, error::Error> {
-    if !cmd_context.command_name.contains(std::path::MAIN_SEPARATOR) {
-        let builtin = cmd_context
-            .shell
-            .builtins
-            .get(&cmd_context.command_name)
-            .cloned();
-
-        // Ignore the builtin if it's marked as disabled.
-        if builtin
-            .as_ref()
-            .is_some_and(|r| !r.disabled && r.special_builtin)
-        {
-            return execute_builtin_command(&builtin.unwrap(), cmd_context, args).await;
-        }
+    let resolved = commandresolution::resolve_command(
+        cmd_context.shell,
+        cmd_context.command_name.as_str(),
+        use_functions,
+    );
 
-        if use_functions {
-            if let Some(func_reg) = cmd_context
-                .shell
-                .funcs
-                .get(cmd_context.command_name.as_str())
-            {
-                // Strip the function name off args.
-                return invoke_shell_function(func_reg.definition.clone(), cmd_context, &args[1..])
-                    .await;
-            }
+    match resolved {
+        Some(commandresolution::ResolvedCommand::Builtin(builtin)) => {
+            execute_builtin_command(&builtin, cmd_context, args).await
         }
-
-        if let Some(builtin) = builtin {
-            if !builtin.disabled {
-                return execute_builtin_command(&builtin, cmd_context, args).await;
-            }
+        Some(commandresolution::ResolvedCommand::Function(func_reg)) => {
+            // Strip the function name off args.
+            invoke_shell_function(
+                func_reg.definition.clone(),
+                func_reg.line_number_offset(),
+                cmd_context,
+                &args[1..],
+            )
+            .await
         }
-
-        if let Some(path) = cmd_context
-            .shell
-            .find_first_executable_in_path_using_cache(&cmd_context.command_name)
-        {
+        Some(commandresolution::ResolvedCommand::External(path)) => {
             let resolved_path = path.to_string_lossy();
+
+            // Strip the command name off args.
             execute_external_command(
                 cmd_context,
                 resolved_path.as_ref(),
                 process_group_id,
                 &args[1..],
             )
-        } else {
+        }
+        None => {
             writeln!(
                 cmd_context.stderr(),
-                "{}: command not found",
-                cmd_context.command_name
+                "{}",
+                cmd_context.shell.localize(
+                    &i18n::COMMAND_NOT_FOUND,
+                    &[("command", &cmd_context.command_name)],
+                )
             )?;
             Ok(CommandSpawnResult::ImmediateExit(127))
         }
-    } else {
-        let resolved_path = cmd_context.command_name.clone();
-
-        // Strip the command name off args.
-        execute_external_command(
-            cmd_context,
-            resolved_path.as_str(),
-            process_group_id,
-            &args[1..],
-        )
     }
 }
 
@@ -386,14 +391,21 @@ pub(crate) fn execute_external_command(
 
     // Compose the std::process::Command that encapsulates what we want to launch.
     #[allow(unused_mut)]
-    let mut cmd = compose_std_command(
+    let mut cmd = match compose_std_command(
         context.shell,
         executable_path,
         context.command_name.as_str(),
         cmd_args.as_slice(),
         context.params.open_files,
         false, /* empty environment? */
-    )?;
+    ) {
+        Ok(cmd) => cmd,
+        Err(e @ error::Error::CommandDeniedByPolicy(_)) => {
+            writeln!(stderr, "{e}")?;
+            return Ok(CommandSpawnResult::ImmediateExit(126));
+        }
+        Err(e) => return Err(e),
+    };
 
     // Set up process group state.
     if new_pg {
@@ -409,10 +421,13 @@ pub(crate) fn execute_external_command(
     // Register some code to run in the forked child process before it execs
     // the target command.
     #[cfg(unix)]
-    if new_pg && child_stdin_is_terminal {
-        unsafe {
+    unsafe {
+        if new_pg && child_stdin_is_terminal {
             cmd.pre_exec(setup_process_before_exec);
         }
+
+        // N.B. This must follow taking the terminal, which relies on SIGTTOU being masked.
+        cmd.pre_exec(sys::signal::prepare_child_dispositions);
     }
 
     // When tracing is enabled, report.
@@ -425,8 +440,18 @@ pub(crate) fn execute_external_command(
             .join(" ")
     );
 
+    // Capture a baseline for measuring the command's resource usage, if requested.
+    let stats_baseline = if context.shell.options.record_command_stats {
+        let command_line = std::iter::once(context.command_name.clone())
+            .chain(cmd.get_args().map(|a| a.to_string_lossy().to_string()))
+            .join(" ");
+        Some(commandstats::CommandStatsBaseline::capture(command_line)?)
+    } else {
+        None
+    };
+
     match sys::process::spawn(cmd) {
-        Ok(child) => {
+        Ok(mut child) => {
             // Retrieve the pid.
             #[allow(clippy::cast_possible_wrap)]
             let pid = child.id().map(|id| id as i32);
@@ -438,8 +463,23 @@ pub(crate) fn execute_external_command(
                 tracing::warn!("could not retrieve pid for child process");
             }
 
+            // Place the process in the embedder's container, if there is one; a process
+            // that escaped its container could outlive any attempt to clean it up, so we
+            // don't let it run in that case.
+            if let (Some(container), Some(pid)) = (&context.shell.process_container, pid) {
+                if let Err(e) = container.add_process(pid) {
+                    let _ = child.start_kill();
+                    return Err(e);
+                }
+            }
+
             Ok(CommandSpawnResult::SpawnedProcess(
-                processes::ChildProcess::new(pid, child),
+                processes::ChildProcess::new(
+                    pid,
+                    child,
+                    stats_baseline,
+                    context.params.cancellation_token.clone(),
+                ),
             ))
         }
         Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
@@ -499,6 +539,8 @@ async fn execute_builtin_command(
                 return Ok(CommandSpawnResult::ContinueLoop(count))
             }
         },
+        // N.B. An interrupted builtin interrupts whatever it's part of, too.
+        Err(error::Error::Interrupted) => return Err(error::Error::Interrupted),
         Err(e) => {
             tracing::error!("error: {}", e);
             1
@@ -510,6 +552,7 @@ async fn execute_builtin_command(
 
 pub(crate) async fn invoke_shell_function(
     function_definition: Arc<ast::FunctionDefinition>,
+    line_number_offset: u32,
     mut context: ExecutionContext<'_>,
     args: &[CommandArg],
 ) -> Result<CommandSpawnResult, error::Error> {
@@ -531,9 +574,11 @@ pub(crate) async fn invoke_shell_function(
 
     // Note that we're going deeper. Once we do this, we need to make sure we don't bail early
     // before "exiting" the function.
-    context
-        .shell
-        .enter_function(context.command_name.as_str(), &function_definition)?;
+    context.shell.enter_function(
+        context.command_name.as_str(),
+        &function_definition,
+        line_number_offset,
+    )?;
 
     // Invoke the function.
     let result = body.execute(context.shell, &params).await;
@@ -547,7 +592,12 @@ pub(crate) async fn invoke_shell_function(
     // Restore positional parameters.
     context.shell.positional_parameters = prior_positional_params;
 
-    Ok(CommandSpawnResult::ImmediateExit(result?.exit_code))
+    let result = result?;
+    if result.exit_shell {
+        Ok(CommandSpawnResult::ExitShell(result.exit_code))
+    } else {
+        Ok(CommandSpawnResult::ImmediateExit(result.exit_code))
+    }
 }
 
 pub(crate) async fn invoke_command_in_subshell_and_get_output(
@@ -555,7 +605,69 @@ pub(crate) async fn invoke_command_in_subshell_and_get_output(
     s: String,
 ) -> Result<String, error::Error> {
     // Instantiate a subshell to run the command in.
-    let mut subshell = shell.clone();
+    let (output_str, exit_code) = run_command_substitution(shell.clone(), s).await?;
+
+    // Store the status.
+    shell.last_exit_status = exit_code;
+
+    Ok(output_str)
+}
+
+/// Runs the given command string in the given subshell, as for a command substitution;
+/// returns the command's output and exit code. If the shell has a deadline for command
+/// substitutions (e.g., because a prompt is being composed) and the command hasn't completed
+/// by then, it's interrupted (along with any processes it's running) and a placeholder is
+/// returned in place of its output.
+///
+/// # Arguments
+///
+/// * `subshell` - The subshell to run the command in.
+/// * `s` - The command string to run.
+pub(crate) async fn run_command_substitution(
+    mut subshell: Shell,
+    s: String,
+) -> Result<(String, u8), error::Error> {
+    let Some(deadline) = subshell.command_substitution_deadline else {
+        return run_command_substitution_to_completion(subshell, s).await;
+    };
+
+    // Give the command its own token, so it can be interrupted once its deadline passes
+    // without interrupting the shell that's waiting on it.
+    let shell_cancellation_token = subshell.cancellation_token.clone();
+    let cancellation_token = CancellationToken::default();
+    subshell.cancellation_token = cancellation_token.clone();
+
+    // N.B. The command runs as its own task, so that it can finish being interrupted (e.g.,
+    // reaping the processes it's running) after we've stopped waiting on it.
+    let mut command = tokio::spawn(run_command_substitution_to_completion(subshell, s));
+
+    tokio::select! {
+        result = &mut command => return result?,
+        () = shell_cancellation_token.cancelled() => {
+            cancellation_token.cancel();
+            return command.await?;
+        },
+        () = tokio::time::sleep_until(deadline) => (),
+    }
+
+    tracing::debug!(target: trace_categories::COMMANDS, "command substitution interrupted after its deadline passed");
+    cancellation_token.cancel();
+
+    Ok((
+        TIMED_OUT_COMMAND_SUBSTITUTION_PLACEHOLDER.to_owned(),
+        TIMED_OUT_COMMAND_SUBSTITUTION_EXIT_CODE,
+    ))
+}
+
+async fn run_command_substitution_to_completion(
+    mut subshell: Shell,
+    s: String,
+) -> Result<(String, u8), error::Error> {
+    // Outside of POSIX mode, command substitutions don't inherit `set -e` unless
+    // `inherit_errexit` is enabled.
+    if !subshell.options.posix_mode && !subshell.options.command_subst_inherits_errexit {
+        subshell.options.exit_on_nonzero_command_exit = false;
+    }
 
     // Set up pipe so we can read the output.
     let (reader, writer) = sys::pipes::pipe()?;
@@ -568,18 +680,15 @@ pub(crate) async fn invoke_command_in_subshell_and_get_output(
     params.process_group_policy = ProcessGroupPolicy::SameProcessGroup;
 
     // Run the command.
-    let result = subshell.run_string(s, &params).await?;
+    let result = subshell.run_nested_string(s, &params).await?;
 
     // Make sure the subshell and params are closed; among other things, this
     // ensures they're not holding onto the write end of the pipe.
     drop(subshell);
     drop(params);
 
-    // Store the status.
-    shell.last_exit_status = result.exit_code;
-
     // Extract output.
     let output_str = std::io::read_to_string(reader)?;
 
-    Ok(output_str)
+    Ok((output_str, result.exit_code))
 }
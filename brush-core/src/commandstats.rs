@@ -0,0 +1,131 @@
+//! Resource usage statistics for individual external commands, recorded when the
+//! `command_stats` option is enabled.
+
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+use crate::variables::{ShellValue, ShellVariable};
+use crate::{error, sys, trace_categories, Shell};
+
+/// Name of the associative array holding the statistics of the last external command.
+const STATS_VAR_NAME: &str = "BRUSH_COMMAND_STATS";
+
+/// Resource usage observed when an external command was spawned, against which its
+/// usage is measured once it completes.
+pub(crate) struct CommandStatsBaseline {
+    /// The command line of the command.
+    command_line: String,
+    /// When the command was spawned.
+    started: Instant,
+    /// User CPU time consumed by waited-on children of the shell when the command was
+    /// spawned.
+    children_user: Duration,
+    /// System CPU time consumed by waited-on children of the shell when the command was
+    /// spawned.
+    children_system: Duration,
+}
+
+impl CommandStatsBaseline {
+    /// Captures the baseline for a command that's about to be spawned.
+    ///
+    /// # Arguments
+    ///
+    /// * `command_line` - The command line of the command.
+    pub fn capture(command_line: String) -> Result<Self, error::Error> {
+        let (children_user, children_system) = sys::resource::get_children_user_and_system_time()?;
+
+        Ok(Self {
+            command_line,
+            started: Instant::now(),
+            children_user,
+            children_system,
+        })
+    }
+
+    /// Measures the resources used by the command since the baseline was captured; must be
+    /// called after the command has been waited on.
+    ///
+    /// # Arguments
+    ///
+    /// * `pid` - The process ID of the command, if known.
+    /// * `exit_code` - The exit code of the command.
+    pub fn finish(
+        self,
+        pid: Option<sys::process::ProcessId>,
+        exit_code: u8,
+    ) -> Result<CommandStats, error::Error> {
+        let (children_user, children_system) = sys::resource::get_children_user_and_system_time()?;
+
+        Ok(CommandStats {
+            command_line: self.command_line,
+            pid,
+            exit_code,
+            wall: self.started.elapsed(),
+            user: children_user.saturating_sub(self.children_user),
+            system: children_system.saturating_sub(self.children_system),
+            max_rss_kb: sys::resource::get_children_max_rss_kb()?,
+        })
+    }
+}
+
+/// Resources used by a completed external command.
+///
+/// N.B. CPU times are measured as the change in the usage of all waited-on children of the
+/// shell, so they include any other children (e.g., background jobs) reaped while the
+/// command ran. The maximum resident set size is the largest of any waited-on child of the
+/// shell so far, which is the command's own unless an earlier child used more.
+pub(crate) struct CommandStats {
+    command_line: String,
+    pid: Option<sys::process::ProcessId>,
+    exit_code: u8,
+    wall: Duration,
+    user: Duration,
+    system: Duration,
+    max_rss_kb: u64,
+}
+
+impl CommandStats {
+    /// Reports the statistics via a trace event and makes them available to the shell's
+    /// user via the `BRUSH_COMMAND_STATS` associative array.
+    ///
+    /// # Arguments
+    ///
+    /// * `shell` - The shell that ran the command.
+    pub fn record(self, shell: &mut Shell) -> Result<(), error::Error> {
+        tracing::info!(
+            target: trace_categories::COMMANDS,
+            command = self.command_line.as_str(),
+            pid = self.pid,
+            exit_code = self.exit_code,
+            wall_secs = self.wall.as_secs_f64(),
+            user_secs = self.user.as_secs_f64(),
+            system_secs = self.system.as_secs_f64(),
+            max_rss_kb = self.max_rss_kb,
+            "command completed"
+        );
+
+        let mut values = BTreeMap::new();
+        values.insert("command".to_owned(), self.command_line);
+        values.insert(
+            "pid".to_owned(),
+            self.pid.map(|pid| pid.to_string()).unwrap_or_default(),
+        );
+        values.insert("exit_status".to_owned(), self.exit_code.to_string());
+        values.insert("wall_time".to_owned(), format_secs(self.wall));
+        values.insert("user_time".to_owned(), format_secs(self.user));
+        values.insert("system_time".to_owned(), format_secs(self.system));
+        values.insert("max_rss_kb".to_owned(), self.max_rss_kb.to_string());
+
+        shell.env.set_global(
+            STATS_VAR_NAME,
+            ShellVariable::new(ShellValue::AssociativeArray(values)),
+        )?;
+
+        Ok(())
+    }
+}
+
+/// Formats a duration as seconds, with millisecond precision.
+fn format_secs(duration: Duration) -> String {
+    std::format!("{:.3}", duration.as_secs_f64())
+}
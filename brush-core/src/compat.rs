@@ -0,0 +1,197 @@
+//! Registry of known divergences between the behavior of this shell and bash (a.k.a.
+//! compatibility quirks). Some quirks can be toggled at runtime; each of those is an alias
+//! for the `shopt` option backing it, which the relevant modules consult.
+
+use lazy_static::lazy_static;
+use std::collections::BTreeMap;
+
+use crate::namedoptions::{OptionGetter, OptionSetter};
+use crate::{error, Shell};
+
+/// A known divergence between the behavior of this shell and bash.
+pub struct Quirk {
+    /// Short name identifying the quirk.
+    pub name: &'static str,
+    /// Describes how the shell's behavior diverges from bash's.
+    pub description: &'static str,
+    /// Accessors for the `shopt` option backing the quirk, if it can be toggled.
+    toggle: Option<(OptionGetter, OptionSetter)>,
+}
+
+impl Quirk {
+    fn fixed(name: &'static str, description: &'static str) -> Self {
+        Self {
+            name,
+            description,
+            toggle: None,
+        }
+    }
+
+    fn toggleable(name: &'static str, description: &'static str, shopt_option: &str) -> Self {
+        let definition = &crate::namedoptions::SHOPT_OPTIONS[shopt_option];
+        Self {
+            name,
+            description,
+            toggle: Some((definition.getter, definition.setter)),
+        }
+    }
+
+    /// Returns whether the quirk can be toggled at runtime.
+    pub fn is_toggleable(&self) -> bool {
+        self.toggle.is_some()
+    }
+
+    /// Returns whether the quirk is in effect in the given shell. Quirks that can't be
+    /// toggled are always in effect.
+    ///
+    /// # Arguments
+    ///
+    /// * `shell` - The shell to inspect.
+    pub fn is_enabled(&self, shell: &Shell) -> bool {
+        self.toggle
+            .as_ref()
+            .map_or(true, |(getter, _)| getter(&shell.options))
+    }
+
+    /// Enables or disables the quirk in the given shell.
+    ///
+    /// # Arguments
+    ///
+    /// * `shell` - The shell to update.
+    /// * `value` - Whether the quirk should be in effect.
+    pub fn set_enabled(&self, shell: &mut Shell, value: bool) -> Result<(), error::Error> {
+        let Some((_, setter)) = &self.toggle else {
+            return Err(error::Error::QuirkNotToggleable(self.name.to_owned()));
+        };
+
+        setter(&mut shell.options, value);
+        Ok(())
+    }
+}
+
+lazy_static! {
+    static ref QUIRKS: Vec<Quirk> = vec![
+        Quirk::toggleable(
+            "rust-regex",
+            "Regular expressions used with `=~` are interpreted with the syntax of the Rust `fancy_regex` crate instead of as POSIX extended regular expressions (`shopt rust_regex`).",
+            "rust_regex"
+        ),
+        Quirk::toggleable(
+            "echo-escapes",
+            "The `echo` builtin expands backslash escapes unless given `-E` (`shopt xpg_echo`).",
+            "xpg_echo"
+        ),
+        Quirk::fixed(
+            "regex-leftmost-alternation",
+            "Where alternatives in a regular expression could match at the same position, the leftmost alternative is preferred over the longest match.",
+        ),
+        Quirk::fixed(
+            "async-lists-in-process",
+            "Asynchronous lists (`cmd &`) run as tasks within the shell process rather than in forked subshells.",
+        ),
+        Quirk::fixed(
+            "random-sequence",
+            "Assigning a seed to `RANDOM` yields a repeatable sequence, but not the same sequence as bash.",
+        ),
+        Quirk::fixed(
+            "funcname-frames",
+            "`FUNCNAME`, `BASH_LINENO`, and `BASH_SOURCE` only include frames for shell functions, omitting bash's `main` and `source` frames.",
+        ),
+    ];
+}
+
+/// Returns all known compatibility quirks.
+pub fn quirks() -> impl Iterator<Item = &'static Quirk> {
+    QUIRKS.iter()
+}
+
+/// Looks up a compatibility quirk by name.
+///
+/// # Arguments
+///
+/// * `name` - The name of the quirk.
+pub fn find_quirk(name: &str) -> Option<&'static Quirk> {
+    QUIRKS.iter().find(|quirk| quirk.name == name)
+}
+
+/// Returns the current value of each option that may be set via `set -o`, keyed by name.
+///
+/// # Arguments
+///
+/// * `shell` - The shell to inspect.
+pub fn set_o_option_values(shell: &Shell) -> BTreeMap<&'static str, bool> {
+    crate::namedoptions::SET_O_OPTIONS
+        .iter()
+        .map(|(name, definition)| (*name, (definition.getter)(&shell.options)))
+        .collect()
+}
+
+/// Returns the current value of each option that may be set via `shopt`, keyed by name.
+///
+/// # Arguments
+///
+/// * `shell` - The shell to inspect.
+pub fn shopt_option_values(shell: &Shell) -> BTreeMap<&'static str, bool> {
+    crate::namedoptions::SHOPT_OPTIONS
+        .iter()
+        .map(|(name, definition)| (*name, (definition.getter)(&shell.options)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+
+    #[test]
+    fn quirk_lookup() {
+        for quirk in quirks() {
+            assert!(std::ptr::eq(
+                find_quirk(quirk.name).unwrap_or_else(|| panic!("{} not found", quirk.name)),
+                quirk
+            ));
+        }
+
+        assert!(find_quirk("not-a-quirk").is_none());
+    }
+
+    #[tokio::test]
+    async fn toggleable_quirks_alias_shopt_options() -> Result<()> {
+        let mut shell = Shell::new(&crate::CreateOptions::default()).await?;
+        let quirk = find_quirk("echo-escapes").ok_or_else(|| anyhow::anyhow!("no quirk"))?;
+        assert!(quirk.is_toggleable());
+
+        for value in [true, false] {
+            quirk.set_enabled(&mut shell, value)?;
+            assert_eq!(quirk.is_enabled(&shell), value);
+            assert_eq!(shopt_option_values(&shell).get("xpg_echo"), Some(&value));
+
+            let params = shell.default_exec_params();
+            let command = if value {
+                "shopt -u xpg_echo"
+            } else {
+                "shopt -s xpg_echo"
+            };
+            shell.run_string(command.to_owned(), &params).await?;
+            assert_eq!(quirk.is_enabled(&shell), !value);
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn fixed_quirks_are_always_enabled() -> Result<()> {
+        let mut shell = Shell::new(&crate::CreateOptions::default()).await?;
+
+        for quirk in quirks().filter(|quirk| !quirk.is_toggleable()) {
+            assert!(quirk.is_enabled(&shell));
+            assert!(matches!(
+                quirk.set_enabled(&mut shell, false),
+                Err(error::Error::QuirkNotToggleable(name)) if name == quirk.name
+            ));
+            assert!(quirk.is_enabled(&shell));
+        }
+
+        Ok(())
+    }
+}
@@ -6,16 +6,23 @@ use std::{
     borrow::Cow,
     collections::HashMap,
     path::{Path, PathBuf},
+    sync::{Arc, Mutex},
 };
 
 use crate::{
-    commands, env, error, escape, jobs, namedoptions, patterns,
+    commands, env, error, escape, frecency, jobs, namedoptions, patterns,
     sys::{self, users},
     trace_categories, traps,
     variables::{self, ShellValueLiteral},
     Shell,
 };
 
+mod external;
+mod hostnames;
+
+pub use external::ExternalCompletionProtocol;
+pub use hostnames::HostnameCompleter;
+
 /// Type of action to take to generate completion candidates.
 #[derive(Clone, Debug, ValueEnum)]
 pub enum CompleteAction {
@@ -58,7 +65,7 @@ pub enum CompleteAction {
     /// Complete with names of valid shell help topics.
     #[clap(name = "helptopic")]
     HelpTopic,
-    /// Complete with the system's hostname(s).
+    /// Complete with hostnames known to the system and the user's ssh configuration.
     #[clap(name = "hostname")]
     HostName,
     /// Complete with the command names of shell-managed jobs.
@@ -67,6 +74,11 @@ pub enum CompleteAction {
     /// Complete with valid shell keywords.
     #[clap(name = "keyword")]
     Keyword,
+    /// Complete with the paths of directories ranked by how frequently and recently they've
+    /// been visited, matching the words up to and including the one being completed (other
+    /// than the command name and options) as keywords, as the `j` builtin does.
+    #[clap(name = "rankeddir")]
+    RankedDirectory,
     /// Complete with the command names of running shell-managed jobs.
     #[clap(name = "running")]
     Running,
@@ -138,6 +150,52 @@ pub struct Config {
     /// Optionally, stores the current completion options in effect. May be mutated
     /// while a completion generation is in-flight.
     pub current_completion_options: Option<GenerationOptions>,
+
+    /// Source of hostname completions; shared across clones so its cache is too.
+    hostnames: Arc<Mutex<HostnameCompleter>>,
+
+    /// Candidates generated so far by an in-flight completion request.
+    partial_completions: PartialCompletions,
+}
+
+/// Handle to the candidates generated so far by an in-flight completion request, which can
+/// be retrieved if the request is abandoned before it completes (e.g., because it's taking
+/// too long).
+#[derive(Clone, Default)]
+pub struct PartialCompletions(Arc<Mutex<Option<Completions>>>);
+
+impl PartialCompletions {
+    fn lock(&self) -> std::sync::MutexGuard<'_, Option<Completions>> {
+        self.0
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+
+    fn begin(&self, insertion_index: usize, delete_count: usize) {
+        *self.lock() = Some(Completions {
+            insertion_index,
+            delete_count,
+            ..Completions::default()
+        });
+    }
+
+    fn add(&self, candidates: &IndexSet<String>) {
+        if let Some(completions) = self.lock().as_mut() {
+            completions.candidates.extend(candidates.iter().cloned());
+        }
+    }
+
+    fn end(&self) {
+        *self.lock() = None;
+    }
+
+    /// Takes the candidates generated so far by the in-flight completion request, if
+    /// there is one.
+    pub fn take(&self) -> Option<Completions> {
+        let mut completions = self.lock().take()?;
+        completions.candidates.sort();
+        Some(completions)
+    }
 }
 
 /// Options for generating completions.
@@ -184,6 +242,8 @@ pub struct Spec {
     pub function_name: Option<String>,
     /// Optionally, the name of a command to execute to generate completions.
     pub command: Option<String>,
+    /// The protocol spoken by `command`.
+    pub command_protocol: ExternalCompletionProtocol,
 
     //
     // Filters
@@ -267,6 +327,8 @@ impl Spec {
             }
         }
         if let Some(function_name) = &self.function_name {
+            shell.completion_config.partial_completions.add(&candidates);
+
             let call_result = self
                 .call_completion_function(shell, function_name.as_str(), context)
                 .await?;
@@ -279,6 +341,8 @@ impl Spec {
             }
         }
         if let Some(command) = &self.command {
+            shell.completion_config.partial_completions.add(&candidates);
+
             let mut new_candidates = self
                 .call_completion_command(shell, command.as_str(), context)
                 .await?;
@@ -371,7 +435,7 @@ impl Spec {
         }
 
         // Sort, unless blocked by options.
-        if !self.options.no_sort {
+        if !options.no_sort {
             candidates.sort();
         }
 
@@ -470,13 +534,8 @@ impl Spec {
                     }
                 }
                 CompleteAction::HostName => {
-                    // N.B. We only retrieve one hostname.
-                    if let Ok(name) = sys::network::get_hostname() {
-                        let name = name.to_string_lossy();
-                        if name.starts_with(token) {
-                            candidates.insert(name.to_string());
-                        }
-                    }
+                    let mut hostname_completions = get_hostname_completions(shell, token);
+                    candidates.append(&mut hostname_completions);
                 }
                 CompleteAction::Job => {
                     for job in &shell.jobs.jobs {
@@ -493,6 +552,28 @@ impl Spec {
                         }
                     }
                 }
+                CompleteAction::RankedDirectory => {
+                    if let Some(store) = &shell.directory_rank_store {
+                        let mut keywords: Vec<_> = context
+                            .tokens
+                            .iter()
+                            .take(context.token_index)
+                            .skip(1)
+                            .map(|t| t.to_str())
+                            .filter(|word| !word.starts_with('-'))
+                            .collect();
+                        keywords.push(token);
+
+                        let ranks = store.ranks()?;
+                        for rank in frecency::find_matches(
+                            ranks.as_slice(),
+                            keywords.as_slice(),
+                            frecency::current_time(),
+                        ) {
+                            candidates.insert(rank.path.to_string_lossy().to_string());
+                        }
+                    }
+                }
                 CompleteAction::Running => {
                     for job in &shell.jobs.jobs {
                         if matches!(job.state, jobs::JobState::Running) {
@@ -564,18 +645,23 @@ impl Spec {
         context: &Context<'_>,
     ) -> Result<IndexSet<String>, error::Error> {
         // Move to a subshell so we can start filling out variables.
-        let mut shell = shell.clone();
+        let mut subshell = shell.clone();
 
-        let vars_and_values: Vec<(&str, ShellValueLiteral)> = vec![
+        // N.B. We only generate completions in response to the user pressing tab,
+        // for which bash sets both COMP_KEY and COMP_TYPE to the tab character's code.
+        let mut vars_and_values: Vec<(&str, ShellValueLiteral)> = vec![
             ("COMP_LINE", context.input_line.into()),
             ("COMP_POINT", context.cursor_index.to_string().into()),
-            // TODO: add COMP_KEY
-            // TODO: add COMP_TYPE
+            ("COMP_KEY", "9".into()),
+            ("COMP_TYPE", "9".into()),
         ];
+        for (var, value) in self.command_protocol.env_vars() {
+            vars_and_values.push((*var, (*value).into()));
+        }
 
         // Fill out variables.
         for (var, value) in vars_and_values {
-            shell.env.update_or_add(
+            subshell.env.update_or_add(
                 var,
                 value,
                 |v| {
@@ -587,18 +673,21 @@ impl Spec {
             )?;
         }
 
-        // Compute args.
-        let mut args = vec![
-            context.command_name.unwrap_or(""),
-            context.token_to_complete,
-        ];
-        if let Some(preceding_token) = context.preceding_token {
-            args.push(preceding_token);
+        // Compute args from the words up to (and including) the one being completed.
+        let mut words: Vec<&str> = context
+            .tokens
+            .iter()
+            .take(context.token_index)
+            .map(|t| t.to_str())
+            .collect();
+        if words.is_empty() {
+            words.push(context.command_name.unwrap_or(""));
         }
+        words.push(context.token_to_complete);
 
         // Compose the full command line.
         let mut command_line = command_name.to_owned();
-        for arg in args {
+        for arg in self.command_protocol.args(words.as_slice()) {
             command_line.push(' ');
 
             let escaped_arg = escape::quote_if_needed(arg, escape::QuoteMode::Quote);
@@ -607,15 +696,18 @@ impl Spec {
 
         // Run the command.
         let output =
-            commands::invoke_command_in_subshell_and_get_output(&mut shell, command_line).await?;
+            commands::invoke_command_in_subshell_and_get_output(&mut subshell, command_line)
+                .await?;
 
-        // Split results.
-        let mut candidates = IndexSet::new();
-        for line in output.lines() {
-            candidates.insert(line.to_owned());
+        // Interpret its output; some protocols can also adjust the options in effect.
+        let completions = self.command_protocol.parse_output(output.as_str());
+        if let Some(options) = &mut shell.completion_config.current_completion_options {
+            options.no_space |= completions.no_space;
+            options.no_sort |= completions.no_sort;
+            options.default |= completions.default;
         }
 
-        Ok(candidates)
+        Ok(completions.candidates)
     }
 
     async fn call_completion_function(
@@ -861,6 +953,11 @@ impl Config {
         }
     }
 
+    /// Returns a handle to the candidates generated so far by in-flight completion requests.
+    pub fn partial_completions(&self) -> PartialCompletions {
+        self.partial_completions.clone()
+    }
+
     /// Generates completions for the given input line and cursor position.
     ///
     /// # Arguments
@@ -877,8 +974,29 @@ impl Config {
     ) -> Result<Completions, error::Error> {
         const MAX_RESTARTS: u32 = 10;
 
-        // Make a best-effort attempt to tokenize.
-        let tokens = Self::tokenize_input_for_completion(shell, input);
+        // Like bash, complete the arguments of an alias as those of the command it expands to.
+        // Completion then proceeds on the expanded input; we only need to translate the
+        // insertion index back to the original input at the end.
+        let expanded = self.expand_command_alias_in_input(shell, input, position);
+        let original_position = position;
+        let (input, position) = expanded
+            .as_ref()
+            .map_or((input, position), |(input, position)| {
+                (input.as_str(), *position)
+            });
+        let alias_offset = i32::try_from(position)? - i32::try_from(original_position)?;
+
+        // Make a best-effort attempt to tokenize; like bash, we further split words at
+        // any of the characters in COMP_WORDBREAKS.
+        let word_breaks = shell
+            .env
+            .get_str("COMP_WORDBREAKS")
+            .map(|value| value.to_string())
+            .unwrap_or_default();
+        let tokens = split_tokens_at_word_breaks(
+            Self::tokenize_input_for_completion(shell, input),
+            word_breaks.as_str(),
+        );
 
         let cursor: i32 = i32::try_from(position)?;
         let mut preceding_token = None;
@@ -916,6 +1034,14 @@ impl Config {
                 let token_str = token.to_str();
                 completion_prefix = &token_str[..offset_into_token];
 
+                // If the token is made up of word break characters (e.g., the ':' in
+                // `host:`), then the word being completed is the empty one following it,
+                // though COMP_CWORD still refers to the token.
+                if is_word_break_token(token_str, word_breaks.as_str()) {
+                    insertion_index = cursor;
+                    completion_prefix = "";
+                }
+
                 // Update token index.
                 completion_token_index = i;
 
@@ -927,6 +1053,8 @@ impl Config {
             preceding_token = Some(token);
         }
 
+        let insertion_index = insertion_index - alias_offset;
+
         // If the position is after the last token, then we need to insert an empty
         // token for the new token to be generated.
         let empty_token =
@@ -954,6 +1082,9 @@ impl Config {
                 cursor_index: position,
             };
 
+            self.partial_completions
+                .begin(insertion_index as usize, completion_prefix.len());
+
             result = self
                 .get_completions_for_token(shell, completion_context)
                 .await;
@@ -961,6 +1092,8 @@ impl Config {
             restart_count += 1;
         }
 
+        self.partial_completions.end();
+
         match result {
             Answer::Candidates(candidates, options) => Ok(Completions {
                 insertion_index: insertion_index as usize,
@@ -977,6 +1110,39 @@ impl Config {
         }
     }
 
+    /// Expands the alias named by the first word of the input, if there is one and aliases
+    /// are being expanded, returning the expanded input and the cursor's position in it. The
+    /// first word is left alone while it's the word being completed, or if there's a
+    /// completion spec for it.
+    fn expand_command_alias_in_input(
+        &self,
+        shell: &mut Shell,
+        input: &str,
+        position: usize,
+    ) -> Option<(String, usize)> {
+        if !shell.options.expand_aliases {
+            return None;
+        }
+
+        let tokens = Self::tokenize_input_for_completion(shell, input);
+        let brush_parser::Token::Word(name, location) = tokens.first()? else {
+            return None;
+        };
+
+        let start = usize::try_from(location.start.index).ok()?;
+        let end = usize::try_from(location.end.index).ok()?;
+        if position <= end || self.commands.contains_key(name) {
+            return None;
+        }
+
+        let expansion = shell.expand_command_alias(name)?;
+
+        let mut expanded_input = input.to_owned();
+        expanded_input.replace_range(start..end, expansion.as_str());
+
+        Some((expanded_input, position - (end - start) + expansion.len()))
+    }
+
     fn tokenize_input_for_completion(shell: &mut Shell, input: &str) -> Vec<brush_parser::Token> {
         // Best-effort tokenization.
         if let Ok(tokens) = brush_parser::tokenize_str_with_options(
@@ -996,6 +1162,7 @@ impl Config {
     async fn get_completions_for_token(&self, shell: &mut Shell, context: Context<'_>) -> Answer {
         // See if we can find a completion spec matching the current command.
         let mut found_spec: Option<&Spec> = None;
+        let builtin_spec;
 
         if let Some(command_name) = context.command_name {
             if context.token_index == 0 {
@@ -1015,6 +1182,12 @@ impl Config {
                     }
                 }
 
+                // Built-in commands supply their own specs, unless the user has provided one.
+                if found_spec.is_none() {
+                    builtin_spec = get_builtin_completion_spec(shell, &context);
+                    found_spec = builtin_spec.as_ref();
+                }
+
                 if found_spec.is_none() {
                     if let Some(spec) = &self.default {
                         found_spec = Some(spec);
@@ -1035,6 +1208,8 @@ impl Config {
                 .unwrap_or_else(|_err| {
                     Answer::Candidates(IndexSet::new(), ProcessingOptions::default())
                 })
+        } else if let Some(answer) = get_remote_host_completions(shell, &context).await {
+            answer
         } else {
             // If we didn't find a spec, then fall back to basic completion.
             get_completions_using_basic_lookup(shell, &context).await
@@ -1083,6 +1258,150 @@ fn get_command_completions(shell: &Shell, context: &Context) -> IndexSet<String>
     candidates.into_iter().collect()
 }
 
+fn get_builtin_completion_spec(shell: &Shell, context: &Context<'_>) -> Option<Spec> {
+    let command_name = context.command_name?;
+
+    // Functions take precedence over builtins of the same name.
+    if shell.funcs.get(command_name).is_some() {
+        return None;
+    }
+
+    let registration = shell.builtins.get(command_name)?;
+    if registration.disabled {
+        return None;
+    }
+
+    let completion_spec_func = registration.completion_spec_func?;
+
+    let preceding_args: Vec<_> = context
+        .tokens
+        .iter()
+        .take(context.token_index)
+        .skip(1)
+        .map(|token| token.to_str())
+        .collect();
+
+    Some(completion_spec_func(
+        preceding_args.as_slice(),
+        context.token_to_complete,
+    ))
+}
+
+fn get_hostname_completions(shell: &Shell, token_to_complete: &str) -> IndexSet<String> {
+    let home_dir = shell.get_home_dir();
+    let hosts_file = shell
+        .env
+        .get_str("HOSTFILE")
+        .filter(|value| !value.is_empty())
+        .map(|value| PathBuf::from(value.as_ref()));
+
+    let mut candidates = shell
+        .completion_config
+        .hostnames
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+        .get_completions(
+            token_to_complete,
+            home_dir.as_deref(),
+            hosts_file.as_deref(),
+        );
+
+    if let Ok(name) = sys::network::get_hostname() {
+        let name = name.to_string_lossy();
+        if name.starts_with(token_to_complete) {
+            candidates.insert(name.to_string());
+        }
+    }
+
+    candidates
+}
+
+/// Commands taking a remote host as `[user@]host`.
+const SSH_STYLE_COMMANDS: [&str; 3] = ["ssh", "sftp", "slogin"];
+
+/// Commands taking remote files as `[user@]host:path`, along with local files.
+const SCP_STYLE_COMMANDS: [&str; 2] = ["scp", "rsync"];
+
+/// Short options of ssh-style and scp-style commands that take an argument, which isn't
+/// a host.
+const SSH_OPTIONS_WITH_ARGS: &str = "bcDEeFIiJLlmOoPpQRSWw";
+
+/// Generates completions for the remote host targets of ssh-style and scp-style commands
+/// that don't have a completion spec. Returns `None` if the command isn't one of those, or
+/// if the token being completed isn't a host.
+async fn get_remote_host_completions(shell: &Shell, context: &Context<'_>) -> Option<Answer> {
+    if context.token_index == 0 {
+        return None;
+    }
+
+    let command_name = Path::new(context.command_name?).file_name()?.to_str()?;
+    let is_scp_style = if SSH_STYLE_COMMANDS.contains(&command_name) {
+        false
+    } else if SCP_STYLE_COMMANDS.contains(&command_name) {
+        true
+    } else {
+        return None;
+    };
+
+    // With the default COMP_WORDBREAKS, `user@host:path` is split into separate tokens, and
+    // paths on remote hosts can't be completed.
+    if context.preceding_token == Some(":") {
+        return is_scp_style
+            .then(|| Answer::Candidates(IndexSet::new(), ProcessingOptions::default()));
+    }
+    let follows_user = context.preceding_token == Some("@");
+
+    // Options, their arguments, and paths all get the usual completion.
+    let token = context.token_to_complete;
+    if token.starts_with('-') || token.contains(['/', ':']) {
+        return None;
+    }
+    if let Some(preceding_token) = context.preceding_token {
+        if let Some(options) = preceding_token.strip_prefix('-') {
+            if options
+                .chars()
+                .last()
+                .is_some_and(|c| SSH_OPTIONS_WITH_ARGS.contains(c))
+            {
+                return None;
+            }
+        }
+    }
+
+    // Preserve any user name preceding the host.
+    let (user_prefix, host_prefix) = token
+        .rfind('@')
+        .map_or(("", token), |i| token.split_at(i + 1));
+    let host_suffix = if is_scp_style { ":" } else { "" };
+
+    let mut candidates: IndexSet<String> = get_hostname_completions(shell, host_prefix)
+        .into_iter()
+        .map(|host| std::format!("{user_prefix}{host}{host_suffix}"))
+        .collect();
+
+    // Local files are valid targets for scp-style commands, too.
+    if is_scp_style && user_prefix.is_empty() && !follows_user {
+        let mut file_candidates = get_file_completions(shell, token, false).await;
+        candidates.append(&mut file_candidates);
+    }
+
+    if candidates.is_empty() {
+        return None;
+    }
+
+    candidates.sort();
+
+    // Don't follow a `host:` with a space; the path on the host is still to come.
+    let options = ProcessingOptions {
+        treat_as_filenames: is_scp_style,
+        no_autoquote_filenames: false,
+        no_trailing_space_at_end_of_line: is_scp_style
+            && candidates.iter().all(|c| c.ends_with(host_suffix)),
+    };
+
+    Some(Answer::Candidates(candidates, options))
+}
+
 async fn get_completions_using_basic_lookup(shell: &Shell, context: &Context<'_>) -> Answer {
     let mut candidates = get_file_completions(shell, context.token_to_complete, false).await;
 
@@ -1180,6 +1499,99 @@ fn simple_tokenize_by_delimiters(input: &str, delimiters: &[char]) -> Vec<brush_
     tokens
 }
 
+/// Returns whether the given character splits words (in addition to whitespace), given the
+/// value of COMP_WORDBREAKS; quote characters in COMP_WORDBREAKS begin quoted words rather
+/// than splitting them.
+fn is_word_break_char(c: char, word_breaks: &str) -> bool {
+    !c.is_whitespace() && c != '"' && c != '\'' && word_breaks.contains(c)
+}
+
+fn is_word_break_token(token_str: &str, word_breaks: &str) -> bool {
+    !token_str.is_empty()
+        && token_str
+            .chars()
+            .all(|c| is_word_break_char(c, word_breaks))
+}
+
+/// Splits word tokens at unquoted word break characters (see `is_word_break_char`), as bash
+/// does when computing COMP_WORDS; each run of word break characters becomes a token of its
+/// own (e.g., `host:path` is split into `host`, `:`, and `path`).
+#[allow(clippy::cast_possible_truncation)]
+#[allow(clippy::cast_possible_wrap)]
+fn split_tokens_at_word_breaks(
+    tokens: Vec<brush_parser::Token>,
+    word_breaks: &str,
+) -> Vec<brush_parser::Token> {
+    let mut result = vec![];
+
+    for token in tokens {
+        let brush_parser::Token::Word(text, location) = &token else {
+            result.push(token);
+            continue;
+        };
+
+        // Find the boundaries between runs of word break characters and runs of other
+        // characters, paying attention to quoting.
+        let mut boundaries = vec![];
+        let mut last_was_break = None;
+        let mut in_single_quotes = false;
+        let mut in_double_quotes = false;
+        let mut escaped = false;
+        for (i, c) in text.char_indices() {
+            let is_break = !in_single_quotes
+                && !in_double_quotes
+                && !escaped
+                && is_word_break_char(c, word_breaks);
+
+            if escaped {
+                escaped = false;
+            } else if in_single_quotes {
+                in_single_quotes = c != '\'';
+            } else if c == '\\' {
+                escaped = true;
+            } else if in_double_quotes {
+                in_double_quotes = c != '"';
+            } else if c == '\'' {
+                in_single_quotes = true;
+            } else if c == '"' {
+                in_double_quotes = true;
+            }
+
+            if last_was_break.is_some_and(|last_was_break| last_was_break != is_break) {
+                boundaries.push(i);
+            }
+            last_was_break = Some(is_break);
+        }
+
+        if boundaries.is_empty() {
+            result.push(token);
+            continue;
+        }
+
+        let mut start = 0;
+        boundaries.push(text.len());
+        for end in boundaries {
+            let offset_position = |offset: usize| brush_parser::SourcePosition {
+                index: location.start.index + offset as i32,
+                line: location.start.line,
+                column: location.start.column + offset as i32,
+            };
+
+            result.push(brush_parser::Token::Word(
+                text[start..end].to_owned(),
+                brush_parser::TokenLocation {
+                    start: offset_position(start),
+                    end: offset_position(end),
+                },
+            ));
+
+            start = end;
+        }
+    }
+
+    result
+}
+
 fn completion_filter_pattern_matches(
     pattern: &str,
     candidate: &str,
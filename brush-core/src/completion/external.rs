@@ -0,0 +1,253 @@
+//! Protocols spoken by external commands that generate completions (i.e., those registered
+//! via `complete -C`). Besides bash's own protocol, many CLIs built with popular frameworks
+//! can generate their own completions dynamically, as can carapace on behalf of others.
+
+use clap::ValueEnum;
+use indexmap::IndexSet;
+
+/// Protocol used to invoke an external command that generates completions, and to interpret
+/// its output.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, ValueEnum)]
+pub enum ExternalCompletionProtocol {
+    /// bash's protocol: the command is invoked with the name of the command whose arguments
+    /// are being completed, the word being completed, and the preceding word; it writes one
+    /// candidate per line.
+    #[default]
+    #[clap(name = "bash")]
+    Bash,
+    /// The dynamic protocol of CLIs built with `clap_complete`: the command is invoked as
+    /// `COMPLETE=fish command -- <words>`; it writes one candidate per line, each optionally
+    /// followed by a tab and a description.
+    #[clap(name = "clap")]
+    Clap,
+    /// The protocol of CLIs built with cobra: the command is invoked as
+    /// `command __complete <args>`; it writes one candidate per line, each optionally followed
+    /// by a tab and a description, and then a line holding `:<directive>`.
+    #[clap(name = "cobra")]
+    Cobra,
+    /// carapace's protocol: carapace is invoked as `carapace <name> export <words>`; it writes
+    /// a JSON object describing the candidates.
+    #[clap(name = "carapace")]
+    Carapace,
+}
+
+/// Completions generated by an external command.
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct ExternalCompletions {
+    /// The candidates.
+    pub candidates: IndexSet<String>,
+    /// Don't append a space to the candidates.
+    pub no_space: bool,
+    /// Don't sort the candidates.
+    pub no_sort: bool,
+    /// Fall back to completing file names if there are no candidates.
+    pub default: bool,
+}
+
+/// Directives that cobra-based commands append to their completions.
+mod cobra_directive {
+    pub const ERROR: u32 = 1;
+    pub const NO_SPACE: u32 = 2;
+    pub const NO_FILE_COMP: u32 = 4;
+    pub const KEEP_ORDER: u32 = 32;
+}
+
+impl ExternalCompletionProtocol {
+    /// Returns the variables to add to the command's environment, beyond the usual ones
+    /// (e.g., `COMP_LINE`).
+    pub(crate) const fn env_vars(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Self::Clap => &[("COMPLETE", "fish")],
+            Self::Bash | Self::Cobra | Self::Carapace => &[],
+        }
+    }
+
+    /// Returns the arguments to invoke the command with.
+    ///
+    /// # Arguments
+    ///
+    /// * `words` - The words of the command line, from the name of the command whose
+    ///   arguments are being completed up to (and including) the word being completed.
+    pub(crate) fn args<'a>(self, words: &[&'a str]) -> Vec<&'a str> {
+        let command_name = words.first().copied().unwrap_or_default();
+
+        let mut args = vec![];
+        match self {
+            Self::Bash => {
+                args.push(command_name);
+                if let Some((word, preceding)) = words.split_last() {
+                    args.push(word);
+                    args.extend(preceding.last());
+                }
+            }
+            Self::Clap => {
+                args.push("--");
+                args.extend(words);
+            }
+            Self::Cobra => {
+                args.push("__complete");
+                args.extend(words.iter().skip(1));
+            }
+            Self::Carapace => {
+                args.push(command_name);
+                args.push("export");
+                args.extend(words);
+            }
+        }
+
+        args
+    }
+
+    /// Interprets the output of the command.
+    ///
+    /// # Arguments
+    ///
+    /// * `output` - The output written by the command.
+    pub(crate) fn parse_output(self, output: &str) -> ExternalCompletions {
+        match self {
+            Self::Bash => ExternalCompletions {
+                candidates: output.lines().map(ToOwned::to_owned).collect(),
+                ..ExternalCompletions::default()
+            },
+            Self::Clap => ExternalCompletions {
+                candidates: parse_described_candidates(output.lines()),
+                ..ExternalCompletions::default()
+            },
+            Self::Cobra => parse_cobra_output(output),
+            Self::Carapace => parse_carapace_output(output),
+        }
+    }
+}
+
+/// Parses lines holding a candidate, each optionally followed by a tab and a description.
+fn parse_described_candidates<'a>(lines: impl Iterator<Item = &'a str>) -> IndexSet<String> {
+    lines
+        .map(|line| {
+            line.split_once('\t')
+                .map_or(line, |(candidate, _)| candidate)
+        })
+        .filter(|candidate| !candidate.is_empty())
+        .map(ToOwned::to_owned)
+        .collect()
+}
+
+fn parse_cobra_output(output: &str) -> ExternalCompletions {
+    let mut lines: Vec<_> = output.lines().collect();
+
+    // Without a directive, cobra's default behavior applies: fall back to file names.
+    let directive = lines
+        .last()
+        .and_then(|line| line.strip_prefix(':'))
+        .and_then(|directive| directive.parse::<u32>().ok());
+    if directive.is_some() {
+        lines.pop();
+    }
+    let directive = directive.unwrap_or_default();
+
+    if directive & cobra_directive::ERROR != 0 {
+        return ExternalCompletions::default();
+    }
+
+    ExternalCompletions {
+        candidates: parse_described_candidates(lines.into_iter()),
+        no_space: directive & cobra_directive::NO_SPACE != 0,
+        no_sort: directive & cobra_directive::KEEP_ORDER != 0,
+        default: directive & cobra_directive::NO_FILE_COMP == 0,
+    }
+}
+
+fn parse_carapace_output(output: &str) -> ExternalCompletions {
+    let Ok(export) = serde_json::from_str::<serde_json::Value>(output) else {
+        return ExternalCompletions::default();
+    };
+
+    let candidates: IndexSet<String> = export["values"]
+        .as_array()
+        .map(|values| {
+            values
+                .iter()
+                .filter_map(|value| value["value"].as_str())
+                .filter(|candidate| !candidate.is_empty())
+                .map(ToOwned::to_owned)
+                .collect()
+        })
+        .unwrap_or_default();
+
+    // `nospace` lists the characters after which no space should be appended, or is `*` to
+    // suppress spaces after all candidates.
+    let no_space_suffixes = export["nospace"].as_str().unwrap_or_default();
+    let no_space = no_space_suffixes == "*"
+        || candidates.iter().any(|candidate| {
+            candidate
+                .chars()
+                .last()
+                .is_some_and(|c| no_space_suffixes.contains(c))
+        });
+
+    ExternalCompletions {
+        candidates,
+        no_space,
+        ..ExternalCompletions::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn args() {
+        let words = ["kubectl", "get", "po"];
+
+        assert_eq!(
+            ExternalCompletionProtocol::Bash.args(&words),
+            ["kubectl", "po", "get"]
+        );
+        assert_eq!(
+            ExternalCompletionProtocol::Clap.args(&words),
+            ["--", "kubectl", "get", "po"]
+        );
+        assert_eq!(
+            ExternalCompletionProtocol::Cobra.args(&words),
+            ["__complete", "get", "po"]
+        );
+        assert_eq!(
+            ExternalCompletionProtocol::Carapace.args(&words),
+            ["kubectl", "export", "kubectl", "get", "po"]
+        );
+    }
+
+    #[test]
+    fn parse_cobra() {
+        let output = "pods\tList pods\npersistentvolumes\n:36\n";
+
+        assert_eq!(
+            ExternalCompletionProtocol::Cobra.parse_output(output),
+            ExternalCompletions {
+                candidates: IndexSet::from(["pods".to_owned(), "persistentvolumes".to_owned()]),
+                no_space: false,
+                no_sort: true,
+                default: false,
+            }
+        );
+        assert_eq!(
+            ExternalCompletionProtocol::Cobra.parse_output("pods\n:1\n"),
+            ExternalCompletions::default()
+        );
+    }
+
+    #[test]
+    fn parse_carapace() {
+        let output = r#"{"version":"v1.0.0","nospace":"/","values":[{"value":"src/","display":"src/"},{"value":"README.md","display":"README.md","description":"readme"}]}"#;
+
+        assert_eq!(
+            ExternalCompletionProtocol::Carapace.parse_output(output),
+            ExternalCompletions {
+                candidates: IndexSet::from(["src/".to_owned(), "README.md".to_owned()]),
+                no_space: true,
+                no_sort: false,
+                default: false,
+            }
+        );
+    }
+}
@@ -0,0 +1,257 @@
+//! Sources of hostnames for completion: the user's ssh configuration and known hosts, along
+//! with the system's hosts file.
+
+use indexmap::IndexSet;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Path of the system-wide list of known ssh hosts.
+const SYSTEM_KNOWN_HOSTS_PATH: &str = "/etc/ssh/ssh_known_hosts";
+
+/// Path of the system's hosts file, used unless `HOSTFILE` names another file.
+const DEFAULT_HOSTS_FILE_PATH: &str = "/etc/hosts";
+
+/// Format of a file that hostnames are read from.
+#[derive(Clone, Copy, Debug)]
+enum SourceFormat {
+    /// An ssh client configuration file (e.g., `~/.ssh/config`).
+    SshConfig,
+    /// An ssh known hosts file (e.g., `~/.ssh/known_hosts`).
+    KnownHosts,
+    /// A hosts file (e.g., `/etc/hosts`).
+    HostsFile,
+}
+
+/// The hostnames read from a single file.
+#[derive(Debug)]
+struct CachedSource {
+    /// The modification time of the file when it was read, if known.
+    modified: Option<SystemTime>,
+    /// The hostnames found in the file.
+    names: Vec<String>,
+}
+
+/// Completes hostnames, drawing them from the user's ssh configuration (`~/.ssh/config`),
+/// the user's and the system's known ssh hosts, and the hosts file (`/etc/hosts`, or the
+/// file named by `HOSTFILE`). Each file is re-read only once it's been modified.
+#[derive(Debug, Default)]
+pub struct HostnameCompleter {
+    /// Hostnames read from each file, keyed by the file's path.
+    sources: HashMap<PathBuf, CachedSource>,
+}
+
+impl HostnameCompleter {
+    /// Returns the known hostnames that start with the given prefix, in the order they
+    /// were found.
+    ///
+    /// # Arguments
+    ///
+    /// * `prefix` - The prefix to match.
+    /// * `home_dir` - The user's home directory, if known.
+    /// * `hosts_file` - The hosts file to read, if not `/etc/hosts`.
+    pub fn get_completions(
+        &mut self,
+        prefix: &str,
+        home_dir: Option<&Path>,
+        hosts_file: Option<&Path>,
+    ) -> IndexSet<String> {
+        let mut files = vec![];
+        if let Some(home_dir) = home_dir {
+            let ssh_dir = home_dir.join(".ssh");
+            files.push((ssh_dir.join("config"), SourceFormat::SshConfig));
+            files.push((ssh_dir.join("known_hosts"), SourceFormat::KnownHosts));
+        }
+        files.push((
+            PathBuf::from(SYSTEM_KNOWN_HOSTS_PATH),
+            SourceFormat::KnownHosts,
+        ));
+        files.push((
+            hosts_file.map_or_else(|| PathBuf::from(DEFAULT_HOSTS_FILE_PATH), Path::to_owned),
+            SourceFormat::HostsFile,
+        ));
+
+        let mut candidates = IndexSet::new();
+        for (path, format) in files {
+            for name in self.names_from(path.as_path(), format) {
+                if name.starts_with(prefix) {
+                    candidates.insert(name.clone());
+                }
+            }
+        }
+
+        candidates
+    }
+
+    /// Returns the hostnames found in the given file, reading it if it's not cached or has
+    /// been modified since it was cached.
+    fn names_from(&mut self, path: &Path, format: SourceFormat) -> &[String] {
+        let modified = std::fs::metadata(path).and_then(|m| m.modified()).ok();
+
+        // If we can't tell when the file was modified, we never trust what we cached.
+        let is_current = modified.is_some()
+            && self
+                .sources
+                .get(path)
+                .is_some_and(|source| source.modified == modified);
+
+        if !is_current {
+            // N.B. Files that don't exist (or can't be read) simply contribute no names.
+            let names = std::fs::read_to_string(path)
+                .map(|contents| parse(contents.as_str(), format))
+                .unwrap_or_default();
+
+            self.sources
+                .insert(path.to_owned(), CachedSource { modified, names });
+        }
+
+        self.sources
+            .get(path)
+            .map(|source| source.names.as_slice())
+            .unwrap_or_default()
+    }
+}
+
+/// Parses the hostnames out of the contents of a file in the given format.
+fn parse(contents: &str, format: SourceFormat) -> Vec<String> {
+    let mut names = vec![];
+
+    for line in contents.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        match format {
+            SourceFormat::SshConfig => parse_ssh_config_line(line, &mut names),
+            SourceFormat::KnownHosts => parse_known_hosts_line(line, &mut names),
+            SourceFormat::HostsFile => parse_hosts_file_line(line, &mut names),
+        }
+    }
+
+    names
+}
+
+/// Collects the aliases from a `Host` line of an ssh client configuration file (e.g.,
+/// `Host web web.example.com`). Patterns (e.g., `*.example.com` or `!web`) aren't hostnames,
+/// so they're skipped.
+fn parse_ssh_config_line(line: &str, names: &mut Vec<String>) {
+    // The keyword may be separated from its arguments by whitespace and/or an '='.
+    let (keyword, args) = line
+        .split_once(|c: char| c.is_whitespace() || c == '=')
+        .unwrap_or((line, ""));
+
+    if !keyword.eq_ignore_ascii_case("host") {
+        return;
+    }
+
+    let args = args.trim_start_matches(|c: char| c.is_whitespace() || c == '=');
+    for alias in args.split_whitespace() {
+        let alias = alias.trim_matches('"');
+        if !alias.is_empty() && !is_pattern(alias) {
+            names.push(alias.to_owned());
+        }
+    }
+}
+
+/// Collects the hostnames from a line of an ssh known hosts file (e.g.,
+/// `host,192.0.2.1 ssh-ed25519 AAAA...`). Hashed hostnames can't be recovered, so they're
+/// skipped; hosts on non-default ports (e.g., `[host]:2222`) are reduced to the hostname.
+fn parse_known_hosts_line(line: &str, names: &mut Vec<String>) {
+    let mut fields = line.split_whitespace();
+
+    // Lines may start with a marker, e.g. `@cert-authority` or `@revoked`.
+    let mut hosts = fields.next().unwrap_or_default();
+    if hosts.starts_with('@') {
+        hosts = fields.next().unwrap_or_default();
+    }
+
+    if hosts.starts_with("|1|") {
+        return;
+    }
+
+    for host in hosts.split(',') {
+        let host = host
+            .strip_prefix('[')
+            .and_then(|host| host.split_once("]:"))
+            .map_or(host, |(host, _port)| host);
+
+        if !host.is_empty() && !is_pattern(host) {
+            names.push(host.to_owned());
+        }
+    }
+}
+
+/// Collects the names from a line of a hosts file (e.g., `192.0.2.1 host host.example.com`).
+fn parse_hosts_file_line(line: &str, names: &mut Vec<String>) {
+    let line = line.split_once('#').map_or(line, |(line, _comment)| line);
+
+    // The first field is the address.
+    for name in line.split_whitespace().skip(1) {
+        names.push(name.to_owned());
+    }
+}
+
+/// Returns whether the given ssh host specification is a pattern rather than a hostname.
+fn is_pattern(host: &str) -> bool {
+    host.starts_with('!') || host.contains(['*', '?'])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_ssh_config() {
+        let contents = "\
+# Comment
+Host web web.example.com
+    HostName 192.0.2.1
+    User admin
+host=db
+Host *.example.com !bastion
+Match host build
+";
+
+        assert_eq!(
+            parse(contents, SourceFormat::SshConfig),
+            ["web", "web.example.com", "db"]
+        );
+    }
+
+    #[test]
+    fn parse_known_hosts() {
+        let contents = "\
+web.example.com,192.0.2.1 ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAA
+[git.example.com]:2222 ssh-rsa AAAAB3NzaC1yc2EAAAA
+|1|F1E1KeoE/eEWhi10WpGv4OdiO6Y=|3988QV0VE8wmZL7suNrYQLITLCg= ssh-rsa AAAAB3NzaC1yc2EAAAA
+@cert-authority *.example.com ssh-rsa AAAAB3NzaC1yc2EAAAA
+@revoked old.example.com ssh-rsa AAAAB3NzaC1yc2EAAAA
+";
+
+        assert_eq!(
+            parse(contents, SourceFormat::KnownHosts),
+            [
+                "web.example.com",
+                "192.0.2.1",
+                "git.example.com",
+                "old.example.com"
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_hosts_file() {
+        let contents = "\
+127.0.0.1 localhost
+::1       localhost ip6-localhost # loopback
+# 192.0.2.1 commented-out
+192.0.2.2\tprinter
+";
+
+        assert_eq!(
+            parse(contents, SourceFormat::HostsFile),
+            ["localhost", "localhost", "ip6-localhost", "printer"]
+        );
+    }
+}
@@ -0,0 +1,36 @@
+//! Facilities for placing the external commands spawned by the shell into a container
+//! provided by the embedder (e.g., a cgroup on Linux or a Job Object on Windows), so that
+//! resource limits apply to them and they can be reliably terminated as a group.
+
+use std::path::PathBuf;
+
+use crate::{error, sys};
+
+/// A container provided by the embedder of the shell, into which all external commands
+/// spawned by the shell are placed. The container must already exist; the shell doesn't
+/// create, configure, or remove it.
+#[derive(Clone, Debug)]
+pub enum ProcessContainer {
+    /// A (v2) cgroup, identified by the path of its directory in the cgroup file system
+    /// (e.g., `/sys/fs/cgroup/ci/job-1`). Only supported on Linux.
+    Cgroup(PathBuf),
+    /// A named Job Object. Only supported on Windows.
+    JobObject(String),
+}
+
+impl ProcessContainer {
+    /// Places the given process in the container.
+    ///
+    /// # Arguments
+    ///
+    /// * `pid` - The ID of the process.
+    pub(crate) fn add_process(&self, pid: sys::process::ProcessId) -> Result<(), error::Error> {
+        sys::containment::add_process(self, pid)
+    }
+
+    /// Terminates all processes in the container, including any descendants of the
+    /// commands spawned by the shell.
+    pub fn terminate_all(&self) -> Result<(), error::Error> {
+        sys::containment::terminate_all(self)
+    }
+}
@@ -0,0 +1,114 @@
+//! History of the shell's working directories. Unlike the directory stack managed by `pushd`
+//! and `popd`, the history is recorded automatically as the working directory changes, and
+//! can be navigated backward and forward in the manner of a web browser's history.
+
+use std::path::{Path, PathBuf};
+
+/// Maximum number of directories retained in the history.
+const MAX_ENTRIES: usize = 100;
+
+/// History of the directories that the shell has had as its working directory.
+#[derive(Clone, Debug, Default)]
+pub struct DirectoryHistory {
+    /// The directories, from least to most recently entered.
+    entries: Vec<PathBuf>,
+    /// Index in `entries` of the current working directory.
+    current: usize,
+}
+
+impl DirectoryHistory {
+    /// Returns the directories in the history, from least to most recently entered.
+    pub fn entries(&self) -> &[PathBuf] {
+        self.entries.as_slice()
+    }
+
+    /// Returns the index in [`Self::entries`] of the current working directory.
+    pub fn current_index(&self) -> usize {
+        self.current
+    }
+
+    /// Returns the index in [`Self::entries`] of the directory the given number of entries
+    /// away from the current one, if the history extends that far.
+    ///
+    /// # Arguments
+    ///
+    /// * `offset` - The number of entries to move; negative offsets move backward.
+    pub fn offset_index(&self, offset: isize) -> Option<usize> {
+        let index = self.current.checked_add_signed(offset)?;
+        (index < self.entries.len()).then_some(index)
+    }
+
+    /// Records that the given directory has become the working directory. Unless it's
+    /// already the current entry (e.g., because the history is being navigated), any entries
+    /// ahead of the current one are discarded and the directory becomes the newest entry.
+    ///
+    /// # Arguments
+    ///
+    /// * `dir` - The absolute path of the directory.
+    pub(crate) fn record(&mut self, dir: &Path) {
+        if self
+            .entries
+            .get(self.current)
+            .is_some_and(|entry| entry == dir)
+        {
+            return;
+        }
+
+        self.entries.truncate(self.current + 1);
+        self.entries.push(dir.to_owned());
+
+        if self.entries.len() > MAX_ENTRIES {
+            self.entries.drain(..self.entries.len() - MAX_ENTRIES);
+        }
+
+        self.current = self.entries.len() - 1;
+    }
+
+    /// Makes the entry at the given index the current one.
+    pub(crate) fn set_current_index(&mut self, index: usize) {
+        self.current = index;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn navigation() {
+        let mut history = DirectoryHistory::default();
+        for dir in ["/a", "/b", "/c"] {
+            history.record(Path::new(dir));
+        }
+        assert_eq!(history.current_index(), 2);
+        assert_eq!(history.offset_index(-2), Some(0));
+        assert_eq!(history.offset_index(-3), None);
+        assert_eq!(history.offset_index(1), None);
+
+        // Re-entering the current directory (as happens when navigating) changes nothing.
+        history.set_current_index(0);
+        history.record(Path::new("/a"));
+        assert_eq!(history.entries().len(), 3);
+        assert_eq!(history.offset_index(2), Some(2));
+
+        // Entering another directory discards the entries ahead of the current one.
+        history.record(Path::new("/d"));
+        assert_eq!(
+            history.entries(),
+            [PathBuf::from("/a"), PathBuf::from("/d")]
+        );
+        assert_eq!(history.current_index(), 1);
+    }
+
+    #[test]
+    fn capacity() {
+        let mut history = DirectoryHistory::default();
+        for i in 0..=MAX_ENTRIES {
+            history.record(PathBuf::from(std::format!("/{i}")).as_path());
+        }
+
+        assert_eq!(history.entries().len(), MAX_ENTRIES);
+        assert_eq!(history.entries()[0], PathBuf::from("/1"));
+        assert_eq!(history.current_index(), MAX_ENTRIES - 1);
+    }
+}
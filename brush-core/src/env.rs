@@ -1,9 +1,13 @@
 use std::borrow::Cow;
 use std::collections::HashMap;
+use std::sync::Arc;
 
 use crate::error;
 use crate::variables::{self, ShellValue, ShellValueUnsetType, ShellVariable};
 
+/// Maximum number of namerefs followed when resolving a variable name.
+const MAX_NAMEREF_DEPTH: usize = 8;
+
 /// Represents the policy for looking up variables in a shell environment.
 #[derive(Clone, Copy)]
 pub enum EnvironmentLookup {
@@ -133,16 +137,45 @@ impl ShellEnvironment {
         visible_vars.into_iter()
     }
 
+    /// Resolves the given variable name through any namerefs (i.e., variables declared
+    /// with `declare -n`), returning the name of the variable ultimately referenced.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name to resolve.
+    pub fn resolve_nameref<'a>(&self, name: &'a str) -> Cow<'a, str> {
+        let mut resolved = Cow::Borrowed(name);
+
+        for _ in 0..MAX_NAMEREF_DEPTH {
+            match self.get_unresolved(resolved.as_ref()) {
+                Some((_, var)) if var.is_treated_as_nameref() => match var.value() {
+                    ShellValue::String(target) if !target.is_empty() => {
+                        resolved = Cow::Owned(target.clone());
+                    }
+                    _ => break,
+                },
+                _ => break,
+            }
+        }
+
+        resolved
+    }
+
     /// Tries to retrieve an immutable reference to the variable with the given name
-    /// in the environment.
+    /// in the environment. Namerefs are followed to the variables they reference.
     ///
     /// # Arguments
     ///
     /// * `name` - The name of the variable to retrieve.
     pub fn get<S: AsRef<str>>(&self, name: S) -> Option<(EnvironmentScope, &ShellVariable)> {
+        let name = self.resolve_nameref(name.as_ref());
+        self.get_unresolved(name.as_ref())
+    }
+
+    fn get_unresolved(&self, name: &str) -> Option<(EnvironmentScope, &ShellVariable)> {
         // Look through scopes, from the top of the stack on down.
         for (scope_type, map) in self.scopes.iter().rev() {
-            if let Some(var) = map.get(name.as_ref()) {
+            if let Some(var) = map.get(name) {
                 return Some((*scope_type, var));
             }
         }
@@ -151,7 +184,7 @@ impl ShellEnvironment {
     }
 
     /// Tries to retrieve a mutable reference to the variable with the given name
-    /// in the environment.
+    /// in the environment. Namerefs are followed to the variables they reference.
     ///
     /// # Arguments
     ///
@@ -160,6 +193,8 @@ impl ShellEnvironment {
         &mut self,
         name: S,
     ) -> Option<(EnvironmentScope, &mut ShellVariable)> {
+        let name = self.resolve_nameref(name.as_ref());
+
         // Look through scopes, from the top of the stack on down.
         for (scope_type, map) in self.scopes.iter_mut().rev() {
             if let Some(var) = map.get_mut(name.as_ref()) {
@@ -199,12 +234,34 @@ impl ShellEnvironment {
     //
 
     /// Tries to unset the variable with the given name in the environment, returning
-    /// whether or not such a variable existed.
+    /// whether or not such a variable existed. If the name refers to a nameref, the
+    /// referenced variable is unset instead.
     ///
     /// # Arguments
     ///
     /// * `name` - The name of the variable to unset.
     pub fn unset(&mut self, name: &str) -> Result<Option<ShellVariable>, error::Error> {
+        let name = self.resolve_nameref(name);
+        self.unset_unresolved(name.as_ref(), false)
+    }
+
+    /// Tries to unset the variable with the given name in the environment, without
+    /// following namerefs; returns whether or not such a variable existed.
+    ///
+    /// Unsetting a local variable of the current function leaves it masked as unset until
+    /// the function returns. Unsetting one from a calling function instead reveals whatever
+    /// variable it had shadowed, unless `mask_outer_locals` is set (as with bash's
+    /// `localvar_unset` option).
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the variable to unset.
+    /// * `mask_outer_locals` - Whether to also mask unset locals of calling functions.
+    pub fn unset_unresolved(
+        &mut self,
+        name: &str,
+        mask_outer_locals: bool,
+    ) -> Result<Option<ShellVariable>, error::Error> {
         let mut local_count = 0;
         for (scope_type, map) in self.scopes.iter_mut().rev() {
             if matches!(scope_type, EnvironmentScope::Local) {
@@ -216,7 +273,9 @@ impl ShellEnvironment {
             if unset_result.is_some() {
                 // If we end up finding a local in the top-most local frame, then we replace
                 // it with a placeholder.
-                if matches!(scope_type, EnvironmentScope::Local) && local_count == 1 {
+                if matches!(scope_type, EnvironmentScope::Local)
+                    && (local_count == 1 || mask_outer_locals)
+                {
                     map.set(
                         name,
                         ShellVariable::new(ShellValue::Unset(ShellValueUnsetType::Untyped)),
@@ -376,6 +435,7 @@ impl ShellEnvironment {
         scope_if_creating: EnvironmentScope,
     ) -> Result<(), error::Error> {
         let name = name.into();
+        let name = self.resolve_nameref(name.as_str()).into_owned();
 
         if let Some(var) = self.get_mut_using_policy(&name, lookup_policy) {
             var.assign(value, false)?;
@@ -409,6 +469,7 @@ impl ShellEnvironment {
         scope_if_creating: EnvironmentScope,
     ) -> Result<(), error::Error> {
         let name = name.into();
+        let name = self.resolve_nameref(name.as_str()).into_owned();
 
         if let Some(var) = self.get_mut_using_policy(&name, lookup_policy) {
             var.assign_at_index(index, value, false)?;
@@ -466,17 +527,23 @@ impl ShellEnvironment {
     }
 }
 
-/// Represents a map from names to shell variables.
+/// Represents a map from names to shell variables. Cloning a map is cheap: clones share
+/// their contents until one of them is modified.
 #[derive(Clone, Debug)]
 pub struct ShellVariableMap {
-    variables: HashMap<String, ShellVariable>,
+    /// Variables with ordinary values, shared between clones until modified.
+    variables: Arc<HashMap<String, ShellVariable>>,
+    /// Variables with dynamic values (e.g., `$RANDOM`). These carry their own state,
+    /// which each clone must own, so they're stored apart from the shared variables.
+    dynamic_variables: HashMap<String, ShellVariable>,
 }
 
 impl ShellVariableMap {
     /// Returns a new shell variable map.
     pub fn new() -> Self {
         Self {
-            variables: HashMap::new(),
+            variables: Arc::new(HashMap::new()),
+            dynamic_variables: HashMap::new(),
         }
     }
 
@@ -486,7 +553,7 @@ impl ShellVariableMap {
 
     /// Returns an iterator over all the variables in the map.
     pub fn iter(&self) -> impl Iterator<Item = (&String, &ShellVariable)> {
-        self.variables.iter()
+        self.variables.iter().chain(self.dynamic_variables.iter())
     }
 
     /// Tries to retrieve an immutable reference to the variable with the given name.
@@ -495,7 +562,9 @@ impl ShellVariableMap {
     ///
     /// * `name` - The name of the variable to retrieve.
     pub fn get(&self, name: &str) -> Option<&ShellVariable> {
-        self.variables.get(name)
+        self.dynamic_variables
+            .get(name)
+            .or_else(|| self.variables.get(name))
     }
 
     /// Tries to retrieve a mutable reference to the variable with the given name.
@@ -504,7 +573,16 @@ impl ShellVariableMap {
     ///
     /// * `name` - The name of the variable to retrieve.
     pub fn get_mut(&mut self, name: &str) -> Option<&mut ShellVariable> {
-        self.variables.get_mut(name)
+        if self.dynamic_variables.contains_key(name) {
+            return self.dynamic_variables.get_mut(name);
+        }
+
+        // N.B. Avoid copying shared contents when there's nothing to modify.
+        if !self.variables.contains_key(name) {
+            return None;
+        }
+
+        Arc::make_mut(&mut self.variables).get_mut(name)
     }
 
     //
@@ -518,7 +596,16 @@ impl ShellVariableMap {
     ///
     /// * `name` - The name of the variable to unset.
     pub fn unset(&mut self, name: &str) -> Option<ShellVariable> {
-        self.variables.remove(name)
+        if let Some(var) = self.dynamic_variables.remove(name) {
+            return Some(var);
+        }
+
+        // N.B. Avoid copying shared contents when there's nothing to remove.
+        if !self.variables.contains_key(name) {
+            return None;
+        }
+
+        Arc::make_mut(&mut self.variables).remove(name)
     }
 
     /// Sets a variable in the map.
@@ -528,6 +615,17 @@ impl ShellVariableMap {
     /// * `name` - The name of the variable to set.
     /// * `var` - The variable to set.
     pub fn set<N: Into<String>>(&mut self, name: N, var: ShellVariable) {
-        self.variables.insert(name.into(), var);
+        let name = name.into();
+
+        // Make sure a stale definition of the variable doesn't linger in the other map.
+        if matches!(var.value(), ShellValue::Dynamic(_)) {
+            if self.variables.contains_key(&name) {
+                Arc::make_mut(&mut self.variables).remove(&name);
+            }
+            self.dynamic_variables.insert(name, var);
+        } else {
+            self.dynamic_variables.remove(&name);
+            Arc::make_mut(&mut self.variables).insert(name, var);
+        }
     }
 }
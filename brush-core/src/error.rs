@@ -27,6 +27,10 @@ pub enum Error {
     #[error("failed to source file: {0}; {1}")]
     FailedSourcingFile(PathBuf, Box<Error>),
 
+    /// The requested kind of process container isn't supported on this platform.
+    #[error("process container not supported on this platform")]
+    UnsupportedProcessContainer,
+
     /// The shell failed to send a signal to a process.
     #[error("failed to send signal to process")]
     FailedToSendSignal,
@@ -189,9 +193,53 @@ pub enum Error {
     #[error("maximum function call depth exceeded")]
     MaxFunctionCallDepthExceeded,
 
+    /// Maximum recursion depth (i.e., nesting of functions, `eval`, `source`, and command
+    /// substitutions) was exceeded.
+    #[error("maximum recursion depth exceeded ({0})")]
+    MaxRecursionDepthExceeded(usize),
+
     /// System time error.
     #[error("system time error: {0}")]
     TimeError(#[from] std::time::SystemTimeError),
+
+    /// An attempt was made to toggle a compatibility quirk that can't be toggled.
+    #[error("compatibility quirk cannot be toggled: {0}")]
+    QuirkNotToggleable(String),
+
+    /// A saved shell state could not be serialized or deserialized.
+    #[error("invalid saved state: {0}")]
+    InvalidSavedState(#[from] serde_json::Error),
+
+    /// A saved shell state was written in a format version that isn't supported.
+    #[error("unsupported saved state version: {0}")]
+    UnsupportedSavedStateVersion(u32),
+
+    /// The shell's sandbox policy denied executing a command.
+    #[error("{0}: command denied by sandbox policy")]
+    CommandDeniedByPolicy(String),
+
+    /// The shell's sandbox policy denied writing to a file.
+    #[error("{0}: write denied by sandbox policy")]
+    WriteDeniedByPolicy(String),
+}
+
+/// Formats errors encountered while parsing shell input, for display to the user.
+pub trait SyntaxErrorFormatter: Send + Sync {
+    /// Returns a displayable description of the given syntax error.
+    ///
+    /// # Arguments
+    ///
+    /// * `error` - The error to describe.
+    /// * `source_info` - Information about the source of the input.
+    /// * `source` - The input that failed to parse.
+    /// * `catalog` - The catalog to localize the description with.
+    fn format_syntax_error(
+        &self,
+        error: &brush_parser::ParseError,
+        source_info: &brush_parser::SourceInfo,
+        source: &str,
+        catalog: &crate::i18n::Catalog,
+    ) -> String;
 }
 
 /// Convenience function for returning an error for unimplemented functionality.
@@ -171,12 +171,16 @@ pub(crate) fn expand_backslash_escapes(
 pub(crate) enum QuoteMode {
     BackslashEscape,
     Quote,
+    /// Double-quotes the string, as bash does when displaying variable values; strings
+    /// containing control characters are ANSI-C quoted (i.e., `$'...'`) instead.
+    DoubleQuote,
 }
 
 pub(crate) fn force_quote(s: &str, mode: QuoteMode) -> String {
     match mode {
         QuoteMode::BackslashEscape => escape_with_backslash(s, true).to_string(),
         QuoteMode::Quote => escape_with_quoting(s, true).to_string(),
+        QuoteMode::DoubleQuote => escape_with_double_quoting(s, true).to_string(),
     }
 }
 
@@ -184,40 +188,142 @@ pub(crate) fn quote_if_needed(s: &str, mode: QuoteMode) -> Cow<'_, str> {
     match mode {
         QuoteMode::BackslashEscape => escape_with_backslash(s, false),
         QuoteMode::Quote => escape_with_quoting(s, false),
+        QuoteMode::DoubleQuote => escape_with_double_quoting(s, false),
     }
 }
 
 fn escape_with_backslash(s: &str, force: bool) -> Cow<'_, str> {
-    if !force && !s.chars().any(needs_escaping) {
-        return s.into();
+    if s.chars().any(char::is_control) {
+        return ansi_c_quote(s).into();
     }
 
     let mut output = String::new();
+    let mut prev = None;
+
+    for c in s.chars() {
+        if needs_escaping(c, prev) {
+            output.push('\\');
+        }
+        output.push(c);
+        prev = Some(c);
+    }
+
+    if !force && output.len() == s.len() {
+        return s.into();
+    }
+
+    output.into()
+}
+
+fn escape_with_quoting(s: &str, force: bool) -> Cow<'_, str> {
+    if !force && !s.is_empty() && !contains_shell_metas(s) {
+        return s.into();
+    }
+
+    // Single quotes can't appear within a single-quoted string, so each one closes the
+    // quoted string, is escaped, and then opens a new one.
+    std::format!("'{}'", s.replace('\'', r"'\''")).into()
+}
+
+fn escape_with_double_quoting(s: &str, force: bool) -> Cow<'_, str> {
+    if s.chars().any(char::is_control) {
+        return ansi_c_quote(s).into();
+    }
+
+    if !force && !s.is_empty() && !contains_shell_metas(s) {
+        return s.into();
+    }
+
+    let mut output = String::from('"');
+    for c in s.chars() {
+        if matches!(c, '"' | '\\' | '$' | '`') {
+            output.push('\\');
+        }
+        output.push(c);
+    }
+    output.push('"');
+
+    output.into()
+}
+
+fn ansi_c_quote(s: &str) -> String {
+    let mut output = String::from("$'");
 
-    // TODO: Handle other interesting sequences.
     for c in s.chars() {
         match c {
-            c if needs_escaping(c) => {
-                output.push('\\');
-                output.push(c);
+            '\x07' => output.push_str("\\a"),
+            '\x08' => output.push_str("\\b"),
+            '\x0c' => output.push_str("\\f"),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            '\x0b' => output.push_str("\\v"),
+            '\x1b' => output.push_str("\\E"),
+            '\\' => output.push_str("\\\\"),
+            '\'' => output.push_str("\\'"),
+            c if c.is_control() => {
+                let mut buf = [0; 4];
+                for b in c.encode_utf8(&mut buf).bytes() {
+                    output.push_str(std::format!("\\{b:03o}").as_str());
+                }
             }
             c => output.push(c),
         }
     }
 
-    output.into()
+    output.push('\'');
+    output
 }
 
-fn escape_with_quoting(s: &str, force: bool) -> Cow<'_, str> {
-    // TODO: Handle single-quote!
-    if force || s.is_empty() || s.chars().any(needs_escaping) {
-        std::format!("'{s}'").into()
-    } else {
-        s.into()
+/// Returns whether the given string contains characters that would be interpreted
+/// specially by the shell if left unquoted.
+fn contains_shell_metas(s: &str) -> bool {
+    s.starts_with(['~', '#'])
+        || s.contains("=~")
+        || s.contains(":~")
+        || s.chars().any(|c| {
+            matches!(
+                c,
+                ' ' | '\t'
+                    | '\n'
+                    | '\''
+                    | '"'
+                    | '\\'
+                    | '|'
+                    | '&'
+                    | ';'
+                    | '('
+                    | ')'
+                    | '<'
+                    | '>'
+                    | '!'
+                    | '{'
+                    | '}'
+                    | '*'
+                    | '['
+                    | '?'
+                    | ']'
+                    | '^'
+                    | '$'
+                    | '`'
+            )
+        })
+}
+
+/// Returns whether the given character needs to be escaped with a backslash, given the
+/// character preceding it (if any).
+fn needs_escaping(c: char, prev: Option<char>) -> bool {
+    match c {
+        // Tildes are only special at the start of a word or after a separator.
+        '~' => matches!(prev, None | Some('=' | ':')),
+        // Comments only start at the beginning of a word.
+        '#' => prev.is_none(),
+        c => is_escaped_anywhere(c),
     }
 }
 
-fn needs_escaping(c: char) -> bool {
+/// Returns whether the given character needs to be escaped wherever it appears.
+fn is_escaped_anywhere(c: char) -> bool {
     matches!(
         c,
         '(' | ')'
@@ -235,6 +341,7 @@ fn needs_escaping(c: char) -> bool {
             | '>'
             | '`'
             | '\\'
+            | '\''
             | '"'
             | '!'
             | '^'
@@ -252,6 +359,19 @@ mod tests {
         assert_eq!(quote_if_needed("a", QuoteMode::BackslashEscape), "a");
         assert_eq!(quote_if_needed("a b", QuoteMode::BackslashEscape), r"a\ b");
         assert_eq!(quote_if_needed("", QuoteMode::BackslashEscape), "");
+        assert_eq!(
+            quote_if_needed("it's", QuoteMode::BackslashEscape),
+            r"it\'s"
+        );
+        assert_eq!(
+            quote_if_needed("~a=~", QuoteMode::BackslashEscape),
+            r"\~a=\~"
+        );
+        assert_eq!(quote_if_needed("#a#", QuoteMode::BackslashEscape), r"\#a#");
+        assert_eq!(
+            quote_if_needed("a\tb", QuoteMode::BackslashEscape),
+            r"$'a\tb'"
+        );
     }
 
     #[test]
@@ -259,6 +379,28 @@ mod tests {
         assert_eq!(quote_if_needed("a", QuoteMode::Quote), "a");
         assert_eq!(quote_if_needed("a b", QuoteMode::Quote), "'a b'");
         assert_eq!(quote_if_needed("", QuoteMode::Quote), "''");
+        assert_eq!(quote_if_needed("it's", QuoteMode::Quote), r"'it'\''s'");
+        assert_eq!(quote_if_needed("#x", QuoteMode::Quote), "'#x'");
+        assert_eq!(quote_if_needed("a#", QuoteMode::Quote), "a#");
+        assert_eq!(quote_if_needed("x:~", QuoteMode::Quote), "'x:~'");
+        assert_eq!(quote_if_needed("a,b", QuoteMode::Quote), "a,b");
+        assert_eq!(quote_if_needed("a\nb", QuoteMode::Quote), "'a\nb'");
+    }
+
+    #[test]
+    fn test_double_quote_escape() {
+        assert_eq!(quote_if_needed("a", QuoteMode::DoubleQuote), "a");
+        assert_eq!(quote_if_needed("a b", QuoteMode::DoubleQuote), r#""a b""#);
+        assert_eq!(quote_if_needed("~a", QuoteMode::DoubleQuote), r#""~a""#);
+        assert_eq!(force_quote("a", QuoteMode::DoubleQuote), r#""a""#);
+        assert_eq!(
+            force_quote(r#"$x "y" \z `w`"#, QuoteMode::DoubleQuote),
+            r#""\$x \"y\" \\z \`w\`""#
+        );
+        assert_eq!(
+            force_quote("it's\n\t\x01\x1b\\", QuoteMode::DoubleQuote),
+            r"$'it\'s\n\t\001\E\\'"
+        );
     }
 
     fn assert_echo_expands_to(unexpanded: &str, expected: &str) {
@@ -1,5 +1,6 @@
 use std::borrow::Cow;
 use std::cmp::min;
+use std::collections::VecDeque;
 
 use brush_parser::ast;
 use brush_parser::word::ParameterTransformOp;
@@ -332,6 +333,18 @@ pub(crate) async fn basic_expand_str_without_tilde(
     expander.basic_expand_to_str(s).await
 }
 
+/// Expands the value of a scalar assignment. Unlike other words, tilde prefixes
+/// are also recognized following each unquoted `:` in the value (e.g., as used
+/// in `PATH`-style lists).
+pub(crate) async fn basic_expand_assignment_value(
+    shell: &mut Shell,
+    word: &ast::Word,
+) -> Result<String, error::Error> {
+    let mut expander = WordExpander::new(shell);
+    expander.parser_options.tilde_expansion_after_colons = true;
+    expander.basic_expand_to_str(word.flatten().as_str()).await
+}
+
 pub(crate) async fn full_expand_and_split_word(
     shell: &mut Shell,
     word: &ast::Word,
@@ -362,6 +375,9 @@ struct WordExpander<'a> {
     shell: &'a mut Shell,
     parser_options: brush_parser::ParserOptions,
     force_disable_brace_expansion: bool,
+    /// Outputs of command substitutions that were run ahead of time, in the order they
+    /// appear in the word being expanded.
+    command_substitution_outputs: VecDeque<String>,
 }
 
 impl<'a> WordExpander<'a> {
@@ -372,13 +388,34 @@ impl<'a> WordExpander<'a> {
             shell,
             parser_options,
             force_disable_brace_expansion: false,
+            command_substitution_outputs: VecDeque::new(),
         }
     }
 
     /// Apply tilde-expansion, parameter expansion, command substitution, and arithmetic expansion.
     pub async fn basic_expand_to_str(&mut self, word: &str) -> Result<String, error::Error> {
-        let expanded = String::from(self.basic_expand(word).await?);
-        Ok(expanded)
+        let expansion = self.basic_expand(word).await?;
+        Ok(self.join_fields(expansion))
+    }
+
+    /// Joins the fields of an expansion into a single string, as happens in contexts where
+    /// no word splitting is performed (e.g., assignments). Fields from concatenating
+    /// expansions (e.g., `$*`) are joined with the first character of IFS; all others
+    /// (e.g., those from `$@`) are joined with a space.
+    fn join_fields(&self, expansion: Expansion) -> String {
+        let joiner = if expansion.concatenate {
+            self.shell
+                .get_ifs_first_char()
+                .map_or_else(String::new, String::from)
+        } else {
+            String::from(" ")
+        };
+
+        expansion
+            .fields
+            .into_iter()
+            .map(String::from)
+            .join(joiner.as_str())
     }
 
     #[allow(clippy::ref_option)]
@@ -448,12 +485,15 @@ impl<'a> WordExpander<'a> {
             .collect();
 
         Ok(crate::regex::Regex::from(regex_pieces)
-            .set_case_insensitive(self.shell.options.case_insensitive_conditionals))
+            .set_case_insensitive(self.shell.options.case_insensitive_conditionals)
+            .set_rust_syntax(self.shell.options.rust_regex_syntax))
     }
 
     /// Apply tilde-expansion, parameter expansion, command substitution, and arithmetic expansion;
     /// yield pieces that could be further processed.
     async fn basic_expand(&mut self, word: &str) -> Result<Expansion, error::Error> {
+        self.shell.cancellation_token.check()?;
+
         tracing::debug!(target: trace_categories::EXPANSION, "Basic expanding: '{word}'");
 
         // Apply brace expansion first, before anything else.
@@ -464,9 +504,16 @@ impl<'a> WordExpander<'a> {
             tracing::debug!(target: trace_categories::EXPANSION, "  => brace expanded to '{brace_expanded}'");
         }
 
+        let pieces = brush_parser::word::parse(brace_expanded.as_str(), &self.parser_options)?;
+
+        // If enabled, run independent command substitutions concurrently, ahead of time.
+        if self.shell.options.parallel_command_substitutions {
+            self.run_command_substitutions_concurrently(&pieces).await?;
+        }
+
         // Expand: tildes, parameters, command substitutions, arithmetic.
         let mut expansions = vec![];
-        for piece in brush_parser::word::parse(brace_expanded.as_str(), &self.parser_options)? {
+        for piece in pieces {
             let piece_expansion = self.expand_word_piece(piece.piece).await?;
             expansions.push(piece_expansion);
         }
@@ -476,6 +523,41 @@ impl<'a> WordExpander<'a> {
         Ok(coalesced)
     }
 
+    /// Runs the command substitutions in the given word pieces concurrently, saving their
+    /// outputs for use when the pieces are expanded. Nothing is done unless there are multiple
+    /// substitutions and no other expansions whose results could depend on, or affect, the
+    /// order in which the substitutions are run.
+    async fn run_command_substitutions_concurrently(
+        &mut self,
+        pieces: &[brush_parser::word::WordPieceWithSource],
+    ) -> Result<(), error::Error> {
+        let mut command_strs = vec![];
+        if !collect_independent_command_substitutions(pieces, &mut command_strs)
+            || command_strs.len() < 2
+        {
+            return Ok(());
+        }
+
+        let results = futures::future::try_join_all(
+            command_strs
+                .into_iter()
+                .map(|s| commands::run_command_substitution(self.shell.clone(), s)),
+        )
+        .await?;
+
+        let (outputs, exit_codes): (VecDeque<_>, Vec<_>) = results.into_iter().unzip();
+
+        // As when the substitutions are run one after another, the last one's exit status
+        // is the one observed.
+        if let Some(exit_code) = exit_codes.last() {
+            self.shell.last_exit_status = *exit_code;
+        }
+
+        self.command_substitution_outputs = outputs;
+
+        Ok(())
+    }
+
     fn brace_expand_if_needed(&self, word: &'a str) -> Result<Vec<Cow<'a, str>>, error::Error> {
         // We perform a non-authoritative check to see if the string *may* contain braces
         // to expand. There may be false positives, but must be no false negatives.
@@ -535,24 +617,34 @@ impl<'a> WordExpander<'a> {
         Ok(result)
     }
 
+    /// Splits the fields of an expansion using the characters of IFS as delimiters. Per
+    /// POSIX, sequences of IFS whitespace (i.e., space, tab, or newline) delimit fields
+    /// but never yield empty ones; each other IFS character delimits exactly one field,
+    /// along with any IFS whitespace adjacent to it, and so may yield empty fields (e.g.,
+    /// `a::b` yields `a`, an empty field, and `b` when IFS is `:`). If IFS is set but empty,
+    /// no splitting is performed.
     fn split_fields(&self, expansion: Expansion) -> Vec<WordField> {
         let ifs = self.shell.get_ifs();
 
         let mut fields: Vec<WordField> = vec![];
-        let mut current_field = WordField::new();
 
         // Go through the fields we have so far.
         for existing_field in expansion.fields {
+            let mut current_field = WordField::new();
+
+            // Tracks whether the last field was just ended by IFS whitespace; if so, an
+            // immediately following non-whitespace delimiter is part of the same delimiter.
+            let mut ended_by_whitespace = false;
+
             for piece in existing_field.0 {
                 match piece {
-                    ExpansionPiece::Unsplittable(_) => current_field.0.push(piece),
+                    ExpansionPiece::Unsplittable(_) => {
+                        current_field.0.push(piece);
+                        ended_by_whitespace = false;
+                    }
                     ExpansionPiece::Splittable(s) => {
                         for c in s.chars() {
-                            if ifs.contains(c) {
-                                if !current_field.0.is_empty() {
-                                    fields.push(std::mem::take(&mut current_field));
-                                }
-                            } else {
+                            if !ifs.contains(c) {
                                 match current_field.0.last_mut() {
                                     Some(ExpansionPiece::Splittable(last)) => last.push(c),
                                     Some(ExpansionPiece::Unsplittable(_)) | None => {
@@ -561,6 +653,21 @@ impl<'a> WordExpander<'a> {
                                             .push(ExpansionPiece::Splittable(c.to_string()));
                                     }
                                 }
+                                ended_by_whitespace = false;
+                            } else if is_ifs_whitespace(c) {
+                                if !current_field.0.is_empty() {
+                                    fields.push(std::mem::take(&mut current_field));
+                                    ended_by_whitespace = true;
+                                }
+                            } else {
+                                if !current_field.0.is_empty() {
+                                    fields.push(std::mem::take(&mut current_field));
+                                } else if !ended_by_whitespace {
+                                    fields.push(WordField::from(ExpansionPiece::Unsplittable(
+                                        String::new(),
+                                    )));
+                                }
+                                ended_by_whitespace = false;
                             }
                         }
                     }
@@ -568,7 +675,7 @@ impl<'a> WordExpander<'a> {
             }
 
             if !current_field.0.is_empty() {
-                fields.push(std::mem::take(&mut current_field));
+                fields.push(current_field);
             }
         }
 
@@ -578,7 +685,8 @@ impl<'a> WordExpander<'a> {
     fn expand_pathnames_in_field(&self, field: WordField) -> Vec<String> {
         let pattern = patterns::Pattern::from(field.clone())
             .set_extended_globbing(self.parser_options.enable_extended_globbing)
-            .set_case_insensitive(self.shell.options.case_insensitive_pathname_expansion);
+            .set_case_insensitive(self.shell.options.case_insensitive_pathname_expansion)
+            .set_sort(self.glob_sort());
 
         let expansions = pattern
             .expand(
@@ -594,6 +702,31 @@ impl<'a> WordExpander<'a> {
         }
     }
 
+    /// Determines how pathname expansion results should be ordered, per the `GLOBSORT`
+    /// variable and the collation locale.
+    fn glob_sort(&self) -> patterns::GlobSort {
+        let collation = if self.shell.options.posix_mode {
+            patterns::Collation::Bytewise
+        } else {
+            // N.B. The first of these that's set and non-empty determines the collation.
+            let locale = ["LC_ALL", "LC_COLLATE", "LANG"].iter().find_map(|name| {
+                self.shell
+                    .env
+                    .get_str(name)
+                    .filter(|value| !value.is_empty())
+            });
+
+            match locale.as_deref() {
+                None | Some("C" | "POSIX") => patterns::Collation::Bytewise,
+                Some(locale) if locale.starts_with("C.") => patterns::Collation::Bytewise,
+                Some(_) => patterns::Collation::Locale,
+            }
+        };
+
+        let spec = self.shell.env.get_str("GLOBSORT").unwrap_or_default();
+        patterns::GlobSort::parse(spec.as_ref(), collation)
+    }
+
     #[async_recursion::async_recursion]
     #[allow(clippy::too_many_lines)]
     async fn expand_word_piece(
@@ -620,7 +753,10 @@ impl<'a> WordExpander<'a> {
                 let mut fields: Vec<WordField> = vec![];
 
                 let pieces_is_empty = pieces.is_empty();
-                let concatenation_joiner = self.shell.get_ifs_first_char();
+                let concatenation_joiner = self
+                    .shell
+                    .get_ifs_first_char()
+                    .map_or_else(String::new, String::from);
 
                 for piece in pieces {
                     let Expansion {
@@ -640,7 +776,7 @@ impl<'a> WordExpander<'a> {
                                     .collect()
                             })
                             .intersperse(vec![ExpansionPiece::Unsplittable(
-                                concatenation_joiner.to_string(),
+                                concatenation_joiner.clone(),
                             )])
                             .flatten()
                             .collect();
@@ -696,7 +832,11 @@ impl<'a> WordExpander<'a> {
             brush_parser::word::WordPiece::BackquotedCommandSubstitution(s)
             | brush_parser::word::WordPiece::CommandSubstitution(s) => {
                 let output_str =
-                    commands::invoke_command_in_subshell_and_get_output(self.shell, s).await?;
+                    if let Some(output_str) = self.command_substitution_outputs.pop_front() {
+                        output_str
+                    } else {
+                        commands::invoke_command_in_subshell_and_get_output(self.shell, s).await?
+                    };
 
                 // We trim trailing newlines, per spec.
                 let trimmed = output_str.trim_end_matches('\n');
@@ -716,16 +856,62 @@ impl<'a> WordExpander<'a> {
     }
 
     fn expand_tilde_expression(&self, prefix: &str) -> Result<String, error::Error> {
-        if !prefix.is_empty() {
-            Ok(sys::users::get_user_home_dir(prefix).map_or_else(
-                || std::format!("~{prefix}"),
-                |p| p.to_string_lossy().to_string(),
-            ))
-        } else if let Some(home_dir) = self.shell.get_home_dir() {
-            Ok(home_dir.to_string_lossy().to_string())
+        if prefix.is_empty() {
+            return self.shell.get_home_dir().map_or_else(
+                || Err(error::Error::TildeWithoutValidHome),
+                |home_dir| Ok(home_dir.to_string_lossy().to_string()),
+            );
+        }
+
+        let expanded = match prefix {
+            "+" => Some(self.shell.env.get_str("PWD").map_or_else(
+                || self.shell.working_dir.to_string_lossy().to_string(),
+                |pwd| pwd.to_string(),
+            )),
+            "-" => self.shell.env.get_str("OLDPWD").map(|d| d.to_string()),
+            _ => self
+                .expand_directory_stack_tilde_prefix(prefix)
+                .or_else(|| {
+                    sys::users::get_user_home_dir(prefix).map(|p| p.to_string_lossy().to_string())
+                }),
+        };
+
+        // Prefixes that can't be resolved are left as-is.
+        Ok(expanded.unwrap_or_else(|| std::format!("~{prefix}")))
+    }
+
+    /// Expands tilde prefixes of the form `N`, `+N`, or `-N` to the corresponding
+    /// entry of the directory stack, as would be displayed by `dirs +N` or `dirs -N`.
+    /// Returns `None` if the prefix doesn't have this form, or if the index is out
+    /// of range.
+    fn expand_directory_stack_tilde_prefix(&self, prefix: &str) -> Option<String> {
+        let (from_end, digits) = if let Some(digits) = prefix.strip_prefix('-') {
+            (true, digits)
         } else {
-            Err(error::Error::TildeWithoutValidHome)
+            (false, prefix.strip_prefix('+').unwrap_or(prefix))
+        };
+
+        if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+            return None;
         }
+
+        let index: usize = digits.parse().ok()?;
+
+        // The stack as displayed by `dirs` starts with the current working directory.
+        let stack_len = self.shell.directory_stack.len() + 1;
+        let index = if from_end {
+            stack_len.checked_sub(index + 1)?
+        } else {
+            index
+        };
+
+        let dir = if index == 0 {
+            &self.shell.working_dir
+        } else {
+            self.shell.directory_stack.iter().rev().nth(index - 1)?
+        };
+
+        Some(dir.to_string_lossy().to_string())
     }
 
     #[allow(clippy::too_many_lines)]
@@ -754,9 +940,9 @@ impl<'a> WordExpander<'a> {
                         brush_parser::word::ParameterTestType::Unset,
                         ParameterState::DefinedEmptyString,
                     ) => Ok(expanded_parameter),
-                    _ => Ok(Expansion::from(
-                        self.basic_expand_to_str(default_value).await?,
-                    )),
+                    // N.B. The default value's fields are preserved as-is (e.g., so
+                    // `${x:-"$@"}` yields the same fields as `"$@"`).
+                    _ => Ok(self.basic_expand(default_value).await?),
                 }
             }
             brush_parser::word::ParameterExpr::AssignDefaultValues {
@@ -967,7 +1153,7 @@ impl<'a> WordExpander<'a> {
                     match var.value() {
                         ShellValue::IndexedArray(_)
                         | ShellValue::AssociativeArray(_)
-                        | ShellValue::Random => {
+                        | ShellValue::Dynamic(_) => {
                             let equals_or_nothing = if assignable_value_str.is_empty() {
                                 ""
                             } else {
@@ -1305,21 +1491,22 @@ impl<'a> WordExpander<'a> {
                 if let Some((_, var)) = self.shell.env.get(name) {
                     let values = var.value().get_element_values();
 
+                    // As with `$@`, arrays without elements are treated as unset.
                     Ok(Expansion {
+                        undefined: values.is_empty(),
                         fields: values
                             .into_iter()
                             .map(|value| WordField(vec![ExpansionPiece::Splittable(value)]))
                             .collect(),
                         concatenate: *concatenate,
                         from_array: true,
-                        undefined: false,
                     })
                 } else {
                     Ok(Expansion {
                         fields: vec![],
                         concatenate: *concatenate,
                         from_array: true,
-                        undefined: false,
+                        undefined: true,
                     })
                 }
             }
@@ -1352,6 +1539,8 @@ impl<'a> WordExpander<'a> {
             brush_parser::word::SpecialParameter::AllPositionalParameters { concatenate } => {
                 let positional_params = self.shell.positional_parameters.iter();
 
+                // N.B. With no positional parameters, `$@` and `$*` are treated as unset
+                // (e.g., by `${@-default}`), but still expand to no fields.
                 Ok(Expansion {
                     fields: positional_params
                         .into_iter()
@@ -1359,7 +1548,7 @@ impl<'a> WordExpander<'a> {
                         .collect(),
                     concatenate: *concatenate,
                     from_array: true,
-                    undefined: false,
+                    undefined: self.shell.positional_parameters.is_empty(),
                 })
             }
             brush_parser::word::SpecialParameter::PositionalParameterCount => Ok(Expansion::from(
@@ -1369,7 +1558,7 @@ impl<'a> WordExpander<'a> {
                 Ok(Expansion::from(self.shell.last_exit_status.to_string()))
             }
             brush_parser::word::SpecialParameter::CurrentOptionFlags => {
-                Ok(Expansion::from(self.shell.current_option_flags()))
+                Ok(Expansion::from(self.shell.options.current_option_flags()))
             }
             brush_parser::word::SpecialParameter::ProcessId => {
                 Ok(Expansion::from(std::process::id().to_string()))
@@ -1554,6 +1743,13 @@ fn coalesce_expansions(expansions: Vec<Expansion>) -> Expansion {
     expansions
         .into_iter()
         .fold(Expansion::default(), |mut acc, expansion| {
+            // N.B. Only expansions of arrays (and the like) or that yield multiple fields
+            // determine how the fields should be joined if they're later concatenated
+            // (e.g., `$*` vs. `$@`).
+            if expansion.from_array || expansion.fields.len() > 1 {
+                acc.concatenate = expansion.concatenate;
+            }
+
             for (i, mut field) in expansion.fields.into_iter().enumerate() {
                 match acc.fields.last_mut() {
                     Some(last) if i == 0 => {
@@ -1563,14 +1759,17 @@ fn coalesce_expansions(expansions: Vec<Expansion>) -> Expansion {
                 }
             }
 
-            // TODO: What if expansions have different concatenation values?
-            acc.concatenate = expansion.concatenate;
             acc.from_array = expansion.from_array;
 
             acc
         })
 }
 
+/// Returns whether the given IFS character is considered IFS whitespace.
+const fn is_ifs_whitespace(c: char) -> bool {
+    matches!(c, ' ' | '\t' | '\n')
+}
+
 fn to_initial_capitals(s: &str) -> String {
     let mut result = String::new();
     let mut capitalize_next = true;
@@ -1645,6 +1844,42 @@ fn may_contain_braces_to_expand(s: &str) -> bool {
     saw_opening_brace && saw_closing_brace
 }
 
+/// Collects the command substitutions in the given word pieces, in order. Returns `false` if
+/// the pieces contain other expansions whose results could depend on, or affect, the order in
+/// which the substitutions are run.
+///
+/// # Arguments
+///
+/// * `pieces` - The word pieces to inspect.
+/// * `command_strs` - Receives the command strings of the substitutions.
+fn collect_independent_command_substitutions(
+    pieces: &[brush_parser::word::WordPieceWithSource],
+    command_strs: &mut Vec<String>,
+) -> bool {
+    for piece in pieces {
+        match &piece.piece {
+            brush_parser::word::WordPiece::BackquotedCommandSubstitution(s)
+            | brush_parser::word::WordPiece::CommandSubstitution(s) => {
+                command_strs.push(s.clone());
+            }
+            brush_parser::word::WordPiece::DoubleQuotedSequence(inner_pieces) => {
+                if !collect_independent_command_substitutions(inner_pieces, command_strs) {
+                    return false;
+                }
+            }
+            brush_parser::word::WordPiece::ParameterExpansion(_)
+            | brush_parser::word::WordPiece::ArithmeticExpression(_) => return false,
+            brush_parser::word::WordPiece::Text(_)
+            | brush_parser::word::WordPiece::SingleQuotedText(_)
+            | brush_parser::word::WordPiece::AnsiCQuotedText(_)
+            | brush_parser::word::WordPiece::TildePrefix(_)
+            | brush_parser::word::WordPiece::EscapeSequence(_) => (),
+        }
+    }
+
+    true
+}
+
 fn generate_and_combine_brace_expansions(
     pieces: Vec<brush_parser::word::BraceExpressionOrText>,
 ) -> Vec<String> {
@@ -1754,6 +1989,43 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_field_splitting_with_ifs() -> Result<()> {
+        let options = crate::shell::CreateOptions::default();
+        let mut shell = crate::shell::Shell::new(&options).await?;
+
+        let cases: &[(&str, &str, &[&str])] = &[
+            (" \t\n", "  a  b  ", &["a", "b"]),
+            (":", "a::b", &["a", "", "b"]),
+            (":", ":a:", &["", "a"]),
+            (":", "a::", &["a", ""]),
+            (" :", " a : b ", &["a", "b"]),
+            (" :", " : a", &["", "a"]),
+            (" :", "a: :b", &["a", "", "b"]),
+            (" :", "a :: b", &["a", "", "b"]),
+            ("", " a:b ", &[" a:b "]),
+        ];
+
+        for (ifs, value, expected) in cases {
+            shell.env.set_global(
+                "IFS",
+                ShellVariable::new(ShellValue::String((*ifs).to_owned())),
+            )?;
+            shell.env.set_global(
+                "value",
+                ShellVariable::new(ShellValue::String((*value).to_owned())),
+            )?;
+
+            assert_eq!(
+                full_expand_and_split_str(&mut shell, "$value").await?,
+                *expected,
+                "IFS={ifs:?} value={value:?}"
+            );
+        }
+
+        Ok(())
+    }
+
     #[test]
     fn test_to_initial_capitals() {
         assert_eq!(to_initial_capitals("ab bc cd"), String::from("Ab Bc Cd"));
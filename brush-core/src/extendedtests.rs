@@ -1,8 +1,10 @@
 use brush_parser::ast;
+use std::borrow::Cow;
+use std::cmp::Ordering;
 use std::path::Path;
 
 use crate::{
-    arithmetic::ExpandAndEvaluate,
+    arithmetic::{Evaluatable, ExpandAndEvaluate},
     env, error, escape, expansion, namedoptions, patterns,
     sys::{
         fs::{MetadataExt, PathExt},
@@ -51,10 +53,12 @@ async fn apply_unary_predicate(
     let expanded_operand = expansion::basic_expand_word(shell, operand).await?;
 
     if shell.options.print_commands_and_arguments {
-        shell.trace_command(std::format!(
-            "[[ {op} {} ]]",
-            escape::quote_if_needed(&expanded_operand, escape::QuoteMode::Quote)
-        ))?;
+        shell
+            .trace_command(std::format!(
+                "[[ {op} {} ]]",
+                escape::quote_if_needed(&expanded_operand, escape::QuoteMode::Quote)
+            ))
+            .await?;
     }
 
     apply_unary_predicate_to_str(op, expanded_operand.as_str(), shell)
@@ -174,13 +178,96 @@ pub(crate) fn apply_unary_predicate_to_str(
                 Ok(false)
             }
         }
-        ast::UnaryPredicate::ShellVariableIsSetAndAssigned => Ok(shell.env.is_set(operand)),
+        ast::UnaryPredicate::ShellVariableIsSetAndAssigned => Ok(variable_is_set(shell, operand)),
         ast::UnaryPredicate::ShellVariableIsSetAndNameRef => {
             error::unimp("unary extended test predicate: ShellVariableIsSetAndNameRef")
         }
     }
 }
 
+/// Checks whether the given variable is set; if it's an array, checks its element at
+/// index 0. The operand may instead identify an array element (e.g., `arr[1]`), or all
+/// of an array's elements (e.g., `arr[@]`), in which case this checks whether any are set.
+fn variable_is_set(shell: &mut Shell, operand: &str) -> bool {
+    let (name, index) = match operand.strip_suffix(']').and_then(|s| s.split_once('[')) {
+        Some((name, index)) => (name, Some(index)),
+        None => (operand, None),
+    };
+
+    let Some((_, var)) = shell.env.get(name) else {
+        return false;
+    };
+
+    let index = match index {
+        None => Cow::Borrowed("0"),
+        Some("@" | "*") => return !var.value().get_element_keys().is_empty(),
+        Some(index) if matches!(var.value(), variables::ShellValue::AssociativeArray(_)) => {
+            Cow::Borrowed(index)
+        }
+        Some(index) => {
+            let last_index = var
+                .value()
+                .get_element_keys()
+                .iter()
+                .filter_map(|key| key.parse::<i64>().ok())
+                .max();
+
+            let Some(index) = brush_parser::arithmetic::parse(index)
+                .ok()
+                .and_then(|expr| expr.eval(shell).ok())
+            else {
+                return false;
+            };
+
+            // N.B. A negative index counts back from the end of the array.
+            let index = if index < 0 {
+                last_index.map_or(index, |last| last + 1 + index)
+            } else {
+                index
+            };
+
+            if index < 0 {
+                return false;
+            }
+
+            Cow::Owned(index.to_string())
+        }
+    };
+
+    shell.env.get(name).is_some_and(|(_, var)| {
+        var.value()
+            .get_at(index.as_ref())
+            .is_ok_and(|element| element.is_some())
+    })
+}
+
+/// Compares two strings per the collation order of the current locale, as `[[ ]]` does.
+fn collate(shell: &Shell, left: &str, right: &str) -> Ordering {
+    let locale = ["LC_ALL", "LC_COLLATE", "LANG"]
+        .iter()
+        .find_map(|name| shell.env.get_str(name).filter(|value| !value.is_empty()));
+
+    let uses_byte_order = locale.as_deref().map_or(true, |locale| {
+        locale == "C" || locale == "POSIX" || locale.starts_with("C.")
+    });
+
+    if uses_byte_order {
+        return left.cmp(right);
+    }
+
+    // We approximate the collation order of natural-language locales: strings are compared
+    // without regard to case, and otherwise-equal strings sort lowercase first.
+    let folded = |s: &'_ str| s.chars().flat_map(char::to_lowercase).collect::<Vec<_>>();
+    folded(left)
+        .cmp(&folded(right))
+        .then_with(|| {
+            left.chars()
+                .map(char::is_uppercase)
+                .cmp(right.chars().map(char::is_uppercase))
+        })
+        .then_with(|| left.cmp(right))
+}
+
 #[allow(clippy::too_many_lines)]
 async fn apply_binary_predicate(
     op: &ast::BinaryPredicate,
@@ -195,23 +282,28 @@ async fn apply_binary_predicate(
             let regex = expansion::basic_expand_regex(shell, right).await?;
 
             if shell.options.print_commands_and_arguments {
-                shell.trace_command(std::format!("[[ {s} {op} {right} ]]"))?;
+                shell
+                    .trace_command(std::format!("[[ {s} {op} {right} ]]"))
+                    .await?;
             }
 
-            let (matches, captures) = match regex.matches(s.as_str()) {
-                Ok(Some(captures)) => (true, captures),
-                Ok(None) => (false, vec![]),
+            let captures = match regex.captures(s.as_str()) {
+                Ok(captures) => captures,
                 // If we can't compile the regex, don't abort the whole operation but make sure to
                 // report it.
                 // TODO: Docs indicate we should yield 2 on an invalid regex (not 1).
                 Err(e) => {
                     tracing::warn!("error using regex: {}", e);
-                    (false, vec![])
+                    None
                 }
             };
+            let matches = captures.is_some();
+            let (groups, named_groups) = captures
+                .map(|c| (c.groups, c.named_groups))
+                .unwrap_or_default();
 
             let captures_value = variables::ShellValueLiteral::Array(ArrayLiteral(
-                captures
+                groups
                     .into_iter()
                     .map(|c| (None, c.unwrap_or_default()))
                     .collect(),
@@ -225,6 +317,21 @@ async fn apply_binary_predicate(
                 env::EnvironmentScope::Global,
             )?;
 
+            // As an extension, the text matched by named groups is made available by name.
+            if named_groups.is_empty() {
+                shell.env.unset("BRUSH_REMATCH_NAMED")?;
+            } else {
+                shell.env.set_global(
+                    "BRUSH_REMATCH_NAMED",
+                    variables::ShellVariable::new(variables::ShellValue::AssociativeArray(
+                        named_groups
+                            .into_iter()
+                            .map(|(name, c)| (name, c.unwrap_or_default()))
+                            .collect(),
+                    )),
+                )?;
+            }
+
             Ok(matches)
         }
         ast::BinaryPredicate::StringContainsSubstring => {
@@ -232,7 +339,9 @@ async fn apply_binary_predicate(
             let substring = expansion::basic_expand_word(shell, right).await?;
 
             if shell.options.print_commands_and_arguments {
-                shell.trace_command(std::format!("[[ {s} {op} {substring} ]]"))?;
+                shell
+                    .trace_command(std::format!("[[ {s} {op} {substring} ]]"))
+                    .await?;
             }
 
             Ok(s.contains(substring.as_str()))
@@ -251,22 +360,24 @@ async fn apply_binary_predicate(
             let right = expansion::basic_expand_word(shell, right).await?;
 
             if shell.options.print_commands_and_arguments {
-                shell.trace_command(std::format!("[[ {left} {op} {right} ]]"))?;
+                shell
+                    .trace_command(std::format!("[[ {left} {op} {right} ]]"))
+                    .await?;
             }
 
-            // TODO: According to docs, should be lexicographical order of the current locale.
-            Ok(left < right)
+            Ok(collate(shell, left.as_str(), right.as_str()).is_lt())
         }
         ast::BinaryPredicate::LeftSortsAfterRight => {
             let left = expansion::basic_expand_word(shell, left).await?;
             let right = expansion::basic_expand_word(shell, right).await?;
 
             if shell.options.print_commands_and_arguments {
-                shell.trace_command(std::format!("[[ {left} {op} {right} ]]"))?;
+                shell
+                    .trace_command(std::format!("[[ {left} {op} {right} ]]"))
+                    .await?;
             }
 
-            // TODO: According to docs, should be lexicographical order of the current locale.
-            Ok(left > right)
+            Ok(collate(shell, left.as_str(), right.as_str()).is_gt())
         }
         ast::BinaryPredicate::ArithmeticEqualTo => {
             let unexpanded_left = ast::UnexpandedArithmeticExpr {
@@ -279,7 +390,9 @@ async fn apply_binary_predicate(
             let right = unexpanded_right.eval(shell, false).await?;
 
             if shell.options.print_commands_and_arguments {
-                shell.trace_command(std::format!("[[ {left} {op} {right} ]]"))?;
+                shell
+                    .trace_command(std::format!("[[ {left} {op} {right} ]]"))
+                    .await?;
             }
 
             Ok(left == right)
@@ -295,7 +408,9 @@ async fn apply_binary_predicate(
             let right = unexpanded_right.eval(shell, false).await?;
 
             if shell.options.print_commands_and_arguments {
-                shell.trace_command(std::format!("[[ {left} {op} {right} ]]"))?;
+                shell
+                    .trace_command(std::format!("[[ {left} {op} {right} ]]"))
+                    .await?;
             }
 
             Ok(left != right)
@@ -311,7 +426,9 @@ async fn apply_binary_predicate(
             let right = unexpanded_right.eval(shell, false).await?;
 
             if shell.options.print_commands_and_arguments {
-                shell.trace_command(std::format!("[[ {left} {op} {right} ]]"))?;
+                shell
+                    .trace_command(std::format!("[[ {left} {op} {right} ]]"))
+                    .await?;
             }
 
             Ok(left < right)
@@ -327,7 +444,9 @@ async fn apply_binary_predicate(
             let right = unexpanded_right.eval(shell, false).await?;
 
             if shell.options.print_commands_and_arguments {
-                shell.trace_command(std::format!("[[ {left} {op} {right} ]]"))?;
+                shell
+                    .trace_command(std::format!("[[ {left} {op} {right} ]]"))
+                    .await?;
             }
 
             Ok(left <= right)
@@ -343,7 +462,9 @@ async fn apply_binary_predicate(
             let right = unexpanded_right.eval(shell, false).await?;
 
             if shell.options.print_commands_and_arguments {
-                shell.trace_command(std::format!("[[ {left} {op} {right} ]]"))?;
+                shell
+                    .trace_command(std::format!("[[ {left} {op} {right} ]]"))
+                    .await?;
             }
 
             Ok(left > right)
@@ -359,7 +480,9 @@ async fn apply_binary_predicate(
             let right = unexpanded_right.eval(shell, false).await?;
 
             if shell.options.print_commands_and_arguments {
-                shell.trace_command(std::format!("[[ {left} {op} {right} ]]"))?;
+                shell
+                    .trace_command(std::format!("[[ {left} {op} {right} ]]"))
+                    .await?;
             }
 
             Ok(left >= right)
@@ -381,7 +504,9 @@ async fn apply_binary_predicate(
                     expanded_right.as_str(),
                     escape::QuoteMode::BackslashEscape,
                 );
-                shell.trace_command(std::format!("[[ {s} {op} {escaped_right} ]]"))?;
+                shell
+                    .trace_command(std::format!("[[ {s} {op} {escaped_right} ]]"))
+                    .await?;
             }
 
             pattern.exactly_matches(s.as_str())
@@ -399,7 +524,9 @@ async fn apply_binary_predicate(
                     expanded_right.as_str(),
                     escape::QuoteMode::BackslashEscape,
                 );
-                shell.trace_command(std::format!("[[ {s} {op} {escaped_right} ]]"))?;
+                shell
+                    .trace_command(std::format!("[[ {s} {op} {escaped_right} ]]"))
+                    .await?;
             }
 
             let eq = pattern.exactly_matches(s.as_str())?;
@@ -424,14 +551,9 @@ pub(crate) fn apply_binary_predicate_to_strs(
         ast::BinaryPredicate::LeftFileIsOlderOrDoesNotExistWhenRightDoes => error::unimp(
             "extended test binary predicate LeftFileIsOlderOrDoesNotExistWhenRightDoes",
         ),
-        ast::BinaryPredicate::LeftSortsBeforeRight => {
-            // TODO: According to docs, should be lexicographical order of the current locale.
-            Ok(left < right)
-        }
-        ast::BinaryPredicate::LeftSortsAfterRight => {
-            // TODO: According to docs, should be lexicographical order of the current locale.
-            Ok(left > right)
-        }
+        // N.B. Unlike `[[ ]]`, `test` compares strings using ASCII ordering.
+        ast::BinaryPredicate::LeftSortsBeforeRight => Ok(left < right),
+        ast::BinaryPredicate::LeftSortsAfterRight => Ok(left > right),
         ast::BinaryPredicate::ArithmeticEqualTo => Ok(apply_test_binary_arithmetic_predicate(
             left,
             right,
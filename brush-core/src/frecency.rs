@@ -0,0 +1,364 @@
+//! Ranking of directories by how frequently and recently they've been visited ("frecency"),
+//! so that they can be jumped to by a few keywords from their paths (see the `j` builtin).
+//! Ranks are kept in a [`DirectoryRankStore`] provided by the embedder of the shell.
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::error;
+
+/// Once the ranks of all directories add up to more than this, they're all aged.
+const MAX_TOTAL_RANK: f64 = 10000.0;
+
+/// Factor applied to all ranks when they're aged.
+const AGING_FACTOR: f64 = 0.9;
+
+/// Directories whose rank falls below this when aged are forgotten.
+const MIN_RANK: f64 = 1.0;
+
+const HOUR_SECS: u64 = 60 * 60;
+const DAY_SECS: u64 = 24 * HOUR_SECS;
+const WEEK_SECS: u64 = 7 * DAY_SECS;
+
+/// How frequently and recently a directory has been visited.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DirectoryRank {
+    /// The absolute path of the directory.
+    pub path: PathBuf,
+    /// The directory's rank, which grows by one with each visit (and decays as the ranks
+    /// of all directories are aged).
+    pub rank: f64,
+    /// When the directory was last visited, in seconds since the Unix epoch.
+    pub last_visited: u64,
+}
+
+impl DirectoryRank {
+    /// Returns the directory's score, which weighs its rank by how recently it was visited.
+    ///
+    /// # Arguments
+    ///
+    /// * `now` - The current time, in seconds since the Unix epoch.
+    pub fn score(&self, now: u64) -> f64 {
+        let elapsed = now.saturating_sub(self.last_visited);
+        if elapsed < HOUR_SECS {
+            self.rank * 4.0
+        } else if elapsed < DAY_SECS {
+            self.rank * 2.0
+        } else if elapsed < WEEK_SECS {
+            self.rank / 2.0
+        } else {
+            self.rank / 4.0
+        }
+    }
+}
+
+/// A store of directory ranks. Stores are shared by all shells in a session (e.g., by
+/// subshells), and may be shared by concurrent sessions.
+pub trait DirectoryRankStore: std::fmt::Debug + Send + Sync {
+    /// Records a visit to the given directory.
+    ///
+    /// # Arguments
+    ///
+    /// * `dir` - The absolute path of the directory.
+    fn record_visit(&self, dir: &Path) -> Result<(), error::Error>;
+
+    /// Returns the ranks of all known directories, in no particular order.
+    fn ranks(&self) -> Result<Vec<DirectoryRank>, error::Error>;
+
+    /// Forgets the given directory (e.g., because it no longer exists).
+    ///
+    /// # Arguments
+    ///
+    /// * `dir` - The absolute path of the directory.
+    fn remove(&self, dir: &Path) -> Result<(), error::Error>;
+}
+
+/// A [`DirectoryRankStore`] that keeps ranks in memory, for the lifetime of the store.
+#[derive(Debug, Default)]
+pub struct InMemoryRankStore {
+    ranks: Mutex<Vec<DirectoryRank>>,
+}
+
+impl DirectoryRankStore for InMemoryRankStore {
+    fn record_visit(&self, dir: &Path) -> Result<(), error::Error> {
+        let mut ranks = self
+            .ranks
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        add_visit(&mut ranks, dir, current_time());
+        Ok(())
+    }
+
+    fn ranks(&self) -> Result<Vec<DirectoryRank>, error::Error> {
+        let ranks = self
+            .ranks
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        Ok(ranks.clone())
+    }
+
+    fn remove(&self, dir: &Path) -> Result<(), error::Error> {
+        let mut ranks = self
+            .ranks
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        ranks.retain(|r| r.path != dir);
+        Ok(())
+    }
+}
+
+/// A [`DirectoryRankStore`] that persists ranks to a file, so they're retained across
+/// sessions. The file is re-read for each operation, so that concurrent sessions see each
+/// other's visits; each line holds a directory's rank, the time it was last visited, and
+/// its path, separated by tabs.
+#[derive(Debug)]
+pub struct FileRankStore {
+    /// The path of the file.
+    path: PathBuf,
+    /// Serializes updates to the file made by shells in this process.
+    update_lock: Mutex<()>,
+}
+
+impl FileRankStore {
+    /// Returns a store persisting ranks to the file at the given path; the file is created
+    /// when the first visit is recorded.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the file.
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            update_lock: Mutex::new(()),
+        }
+    }
+
+    fn read(&self) -> Result<Vec<DirectoryRank>, error::Error> {
+        match std::fs::read_to_string(&self.path) {
+            Ok(contents) => Ok(parse_ranks(contents.as_str())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(vec![]),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn update(&self, f: impl FnOnce(&mut Vec<DirectoryRank>)) -> Result<(), error::Error> {
+        let _guard = self
+            .update_lock
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+
+        let mut ranks = self.read()?;
+        f(&mut ranks);
+
+        // Write to a temporary file and then rename it into place, so that readers never
+        // observe a partially written file.
+        let mut temp_path = self.path.clone().into_os_string();
+        temp_path.push(std::format!(".{}.tmp", std::process::id()));
+
+        let mut file = std::fs::File::create(&temp_path)?;
+        file.write_all(format_ranks(ranks.as_slice()).as_bytes())?;
+        drop(file);
+
+        std::fs::rename(&temp_path, &self.path)?;
+
+        Ok(())
+    }
+}
+
+impl DirectoryRankStore for FileRankStore {
+    fn record_visit(&self, dir: &Path) -> Result<(), error::Error> {
+        self.update(|ranks| add_visit(ranks, dir, current_time()))
+    }
+
+    fn ranks(&self) -> Result<Vec<DirectoryRank>, error::Error> {
+        self.read()
+    }
+
+    fn remove(&self, dir: &Path) -> Result<(), error::Error> {
+        self.update(|ranks| ranks.retain(|r| r.path != dir))
+    }
+}
+
+/// Returns the ranked directories whose paths match all of the given keywords, ordered from
+/// highest to lowest score. Matching ignores case; the keywords must appear in the path in
+/// the order given, and the last of them must appear in the path's final component.
+///
+/// # Arguments
+///
+/// * `ranks` - The ranked directories.
+/// * `keywords` - The keywords to match.
+/// * `now` - The current time, in seconds since the Unix epoch.
+pub fn find_matches<'a>(
+    ranks: &'a [DirectoryRank],
+    keywords: &[&str],
+    now: u64,
+) -> Vec<&'a DirectoryRank> {
+    let keywords: Vec<_> = keywords.iter().map(|k| k.to_lowercase()).collect();
+
+    let mut matches: Vec<_> = ranks
+        .iter()
+        .filter(|r| path_matches(r.path.as_path(), keywords.as_slice()))
+        .collect();
+
+    matches.sort_by(|left, right| right.score(now).total_cmp(&left.score(now)));
+
+    matches
+}
+
+/// Returns the current time, in seconds since the Unix epoch.
+pub fn current_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+fn path_matches(path: &Path, keywords: &[String]) -> bool {
+    let path = path.to_string_lossy().to_lowercase();
+    let last_component_start = path
+        .rfind(std::path::is_separator)
+        .map_or(0, |index| index + 1);
+
+    let mut search_start = 0;
+    for (i, keyword) in keywords.iter().enumerate() {
+        let is_last = i + 1 == keywords.len();
+        if is_last {
+            search_start = search_start.max(last_component_start);
+        }
+
+        let Some(offset) = path[search_start..].find(keyword.as_str()) else {
+            return false;
+        };
+        search_start += offset + keyword.len();
+    }
+
+    true
+}
+
+fn add_visit(ranks: &mut Vec<DirectoryRank>, dir: &Path, now: u64) {
+    if let Some(existing) = ranks.iter_mut().find(|r| r.path == dir) {
+        existing.rank += 1.0;
+        existing.last_visited = now;
+    } else {
+        ranks.push(DirectoryRank {
+            path: dir.to_owned(),
+            rank: 1.0,
+            last_visited: now,
+        });
+    }
+
+    let total_rank: f64 = ranks.iter().map(|r| r.rank).sum();
+    if total_rank > MAX_TOTAL_RANK {
+        for r in ranks.iter_mut() {
+            r.rank *= AGING_FACTOR;
+        }
+        ranks.retain(|r| r.rank >= MIN_RANK);
+    }
+}
+
+fn parse_ranks(contents: &str) -> Vec<DirectoryRank> {
+    contents
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(3, '\t');
+            let rank = fields.next()?.parse().ok()?;
+            let last_visited = fields.next()?.parse().ok()?;
+            let path = PathBuf::from(fields.next()?);
+
+            Some(DirectoryRank {
+                path,
+                rank,
+                last_visited,
+            })
+        })
+        .collect()
+}
+
+fn format_ranks(ranks: &[DirectoryRank]) -> String {
+    let mut contents = String::new();
+    for r in ranks {
+        contents.push_str(
+            std::format!("{}\t{}\t{}\n", r.rank, r.last_visited, r.path.display()).as_str(),
+        );
+    }
+
+    contents
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rank(path: &str, rank: f64, last_visited: u64) -> DirectoryRank {
+        DirectoryRank {
+            path: PathBuf::from(path),
+            rank,
+            last_visited,
+        }
+    }
+
+    #[test]
+    fn matching() {
+        let now = 10 * WEEK_SECS;
+        let ranks = [
+            rank("/home/user/src/brush", 2.0, now),
+            rank("/home/user/src/brush/brush-core", 10.0, now - 2 * WEEK_SECS),
+            rank("/home/user/Documents", 1.0, now),
+            rank("/tmp/brushes", 1.0, now),
+        ];
+
+        let paths = |keywords: &[&str]| -> Vec<_> {
+            find_matches(&ranks, keywords, now)
+                .into_iter()
+                .map(|r| r.path.to_string_lossy().into_owned())
+                .collect()
+        };
+
+        assert_eq!(
+            paths(&["brush"]),
+            [
+                "/home/user/src/brush",
+                "/tmp/brushes",
+                "/home/user/src/brush/brush-core"
+            ]
+        );
+        assert_eq!(paths(&["src", "core"]), ["/home/user/src/brush/brush-core"]);
+        assert_eq!(paths(&["doc"]), ["/home/user/Documents"]);
+        assert!(paths(&["core", "src"]).is_empty());
+        assert!(paths(&["user"]).is_empty());
+    }
+
+    #[test]
+    fn visits_and_aging() {
+        let mut ranks = vec![rank("/a", MAX_TOTAL_RANK - 3.0, 0), rank("/b", 1.0, 0)];
+
+        add_visit(&mut ranks, Path::new("/c"), 5);
+        add_visit(&mut ranks, Path::new("/c"), 6);
+        assert_eq!(
+            ranks,
+            [
+                rank("/a", MAX_TOTAL_RANK - 3.0, 0),
+                rank("/b", 1.0, 0),
+                rank("/c", 2.0, 6),
+            ]
+        );
+
+        // Once the total exceeds the maximum, all ranks are aged and the lowest forgotten.
+        add_visit(&mut ranks, Path::new("/c"), 7);
+        assert_eq!(
+            ranks,
+            [
+                rank("/a", (MAX_TOTAL_RANK - 3.0) * AGING_FACTOR, 0),
+                rank("/c", 3.0 * AGING_FACTOR, 7),
+            ]
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        let ranks = vec![rank("/home/user/my dir", 3.5, 1234)];
+        assert_eq!(parse_ranks(format_ranks(ranks.as_slice()).as_str()), ranks);
+    }
+}
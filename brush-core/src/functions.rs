@@ -1,9 +1,16 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::HashMap,
+    sync::{Arc, OnceLock},
+};
 
-/// An environment for defined, named functions.
+/// An environment for defined, named functions. Cloning an environment is cheap: clones
+/// share their contents until one of them is modified.
 #[derive(Clone, Default)]
 pub struct FunctionEnv {
-    functions: HashMap<String, FunctionRegistration>,
+    functions: Arc<HashMap<String, FunctionRegistration>>,
+    /// Environment variables exporting the exported functions; computed on demand and
+    /// discarded whenever the environment is modified.
+    exported_env_vars: OnceLock<Arc<Vec<(String, String)>>>,
 }
 
 impl FunctionEnv {
@@ -16,13 +23,35 @@ impl FunctionEnv {
         self.functions.get(name)
     }
 
+    /// Tries to retrieve a mutable reference to the registration for a function by name.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the function to retrieve.
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut FunctionRegistration> {
+        // N.B. Avoid copying shared contents when there's nothing to modify.
+        if !self.functions.contains_key(name) {
+            return None;
+        }
+
+        // N.B. The caller may change whether the function is exported.
+        self.exported_env_vars.take();
+        Arc::make_mut(&mut self.functions).get_mut(name)
+    }
+
     /// Unregisters a function from the environment.
     ///
     /// # Arguments
     ///
     /// * `name` - The name of the function to remove.
     pub fn remove(&mut self, name: &str) -> Option<FunctionRegistration> {
-        self.functions.remove(name)
+        // N.B. Avoid copying shared contents when there's nothing to remove.
+        if !self.functions.contains_key(name) {
+            return None;
+        }
+
+        self.exported_env_vars.take();
+        Arc::make_mut(&mut self.functions).remove(name)
     }
 
     /// Updates a function registration in this environment.
@@ -32,14 +61,41 @@ impl FunctionEnv {
     /// * `name` - The name of the function to update.
     /// * `definition` - The new definition for the function.
     pub fn update(&mut self, name: String, definition: Arc<brush_parser::ast::FunctionDefinition>) {
-        self.functions
-            .insert(name, FunctionRegistration { definition });
+        // N.B. Redefining a function doesn't change whether it's exported.
+        let exported = self.functions.get(&name).is_some_and(|r| r.exported);
+
+        self.exported_env_vars.take();
+        Arc::make_mut(&mut self.functions).insert(
+            name,
+            FunctionRegistration {
+                definition,
+                exported,
+                line_number_offset: 0,
+            },
+        );
     }
 
     /// Returns an iterator over the functions registered in this environment.
     pub fn iter(&self) -> impl Iterator<Item = (&String, &FunctionRegistration)> {
         self.functions.iter()
     }
+
+    /// Returns the names and values of the environment variables used to export the
+    /// exported functions to child processes. They're cached until the environment is
+    /// next modified, so spawning many processes doesn't format each function each time.
+    pub(crate) fn exported_env_vars(&self) -> Arc<Vec<(String, String)>> {
+        self.exported_env_vars
+            .get_or_init(|| {
+                Arc::new(
+                    self.functions
+                        .values()
+                        .filter(|registration| registration.is_exported())
+                        .map(FunctionRegistration::to_env_var)
+                        .collect(),
+                )
+            })
+            .clone()
+    }
 }
 
 /// Encapsulates a registration for a defined function.
@@ -47,4 +103,73 @@ impl FunctionEnv {
 pub struct FunctionRegistration {
     /// The definition of the function.
     pub definition: Arc<brush_parser::ast::FunctionDefinition>,
+    /// Whether or not the function is exported to child processes.
+    exported: bool,
+    /// Offset to apply to line numbers within the definition.
+    line_number_offset: u32,
+}
+
+impl FunctionRegistration {
+    /// Returns whether or not the function is exported to child processes.
+    pub fn is_exported(&self) -> bool {
+        self.exported
+    }
+
+    /// Marks the function for export to child processes.
+    pub fn export(&mut self) {
+        self.exported = true;
+    }
+
+    /// Unmarks the function for export to child processes.
+    pub fn unexport(&mut self) {
+        self.exported = false;
+    }
+
+    /// Returns the offset to apply to line numbers within the definition.
+    pub fn line_number_offset(&self) -> u32 {
+        self.line_number_offset
+    }
+
+    /// Sets the offset to apply to line numbers within the definition; this is the line number
+    /// offset that was in effect when the function was defined.
+    ///
+    /// # Arguments
+    ///
+    /// * `offset` - The offset to apply.
+    pub fn set_line_number_offset(&mut self, offset: u32) {
+        self.line_number_offset = offset;
+    }
+
+    /// Returns the name and value of the environment variable used to export this function,
+    /// in the encoding used by bash (i.e., `BASH_FUNC_name%%=() { ... }`).
+    pub(crate) fn to_env_var(&self) -> (String, String) {
+        let definition = self.definition.as_ref();
+        let body = brush_parser::format::format_function_body(&definition.body);
+
+        (
+            env_var_name(definition.fname.as_str()),
+            std::format!("() {body}"),
+        )
+    }
+}
+
+const EXPORTED_FUNCTION_VAR_PREFIX: &str = "BASH_FUNC_";
+const EXPORTED_FUNCTION_VAR_SUFFIX: &str = "%%";
+
+/// Returns the name of the environment variable used to export the named function.
+fn env_var_name(function_name: &str) -> String {
+    std::format!("{EXPORTED_FUNCTION_VAR_PREFIX}{function_name}{EXPORTED_FUNCTION_VAR_SUFFIX}")
+}
+
+/// If the given environment variable name is one used to export a function, returns the
+/// name of that function.
+///
+/// # Arguments
+///
+/// * `var_name` - The name of the environment variable.
+pub(crate) fn exported_function_name(var_name: &str) -> Option<&str> {
+    var_name
+        .strip_prefix(EXPORTED_FUNCTION_VAR_PREFIX)?
+        .strip_suffix(EXPORTED_FUNCTION_VAR_SUFFIX)
+        .filter(|name| !name.is_empty())
 }
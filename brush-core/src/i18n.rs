@@ -0,0 +1,301 @@
+//! Localization of the shell's own messages.
+//!
+//! Each message is identified by an ID and has English text, which is used when no
+//! translation is available. Translations are provided by catalogs bundled with the
+//! shell, one per language; the catalog used is selected by the `LC_ALL`,
+//! `LC_MESSAGES`, and `LANG` variables, in that order of precedence.
+//!
+//! A catalog consists of lines of the form `id = text`; blank lines and lines starting
+//! with `#` are ignored. Within text, `\n` denotes a newline and `{name}` is replaced
+//! with the message argument of the same name.
+
+use std::collections::HashMap;
+use std::fmt::Display;
+
+/// A message displayed by the shell.
+#[derive(Clone, Copy, Debug)]
+pub struct Message {
+    /// Identifies the message in catalogs.
+    pub id: &'static str,
+    /// The English text of the message.
+    pub text: &'static str,
+}
+
+/// A command couldn't be found.
+pub const COMMAND_NOT_FOUND: Message = Message {
+    id: "command-not-found",
+    text: "{command}: command not found",
+};
+
+/// A syntax error was found near a token.
+pub const SYNTAX_ERROR_NEAR_TOKEN: Message = Message {
+    id: "syntax-error-near-token",
+    text: "syntax error near token `{token}' (line {line} col {column})",
+};
+
+/// A syntax error was found at the end of the input.
+pub const SYNTAX_ERROR_AT_END_OF_INPUT: Message = Message {
+    id: "syntax-error-at-end-of-input",
+    text: "syntax error at end of input",
+};
+
+/// Locates an error found while tokenizing input.
+pub const TOKENIZER_ERROR_POSITION: Message = Message {
+    id: "tokenizer-error-position",
+    text: "(detected near line {line} column {column})",
+};
+
+/// Introduces the description of a syntax error, as rendered with an excerpt of the
+/// offending source.
+pub const SYNTAX_ERROR_DETAIL: Message = Message {
+    id: "syntax-error-detail",
+    text: "syntax error: {detail}",
+};
+
+/// Describes an unexpected token found while parsing.
+pub const SYNTAX_ERROR_UNEXPECTED_TOKEN: Message = Message {
+    id: "syntax-error-unexpected-token",
+    text: "unexpected token `{token}'",
+};
+
+/// Describes input that ended before parsing could complete.
+pub const SYNTAX_ERROR_UNEXPECTED_END_OF_INPUT: Message = Message {
+    id: "syntax-error-unexpected-end-of-input",
+    text: "unexpected end of input",
+};
+
+/// Introduces a hint for fixing a syntax error.
+pub const SYNTAX_ERROR_HINT: Message = Message {
+    id: "syntax-error-hint",
+    text: "hint: {hint}",
+};
+
+/// Hints that a quote is never closed.
+pub const SYNTAX_ERROR_UNCLOSED_QUOTE_HINT: Message = Message {
+    id: "syntax-error-unclosed-quote-hint",
+    text: "the quote opened here is never closed; add a matching {quote} where the quoted text should end",
+};
+
+/// Hints that a compound command (e.g., an `if`) is never closed.
+pub const SYNTAX_ERROR_UNCLOSED_COMMAND_HINT: Message = Message {
+    id: "syntax-error-unclosed-command-hint",
+    text: "`{opener}` on line {line} is missing a matching `{closer}`",
+};
+
+/// Hints that `==` isn't portable within `[ ]`.
+pub const SYNTAX_ERROR_DOUBLE_EQUALS_HINT: Message = Message {
+    id: "syntax-error-double-equals-hint",
+    text: "`==` isn't supported by `[ ]` in all shells; use `=` to compare strings, or use `[[ ]]`",
+};
+
+/// Introduces the list of builtins displayed by `help`.
+pub const HELP_BUILTINS_HEADER: Message = Message {
+    id: "help-builtins-header",
+    text: "The following commands are implemented as shell built-ins:",
+};
+
+/// No help topics matched a pattern given to `help`.
+pub const HELP_NO_MATCHING_TOPICS: Message = Message {
+    id: "help-no-matching-topics",
+    text: "No help topics match '{pattern}'",
+};
+
+/// A name given to `builtin` isn't that of an enabled builtin.
+pub const NOT_A_SHELL_BUILTIN: Message = Message {
+    id: "not-a-shell-builtin",
+    text: "{builtin}: not a shell builtin",
+};
+
+/// All messages that may be translated, in the order they appear in catalogs.
+pub const MESSAGES: &[Message] = &[
+    COMMAND_NOT_FOUND,
+    SYNTAX_ERROR_NEAR_TOKEN,
+    SYNTAX_ERROR_AT_END_OF_INPUT,
+    TOKENIZER_ERROR_POSITION,
+    SYNTAX_ERROR_DETAIL,
+    SYNTAX_ERROR_UNEXPECTED_TOKEN,
+    SYNTAX_ERROR_UNEXPECTED_END_OF_INPUT,
+    SYNTAX_ERROR_HINT,
+    SYNTAX_ERROR_UNCLOSED_QUOTE_HINT,
+    SYNTAX_ERROR_UNCLOSED_COMMAND_HINT,
+    SYNTAX_ERROR_DOUBLE_EQUALS_HINT,
+    HELP_BUILTINS_HEADER,
+    HELP_NO_MATCHING_TOPICS,
+    NOT_A_SHELL_BUILTIN,
+];
+
+/// Catalogs bundled with the shell, keyed by language.
+const BUNDLED_CATALOGS: &[(&str, &str)] = &[
+    ("de", include_str!("../locales/de.catalog")),
+    ("es", include_str!("../locales/es.catalog")),
+    ("fr", include_str!("../locales/fr.catalog")),
+];
+
+/// A set of translated messages.
+#[derive(Clone, Debug, Default)]
+pub struct Catalog {
+    translations: HashMap<String, String>,
+}
+
+impl Catalog {
+    /// Parses a catalog from its textual form.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The text of the catalog.
+    pub fn parse(text: &str) -> Self {
+        let translations = text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .filter_map(|line| line.split_once('='))
+            .map(|(id, translation)| {
+                (
+                    id.trim().to_owned(),
+                    translation.trim().replace("\\n", "\n"),
+                )
+            })
+            .filter(|(_, translation)| !translation.is_empty())
+            .collect();
+
+        Self { translations }
+    }
+
+    /// Returns the bundled catalog for the given locale, if there is one. Catalogs are
+    /// bundled per language, so only the language portion of the locale is considered.
+    ///
+    /// # Arguments
+    ///
+    /// * `locale` - The name of the locale (e.g., `de_DE.UTF-8`).
+    pub fn for_locale(locale: &str) -> Option<Self> {
+        let language = locale
+            .split(['_', '.', '@'])
+            .next()
+            .filter(|language| !language.is_empty())?;
+
+        BUNDLED_CATALOGS
+            .iter()
+            .find(|(name, _)| *name == language)
+            .map(|(_, text)| Self::parse(text))
+    }
+
+    /// Returns the text of the given message, translated if the catalog has a
+    /// translation for it, with its arguments substituted.
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The message.
+    /// * `args` - The message's arguments, by name.
+    pub fn format(&self, message: &Message, args: &[(&str, &dyn Display)]) -> String {
+        let text = self
+            .translations
+            .get(message.id)
+            .map_or(message.text, String::as_str);
+
+        substitute(text, args)
+    }
+
+    /// Renders the catalog in its textual form, including an entry for each message
+    /// in [`MESSAGES`] preceded by its English text. Messages without a translation are
+    /// included but commented out; translations of unknown messages are dropped.
+    pub fn to_catalog_text(&self) -> String {
+        let mut text = String::new();
+
+        for message in MESSAGES {
+            let english = message.text.replace('\n', "\\n");
+            let translation = self.translations.get(message.id);
+
+            text.push_str(std::format!("# {english}\n").as_str());
+            match translation {
+                Some(translation) => text.push_str(
+                    std::format!("{} = {}\n\n", message.id, translation.replace('\n', "\\n"))
+                        .as_str(),
+                ),
+                None => text.push_str(std::format!("# {} =\n\n", message.id).as_str()),
+            }
+        }
+
+        text
+    }
+}
+
+/// Selects a locale per the values of the `LC_ALL`, `LC_MESSAGES`, and `LANG`
+/// variables, given in that order; the first that's set and non-empty applies.
+///
+/// # Arguments
+///
+/// * `values` - The values of the variables.
+pub(crate) fn messages_locale<S: AsRef<str>>(values: [Option<S>; 3]) -> Option<S> {
+    values
+        .into_iter()
+        .flatten()
+        .find(|value| !value.as_ref().is_empty())
+}
+
+fn substitute(text: &str, args: &[(&str, &dyn Display)]) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut remaining = text;
+
+    while let Some(start) = remaining.find('{') {
+        result.push_str(&remaining[..start]);
+        remaining = &remaining[start..];
+
+        let arg = remaining.find('}').and_then(|end| {
+            let name = &remaining[1..end];
+            args.iter()
+                .find(|(arg_name, _)| *arg_name == name)
+                .map(|(_, value)| (end, value))
+        });
+
+        if let Some((end, value)) = arg {
+            result.push_str(value.to_string().as_str());
+            remaining = &remaining[end + 1..];
+        } else {
+            result.push('{');
+            remaining = &remaining[1..];
+        }
+    }
+
+    result.push_str(remaining);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_messages() {
+        let catalog =
+            Catalog::parse("# comment\ncommand-not-found = {command}: Befehl nicht gefunden\n");
+
+        assert_eq!(
+            catalog.format(&COMMAND_NOT_FOUND, &[("command", &"foo")]),
+            "foo: Befehl nicht gefunden"
+        );
+        assert_eq!(
+            catalog.format(&HELP_NO_MATCHING_TOPICS, &[("pattern", &"x*")]),
+            "No help topics match 'x*'"
+        );
+        assert_eq!(substitute("{a} {b} {", &[("a", &1)]), "1 {b} {");
+    }
+
+    #[test]
+    fn bundled_catalogs() {
+        for (language, text) in BUNDLED_CATALOGS {
+            let catalog = Catalog::parse(text);
+            assert_eq!(catalog.to_catalog_text(), *text, "{language}");
+        }
+
+        assert!(Catalog::for_locale("de_DE.UTF-8").is_some());
+        assert!(Catalog::for_locale("C").is_none());
+        assert!(Catalog::for_locale("").is_none());
+    }
+
+    #[test]
+    fn locale_precedence() {
+        assert_eq!(messages_locale([None, Some("fr"), Some("de")]), Some("fr"));
+        assert_eq!(messages_locale([Some(""), None, Some("de")]), Some("de"));
+        assert_eq!(messages_locale::<&str>([None, None, None]), None);
+    }
+}
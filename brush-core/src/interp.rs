@@ -10,6 +10,7 @@ use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 use crate::arithmetic::ExpandAndEvaluate;
+use crate::cancellation::CancellationToken;
 use crate::commands::{self, CommandArg, CommandSpawnResult};
 use crate::env::{EnvironmentLookup, EnvironmentScope};
 use crate::openfiles::{OpenFile, OpenFiles};
@@ -17,7 +18,13 @@ use crate::shell::Shell;
 use crate::variables::{
     ArrayLiteral, ShellValue, ShellValueLiteral, ShellValueUnsetType, ShellVariable,
 };
-use crate::{error, expansion, extendedtests, jobs, openfiles, processes, sys, timing, traps};
+use crate::{
+    audit, commandresolution, error, expansion, extendedtests, jobs, openfiles, processes, sys,
+    timing, traps,
+};
+
+/// Exit code of a command interrupted by SIGINT (i.e., 128 + SIGINT).
+pub(crate) const INTERRUPTED_EXIT_CODE: u8 = 130;
 
 /// Encapsulates the result of executing a command.
 #[derive(Debug, Default)]
@@ -32,6 +39,9 @@ pub struct ExecutionResult {
     pub break_loop: Option<u8>,
     /// If the command was executed in a loop, this is the number of levels to continue.
     pub continue_loop: Option<u8>,
+    /// Whether the command was a process terminated by SIGINT (e.g., because the user
+    /// pressed Ctrl+C), as opposed to one that merely exited with the same status.
+    pub killed_by_sigint: bool,
 }
 
 impl From<processes::ProcessWaitResult> for ExecutionResult {
@@ -53,7 +63,11 @@ impl From<std::process::Output> for ExecutionResult {
         #[cfg(unix)]
         if let Some(signal) = output.status.signal() {
             #[allow(clippy::cast_sign_loss)]
-            return Self::new((signal & 0xFF) as u8 + 128);
+            return ExecutionResult {
+                exit_code: (signal & 0xFF) as u8 + 128,
+                killed_by_sigint: signal == nix::sys::signal::Signal::SIGINT as i32,
+                ..ExecutionResult::default()
+            };
         }
 
         tracing::error!("unhandled process exit");
@@ -114,6 +128,9 @@ pub struct ExecutionParameters {
     pub open_files: openfiles::OpenFiles,
     /// Policy for how to manage spawned external processes.
     pub process_group_policy: ProcessGroupPolicy,
+    /// Token checked to see whether execution should stop early (e.g., because the user
+    /// pressed Ctrl+C).
+    pub cancellation_token: CancellationToken,
 }
 
 #[derive(Clone, Debug, Default)]
@@ -192,8 +209,13 @@ impl Execute for ast::CompoundList {
                 result = ao_list.execute(shell, params).await?;
             }
 
-            // Check for early return.
-            if result.return_from_function_or_script {
+            // Report any background jobs that finished in the meantime, if requested.
+            if shell.options.interactive {
+                shell.report_job_state_changes_if_notifying()?;
+            }
+
+            // Check for exit/early return.
+            if result.exit_shell || result.return_from_function_or_script {
                 break;
             }
 
@@ -215,16 +237,25 @@ fn spawn_ao_list_in_task<'a>(
 ) -> &'a jobs::Job {
     // Clone the inputs.
     let mut cloned_shell = shell.clone();
-    let cloned_params = params.clone();
+    let mut cloned_params = params.clone();
     let cloned_ao_list = ao_list.clone();
 
     // Mark the child shell as not interactive; we don't want it messing with the terminal too much.
     cloned_shell.options.interactive = false;
 
+    // Background jobs aren't interrupted along with the foreground.
+    cloned_shell.cancellation_token = CancellationToken::default();
+    cloned_params.cancellation_token = cloned_shell.cancellation_token.clone();
+
+    // Flag the job's completion to the job monitor, so it can be reported promptly.
+    let monitor = shell.jobs.monitor();
+
     let join_handle = tokio::spawn(async move {
-        cloned_ao_list
+        let result = cloned_ao_list
             .execute(&mut cloned_shell, &cloned_params)
-            .await
+            .await;
+        monitor.notify();
+        result
     });
 
     let job = shell.jobs.add_as_current(jobs::Job::new(
@@ -243,9 +274,14 @@ impl Execute for ast::AndOrList {
         shell: &mut Shell,
         params: &ExecutionParameters,
     ) -> Result<ExecutionResult, error::Error> {
-        let mut result = self.first.execute(shell, params).await?;
+        // N.B. `set -e` is ignored for all but the last pipeline in the list.
+        let mut result = if self.additional.is_empty() {
+            self.first.execute(shell, params).await?
+        } else {
+            execute_ignoring_errexit(&self.first, shell, params).await?
+        };
 
-        for next_ao in &self.additional {
+        for (i, next_ao) in self.additional.iter().enumerate() {
             // Check for exit/return
             if result.exit_shell || result.return_from_function_or_script {
                 break;
@@ -274,13 +310,31 @@ impl Execute for ast::AndOrList {
                 continue;
             }
 
-            result = pipeline.execute(shell, params).await?;
+            result = if i + 1 == self.additional.len() {
+                pipeline.execute(shell, params).await?
+            } else {
+                execute_ignoring_errexit(pipeline, shell, params).await?
+            };
         }
 
         Ok(result)
     }
 }
 
+/// Executes the given command with `set -e` ignored for its duration, as is done for
+/// (e.g.) `if` conditions and all but the last pipeline in an and-or list.
+async fn execute_ignoring_errexit<E: Execute + Sync + ?Sized>(
+    command: &E,
+    shell: &mut Shell,
+    params: &ExecutionParameters,
+) -> Result<ExecutionResult, error::Error> {
+    shell.errexit_ignore_depth += 1;
+    let result = command.execute(shell, params).await;
+    shell.errexit_ignore_depth -= 1;
+
+    result
+}
+
 #[async_trait::async_trait]
 impl Execute for ast::Pipeline {
     async fn execute(
@@ -295,16 +349,37 @@ impl Execute for ast::Pipeline {
             .then(timing::start_timing)
             .transpose()?;
 
+        let line_number = u32::try_from(self.line).unwrap_or(0);
+        shell.set_current_line_number(shell.line_number_offset.saturating_add(line_number))?;
+
+        // N.B. `set -e` is ignored while executing a pipeline whose status is inverted.
+        if self.bang {
+            shell.errexit_ignore_depth += 1;
+        }
+
         // Spawn all the processes required for the pipeline, connecting outputs/inputs with pipes
-        // as needed.
-        let spawn_results = spawn_pipeline_processes(self, shell, params).await?;
+        // as needed; then wait for them.
+        let result = match spawn_pipeline_processes(self, shell, params).await {
+            Ok(spawn_results) => wait_for_pipeline_processes(self, spawn_results, shell).await,
+            Err(e) => Err(e),
+        };
+
+        if self.bang {
+            shell.errexit_ignore_depth -= 1;
+        }
 
-        // Wait for the processes.
-        let mut result = wait_for_pipeline_processes(self, spawn_results, shell).await?;
+        let mut result = result?;
 
         // Invert the exit code if requested.
         if self.bang {
             result.exit_code = if result.exit_code == 0 { 1 } else { 0 };
+        } else if !result.is_success()
+            && !result.return_from_function_or_script
+            && errexit_applies_to(self, shell)
+        {
+            // With `set -e`, the failure means we need to exit. (A `return` is checked by
+            // the function's caller instead.)
+            result.exit_shell = true;
         }
 
         shell.last_exit_status = result.exit_code;
@@ -341,6 +416,23 @@ impl Execute for ast::Pipeline {
     }
 }
 
+/// Checks whether a failure of the given pipeline should cause the shell to exit (i.e.,
+/// `set -e` is enabled and not being ignored). Compound commands like `if` and loops are
+/// exempt; the commands inside them are checked individually.
+fn errexit_applies_to(pipeline: &ast::Pipeline, shell: &Shell) -> bool {
+    if !shell.options.exit_on_nonzero_command_exit || shell.errexit_ignore_depth > 0 {
+        return false;
+    }
+
+    match pipeline.seq.as_slice() {
+        [ast::Command::Compound(compound, _)] => matches!(
+            compound,
+            ast::CompoundCommand::Subshell(_) | ast::CompoundCommand::Arithmetic(_)
+        ),
+        _ => true,
+    }
+}
+
 async fn spawn_pipeline_processes(
     pipeline: &ast::Pipeline,
     shell: &mut Shell,
@@ -408,17 +500,44 @@ async fn wait_for_pipeline_processes(
 ) -> Result<ExecutionResult, error::Error> {
     let mut result = ExecutionResult::success();
     let mut stopped_children = vec![];
+    let mut last_was_process = false;
+    let mut any_was_process = false;
+
+    while let Some(mut child) = process_spawn_results.pop_front() {
+        last_was_process = matches!(child, commands::CommandSpawnResult::SpawnedProcess(_));
+        any_was_process |= last_was_process;
+        let (stats_baseline, audit_record) = match &mut child {
+            commands::CommandSpawnResult::SpawnedProcess(process) => (
+                process
+                    .take_stats_baseline()
+                    .map(|baseline| (process.pid(), baseline)),
+                process.take_audit_record(),
+            ),
+            _ => (None, None),
+        };
 
-    while let Some(child) = process_spawn_results.pop_front() {
         match child.wait(!stopped_children.is_empty()).await? {
             commands::CommandWaitResult::CommandCompleted(current_result) => {
                 result = current_result;
                 shell.last_exit_status = result.exit_code;
+
+                if let Some((pid, baseline)) = stats_baseline {
+                    baseline.finish(pid, result.exit_code)?.record(shell)?;
+                }
+                if let Some(audit_record) = audit_record {
+                    audit_record.complete(shell, result.exit_code);
+                }
             }
             commands::CommandWaitResult::CommandStopped(current_result, child) => {
                 result = current_result;
                 shell.last_exit_status = result.exit_code;
 
+                // N.B. A stopped command is audited with the status it stopped with; it's
+                // not audited again if it's later resumed.
+                if let Some(audit_record) = audit_record {
+                    audit_record.complete(shell, result.exit_code);
+                }
+
                 stopped_children.push(jobs::JobTask::External(child));
             }
         }
@@ -426,6 +545,19 @@ async fn wait_for_pipeline_processes(
 
     if shell.options.interactive {
         sys::terminal::move_self_to_foreground()?;
+
+        // N.B. When job control is in effect, Ctrl+C is only delivered to the foreground
+        // process, so we infer it from how the process exited; as in bash, if it was killed
+        // by SIGINT, it interrupts whatever the shell is running too (e.g., a loop the
+        // process was run from). A process that merely exits with status 130 doesn't.
+        if last_was_process && result.killed_by_sigint {
+            shell.cancellation_token.cancel();
+        }
+    }
+
+    // With `checkwinsize`, pick up any change the command made to the terminal's size.
+    if any_was_process && shell.options.check_window_size_after_external_commands {
+        shell.update_window_size_vars()?;
     }
 
     // If there were stopped jobs, then encapsulate the pipeline as a managed job and hand it
@@ -523,9 +655,11 @@ impl Execute for ast::CompoundCommand {
                 g.execute(shell, params).await
             }
             ast::CompoundCommand::Subshell(ast::SubshellCommand(s)) => {
-                // Clone off a new subshell, and run the body of the subshell there.
+                // Clone off a new subshell, and run the body of the subshell there. Exiting
+                // (or returning from) the subshell only affects the subshell.
                 let mut subshell = shell.clone();
-                s.execute(&mut subshell, params).await
+                let result = s.execute(&mut subshell, params).await?;
+                Ok(ExecutionResult::new(result.exit_code))
             }
             ast::CompoundCommand::ForClause(f) => f.execute(shell, params).await,
             ast::CompoundCommand::CaseClause(c) => c.execute(shell, params).await,
@@ -560,12 +694,16 @@ impl Execute for ast::ForClauseCommand {
             }
 
             for value in expanded_values {
+                params.cancellation_token.check()?;
+
                 if shell.options.print_commands_and_arguments {
-                    shell.trace_command(std::format!(
-                        "for {} in {}",
-                        self.variable_name,
-                        unexpanded_values.iter().join(" ")
-                    ))?;
+                    shell
+                        .trace_command(std::format!(
+                            "for {} in {}",
+                            self.variable_name,
+                            unexpanded_values.iter().join(" ")
+                        ))
+                        .await?;
                 }
 
                 // Update the variable.
@@ -578,7 +716,7 @@ impl Execute for ast::ForClauseCommand {
                 )?;
 
                 result = self.body.0.execute(shell, params).await?;
-                if result.return_from_function_or_script {
+                if result.exit_shell || result.return_from_function_or_script {
                     break;
                 }
 
@@ -615,7 +753,9 @@ impl Execute for ast::CaseClauseCommand {
         // N.B. One would think it makes sense to trace the expanded value being switched
         // on, but that's not it.
         if shell.options.print_commands_and_arguments {
-            shell.trace_command(std::format!("case {} in", &self.value))?;
+            shell
+                .trace_command(std::format!("case {} in", &self.value))
+                .await?;
         }
 
         let expanded_value = expansion::basic_expand_word(shell, &self.value).await?;
@@ -650,6 +790,10 @@ impl Execute for ast::CaseClauseCommand {
                 ExecutionResult::success()
             };
 
+            if result.exit_shell || result.return_from_function_or_script {
+                break;
+            }
+
             match case.post_action {
                 ast::CaseItemPostAction::ExitCase => break,
                 ast::CaseItemPostAction::UnconditionallyExecuteNextCaseItem => {
@@ -672,7 +816,8 @@ impl Execute for ast::IfClauseCommand {
         shell: &mut Shell,
         params: &ExecutionParameters,
     ) -> Result<ExecutionResult, error::Error> {
-        let condition = self.condition.execute(shell, params).await?;
+        // N.B. `set -e` is ignored while evaluating conditions.
+        let condition = execute_ignoring_errexit(&self.condition, shell, params).await?;
 
         if condition.is_success() {
             return self.then.execute(shell, params).await;
@@ -682,7 +827,8 @@ impl Execute for ast::IfClauseCommand {
             for else_clause in elses {
                 match &else_clause.condition {
                     Some(else_condition) => {
-                        let else_condition_result = else_condition.execute(shell, params).await?;
+                        let else_condition_result =
+                            execute_ignoring_errexit(else_condition, shell, params).await?;
                         if else_condition_result.is_success() {
                             return else_clause.body.execute(shell, params).await;
                         }
@@ -718,18 +864,22 @@ impl Execute for (WhileOrUntil, &ast::WhileOrUntilClauseCommand) {
         let mut result = ExecutionResult::success();
 
         loop {
-            let condition_result = test_condition.execute(shell, params).await?;
+            params.cancellation_token.check()?;
 
-            if condition_result.is_success() != is_while {
+            // N.B. `set -e` is ignored while evaluating the loop's condition.
+            let condition_result = execute_ignoring_errexit(test_condition, shell, params).await?;
+
+            if condition_result.exit_shell || condition_result.return_from_function_or_script {
+                result = condition_result;
                 break;
             }
 
-            if condition_result.return_from_function_or_script {
+            if condition_result.is_success() != is_while {
                 break;
             }
 
             result = body.0.execute(shell, params).await?;
-            if result.return_from_function_or_script {
+            if result.exit_shell || result.return_from_function_or_script {
                 break;
             }
 
@@ -787,6 +937,8 @@ impl Execute for ast::ArithmeticForClauseCommand {
         }
 
         loop {
+            params.cancellation_token.check()?;
+
             if let Some(condition) = &self.condition {
                 if condition.eval(shell, true).await? == 0 {
                     break;
@@ -794,7 +946,7 @@ impl Execute for ast::ArithmeticForClauseCommand {
             }
 
             result = self.body.0.execute(shell, params).await?;
-            if result.return_from_function_or_script {
+            if result.exit_shell || result.return_from_function_or_script {
                 break;
             }
 
@@ -831,10 +983,27 @@ impl Execute for ast::FunctionDefinition {
         shell: &mut Shell,
         _params: &ExecutionParameters,
     ) -> Result<ExecutionResult, error::Error> {
+        if !brush_parser::is_valid_function_name(self.fname.as_str(), shell.options.posix_mode) {
+            writeln!(shell.stderr(), "`{}': not a valid identifier", self.fname)?;
+
+            // N.B. In POSIX mode, this is fatal to non-interactive shells.
+            let fatal = shell.options.posix_mode && !shell.options.interactive;
+            let mut result = ExecutionResult::new(if fatal { 2 } else { 1 });
+            result.exit_shell = fatal;
+            shell.last_exit_status = result.exit_code;
+
+            return Ok(result);
+        }
+
         shell
             .funcs
             .update(self.fname.clone(), Arc::new(self.clone()));
 
+        let line_number_offset = shell.line_number_offset;
+        if let Some(registration) = shell.funcs.get_mut(self.fname.as_str()) {
+            registration.set_line_number_offset(line_number_offset);
+        }
+
         let result = ExecutionResult::success();
         shell.last_exit_status = result.exit_code;
 
@@ -849,6 +1018,8 @@ impl ExecuteInPipeline for ast::SimpleCommand {
         &self,
         context: &mut PipelineExecutionContext,
     ) -> Result<CommandSpawnResult, error::Error> {
+        context.params.cancellation_token.check()?;
+
         let default_prefix = ast::CommandPrefix::default();
         let prefix_items = self.prefix.as_ref().unwrap_or(&default_prefix);
 
@@ -885,12 +1056,9 @@ impl ExecuteInPipeline for ast::SimpleCommand {
                     }
                 }
                 CommandPrefixOrSuffixItem::ProcessSubstitution(kind, subshell_command) => {
-                    let (installed_fd_num, substitution_file) = setup_process_substitution(
-                        &mut params.open_files,
-                        context.shell,
-                        kind,
-                        subshell_command,
-                    )?;
+                    let installed_fd_num = find_process_substitution_fd(&params.open_files)?;
+                    let substitution_file =
+                        setup_process_substitution(context.shell, kind, subshell_command)?;
 
                     params
                         .open_files
@@ -928,35 +1096,18 @@ impl ExecuteInPipeline for ast::SimpleCommand {
                     }
                 }
                 CommandPrefixOrSuffixItem::Word(arg) => {
-                    let mut next_args =
+                    let next_args =
                         expansion::full_expand_and_split_word(context.shell, arg).await?;
 
                     if args.is_empty() {
                         if let Some(cmd_name) = next_args.first() {
-                            if let Some(alias_value) = context.shell.aliases.get(cmd_name.as_str())
-                            {
-                                //
-                                // TODO: This is a total hack; aliases are supposed to be handled
-                                // much earlier in the process.
-                                //
-                                let mut alias_pieces: Vec<_> = alias_value
-                                    .split_ascii_whitespace()
-                                    .map(|i| i.to_owned())
-                                    .collect();
-
-                                next_args.remove(0);
-                                alias_pieces.append(&mut next_args);
-
-                                next_args = alias_pieces;
-                            }
-
                             // Check if we're going to be invoking a special declaration builtin.
                             // That will change how we parse and process
                             // args.
                             if context
                                 .shell
                                 .builtins
-                                .get(next_args[0].as_str())
+                                .get(cmd_name.as_str())
                                 .is_some_and(|r| r.declaration_builtin)
                             {
                                 invoking_declaration_builtin = true;
@@ -972,26 +1123,45 @@ impl ExecuteInPipeline for ast::SimpleCommand {
 
         // If we have a command, then execute it.
         if let Some(CommandArg::String(cmd_name)) = args.first().cloned() {
+            // In POSIX mode, assignments preceding a special builtin affect the shell's own
+            // environment, and so persist after the builtin completes. Otherwise, they're only
+            // visible for the duration of the command (including to any function it invokes).
+            let assignments_persist = context.shell.options.posix_mode
+                && commandresolution::is_special_builtin(context.shell, cmd_name.as_str());
+
             // Push a new ephemeral environment scope for the duration of the command. We'll
             // set command-scoped variable assignments after doing so, and revert them before
             // returning.
             context.shell.env.push_scope(EnvironmentScope::Command);
             for assignment in &assignments {
-                // Ensure it's tagged as exported and created in the command scope.
-                apply_assignment(
-                    assignment,
-                    context.shell,
-                    true,
-                    Some(EnvironmentScope::Command),
-                    EnvironmentScope::Command,
-                )
-                .await?;
+                if assignments_persist {
+                    // N.B. As in bash, the variables are also exported.
+                    apply_assignment(
+                        assignment,
+                        context.shell,
+                        true,
+                        None,
+                        EnvironmentScope::Global,
+                    )
+                    .await?;
+                } else {
+                    // Ensure it's tagged as exported and created in the command scope.
+                    apply_assignment(
+                        assignment,
+                        context.shell,
+                        true,
+                        Some(EnvironmentScope::Command),
+                        EnvironmentScope::Command,
+                    )
+                    .await?;
+                }
             }
 
             if context.shell.options.print_commands_and_arguments {
                 context
                     .shell
-                    .trace_command(args.iter().map(|arg| arg.quote_for_tracing()).join(" "))?;
+                    .trace_command(args.iter().map(|arg| arg.quote_for_tracing()).join(" "))
+                    .await?;
             }
 
             // TODO: This is adding more complexity here; should be factored out into an appropriate
@@ -1009,6 +1179,7 @@ impl ExecuteInPipeline for ast::SimpleCommand {
                     let handler_params = ExecutionParameters {
                         open_files: params.open_files.clone(),
                         process_group_policy: ProcessGroupPolicy::SameProcessGroup,
+                        cancellation_token: params.cancellation_token.clone(),
                     };
 
                     let full_cmd = args.iter().map(|arg| arg.to_string()).join(" ");
@@ -1027,13 +1198,21 @@ impl ExecuteInPipeline for ast::SimpleCommand {
                     // TODO: Discard result?
                     let _ = context
                         .shell
-                        .run_string(debug_trap_handler, &handler_params)
+                        .run_nested_string(debug_trap_handler, &handler_params)
                         .await?;
 
                     context.shell.traps.handler_depth -= 1;
                 }
             }
 
+            // Capture the details of the command for auditing, if requested.
+            let audit_record = (!context.shell.audit_sinks.is_empty()).then(|| {
+                audit::PendingAuditRecord::capture(
+                    context.shell,
+                    args.iter().map(|arg| arg.to_string()).collect(),
+                )
+            });
+
             let cmd_context = commands::ExecutionContext {
                 shell: context.shell,
                 command_name: cmd_name,
@@ -1041,7 +1220,7 @@ impl ExecuteInPipeline for ast::SimpleCommand {
             };
 
             // Execute.
-            let execution_result = commands::execute(
+            let mut execution_result = commands::execute(
                 cmd_context,
                 &mut context.process_group_id,
                 args,
@@ -1049,6 +1228,24 @@ impl ExecuteInPipeline for ast::SimpleCommand {
             )
             .await;
 
+            // Complete the audit record, unless we need to wait for the command to exit.
+            if let Some(audit_record) = audit_record {
+                match &mut execution_result {
+                    Ok(CommandSpawnResult::SpawnedProcess(child)) => {
+                        child.set_audit_record(audit_record);
+                    }
+                    Ok(
+                        CommandSpawnResult::ImmediateExit(exit_code)
+                        | CommandSpawnResult::ExitShell(exit_code)
+                        | CommandSpawnResult::ReturnFromFunctionOrScript(exit_code),
+                    ) => audit_record.complete(context.shell, *exit_code),
+                    Ok(CommandSpawnResult::BreakLoop(_) | CommandSpawnResult::ContinueLoop(_)) => {
+                        audit_record.complete(context.shell, 0);
+                    }
+                    Err(_) => audit_record.complete(context.shell, 1),
+                }
+            }
+
             // Pop off that ephemeral environment scope.
             // TODO: jobs: do we need to move self back to foreground on error here?
             context.shell.env.pop_scope(EnvironmentScope::Command)?;
@@ -1117,7 +1314,7 @@ async fn expand_assignment_value(
 ) -> Result<ast::AssignmentValue, error::Error> {
     let expanded = match value {
         ast::AssignmentValue::Scalar(s) => {
-            let expanded_word = expansion::basic_expand_word(shell, s).await?;
+            let expanded_word = expansion::basic_expand_assignment_value(shell, s).await?;
             ast::AssignmentValue::Scalar(ast::Word {
                 value: expanded_word,
             })
@@ -1168,10 +1365,19 @@ async fn apply_assignment(
         }
     };
 
+    // Assignments through a nameref apply to the variable it references.
+    let variable_name = shell.env.resolve_nameref(variable_name);
+
+    // As in bash, any assignment to PATH discards remembered command locations, even if the
+    // value is unchanged.
+    if variable_name.as_ref() == "PATH" {
+        shell.program_location_cache.reset();
+    }
+
     // Expand the values.
     let new_value = match &assignment.value {
         ast::AssignmentValue::Scalar(unexpanded_value) => {
-            let value = expansion::basic_expand_word(shell, unexpanded_value).await?;
+            let value = expansion::basic_expand_assignment_value(shell, unexpanded_value).await?;
             ShellValueLiteral::Scalar(value)
         }
         ast::AssignmentValue::Array(unexpanded_values) => {
@@ -1201,13 +1407,26 @@ async fn apply_assignment(
 
     if shell.options.print_commands_and_arguments {
         let op = if assignment.append { "+=" } else { "=" };
-        shell.trace_command(std::format!("{}{op}{new_value}", assignment.name))?;
+        shell
+            .trace_command(std::format!("{}{op}{new_value}", assignment.name))
+            .await?;
     }
 
+    // Values assigned to integer variables are evaluated arithmetically.
+    let new_value = if shell
+        .env
+        .get(variable_name.as_ref())
+        .is_some_and(|(_, var)| var.is_treated_as_integer())
+    {
+        crate::arithmetic::eval_integer_assignment(shell, new_value)?
+    } else {
+        new_value
+    };
+
     // See if we need to eval an array index.
     if let Some(idx) = &array_index {
         let will_be_indexed_array = if let Some((_, existing_value)) =
-            shell.env.get(variable_name.as_str())
+            shell.env.get(variable_name.as_ref())
         {
             matches!(
                 existing_value.value(),
@@ -1228,7 +1447,7 @@ async fn apply_assignment(
     }
 
     // See if we can find an existing value associated with the variable.
-    if let Some((existing_value_scope, existing_value)) = shell.env.get_mut(variable_name.as_str())
+    if let Some((existing_value_scope, existing_value)) = shell.env.get_mut(variable_name.as_ref())
     {
         if required_scope.is_none() || Some(existing_value_scope) == required_scope {
             if let Some(array_index) = array_index {
@@ -1323,6 +1542,8 @@ pub(crate) async fn setup_redirect(
             let expanded_file_path: PathBuf =
                 shell.get_absolute_path(Path::new(expanded_fields.remove(0).as_str()));
 
+            shell.check_write_allowed(expanded_file_path.as_path())?;
+
             let opened_file = std::fs::File::options()
                 .create(true)
                 .write(true)
@@ -1410,6 +1631,13 @@ pub(crate) async fn setup_redirect(
 
                     fd_num = specified_fd_num.unwrap_or(default_fd_if_unspecified);
 
+                    if !matches!(
+                        kind,
+                        ast::IoFileRedirectKind::Read | ast::IoFileRedirectKind::DuplicateInput
+                    ) {
+                        shell.check_write_allowed(expanded_file_path.as_path())?;
+                    }
+
                     let opened_file =
                         options.open(expanded_file_path.as_path()).map_err(|err| {
                             error::Error::RedirectionFailure(
@@ -1444,15 +1672,12 @@ pub(crate) async fn setup_redirect(
                         | ast::IoFileRedirectKind::Append
                         | ast::IoFileRedirectKind::ReadAndWrite
                         | ast::IoFileRedirectKind::Clobber => {
-                            let (substitution_fd, substitution_file) = setup_process_substitution(
-                                open_files,
-                                shell,
-                                substitution_kind,
-                                subshell_cmd,
-                            )?;
-
-                            target_file = substitution_file.try_dup()?;
-                            open_files.files.insert(substitution_fd, substitution_file);
+                            // N.B. Unlike a process substitution used as an argument, there's
+                            // no need to also install the file at its own fd; doing so would
+                            // keep an extra copy of the pipe open (and inherited by child
+                            // processes) until the whole redirected command completes.
+                            target_file =
+                                setup_process_substitution(shell, substitution_kind, subshell_cmd)?;
 
                             fd_num = specified_fd_num
                                 .unwrap_or_else(|| get_default_fd_for_redirect_kind(kind));
@@ -1508,12 +1733,15 @@ fn get_default_fd_for_redirect_kind(kind: &ast::IoFileRedirectKind) -> u32 {
     }
 }
 
+/// Spawns the given command in a subshell, connected via a pipe to the returned file. The
+/// subshell runs asynchronously and its end of the pipe is closed when it completes; the
+/// caller owns the other end, which should be dropped when the command consuming it
+/// completes.
 fn setup_process_substitution(
-    open_files: &mut OpenFiles,
     shell: &mut Shell,
     kind: &ast::ProcessSubstitutionKind,
     subshell_cmd: &ast::SubshellCommand,
-) -> Result<(u32, OpenFile), error::Error> {
+) -> Result<OpenFile, error::Error> {
     // TODO: Don't execute synchronously!
     // Execute in a subshell.
     let mut subshell = shell.clone();
@@ -1539,8 +1767,9 @@ fn setup_process_substitution(
     };
 
     let exec_params = ExecutionParameters {
-        open_files: subshell.open_files.clone(),
+        open_files: subshell.open_files.try_clone()?,
         process_group_policy: ProcessGroupPolicy::SameProcessGroup,
+        cancellation_token: subshell.cancellation_token.clone(),
     };
 
     // Asynchronously spawn off the subshell; we intentionally don't block on its
@@ -1551,6 +1780,10 @@ fn setup_process_substitution(
         let _ = subshell_cmd.0.execute(&mut subshell, &exec_params).await;
     });
 
+    Ok(target_file)
+}
+
+fn find_process_substitution_fd(open_files: &OpenFiles) -> Result<u32, error::Error> {
     // Starting at 63 (a.k.a. 64-1)--and decrementing--look for an
     // available fd.
     let mut candidate_fd_num = 63;
@@ -1561,7 +1794,7 @@ fn setup_process_substitution(
         }
     }
 
-    Ok((candidate_fd_num, target_file))
+    Ok(candidate_fd_num)
 }
 
 #[allow(unused_variables)]
@@ -1582,3 +1815,30 @@ fn setup_open_file_with_contents(contents: &str) -> Result<OpenFile, error::Erro
 
     Ok(OpenFile::PipeReader(reader))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn sigint_termination_is_distinguished_from_exit_status() {
+        let result_from = |raw_status| {
+            ExecutionResult::from(std::process::Output {
+                status: std::process::ExitStatus::from_raw(raw_status),
+                stdout: vec![],
+                stderr: vec![],
+            })
+        };
+
+        // Killed by SIGINT.
+        let result = result_from(nix::sys::signal::Signal::SIGINT as i32);
+        assert_eq!(result.exit_code, INTERRUPTED_EXIT_CODE);
+        assert!(result.killed_by_sigint);
+
+        // Exited with status 130.
+        let result = result_from(i32::from(INTERRUPTED_EXIT_CODE) << 8);
+        assert_eq!(result.exit_code, INTERRUPTED_EXIT_CODE);
+        assert!(!result.killed_by_sigint);
+    }
+}
@@ -1,5 +1,7 @@
 use std::collections::VecDeque;
 use std::fmt::Display;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 
 use futures::FutureExt;
 
@@ -18,6 +20,68 @@ pub(crate) type JobResult = (Job, Result<ExecutionResult, error::Error>);
 pub struct JobManager {
     /// The jobs that are currently managed by the shell.
     pub jobs: Vec<Job>,
+
+    /// Monitors the managed jobs for state changes.
+    monitor: JobMonitor,
+}
+
+/// Tracks whether any job managed by a [`JobManager`] may have changed state since the
+/// manager was last polled. Cloned handles share the same state, allowing tasks to flag
+/// changes as they happen.
+#[derive(Clone, Default)]
+pub struct JobMonitor {
+    /// Whether a state change has been flagged but not yet observed.
+    pending: Arc<AtomicBool>,
+    /// Background task that flags state changes of external processes, if started.
+    #[cfg(unix)]
+    signal_task: Option<Arc<tokio::task::JoinHandle<()>>>,
+}
+
+impl JobMonitor {
+    /// Flags that a job may have changed state.
+    pub fn notify(&self) {
+        self.pending.store(true, Ordering::SeqCst);
+    }
+
+    /// Returns whether a job may have changed state since the last call, clearing the
+    /// flag.
+    pub fn take_pending_changes(&self) -> bool {
+        self.pending.swap(false, Ordering::SeqCst)
+    }
+
+    /// Starts the background task that watches for external processes changing state,
+    /// if it's not already running.
+    fn ensure_started(&mut self) {
+        #[cfg(unix)]
+        if self.signal_task.is_none() {
+            use tokio::signal::unix::{signal, SignalKind};
+
+            let Ok(mut sigchld) = signal(SignalKind::child()) else {
+                tracing::debug!(target: trace_categories::JOBS, "failed to listen for SIGCHLD");
+                return;
+            };
+
+            let monitor = self.clone();
+            self.signal_task = Some(Arc::new(tokio::spawn(async move {
+                while sigchld.recv().await.is_some() {
+                    monitor.notify();
+                }
+            })));
+        }
+    }
+}
+
+#[cfg(unix)]
+impl Drop for JobMonitor {
+    fn drop(&mut self) {
+        // N.B. The signal task's own handle doesn't share the task, so we stop it once the
+        // last handle that does goes away.
+        if let Some(task) = &self.signal_task {
+            if Arc::strong_count(task) == 1 {
+                task.abort();
+            }
+        }
+    }
 }
 
 /// Represents a task that is part of a job.
@@ -73,6 +137,11 @@ impl JobManager {
         Self::default()
     }
 
+    /// Returns a handle to the monitor tracking state changes of the managed jobs.
+    pub fn monitor(&self) -> JobMonitor {
+        self.monitor.clone()
+    }
+
     /// Adds a job to the job manager and marks it as the current job;
     /// returns an immutable reference to the job.
     ///
@@ -87,6 +156,8 @@ impl JobManager {
             }
         }
 
+        self.monitor.ensure_started();
+
         let id = self.jobs.len() + 1;
         job.id = id;
         job.annotation = JobAnnotation::Current;
@@ -262,10 +333,10 @@ impl Display for Job {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "[{}]{:3}{}\t{}",
+            "[{}]{:3}{:24}{}",
             self.id,
             self.annotation.to_string(),
-            self.state,
+            self.state.to_string(),
             self.command_line
         )
     }
@@ -293,6 +364,28 @@ impl Job {
         }
     }
 
+    /// Returns a string reporting the completion of the job, in the style of the
+    /// notifications bash displays when a background job finishes.
+    ///
+    /// # Arguments
+    ///
+    /// * `exit_code` - The exit code the job completed with.
+    pub fn to_completion_string(&self, exit_code: u8) -> String {
+        let status = if exit_code == 0 {
+            JobState::Done.to_string()
+        } else {
+            std::format!("Exit {exit_code}")
+        };
+
+        std::format!(
+            "[{}]{:3}{:24}{}",
+            self.id,
+            self.annotation.to_string(),
+            status,
+            self.command_line
+        )
+    }
+
     /// Returns a pid-style string for the job.
     pub fn to_pid_style_string(&self) -> String {
         let display_pid = self
@@ -418,6 +511,32 @@ impl Job {
         }
     }
 
+    /// Hangs up the job (i.e., sends it `SIGHUP`), continuing it first if it's stopped so
+    /// that it can act on the signal.
+    pub fn hang_up(&mut self) -> Result<(), error::Error> {
+        let Some(pgid) = self.get_process_group_id() else {
+            return Err(error::Error::FailedToSendSignal);
+        };
+
+        sys::signal::hang_up_process(pgid)?;
+        if matches!(self.state, JobState::Stopped) {
+            sys::signal::continue_process(pgid)?;
+            self.state = JobState::Running;
+        }
+
+        Ok(())
+    }
+
+    /// Terminates the job, using the platform's default means of doing so (i.e., `SIGTERM`
+    /// on Unix-like platforms).
+    pub fn terminate(&mut self) -> Result<(), error::Error> {
+        if let Some(pid) = self.get_process_group_id() {
+            sys::signal::terminate_process(pid)
+        } else {
+            Err(error::Error::FailedToSendSignal)
+        }
+    }
+
     /// Tries to retrieve a "representative" pid for the job.
     pub fn get_representative_pid(&self) -> Option<sys::process::ProcessId> {
         for task in &self.tasks {
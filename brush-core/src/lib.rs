@@ -5,15 +5,25 @@
 
 pub mod completion;
 
+pub mod abbreviations;
 mod arithmetic;
+pub mod audit;
 pub mod builtins;
+mod cancellation;
+mod commandresolution;
 mod commands;
+mod commandstats;
+pub mod compat;
+mod containment;
+pub mod dirhistory;
 mod env;
 mod error;
 mod escape;
 mod expansion;
 mod extendedtests;
+pub mod frecency;
 mod functions;
+pub mod i18n;
 mod interp;
 mod jobs;
 mod keywords;
@@ -21,10 +31,14 @@ mod namedoptions;
 mod openfiles;
 mod options;
 mod pathcache;
+mod pathsearch;
 mod patterns;
 mod processes;
+mod profiling;
 mod prompt;
 mod regex;
+pub mod sandbox;
+pub mod savedstate;
 mod shell;
 mod sys;
 mod terminal;
@@ -34,9 +48,13 @@ mod trace_categories;
 mod traps;
 mod variables;
 
+pub use cancellation::CancellationToken;
 pub use commands::ExecutionContext;
-pub use error::Error;
+pub use containment::ProcessContainer;
+pub use error::{Error, SyntaxErrorFormatter};
 pub use interp::{ExecutionParameters, ExecutionResult};
-pub use shell::{CreateOptions, Shell};
+pub use profiling::FunctionProfiler;
+pub use shell::{CreateOptions, ProductInfo, Shell, ShellCheckpoint, DEFAULT_MAX_RECURSION_DEPTH};
+pub use sys::capabilities::Capability;
 pub use terminal::TerminalControl;
 pub use variables::{ShellValue, ShellVariable};
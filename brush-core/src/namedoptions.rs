@@ -324,7 +324,22 @@ lazy_static! {
             "posix",
             OptionDefinition::new(
                 |options| options.posix_mode,
-                |options, value| options.posix_mode = value
+                |options, value| {
+                    // N.B. Aliases are always expanded in POSIX mode; leaving it restores
+                    // alias expansion to how it was before POSIX mode was entered.
+                    if value {
+                        if !options.posix_mode {
+                            options.expand_aliases_outside_posix_mode =
+                                Some(options.expand_aliases);
+                        }
+                        options.expand_aliases = true;
+                    } else if let Some(expand_aliases) =
+                        options.expand_aliases_outside_posix_mode.take()
+                    {
+                        options.expand_aliases = expand_aliases;
+                    }
+                    options.posix_mode = value;
+                }
             )
         ),
         (
@@ -413,6 +428,13 @@ lazy_static! {
                 |options, value| options.save_multiline_cmds_in_history = value
             )
         ),
+        (
+            "command_stats",
+            OptionDefinition::new(
+                |options| options.record_command_stats,
+                |options, value| options.record_command_stats = value
+            )
+        ),
         (
             "compat31",
             OptionDefinition::new(
@@ -679,6 +701,20 @@ lazy_static! {
                 |options, value| options.expand_non_matching_patterns_to_null = value
             )
         ),
+        (
+            "parallel_cmdsubst",
+            OptionDefinition::new(
+                |options| options.parallel_command_substitutions,
+                |options, value| options.parallel_command_substitutions = value
+            )
+        ),
+        (
+            "path_cache",
+            OptionDefinition::new(
+                |options| options.cache_path_search,
+                |options, value| options.cache_path_search = value
+            )
+        ),
         (
             "progcomp",
             OptionDefinition::new(
@@ -707,6 +743,13 @@ lazy_static! {
                 |options, value| options.restricted_shell = value
             )
         ),
+        (
+            "rust_regex",
+            OptionDefinition::new(
+                |options| options.rust_regex_syntax,
+                |options, value| options.rust_regex_syntax = value
+            )
+        ),
         (
             "shift_verbose",
             OptionDefinition::new(
@@ -88,7 +88,8 @@ impl OpenFile {
         }
     }
 
-    pub(crate) fn is_term(&self) -> bool {
+    /// Returns whether the file is a terminal.
+    pub fn is_term(&self) -> bool {
         match self {
             OpenFile::Stdin => std::io::stdin().is_terminal(),
             OpenFile::Stdout => std::io::stdout().is_terminal(),
@@ -100,6 +101,19 @@ impl OpenFile {
         }
     }
 
+    /// Returns whether the file has input available to be read without blocking (including
+    /// the end of input). Files that aren't read from are always reported as ready.
+    pub(crate) fn has_pending_input(&self) -> Result<bool, error::Error> {
+        match self {
+            OpenFile::Stdin => sys::fs::has_pending_input(std::io::stdin()),
+            OpenFile::File(f) => sys::fs::has_pending_input(f),
+            OpenFile::PipeReader(r) => sys::fs::has_pending_input(r),
+            OpenFile::Stdout | OpenFile::Stderr | OpenFile::Null | OpenFile::PipeWriter(_) => {
+                Ok(true)
+            }
+        }
+    }
+
     pub(crate) fn get_term_attr(
         &self,
     ) -> Result<Option<sys::terminal::TerminalSettings>, error::Error> {
@@ -78,6 +78,8 @@ pub struct RuntimeOptions {
     pub check_window_size_after_external_commands: bool,
     /// 'cmdhist'
     pub save_multiline_cmds_in_history: bool,
+    /// `command_stats` (a brush extension)
+    pub record_command_stats: bool,
     /// 'compat31'
     pub compat31: bool,
     /// 'compat32'
@@ -154,6 +156,10 @@ pub struct RuntimeOptions {
     pub case_insensitive_conditionals: bool,
     /// 'nullglob'
     pub expand_non_matching_patterns_to_null: bool,
+    /// `parallel_cmdsubst` (a brush extension)
+    pub parallel_command_substitutions: bool,
+    /// `path_cache` (a brush extension)
+    pub cache_path_search: bool,
     /// 'progcomp'
     pub programmable_completion: bool,
     /// `progcomp_alias`
@@ -162,6 +168,8 @@ pub struct RuntimeOptions {
     pub expand_prompt_strings: bool,
     /// `restricted_shell`
     pub restricted_shell: bool,
+    /// `rust_regex` (a brush extension)
+    pub rust_regex_syntax: bool,
     /// `shift_verbose`
     pub shift_verbose: bool,
     /// `sourcepath`
@@ -175,10 +183,18 @@ pub struct RuntimeOptions {
     pub interactive: bool,
     /// Whether or not the shell is reading commands from standard input.
     pub read_commands_from_stdin: bool,
+    /// Whether or not the shell is running commands given as a string (i.e., with `-c`).
+    pub read_commands_from_command_string: bool,
     /// Whether or not the shell is in maximal `sh` compatibility mode.    
     pub sh_mode: bool,
+    /// Whether aliases were expanded before POSIX mode was entered, if it has been; restored
+    /// once POSIX mode is left.
+    pub expand_aliases_outside_posix_mode: Option<bool>,
     /// Maximum function call depth.
     pub max_function_call_depth: Option<usize>,
+    /// Maximum depth to which functions, `eval`, `source`, and command substitutions may be
+    /// nested.
+    pub max_recursion_depth: usize,
 }
 
 impl RuntimeOptions {
@@ -197,8 +213,14 @@ impl RuntimeOptions {
             enable_command_history: create_options.interactive,
             enable_job_control: create_options.interactive,
             read_commands_from_stdin: create_options.read_commands_from_stdin,
+            read_commands_from_command_string: create_options.read_commands_from_command_string,
             sh_mode: create_options.sh_mode,
             posix_mode: create_options.posix,
+            // N.B. Aliases are always expanded in POSIX mode, even in non-interactive shells.
+            expand_aliases: create_options.posix,
+            expand_aliases_outside_posix_mode: create_options
+                .posix
+                .then_some(create_options.interactive),
             print_commands_and_arguments: create_options.print_commands_and_arguments,
             print_shell_input_lines: create_options.verbose,
             remember_command_locations: true,
@@ -215,6 +237,9 @@ impl RuntimeOptions {
             programmable_completion: true,
             glob_ranges_use_c_locale: true,
             max_function_call_depth: create_options.max_function_call_depth,
+            max_recursion_depth: create_options
+                .max_recursion_depth
+                .unwrap_or(crate::shell::DEFAULT_MAX_RECURSION_DEPTH),
             ..Self::default()
         };
 
@@ -243,4 +268,28 @@ impl RuntimeOptions {
 
         options
     }
+
+    /// Returns the single-letter flags of the options that are currently enabled, as
+    /// reported by `$-`.
+    pub(crate) fn current_option_flags(&self) -> String {
+        let mut cs = vec![];
+
+        for (x, y) in crate::namedoptions::SET_OPTIONS.iter() {
+            if (y.getter)(self) {
+                cs.push(*x);
+            }
+        }
+
+        // N.B. `-c` can only be given when the shell is invoked, so it isn't among the
+        // options that may be changed with `set`.
+        if self.read_commands_from_command_string {
+            cs.push('c');
+        }
+
+        // Sort the flags in a way that matches what bash does: flags reflecting how the shell
+        // was invoked come last.
+        cs.sort_by_key(|c| (matches!(c, 'c' | 's'), c.is_ascii_uppercase(), *c));
+
+        cs.into_iter().collect()
+    }
 }
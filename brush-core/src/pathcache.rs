@@ -3,8 +3,19 @@ use std::path::PathBuf;
 /// A cache of paths associated with names.
 #[derive(Clone, Default)]
 pub struct PathCache {
-    /// The cache itself.
-    cache: std::collections::HashMap<String, PathBuf>,
+    /// The cache itself, in the order in which entries were added.
+    cache: indexmap::IndexMap<String, PathCacheEntry>,
+    /// The search path (i.e., `PATH`) the cached entries were found using, if known.
+    search_path: Option<String>,
+}
+
+/// An entry in a [`PathCache`].
+#[derive(Clone)]
+struct PathCacheEntry {
+    /// The path associated with the name.
+    path: PathBuf,
+    /// The number of times the entry has been used to locate a command to execute.
+    hits: usize,
 }
 
 impl PathCache {
@@ -13,22 +24,55 @@ impl PathCache {
         self.cache.clear();
     }
 
+    /// Returns whether the cache is empty.
+    pub fn is_empty(&self) -> bool {
+        self.cache.is_empty()
+    }
+
+    /// Clears all elements from the cache if they were found using a search path other than
+    /// the given one; in any case, associates the cache with the given search path.
+    ///
+    /// # Arguments
+    ///
+    /// * `search_path` - The current search path.
+    pub fn sync_search_path(&mut self, search_path: &str) {
+        if self.search_path.as_deref() != Some(search_path) {
+            self.reset();
+            self.search_path = Some(search_path.to_owned());
+        }
+    }
+
     /// Returns the path associated with the given name.
     ///
     /// # Arguments
     ///
     /// * `name` - The name to lookup.
     pub fn get<S: AsRef<str>>(&self, name: S) -> Option<PathBuf> {
-        self.cache.get(name.as_ref()).cloned()
+        self.cache
+            .get(name.as_ref())
+            .map(|entry| entry.path.clone())
     }
 
-    /// Sets the path associated with the given name.
+    /// Sets the path associated with the given name, resetting its hit count.
     ///
     /// # Arguments
     ///
     /// * `name` - The name to set.
     pub fn set<S: AsRef<str>>(&mut self, name: S, path: PathBuf) {
-        self.cache.insert(name.as_ref().to_string(), path);
+        self.cache
+            .insert(name.as_ref().to_string(), PathCacheEntry { path, hits: 0 });
+    }
+
+    /// Records that the entry associated with the given name was used to locate a command
+    /// to execute.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name whose entry was used.
+    pub fn record_hit<S: AsRef<str>>(&mut self, name: S) {
+        if let Some(entry) = self.cache.get_mut(name.as_ref()) {
+            entry.hits += 1;
+        }
     }
 
     /// Removes the path associated with the given name, if there is one.
@@ -38,6 +82,14 @@ impl PathCache {
     ///
     /// * `name` - The name to remove.
     pub fn unset<S: AsRef<str>>(&mut self, name: S) -> bool {
-        self.cache.remove(name.as_ref()).is_some()
+        self.cache.shift_remove(name.as_ref()).is_some()
+    }
+
+    /// Returns an iterator over the entries in the cache, in the order they were added; each
+    /// item is a tuple of the name, its associated path, and its hit count.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &PathBuf, usize)> {
+        self.cache
+            .iter()
+            .map(|(name, entry)| (name.as_str(), &entry.path, entry.hits))
     }
 }
@@ -0,0 +1,117 @@
+//! Caching of the contents of the directories in the shell's search path (i.e., `PATH`), so
+//! that looking up commands needn't probe each directory for each lookup. This matters most
+//! with long search paths (e.g., on NixOS or under WSL), where a single lookup of a command
+//! that lives late in the path (or doesn't exist at all) touches many directories.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// A cache of the names of the entries in the directories searched for commands.
+#[derive(Default)]
+pub struct PathSearchCache {
+    /// Cached listings, keyed by directory path.
+    dirs: HashMap<PathBuf, CachedDirectory>,
+}
+
+/// A cached listing of a single directory.
+struct CachedDirectory {
+    /// The modification time of the directory when it was listed, if known.
+    modified: Option<SystemTime>,
+    /// The names of the entries in the directory.
+    names: HashSet<String>,
+}
+
+impl CachedDirectory {
+    fn read(dir: &Path) -> Self {
+        let modified = std::fs::metadata(dir).and_then(|m| m.modified()).ok();
+
+        let names = std::fs::read_dir(dir)
+            .map(|entries| {
+                entries
+                    .filter_map(Result::ok)
+                    .filter_map(|entry| entry.file_name().into_string().ok())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Self { modified, names }
+    }
+
+    fn is_current(&self, dir: &Path) -> bool {
+        // Adding or removing an entry updates the directory's modification time, which is
+        // much cheaper to check than re-reading the directory. If we can't tell when the
+        // directory was modified, we never trust the listing.
+        let modified = std::fs::metadata(dir).and_then(|m| m.modified()).ok();
+        modified.is_some() && modified == self.modified
+    }
+}
+
+impl PathSearchCache {
+    /// Clears all cached listings.
+    pub fn reset(&mut self) {
+        self.dirs.clear();
+    }
+
+    /// Returns whether the given directory (likely) contains an entry with the given name,
+    /// listing the directory if it's not cached or its cached listing is stale. Callers
+    /// should still check the entry is executable before using it.
+    ///
+    /// # Arguments
+    ///
+    /// * `dir` - The directory to look in.
+    /// * `name` - The name of the entry to look for.
+    pub fn contains(&mut self, dir: &Path, name: &str) -> bool {
+        self.listing(dir).names.contains(name)
+    }
+
+    /// Returns the names of the entries in the given directory, listing the directory if
+    /// it's not cached or its cached listing is stale.
+    ///
+    /// # Arguments
+    ///
+    /// * `dir` - The directory to list.
+    pub fn names(&mut self, dir: &Path) -> impl Iterator<Item = &str> {
+        self.listing(dir).names.iter().map(String::as_str)
+    }
+
+    fn listing(&mut self, dir: &Path) -> &CachedDirectory {
+        let listing = self
+            .dirs
+            .entry(dir.to_owned())
+            .or_insert_with(|| CachedDirectory::read(dir));
+
+        if !listing.is_current(dir) {
+            *listing = CachedDirectory::read(dir);
+        }
+
+        listing
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+
+    #[test]
+    fn test_invalidation() -> Result<()> {
+        let dir = std::env::temp_dir().join(format!("brush-pathsearch-{}", std::process::id()));
+        std::fs::create_dir_all(&dir)?;
+
+        let mut cache = PathSearchCache::default();
+        assert!(!cache.contains(&dir, "cmd"));
+
+        // Entries added or removed after the directory was listed are noticed on the next
+        // lookup.
+        std::fs::write(dir.join("cmd"), "")?;
+        assert!(cache.contains(&dir, "cmd"));
+
+        std::fs::remove_file(dir.join("cmd"))?;
+        assert!(!cache.contains(&dir, "cmd"));
+
+        std::fs::remove_dir(&dir)?;
+
+        Ok(())
+    }
+}
@@ -1,5 +1,6 @@
 use crate::{error, regex, trace_categories};
 use std::{
+    cmp::Ordering,
     collections::VecDeque,
     path::{Path, PathBuf},
 };
@@ -24,6 +25,154 @@ impl PatternPiece {
 
 type PatternWord = Vec<PatternPiece>;
 
+/// The file attribute used to order the results of pathname expansion.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) enum GlobSortKey {
+    /// Sort by file name.
+    #[default]
+    Name,
+    /// Sort by file size.
+    Size,
+    /// Sort by last modification time.
+    ModificationTime,
+    /// Sort by last access time.
+    AccessTime,
+    /// Leave results in the order they were found in the file system.
+    Unsorted,
+}
+
+/// How file names are compared with each other when ordering expansion results.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) enum Collation {
+    /// Compare names byte by byte, as in the C locale.
+    #[default]
+    Bytewise,
+    /// Approximate the collation order used by typical non-C locales: letters and
+    /// digits are compared ignoring case and punctuation, with ties broken by case
+    /// (lowercase first) and then byte by byte.
+    Locale,
+}
+
+/// Describes how the results of pathname expansion are ordered.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct GlobSort {
+    /// The attribute to sort by.
+    pub key: GlobSortKey,
+    /// Whether to sort in descending order.
+    pub descending: bool,
+    /// How names are compared, whether as the primary key or to break ties.
+    pub collation: Collation,
+}
+
+impl GlobSort {
+    /// Parses a sort specification in the format used by the `GLOBSORT` variable:
+    /// an optional `+` (ascending) or `-` (descending) followed by one of `name`,
+    /// `size`, `mtime`, `atime`, or `nosort`. Unrecognized specifications result
+    /// in sorting by name.
+    ///
+    /// # Arguments
+    ///
+    /// * `spec` - The specification to parse.
+    /// * `collation` - How names should be compared.
+    pub fn parse(spec: &str, collation: Collation) -> Self {
+        let (descending, key_str) = if let Some(key_str) = spec.strip_prefix('-') {
+            (true, key_str)
+        } else {
+            (false, spec.strip_prefix('+').unwrap_or(spec))
+        };
+
+        let key = match key_str {
+            "size" => GlobSortKey::Size,
+            "mtime" => GlobSortKey::ModificationTime,
+            "atime" => GlobSortKey::AccessTime,
+            "nosort" => GlobSortKey::Unsorted,
+            _ => GlobSortKey::Name,
+        };
+
+        Self {
+            key,
+            descending,
+            collation,
+        }
+    }
+
+    fn sort(&self, paths: &mut Vec<PathBuf>) {
+        match self.key {
+            GlobSortKey::Unsorted => return,
+            GlobSortKey::Name => paths.sort_by(|a, b| self.compare_names(a, b)),
+            GlobSortKey::Size | GlobSortKey::ModificationTime | GlobSortKey::AccessTime => {
+                // N.B. Retrieve each attribute just once; ties are broken by name so the
+                // results are deterministic.
+                let mut keyed: Vec<_> = paths
+                    .drain(..)
+                    .map(|path| (self.attribute(&path), path))
+                    .collect();
+
+                keyed.sort_by(|(a_attr, a), (b_attr, b)| {
+                    a_attr.cmp(b_attr).then_with(|| self.compare_names(a, b))
+                });
+
+                paths.extend(keyed.into_iter().map(|(_, path)| path));
+            }
+        }
+
+        if self.descending {
+            paths.reverse();
+        }
+    }
+
+    fn attribute(&self, path: &Path) -> u128 {
+        let Ok(metadata) = path.metadata() else {
+            return 0;
+        };
+
+        let time = match self.key {
+            GlobSortKey::Size => return u128::from(metadata.len()),
+            GlobSortKey::ModificationTime => metadata.modified(),
+            GlobSortKey::AccessTime => metadata.accessed(),
+            GlobSortKey::Name | GlobSortKey::Unsorted => return 0,
+        };
+
+        time.ok()
+            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+            .map_or(0, |d| d.as_nanos())
+    }
+
+    fn compare_names(&self, a: &Path, b: &Path) -> Ordering {
+        match self.collation {
+            Collation::Bytewise => a.as_os_str().cmp(b.as_os_str()),
+            Collation::Locale => {
+                collate(a.to_string_lossy().as_ref(), b.to_string_lossy().as_ref())
+            }
+        }
+    }
+}
+
+/// Compares two strings in an approximation of the collation order of typical
+/// non-C locales.
+fn collate(a: &str, b: &str) -> Ordering {
+    let significant_chars = |s: &str| {
+        s.chars()
+            .filter(|c| c.is_alphanumeric())
+            .collect::<Vec<_>>()
+    };
+
+    let a_chars = significant_chars(a);
+    let b_chars = significant_chars(b);
+
+    a_chars
+        .iter()
+        .flat_map(|c| c.to_lowercase())
+        .cmp(b_chars.iter().flat_map(|c| c.to_lowercase()))
+        .then_with(|| {
+            a_chars
+                .iter()
+                .map(|c| c.is_uppercase())
+                .cmp(b_chars.iter().map(|c| c.is_uppercase()))
+        })
+        .then_with(|| a.cmp(b))
+}
+
 /// Encapsulates a shell pattern.
 #[derive(Clone, Debug)]
 pub struct Pattern {
@@ -31,6 +180,7 @@ pub struct Pattern {
     enable_extended_globbing: bool,
     multiline: bool,
     case_insensitive: bool,
+    sort: GlobSort,
 }
 
 impl Default for Pattern {
@@ -40,6 +190,7 @@ impl Default for Pattern {
             enable_extended_globbing: false,
             multiline: true,
             case_insensitive: false,
+            sort: GlobSort::default(),
         }
     }
 }
@@ -112,6 +263,16 @@ impl Pattern {
         self
     }
 
+    /// Sets how the results of expanding this pattern are ordered.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The ordering to apply.
+    pub(crate) fn set_sort(mut self, value: GlobSort) -> Pattern {
+        self.sort = value;
+        self
+    }
+
     /// Returns whether or not the pattern is empty.
     pub fn is_empty(&self) -> bool {
         self.pieces.iter().all(|p| p.as_str().is_empty())
@@ -247,12 +408,12 @@ impl Pattern {
                     .map(|entry| entry.path())
                     .collect();
 
-                matching_paths_in_dir.sort();
-
                 paths_so_far.append(&mut matching_paths_in_dir);
             }
         }
 
+        self.sort.sort(&mut paths_so_far);
+
         let results: Vec<_> = paths_so_far
             .into_iter()
             .filter_map(|path| {
@@ -812,4 +973,42 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_glob_sort_by_name() {
+        let mut paths: Vec<_> = ["b", "B", "a.txt", "_c", "A"]
+            .iter()
+            .map(PathBuf::from)
+            .collect();
+
+        GlobSort::parse("", Collation::Bytewise).sort(&mut paths);
+        assert_eq!(paths, ["A", "B", "_c", "a.txt", "b"].map(PathBuf::from));
+
+        GlobSort::parse("name", Collation::Locale).sort(&mut paths);
+        assert_eq!(paths, ["A", "a.txt", "b", "B", "_c"].map(PathBuf::from));
+
+        GlobSort::parse("-name", Collation::Bytewise).sort(&mut paths);
+        assert_eq!(paths, ["b", "a.txt", "_c", "B", "A"].map(PathBuf::from));
+    }
+
+    #[test]
+    fn test_glob_sort_parsing() {
+        let sort = GlobSort::parse("-mtime", Collation::Locale);
+        assert_eq!(sort.key, GlobSortKey::ModificationTime);
+        assert!(sort.descending);
+        assert_eq!(sort.collation, Collation::Locale);
+
+        let sort = GlobSort::parse("+size", Collation::Bytewise);
+        assert_eq!(sort.key, GlobSortKey::Size);
+        assert!(!sort.descending);
+
+        assert_eq!(
+            GlobSort::parse("nosort", Collation::Bytewise).key,
+            GlobSortKey::Unsorted
+        );
+        assert_eq!(
+            GlobSort::parse("bogus", Collation::Bytewise).key,
+            GlobSortKey::Name
+        );
+    }
 }
@@ -1,6 +1,10 @@
 use futures::FutureExt;
 
-use crate::{error, sys};
+use crate::{audit, commandstats, error, sys, CancellationToken};
+
+/// How long a child process interrupted by cancellation is given to exit before it's killed
+/// outright.
+const INTERRUPT_GRACE_PERIOD: std::time::Duration = std::time::Duration::from_secs(2);
 
 /// A waitable future that will yield the results of a child process's execution.
 pub(crate) type WaitableChildProcess = std::pin::Pin<
@@ -13,14 +17,28 @@ pub(crate) struct ChildProcess {
     pid: Option<sys::process::ProcessId>,
     /// A waitable future that will yield the results of a child process's execution.
     exec_future: WaitableChildProcess,
+    /// If resource usage of the child is being measured, the baseline to measure against.
+    stats_baseline: Option<commandstats::CommandStatsBaseline>,
+    /// If the child is being audited, the record to complete once it exits.
+    audit_record: Option<audit::PendingAuditRecord>,
+    /// Token that, once cancelled, interrupts the child while it's being awaited.
+    cancellation_token: CancellationToken,
 }
 
 impl ChildProcess {
     /// Wraps a child process and its future.
-    pub fn new(pid: Option<sys::process::ProcessId>, child: sys::process::Child) -> Self {
+    pub fn new(
+        pid: Option<sys::process::ProcessId>,
+        child: sys::process::Child,
+        stats_baseline: Option<commandstats::CommandStatsBaseline>,
+        cancellation_token: CancellationToken,
+    ) -> Self {
         Self {
             pid,
             exec_future: Box::pin(child.wait_with_output()),
+            stats_baseline,
+            audit_record: None,
+            cancellation_token,
         }
     }
 
@@ -28,13 +46,33 @@ impl ChildProcess {
         self.pid
     }
 
+    /// Takes the baseline for measuring the child's resource usage, if there is one.
+    pub fn take_stats_baseline(&mut self) -> Option<commandstats::CommandStatsBaseline> {
+        self.stats_baseline.take()
+    }
+
+    /// Sets the record to complete once the child exits.
+    pub fn set_audit_record(&mut self, record: audit::PendingAuditRecord) {
+        self.audit_record = Some(record);
+    }
+
+    /// Takes the record to complete once the child exits, if there is one.
+    pub fn take_audit_record(&mut self) -> Option<audit::PendingAuditRecord> {
+        self.audit_record.take()
+    }
+
     pub async fn wait(&mut self) -> Result<ProcessWaitResult, error::Error> {
         #[allow(unused_mut)]
         let mut sigtstp = sys::signal::tstp_signal_listener()?;
         #[allow(unused_mut)]
         let mut sigchld = sys::signal::chld_signal_listener()?;
 
+        // Once interrupted by cancellation, the time by which the child must have exited.
+        let mut kill_deadline: Option<tokio::time::Instant> = None;
+
         loop {
+            let kill_at = kill_deadline.unwrap_or_else(tokio::time::Instant::now);
+
             tokio::select! {
                 output = &mut self.exec_future => {
                     break Ok(ProcessWaitResult::Completed(output?))
@@ -52,6 +90,24 @@ impl ChildProcess {
                     // have received it as well, and either handled it or ended up getting
                     // terminated (in which case we'll see the child exit).
                 },
+                () = self.cancellation_token.cancelled(), if kill_deadline.is_none() => {
+                    // Interrupt the child as Ctrl+C would, giving it a chance to clean up.
+                    if let Some(pid) = self.pid {
+                        if let Err(e) = sys::signal::interrupt_process(pid) {
+                            tracing::debug!("failed to interrupt child process {pid}: {e}");
+                        }
+                    }
+                    kill_deadline = Some(tokio::time::Instant::now() + INTERRUPT_GRACE_PERIOD);
+                },
+                () = tokio::time::sleep_until(kill_at), if kill_deadline.is_some() => {
+                    // The child outlived its grace period; kill it and wait for it to exit.
+                    if let Some(pid) = self.pid {
+                        if let Err(e) = sys::signal::kill_process_forcibly(pid) {
+                            tracing::debug!("failed to kill child process {pid}: {e}");
+                        }
+                    }
+                    kill_deadline = Some(tokio::time::Instant::now() + INTERRUPT_GRACE_PERIOD);
+                },
             }
         }
     }
@@ -0,0 +1,125 @@
+//! Profiling of time spent in shell functions.
+
+use std::collections::HashMap;
+use std::io::Write;
+use std::time::{Duration, Instant};
+
+/// Records the time spent in each shell function call stack, for rendering as a
+/// flamegraph.
+#[derive(Clone, Debug, Default)]
+pub struct FunctionProfiler {
+    /// Functions currently being executed, outermost first.
+    frames: Vec<Frame>,
+    /// Time spent directly in each unique call stack, keyed by the stack's folded form.
+    self_times: HashMap<String, Duration>,
+}
+
+#[derive(Clone, Debug)]
+struct Frame {
+    function_name: String,
+    start: Instant,
+    time_in_callees: Duration,
+}
+
+impl FunctionProfiler {
+    /// Records that the given function is being entered.
+    ///
+    /// # Arguments
+    ///
+    /// * `function_name` - The name of the function.
+    pub(crate) fn enter(&mut self, function_name: &str) {
+        self.frames.push(Frame {
+            function_name: function_name.to_owned(),
+            start: Instant::now(),
+            time_in_callees: Duration::ZERO,
+        });
+    }
+
+    /// Records that the innermost function being executed has returned.
+    pub(crate) fn leave(&mut self) {
+        let now = Instant::now();
+        let stack = self.folded_stack();
+
+        // N.B. A subshell may return from a function it didn't enter (e.g., a subshell
+        // created within a function); there's nothing to record in that case.
+        let Some(frame) = self.frames.pop() else {
+            return;
+        };
+
+        let elapsed = now.duration_since(frame.start);
+        *self.self_times.entry(stack).or_default() += elapsed.saturating_sub(frame.time_in_callees);
+
+        if let Some(caller) = self.frames.last_mut() {
+            caller.time_in_callees += elapsed;
+        }
+    }
+
+    /// Writes the recorded samples in the "folded stacks" format consumed by
+    /// `flamegraph.pl` and `inferno-flamegraph`: one line per unique call stack, with
+    /// function names separated by semicolons (outermost first), followed by the time
+    /// spent directly in the innermost function, in microseconds. Functions that
+    /// haven't yet returned are included with the time spent in them so far.
+    ///
+    /// # Arguments
+    ///
+    /// * `writer` - The writer to write the samples to.
+    pub fn write_folded_stacks(&self, writer: &mut impl Write) -> Result<(), std::io::Error> {
+        let mut profiler = self.clone();
+        while !profiler.frames.is_empty() {
+            profiler.leave();
+        }
+
+        let mut stacks: Vec<_> = profiler.self_times.into_iter().collect();
+        stacks.sort();
+
+        for (stack, time) in stacks {
+            writeln!(writer, "{stack} {}", time.as_micros())?;
+        }
+
+        Ok(())
+    }
+
+    fn folded_stack(&self) -> String {
+        self.frames
+            .iter()
+            .map(|frame| frame.function_name.as_str())
+            .collect::<Vec<_>>()
+            .join(";")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn folded_stacks() -> Result<(), std::io::Error> {
+        let mut profiler = FunctionProfiler::default();
+        profiler.enter("outer");
+        profiler.enter("inner");
+        profiler.leave();
+        profiler.enter("inner");
+        profiler.leave();
+        profiler.leave();
+        profiler.enter("other");
+        profiler.leave();
+
+        // N.B. A return without a matching call is ignored.
+        profiler.leave();
+
+        profiler.enter("pending");
+
+        let mut output = vec![];
+        profiler.write_folded_stacks(&mut output)?;
+        let output = String::from_utf8_lossy(&output);
+
+        let stacks: Vec<_> = output
+            .lines()
+            .filter_map(|line| line.rsplit_once(' '))
+            .map(|(stack, _)| stack)
+            .collect();
+        assert_eq!(stacks, ["other", "outer", "outer;inner", "pending"]);
+
+        Ok(())
+    }
+}
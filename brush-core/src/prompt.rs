@@ -1,9 +1,10 @@
 use crate::{
-    error,
+    commands, error,
     shell::Shell,
     sys::{self, users},
 };
-use std::path::Path;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 
 const VERSION_MAJOR: &str = env!("CARGO_PKG_VERSION_MAJOR");
 const VERSION_MINOR: &str = env!("CARGO_PKG_VERSION_MINOR");
@@ -29,6 +30,153 @@ fn parse_prompt(
     brush_parser::prompt::parse(spec.as_str())
 }
 
+/// Variable holding a command whose output is used as the prompt, in place of `PS1` (a brush
+/// extension).
+pub(crate) const PROMPT_COMMAND_VAR: &str = "BRUSH_PROMPT_COMMAND";
+
+/// Variable holding a command whose output is used as the alternate-side prompt, in place of
+/// `BRUSH_PS_ALT` (a brush extension).
+pub(crate) const ALT_SIDE_PROMPT_COMMAND_VAR: &str = "BRUSH_PROMPT_COMMAND_RIGHT";
+
+/// Outputs of prompt commands (e.g., from `BRUSH_PROMPT_COMMAND`), cached so the commands
+/// are only run again once the state their output might reflect has changed.
+#[derive(Clone, Default)]
+pub(crate) struct PromptCommandCache {
+    /// Cached outputs, keyed by the name of the variable holding the command.
+    outputs: HashMap<String, CachedPromptCommandOutput>,
+}
+
+#[derive(Clone)]
+struct CachedPromptCommandOutput {
+    key: PromptCommandCacheKey,
+    output: String,
+}
+
+/// The command and shell state that a prompt command's output was produced for.
+#[derive(Clone, PartialEq, Eq)]
+struct PromptCommandCacheKey {
+    command: String,
+    working_dir: PathBuf,
+    last_exit_status: u8,
+    command_number: usize,
+    job_count: usize,
+}
+
+impl PromptCommandCacheKey {
+    fn new(shell: &Shell, command: String) -> Self {
+        Self {
+            command,
+            working_dir: shell.working_dir.clone(),
+            last_exit_status: shell.last_exit_status,
+            command_number: shell.command_number,
+            job_count: shell.jobs.jobs.len(),
+        }
+    }
+}
+
+/// Returns the output of the prompt command held in the given variable, or `None` if the
+/// variable isn't set. The command is only run if its output isn't already cached.
+///
+/// # Arguments
+///
+/// * `shell` - The shell composing the prompt.
+/// * `var_name` - The name of the variable holding the command.
+pub(crate) async fn get_prompt_command_output(
+    shell: &mut Shell,
+    var_name: &str,
+) -> Result<Option<String>, error::Error> {
+    let Some(key) = get_prompt_command_cache_key(shell, var_name) else {
+        return Ok(None);
+    };
+
+    if let Some(cached) = shell.prompt_command_cache.outputs.get(var_name) {
+        if cached.key == key {
+            return Ok(Some(cached.output.clone()));
+        }
+    }
+
+    let (output, exit_code) = run_prompt_command(shell.clone(), key.command.clone()).await?;
+    cache_prompt_command_output(shell, var_name, key, &output, exit_code);
+
+    Ok(Some(output))
+}
+
+/// Runs those prompt commands whose outputs aren't already cached, concurrently, and caches
+/// their outputs.
+///
+/// # Arguments
+///
+/// * `shell` - The shell composing the prompts.
+pub(crate) async fn run_prompt_commands(shell: &mut Shell) -> Result<(), error::Error> {
+    let stale: Vec<_> = [PROMPT_COMMAND_VAR, ALT_SIDE_PROMPT_COMMAND_VAR]
+        .into_iter()
+        .filter_map(|var_name| {
+            let key = get_prompt_command_cache_key(shell, var_name)?;
+            let cached = shell.prompt_command_cache.outputs.get(var_name);
+            (!cached.is_some_and(|cached| cached.key == key)).then_some((var_name, key))
+        })
+        .collect();
+
+    let results = futures::future::try_join_all(
+        stale
+            .iter()
+            .map(|(_, key)| run_prompt_command(shell.clone(), key.command.clone())),
+    )
+    .await?;
+
+    for ((var_name, key), (output, exit_code)) in stale.into_iter().zip(results) {
+        cache_prompt_command_output(shell, var_name, key, &output, exit_code);
+    }
+
+    Ok(())
+}
+
+fn get_prompt_command_cache_key(shell: &Shell, var_name: &str) -> Option<PromptCommandCacheKey> {
+    let (_, var) = shell.env.get(var_name)?;
+    let command = var.value().to_cow_string().to_string();
+
+    (!command.is_empty()).then(|| PromptCommandCacheKey::new(shell, command))
+}
+
+fn cache_prompt_command_output(
+    shell: &mut Shell,
+    var_name: &str,
+    key: PromptCommandCacheKey,
+    output: &str,
+    exit_code: u8,
+) {
+    // N.B. We don't cache the output of commands that fail (or time out), so they'll be
+    // retried when the prompt is next composed.
+    if exit_code == 0 {
+        shell.prompt_command_cache.outputs.insert(
+            var_name.to_owned(),
+            CachedPromptCommandOutput {
+                key,
+                output: output.to_owned(),
+            },
+        );
+    } else {
+        shell.prompt_command_cache.outputs.remove(var_name);
+    }
+}
+
+/// Runs the given prompt command in the given subshell, as for a command substitution
+/// subject to the shell's prompt timeout; returns its output (sans trailing newlines) and
+/// exit code.
+async fn run_prompt_command(
+    mut subshell: Shell,
+    command: String,
+) -> Result<(String, u8), error::Error> {
+    subshell.command_substitution_deadline = subshell
+        .prompt_timeout
+        .map(|timeout| tokio::time::Instant::now() + timeout);
+
+    let (mut output, exit_code) = commands::run_command_substitution(subshell, command).await?;
+    output.truncate(output.trim_end_matches('\n').len());
+
+    Ok((output, exit_code))
+}
+
 pub(crate) fn format_prompt_piece(
     shell: &Shell,
     piece: brush_parser::prompt::PromptPiece,
@@ -41,12 +189,8 @@ pub(crate) fn format_prompt_piece(
         brush_parser::prompt::PromptPiece::Backslash => "\\".to_owned(),
         brush_parser::prompt::PromptPiece::BellCharacter => "\x07".to_owned(),
         brush_parser::prompt::PromptPiece::CarriageReturn => "\r".to_owned(),
-        brush_parser::prompt::PromptPiece::CurrentCommandNumber => {
-            return error::unimp("prompt: current command number")
-        }
-        brush_parser::prompt::PromptPiece::CurrentHistoryNumber => {
-            return error::unimp("prompt: current history number")
-        }
+        brush_parser::prompt::PromptPiece::CurrentCommandNumber => shell.command_number.to_string(),
+        brush_parser::prompt::PromptPiece::CurrentHistoryNumber => shell.history_number.to_string(),
         brush_parser::prompt::PromptPiece::CurrentUser => users::get_current_username()?,
         brush_parser::prompt::PromptPiece::CurrentWorkingDirectory {
             tilde_replaced,
@@ -98,7 +242,12 @@ pub(crate) fn format_prompt_piece(
         }
         brush_parser::prompt::PromptPiece::StartNonPrintingSequence => String::new(),
         brush_parser::prompt::PromptPiece::TerminalDeviceBaseName => {
-            return error::unimp("prompt: terminal device base name")
+            sys::terminal::get_terminal_device_name()
+                .and_then(|name| {
+                    name.file_name()
+                        .map(|name| name.to_string_lossy().to_string())
+                })
+                .unwrap_or_else(|| "tty".to_owned())
         }
         brush_parser::prompt::PromptPiece::Time(time_fmt) => {
             format_time(&chrono::Local::now(), &time_fmt)
@@ -125,9 +274,55 @@ fn format_current_working_directory(shell: &Shell, tilde_replaced: bool, basenam
         working_dir_str = working_dir_str.replace('\\', "/");
     }
 
+    if !basename {
+        if let Some(max_components) = get_prompt_dir_trim(shell) {
+            working_dir_str = trim_working_directory(working_dir_str, max_components);
+        }
+    }
+
     working_dir_str
 }
 
+/// Returns the number of trailing components of the working directory to retain in
+/// prompts, as specified by `PROMPT_DIRTRIM`, if set to a positive number.
+fn get_prompt_dir_trim(shell: &Shell) -> Option<usize> {
+    let (_, var) = shell.env.get("PROMPT_DIRTRIM")?;
+    let max_components = var.value().to_cow_string().parse().ok()?;
+    (max_components > 0).then_some(max_components)
+}
+
+/// Shortens the given (possibly tilde-replaced) working directory path to its last
+/// `max_components` components, replacing those removed with an ellipsis in the manner of
+/// bash (e.g., `~/.../c/d`).
+fn trim_working_directory(dir: String, max_components: usize) -> String {
+    // A leading tilde (and the separators following it) is always retained.
+    let prefix_len = if let Some(after_tilde) = dir.strip_prefix('~') {
+        dir.len() - after_tilde.trim_start_matches('/').len()
+    } else {
+        0
+    };
+    let (prefix, rest) = dir.split_at(prefix_len);
+
+    // Find the separator preceding the components to retain; a separator at the very start
+    // of the path doesn't count.
+    let Some(tail_start) = rest
+        .match_indices('/')
+        .rev()
+        .filter(|(index, _)| *index > 0)
+        .nth(max_components - 1)
+        .map(|(index, _)| index)
+    else {
+        return dir;
+    };
+
+    // Don't bother if the ellipsis wouldn't be any shorter than what it replaces.
+    if tail_start <= 3 {
+        return dir;
+    }
+
+    std::format!("{prefix}...{}", &rest[tail_start..])
+}
+
 fn format_time<Tz: chrono::TimeZone>(
     datetime: &chrono::DateTime<Tz>,
     format: &brush_parser::prompt::PromptTimeFormat,
@@ -138,6 +333,7 @@ where
     let formatted = match format {
         brush_parser::prompt::PromptTimeFormat::TwelveHourAM => datetime.format("%I:%M %p"),
         brush_parser::prompt::PromptTimeFormat::TwelveHourHHMMSS => datetime.format("%I:%M:%S"),
+        brush_parser::prompt::PromptTimeFormat::TwentyFourHourHHMM => datetime.format("%H:%M"),
         brush_parser::prompt::PromptTimeFormat::TwentyFourHourHHMMSS => datetime.format("%H:%M:%S"),
     };
 
@@ -156,6 +352,8 @@ where
             datetime.format("%a %b %d").to_string()
         }
         brush_parser::prompt::PromptDateFormat::Custom(fmt) => {
+            // As with bash, an empty format yields the locale's time representation.
+            let fmt = if fmt.is_empty() { "%X" } else { fmt.as_str() };
             let fmt_items = chrono::format::StrftimeItems::new(fmt);
             datetime.format_with_items(fmt_items).to_string()
         }
@@ -191,6 +389,14 @@ mod tests {
             ),
             "01:34:56"
         );
+
+        assert_eq!(
+            format_time(
+                &dt,
+                &brush_parser::prompt::PromptTimeFormat::TwentyFourHourHHMM
+            ),
+            "13:34"
+        );
     }
 
     #[test]
@@ -223,5 +429,166 @@ mod tests {
             ),
             "2024-12-25 12:34:56.789000000"
         );
+
+        assert_eq!(
+            format_date(
+                &dt,
+                &brush_parser::prompt::PromptDateFormat::Custom(String::new())
+            ),
+            "12:34:56"
+        );
+    }
+
+    #[test]
+    fn test_trim_working_directory() {
+        let trim =
+            |dir: &str, max_components| trim_working_directory(dir.to_owned(), max_components);
+
+        assert_eq!(trim("/usr/local/share/doc", 2), ".../share/doc");
+        assert_eq!(trim("/usr/local/share/doc", 3), ".../local/share/doc");
+        assert_eq!(trim("/usr/local/share/doc", 4), "/usr/local/share/doc");
+        assert_eq!(trim("~/src/brush/brush-core", 1), "~/.../brush-core");
+        assert_eq!(trim("~/src/brush", 2), "~/src/brush");
+        assert_eq!(trim("~", 1), "~");
+        assert_eq!(trim("/", 1), "/");
+
+        // The ellipsis doesn't replace anything as short as itself.
+        assert_eq!(trim("/a/b/c", 2), "/a/b/c");
+        assert_eq!(trim("/ab/c/d", 2), "/ab/c/d");
+        assert_eq!(trim("/abcd/e/f", 2), ".../e/f");
+    }
+
+    #[tokio::test]
+    async fn test_expand_shell_state_escapes() -> Result<(), error::Error> {
+        let options = crate::shell::CreateOptions::default();
+        let mut shell = crate::shell::Shell::new(&options).await?;
+        shell.env.set_global(
+            "HOME",
+            crate::variables::ShellVariable::new("/home/user".into()),
+        )?;
+        shell.working_dir = std::path::PathBuf::from("/home/user/src/brush/brush-core");
+        shell.command_number = 7;
+        shell.history_number = 42;
+
+        assert_eq!(expand_prompt(&shell, r"\#".to_owned())?, "7");
+        assert_eq!(expand_prompt(&shell, r"\!".to_owned())?, "42");
+        assert_eq!(expand_prompt(&shell, r"\j".to_owned())?, "0");
+        assert!(!expand_prompt(&shell, r"\l".to_owned())?.is_empty());
+        assert_eq!(expand_prompt(&shell, r"\101\\".to_owned())?, "A\\");
+        assert_eq!(
+            expand_prompt(&shell, r"\[\e[1m\]x\[\e[0m\]".to_owned())?,
+            "\x1b[1mx\x1b[0m"
+        );
+
+        assert_eq!(
+            expand_prompt(&shell, r"\w".to_owned())?,
+            "~/src/brush/brush-core"
+        );
+        assert_eq!(expand_prompt(&shell, r"\W".to_owned())?, "brush-core");
+
+        shell.env.set_global(
+            "PROMPT_DIRTRIM",
+            crate::variables::ShellVariable::new("2".into()),
+        )?;
+        assert_eq!(
+            expand_prompt(&shell, r"\w".to_owned())?,
+            "~/.../brush/brush-core"
+        );
+        assert_eq!(expand_prompt(&shell, r"\W".to_owned())?, "brush-core");
+
+        // Only whole components of the home directory are replaced with a tilde.
+        shell.working_dir = std::path::PathBuf::from("/home/user2");
+        assert_eq!(expand_prompt(&shell, r"\w".to_owned())?, "/home/user2");
+
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_prompt_timeout() -> Result<(), error::Error> {
+        let options = crate::shell::CreateOptions::default();
+        let mut shell = crate::shell::Shell::new(&options).await?;
+        let pid_file = std::env::temp_dir().join(format!(
+            "brush-test-prompt-timeout-{}.pid",
+            std::process::id()
+        ));
+        shell.env.set_global(
+            "PS1",
+            crate::variables::ShellVariable::new(
+                format!(
+                    "[$(echo fast)|$(sh -c 'echo $$ >$1; exec sleep 10' sh '{}')]$ ",
+                    pid_file.display()
+                )
+                .into(),
+            ),
+        )?;
+        shell.prompt_timeout = Some(std::time::Duration::from_millis(500));
+
+        let start = std::time::Instant::now();
+        assert_eq!(shell.compose_prompt().await?, "[fast|...]$ ");
+        assert!(start.elapsed() < std::time::Duration::from_secs(5));
+
+        // The process run by the abandoned command substitution doesn't outlive it.
+        let pid = std::fs::read_to_string(&pid_file)?;
+        std::fs::remove_file(&pid_file)?;
+        let pid = nix::unistd::Pid::from_raw(pid.trim().parse().unwrap_or_default());
+        let start = std::time::Instant::now();
+        while nix::sys::signal::kill(pid, None).is_ok() {
+            assert!(start.elapsed() < std::time::Duration::from_secs(5));
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        }
+
+        // Command substitutions run outside of prompts aren't affected.
+        assert!(shell.command_substitution_deadline.is_none());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_prompt_commands() -> Result<(), error::Error> {
+        fn set_var(shell: &mut Shell, name: &str, value: &str) -> Result<(), error::Error> {
+            shell
+                .env
+                .set_global(name, crate::variables::ShellVariable::new(value.into()))
+        }
+
+        let options = crate::shell::CreateOptions::default();
+        let mut shell = crate::shell::Shell::new(&options).await?;
+        set_var(&mut shell, "PS1", "unused$ ")?;
+        set_var(&mut shell, "PROMPT_TEXT", "first")?;
+        set_var(&mut shell, PROMPT_COMMAND_VAR, "echo \"$PROMPT_TEXT\"")?;
+        set_var(
+            &mut shell,
+            ALT_SIDE_PROMPT_COMMAND_VAR,
+            "echo \"[$?]\"; echo",
+        )?;
+
+        shell.run_prompt_commands().await?;
+        assert_eq!(shell.compose_prompt().await?, "first");
+        assert_eq!(shell.compose_alt_side_prompt().await?, "[0]");
+
+        // Outputs are reused until the state they're keyed on changes.
+        set_var(&mut shell, "PROMPT_TEXT", "second")?;
+        assert_eq!(shell.compose_prompt().await?, "first");
+
+        shell.last_exit_status = 2;
+        assert_eq!(shell.compose_prompt().await?, "second");
+        assert_eq!(shell.compose_alt_side_prompt().await?, "[2]");
+
+        // Outputs of failing commands aren't cached.
+        set_var(
+            &mut shell,
+            PROMPT_COMMAND_VAR,
+            "echo \"$PROMPT_TEXT\"; false",
+        )?;
+        assert_eq!(shell.compose_prompt().await?, "second");
+        set_var(&mut shell, "PROMPT_TEXT", "third")?;
+        assert_eq!(shell.compose_prompt().await?, "third");
+
+        // Without a command, the prompt is composed as usual.
+        set_var(&mut shell, PROMPT_COMMAND_VAR, "")?;
+        assert_eq!(shell.compose_prompt().await?, "unused$ ");
+
+        Ok(())
     }
 }
@@ -1,6 +1,30 @@
+//! Regular expressions, as used by the `=~` operator in extended tests.
+//!
+//! Like bash, we interpret regular expressions as POSIX extended regular expressions
+//! (EREs), along with the GNU extensions bash supports on most platforms (e.g., `\w`,
+//! `\<`, and backreferences). They're matched by translating them into the syntax of
+//! the `fancy_regex` crate. When the `rust_regex` shell option is enabled, they're
+//! instead interpreted directly with that crate's syntax, which offers additional
+//! features (e.g., `\d`, lookaround, and lazy quantifiers).
+//!
+//! Notable differences in the default (ERE) interpretation include:
+//!
+//! * Within a bracket expression, a backslash is an ordinary character, and `]` is
+//!   literal when it's the first character of the expression.
+//! * Escaping an alphanumeric character that has no special meaning (e.g., `\d`) yields
+//!   that character.
+//! * `.` matches newlines.
+//! * `{` is literal when it doesn't begin a valid interval (e.g., `a{x`), and `*`, `+`,
+//!   and `?` are literal at the start of an expression.
+//! * `(?...)` groups, such as named groups, have no meaning in EREs; they're passed
+//!   through as is.
+//! * Where alternatives could match at the same position, POSIX prefers the longest
+//!   match while we prefer the leftmost alternative; this isn't reconciled.
+
 #![allow(clippy::needless_pass_by_value)]
 
 use std::borrow::Cow;
+use std::collections::BTreeMap;
 
 use crate::error;
 
@@ -29,6 +53,7 @@ type RegexWord = Vec<RegexPiece>;
 pub struct Regex {
     pieces: RegexWord,
     case_insensitive: bool,
+    rust_syntax: bool,
 }
 
 impl From<RegexWord> for Regex {
@@ -36,6 +61,7 @@ impl From<RegexWord> for Regex {
         Self {
             pieces,
             case_insensitive: false,
+            rust_syntax: false,
         }
     }
 }
@@ -51,30 +77,70 @@ impl Regex {
         self
     }
 
-    /// Computes if the regular expression matches the given string.
+    /// Sets whether the regular expression uses the syntax of the `fancy_regex` crate,
+    /// as opposed to POSIX extended regular expression syntax.
     ///
     /// # Arguments
     ///
-    /// * `value` - The string to check for a match.
-    pub fn matches(&self, value: &str) -> Result<Option<Vec<Option<String>>>, error::Error> {
-        let regex_pattern: String = self
-            .pieces
-            .iter()
-            .map(|piece| piece.to_regex_str())
-            .collect();
-
-        // TODO: Evaluate how compatible the `fancy_regex` crate is with POSIX EREs.
+    /// * `value` - Whether to use `fancy_regex` syntax.
+    pub fn set_rust_syntax(mut self, value: bool) -> Self {
+        self.rust_syntax = value;
+        self
+    }
+
+    /// Matches the regular expression against the given string, returning the text
+    /// captured by each of its groups if it matches.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The string to match against.
+    pub fn captures(&self, value: &str) -> Result<Option<RegexCaptures>, error::Error> {
+        let regex_pattern: String = if self.rust_syntax {
+            self.pieces
+                .iter()
+                .map(|piece| piece.to_regex_str())
+                .collect()
+        } else {
+            translate_ere(&self.pieces)
+        };
+
         let re = compile_regex(regex_pattern, self.case_insensitive)?;
 
         Ok(re.captures(value)?.map(|captures| {
-            captures
+            let groups = captures
                 .iter()
                 .map(|c| c.map(|m| m.as_str().to_owned()))
-                .collect()
+                .collect();
+
+            let named_groups = re
+                .capture_names()
+                .flatten()
+                .map(|name| {
+                    (
+                        name.to_owned(),
+                        captures.name(name).map(|m| m.as_str().to_owned()),
+                    )
+                })
+                .collect();
+
+            RegexCaptures {
+                groups,
+                named_groups,
+            }
         }))
     }
 }
 
+/// The text captured by a regular expression's groups when it matched a string.
+#[derive(Clone, Debug)]
+pub struct RegexCaptures {
+    /// The text matched by the whole expression, followed by the text matched by each
+    /// group, in order. A group that didn't participate in the match is `None`.
+    pub groups: Vec<Option<String>>,
+    /// The text matched by each named group, keyed by name.
+    pub named_groups: BTreeMap<String, Option<String>>,
+}
+
 #[cached::proc_macro::cached(size = 64, result = true)]
 pub(crate) fn compile_regex(
     regex_str: String,
@@ -133,6 +199,214 @@ fn add_missing_escape_chars_to_regex(s: &str) -> Cow<str> {
     updated.into()
 }
 
+/// Translates a POSIX extended regular expression into the syntax of the `fancy_regex`
+/// crate.
+fn translate_ere(pieces: &[RegexPiece]) -> String {
+    let mut translator = EreTranslator::default();
+
+    // N.B. `.` matches newlines in EREs.
+    translator.output.push_str("(?s)");
+    translator.expecting_atom = true;
+
+    for piece in pieces {
+        match piece {
+            RegexPiece::Pattern(s) => translator.translate_pattern(s.as_str()),
+            RegexPiece::Literal(s) => translator.translate_literal(s.as_str()),
+        }
+    }
+
+    translator.output
+}
+
+#[derive(Default)]
+struct EreTranslator {
+    output: String,
+    /// Whether we're within a bracket expression.
+    in_brackets: bool,
+    /// The character most recently added to the current bracket expression.
+    prev_bracket_char: Option<char>,
+    /// Whether a quantifier at this point would have nothing to apply to.
+    expecting_atom: bool,
+}
+
+impl EreTranslator {
+    fn translate_literal(&mut self, s: &str) {
+        for c in s.chars() {
+            if self.in_brackets {
+                self.push_bracket_char(c);
+            } else {
+                self.push_literal(c);
+                self.expecting_atom = false;
+            }
+        }
+    }
+
+    fn translate_pattern(&mut self, s: &str) {
+        let mut chars = s.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            if self.in_brackets {
+                self.translate_bracket_char(c, &mut chars);
+                continue;
+            }
+
+            let expecting_atom = std::mem::take(&mut self.expecting_atom);
+
+            match c {
+                '\\' => match chars.next() {
+                    Some(c @ ('1'..='9' | 'w' | 'W' | 's' | 'S' | 'b' | 'B')) => {
+                        self.output.push('\\');
+                        self.output.push(c);
+                    }
+                    Some('<') => self.output.push_str(r"\b(?=\w)"),
+                    Some('>') => self.output.push_str(r"\b(?<=\w)"),
+                    Some('`') => self.output.push_str(r"\A"),
+                    Some('\'') => self.output.push_str(r"\z"),
+                    Some(c) => self.push_literal(c),
+                    None => self.push_literal('\\'),
+                },
+                '[' => {
+                    self.output.push('[');
+                    if chars.next_if_eq(&'^').is_some() {
+                        self.output.push('^');
+                    }
+                    // N.B. A leading `]` is part of the expression rather than ending it.
+                    if chars.next_if_eq(&']').is_some() {
+                        self.output.push_str(r"\]");
+                    }
+                    self.in_brackets = true;
+                    self.prev_bracket_char = None;
+                }
+                '*' | '+' | '?' if expecting_atom => {
+                    self.push_literal(c);
+                }
+                '{' => {
+                    let rest: String = chars.clone().collect();
+                    match parse_interval(rest.as_str()) {
+                        Some((interval, len)) if !expecting_atom => {
+                            self.output.push_str(interval.as_str());
+                            for _ in 0..len {
+                                chars.next();
+                            }
+                        }
+                        _ => self.push_literal('{'),
+                    }
+                }
+                '}' => self.push_literal('}'),
+                '(' => {
+                    self.output.push('(');
+                    // N.B. `(?...)` groups aren't meaningful in EREs; pass them through.
+                    if chars.next_if_eq(&'?').is_some() {
+                        self.output.push('?');
+                    } else {
+                        self.expecting_atom = true;
+                    }
+                }
+                '|' | '^' => {
+                    self.output.push(c);
+                    self.expecting_atom = true;
+                }
+                c => self.output.push(c),
+            }
+        }
+    }
+
+    fn translate_bracket_char(
+        &mut self,
+        c: char,
+        chars: &mut std::iter::Peekable<std::str::Chars<'_>>,
+    ) {
+        match c {
+            ']' => {
+                self.output.push(']');
+                self.in_brackets = false;
+            }
+            '[' => match chars.peek().copied() {
+                Some(':') => {
+                    // Character classes (e.g., `[:alpha:]`) are supported as is.
+                    chars.next();
+                    let name: String = chars.by_ref().take_while(|c| *c != ':').collect();
+                    chars.next_if_eq(&']');
+                    self.output.push_str("[:");
+                    self.output.push_str(name.as_str());
+                    self.output.push_str(":]");
+                    self.prev_bracket_char = None;
+                }
+                Some(delimiter @ ('=' | '.')) => {
+                    // Equivalence classes (e.g., `[=a=]`) and collating symbols (e.g.,
+                    // `[.a.]`) are treated as the characters they contain.
+                    chars.next();
+                    let contents: String = chars.by_ref().take_while(|c| *c != delimiter).collect();
+                    chars.next_if_eq(&']');
+                    for c in contents.chars() {
+                        self.push_bracket_char(c);
+                    }
+                }
+                _ => self.push_bracket_char('['),
+            },
+            c => self.push_bracket_char(c),
+        }
+    }
+
+    fn push_bracket_char(&mut self, c: char) {
+        // N.B. Besides the characters that are special within bracket expressions in
+        // both syntaxes, `fancy_regex` gives meaning to `&&`, `--`, and `~~`.
+        let needs_escape = match c {
+            '\\' | '[' | ']' | '^' | '&' | '~' => true,
+            '-' => self.prev_bracket_char == Some('-'),
+            _ => false,
+        };
+
+        if needs_escape {
+            self.output.push('\\');
+        }
+        self.output.push(c);
+        self.prev_bracket_char = Some(c);
+    }
+
+    fn push_literal(&mut self, c: char) {
+        if regex_char_is_special(c) {
+            self.output.push('\\');
+        }
+        self.output.push(c);
+    }
+}
+
+/// Parses an interval (e.g., `{2,5}`), given the text following its opening brace.
+/// Returns the interval in `fancy_regex` syntax along with the number of characters it
+/// spans following its opening brace, or `None` if the text isn't a valid interval.
+fn parse_interval(s: &str) -> Option<(String, usize)> {
+    let end = s.find('}')?;
+    let contents = &s[..end];
+
+    let (min, max) = match contents.split_once(',') {
+        Some((min, max)) => (min, Some(max)),
+        None => (contents, None),
+    };
+
+    let is_number = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_digit());
+    let valid = match max {
+        None => is_number(min),
+        Some(max) => {
+            (min.is_empty() || is_number(min))
+                && (max.is_empty() || is_number(max))
+                && !(min.is_empty() && max.is_empty())
+        }
+    };
+
+    if !valid {
+        return None;
+    }
+
+    let min = if min.is_empty() { "0" } else { min };
+    let interval = match max {
+        None => std::format!("{{{min}}}"),
+        Some(max) => std::format!("{{{min},{max}}}"),
+    };
+
+    Some((interval, contents.chars().count() + 1))
+}
+
 fn escape_literal_regex_piece(s: &str) -> Cow<str> {
     let mut result = String::new();
 
@@ -171,4 +445,57 @@ mod tests {
         assert_eq!(add_missing_escape_chars_to_regex(r"a[b[]"), r"a[b\[]");
         assert_eq!(add_missing_escape_chars_to_regex(r"a[[]"), r"a[\[]");
     }
+
+    fn translate_pattern(pattern: &str) -> String {
+        translate_ere(&[RegexPiece::Pattern(pattern.to_owned())])
+    }
+
+    #[test]
+    fn test_translate_ere() {
+        assert_eq!(translate_pattern(r"^a(b|c)+$"), r"(?s)^a(b|c)+$");
+        assert_eq!(translate_pattern(r"[\d]\d\1\<"), r"(?s)[\\d]d\1\b(?=\w)");
+        assert_eq!(translate_pattern(r"[]a-][^]]"), r"(?s)[\]a-][^\]]");
+        assert_eq!(
+            translate_pattern(r"[[:alpha:][=e=]&&]"),
+            r"(?s)[[:alpha:]e\&\&]"
+        );
+        assert_eq!(
+            translate_pattern(r"a{2}b{,3}c{x}*d"),
+            r"(?s)a{2}b{0,3}c\{x\}*d"
+        );
+        assert_eq!(translate_pattern(r"(?<name>x)"), r"(?s)(?<name>x)");
+
+        assert_eq!(
+            translate_ere(&[
+                RegexPiece::Pattern(String::from("[")),
+                RegexPiece::Literal(String::from("a]")),
+                RegexPiece::Pattern(String::from("]*")),
+            ]),
+            r"(?s)[a\]]*"
+        );
+    }
+
+    #[test]
+    fn test_named_captures() -> Result<(), error::Error> {
+        let regex = Regex::from(vec![RegexPiece::Pattern(String::from(
+            r"(?<key>[a-z]+)=(?<value>[0-9]+)?(x)?",
+        ))]);
+
+        let Some(captures) = regex.captures("key=")? else {
+            panic!("expected a match");
+        };
+        assert_eq!(
+            captures.groups,
+            [Some("key="), Some("key"), None, None].map(|s| s.map(String::from))
+        );
+        assert_eq!(
+            captures.named_groups.get("key"),
+            Some(&Some(String::from("key")))
+        );
+        assert_eq!(captures.named_groups.get("value"), Some(&None));
+
+        assert!(regex.captures("nope")?.is_none());
+
+        Ok(())
+    }
 }
@@ -0,0 +1,135 @@
+//! Policies restricting what the commands run by a shell may do, for embedders running
+//! untrusted input.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use crate::error;
+
+/// A policy consulted by the shell before it executes external commands or opens files for
+/// writing, whether via redirection or on behalf of a builtin (e.g., `save`) or the
+/// interactive shell's history.
+///
+/// N.B. The shell can only enforce the policy on what it does itself. Restricting what an
+/// external command does once it's running (e.g., which files *it* writes, or whether it
+/// may use the network) requires OS-level mechanisms, which may be applied to each command
+/// in [`SandboxPolicy::prepare_command`] (e.g., installing a seccomp filter or a landlock
+/// ruleset via a pre-exec hook on Linux).
+pub trait SandboxPolicy: std::fmt::Debug + Send + Sync {
+    /// Returns whether the given external command may be executed. Denied commands fail
+    /// as not executable.
+    ///
+    /// # Arguments
+    ///
+    /// * `command` - The command about to be executed; its program is the resolved path of
+    ///   the executable.
+    fn allows_command(&self, command: &std::process::Command) -> bool {
+        let _ = command;
+        true
+    }
+
+    /// Returns whether the file at the given path may be opened for writing.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The absolute path of the file.
+    fn allows_write(&self, path: &Path) -> bool {
+        let _ = path;
+        true
+    }
+
+    /// Prepares an allowed external command for execution, e.g. by installing hooks that
+    /// confine the process once it's spawned.
+    ///
+    /// # Arguments
+    ///
+    /// * `command` - The command about to be executed.
+    fn prepare_command(&self, command: &mut std::process::Command) -> Result<(), error::Error> {
+        let _ = command;
+        Ok(())
+    }
+}
+
+/// A simple [`SandboxPolicy`] that denies executing a fixed set of commands and, optionally,
+/// denies writing to files outside a given directory.
+#[derive(Clone, Debug, Default)]
+pub struct RestrictedPolicy {
+    /// Commands that may not be executed, identified by either their name (e.g., `curl`) or
+    /// the full path of their executable (e.g., `/usr/bin/curl`).
+    pub denied_commands: HashSet<String>,
+    /// If set, the directory that files may be written within; writes to files anywhere
+    /// else are denied.
+    pub write_root: Option<PathBuf>,
+}
+
+impl SandboxPolicy for RestrictedPolicy {
+    fn allows_command(&self, command: &std::process::Command) -> bool {
+        let program = Path::new(command.get_program());
+
+        let denied_by_path = self
+            .denied_commands
+            .contains(program.to_string_lossy().as_ref());
+        let denied_by_name = program.file_name().is_some_and(|name| {
+            self.denied_commands
+                .contains(name.to_string_lossy().as_ref())
+        });
+
+        !denied_by_path && !denied_by_name
+    }
+
+    fn allows_write(&self, path: &Path) -> bool {
+        let Some(write_root) = &self.write_root else {
+            return true;
+        };
+
+        // Resolve symlinks and `..` components before comparing, so neither can be used to
+        // escape the root. If either path can't be resolved, we deny the write.
+        match (resolve_path(write_root), resolve_path(path)) {
+            (Some(write_root), Some(path)) => path.starts_with(write_root),
+            _ => false,
+        }
+    }
+}
+
+/// Resolves the given path to its canonical form; the file it refers to needn't exist,
+/// but its parent directory must.
+fn resolve_path(path: &Path) -> Option<PathBuf> {
+    if let Ok(resolved) = path.canonicalize() {
+        return Some(resolved);
+    }
+
+    let parent = path.parent()?.canonicalize().ok()?;
+    let file_name = path.file_name()?;
+
+    Some(parent.join(file_name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn denied_commands() {
+        let policy = RestrictedPolicy {
+            denied_commands: HashSet::from(["curl".to_owned(), "/opt/bin/tool".to_owned()]),
+            ..RestrictedPolicy::default()
+        };
+
+        assert!(!policy.allows_command(&std::process::Command::new("/usr/bin/curl")));
+        assert!(!policy.allows_command(&std::process::Command::new("/opt/bin/tool")));
+        assert!(policy.allows_command(&std::process::Command::new("/usr/bin/wget")));
+    }
+
+    #[test]
+    fn writes_outside_root() {
+        let root = std::env::temp_dir();
+        let policy = RestrictedPolicy {
+            write_root: Some(root.clone()),
+            ..RestrictedPolicy::default()
+        };
+
+        assert!(policy.allows_write(root.join("file.txt").as_path()));
+        assert!(!policy.allows_write(root.join("..").join("file.txt").as_path()));
+        assert!(!policy.allows_write(root.join("no-such-dir").join("file.txt").as_path()));
+    }
+}
@@ -0,0 +1,256 @@
+//! Facilities for saving the state of a shell session to a file, and later restoring it
+//! into a (possibly different) shell instance.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::env::EnvironmentLookup;
+use crate::variables::{ShellValue, ShellVariable};
+use crate::{compat, error, namedoptions, Shell};
+
+/// Version of the saved state format. Incremented whenever a change is made to the format
+/// that isn't backwards compatible.
+const FORMAT_VERSION: u32 = 1;
+
+/// A snapshot of the state of a shell session.
+#[derive(Deserialize, Serialize)]
+pub struct SavedState {
+    /// Version of the format the state was saved in.
+    version: u32,
+    /// The shell's working directory.
+    working_dir: PathBuf,
+    /// The shell's directory stack.
+    directory_stack: Vec<PathBuf>,
+    /// The shell's positional parameters.
+    positional_parameters: Vec<String>,
+    /// The shell's global variables, along with their attributes, keyed by name.
+    variables: BTreeMap<String, ShellVariable>,
+    /// The shell's functions, keyed by name.
+    functions: BTreeMap<String, SavedFunction>,
+    /// The shell's aliases, keyed by name.
+    aliases: BTreeMap<String, String>,
+    /// Values of the options that may be set via `set -o`, keyed by name.
+    set_options: BTreeMap<String, bool>,
+    /// Values of the options that may be set via `shopt`, keyed by name.
+    shopt_options: BTreeMap<String, bool>,
+    /// History number of the next command to be read at an interactive prompt.
+    history_number: usize,
+}
+
+/// A saved function definition.
+#[derive(Deserialize, Serialize)]
+struct SavedFunction {
+    /// Source of the function's body, in the form `() { ... }`.
+    body: String,
+    /// Whether the function is exported to child processes.
+    exported: bool,
+}
+
+/// The portion of a saved state common to all format versions.
+#[derive(Deserialize)]
+struct SavedStateHeader {
+    /// Version of the format the state was saved in.
+    version: u32,
+}
+
+impl SavedState {
+    /// Captures the current state of the given shell.
+    ///
+    /// # Arguments
+    ///
+    /// * `shell` - The shell to capture the state of.
+    pub fn capture(shell: &Shell) -> Self {
+        // N.B. Dynamic values (e.g., `$RANDOM`) are owned by the shell and recreated by
+        // each instance; they're not part of the saved state.
+        let variables = shell
+            .env
+            .iter_using_policy(EnvironmentLookup::OnlyInGlobal)
+            .filter(|(_, var)| !matches!(var.value(), ShellValue::Dynamic(_)))
+            .map(|(name, var)| (name.clone(), var.clone()))
+            .collect();
+
+        let functions = shell
+            .funcs
+            .iter()
+            .map(|(name, registration)| {
+                let body =
+                    brush_parser::format::format_function_body(&registration.definition.body);
+
+                (
+                    name.clone(),
+                    SavedFunction {
+                        body: std::format!("() {body}"),
+                        exported: registration.is_exported(),
+                    },
+                )
+            })
+            .collect();
+
+        Self {
+            version: FORMAT_VERSION,
+            working_dir: shell.working_dir.clone(),
+            directory_stack: shell.directory_stack.clone(),
+            positional_parameters: shell.positional_parameters.clone(),
+            variables,
+            functions,
+            aliases: shell
+                .aliases
+                .iter()
+                .map(|(name, value)| (name.clone(), value.clone()))
+                .collect(),
+            set_options: compat::set_o_option_values(shell)
+                .into_iter()
+                .map(|(name, value)| (name.to_owned(), value))
+                .collect(),
+            shopt_options: compat::shopt_option_values(shell)
+                .into_iter()
+                .map(|(name, value)| (name.to_owned(), value))
+                .collect(),
+            history_number: shell.history_number,
+        }
+    }
+
+    /// Restores this state into the given shell. Variables that are read-only in the
+    /// shell, and options the shell doesn't recognize, are left untouched.
+    ///
+    /// # Arguments
+    ///
+    /// * `shell` - The shell to restore the state into.
+    pub fn restore(self, shell: &mut Shell) -> Result<(), error::Error> {
+        for (name, option_value) in &self.set_options {
+            if let Some(definition) = namedoptions::SET_O_OPTIONS.get(name.as_str()) {
+                (definition.setter)(&mut shell.options, *option_value);
+            }
+        }
+
+        for (name, option_value) in &self.shopt_options {
+            if let Some(definition) = namedoptions::SHOPT_OPTIONS.get(name.as_str()) {
+                (definition.setter)(&mut shell.options, *option_value);
+            }
+        }
+
+        for (name, var) in self.variables {
+            let is_readonly = shell
+                .env
+                .get(name.as_str())
+                .is_some_and(|(_, existing)| existing.is_readonly());
+
+            if !is_readonly {
+                shell.env.set_global(name, var)?;
+            }
+        }
+
+        for (name, function) in self.functions {
+            let Some(definition) =
+                shell.parse_exported_function(name.as_str(), function.body.as_str())
+            else {
+                tracing::warn!("error restoring function definition for `{name}'");
+                continue;
+            };
+
+            shell.funcs.update(name.clone(), Arc::new(definition));
+            if function.exported {
+                if let Some(registration) = shell.funcs.get_mut(name.as_str()) {
+                    registration.export();
+                }
+            }
+        }
+
+        shell.aliases.extend(self.aliases);
+        shell.positional_parameters = self.positional_parameters;
+        shell.directory_stack = self.directory_stack;
+        shell.history_number = self.history_number;
+        shell.set_working_dir(self.working_dir.as_path())?;
+
+        Ok(())
+    }
+
+    /// Writes this state to the file at the given path.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the file to write.
+    pub fn save_to_file(&self, path: &Path) -> Result<(), error::Error> {
+        let file = std::io::BufWriter::new(std::fs::File::create(path)?);
+        serde_json::to_writer_pretty(file, self)?;
+        Ok(())
+    }
+
+    /// Reads a state previously written to the file at the given path.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the file to read.
+    pub fn load_from_file(path: &Path) -> Result<Self, error::Error> {
+        let contents = std::fs::read_to_string(path)?;
+
+        // Check the version before interpreting the rest of the contents, whose format
+        // may differ between versions.
+        let header: SavedStateHeader = serde_json::from_str(contents.as_str())?;
+        if header.version != FORMAT_VERSION {
+            return Err(error::Error::UnsupportedSavedStateVersion(header.version));
+        }
+
+        Ok(serde_json::from_str(contents.as_str())?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+
+    #[tokio::test]
+    async fn test_round_trip() -> Result<()> {
+        let mut shell = Shell::new(&crate::CreateOptions::default()).await?;
+        let params = shell.default_exec_params();
+
+        let script = std::format!(
+            "cd '{}'; declare -i n=3; arr=(a b); export e=x; f() {{ return 7; }}; \
+             alias a='echo hi'; set -o noglob; shopt -s extglob",
+            std::env::temp_dir().to_string_lossy()
+        );
+        let result = shell.run_string(script, &params).await?;
+        assert_eq!(result.exit_code, 0);
+        shell.history_number = 42;
+
+        let saved = serde_json::to_string(&SavedState::capture(&shell))?;
+
+        let mut restored = Shell::new(&crate::CreateOptions::default()).await?;
+        serde_json::from_str::<SavedState>(saved.as_str())?.restore(&mut restored)?;
+
+        assert_eq!(restored.working_dir, shell.working_dir);
+        assert_eq!(restored.history_number, 42);
+        assert_eq!(restored.aliases.get("a"), shell.aliases.get("a"));
+        assert_eq!(
+            compat::set_o_option_values(&restored),
+            compat::set_o_option_values(&shell)
+        );
+        assert_eq!(
+            compat::shopt_option_values(&restored),
+            compat::shopt_option_values(&shell)
+        );
+
+        for (name, integer, exported) in [("n", true, false), ("e", false, true)] {
+            let (_, var) = restored
+                .env
+                .get(name)
+                .ok_or_else(|| anyhow::anyhow!("{name} not restored"))?;
+            assert_eq!(var.is_treated_as_integer(), integer, "variable: {name}");
+            assert_eq!(var.is_exported(), exported, "variable: {name}");
+        }
+
+        let params = restored.default_exec_params();
+        let result = restored
+            .run_string(
+                "[[ $n == 3 && ${arr[1]} == b && $e == x ]] || exit 1; f".to_owned(),
+                &params,
+            )
+            .await?;
+        assert_eq!(result.exit_code, 7);
+
+        Ok(())
+    }
+}
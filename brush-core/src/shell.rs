@@ -1,21 +1,32 @@
 use std::borrow::Cow;
-use std::collections::{HashMap, VecDeque};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
 use std::fmt::Write as _;
 use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
 use crate::arithmetic::Evaluatable;
+use crate::cancellation::CancellationToken;
 use crate::env::{EnvironmentLookup, EnvironmentScope, ShellEnvironment};
 use crate::interp::{self, Execute, ExecutionParameters, ExecutionResult};
 use crate::options::RuntimeOptions;
 use crate::sys::fs::PathExt;
-use crate::variables::{self, ShellValue, ShellVariable};
+use crate::variables::{self, DynamicValue, ShellValue, ShellVariable};
 use crate::{
-    builtins, commands, completion, env, error, expansion, functions, jobs, keywords, openfiles,
-    patterns, prompt, sys::users, traps,
+    abbreviations, audit, builtins, commands, completion, dirhistory, env, error, expansion,
+    frecency, functions, jobs, keywords, openfiles, patterns, prompt, sys, sys::users, terminal,
+    traps,
 };
-use crate::{pathcache, trace_categories};
+use crate::{i18n, pathcache, pathsearch, profiling, sandbox, trace_categories, ProcessContainer};
+
+/// Default maximum depth to which functions, `eval`, `source`, and command substitutions
+/// may be nested. Each level of nesting consumes native stack, so this is kept low enough
+/// that the limit is reached before the stack is exhausted.
+pub const DEFAULT_MAX_RECURSION_DEPTH: usize = 1000;
+
+/// How often to check for more output while capturing the output of a running program.
+const CAPTURED_OUTPUT_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(5);
 
 /// Represents an instance of a shell.
 pub struct Shell {
@@ -37,6 +48,8 @@ pub struct Shell {
     pub jobs: jobs::JobManager,
     /// Shell aliases.
     pub aliases: HashMap<String, String>,
+    /// Abbreviations expanded as they're typed at an interactive prompt, keyed by name.
+    pub abbreviations: HashMap<String, abbreviations::Abbreviation>,
 
     //
     // Additional state
@@ -46,6 +59,24 @@ pub struct Shell {
     /// Clone depth from the original ancestor shell.
     pub depth: usize,
 
+    /// Number of enclosing contexts (e.g., `if` conditions) in which `set -e` is ignored.
+    pub errexit_ignore_depth: usize,
+
+    /// Depth to which functions, `eval`, `source`, and command substitutions are currently
+    /// nested, including in ancestor shells.
+    pub(crate) recursion_depth: usize,
+
+    /// Number of enclosing `eval`s, `source`s, and command substitutions; each level is
+    /// reflected in `set -x` output by repeating the first character of `PS4`.
+    pub(crate) trace_nesting_level: usize,
+
+    /// Token used to interrupt the commands being run; shared with subshells, except for
+    /// those running background jobs.
+    pub(crate) cancellation_token: CancellationToken,
+
+    /// Monitors the terminal for resizes, which are reflected in `LINES` and `COLUMNS`.
+    window_size_monitor: terminal::WindowSizeMonitor,
+
     /// Positional parameters ($1 and beyond)
     pub positional_parameters: Vec<String>,
 
@@ -64,20 +95,78 @@ pub struct Shell {
     /// Directory stack used by pushd et al.
     pub directory_stack: Vec<PathBuf>,
 
+    /// History of the shell's working directories, navigable backward and forward.
+    pub directory_history: dirhistory::DirectoryHistory,
+
     /// Current line number being processed.
     pub current_line_number: u32,
 
+    /// Offset added to the line numbers of the commands being executed to yield the line
+    /// numbers reported for them (e.g., in `$LINENO`).
+    pub line_number_offset: u32,
+
+    /// Number of the next command to be read at an interactive prompt, as displayed in
+    /// prompts (`\#`).
+    pub command_number: usize,
+
+    /// History number of the next command to be read at an interactive prompt, as displayed
+    /// in prompts (`\!`); an embedder that loads previously saved history should advance it
+    /// past the loaded entries.
+    pub history_number: usize,
+
     /// Completion configuration.
     pub completion_config: completion::Config,
 
+    /// Key sequences (in readline's notation, e.g. `\C-x`) bound to named editing functions
+    /// with `bind`, in the order they were bound; it's up to the input backend to honor them.
+    pub key_bindings: Vec<(String, String)>,
+
     /// Shell built-in commands.
     pub builtins: HashMap<String, builtins::Registration>,
 
     /// Shell program location cache.
     pub program_location_cache: pathcache::PathCache,
+
+    /// Cache of the contents of directories searched for commands, used if the
+    /// `path_cache` option is enabled; shared by all shells in the session.
+    pub path_search_cache: Arc<std::sync::Mutex<pathsearch::PathSearchCache>>,
+
+    /// Profiler recording time spent in shell functions, if profiling is enabled.
+    pub function_profiler: Option<profiling::FunctionProfiler>,
+
+    /// Container that external commands spawned by the shell are placed in, if any.
+    pub process_container: Option<ProcessContainer>,
+
+    /// Policy restricting the commands the shell executes and the files it writes, if any.
+    pub sandbox_policy: Option<Arc<dyn sandbox::SandboxPolicy>>,
+
+    /// Sinks that records of executed commands are delivered to.
+    pub audit_sinks: Vec<Arc<dyn audit::AuditSink>>,
+
+    /// Store recording visits to directories, for the `j` builtin; if not provided, visits
+    /// aren't recorded.
+    pub directory_rank_store: Option<Arc<dyn frecency::DirectoryRankStore>>,
+
+    /// Optionally provides a formatter used to describe syntax errors; if not provided,
+    /// a terse description of each error is displayed.
+    pub syntax_error_formatter: Option<Arc<dyn error::SyntaxErrorFormatter>>,
+
+    /// Optionally, the longest to wait for the command substitutions in a prompt while
+    /// composing it; any still running once it elapses are abandoned, and their output
+    /// replaced by a placeholder.
+    pub prompt_timeout: Option<std::time::Duration>,
+
+    /// Time by which command substitutions must complete (e.g., while composing a prompt),
+    /// if any.
+    pub(crate) command_substitution_deadline: Option<tokio::time::Instant>,
+
+    /// Cached outputs of external prompt commands.
+    pub(crate) prompt_command_cache: prompt::PromptCommandCache,
 }
 
 impl Clone for Shell {
+    // N.B. Variables and functions are shared with the clone until either is modified, so
+    // forking a shell (e.g., for a subshell) stays cheap even with a large environment.
     fn clone(&self) -> Self {
         Self {
             traps: self.traps.clone(),
@@ -88,6 +177,7 @@ impl Clone for Shell {
             options: self.options.clone(),
             jobs: jobs::JobManager::new(),
             aliases: self.aliases.clone(),
+            abbreviations: self.abbreviations.clone(),
             last_exit_status: self.last_exit_status,
             positional_parameters: self.positional_parameters.clone(),
             shell_name: self.shell_name.clone(),
@@ -95,15 +185,45 @@ impl Clone for Shell {
             function_call_stack: self.function_call_stack.clone(),
             script_call_stack: self.script_call_stack.clone(),
             directory_stack: self.directory_stack.clone(),
+            directory_history: self.directory_history.clone(),
             current_line_number: self.current_line_number,
+            line_number_offset: self.line_number_offset,
+            command_number: self.command_number,
+            history_number: self.history_number,
             completion_config: self.completion_config.clone(),
+            key_bindings: self.key_bindings.clone(),
             builtins: self.builtins.clone(),
             program_location_cache: self.program_location_cache.clone(),
+            path_search_cache: self.path_search_cache.clone(),
+            // N.B. Time spent in subshells is attributed to the caller in the parent shell.
+            function_profiler: None,
+            process_container: self.process_container.clone(),
+            sandbox_policy: self.sandbox_policy.clone(),
+            audit_sinks: self.audit_sinks.clone(),
+            directory_rank_store: self.directory_rank_store.clone(),
+            syntax_error_formatter: self.syntax_error_formatter.clone(),
+            prompt_timeout: self.prompt_timeout,
+            command_substitution_deadline: self.command_substitution_deadline,
+            prompt_command_cache: self.prompt_command_cache.clone(),
             depth: self.depth + 1,
+            errexit_ignore_depth: self.errexit_ignore_depth,
+            recursion_depth: self.recursion_depth,
+            trace_nesting_level: self.trace_nesting_level,
+            cancellation_token: self.cancellation_token.clone(),
+            window_size_monitor: self.window_size_monitor.clone(),
         }
     }
 }
 
+/// A snapshot of a shell's variables, functions, and options, taken with
+/// [`Shell::checkpoint`].
+#[derive(Clone)]
+pub struct ShellCheckpoint {
+    env: ShellEnvironment,
+    funcs: functions::FunctionEnv,
+    options: RuntimeOptions,
+}
+
 impl AsRef<Shell> for Shell {
     fn as_ref(&self) -> &Shell {
         self
@@ -139,22 +259,62 @@ pub struct CreateOptions {
     pub no_rc: bool,
     /// Whether to skip inheriting environment variables from the calling process.
     pub do_not_inherit_env: bool,
+    /// Whether to import functions exported to the shell via the environment. This is
+    /// opt-in, since it lets the calling process define commands the shell runs.
+    pub import_functions: bool,
     /// Whether the shell is in POSIX compliance mode.
     pub posix: bool,
     /// Whether to print commands and arguments as they are read.
     pub print_commands_and_arguments: bool,
     /// Whether commands are being read from stdin.
     pub read_commands_from_stdin: bool,
+    /// Whether commands are being run from a string (i.e., given with `-c`).
+    pub read_commands_from_command_string: bool,
     /// The name of the shell.
     pub shell_name: Option<String>,
     /// Optionally provides a display string describing the version and variant of the shell.
     pub shell_product_display_str: Option<String>,
+    /// Optionally provides details about the shell product, exposed to scripts via the
+    /// `BRUSH_VERSION`, `BRUSH_VERSINFO`, and `BRUSH_FEATURES` variables.
+    pub product_info: Option<ProductInfo>,
     /// Whether to run in maximal POSIX sh compatibility mode.
     pub sh_mode: bool,
     /// Whether to print verbose output.
     pub verbose: bool,
     /// Maximum function call depth.
     pub max_function_call_depth: Option<usize>,
+    /// Maximum depth to which functions, `eval`, `source`, and command substitutions may be
+    /// nested; if not specified, [`DEFAULT_MAX_RECURSION_DEPTH`] is used.
+    pub max_recursion_depth: Option<usize>,
+    /// Whether to profile the time spent in shell functions.
+    pub profile_functions: bool,
+    /// Optionally provides a container (e.g., a cgroup or Job Object) that external commands
+    /// spawned by the shell are placed in, so that the embedder can limit the resources they
+    /// use and reliably terminate them along with their descendants.
+    pub process_container: Option<ProcessContainer>,
+    /// Optionally provides a policy restricting the commands the shell executes and the files
+    /// it writes, e.g. for running untrusted input.
+    pub sandbox_policy: Option<Arc<dyn sandbox::SandboxPolicy>>,
+    /// Sinks that records of all commands executed by the shell are delivered to, along
+    /// with their exit status; e.g., for audit logging separate from history.
+    pub audit_sinks: Vec<Arc<dyn audit::AuditSink>>,
+    /// Optionally provides a store recording the directories the shell visits, ranked by
+    /// frequency and recency, for the `j` builtin.
+    pub directory_rank_store: Option<Arc<dyn frecency::DirectoryRankStore>>,
+}
+
+/// Details about the shell product, exposed to scripts so they can detect its capabilities.
+#[derive(Clone, Debug, Default)]
+pub struct ProductInfo {
+    /// The version of the product (e.g., `0.2.14`).
+    pub version: String,
+    /// Identifies the specific version of sources used to build the product (e.g., a git
+    /// commit hash).
+    pub source_version: String,
+    /// The build profile used to build the product (e.g., `release`).
+    pub build_profile: String,
+    /// The optional features enabled in the product.
+    pub features: Vec<String>,
 }
 
 /// Represents an active shell function call.
@@ -164,6 +324,29 @@ pub struct FunctionCall {
     function_name: String,
     /// The definition of the invoked function.
     function_definition: Arc<brush_parser::ast::FunctionDefinition>,
+    /// The line number from which the function was invoked.
+    call_line_number: u32,
+    /// The caller's line number offset, restored when the function returns.
+    caller_line_number_offset: u32,
+    /// Options to restore when the function returns, if requested via `local -`.
+    saved_options: Option<RuntimeOptions>,
+}
+
+impl FunctionCall {
+    /// Returns the name of the function invoked.
+    pub fn function_name(&self) -> &str {
+        self.function_name.as_str()
+    }
+
+    /// Returns the source in which the invoked function was defined.
+    pub fn source(&self) -> &str {
+        self.function_definition.source.as_str()
+    }
+
+    /// Returns the line number from which the function was invoked.
+    pub fn call_line_number(&self) -> u32 {
+        self.call_line_number
+    }
 }
 
 impl Shell {
@@ -173,6 +356,14 @@ impl Shell {
     ///
     /// * `options` - The options to use when creating the shell.
     pub async fn new(options: &CreateOptions) -> Result<Shell, error::Error> {
+        // Take note of which signals we were started with ignored, before we start handling
+        // any; they stay ignored for the commands we run. As in bash, interactive shells
+        // also ignore SIGQUIT.
+        sys::signal::record_startup_dispositions();
+        if options.interactive {
+            sys::signal::mask_sigquit()?;
+        }
+
         // Instantiate the shell with some defaults.
         let mut shell = Shell {
             traps: traps::TrapHandlerConfig::default(),
@@ -183,6 +374,7 @@ impl Shell {
             options: RuntimeOptions::defaults_from(options),
             jobs: jobs::JobManager::new(),
             aliases: HashMap::default(),
+            abbreviations: HashMap::default(),
             last_exit_status: 0,
             positional_parameters: vec![],
             shell_name: options.shell_name.clone(),
@@ -190,23 +382,123 @@ impl Shell {
             function_call_stack: VecDeque::new(),
             script_call_stack: VecDeque::new(),
             directory_stack: vec![],
+            directory_history: dirhistory::DirectoryHistory::default(),
             current_line_number: 0,
+            line_number_offset: 0,
+            command_number: 1,
+            history_number: 1,
             completion_config: completion::Config::default(),
+            key_bindings: vec![],
             builtins: builtins::get_default_builtins(options),
             program_location_cache: pathcache::PathCache::default(),
+            path_search_cache: Arc::default(),
+            function_profiler: options
+                .profile_functions
+                .then(profiling::FunctionProfiler::default),
+            process_container: options.process_container.clone(),
+            sandbox_policy: options.sandbox_policy.clone(),
+            audit_sinks: options.audit_sinks.clone(),
+            directory_rank_store: options.directory_rank_store.clone(),
+            syntax_error_formatter: None,
+            prompt_timeout: None,
+            command_substitution_deadline: None,
+            prompt_command_cache: prompt::PromptCommandCache::default(),
             depth: 0,
+            errexit_ignore_depth: 0,
+            recursion_depth: 0,
+            trace_nesting_level: 0,
+            cancellation_token: CancellationToken::default(),
+            window_size_monitor: terminal::WindowSizeMonitor::default(),
         };
 
         // TODO: Without this a script that sets extglob will fail because we
         // parse the entire script with the same settings.
         shell.options.extended_globbing = true;
 
+        // Start the directory history off with our initial working directory.
+        shell.directory_history.record(&shell.working_dir);
+
+        // As with bash (courtesy of readline), interactive shells track the terminal's size.
+        if shell.options.interactive {
+            shell.window_size_monitor.ensure_started();
+            shell.update_window_size_vars()?;
+        }
+
+        // Import any functions exported by the calling process, if asked to.
+        if options.import_functions {
+            shell.import_functions_from_env()?;
+        }
+
         // Load profiles/configuration.
         shell.load_config(options).await?;
 
         Ok(shell)
     }
 
+    /// Imports functions exported to the shell via the environment, in the encoding used by
+    /// bash (i.e., `BASH_FUNC_name%%=() { ... }`). The variables carrying them are removed.
+    fn import_functions_from_env(&mut self) -> Result<(), error::Error> {
+        let exported_funcs: Vec<_> = self
+            .env
+            .iter()
+            .filter_map(|(var_name, var)| {
+                let name = functions::exported_function_name(var_name)?;
+                let value = var.value().to_cow_string();
+                value
+                    .starts_with("() {")
+                    .then(|| (var_name.clone(), name.to_owned(), value.into_owned()))
+            })
+            .collect();
+
+        for (var_name, name, value) in exported_funcs {
+            self.env.unset(var_name.as_str())?;
+
+            if let Some(definition) = self.parse_exported_function(name.as_str(), value.as_str()) {
+                self.funcs.update(name.clone(), Arc::new(definition));
+                if let Some(registration) = self.funcs.get_mut(name.as_str()) {
+                    registration.export();
+                }
+            } else {
+                tracing::warn!("error importing function definition for `{name}'");
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Parses the value of an environment variable exporting the named function. To avoid
+    /// executing arbitrary code smuggled in through the environment, the value must consist
+    /// of exactly one definition of that function and nothing else.
+    pub(crate) fn parse_exported_function(
+        &self,
+        name: &str,
+        value: &str,
+    ) -> Option<brush_parser::ast::FunctionDefinition> {
+        let program = self.parse_string(std::format!("{name} {value}")).ok()?;
+
+        let [brush_parser::ast::CompoundList(items)] = program.complete_commands.as_slice() else {
+            return None;
+        };
+        let [brush_parser::ast::CompoundListItem(
+            and_or,
+            brush_parser::ast::SeparatorOperator::Sequence,
+        )] = items.as_slice()
+        else {
+            return None;
+        };
+
+        if !and_or.additional.is_empty() || and_or.first.bang || and_or.first.timed.is_some() {
+            return None;
+        }
+
+        match and_or.first.seq.as_slice() {
+            [brush_parser::ast::Command::Function(definition)] if definition.fname == name => {
+                Some(definition.clone())
+            }
+            _ => None,
+        }
+    }
+
     fn initialize_vars(options: &CreateOptions) -> Result<ShellEnvironment, error::Error> {
         let mut env = ShellEnvironment::new();
 
@@ -220,23 +512,44 @@ impl Shell {
         }
 
         // Set some additional ones.
-        #[cfg(unix)]
-        {
+        if users::SUPPORTED {
             let mut euid_var = ShellVariable::new(ShellValue::String(format!(
                 "{}",
-                uzers::get_effective_uid()
+                users::get_effective_uid()?
             )));
             euid_var.set_readonly();
             env.set_global("EUID", euid_var)?;
         }
 
-        let mut random_var = ShellVariable::new(ShellValue::Random);
+        // Dynamic vars
+        let mut random_var = ShellVariable::new(ShellValue::Dynamic(DynamicValue::random()));
         random_var.hide_from_enumeration();
         random_var.treat_as_integer();
         env.set_global("RANDOM", random_var)?;
 
+        let mut srandom_var = ShellVariable::new(ShellValue::Dynamic(DynamicValue::SecureRandom));
+        srandom_var.hide_from_enumeration();
+        srandom_var.treat_as_integer();
+        env.set_global("SRANDOM", srandom_var)?;
+
+        let mut seconds_var = ShellVariable::new(ShellValue::Dynamic(DynamicValue::seconds()));
+        seconds_var.hide_from_enumeration();
+        seconds_var.treat_as_integer();
+        env.set_global("SECONDS", seconds_var)?;
+
+        let mut epoch_seconds_var =
+            ShellVariable::new(ShellValue::Dynamic(DynamicValue::EpochSeconds));
+        epoch_seconds_var.hide_from_enumeration();
+        env.set_global("EPOCHSECONDS", epoch_seconds_var)?;
+
+        let mut epoch_realtime_var =
+            ShellVariable::new(ShellValue::Dynamic(DynamicValue::EpochRealTime));
+        epoch_realtime_var.hide_from_enumeration();
+        env.set_global("EPOCHREALTIME", epoch_realtime_var)?;
+
         // Parsing and completion vars
         env.set_global("IFS", ShellVariable::new(" \t\n".into()))?;
+        env.set_global("PS4", ShellVariable::new("+ ".into()))?;
         env.set_global(
             "COMP_WORDBREAKS",
             ShellVariable::new(" \t\n\"\'@><=;|&(:".into()),
@@ -285,6 +598,33 @@ impl Shell {
         pwd_var.export();
         env.set_global("PWD", pwd_var)?;
 
+        // Set brush-specific version info.
+        if let Some(product_info) = &options.product_info {
+            env.set_global(
+                "BRUSH_VERSION",
+                ShellVariable::new(product_info.version.as_str().into()),
+            )?;
+
+            let mut version_parts = product_info.version.split(['.', '-', '+']);
+            let mut versinfo_var = ShellVariable::new(ShellValue::indexed_array_from_slice(
+                [
+                    version_parts.next().unwrap_or("0"),
+                    version_parts.next().unwrap_or("0"),
+                    version_parts.next().unwrap_or("0"),
+                    product_info.source_version.as_str(),
+                    product_info.build_profile.as_str(),
+                ]
+                .as_slice(),
+            ));
+            versinfo_var.set_readonly();
+            env.set_global("BRUSH_VERSINFO", versinfo_var)?;
+
+            env.set_global(
+                "BRUSH_FEATURES",
+                ShellVariable::new(product_info.features.join(" ").into()),
+            )?;
+        }
+
         // Set version info.
         if !options.sh_mode {
             const BASH_MAJOR: u32 = 5;
@@ -430,6 +770,75 @@ impl Shell {
         args: &[S],
         params: &ExecutionParameters,
     ) -> Result<ExecutionResult, error::Error> {
+        let opened_file = self.open_file_to_source(path, params)?;
+
+        let source_info = brush_parser::SourceInfo {
+            source: path.to_string_lossy().to_string(),
+        };
+
+        let positional_parameters = args.iter().map(|s| s.as_ref().to_owned()).collect();
+        let shell_name = source_info.source.clone();
+
+        self.source_file(
+            opened_file,
+            &source_info,
+            Some(positional_parameters),
+            Some(shell_name),
+            params,
+        )
+        .await
+    }
+
+    /// Sources the given file in the current execution context, as the `.` builtin does:
+    /// `$0` is left unchanged, and the positional parameters are only replaced if arguments
+    /// are provided. In that case, the originals are restored afterwards unless the file
+    /// itself replaced them (e.g., via `set`).
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to the file to source.
+    /// * `args` - The arguments to pass to the file as positional parameters, if any.
+    /// * `params` - Execution parameters.
+    pub(crate) async fn source_in_current_context<S: AsRef<str>>(
+        &mut self,
+        path: &Path,
+        args: &[S],
+        params: &ExecutionParameters,
+    ) -> Result<ExecutionResult, error::Error> {
+        let opened_file = self.open_file_to_source(path, params)?;
+
+        let source_info = brush_parser::SourceInfo {
+            source: path.to_string_lossy().to_string(),
+        };
+
+        let replaced_positional_parameters = if args.is_empty() {
+            None
+        } else {
+            let args: Vec<_> = args.iter().map(|s| s.as_ref().to_owned()).collect();
+            let saved = std::mem::replace(&mut self.positional_parameters, args.clone());
+            Some((args, saved))
+        };
+
+        self.trace_nesting_level += 1;
+        let result = self
+            .source_file(opened_file, &source_info, None, None, params)
+            .await;
+        self.trace_nesting_level -= 1;
+
+        if let Some((args, saved_positional_parameters)) = replaced_positional_parameters {
+            if self.positional_parameters == args {
+                self.positional_parameters = saved_positional_parameters;
+            }
+        }
+
+        result
+    }
+
+    fn open_file_to_source(
+        &self,
+        path: &Path,
+        params: &ExecutionParameters,
+    ) -> Result<openfiles::OpenFile, error::Error> {
         tracing::debug!("sourcing: {}", path.display());
         let opened_file: openfiles::OpenFile = self
             .open_file(path, params)
@@ -442,12 +851,7 @@ impl Shell {
             ));
         }
 
-        let source_info = brush_parser::SourceInfo {
-            source: path.to_string_lossy().to_string(),
-        };
-
-        self.source_file(opened_file, &source_info, args, params)
-            .await
+        Ok(opened_file)
     }
 
     /// Source the given file as a shell script, returning the execution result.
@@ -456,53 +860,121 @@ impl Shell {
     ///
     /// * `file` - The file to source.
     /// * `source_info` - Information about the source of the script.
-    /// * `args` - The arguments to pass to the script as positional parameters.
+    /// * `positional_parameters` - The positional parameters to use while running the
+    ///   script, if they should be replaced.
+    /// * `shell_name` - The value of `$0` to use while running the script, if it should be
+    ///   replaced.
     /// * `params` - Execution parameters.
-    async fn source_file<F: Read, S: AsRef<str>>(
+    async fn source_file<F: Read>(
         &mut self,
         file: F,
         source_info: &brush_parser::SourceInfo,
-        args: &[S],
+        positional_parameters: Option<Vec<String>>,
+        shell_name: Option<String>,
         params: &ExecutionParameters,
     ) -> Result<ExecutionResult, error::Error> {
-        let mut reader = std::io::BufReader::new(file);
-        let mut parser =
-            brush_parser::Parser::new(&mut reader, &self.parser_options(), source_info);
-
-        tracing::debug!(target: trace_categories::PARSE, "Parsing sourced file: {}", source_info.source);
-        let parse_result = parser.parse();
-
-        let mut other_positional_parameters = args.iter().map(|s| s.as_ref().to_owned()).collect();
-        let mut other_shell_name = Some(source_info.source.clone());
-
-        // TODO: Find a cleaner way to change args.
-        std::mem::swap(&mut self.shell_name, &mut other_shell_name);
-        std::mem::swap(
-            &mut self.positional_parameters,
-            &mut other_positional_parameters,
-        );
+        // N.B. We read the whole file up front so its contents are available to describe
+        // any syntax errors.
+        let mut contents = vec![];
+        std::io::BufReader::new(file).read_to_end(&mut contents)?;
+        let source_text = String::from_utf8_lossy(&contents);
+
+        // Swap in the requested positional parameters and shell name, if any; we'll restore
+        // the originals afterwards.
+        let saved_positional_parameters = positional_parameters
+            .map(|params| std::mem::replace(&mut self.positional_parameters, params));
+        let saved_shell_name =
+            shell_name.map(|name| std::mem::replace(&mut self.shell_name, Some(name)));
 
         self.script_call_stack
             .push_front(source_info.source.clone());
         self.update_bash_source_var()?;
 
-        let result = self
-            .run_parsed_result(parse_result, source_info, params)
-            .await;
+        // Line numbers within the file are reported relative to its start.
+        let saved_line_number = self.current_line_number;
+        let saved_line_number_offset = std::mem::replace(&mut self.line_number_offset, 0);
+
+        let result = match self.enter_nested_evaluation() {
+            Ok(()) => {
+                let result = self
+                    .run_source_text(&source_text, source_info, params)
+                    .await;
+                self.leave_nested_evaluation();
+                result
+            }
+            Err(e) => Err(e),
+        };
+
+        self.line_number_offset = saved_line_number_offset;
+        self.set_current_line_number(saved_line_number)?;
 
         self.script_call_stack.pop_front();
         self.update_bash_source_var()?;
 
         // Restore.
-        std::mem::swap(&mut self.shell_name, &mut other_shell_name);
-        std::mem::swap(
-            &mut self.positional_parameters,
-            &mut other_positional_parameters,
-        );
+        if let Some(saved_shell_name) = saved_shell_name {
+            self.shell_name = saved_shell_name;
+        }
+        if let Some(saved_positional_parameters) = saved_positional_parameters {
+            self.positional_parameters = saved_positional_parameters;
+        }
 
         result
     }
 
+    /// Parses and runs the given text of a script one complete command at a time, so that
+    /// aliases defined by earlier commands take effect for later ones. Stops at the first
+    /// syntax error, or once the script exits or returns.
+    ///
+    /// # Arguments
+    ///
+    /// * `source_text` - The text of the script.
+    /// * `source_info` - Information about the source of the script.
+    /// * `params` - Execution parameters.
+    async fn run_source_text(
+        &mut self,
+        source_text: &str,
+        source_info: &brush_parser::SourceInfo,
+        params: &ExecutionParameters,
+    ) -> Result<ExecutionResult, error::Error> {
+        tracing::debug!(target: trace_categories::PARSE, "Parsing sourced file: {}", source_info.source);
+
+        let mut parser =
+            brush_parser::StreamingParser::new(&self.program_parser_options(), source_info);
+        let mut lines = source_text.split_inclusive('\n');
+        let mut result = ExecutionResult::success();
+
+        loop {
+            let (parse_result, at_end) = match lines.next() {
+                Some(line) => {
+                    parser.feed(line);
+                    (parser.poll(), false)
+                }
+                None => (parser.finish(), true),
+            };
+
+            let parse_result = match parse_result {
+                brush_parser::StreamingParseResult::Complete { program, .. } => Ok(program),
+                brush_parser::StreamingParseResult::Error { error, .. } => Err(error),
+                brush_parser::StreamingParseResult::NeedMoreInput if at_end => break,
+                brush_parser::StreamingParseResult::NeedMoreInput => continue,
+            };
+
+            let syntax_error = parse_result.is_err();
+            result = self
+                .run_parsed_result(parse_result, source_info, source_text, params)
+                .await?;
+
+            if syntax_error || result.exit_shell || result.return_from_function_or_script {
+                break;
+            }
+
+            parser.set_options(&self.program_parser_options());
+        }
+
+        Ok(result)
+    }
+
     /// Invokes a function defined in this shell, returning the resulting exit status.
     ///
     /// # Arguments
@@ -521,6 +993,7 @@ impl Shell {
             .ok_or_else(|| error::Error::FunctionNotFound(name.to_owned()))?;
 
         let func = func_registration.definition.clone();
+        let line_number_offset = func_registration.line_number_offset();
 
         let context = commands::ExecutionContext {
             shell: self,
@@ -533,7 +1006,9 @@ impl Shell {
             .map(|s| commands::CommandArg::String(String::from(*s)))
             .collect::<Vec<_>>();
 
-        match commands::invoke_shell_function(func, context, &command_args).await? {
+        match commands::invoke_shell_function(func, line_number_offset, context, &command_args)
+            .await?
+        {
             commands::CommandSpawnResult::SpawnedProcess(_) => {
                 error::unimp("child spawned from function invocation")
             }
@@ -548,6 +1023,8 @@ impl Shell {
     }
 
     /// Executes the given string as a shell program, returning the resulting exit status.
+    /// The string is treated as the next chunk of the shell's input, following any strings
+    /// previously run this way.
     ///
     /// # Arguments
     ///
@@ -558,17 +1035,191 @@ impl Shell {
         command: String,
         params: &ExecutionParameters,
     ) -> Result<ExecutionResult, error::Error> {
-        // TODO: Actually track line numbers; this is something of a hack, assuming each time
-        // this function is invoked we are on the next line of the input. For one thing,
-        // each string we run could be multiple lines.
-        self.current_line_number += 1;
+        self.run_string_with_deadline(command, params, None).await
+    }
+
+    /// Executes the given string as a shell program, as with [`Shell::run_string`], but
+    /// interrupts it (as with [`Shell::interrupt`]) if it hasn't completed within the given
+    /// time. An interrupted run completes with exit status 130.
+    ///
+    /// # Arguments
+    ///
+    /// * `command` - The command to execute.
+    /// * `params` - Execution parameters.
+    /// * `timeout` - The time allowed for the command to complete.
+    pub async fn run_string_with_timeout(
+        &mut self,
+        command: String,
+        params: &ExecutionParameters,
+        timeout: std::time::Duration,
+    ) -> Result<ExecutionResult, error::Error> {
+        self.run_string_with_deadline(command, params, Some(timeout))
+            .await
+    }
+
+    /// Executes the given string as a shell program, as with [`Shell::run_string`], but
+    /// captures what it writes to standard output and standard error instead of passing it
+    /// through. Returns the result of execution along with the captured output and error
+    /// text, in that order. Unlike command substitution, this doesn't wait for background
+    /// jobs started by the program; whatever they write after the program completes isn't
+    /// captured.
+    ///
+    /// # Arguments
+    ///
+    /// * `command` - The command to execute.
+    /// * `params` - Execution parameters.
+    pub async fn run_string_capturing_output(
+        &mut self,
+        command: String,
+        params: &ExecutionParameters,
+    ) -> Result<(ExecutionResult, String, String), error::Error> {
+        let (stdout_reader, stdout_writer) = sys::pipes::pipe()?;
+        let (stderr_reader, stderr_writer) = sys::pipes::pipe()?;
+
+        // Drain the pipes as the program runs, so it can't block on writing more output
+        // than a pipe can buffer.
+        let completed = Arc::new(AtomicBool::new(false));
+        let stdout_drainer = spawn_drainer(stdout_reader, completed.clone());
+        let stderr_drainer = spawn_drainer(stderr_reader, completed.clone());
+
+        let mut params = params.clone();
+        params
+            .open_files
+            .files
+            .insert(1, openfiles::OpenFile::PipeWriter(stdout_writer));
+        params
+            .open_files
+            .files
+            .insert(2, openfiles::OpenFile::PipeWriter(stderr_writer));
+
+        let result = self.run_string(command, &params).await;
+
+        // Close our copies of the write ends of the pipes, and let the drainers know there's
+        // no more output to wait for beyond what's already been written.
+        drop(params);
+        completed.store(true, Ordering::SeqCst);
+
+        let stdout = stdout_drainer.await??;
+        let stderr = stderr_drainer.await??;
+
+        Ok((result?, stdout, stderr))
+    }
+
+    /// Executes the given string as a shell program, interrupting it if it hasn't completed
+    /// within the given time (if any).
+    async fn run_string_with_deadline(
+        &mut self,
+        command: String,
+        params: &ExecutionParameters,
+        timeout: Option<std::time::Duration>,
+    ) -> Result<ExecutionResult, error::Error> {
+        // N.B. Once the previous chunk of input has completed, the current line number
+        // refers to its last line.
+        let line_number_offset = self.current_line_number;
+        let line_count = u32::try_from(command.lines().count().max(1)).unwrap_or(u32::MAX);
+
+        // Start afresh, even if whatever ran last was interrupted. In interactive shells,
+        // Ctrl+C interrupts whatever's being run.
+        self.cancellation_token.reset();
+        let interrupt_watcher = self.options.interactive.then(|| {
+            let token = self.cancellation_token.clone();
+            tokio::spawn(async move {
+                while sys::signal::await_ctrl_c().await.is_ok() {
+                    token.cancel();
+                }
+            })
+        });
+
+        let deadline_watcher = timeout.map(|timeout| {
+            let token = self.cancellation_token.clone();
+            tokio::spawn(async move {
+                tokio::time::sleep(timeout).await;
+                token.cancel();
+            })
+        });
+
+        let result = self
+            .run_string_at_line_number_offset(command, line_number_offset, params)
+            .await;
+
+        for watcher in [interrupt_watcher, deadline_watcher].into_iter().flatten() {
+            watcher.abort();
+        }
+
+        self.current_line_number = line_number_offset.saturating_add(line_count);
+
+        result
+    }
+
+    /// Executes the given string as a shell program nested within the currently executing
+    /// command (e.g., for `eval` or a command substitution), returning the resulting exit
+    /// status. Line numbers within the string are reported relative to the current line.
+    ///
+    /// # Arguments
+    ///
+    /// * `command` - The command to execute.
+    /// * `params` - Execution parameters.
+    pub async fn run_nested_string(
+        &mut self,
+        command: String,
+        params: &ExecutionParameters,
+    ) -> Result<ExecutionResult, error::Error> {
+        let saved_line_number = self.current_line_number;
+
+        self.enter_nested_evaluation()?;
+        self.trace_nesting_level += 1;
+        let result = self
+            .run_string_at_line_number_offset(command, saved_line_number.saturating_sub(1), params)
+            .await;
+        self.trace_nesting_level -= 1;
+        self.leave_nested_evaluation();
+
+        self.set_current_line_number(saved_line_number)?;
+
+        result
+    }
+
+    /// Notes that evaluation is going one level deeper (e.g., into a function, `eval`,
+    /// `source`, or a command substitution), failing if that would exceed the maximum
+    /// recursion depth. Each successful call must be balanced by a call to
+    /// [`Self::leave_nested_evaluation`].
+    pub(crate) fn enter_nested_evaluation(&mut self) -> Result<(), error::Error> {
+        if self.recursion_depth >= self.options.max_recursion_depth {
+            return Err(error::Error::MaxRecursionDepthExceeded(
+                self.options.max_recursion_depth,
+            ));
+        }
+
+        self.recursion_depth += 1;
+        Ok(())
+    }
+
+    /// Notes that evaluation has come back out of a level entered with
+    /// [`Self::enter_nested_evaluation`].
+    pub(crate) fn leave_nested_evaluation(&mut self) {
+        self.recursion_depth = self.recursion_depth.saturating_sub(1);
+    }
+
+    async fn run_string_at_line_number_offset(
+        &mut self,
+        command: String,
+        line_number_offset: u32,
+        params: &ExecutionParameters,
+    ) -> Result<ExecutionResult, error::Error> {
+        let saved_line_number_offset =
+            std::mem::replace(&mut self.line_number_offset, line_number_offset);
 
-        let parse_result = self.parse_string(command);
+        let parse_result = self.parse_string(command.clone());
         let source_info = brush_parser::SourceInfo {
             source: String::from("main"),
         };
-        self.run_parsed_result(parse_result, &source_info, params)
-            .await
+        let result = self
+            .run_parsed_result(parse_result, &source_info, command.as_str(), params)
+            .await;
+
+        self.line_number_offset = saved_line_number_offset;
+
+        result
     }
 
     /// Parses the given string as a shell program, returning the resulting Abstract Syntax Tree
@@ -581,7 +1232,7 @@ impl Shell {
         &self,
         s: String,
     ) -> Result<brush_parser::ast::Program, brush_parser::ParseError> {
-        parse_string_impl(s, self.parser_options())
+        parse_string_impl(s, self.program_parser_options())
     }
 
     /// Applies basic shell expansion to the provided string.
@@ -611,14 +1262,52 @@ impl Shell {
         Ok(result)
     }
 
+    /// Assigns the given string value to the named shell variable, creating it as a global
+    /// variable if it doesn't already exist.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the variable to assign.
+    /// * `value` - The value to assign.
+    pub fn set_var_str<N: AsRef<str>, V: Into<String>>(
+        &mut self,
+        name: N,
+        value: V,
+    ) -> Result<(), error::Error> {
+        self.env.update_or_add(
+            name.as_ref(),
+            variables::ShellValueLiteral::Scalar(value.into()),
+            |_| Ok(()),
+            EnvironmentLookup::Anywhere,
+            EnvironmentScope::Global,
+        )
+    }
+
     /// Returns the default execution parameters for this shell.
     pub fn default_exec_params(&self) -> ExecutionParameters {
         ExecutionParameters {
             open_files: self.open_files.clone(),
+            cancellation_token: self.cancellation_token.clone(),
             ..Default::default()
         }
     }
 
+    /// Interrupts the commands currently being run by this shell (or any of its subshells),
+    /// as if the user had pressed Ctrl+C. Loops, commands, and expansions that haven't yet
+    /// started are abandoned, and the run in progress completes with exit status 130.
+    /// External commands being waited on are sent SIGINT, and killed if they haven't exited
+    /// shortly afterwards; builtins waiting on input or jobs stop waiting.
+    pub fn interrupt(&self) {
+        self.cancellation_token.cancel();
+    }
+
+    /// Returns a token that can be used to interrupt the commands being run by this shell
+    /// from another task or thread (e.g., while awaiting [`Shell::run_string`]); cancelling
+    /// it has the same effect as calling [`Shell::interrupt`].
+    pub fn interrupt_handle(&self) -> CancellationToken {
+        self.cancellation_token.clone()
+    }
+
     /// Executes the given script file, returning the resulting exit status.
     ///
     /// # Arguments
@@ -638,9 +1327,25 @@ impl Shell {
         &mut self,
         parse_result: Result<brush_parser::ast::Program, brush_parser::ParseError>,
         source_info: &brush_parser::SourceInfo,
+        source: &str,
         params: &ExecutionParameters,
     ) -> Result<ExecutionResult, error::Error> {
-        let mut error_prefix = String::new();
+        if let (Err(parse_error), Some(formatter)) = (&parse_result, &self.syntax_error_formatter) {
+            tracing::error!(
+                "{}",
+                formatter.format_syntax_error(
+                    parse_error,
+                    source_info,
+                    source,
+                    &self.message_catalog()
+                )
+            );
+
+            self.last_exit_status = 2;
+            return Ok(ExecutionResult::new(2));
+        }
+
+        let mut error_prefix = String::new();
 
         if !source_info.source.is_empty() {
             error_prefix = format!("{}: ", source_info.source);
@@ -649,6 +1354,10 @@ impl Shell {
         let result = match parse_result {
             Ok(prog) => match self.run_program(prog, params).await {
                 Ok(result) => result,
+                Err(error::Error::Interrupted) => {
+                    self.last_exit_status = interp::INTERRUPTED_EXIT_CODE;
+                    ExecutionResult::new(interp::INTERRUPTED_EXIT_CODE)
+                }
                 Err(e) => {
                     tracing::error!("error: {:#}", e);
                     self.last_exit_status = 1;
@@ -659,17 +1368,26 @@ impl Shell {
                 let error_loc = &token_near_error.location().start;
 
                 tracing::error!(
-                    "{}syntax error near token `{}' (line {} col {})",
+                    "{}{}",
                     error_prefix,
-                    token_near_error.to_str(),
-                    error_loc.line,
-                    error_loc.column,
+                    self.localize(
+                        &i18n::SYNTAX_ERROR_NEAR_TOKEN,
+                        &[
+                            ("token", &token_near_error.to_str()),
+                            ("line", &error_loc.line),
+                            ("column", &error_loc.column),
+                        ],
+                    )
                 );
                 self.last_exit_status = 2;
                 ExecutionResult::new(2)
             }
             Err(brush_parser::ParseError::ParsingAtEndOfInput) => {
-                tracing::error!("{}syntax error at end of input", error_prefix);
+                tracing::error!(
+                    "{}{}",
+                    error_prefix,
+                    self.localize(&i18n::SYNTAX_ERROR_AT_END_OF_INPUT, &[])
+                );
 
                 self.last_exit_status = 2;
                 ExecutionResult::new(2)
@@ -681,8 +1399,11 @@ impl Shell {
                 if let Some(position) = position {
                     write!(
                         error_message,
-                        " (detected near line {} column {})",
-                        position.line, position.column
+                        " {}",
+                        self.localize(
+                            &i18n::TOKENIZER_ERROR_POSITION,
+                            &[("line", &position.line), ("column", &position.column)],
+                        )
                     )?;
                 }
 
@@ -723,19 +1444,39 @@ impl Shell {
         self.prompt_from_var_or_default("PS0", "").await
     }
 
-    /// Composes the shell's prompt, applying all appropriate expansions.
+    /// Composes the shell's prompt, applying all appropriate expansions. If
+    /// `BRUSH_PROMPT_COMMAND` is set, the output of the command it holds is used instead.
     pub async fn compose_prompt(&mut self) -> Result<String, error::Error> {
+        if let Some(output) =
+            prompt::get_prompt_command_output(self, prompt::PROMPT_COMMAND_VAR).await?
+        {
+            return Ok(output);
+        }
+
         self.prompt_from_var_or_default("PS1", self.default_prompt())
             .await
     }
 
-    /// Compose's the shell's alternate-side prompt, applying all appropriate expansions.
-    #[allow(clippy::unused_async)]
+    /// Compose's the shell's alternate-side prompt, applying all appropriate expansions. If
+    /// `BRUSH_PROMPT_COMMAND_RIGHT` is set, the output of the command it holds is used instead.
     pub async fn compose_alt_side_prompt(&mut self) -> Result<String, error::Error> {
         // This is a brush extension.
+        if let Some(output) =
+            prompt::get_prompt_command_output(self, prompt::ALT_SIDE_PROMPT_COMMAND_VAR).await?
+        {
+            return Ok(output);
+        }
+
         self.prompt_from_var_or_default("BRUSH_PS_ALT", "").await
     }
 
+    /// Runs any external prompt commands (held in `BRUSH_PROMPT_COMMAND` and
+    /// `BRUSH_PROMPT_COMMAND_RIGHT`) whose output isn't already cached, concurrently, so that
+    /// composing the prompts afterward doesn't wait on each in turn.
+    pub async fn run_prompt_commands(&mut self) -> Result<(), error::Error> {
+        prompt::run_prompt_commands(self).await
+    }
+
     /// Composes the shell's continuation prompt.
     pub async fn compose_continuation_prompt(&mut self) -> Result<String, error::Error> {
         self.prompt_from_var_or_default("PS2", "> ").await
@@ -748,6 +1489,20 @@ impl Shell {
     ) -> Result<String, error::Error> {
         // Retrieve the spec.
         let prompt_spec = self.parameter_or_default(var_name, default);
+
+        self.expand_prompt_string(prompt_spec).await
+    }
+
+    /// Expands the given string as a prompt is, applying prompt escapes and then all
+    /// appropriate expansions.
+    ///
+    /// # Arguments
+    ///
+    /// * `prompt_spec` - The string to expand.
+    pub async fn expand_prompt_string(
+        &mut self,
+        prompt_spec: String,
+    ) -> Result<String, error::Error> {
         if prompt_spec.is_empty() {
             return Ok(prompt_spec);
         }
@@ -755,10 +1510,17 @@ impl Shell {
         // Expand it.
         let formatted_prompt = prompt::expand_prompt(self, prompt_spec)?;
 
-        // Now expand.
-        let formatted_prompt = expansion::basic_expand_str(self, &formatted_prompt).await?;
+        // Now expand, bounding the time spent running command substitutions if requested.
+        let deadline = self
+            .prompt_timeout
+            .map(|timeout| tokio::time::Instant::now() + timeout);
+        let saved_deadline = std::mem::replace(&mut self.command_substitution_deadline, deadline);
 
-        Ok(formatted_prompt)
+        let result = expansion::basic_expand_str(self, &formatted_prompt).await;
+
+        self.command_substitution_deadline = saved_deadline;
+
+        result
     }
 
     /// Returns the exit status of the last command executed in this shell.
@@ -766,6 +1528,29 @@ impl Shell {
         self.last_exit_status
     }
 
+    /// Takes a snapshot of the shell's variables, functions, and options, which may later be
+    /// restored with [`Shell::rollback`]. Taking a snapshot is cheap: its contents are shared
+    /// with the shell until either is modified.
+    pub fn checkpoint(&self) -> ShellCheckpoint {
+        ShellCheckpoint {
+            env: self.env.clone(),
+            funcs: self.funcs.clone(),
+            options: self.options.clone(),
+        }
+    }
+
+    /// Restores the shell's variables, functions, and options to those captured in the
+    /// given snapshot, discarding any changes made since it was taken.
+    ///
+    /// # Arguments
+    ///
+    /// * `checkpoint` - The snapshot to restore.
+    pub fn rollback(&mut self, checkpoint: ShellCheckpoint) {
+        self.env = checkpoint.env;
+        self.funcs = checkpoint.funcs;
+        self.options = checkpoint.options;
+    }
+
     fn parameter_or_default(&self, name: &str, default: &str) -> String {
         self.env.get(name).map_or_else(
             || default.to_owned(),
@@ -773,34 +1558,31 @@ impl Shell {
         )
     }
 
-    /// Returns a string representing the current `set`-style option flags set in the shell.
-    pub(crate) fn current_option_flags(&self) -> String {
-        let mut cs = vec![];
-
-        for (x, y) in crate::namedoptions::SET_OPTIONS.iter() {
-            if (y.getter)(&self.options) {
-                cs.push(*x);
-            }
-        }
+    /// Returns the text of the given message in the language selected by the shell's
+    /// locale variables, with its arguments substituted.
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The message.
+    /// * `args` - The message's arguments, by name.
+    pub(crate) fn localize(
+        &self,
+        message: &i18n::Message,
+        args: &[(&str, &dyn std::fmt::Display)],
+    ) -> String {
+        self.message_catalog().format(message, args)
+    }
 
-        // Sort the flags in a way that matches what bash does.
-        cs.sort_by(|a, b| {
-            if a == b {
-                std::cmp::Ordering::Equal
-            } else if *a == 's' {
-                std::cmp::Ordering::Greater
-            } else if *b == 's' {
-                std::cmp::Ordering::Less
-            } else if a.is_ascii_lowercase() && b.is_ascii_uppercase() {
-                std::cmp::Ordering::Less
-            } else if a.is_ascii_uppercase() && b.is_ascii_lowercase() {
-                std::cmp::Ordering::Greater
-            } else {
-                a.cmp(b)
-            }
-        });
+    /// Returns the catalog of translated messages for the shell's current locale; it's
+    /// empty if there's no catalog for the locale.
+    pub fn message_catalog(&self) -> i18n::Catalog {
+        let locale = i18n::messages_locale(
+            ["LC_ALL", "LC_MESSAGES", "LANG"].map(|name| self.env.get_str(name)),
+        );
 
-        cs.into_iter().collect()
+        locale
+            .and_then(|locale| i18n::Catalog::for_locale(locale.as_ref()))
+            .unwrap_or_default()
     }
 
     /// Returns the options that should be used for parsing shell programs; reflects
@@ -811,7 +1593,26 @@ impl Shell {
             posix_mode: self.options.posix_mode,
             sh_mode: self.options.sh_mode,
             tilde_expansion: true,
+            tilde_expansion_after_colons: false,
+            capture_trivia: false,
+            aliases: BTreeMap::new(),
+        }
+    }
+
+    /// Returns the options that should be used for parsing programs (as opposed to
+    /// individual words). Beyond those returned by [`Shell::parser_options`], these
+    /// include the aliases to expand, if alias expansion is enabled.
+    pub fn program_parser_options(&self) -> brush_parser::ParserOptions {
+        let mut options = self.parser_options();
+        if self.options.expand_aliases {
+            options.aliases = self
+                .aliases
+                .iter()
+                .map(|(name, value)| (name.clone(), value.clone()))
+                .collect();
         }
+
+        options
     }
 
     /// Returns whether or not the shell is actively executing in a shell function.
@@ -826,10 +1627,12 @@ impl Shell {
     ///
     /// * `name` - The name of the function being entered.
     /// * `function_def` - The definition of the function being entered.
+    /// * `line_number_offset` - The line number offset in effect where the function was defined.
     pub(crate) fn enter_function(
         &mut self,
         name: &str,
         function_def: &Arc<brush_parser::ast::FunctionDefinition>,
+        line_number_offset: u32,
     ) -> Result<(), error::Error> {
         if let Some(max_call_depth) = self.options.max_function_call_depth {
             if self.function_call_stack.len() >= max_call_depth {
@@ -837,15 +1640,27 @@ impl Shell {
             }
         }
 
+        self.enter_nested_evaluation()?;
+
         if tracing::enabled!(target: trace_categories::FUNCTIONS, tracing::Level::DEBUG) {
             let depth = self.function_call_stack.len();
             let prefix = repeated_char_str(' ', depth);
             tracing::debug!(target: trace_categories::FUNCTIONS, "Entering func [depth={depth}]: {prefix}{name}");
         }
 
+        if let Some(profiler) = &mut self.function_profiler {
+            profiler.enter(name);
+        }
+
         self.function_call_stack.push_front(FunctionCall {
             function_name: name.to_owned(),
             function_definition: function_def.clone(),
+            call_line_number: self.current_line_number,
+            caller_line_number_offset: std::mem::replace(
+                &mut self.line_number_offset,
+                line_number_offset,
+            ),
+            saved_options: None,
         });
         self.env.push_scope(env::EnvironmentScope::Local);
         self.update_funcname_var()?;
@@ -855,20 +1670,58 @@ impl Shell {
     /// Updates the shell's internal tracking state to reflect that the shell
     /// has exited the top-most function on its call stack.
     pub(crate) fn leave_function(&mut self) -> Result<(), error::Error> {
+        self.leave_nested_evaluation();
         self.env.pop_scope(env::EnvironmentScope::Local)?;
 
+        if let Some(profiler) = &mut self.function_profiler {
+            profiler.leave();
+        }
+
         if let Some(exited_call) = self.function_call_stack.pop_front() {
             if tracing::enabled!(target: trace_categories::FUNCTIONS, tracing::Level::DEBUG) {
                 let depth = self.function_call_stack.len();
                 let prefix = repeated_char_str(' ', depth);
                 tracing::debug!(target: trace_categories::FUNCTIONS, "Exiting func  [depth={depth}]: {prefix}{}", exited_call.function_name);
             }
+
+            if let Some(saved_options) = &exited_call.saved_options {
+                self.restore_set_options(saved_options);
+            }
+
+            self.line_number_offset = exited_call.caller_line_number_offset;
+            self.set_current_line_number(exited_call.call_line_number)?;
         }
 
         self.update_funcname_var()?;
         Ok(())
     }
 
+    /// Arranges for the options controlled by the `set` builtin to be restored to their
+    /// current values when the currently executing function returns (i.e., `local -`).
+    /// Returns `false` if no function is executing.
+    pub(crate) fn save_set_options_until_function_return(&mut self) -> bool {
+        let Some(call) = self.function_call_stack.front_mut() else {
+            return false;
+        };
+
+        // N.B. If the options were already saved, then it's the earlier values that get
+        // restored.
+        if call.saved_options.is_none() {
+            call.saved_options = Some(self.options.clone());
+        }
+
+        true
+    }
+
+    fn restore_set_options(&mut self, saved_options: &RuntimeOptions) {
+        for definition in crate::namedoptions::SET_OPTIONS.values() {
+            (definition.setter)(&mut self.options, (definition.getter)(saved_options));
+        }
+        for definition in crate::namedoptions::SET_O_OPTIONS.values() {
+            (definition.setter)(&mut self.options, (definition.getter)(saved_options));
+        }
+    }
+
     fn update_funcname_var(&mut self) -> Result<(), error::Error> {
         //
         // Fill out FUNCNAME[*]
@@ -887,6 +1740,23 @@ impl Shell {
             EnvironmentScope::Global,
         )?;
 
+        //
+        // Fill out BASH_LINENO[*]
+        //
+        let lineno_values = self
+            .function_call_stack
+            .iter()
+            .map(|s| (None, s.call_line_number.to_string()))
+            .collect::<Vec<_>>();
+
+        self.env.update_or_add(
+            "BASH_LINENO",
+            variables::ShellValueLiteral::Array(variables::ArrayLiteral(lineno_values)),
+            |_| Ok(()),
+            EnvironmentLookup::Anywhere,
+            EnvironmentScope::Global,
+        )?;
+
         self.update_bash_source_var()
     }
 
@@ -916,6 +1786,22 @@ impl Shell {
         Ok(())
     }
 
+    /// Returns an error if the shell's sandbox policy denies writing to the file at the
+    /// given path. Anything the shell itself writes to a file (e.g., for a redirection or a
+    /// builtin) should be checked first.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The absolute path of the file.
+    pub fn check_write_allowed(&self, path: &Path) -> Result<(), error::Error> {
+        match &self.sandbox_policy {
+            Some(policy) if !policy.allows_write(path) => Err(error::Error::WriteDeniedByPolicy(
+                path.to_string_lossy().to_string(),
+            )),
+            _ => Ok(()),
+        }
+    }
+
     /// Returns the path to the history file used by the shell, if one is set.
     pub fn get_history_file_path(&self) -> Option<PathBuf> {
         self.env.get("HISTFILE").map(|(_, var)| {
@@ -929,6 +1815,25 @@ impl Shell {
         self.current_line_number
     }
 
+    /// Updates the number of the line being executed, reflecting it in `$LINENO`.
+    ///
+    /// # Arguments
+    ///
+    /// * `line_number` - The new line number.
+    pub(crate) fn set_current_line_number(&mut self, line_number: u32) -> Result<(), error::Error> {
+        self.current_line_number = line_number;
+
+        self.env.update_or_add(
+            "LINENO",
+            variables::ShellValueLiteral::Scalar(line_number.to_string()),
+            |_| Ok(()),
+            EnvironmentLookup::Anywhere,
+            EnvironmentScope::Global,
+        )?;
+
+        Ok(())
+    }
+
     /// Returns the current value of the IFS variable, or the default value if it is not set.
     pub(crate) fn get_ifs(&self) -> Cow<'_, str> {
         self.env.get("IFS").map_or_else(
@@ -937,9 +1842,10 @@ impl Shell {
         )
     }
 
-    /// Returns the first character of the IFS variable, or a space if it is not set.
-    pub(crate) fn get_ifs_first_char(&self) -> char {
-        self.get_ifs().chars().next().unwrap_or(' ')
+    /// Returns the first character of the IFS variable, or a space if it is not set. Returns
+    /// `None` if the variable is set but empty.
+    pub(crate) fn get_ifs_first_char(&self) -> Option<char> {
+        self.get_ifs().chars().next()
     }
 
     /// Generates command completions for the shell.
@@ -968,8 +1874,36 @@ impl Shell {
     pub fn find_executables_in_path(&self, required_glob_pattern: &str) -> Vec<PathBuf> {
         let is_executable = |path: &Path| path.is_file() && path.executable();
 
+        let mut search_cache = self.lock_path_search_cache();
+        let name_pattern = patterns::Pattern::from(required_glob_pattern)
+            .set_extended_globbing(self.options.extended_globbing)
+            .set_case_insensitive(self.options.case_insensitive_pathname_expansion);
+
         let mut executables = vec![];
         for dir_str in self.env.get_str("PATH").unwrap_or_default().split(':') {
+            let dir = Path::new(dir_str);
+
+            // If we have a listing of the directory, match against it instead of reading
+            // the directory.
+            if let Some(search_cache) = search_cache.as_mut().filter(|_| dir.is_absolute()) {
+                for name in search_cache.names(dir) {
+                    // N.B. As with pathname expansion, hidden entries must be explicitly
+                    // matched.
+                    if name.starts_with('.') && !required_glob_pattern.starts_with('.') {
+                        continue;
+                    }
+
+                    if name_pattern.exactly_matches(name).unwrap_or(false) {
+                        let path = dir.join(name);
+                        if is_executable(&path) {
+                            executables.push(path);
+                        }
+                    }
+                }
+
+                continue;
+            }
+
             let pattern =
                 patterns::Pattern::from(std::format!("{dir_str}/{required_glob_pattern}"))
                     .set_extended_globbing(self.options.extended_globbing)
@@ -996,8 +1930,25 @@ impl Shell {
         &self,
         candidate_name: S,
     ) -> Option<PathBuf> {
+        let candidate_name = candidate_name.as_ref();
+
+        let mut search_cache = if candidate_name.contains('/') {
+            None
+        } else {
+            self.lock_path_search_cache()
+        };
+
         for dir_str in self.env.get_str("PATH").unwrap_or_default().split(':') {
-            let candidate_path = Path::new(dir_str).join(candidate_name.as_ref());
+            let dir = Path::new(dir_str);
+
+            // Skip directories we know don't contain the candidate.
+            if let Some(search_cache) = search_cache.as_mut().filter(|_| dir.is_absolute()) {
+                if !search_cache.contains(dir, candidate_name) {
+                    continue;
+                }
+            }
+
+            let candidate_path = dir.join(candidate_name);
             if candidate_path.executable() {
                 return Some(candidate_path);
             }
@@ -1005,9 +1956,21 @@ impl Shell {
         None
     }
 
+    /// Returns exclusive access to the cache of directories searched for commands, if the
+    /// `path_cache` option is enabled.
+    fn lock_path_search_cache(
+        &self,
+    ) -> Option<std::sync::MutexGuard<'_, pathsearch::PathSearchCache>> {
+        self.options.cache_path_search.then(|| {
+            self.path_search_cache
+                .lock()
+                .unwrap_or_else(std::sync::PoisonError::into_inner)
+        })
+    }
+
     /// Uses the shell's hash-based path cache to check whether the given filename is the name
     /// of an executable in one of the directories in the shell's current PATH. If found,
-    /// ensures the path is in the cache and returns it.
+    /// ensures the path is in the cache, records a hit against it, and returns it.
     ///
     /// # Arguments
     ///
@@ -1016,15 +1979,39 @@ impl Shell {
         &mut self,
         candidate_name: S,
     ) -> Option<PathBuf> {
-        if let Some(cached_path) = self.program_location_cache.get(&candidate_name) {
-            Some(cached_path)
-        } else if let Some(found_path) = self.find_first_executable_in_path(&candidate_name) {
+        self.sync_program_location_cache();
+
+        let mut cached_path = self.program_location_cache.get(&candidate_name);
+
+        // With `checkhash` enabled, a cached path that no longer refers to an executable is
+        // discarded in favor of searching PATH again.
+        if self.options.check_hashtable_before_command_exec
+            && cached_path.as_ref().is_some_and(|path| !path.executable())
+        {
+            self.program_location_cache.unset(&candidate_name);
+            cached_path = None;
+        }
+
+        let path = if let Some(cached_path) = cached_path {
+            cached_path
+        } else {
+            let found_path = self.find_first_executable_in_path(&candidate_name)?;
             self.program_location_cache
                 .set(&candidate_name, found_path.clone());
-            Some(found_path)
-        } else {
-            None
-        }
+            found_path
+        };
+
+        self.program_location_cache.record_hit(&candidate_name);
+
+        Some(path)
+    }
+
+    /// Discards the contents of the shell's hash-based path cache if the shell's PATH has
+    /// changed since they were cached.
+    pub fn sync_program_location_cache(&mut self) {
+        let search_path = self.env.get_str("PATH").unwrap_or_default();
+        self.program_location_cache
+            .sync_search_path(search_path.as_ref());
     }
 
     /// Gets the absolute form of the given path.
@@ -1105,6 +2092,15 @@ impl Shell {
             EnvironmentLookup::Anywhere,
             EnvironmentScope::Global,
         )?;
+
+        if let Some(store) = &self.directory_rank_store {
+            if let Err(e) = store.record_visit(cleaned_path.as_path()) {
+                tracing::warn!("failed to record visit to directory: {e}");
+            }
+        }
+
+        self.directory_history.record(cleaned_path.as_path());
+
         let oldpwd = std::mem::replace(&mut self.working_dir, cleaned_path);
 
         self.env.update_or_add(
@@ -1121,6 +2117,34 @@ impl Shell {
         Ok(())
     }
 
+    /// Changes the shell's working directory to the one the given number of entries away
+    /// from the current one in its directory history, leaving the history itself intact.
+    /// Returns the new working directory, or `None` if the history doesn't extend that far.
+    ///
+    /// # Arguments
+    ///
+    /// * `offset` - The number of entries to move; negative offsets move backward.
+    pub fn navigate_directory_history(
+        &mut self,
+        offset: isize,
+    ) -> Result<Option<PathBuf>, error::Error> {
+        let Some(index) = self.directory_history.offset_index(offset) else {
+            return Ok(None);
+        };
+
+        let target_dir = self.directory_history.entries()[index].clone();
+
+        let previous_index = self.directory_history.current_index();
+        self.directory_history.set_current_index(index);
+
+        if let Err(e) = self.set_working_dir(target_dir.as_path()) {
+            self.directory_history.set_current_index(previous_index);
+            return Err(e);
+        }
+
+        Ok(Some(target_dir))
+    }
+
     /// Tilde-shortens the given string, replacing the user's home directory with a tilde.
     ///
     /// # Arguments
@@ -1128,8 +2152,15 @@ impl Shell {
     /// * `s` - The string to shorten.
     pub(crate) fn tilde_shorten(&self, s: String) -> String {
         if let Some(home_dir) = self.get_home_dir() {
-            if let Some(stripped) = s.strip_prefix(home_dir.to_string_lossy().as_ref()) {
-                return format!("~{stripped}");
+            // As with bash, only whole path components are replaced (e.g., `/home/user` isn't
+            // replaced in `/home/user2`), and a home directory of `/` is never replaced.
+            let home_dir = home_dir.to_string_lossy();
+            if home_dir.len() > 1 {
+                if let Some(stripped) = s.strip_prefix(home_dir.as_ref()) {
+                    if stripped.is_empty() || stripped.starts_with(std::path::MAIN_SEPARATOR) {
+                        return format!("~{stripped}");
+                    }
+                }
             }
         }
         s
@@ -1161,25 +2192,34 @@ impl Shell {
         self.open_files.files.get(&2).unwrap().try_dup().unwrap()
     }
 
-    /// Outputs `set -x` style trace output for a command.
+    /// Outputs `set -x` style trace output for a command, prefixed by the expansion of
+    /// `PS4`. As in other shells, the first character of the expanded prefix is repeated
+    /// once for each enclosing `eval`, `source`, or command substitution.
     ///
     /// # Arguments
     ///
     /// * `command` - The command to trace.
-    pub(crate) fn trace_command<S: AsRef<str>>(&self, command: S) -> Result<(), std::io::Error> {
-        // TODO: get prefix from PS4
-        const DEFAULT_PREFIX: &str = "+ ";
-
-        let mut prefix = DEFAULT_PREFIX.to_owned();
-
-        let additional_depth = self.script_call_stack.len() + self.depth;
+    pub(crate) async fn trace_command<S: AsRef<str>>(
+        &mut self,
+        command: S,
+    ) -> Result<(), error::Error> {
+        // N.B. Tracing is suspended while expanding PS4, so that any commands it runs
+        // aren't themselves traced.
+        let prompt_spec = self.parameter_or_default("PS4", "");
+        let saved_xtrace = std::mem::replace(&mut self.options.print_commands_and_arguments, false);
+        let result = self.expand_prompt_string(prompt_spec).await;
+        self.options.print_commands_and_arguments = saved_xtrace;
+
+        let mut prefix = result?;
         if let Some(c) = prefix.chars().next() {
-            for _ in 0..additional_depth {
+            for _ in 0..self.trace_nesting_level {
                 prefix.insert(0, c);
             }
         }
 
-        writeln!(self.stderr(), "{prefix}{}", command.as_ref())
+        writeln!(self.stderr(), "{prefix}{}", command.as_ref())?;
+
+        Ok(())
     }
 
     /// Returns the keywords that are reserved by the shell.
@@ -1204,19 +2244,106 @@ impl Shell {
         }
     }
 
+    /// Expands the alias with the given name, if there is one. If the first word of the
+    /// expansion is itself an alias, it's expanded in turn; as when executing commands, an
+    /// alias isn't expanded again within its own expansion. Returns `None` if the name isn't
+    /// that of an alias.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the alias, i.e., the command word to expand.
+    pub fn expand_command_alias(&self, name: &str) -> Option<String> {
+        let mut expansion = self.aliases.get(name)?.clone();
+        let mut expanded_names = HashSet::from([name.to_owned()]);
+
+        loop {
+            let trimmed = expansion.trim_start();
+            let (first_word, rest) =
+                trimmed.split_at(trimmed.find(char::is_whitespace).unwrap_or(trimmed.len()));
+
+            if expanded_names.contains(first_word) {
+                break;
+            }
+            let Some(value) = self.aliases.get(first_word) else {
+                break;
+            };
+
+            expanded_names.insert(first_word.to_owned());
+            expansion = std::format!("{value}{rest}");
+        }
+
+        Some(expansion)
+    }
+
     /// Checks for completed jobs in the shell, reporting any changes found.
     pub fn check_for_completed_jobs(&mut self) -> Result<(), error::Error> {
         let results = self.jobs.poll()?;
 
         if self.options.enable_job_control {
-            for (job, _result) in results {
-                writeln!(self.stderr(), "{job}")?;
+            for (job, result) in results {
+                let exit_code = result.map_or(1, |result| result.exit_code);
+                writeln!(self.stderr(), "{}", job.to_completion_string(exit_code))?;
             }
         }
 
         Ok(())
     }
 
+    /// Updates the `LINES` and `COLUMNS` variables to reflect the size of the terminal, if
+    /// the shell is attached to one.
+    pub(crate) fn update_window_size_vars(&mut self) -> Result<(), error::Error> {
+        if let Some(size) = terminal::get_window_size() {
+            for (name, value) in [("LINES", size.rows), ("COLUMNS", size.columns)] {
+                self.env.update_or_add(
+                    name,
+                    variables::ShellValueLiteral::Scalar(value.to_string()),
+                    |_| Ok(()),
+                    EnvironmentLookup::Anywhere,
+                    EnvironmentScope::Global,
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Updates the `LINES` and `COLUMNS` variables if the terminal has been resized since
+    /// they were last updated. Interactive shells check before each prompt.
+    pub fn check_window_size(&mut self) -> Result<(), error::Error> {
+        if self.window_size_monitor.take_pending_changes() {
+            self.update_window_size_vars()?;
+        }
+
+        Ok(())
+    }
+
+    /// Hangs up all of the shell's jobs (i.e., sends them `SIGHUP`) if it's a login shell
+    /// with the `huponexit` option set, as bash does when such a shell exits; meant to be
+    /// called as an interactive shell exits.
+    pub fn hang_up_jobs_if_requested(&mut self) {
+        if !self.options.login_shell || !self.options.send_sighup_to_all_jobs_on_exit {
+            return;
+        }
+
+        for job in &mut self.jobs.jobs {
+            if let Err(e) = job.hang_up() {
+                tracing::debug!(target: trace_categories::JOBS, "couldn't hang up job: {e}");
+            }
+        }
+    }
+
+    /// Reports any changes in job state flagged by the job monitor since the last check, if
+    /// the shell is configured to report them immediately (i.e., `set -b`).
+    pub fn report_job_state_changes_if_notifying(&mut self) -> Result<(), error::Error> {
+        if self.options.notify_job_termination_immediately
+            && self.jobs.monitor().take_pending_changes()
+        {
+            self.check_for_completed_jobs()?;
+        }
+
+        Ok(())
+    }
+
     /// Evaluate the given arithmetic expression, returning the result.
     pub fn eval_arithmetic(
         &mut self,
@@ -1245,3 +2372,332 @@ fn parse_string_impl(
 fn repeated_char_str(c: char, count: usize) -> String {
     (0..count).map(|_| c).collect()
 }
+
+/// Spawns a task that collects what's written to the given pipe until the program writing
+/// to it has completed (as indicated by `completed`) and no more of its output remains to be
+/// read. Writers that outlive the program (e.g., background jobs) aren't waited for.
+///
+/// # Arguments
+///
+/// * `reader` - The read end of the pipe.
+/// * `completed` - Set once the program writing to the pipe has completed.
+fn spawn_drainer(
+    reader: sys::pipes::PipeReader,
+    completed: Arc<AtomicBool>,
+) -> tokio::task::JoinHandle<Result<String, error::Error>> {
+    tokio::task::spawn_blocking(move || {
+        let mut reader = openfiles::OpenFile::PipeReader(reader);
+        let mut output = vec![];
+        let mut buffer = [0; 4096];
+
+        loop {
+            // N.B. We check for completion before checking for output, so that any output
+            // written before completion is seen.
+            let program_completed = completed.load(Ordering::SeqCst);
+            if reader.has_pending_input()? {
+                let n = reader.read(&mut buffer)?;
+                if n == 0 {
+                    break;
+                }
+                output.extend_from_slice(&buffer[..n]);
+            } else if program_completed {
+                break;
+            } else {
+                std::thread::sleep(CAPTURED_OUTPUT_POLL_INTERVAL);
+            }
+        }
+
+        String::from_utf8(output)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e).into())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+
+    #[tokio::test]
+    async fn test_max_recursion_depth() -> Result<()> {
+        let options = CreateOptions {
+            max_recursion_depth: Some(20),
+            ..CreateOptions::default()
+        };
+        let mut shell = Shell::new(&options).await?;
+        let params = shell.default_exec_params();
+
+        // Unbounded recursion through functions, `eval`, and command substitutions fails
+        // cleanly once the limit is reached...
+        for script in ["f() { f; }; f", "f() { eval f; }; f", "f() { x=$(f); }; f"] {
+            let result = shell.run_string(script.to_owned(), &params).await?;
+            assert_eq!(result.exit_code, 1, "script: {script}");
+            assert_eq!(shell.recursion_depth, 0, "script: {script}");
+        }
+
+        // ...while recursion within the limit is unaffected.
+        let result = shell
+            .run_string(
+                "g() { (( $1 > 0 )) && g $(( $1 - 1 )); return 7; }; g 10".to_owned(),
+                &params,
+            )
+            .await?;
+        assert_eq!(result.exit_code, 7);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_sandbox_policy_denies_builtin_writes() -> Result<()> {
+        let test_dir = std::env::temp_dir().join(format!("brush-sandbox-{}", std::process::id()));
+        let write_root = test_dir.join("allowed");
+        std::fs::create_dir_all(&write_root)?;
+
+        let options = CreateOptions {
+            sandbox_policy: Some(Arc::new(sandbox::RestrictedPolicy {
+                write_root: Some(write_root.clone()),
+                ..sandbox::RestrictedPolicy::default()
+            })),
+            ..CreateOptions::default()
+        };
+        let mut shell = Shell::new(&options).await?;
+        let params = shell.default_exec_params();
+
+        // Saving state outside the permitted directory is refused...
+        let denied_path = test_dir.join("denied.json");
+        let result = shell
+            .run_string(
+                format!("enable save; save '{}'", denied_path.to_string_lossy()),
+                &params,
+            )
+            .await?;
+        assert_ne!(result.exit_code, 0);
+        assert!(!denied_path.exists());
+
+        // ...while saving it within the directory is allowed.
+        let allowed_path = write_root.join("allowed.json");
+        let result = shell
+            .run_string(
+                format!("save '{}'", allowed_path.to_string_lossy()),
+                &params,
+            )
+            .await?;
+        assert_eq!(result.exit_code, 0);
+        assert!(allowed_path.exists());
+
+        std::fs::remove_dir_all(test_dir)?;
+
+        Ok(())
+    }
+
+    #[cfg(any(unix, windows))]
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_interrupt() -> Result<()> {
+        let mut shell = Shell::new(&CreateOptions::default()).await?;
+        let params = shell.default_exec_params();
+
+        // Interrupting a loop that would otherwise never finish stops it...
+        let result = run_interrupted(&mut shell, "while :; do x=$((x + 1)); done").await?;
+        assert_eq!(result.exit_code, 130);
+
+        // ...without affecting what's run next.
+        let result = shell.run_string("true".to_owned(), &params).await?;
+        assert_eq!(result.exit_code, 0);
+
+        Ok(())
+    }
+
+    #[cfg(any(unix, windows))]
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_interrupt_pipeline() -> Result<()> {
+        let mut shell = Shell::new(&CreateOptions::default()).await?;
+        let params = shell.default_exec_params();
+
+        // Every stage of the pipeline is stopped...
+        let result = run_interrupted(
+            &mut shell,
+            "while :; do echo x; done | while read -r line; do :; done",
+        )
+        .await?;
+        assert_eq!(result.exit_code, 130);
+
+        // ...and pipelines run afterwards aren't affected.
+        let result = shell
+            .run_string("echo x | read -r line".to_owned(), &params)
+            .await?;
+        assert_eq!(result.exit_code, 0);
+
+        Ok(())
+    }
+
+    #[cfg(any(unix, windows))]
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_interrupt_restores_state() -> Result<()> {
+        let mut shell = Shell::new(&CreateOptions::default()).await?;
+        let params = shell.default_exec_params();
+
+        shell
+            .run_string(
+                "v=outer; set -- a b; f() { local v=inner; while :; do :; done; }".to_owned(),
+                &params,
+            )
+            .await?;
+
+        // Interrupting a loop within a function unwinds the function call...
+        let result = run_interrupted(&mut shell, "f c").await?;
+        assert_eq!(result.exit_code, 130);
+        assert!(shell.function_call_stack.is_empty());
+        assert_eq!(shell.recursion_depth, 0);
+
+        // ...restoring its caller's variables and positional parameters.
+        assert_eq!(shell.env.get_str("v").as_deref(), Some("outer"));
+        assert_eq!(shell.positional_parameters, ["a", "b"]);
+
+        let result = shell
+            .run_string("[[ $v == outer && $1 == a ]]".to_owned(), &params)
+            .await?;
+        assert_eq!(result.exit_code, 0);
+
+        Ok(())
+    }
+
+    /// Runs the given command in the shell, interrupting it via its interrupt handle after
+    /// a short delay.
+    async fn run_interrupted(shell: &mut Shell, command: &str) -> Result<ExecutionResult> {
+        let params = shell.default_exec_params();
+        let token = shell.interrupt_handle();
+        let interrupter = tokio::spawn(async move {
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+            token.cancel();
+        });
+
+        let result = shell.run_string(command.to_owned(), &params).await?;
+        interrupter.await?;
+
+        Ok(result)
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_run_string_capturing_output() -> Result<()> {
+        let mut shell = Shell::new(&CreateOptions::default()).await?;
+        let params = shell.default_exec_params();
+
+        // Output is captured in full, even when there's more than a pipe can buffer...
+        let (result, stdout, stderr) = shell
+            .run_string_capturing_output(
+                "head -c 70000 /dev/zero | tr '\\0' x; echo err >&2; exit 3".to_owned(),
+                &params,
+            )
+            .await?;
+        assert_eq!(result.exit_code, 3);
+        assert_eq!(stdout.len(), 70000);
+        assert_eq!(stderr, "err\n");
+
+        // ...but background jobs that are still running aren't waited for.
+        let started = std::time::Instant::now();
+        let (result, stdout, _) = shell
+            .run_string_capturing_output("sleep 10 & echo started".to_owned(), &params)
+            .await?;
+        assert_eq!(result.exit_code, 0);
+        assert_eq!(stdout, "started\n");
+        assert!(started.elapsed() < std::time::Duration::from_secs(5));
+
+        Ok(())
+    }
+
+    #[cfg(any(unix, windows))]
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_run_string_with_timeout() -> Result<()> {
+        let mut shell = Shell::new(&CreateOptions::default()).await?;
+        let params = shell.default_exec_params();
+        let timeout = std::time::Duration::from_millis(100);
+
+        // A run that doesn't complete in time is interrupted, completing with status 130...
+        let started = std::time::Instant::now();
+        let result = shell
+            .run_string_with_timeout("x=1; while :; do :; done".to_owned(), &params, timeout)
+            .await?;
+        assert_eq!(result.exit_code, 130);
+        assert!(started.elapsed() >= timeout);
+
+        // ...leaving the shell usable afterwards, with what it did before being interrupted
+        // intact.
+        let result = shell
+            .run_string("[[ $x == 1 ]] && echo ok | read -r y".to_owned(), &params)
+            .await?;
+        assert_eq!(result.exit_code, 0);
+
+        // Runs that complete in time aren't affected, and their timeouts don't linger to
+        // interrupt later runs.
+        let result = shell
+            .run_string_with_timeout("exit 3".to_owned(), &params, timeout)
+            .await?;
+        assert_eq!(result.exit_code, 3);
+
+        tokio::time::sleep(timeout * 2).await;
+        let result = shell.run_string("true".to_owned(), &params).await?;
+        assert_eq!(result.exit_code, 0);
+
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_run_string_with_timeout_interrupts_waits() -> Result<()> {
+        let mut shell = Shell::new(&CreateOptions::default()).await?;
+        let mut params = shell.default_exec_params();
+        let timeout = std::time::Duration::from_millis(100);
+
+        // External commands that don't complete in time are interrupted...
+        let started = std::time::Instant::now();
+        let result = shell
+            .run_string_with_timeout("sleep 600; x=1".to_owned(), &params, timeout)
+            .await?;
+        assert_eq!(result.exit_code, 130);
+        assert!(started.elapsed() < std::time::Duration::from_secs(10));
+
+        // ...as are builtins waiting on input that never arrives.
+        let (reader, _writer) = sys::pipes::pipe()?;
+        params
+            .open_files
+            .files
+            .insert(0, openfiles::OpenFile::PipeReader(reader));
+
+        let started = std::time::Instant::now();
+        let result = shell
+            .run_string_with_timeout("read -r y; x=1".to_owned(), &params, timeout)
+            .await?;
+        assert_eq!(result.exit_code, 130);
+        assert!(started.elapsed() < std::time::Duration::from_secs(10));
+
+        // Nothing after the interrupted commands ran.
+        assert!(shell.env.get_str("x").is_none());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_exported_function_env_vars() -> Result<()> {
+        let mut shell = Shell::new(&CreateOptions::default()).await?;
+        let params = shell.default_exec_params();
+
+        shell
+            .run_string("f() { echo 1; }; export -f f".to_owned(), &params)
+            .await?;
+        let exported = shell.funcs.exported_env_vars();
+        assert_eq!(exported.len(), 1);
+        assert_eq!(exported[0].0, "BASH_FUNC_f%%");
+        assert!(exported[0].1.contains("echo 1"));
+
+        // Redefining or unexporting the function invalidates the cached variables.
+        shell
+            .run_string("f() { echo 2; }".to_owned(), &params)
+            .await?;
+        assert!(shell.funcs.exported_env_vars()[0].1.contains("echo 2"));
+
+        shell.run_string("export -nf f".to_owned(), &params).await?;
+        assert!(shell.funcs.exported_env_vars().is_empty());
+
+        Ok(())
+    }
+}
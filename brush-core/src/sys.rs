@@ -13,6 +13,11 @@ pub(crate) mod wasm;
 #[cfg(target_family = "wasm")]
 pub(crate) use wasm as platform;
 
+// N.B. Other platforms fall back to stubs for all capabilities, running with reduced
+// functionality; see `capabilities::Capability` for what's affected.
+#[cfg(not(any(unix, windows, target_family = "wasm")))]
+pub(crate) use stubs as platform;
+
 #[cfg(not(unix))]
 pub(crate) mod stubs;
 
@@ -23,8 +28,10 @@ pub(crate) mod os_pipe;
 #[cfg(any(unix, windows))]
 pub(crate) mod tokio_process;
 
+pub(crate) mod capabilities;
 pub(crate) mod fs;
 
+pub(crate) use platform::containment;
 pub(crate) use platform::network;
 pub(crate) use platform::pipes;
 pub(crate) use platform::process;
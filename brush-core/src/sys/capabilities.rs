@@ -0,0 +1,72 @@
+//! Queries for the platform capabilities available to the shell.
+
+use std::fmt::Display;
+
+use crate::sys;
+
+/// A capability of the host platform that some of the shell's functionality depends on.
+/// When a capability isn't supported, the shell still runs, but the functionality that
+/// depends on it is degraded as described for each capability.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Capability {
+    /// Querying user and group identities. Without it, `~user` expansion and user and
+    /// group name completion find nothing, `$EUID` isn't set, and queries for the
+    /// current user's identity (e.g., `\u` in prompts) fail.
+    Users,
+    /// Sending and receiving process signals. Without it, sending a signal (e.g., with
+    /// `kill`, `bg`, or `suspend`) fails, and the shell never observes stopped children.
+    Signals,
+    /// Measuring the resources consumed by the shell and its children. Without it, all
+    /// times reported by `times` and `time` are zero.
+    Resources,
+    /// Controlling the terminal and its foreground process group. Without it, terminal
+    /// settings and the foreground process group are left unchanged.
+    TerminalControl,
+}
+
+impl Capability {
+    /// All capabilities.
+    pub const ALL: [Self; 4] = [
+        Self::Users,
+        Self::Signals,
+        Self::Resources,
+        Self::TerminalControl,
+    ];
+
+    /// Returns the name of the capability.
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Users => "users",
+            Self::Signals => "signals",
+            Self::Resources => "resources",
+            Self::TerminalControl => "terminal control",
+        }
+    }
+
+    /// Returns whether the capability is supported on the current platform.
+    pub const fn is_supported(self) -> bool {
+        match self {
+            Self::Users => sys::users::SUPPORTED,
+            Self::Signals => sys::signal::SUPPORTED,
+            Self::Resources => sys::resource::SUPPORTED,
+            Self::TerminalControl => sys::terminal::SUPPORTED,
+        }
+    }
+}
+
+impl Display for Capability {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(unix)]
+    fn unix_supports_all_capabilities() {
+        assert!(Capability::ALL.iter().all(|c| c.is_supported()));
+    }
+}
@@ -4,6 +4,7 @@
 #![allow(clippy::unused_self)]
 #![allow(clippy::unnecessary_wraps)]
 
+pub(crate) mod containment;
 pub(crate) mod fs;
 pub(crate) mod network;
 pub(crate) mod pipes;
@@ -0,0 +1,16 @@
+//! Stub implementation of process containment, for platforms without support for any
+//! kind of process container. Placing a process in a container always fails.
+
+use crate::containment::ProcessContainer;
+use crate::{error, sys};
+
+pub(crate) fn add_process(
+    _container: &ProcessContainer,
+    _pid: sys::process::ProcessId,
+) -> Result<(), error::Error> {
+    Err(error::Error::UnsupportedProcessContainer)
+}
+
+pub(crate) fn terminate_all(_container: &ProcessContainer) -> Result<(), error::Error> {
+    Err(error::Error::UnsupportedProcessContainer)
+}
@@ -1,3 +1,5 @@
+use crate::error;
+
 #[cfg(not(unix))]
 impl crate::sys::fs::PathExt for std::path::Path {
     fn readable(&self) -> bool {
@@ -41,6 +43,12 @@ impl crate::sys::fs::PathExt for std::path::Path {
     }
 }
 
+/// Returns whether the file has input available to be read without blocking. Files can't
+/// be polled on this platform, so they're always assumed to.
+pub(crate) fn has_pending_input<Fd>(_fd: Fd) -> Result<bool, error::Error> {
+    Ok(true)
+}
+
 pub(crate) trait StubMetadataExt {
     fn gid(&self) -> u32 {
         0
@@ -16,6 +16,10 @@ impl Child {
         self.inner.wait()
     }
 
+    pub fn start_kill(&mut self) -> std::io::Result<()> {
+        self.inner.kill()
+    }
+
     pub async fn wait_with_output(self) -> std::io::Result<Output> {
         self.inner.wait_with_output()
     }
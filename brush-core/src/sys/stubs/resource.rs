@@ -1,5 +1,11 @@
+//! Stub implementation of resource usage queries, for platforms without them. All
+//! measured times are reported as zero.
+
 use crate::error;
 
+/// Whether measuring resource usage is supported on this platform.
+pub(crate) const SUPPORTED: bool = false;
+
 #[allow(clippy::unnecessary_wraps)]
 pub(crate) fn get_self_user_and_system_time(
 ) -> Result<(std::time::Duration, std::time::Duration), error::Error> {
@@ -11,3 +17,8 @@ pub(crate) fn get_children_user_and_system_time(
 ) -> Result<(std::time::Duration, std::time::Duration), error::Error> {
     Ok((std::time::Duration::ZERO, std::time::Duration::ZERO))
 }
+
+#[allow(clippy::unnecessary_wraps)]
+pub(crate) fn get_children_max_rss_kb() -> Result<u64, error::Error> {
+    Ok(0)
+}
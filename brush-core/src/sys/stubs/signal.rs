@@ -1,5 +1,12 @@
+//! Stub implementation of signal handling, for platforms without signals. Sending a
+//! signal to another process fails; listeners never receive a signal, and operations
+//! that only affect the shell itself (e.g., masking signals) silently do nothing.
+
 use crate::{error, sys, traps};
 
+/// Whether sending and receiving signals is supported on this platform.
+pub(crate) const SUPPORTED: bool = false;
+
 pub(crate) fn continue_process(_pid: sys::process::ProcessId) -> Result<(), error::Error> {
     error::unimp("continue process")
 }
@@ -11,6 +18,24 @@ pub(crate) fn kill_process(
     error::unimp("kill process")
 }
 
+pub(crate) fn terminate_process(_pid: sys::process::ProcessId) -> Result<(), error::Error> {
+    error::unimp("terminate process")
+}
+
+pub(crate) fn hang_up_process(_pid: sys::process::ProcessId) -> Result<(), error::Error> {
+    error::unimp("hang up process")
+}
+
+pub(crate) fn interrupt_process(_pid: sys::process::ProcessId) -> Result<(), error::Error> {
+    error::unimp("interrupt process")
+}
+
+pub(crate) fn kill_process_forcibly(_pid: sys::process::ProcessId) -> Result<(), error::Error> {
+    error::unimp("kill process forcibly")
+}
+
+pub(crate) fn record_startup_dispositions() {}
+
 pub(crate) fn lead_new_process_group() -> Result<(), error::Error> {
     Ok(())
 }
@@ -44,6 +69,10 @@ pub(crate) fn mask_sigttou() -> Result<(), error::Error> {
     Ok(())
 }
 
+pub(crate) fn mask_sigquit() -> Result<(), error::Error> {
+    Ok(())
+}
+
 pub(crate) fn poll_for_stopped_children() -> Result<bool, error::Error> {
     Ok(false)
 }
@@ -1,5 +1,12 @@
+//! Stub implementation of terminal control, for platforms without it. Terminal settings
+//! can be retrieved and applied but have no effect, process IDs can't be queried, and
+//! changing the foreground process group silently does nothing.
+
 use crate::{error, sys};
 
+/// Whether controlling the terminal is supported on this platform.
+pub(crate) const SUPPORTED: bool = false;
+
 #[derive(Clone)]
 pub(crate) struct TerminalSettings {}
 
@@ -40,6 +47,14 @@ pub(crate) fn get_foreground_pid() -> Option<sys::process::ProcessId> {
     None
 }
 
+pub(crate) fn get_window_size() -> Option<(u16, u16)> {
+    None
+}
+
+pub(crate) fn get_terminal_device_name() -> Option<std::path::PathBuf> {
+    None
+}
+
 pub(crate) fn move_to_foreground(_pid: sys::process::ProcessId) -> Result<(), error::Error> {
     Ok(())
 }
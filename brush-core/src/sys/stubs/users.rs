@@ -1,6 +1,13 @@
+//! Stub implementation of user and group queries, for platforms without them. Lookups
+//! of home directories and of all users and groups find nothing, the current user is
+//! never considered to be root, and queries for the current user's identity fail.
+
 use crate::error;
 use std::path::PathBuf;
 
+/// Whether querying users and groups is supported on this platform.
+pub(crate) const SUPPORTED: bool = false;
+
 pub(crate) fn get_user_home_dir(_username: &str) -> Option<PathBuf> {
     None
 }
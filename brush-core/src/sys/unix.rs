@@ -1,4 +1,5 @@
 pub(crate) use crate::sys::os_pipe as pipes;
+pub(crate) mod containment;
 pub(crate) mod fs;
 pub(crate) mod network;
 pub(crate) use crate::sys::tokio_process as process;
@@ -0,0 +1,31 @@
+use crate::containment::ProcessContainer;
+use crate::{error, sys};
+
+pub(crate) fn add_process(
+    container: &ProcessContainer,
+    pid: sys::process::ProcessId,
+) -> Result<(), error::Error> {
+    match container {
+        #[cfg(target_os = "linux")]
+        ProcessContainer::Cgroup(dir) => {
+            std::fs::write(dir.join("cgroup.procs"), pid.to_string())?;
+            Ok(())
+        }
+        _ => {
+            let _ = pid;
+            Err(error::Error::UnsupportedProcessContainer)
+        }
+    }
+}
+
+pub(crate) fn terminate_all(container: &ProcessContainer) -> Result<(), error::Error> {
+    match container {
+        // N.B. Requires Linux 5.14 or later.
+        #[cfg(target_os = "linux")]
+        ProcessContainer::Cgroup(dir) => {
+            std::fs::write(dir.join("cgroup.kill"), "1")?;
+            Ok(())
+        }
+        _ => Err(error::Error::UnsupportedProcessContainer),
+    }
+}
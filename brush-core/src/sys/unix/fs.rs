@@ -1,6 +1,9 @@
+use std::os::fd::AsFd;
 use std::os::unix::fs::{FileTypeExt, MetadataExt};
 use std::path::Path;
 
+use crate::error;
+
 impl crate::sys::fs::PathExt for Path {
     fn readable(&self) -> bool {
         nix::unistd::access(self, nix::unistd::AccessFlags::R_OK).is_ok()
@@ -56,3 +59,18 @@ fn try_get_file_type(path: &Path) -> Option<std::fs::FileType> {
 fn try_get_file_mode(path: &Path) -> Option<u32> {
     path.metadata().map(|metadata| metadata.mode()).ok()
 }
+
+/// Returns whether the file has input available to be read without blocking (including
+/// the end of input).
+pub(crate) fn has_pending_input<Fd: AsFd>(fd: Fd) -> Result<bool, error::Error> {
+    let mut poll_fds = [nix::poll::PollFd::new(
+        fd.as_fd(),
+        nix::poll::PollFlags::POLLIN,
+    )];
+
+    match nix::poll::poll(&mut poll_fds, nix::poll::PollTimeout::ZERO) {
+        Ok(ready_count) => Ok(ready_count > 0),
+        Err(nix::errno::Errno::EINTR) => Ok(false),
+        Err(e) => Err(e.into()),
+    }
+}
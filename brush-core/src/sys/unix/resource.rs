@@ -1,5 +1,8 @@
 use crate::error;
 
+/// Whether measuring resource usage is supported on this platform.
+pub(crate) const SUPPORTED: bool = true;
+
 #[allow(clippy::unnecessary_wraps)]
 pub(crate) fn get_self_user_and_system_time(
 ) -> Result<(std::time::Duration, std::time::Duration), error::Error> {
@@ -20,6 +23,20 @@ pub(crate) fn get_children_user_and_system_time(
     ))
 }
 
+/// Returns the largest resident set size, in kilobytes, of any child of the shell that has
+/// been waited on.
+#[allow(clippy::unnecessary_wraps)]
+pub(crate) fn get_children_max_rss_kb() -> Result<u64, error::Error> {
+    let usage = nix::sys::resource::getrusage(nix::sys::resource::UsageWho::RUSAGE_CHILDREN)?;
+    let max_rss = u64::try_from(usage.max_rss()).unwrap_or_default();
+
+    // N.B. macOS reports the size in bytes, where other platforms report kilobytes.
+    #[cfg(target_os = "macos")]
+    let max_rss = max_rss / 1024;
+
+    Ok(max_rss)
+}
+
 fn convert_rusage_time(time: nix::sys::time::TimeVal) -> std::time::Duration {
     #[allow(clippy::cast_sign_loss)]
     #[allow(clippy::cast_possible_truncation)]
@@ -1,5 +1,14 @@
+use std::sync::OnceLock;
+
 use crate::{error, sys, traps};
 
+/// Whether sending and receiving signals is supported on this platform.
+pub(crate) const SUPPORTED: bool = true;
+
+/// Signals that were ignored when the shell started; as in other shells, these stay
+/// ignored in the commands it runs.
+static IGNORED_AT_STARTUP: OnceLock<Vec<nix::sys::signal::Signal>> = OnceLock::new();
+
 pub(crate) fn continue_process(pid: sys::process::ProcessId) -> Result<(), error::Error> {
     #[allow(clippy::cast_possible_wrap)]
     nix::sys::signal::kill(nix::unistd::Pid::from_raw(pid), nix::sys::signal::SIGCONT)
@@ -12,8 +21,10 @@ pub(crate) fn kill_process(
     signal: traps::TrapSignal,
 ) -> Result<(), error::Error> {
     let translated_signal = match signal {
-        traps::TrapSignal::Signal(signal) => signal,
-        traps::TrapSignal::Debug | traps::TrapSignal::Err | traps::TrapSignal::Exit => {
+        traps::TrapSignal::Signal(signal) => Some(signal),
+        // N.B. Signal 0 (a.k.a. EXIT) only checks whether the process exists.
+        traps::TrapSignal::Exit => None,
+        traps::TrapSignal::Debug | traps::TrapSignal::Err => {
             return Err(error::Error::InvalidSignal(signal.to_string()));
         }
     };
@@ -24,6 +35,95 @@ pub(crate) fn kill_process(
     Ok(())
 }
 
+pub(crate) fn terminate_process(pid: sys::process::ProcessId) -> Result<(), error::Error> {
+    kill_process(pid, traps::TrapSignal::Signal(nix::sys::signal::SIGTERM))
+}
+
+pub(crate) fn hang_up_process(pid: sys::process::ProcessId) -> Result<(), error::Error> {
+    kill_process(pid, traps::TrapSignal::Signal(nix::sys::signal::SIGHUP))
+}
+
+/// Interrupts the process as Ctrl+C would, along with the rest of its process group if it
+/// leads one.
+pub(crate) fn interrupt_process(pid: sys::process::ProcessId) -> Result<(), error::Error> {
+    signal_process_or_group(pid, nix::sys::signal::SIGINT)
+}
+
+/// Kills the process outright, along with the rest of its process group if it leads one.
+pub(crate) fn kill_process_forcibly(pid: sys::process::ProcessId) -> Result<(), error::Error> {
+    signal_process_or_group(pid, nix::sys::signal::SIGKILL)
+}
+
+fn signal_process_or_group(
+    pid: sys::process::ProcessId,
+    signal: nix::sys::signal::Signal,
+) -> Result<(), error::Error> {
+    let pid = nix::unistd::Pid::from_raw(pid);
+
+    // N.B. A process that doesn't lead a process group of its own shares one with the
+    // shell, which mustn't be signaled; there's then no group with the process's ID.
+    nix::sys::signal::killpg(pid, signal)
+        .or_else(|_errno| nix::sys::signal::kill(pid, signal))
+        .map_err(|_errno| error::Error::FailedToSendSignal)
+}
+
+/// Records which signals were ignored when the shell started. Must be called before the
+/// shell installs any handlers of its own; only the first call has any effect.
+pub(crate) fn record_startup_dispositions() {
+    let _ = IGNORED_AT_STARTUP.get_or_init(|| {
+        nix::sys::signal::Signal::iterator()
+            .filter(|signal| is_ignored(*signal))
+            .collect()
+    });
+}
+
+/// Returns whether the given signal was ignored when the shell started.
+pub(crate) fn was_ignored_at_startup(signal: nix::sys::signal::Signal) -> bool {
+    IGNORED_AT_STARTUP
+        .get()
+        .is_some_and(|signals| signals.contains(&signal))
+}
+
+fn is_ignored(signal: nix::sys::signal::Signal) -> bool {
+    // N.B. Passing no new action only queries the signal's current disposition.
+    let mut current: nix::libc::sigaction = unsafe { std::mem::zeroed() };
+    let result = unsafe { nix::libc::sigaction(signal as i32, std::ptr::null(), &mut current) };
+    result == 0 && current.sa_sigaction == nix::libc::SIG_IGN
+}
+
+/// Prepares the dispositions of signals for a command about to be run: signals the shell
+/// itself ignores are restored to their defaults, while those that were ignored when the
+/// shell started are ignored. Runs in the forked child process before it execs the
+/// command, so it must only make async-signal-safe calls.
+pub(crate) fn prepare_child_dispositions() -> Result<(), std::io::Error> {
+    use nix::sys::signal::{SigHandler, Signal};
+
+    for signal in [Signal::SIGQUIT, Signal::SIGTTOU] {
+        if !was_ignored_at_startup(signal) {
+            set_handler(signal, SigHandler::SigDfl)?;
+        }
+    }
+
+    for signal in IGNORED_AT_STARTUP.get().into_iter().flatten() {
+        set_handler(*signal, SigHandler::SigIgn)?;
+    }
+
+    Ok(())
+}
+
+fn set_handler(
+    signal: nix::sys::signal::Signal,
+    handler: nix::sys::signal::SigHandler,
+) -> Result<(), nix::errno::Errno> {
+    let action = nix::sys::signal::SigAction::new(
+        handler,
+        nix::sys::signal::SaFlags::empty(),
+        nix::sys::signal::SigSet::empty(),
+    );
+    unsafe { nix::sys::signal::sigaction(signal, &action) }?;
+    Ok(())
+}
+
 pub(crate) fn lead_new_process_group() -> Result<(), error::Error> {
     nix::unistd::setpgid(nix::unistd::Pid::from_raw(0), nix::unistd::Pid::from_raw(0))?;
     Ok(())
@@ -45,12 +145,18 @@ pub(crate) fn chld_signal_listener() -> Result<tokio::signal::unix::Signal, erro
 pub(crate) use tokio::signal::ctrl_c as await_ctrl_c;
 
 pub(crate) fn mask_sigttou() -> Result<(), error::Error> {
-    let ignore = nix::sys::signal::SigAction::new(
+    set_handler(
+        nix::sys::signal::Signal::SIGTTOU,
         nix::sys::signal::SigHandler::SigIgn,
-        nix::sys::signal::SaFlags::empty(),
-        nix::sys::signal::SigSet::empty(),
-    );
-    unsafe { nix::sys::signal::sigaction(nix::sys::signal::Signal::SIGTTOU, &ignore) }?;
+    )?;
+    Ok(())
+}
+
+pub(crate) fn mask_sigquit() -> Result<(), error::Error> {
+    set_handler(
+        nix::sys::signal::Signal::SIGQUIT,
+        nix::sys::signal::SigHandler::SigIgn,
+    )?;
     Ok(())
 }
 
@@ -1,5 +1,11 @@
 use crate::{error, sys};
-use std::{io::IsTerminal, os::fd::AsFd};
+use std::{
+    io::IsTerminal,
+    os::fd::{AsFd, AsRawFd},
+};
+
+/// Whether controlling the terminal is supported on this platform.
+pub(crate) const SUPPORTED: bool = true;
 
 #[derive(Clone)]
 pub(crate) struct TerminalSettings {
@@ -58,6 +64,25 @@ pub(crate) fn get_foreground_pid() -> Option<sys::process::ProcessId> {
         .map(|pgid| pgid.as_raw())
 }
 
+pub(crate) fn get_window_size() -> Option<(u16, u16)> {
+    // N.B. We query standard error, as it's the stream most likely to remain connected to
+    // the terminal when the others are redirected.
+    let mut size: nix::libc::winsize = unsafe { std::mem::zeroed() };
+    let result = unsafe {
+        nix::libc::ioctl(
+            std::io::stderr().as_raw_fd(),
+            nix::libc::TIOCGWINSZ,
+            &mut size,
+        )
+    };
+
+    (result == 0 && size.ws_row > 0 && size.ws_col > 0).then_some((size.ws_row, size.ws_col))
+}
+
+pub(crate) fn get_terminal_device_name() -> Option<std::path::PathBuf> {
+    nix::unistd::ttyname(std::io::stdin()).ok()
+}
+
 pub(crate) fn move_to_foreground(pid: sys::process::ProcessId) -> Result<(), error::Error> {
     nix::unistd::tcsetpgrp(std::io::stdin(), nix::unistd::Pid::from_raw(pid))?;
     Ok(())
@@ -3,6 +3,9 @@ use std::path::PathBuf;
 
 use uzers::os::unix::UserExt;
 
+/// Whether querying users and groups is supported on this platform.
+pub(crate) const SUPPORTED: bool = true;
+
 pub(crate) fn is_root() -> bool {
     uzers::get_current_uid() == 0
 }
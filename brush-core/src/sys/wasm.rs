@@ -1,3 +1,4 @@
+pub(crate) use crate::sys::stubs::containment;
 pub(crate) use crate::sys::stubs::fs;
 pub(crate) use crate::sys::stubs::network;
 pub(crate) use crate::sys::stubs::pipes;
@@ -1,12 +1,10 @@
 pub(crate) use crate::sys::os_pipe as pipes;
+pub(crate) mod containment;
 pub(crate) use crate::sys::stubs::fs;
 pub(crate) mod network;
 pub(crate) use crate::sys::stubs::resource;
 
-pub(crate) mod signal {
-    pub(crate) use crate::sys::stubs::signal::*;
-    pub(crate) use tokio::signal::ctrl_c as await_ctrl_c;
-}
+pub(crate) mod signal;
 
 pub(crate) use crate::sys::stubs::terminal;
 pub(crate) use crate::sys::tokio_process as process;
@@ -0,0 +1,79 @@
+use windows_sys::Win32::Foundation::{CloseHandle, FALSE, HANDLE};
+use windows_sys::Win32::System::JobObjects::{
+    AssignProcessToJobObject, OpenJobObjectW, TerminateJobObject, JOB_OBJECT_ASSIGN_PROCESS,
+    JOB_OBJECT_TERMINATE,
+};
+use windows_sys::Win32::System::Threading::{OpenProcess, PROCESS_SET_QUOTA, PROCESS_TERMINATE};
+
+use crate::containment::ProcessContainer;
+use crate::{error, sys};
+
+/// A handle that's closed when dropped.
+struct OwnedHandle(HANDLE);
+
+impl OwnedHandle {
+    fn new(handle: HANDLE) -> Result<Self, error::Error> {
+        if handle.is_null() {
+            Err(std::io::Error::last_os_error().into())
+        } else {
+            Ok(Self(handle))
+        }
+    }
+}
+
+impl Drop for OwnedHandle {
+    fn drop(&mut self) {
+        // SAFETY: The handle is valid, and owned by us.
+        unsafe {
+            CloseHandle(self.0);
+        }
+    }
+}
+
+fn open_job_object(name: &str, access: u32) -> Result<OwnedHandle, error::Error> {
+    let wide_name: Vec<u16> = name.encode_utf16().chain(std::iter::once(0)).collect();
+
+    // SAFETY: The name is a valid, null-terminated wide string.
+    OwnedHandle::new(unsafe { OpenJobObjectW(access, FALSE, wide_name.as_ptr()) })
+}
+
+pub(crate) fn add_process(
+    container: &ProcessContainer,
+    pid: sys::process::ProcessId,
+) -> Result<(), error::Error> {
+    match container {
+        ProcessContainer::JobObject(name) => {
+            let job = open_job_object(name.as_str(), JOB_OBJECT_ASSIGN_PROCESS)?;
+
+            #[allow(clippy::cast_sign_loss)]
+            // SAFETY: Opening a process has no preconditions.
+            let process = OwnedHandle::new(unsafe {
+                OpenProcess(PROCESS_SET_QUOTA | PROCESS_TERMINATE, FALSE, pid as u32)
+            })?;
+
+            // SAFETY: Both handles are valid.
+            if unsafe { AssignProcessToJobObject(job.0, process.0) } == FALSE {
+                return Err(std::io::Error::last_os_error().into());
+            }
+
+            Ok(())
+        }
+        ProcessContainer::Cgroup(_) => Err(error::Error::UnsupportedProcessContainer),
+    }
+}
+
+pub(crate) fn terminate_all(container: &ProcessContainer) -> Result<(), error::Error> {
+    match container {
+        ProcessContainer::JobObject(name) => {
+            let job = open_job_object(name.as_str(), JOB_OBJECT_TERMINATE)?;
+
+            // SAFETY: The handle is valid.
+            if unsafe { TerminateJobObject(job.0, 1) } == FALSE {
+                return Err(std::io::Error::last_os_error().into());
+            }
+
+            Ok(())
+        }
+        ProcessContainer::Cgroup(_) => Err(error::Error::UnsupportedProcessContainer),
+    }
+}
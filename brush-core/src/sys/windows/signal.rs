@@ -0,0 +1,32 @@
+//! Signal handling on Windows. Windows has no signals to speak of; beyond handling
+//! Ctrl+C and terminating processes, we fall back to the stub implementation.
+
+use crate::{error, sys};
+
+pub(crate) use crate::sys::stubs::signal::*;
+pub(crate) use tokio::signal::ctrl_c as await_ctrl_c;
+
+pub(crate) fn terminate_process(pid: sys::process::ProcessId) -> Result<(), error::Error> {
+    // N.B. There's no graceful equivalent of SIGTERM for arbitrary processes, so we
+    // forcibly terminate the process (and any children it has).
+    let status = std::process::Command::new("taskkill")
+        .args(["/F", "/T", "/PID", pid.to_string().as_str()])
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status()?;
+
+    if status.success() {
+        Ok(())
+    } else {
+        Err(error::Error::FailedToSendSignal)
+    }
+}
+
+pub(crate) fn interrupt_process(pid: sys::process::ProcessId) -> Result<(), error::Error> {
+    // N.B. Ctrl+C can't be delivered to a single process, so we terminate it instead.
+    terminate_process(pid)
+}
+
+pub(crate) fn kill_process_forcibly(pid: sys::process::ProcessId) -> Result<(), error::Error> {
+    terminate_process(pid)
+}
@@ -1,6 +1,10 @@
 use crate::error;
 use std::path::PathBuf;
 
+/// Whether querying users and groups is supported on this platform. Home directories
+/// and the current user's name can be looked up, but user and group IDs aren't available.
+pub(crate) const SUPPORTED: bool = false;
+
 //
 // Non-Unix implementation
 //
@@ -1,3 +1,6 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
 use crate::{error, sys};
 
 /// Encapsulates the state of a controlled terminal.
@@ -39,3 +42,70 @@ impl Drop for TerminalControl {
         self.try_release();
     }
 }
+
+/// Size of a terminal window, in characters.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct WindowSize {
+    /// Number of lines.
+    pub rows: u16,
+    /// Number of columns.
+    pub columns: u16,
+}
+
+/// Returns the size of the terminal the shell is attached to, if it's attached to one.
+pub(crate) fn get_window_size() -> Option<WindowSize> {
+    sys::terminal::get_window_size().map(|(rows, columns)| WindowSize { rows, columns })
+}
+
+/// Tracks whether the terminal may have been resized (i.e., the shell has received
+/// `SIGWINCH`) since last checked. Cloned handles share the same state.
+#[derive(Clone, Default)]
+pub(crate) struct WindowSizeMonitor {
+    /// Whether a resize has been flagged but not yet observed.
+    pending: Arc<AtomicBool>,
+    /// Background task that flags resizes, if started.
+    #[cfg(unix)]
+    signal_task: Option<Arc<tokio::task::JoinHandle<()>>>,
+}
+
+impl WindowSizeMonitor {
+    /// Returns whether the terminal may have been resized since the last call, clearing the
+    /// flag.
+    pub fn take_pending_changes(&self) -> bool {
+        self.pending.swap(false, Ordering::SeqCst)
+    }
+
+    /// Starts the background task that watches for the terminal being resized, if it's not
+    /// already running.
+    pub fn ensure_started(&mut self) {
+        #[cfg(unix)]
+        if self.signal_task.is_none() {
+            use tokio::signal::unix::{signal, SignalKind};
+
+            let Ok(mut sigwinch) = signal(SignalKind::window_change()) else {
+                tracing::debug!("failed to listen for SIGWINCH");
+                return;
+            };
+
+            let pending = self.pending.clone();
+            self.signal_task = Some(Arc::new(tokio::spawn(async move {
+                while sigwinch.recv().await.is_some() {
+                    pending.store(true, Ordering::SeqCst);
+                }
+            })));
+        }
+    }
+}
+
+#[cfg(unix)]
+impl Drop for WindowSizeMonitor {
+    fn drop(&mut self) {
+        // N.B. As with the job monitor, the signal task is stopped once the last handle
+        // that shares it goes away.
+        if let Some(task) = &self.signal_task {
+            if Arc::strong_count(task) == 1 {
+                task.abort();
+            }
+        }
+    }
+}
@@ -1,4 +1,5 @@
-use rand::Rng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
 use std::collections::BTreeMap;
 use std::fmt::{Display, Write};
@@ -6,7 +7,7 @@ use std::fmt::{Display, Write};
 use crate::{error, escape};
 
 /// A shell variable.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct ShellVariable {
     /// The value currently associated with the variable.
     value: ShellValue,
@@ -27,7 +28,7 @@ pub struct ShellVariable {
 }
 
 /// Kind of transformation to apply to a variable's value when it is updated.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub enum ShellVariableUpdateTransform {
     /// No transformation.
     None,
@@ -202,6 +203,16 @@ impl ShellVariable {
         }
     }
 
+    /// Applies the variable's update transform (e.g., from `declare -l`) to a value being
+    /// assigned to it.
+    fn transform_value(&self, value: String) -> String {
+        match self.transform_on_update {
+            ShellVariableUpdateTransform::None => value,
+            ShellVariableUpdateTransform::Lowercase => value.to_lowercase(),
+            ShellVariableUpdateTransform::Uppercase => value.to_uppercase(),
+        }
+    }
+
     /// Assign the given value to the variable, conditionally appending to the preexisting value.
     ///
     /// # Arguments
@@ -213,6 +224,18 @@ impl ShellVariable {
             return Err(error::Error::ReadonlyVariable);
         }
 
+        let value = match value {
+            ShellValueLiteral::Scalar(s) => ShellValueLiteral::Scalar(self.transform_value(s)),
+            ShellValueLiteral::Array(ArrayLiteral(elements)) => {
+                ShellValueLiteral::Array(ArrayLiteral(
+                    elements
+                        .into_iter()
+                        .map(|(key, value)| (key, self.transform_value(value)))
+                        .collect(),
+                ))
+            }
+        };
+
         if append {
             match (&self.value, &value) {
                 // If we're appending an array to a declared-but-unset variable (or appending
@@ -232,9 +255,9 @@ impl ShellVariable {
                 (ShellValue::Unset(_), ShellValueLiteral::Scalar(_)) => {
                     self.assign(ShellValueLiteral::Scalar(String::new()), false)?;
                 }
-                // If we're trying to append an array to a string, we first promote the string to be
-                // an array with the string being present at index 0.
-                (ShellValue::String(_), ShellValueLiteral::Array(_)) => {
+                // If we're trying to append an array to a string (or dynamic value), we first
+                // promote the string to be an array with the string being present at index 0.
+                (ShellValue::String(_) | ShellValue::Dynamic(_), ShellValueLiteral::Array(_)) => {
                     self.convert_to_indexed_array()?;
                 }
                 _ => (),
@@ -280,7 +303,19 @@ impl ShellVariable {
                     }
                 },
                 ShellValue::Unset(_) => unreachable!("covered in conversion above"),
-                ShellValue::Random => Ok(()),
+                ShellValue::Dynamic(dynamic_value) => {
+                    if let ShellValueLiteral::Scalar(suffix) = value {
+                        let base = dynamic_value.get();
+                        let new_value = if treat_as_int {
+                            (base.parse::<i64>().unwrap_or(0) + suffix.parse::<i64>().unwrap_or(0))
+                                .to_string()
+                        } else {
+                            base + suffix.as_str()
+                        };
+                        dynamic_value.assign(new_value.as_str());
+                    }
+                    Ok(())
+                }
             }
         } else {
             match (&self.value, value) {
@@ -304,7 +339,7 @@ impl ShellVariable {
                         ShellValueUnsetType::IndexedArray | ShellValueUnsetType::Untyped,
                     )
                     | ShellValue::String(_)
-                    | ShellValue::Random,
+                    | ShellValue::Dynamic(_),
                     ShellValueLiteral::Array(literal_values),
                 ) => {
                     self.value = ShellValue::indexed_array_from_literals(literal_values)?;
@@ -322,8 +357,13 @@ impl ShellVariable {
                     Ok(())
                 }
 
-                // Drop other updates to random values.
-                (ShellValue::Random, _) => Ok(()),
+                // Let dynamic values decide how to handle scalar assignments.
+                (ShellValue::Dynamic(_), ShellValueLiteral::Scalar(s)) => {
+                    if let ShellValue::Dynamic(dynamic_value) = &mut self.value {
+                        dynamic_value.assign(s.as_str());
+                    }
+                    Ok(())
+                }
 
                 // Assign a scalar value to a scalar or unset (and untyped) variable.
                 (ShellValue::String(_) | ShellValue::Unset(_), ShellValueLiteral::Scalar(s)) => {
@@ -350,6 +390,8 @@ impl ShellVariable {
         value: String,
         append: bool,
     ) -> Result<(), error::Error> {
+        let value = self.transform_value(value);
+
         match &self.value {
             ShellValue::Unset(_) => {
                 self.assign(ShellValueLiteral::Array(ArrayLiteral(vec![])), false)?;
@@ -429,7 +471,7 @@ impl ShellVariable {
                     Ok(false)
                 }
             },
-            ShellValue::String(_) | ShellValue::Random => Err(error::Error::NotArray),
+            ShellValue::String(_) | ShellValue::Dynamic(_) => Err(error::Error::NotArray),
             ShellValue::AssociativeArray(values) => Ok(values.remove(index).is_some()),
             ShellValue::IndexedArray(values) => {
                 let key = index.parse::<u64>().unwrap_or(0);
@@ -464,25 +506,25 @@ impl ShellVariable {
         if self.is_readonly() {
             result.push('r');
         }
-        if let ShellVariableUpdateTransform::Lowercase = self.get_update_transform() {
-            result.push('l');
-        }
         if self.is_trace_enabled() {
             result.push('t');
         }
-        if let ShellVariableUpdateTransform::Uppercase = self.get_update_transform() {
-            result.push('u');
-        }
         if self.is_exported() {
             result.push('x');
         }
+        if let ShellVariableUpdateTransform::Lowercase = self.get_update_transform() {
+            result.push('l');
+        }
+        if let ShellVariableUpdateTransform::Uppercase = self.get_update_transform() {
+            result.push('u');
+        }
 
         result
     }
 }
 
 /// A shell value.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub enum ShellValue {
     /// A value that has been typed but not yet set.
     Unset(ShellValueUnsetType),
@@ -492,12 +534,146 @@ pub enum ShellValue {
     AssociativeArray(BTreeMap<String, String>),
     /// An indexed array.
     IndexedArray(BTreeMap<u64, String>),
-    /// A special value that yields a different random number each time its read.
-    Random,
+    /// A special value whose contents are computed each time it's read. Such values are
+    /// owned by the shell and aren't serialized.
+    #[serde(skip)]
+    Dynamic(DynamicValue),
 }
 
-/// The type of an unset shell value.
+/// A value computed by the shell each time it's read (e.g., `$RANDOM` or `$SECONDS`).
 #[derive(Clone, Debug)]
+pub enum DynamicValue {
+    /// Yields a pseudo-random integer between 0 and 32767; assigning a value reseeds the
+    /// generator.
+    Random(RandomGenerator),
+    /// Yields a 32-bit random integer from a cryptographically secure source; assignments are
+    /// ignored.
+    SecureRandom,
+    /// Yields the number of seconds elapsed since the counter was last set; assigning a value
+    /// rebases the counter to start from that value.
+    Seconds(SecondsCounter),
+    /// Yields the number of whole seconds since the Unix epoch; assignments are ignored.
+    EpochSeconds,
+    /// Yields the number of seconds since the Unix epoch, with microsecond precision;
+    /// assignments are ignored.
+    EpochRealTime,
+}
+
+impl DynamicValue {
+    /// Returns a new `$RANDOM`-style value, seeded from system entropy.
+    pub fn random() -> Self {
+        DynamicValue::Random(RandomGenerator::default())
+    }
+
+    /// Returns a new `$SECONDS`-style value, counting up from zero as of now.
+    pub fn seconds() -> Self {
+        DynamicValue::Seconds(SecondsCounter::new(0))
+    }
+
+    /// Computes the current value.
+    pub fn get(&self) -> String {
+        match self {
+            DynamicValue::Random(generator) => generator.next_value().to_string(),
+            DynamicValue::SecureRandom => rand::rngs::OsRng.gen::<u32>().to_string(),
+            DynamicValue::Seconds(counter) => counter.value().to_string(),
+            DynamicValue::EpochSeconds => time_since_epoch().as_secs().to_string(),
+            DynamicValue::EpochRealTime => {
+                let now = time_since_epoch();
+                std::format!("{}.{:06}", now.as_secs(), now.subsec_micros())
+            }
+        }
+    }
+
+    /// Applies an assignment of the given value.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The value being assigned.
+    pub fn assign(&mut self, value: &str) {
+        match self {
+            DynamicValue::Random(generator) => generator.reseed(value.parse().unwrap_or(0)),
+            DynamicValue::Seconds(counter) => {
+                *counter = SecondsCounter::new(value.parse().unwrap_or(0))
+            }
+            DynamicValue::SecureRandom
+            | DynamicValue::EpochSeconds
+            | DynamicValue::EpochRealTime => {}
+        }
+    }
+}
+
+/// Pseudo-random number generator backing `$RANDOM`.
+#[derive(Debug)]
+pub struct RandomGenerator(std::sync::Mutex<rand::rngs::StdRng>);
+
+impl Default for RandomGenerator {
+    fn default() -> Self {
+        Self(std::sync::Mutex::new(rand::rngs::StdRng::from_entropy()))
+    }
+}
+
+impl Clone for RandomGenerator {
+    // N.B. As in bash, clones (e.g., for subshells) are freshly seeded rather than
+    // seeded from this generator, so they neither repeat its upcoming sequence nor
+    // advance it.
+    fn clone(&self) -> Self {
+        Self::default()
+    }
+}
+
+impl RandomGenerator {
+    fn lock(&self) -> std::sync::MutexGuard<'_, rand::rngs::StdRng> {
+        self.0
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+
+    fn next_value(&self) -> u16 {
+        self.lock().gen_range(0..32768)
+    }
+
+    #[allow(clippy::cast_sign_loss)]
+    fn reseed(&mut self, seed: i64) {
+        *self
+            .0
+            .get_mut()
+            .unwrap_or_else(std::sync::PoisonError::into_inner) =
+            rand::rngs::StdRng::seed_from_u64(seed as u64);
+    }
+}
+
+/// Counter backing `$SECONDS`.
+#[derive(Clone, Copy, Debug)]
+pub struct SecondsCounter {
+    /// When the counter was last set.
+    base: std::time::Instant,
+    /// The value the counter was set to.
+    offset: i64,
+}
+
+impl SecondsCounter {
+    fn new(offset: i64) -> Self {
+        Self {
+            base: std::time::Instant::now(),
+            offset,
+        }
+    }
+
+    #[allow(clippy::cast_possible_wrap)]
+    fn value(&self) -> i64 {
+        self.offset
+            .saturating_add(self.base.elapsed().as_secs() as i64)
+    }
+}
+
+fn time_since_epoch() -> std::time::Duration {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+}
+
+/// The type of an unset shell value.
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub enum ShellValueUnsetType {
     /// The value is untyped.
     Untyped,
@@ -693,25 +869,31 @@ impl ShellValue {
     pub fn format(&self, style: FormatStyle) -> Result<Cow<'_, str>, error::Error> {
         match self {
             ShellValue::Unset(_) => Ok("".into()),
-            ShellValue::String(s) => {
-                // TODO: Handle embedded newlines and other special chars.
-                match style {
-                    FormatStyle::Basic => {
-                        if s.contains(' ') {
-                            Ok(format!("'{s}'").into())
-                        } else {
-                            Ok(s.into())
-                        }
+            ShellValue::String(s) => match style {
+                FormatStyle::Basic => {
+                    if s.contains(' ') {
+                        Ok(format!("'{s}'").into())
+                    } else {
+                        Ok(s.into())
                     }
-                    FormatStyle::DeclarePrint => Ok(format!("\"{s}\"").into()),
                 }
-            }
+                FormatStyle::DeclarePrint => {
+                    Ok(escape::force_quote(s, escape::QuoteMode::DoubleQuote).into())
+                }
+            },
             ShellValue::AssociativeArray(values) => {
                 let mut result = String::new();
                 result.push('(');
 
                 for (key, value) in values {
-                    write!(result, "[{key}]=\"{value}\" ")?;
+                    // N.B. The `@` key is quoted to distinguish it from an `@` subscript.
+                    let key: Cow<'_, str> = if key == "@" {
+                        escape::force_quote(key, escape::QuoteMode::DoubleQuote).into()
+                    } else {
+                        escape::quote_if_needed(key, escape::QuoteMode::DoubleQuote)
+                    };
+                    let value = escape::force_quote(value, escape::QuoteMode::DoubleQuote);
+                    write!(result, "[{key}]={value} ")?;
                 }
 
                 result.push(')');
@@ -725,13 +907,16 @@ impl ShellValue {
                     if i > 0 {
                         result.push(' ');
                     }
-                    write!(result, "[{key}]=\"{value}\"")?;
+                    let value = escape::force_quote(value, escape::QuoteMode::DoubleQuote);
+                    write!(result, "[{key}]={value}")?;
                 }
 
                 result.push(')');
                 Ok(result.into())
             }
-            ShellValue::Random => Ok(std::format!("\"{}\"", get_random_str()).into()),
+            ShellValue::Dynamic(dynamic_value) => {
+                Ok(std::format!("\"{}\"", dynamic_value.get()).into())
+            }
         }
     }
 
@@ -758,7 +943,7 @@ impl ShellValue {
                 let key = index.parse::<u64>().unwrap_or(0);
                 Ok(values.get(&key).map(|s| Cow::Borrowed(s.as_str())))
             }
-            ShellValue::Random => Ok(Some(Cow::Owned(get_random_str()))),
+            ShellValue::Dynamic(dynamic_value) => Ok(Some(Cow::Owned(dynamic_value.get()))),
         }
     }
 
@@ -766,7 +951,7 @@ impl ShellValue {
     pub fn get_element_keys(&self) -> Vec<String> {
         match self {
             ShellValue::Unset(_) => vec![],
-            ShellValue::String(_) | ShellValue::Random => vec!["0".to_owned()],
+            ShellValue::String(_) | ShellValue::Dynamic(_) => vec!["0".to_owned()],
             ShellValue::AssociativeArray(array) => array.keys().map(|k| k.to_owned()).collect(),
             ShellValue::IndexedArray(array) => array.keys().map(|k| k.to_string()).collect(),
         }
@@ -779,7 +964,7 @@ impl ShellValue {
             ShellValue::String(s) => vec![s.to_owned()],
             ShellValue::AssociativeArray(array) => array.values().map(|v| v.to_owned()).collect(),
             ShellValue::IndexedArray(array) => array.values().map(|v| v.to_owned()).collect(),
-            ShellValue::Random => vec![get_random_str()],
+            ShellValue::Dynamic(dynamic_value) => vec![dynamic_value.get()],
         }
     }
 
@@ -794,7 +979,7 @@ impl ShellValue {
             ShellValue::IndexedArray(values) => values
                 .get(&0)
                 .map_or_else(|| Cow::Borrowed(""), |s| Cow::Borrowed(s.as_str())),
-            ShellValue::Random => Cow::Owned(get_random_str()),
+            ShellValue::Dynamic(dynamic_value) => Cow::Owned(dynamic_value.get()),
         }
     }
 
@@ -818,7 +1003,9 @@ impl ShellValue {
                     self.format(FormatStyle::DeclarePrint).unwrap().into_owned()
                 }
             }
-            ShellValue::Random => quote_str_for_assignment(get_random_str().as_str()),
+            ShellValue::Dynamic(dynamic_value) => {
+                quote_str_for_assignment(dynamic_value.get().as_str())
+            }
         }
     }
 }
@@ -841,12 +1028,6 @@ impl From<String> for ShellValue {
     }
 }
 
-pub(crate) fn get_random_str() -> String {
-    let mut rng = rand::thread_rng();
-    let value = rng.gen_range(0..32768);
-    value.to_string()
-}
-
 pub(crate) fn quote_str_for_assignment(s: &str) -> String {
     let mut result = String::new();
 
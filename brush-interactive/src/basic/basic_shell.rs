@@ -12,6 +12,7 @@ use super::term_line_reader;
 /// for completion and test-focused automation via pexpect and similar technologies.
 pub struct BasicShell {
     shell: brush_core::Shell,
+    completion_timeout: Option<std::time::Duration>,
 }
 
 impl BasicShell {
@@ -21,8 +22,14 @@ impl BasicShell {
     ///
     /// * `options` - Options for creating the interactive shell.
     pub async fn new(options: &crate::Options) -> Result<Self, ShellError> {
-        let shell = brush_core::Shell::new(&options.shell).await?;
-        Ok(Self { shell })
+        let mut shell = brush_core::Shell::new(&options.shell).await?;
+        shell.prompt_timeout = options.prompt_timeout;
+        crate::term::set_default_title_template(&mut shell, options.term_title.as_deref())?;
+
+        Ok(Self {
+            shell,
+            completion_timeout: options.completion_timeout,
+        })
     }
 }
 
@@ -38,29 +45,53 @@ impl InteractiveShell for BasicShell {
     }
 
     fn read_line(&mut self, prompt: InteractivePrompt) -> Result<ReadResult, ShellError> {
-        self.display_prompt(&prompt)?;
+        let mut current_prompt = prompt.prompt.clone();
+        self.display_prompt(current_prompt.as_str())?;
 
-        let mut result = String::new();
+        let mut parser = brush_parser::StreamingParser::new(
+            &self.shell.program_parser_options(),
+            &brush_parser::SourceInfo::default(),
+        );
 
         loop {
-            match self.read_input_line(&prompt)? {
-                ReadResult::Input(s) => {
-                    result.push_str(s.as_str());
-                    if self.is_valid_input(result.as_str()) {
-                        break;
+            // N.B. A here-document body is literal text, so we don't offer completions
+            // while it's being entered.
+            let in_here_document = matches!(
+                parser.unterminated_construct(),
+                Some(brush_parser::UnterminatedConstruct::HereDocument(_))
+            );
+
+            let result = match self.read_input_line(current_prompt.as_str(), !in_here_document)? {
+                ReadResult::Input(mut s) => {
+                    // N.B. Lines read from the terminal don't include their terminating
+                    // newline.
+                    if !s.ends_with('\n') {
+                        s.push('\n');
                     }
+                    parser.feed(s.as_str());
+                    parser.poll()
+                }
+                ReadResult::Eof => parser.finish(),
+                result @ (ReadResult::Interrupted | ReadResult::Refresh) => return Ok(result),
+            };
+
+            // N.B. Syntax errors are reported when the input is run.
+            match result {
+                brush_parser::StreamingParseResult::Complete { text, .. }
+                | brush_parser::StreamingParseResult::Error { text, .. } => {
+                    return Ok(ReadResult::Input(text));
                 }
-                ReadResult::Eof => {
-                    if result.is_empty() {
+                brush_parser::StreamingParseResult::NeedMoreInput => {
+                    if !parser.has_pending_input() {
                         return Ok(ReadResult::Eof);
                     }
-                    break;
+
+                    current_prompt =
+                        prompt.continuation_prompt_for(parser.unterminated_construct());
+                    self.display_prompt(current_prompt.as_str())?;
                 }
-                ReadResult::Interrupted => return Ok(ReadResult::Interrupted),
             }
         }
-
-        Ok(ReadResult::Input(result))
     }
 
     fn update_history(&mut self) -> Result<(), ShellError> {
@@ -74,19 +105,24 @@ impl BasicShell {
         std::io::stdin().is_terminal()
     }
 
-    fn display_prompt(&self, prompt: &InteractivePrompt) -> Result<(), ShellError> {
+    fn display_prompt(&self, prompt: &str) -> Result<(), ShellError> {
         if self.should_display_prompt() {
-            eprint!("{}", prompt.prompt);
+            eprint!("{prompt}");
             std::io::stderr().flush()?;
         }
 
         Ok(())
     }
 
-    fn read_input_line(&mut self, prompt: &InteractivePrompt) -> Result<ReadResult, ShellError> {
+    fn read_input_line(&mut self, prompt: &str, complete: bool) -> Result<ReadResult, ShellError> {
         if std::io::stdin().is_terminal() {
-            term_line_reader::read_line(prompt.prompt.as_str(), |line, cursor| {
-                self.generate_completions(line, cursor)
+            let abbreviations = self.shell.abbreviations.clone();
+            term_line_reader::read_line(prompt, &abbreviations, |line, cursor| {
+                if complete {
+                    self.generate_completions(line, cursor)
+                } else {
+                    Ok(completion::no_completions(cursor))
+                }
             })
         } else {
             let mut input = String::new();
@@ -102,18 +138,6 @@ impl BasicShell {
         }
     }
 
-    fn is_valid_input(&self, input: &str) -> bool {
-        match self.shell.parse_string(input.to_owned()) {
-            Err(brush_parser::ParseError::Tokenizing { inner, position: _ })
-                if inner.is_incomplete() =>
-            {
-                false
-            }
-            Err(brush_parser::ParseError::ParsingAtEndOfInput) => false,
-            _ => true,
-        }
-    }
-
     fn generate_completions(
         &mut self,
         line: &str,
@@ -130,6 +154,14 @@ impl BasicShell {
         line: &str,
         cursor: usize,
     ) -> Result<brush_core::completion::Completions, ShellError> {
-        Ok(completion::complete_async(&mut self.shell, line, cursor).await)
+        let completions = completion::complete_async(
+            &mut self.shell,
+            line,
+            cursor,
+            self.completion_timeout,
+            true,
+        )
+        .await;
+        Ok(completions)
     }
 }
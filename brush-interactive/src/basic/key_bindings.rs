@@ -0,0 +1,91 @@
+//! Key bindings for the basic backend's line editor. Editing functions are named as they are
+//! in readline (and bound to the same keys as in its emacs mode), so users of the basic
+//! backend find the keys they're used to.
+
+use crossterm::event::{KeyCode, KeyModifiers};
+
+/// Line-editing functions supported by the basic backend's line editor, corresponding to the
+/// readline functions of the same names (e.g., `kill-line` and `yank-pop`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum EditFunction {
+    /// Moves to the start of the line.
+    BeginningOfLine,
+    /// Moves to the end of the line.
+    EndOfLine,
+    /// Moves back a character.
+    BackwardChar,
+    /// Moves forward a character.
+    ForwardChar,
+    /// Moves back to the start of the current or previous word.
+    BackwardWord,
+    /// Moves forward to the end of the current or next word.
+    ForwardWord,
+    /// Deletes the character before the cursor.
+    BackwardDeleteChar,
+    /// Deletes the character under the cursor.
+    DeleteChar,
+    /// Kills from the cursor to the end of the line.
+    KillLine,
+    /// Kills from the start of the line to the cursor.
+    UnixLineDiscard,
+    /// Kills the whitespace-delimited word behind the cursor.
+    UnixWordRubout,
+    /// Kills from the cursor to the end of the current or next word.
+    KillWord,
+    /// Kills from the cursor to the start of the current or previous word.
+    BackwardKillWord,
+    /// Inserts the most recently killed text.
+    Yank,
+    /// Replaces the text just yanked with the previously killed text.
+    YankPop,
+    /// Undoes the last edit.
+    Undo,
+}
+
+const CTRL: KeyModifiers = KeyModifiers::CONTROL;
+const ALT: KeyModifiers = KeyModifiers::ALT;
+const NONE: KeyModifiers = KeyModifiers::NONE;
+
+/// Default key bindings, as in readline's emacs mode.
+const DEFAULT_BINDINGS: &[(KeyModifiers, KeyCode, EditFunction)] = &[
+    (CTRL, KeyCode::Char('a'), EditFunction::BeginningOfLine),
+    (NONE, KeyCode::Home, EditFunction::BeginningOfLine),
+    (CTRL, KeyCode::Char('e'), EditFunction::EndOfLine),
+    (NONE, KeyCode::End, EditFunction::EndOfLine),
+    (CTRL, KeyCode::Char('b'), EditFunction::BackwardChar),
+    (NONE, KeyCode::Left, EditFunction::BackwardChar),
+    (CTRL, KeyCode::Char('f'), EditFunction::ForwardChar),
+    (NONE, KeyCode::Right, EditFunction::ForwardChar),
+    (ALT, KeyCode::Char('b'), EditFunction::BackwardWord),
+    (CTRL, KeyCode::Left, EditFunction::BackwardWord),
+    (ALT, KeyCode::Char('f'), EditFunction::ForwardWord),
+    (CTRL, KeyCode::Right, EditFunction::ForwardWord),
+    (NONE, KeyCode::Backspace, EditFunction::BackwardDeleteChar),
+    (CTRL, KeyCode::Char('h'), EditFunction::BackwardDeleteChar),
+    (NONE, KeyCode::Delete, EditFunction::DeleteChar),
+    (CTRL, KeyCode::Char('k'), EditFunction::KillLine),
+    (CTRL, KeyCode::Char('u'), EditFunction::UnixLineDiscard),
+    (CTRL, KeyCode::Char('w'), EditFunction::UnixWordRubout),
+    (ALT, KeyCode::Char('d'), EditFunction::KillWord),
+    (ALT, KeyCode::Backspace, EditFunction::BackwardKillWord),
+    (CTRL, KeyCode::Char('y'), EditFunction::Yank),
+    (ALT, KeyCode::Char('y'), EditFunction::YankPop),
+    (CTRL, KeyCode::Char('_'), EditFunction::Undo),
+    // N.B. Terminals send the same code for Ctrl+_ and Ctrl+/, which is reported to us as
+    // Ctrl+7.
+    (CTRL, KeyCode::Char('7'), EditFunction::Undo),
+    (CTRL, KeyCode::Char('/'), EditFunction::Undo),
+];
+
+/// Looks up the editing function bound to the given key, if any.
+///
+/// # Arguments
+///
+/// * `modifiers` - The modifiers held with the key.
+/// * `code` - The key.
+pub(crate) fn lookup(modifiers: KeyModifiers, code: KeyCode) -> Option<EditFunction> {
+    DEFAULT_BINDINGS
+        .iter()
+        .find(|(m, c, _)| *m == modifiers && *c == code)
+        .map(|(_, _, f)| *f)
+}
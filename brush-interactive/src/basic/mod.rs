@@ -1,4 +1,5 @@
 mod basic_shell;
+mod key_bindings;
 mod raw_mode;
 mod term_line_reader;
 
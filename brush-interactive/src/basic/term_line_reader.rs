@@ -5,21 +5,27 @@
 //
 
 use crossterm::ExecutableCommand;
+use std::collections::{HashMap, VecDeque};
 use std::io::Write;
 
+use super::key_bindings::{self, EditFunction};
 use super::raw_mode;
 use crate::{ReadResult, ShellError};
 
 const BACKSPACE: char = 8u8 as char;
 
+/// Maximum number of killed texts retained in the kill ring.
+const MAX_KILL_RING_LEN: usize = 10;
+
 pub(crate) fn read_line(
     prompt: &str,
+    abbreviations: &HashMap<String, brush_core::abbreviations::Abbreviation>,
     mut completion_handler: impl FnMut(
         &str,
         usize,
     ) -> Result<brush_core::completion::Completions, ShellError>,
 ) -> Result<ReadResult, ShellError> {
-    let mut state = ReadLineState::new(prompt)?;
+    let mut state = ReadLineState::new(prompt, abbreviations)?;
 
     loop {
         state.raw_mode.enable()?;
@@ -31,20 +37,52 @@ pub(crate) fn read_line(
     }
 }
 
+/// The kind of the last editing operation performed, where it affects the next one.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum LastEdit {
+    Other,
+    /// Characters were inserted; consecutive insertions are undone together.
+    Insert,
+    /// Text was killed; consecutive kills are accumulated in the same kill ring entry.
+    Kill,
+    /// Text was yanked into the given byte range of the line; it may be replaced by a
+    /// subsequent `yank-pop`.
+    Yank {
+        start: usize,
+        end: usize,
+    },
+}
+
 struct ReadLineState<'a> {
     line: String,
     cursor: usize,
     prompt: &'a str,
+    abbreviations: &'a HashMap<String, brush_core::abbreviations::Abbreviation>,
     raw_mode: raw_mode::RawModeToggle,
+    /// Killed texts, most recent first.
+    kill_ring: VecDeque<String>,
+    /// Index in the kill ring of the text last yanked.
+    yank_index: usize,
+    /// Line contents and cursor positions to restore on undo, most recent last.
+    undo_stack: Vec<(String, usize)>,
+    last_edit: LastEdit,
 }
 
 impl<'a> ReadLineState<'a> {
-    fn new(prompt: &'a str) -> Result<Self, ShellError> {
+    fn new(
+        prompt: &'a str,
+        abbreviations: &'a HashMap<String, brush_core::abbreviations::Abbreviation>,
+    ) -> Result<Self, ShellError> {
         Ok(Self {
             line: String::new(),
             cursor: 0,
             prompt,
+            abbreviations,
             raw_mode: raw_mode::RawModeToggle::new()?,
+            kill_ring: VecDeque::new(),
+            yank_index: 0,
+            undo_stack: vec![],
+            last_edit: LastEdit::Other,
         })
     }
 
@@ -65,9 +103,15 @@ impl<'a> ReadLineState<'a> {
         )
             -> Result<brush_core::completion::Completions, ShellError>,
     ) -> Result<Option<ReadResult>, ShellError> {
+        if let Some(func) = key_bindings::lookup(event.modifiers, event.code) {
+            self.on_edit_function(func)?;
+            return Ok(None);
+        }
+
         match (event.modifiers, event.code) {
             (_, crossterm::event::KeyCode::Enter)
             | (crossterm::event::KeyModifiers::CONTROL, crossterm::event::KeyCode::Char('j')) => {
+                self.expand_abbreviation()?;
                 self.display_newline()?;
                 let line = std::mem::take(&mut self.line);
                 return Ok(Some(ReadResult::Input(line)));
@@ -76,6 +120,9 @@ impl<'a> ReadLineState<'a> {
                 crossterm::event::KeyModifiers::SHIFT | crossterm::event::KeyModifiers::NONE,
                 crossterm::event::KeyCode::Char(c),
             ) => {
+                if c == ' ' {
+                    self.expand_abbreviation()?;
+                }
                 self.on_char(c)?;
             }
             (crossterm::event::KeyModifiers::CONTROL, crossterm::event::KeyCode::Char('c')) => {
@@ -89,16 +136,12 @@ impl<'a> ReadLineState<'a> {
                     eprintln!();
                     return Ok(Some(ReadResult::Eof));
                 }
+
+                self.on_edit_function(EditFunction::DeleteChar)?;
             }
             (crossterm::event::KeyModifiers::CONTROL, crossterm::event::KeyCode::Char('l')) => {
                 self.clear_screen()?;
             }
-            (_, crossterm::event::KeyCode::Backspace) => {
-                self.backspace()?;
-            }
-            (_, crossterm::event::KeyCode::Left) => {
-                self.move_cursor_left()?;
-            }
             (_, crossterm::event::KeyCode::Tab) => {
                 let completions = completion_handler(self.line.as_str(), self.cursor)?;
                 self.handle_completions(&completions)?;
@@ -110,67 +153,261 @@ impl<'a> ReadLineState<'a> {
     }
 
     fn on_char(&mut self, c: char) -> Result<(), ShellError> {
+        // Consecutive insertions are undone together.
+        if self.last_edit != LastEdit::Insert {
+            self.save_undo_state();
+        }
+
+        let old_line = self.line.clone();
+        let old_cursor = self.cursor;
+
         self.line.insert(self.cursor, c);
-        self.cursor += 1;
-        eprint!("{c}");
-        std::io::stderr().flush()?;
+        self.cursor += c.len_utf8();
+        self.last_edit = LastEdit::Insert;
 
-        Ok(())
+        self.redisplay(old_line.as_str(), old_cursor)
     }
 
-    fn display_newline(&mut self) -> Result<(), ShellError> {
-        self.raw_mode.disable()?;
-        eprintln!();
-        self.raw_mode.enable()?;
-        std::io::stderr().flush()?;
+    fn on_edit_function(&mut self, func: EditFunction) -> Result<(), ShellError> {
+        let old_line = self.line.clone();
+        let old_cursor = self.cursor;
+        let last_edit = std::mem::replace(&mut self.last_edit, LastEdit::Other);
+
+        match func {
+            EditFunction::BeginningOfLine => self.cursor = 0,
+            EditFunction::EndOfLine => self.cursor = self.line.len(),
+            EditFunction::BackwardChar => self.cursor = self.previous_char_boundary(),
+            EditFunction::ForwardChar => self.cursor = self.next_char_boundary(),
+            EditFunction::BackwardWord => self.cursor = self.previous_word_start(),
+            EditFunction::ForwardWord => self.cursor = self.next_word_end(),
+            EditFunction::BackwardDeleteChar => {
+                self.delete(self.previous_char_boundary()..self.cursor);
+            }
+            EditFunction::DeleteChar => self.delete(self.cursor..self.next_char_boundary()),
+            EditFunction::KillLine => self.kill(self.cursor..self.line.len(), last_edit),
+            EditFunction::UnixLineDiscard => self.kill(0..self.cursor, last_edit),
+            EditFunction::UnixWordRubout => {
+                let before_cursor = self.line[..self.cursor].trim_end();
+                let start = before_cursor
+                    .rfind(char::is_whitespace)
+                    .map_or(0, |index| index + 1);
+                self.kill(start..self.cursor, last_edit);
+            }
+            EditFunction::KillWord => self.kill(self.cursor..self.next_word_end(), last_edit),
+            EditFunction::BackwardKillWord => {
+                self.kill(self.previous_word_start()..self.cursor, last_edit);
+            }
+            EditFunction::Yank => {
+                if let Some(text) = self.kill_ring.front().cloned() {
+                    self.save_undo_state();
+                    self.yank_index = 0;
+                    self.insert_yanked(self.cursor..self.cursor, text.as_str());
+                }
+            }
+            EditFunction::YankPop => {
+                if let LastEdit::Yank { start, end } = last_edit {
+                    self.yank_index = (self.yank_index + 1) % self.kill_ring.len();
+                    let text = self.kill_ring[self.yank_index].clone();
+                    self.insert_yanked(start..end, text.as_str());
+                }
+            }
+            EditFunction::Undo => {
+                if let Some((line, cursor)) = self.undo_stack.pop() {
+                    self.line = line;
+                    self.cursor = cursor;
+                }
+            }
+        }
 
-        Ok(())
+        self.redisplay(old_line.as_str(), old_cursor)
     }
 
-    fn clear_screen(&mut self) -> Result<(), ShellError> {
-        std::io::stderr()
-            .execute(crossterm::terminal::Clear(
-                crossterm::terminal::ClearType::All,
-            ))?
-            .execute(crossterm::cursor::MoveTo(0, 0))?;
+    fn save_undo_state(&mut self) {
+        self.undo_stack.push((self.line.clone(), self.cursor));
+    }
 
-        self.display_prompt()?;
-        eprint!("{}", self.line.as_str());
+    fn delete(&mut self, range: std::ops::Range<usize>) {
+        if range.is_empty() {
+            return;
+        }
+
+        self.save_undo_state();
+        self.cursor = range.start;
+        self.line.replace_range(range, "");
+    }
+
+    fn kill(&mut self, range: std::ops::Range<usize>, last_edit: LastEdit) {
+        if range.is_empty() {
+            return;
+        }
+
+        self.save_undo_state();
+
+        let forward = range.start == self.cursor;
+        self.cursor = range.start;
+        let text: String = self.line.drain(range).collect();
+
+        // Consecutive kills accumulate in the same entry, so they can be yanked together.
+        match self.kill_ring.front_mut() {
+            Some(entry) if last_edit == LastEdit::Kill => {
+                if forward {
+                    entry.push_str(text.as_str());
+                } else {
+                    entry.insert_str(0, text.as_str());
+                }
+            }
+            _ => {
+                self.kill_ring.push_front(text);
+                self.kill_ring.truncate(MAX_KILL_RING_LEN);
+            }
+        }
+
+        self.last_edit = LastEdit::Kill;
+    }
+
+    fn insert_yanked(&mut self, range: std::ops::Range<usize>, text: &str) {
+        let start = range.start;
+        self.line.replace_range(range, text);
+        self.cursor = start + text.len();
+        self.last_edit = LastEdit::Yank {
+            start,
+            end: self.cursor,
+        };
+    }
+
+    fn previous_char_boundary(&self) -> usize {
+        self.line[..self.cursor]
+            .char_indices()
+            .next_back()
+            .map_or(0, |(index, _)| index)
+    }
+
+    fn next_char_boundary(&self) -> usize {
+        self.line[self.cursor..]
+            .chars()
+            .next()
+            .map_or(self.cursor, |c| self.cursor + c.len_utf8())
+    }
+
+    /// Returns the start of the word before the cursor (or containing it), where words are
+    /// runs of alphanumeric characters, as in readline.
+    fn previous_word_start(&self) -> usize {
+        let before_cursor =
+            self.line[..self.cursor].trim_end_matches(|c: char| !c.is_alphanumeric());
+        before_cursor
+            .char_indices()
+            .rev()
+            .find(|(_, c)| !c.is_alphanumeric())
+            .map_or(0, |(index, c)| index + c.len_utf8())
+    }
+
+    /// Returns the end of the word after the cursor (or containing it), where words are runs
+    /// of alphanumeric characters, as in readline.
+    fn next_word_end(&self) -> usize {
+        let after_cursor = &self.line[self.cursor..];
+        let word_start = after_cursor
+            .find(char::is_alphanumeric)
+            .unwrap_or(after_cursor.len());
+        after_cursor[word_start..]
+            .find(|c: char| !c.is_alphanumeric())
+            .map_or(self.line.len(), |index| self.cursor + word_start + index)
+    }
+
+    /// Updates the display of the line, given the line and cursor position (in bytes) as they
+    /// were last displayed. As elsewhere, the cursor is moved back with backspaces rather than
+    /// VT100-style escape sequences.
+    fn redisplay(&mut self, old_line: &str, old_cursor: usize) -> Result<(), ShellError> {
+        self.raw_mode.disable()?;
+
+        if old_line == self.line && self.cursor >= old_cursor {
+            eprint!("{}", &self.line[old_cursor..self.cursor]);
+        } else if old_line == self.line {
+            let distance = self.line[self.cursor..old_cursor].chars().count();
+            eprint!("{}", repeated_char_str(BACKSPACE, distance));
+        } else {
+            // Re-display the line from its start, erasing any characters left over if it's
+            // gotten shorter, and then move back to the cursor.
+            let old_len = old_line.chars().count();
+            let new_len = self.line.chars().count();
+            let erased_len = old_len.saturating_sub(new_len);
+            eprint!(
+                "{}{}{}",
+                repeated_char_str(BACKSPACE, old_line[..old_cursor].chars().count()),
+                self.line,
+                repeated_char_str(' ', erased_len)
+            );
+            eprint!(
+                "{}",
+                repeated_char_str(
+                    BACKSPACE,
+                    self.line[self.cursor..].chars().count() + erased_len
+                )
+            );
+        }
+
+        self.raw_mode.enable()?;
         std::io::stderr().flush()?;
+
         Ok(())
     }
 
-    fn backspace(&mut self) -> Result<(), ShellError> {
-        if self.cursor == 0 {
+    fn expand_abbreviation(&mut self) -> Result<(), ShellError> {
+        let Some((range, abbreviation)) = brush_core::abbreviations::find_expandable(
+            self.abbreviations,
+            self.line.as_str(),
+            self.cursor,
+        ) else {
             return Ok(());
-        }
+        };
+
+        self.save_undo_state();
+        self.last_edit = LastEdit::Other;
 
-        self.cursor -= 1;
-        self.line.remove(self.cursor);
+        let start = range.start;
+        let removed_len = range.len();
+        self.line
+            .replace_range(range, abbreviation.expansion.as_str());
+        self.cursor = start + abbreviation.expansion.len();
+
+        // Re-display the line from the start of the abbreviation, erasing any characters left
+        // over if the expansion is shorter than the abbreviation.
+        let erased_len = removed_len.saturating_sub(abbreviation.expansion.len());
         self.raw_mode.disable()?;
-        eprint!("{BACKSPACE}");
-        eprint!("{} ", &self.line[self.cursor..]);
+        eprint!(
+            "{}{}{}",
+            repeated_char_str(BACKSPACE, removed_len),
+            &self.line[start..],
+            repeated_char_str(' ', erased_len)
+        );
         eprint!(
             "{}",
-            repeated_char_str(BACKSPACE, self.line.len() + 1 - self.cursor)
+            repeated_char_str(BACKSPACE, self.line.len() - self.cursor + erased_len)
         );
         self.raw_mode.enable()?;
         std::io::stderr().flush()?;
+
         Ok(())
     }
 
-    fn move_cursor_left(&mut self) -> Result<(), ShellError> {
+    fn display_newline(&mut self) -> Result<(), ShellError> {
         self.raw_mode.disable()?;
-        eprint!("{BACKSPACE}");
+        eprintln!();
         self.raw_mode.enable()?;
         std::io::stderr().flush()?;
 
-        if self.cursor == 0 {
-            return Ok(());
-        }
+        Ok(())
+    }
 
-        self.cursor -= 1;
+    fn clear_screen(&mut self) -> Result<(), ShellError> {
+        std::io::stderr()
+            .execute(crossterm::terminal::Clear(
+                crossterm::terminal::ClearType::All,
+            ))?
+            .execute(crossterm::cursor::MoveTo(0, 0))?;
 
+        self.display_prompt()?;
+        eprint!("{}", self.line.as_str());
+        std::io::stderr().flush()?;
         Ok(())
     }
 
@@ -212,6 +449,9 @@ impl<'a> ReadLineState<'a> {
             delete_count = 0;
         }
 
+        self.save_undo_state();
+        self.last_edit = LastEdit::Other;
+
         let mut updated_line = self.line.clone();
         updated_line.truncate(completions.insertion_index);
         updated_line.push_str(candidate);
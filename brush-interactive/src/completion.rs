@@ -1,21 +1,48 @@
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 use indexmap::IndexSet;
 
-use crate::trace_categories;
+use crate::{here_documents, trace_categories};
 
+/// How often to check whether the user has typed more while completions are generated.
+#[cfg(any(feature = "basic", feature = "reedline"))]
+const INPUT_POLL_INTERVAL: Duration = Duration::from_millis(25);
+
+/// Generates completions for the given line. Generation is abandoned if the user interrupts
+/// it or (if requested) types more in the meantime (in which case the completions would be
+/// moot); if it takes longer than the given timeout, the candidates generated by then are
+/// returned.
+///
+/// # Arguments
+///
+/// * `shell` - The shell to generate completions with.
+/// * `line` - The line to generate completions for.
+/// * `pos` - The position of the cursor in the line.
+/// * `timeout` - Optionally, the longest to wait for completions.
+/// * `abandon_on_input` - Whether to abandon generation if the user types more at the
+///   terminal; this should only be requested when the shell is reading from the terminal.
 pub(crate) async fn complete_async(
     shell: &mut brush_core::Shell,
     line: &str,
     pos: usize,
+    timeout: Option<Duration>,
+    abandon_on_input: bool,
 ) -> brush_core::completion::Completions {
+    // N.B. A here-document body is literal text; there's nothing to complete within it.
+    if here_documents::is_in_body(line, pos, &shell.parser_options()) {
+        return no_completions(pos);
+    }
+
     let working_dir = shell.working_dir.clone();
+    let partial_completions = shell.completion_config.partial_completions();
 
     // Intentionally ignore any errors that arise.
     let completion_future = shell.get_completions(line, pos);
     tokio::pin!(completion_future);
 
-    // Wait for the completions to come back or interruption, whichever happens first.
+    // Wait for the completions to come back, interruption, further input, or the timeout,
+    // whichever happens first.
     let result = tokio::select! {
         result = &mut completion_future => {
             result
@@ -23,14 +50,17 @@ pub(crate) async fn complete_async(
         _ = tokio::signal::ctrl_c() => {
             Err(brush_core::Error::Interrupted)
         },
+        () = wait_for_pending_input(), if abandon_on_input => {
+            tracing::debug!(target: trace_categories::COMPLETION, "completion abandoned due to further input");
+            Err(brush_core::Error::Interrupted)
+        },
+        () = wait_for_timeout(timeout) => {
+            tracing::debug!(target: trace_categories::COMPLETION, "completion timed out");
+            Ok(partial_completions.take().unwrap_or_else(|| no_completions(pos)))
+        },
     };
 
-    let mut completions = result.unwrap_or_else(|_| brush_core::completion::Completions {
-        insertion_index: pos,
-        delete_count: 0,
-        candidates: IndexSet::new(),
-        options: brush_core::completion::ProcessingOptions::default(),
-    });
+    let mut completions = result.unwrap_or_else(|_| no_completions(pos));
 
     // TODO: Consider optimizing this out when not needed?
     let completing_end_of_line = pos == line.len();
@@ -50,6 +80,37 @@ pub(crate) async fn complete_async(
     completions
 }
 
+/// Resolves once the user has typed something that hasn't been read yet.
+#[cfg(any(feature = "basic", feature = "reedline"))]
+async fn wait_for_pending_input() {
+    // N.B. Polling doesn't consume the input; it remains to be read by the line editor.
+    while !crossterm::event::poll(Duration::ZERO).unwrap_or(false) {
+        tokio::time::sleep(INPUT_POLL_INTERVAL).await;
+    }
+}
+
+#[cfg(not(any(feature = "basic", feature = "reedline")))]
+async fn wait_for_pending_input() {
+    std::future::pending().await
+}
+
+/// Resolves once the given timeout (if any) elapses.
+async fn wait_for_timeout(timeout: Option<Duration>) {
+    match timeout {
+        Some(timeout) => tokio::time::sleep(timeout).await,
+        None => std::future::pending().await,
+    }
+}
+
+pub(crate) fn no_completions(pos: usize) -> brush_core::completion::Completions {
+    brush_core::completion::Completions {
+        insertion_index: pos,
+        delete_count: 0,
+        candidates: IndexSet::new(),
+        options: brush_core::completion::ProcessingOptions::default(),
+    }
+}
+
 fn postprocess_completion_candidate(
     mut candidate: String,
     options: &brush_core::completion::ProcessingOptions,
@@ -15,6 +15,10 @@ pub enum ShellError {
     #[error("input error occurred")]
     InputError,
 
+    /// An error occurred while communicating with an external line editor.
+    #[error("external line editor error: {0}")]
+    ExternalEditorError(String),
+
     /// The requested input backend type is not supported.
     #[error("requested input backend type not supported")]
     InputBackendNotSupported,
@@ -0,0 +1,170 @@
+use serde_json::{json, Value};
+
+use super::protocol;
+use crate::{
+    completion,
+    interactive_shell::{InteractivePrompt, InteractiveShell, ReadResult},
+    ShellError,
+};
+
+/// Represents an interactive shell that delegates reading input to an external line editor
+/// program (e.g., a GUI command palette), which it talks to over the program's standard
+/// input and output.
+pub struct ExternalShell {
+    shell: brush_core::Shell,
+    editor: protocol::EditorConnection,
+    completion_timeout: Option<std::time::Duration>,
+}
+
+impl ExternalShell {
+    /// Returns a new interactive shell instance, created with the provided options. The
+    /// external line editor is started right away.
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - Options for creating the interactive shell.
+    pub async fn new(options: &crate::Options) -> Result<Self, ShellError> {
+        let mut shell = brush_core::Shell::new(&options.shell).await?;
+        shell.prompt_timeout = options.prompt_timeout;
+        crate::term::set_default_title_template(&mut shell, options.term_title.as_deref())?;
+
+        let editor = protocol::EditorConnection::start(options.external_editor_command.as_slice())?;
+
+        Ok(Self {
+            shell,
+            editor,
+            completion_timeout: options.completion_timeout,
+        })
+    }
+}
+
+impl InteractiveShell for ExternalShell {
+    /// Returns an immutable reference to the inner shell object.
+    fn shell(&self) -> impl AsRef<brush_core::Shell> {
+        self.shell.as_ref()
+    }
+
+    /// Returns a mutable reference to the inner shell object.
+    fn shell_mut(&mut self) -> impl AsMut<brush_core::Shell> {
+        self.shell.as_mut()
+    }
+
+    fn read_line(&mut self, prompt: InteractivePrompt) -> Result<ReadResult, ShellError> {
+        let mut parser = brush_parser::StreamingParser::new(
+            &self.shell.program_parser_options(),
+            &brush_parser::SourceInfo::default(),
+        );
+
+        let mut params = json!({
+            "prompt": prompt.prompt,
+            "altSidePrompt": prompt.alt_side_prompt,
+            "continuation": false,
+        });
+
+        loop {
+            let result = match self.read_input_line(params)? {
+                ReadResult::Input(mut s) => {
+                    // N.B. Lines returned by the editor don't include their terminating
+                    // newline.
+                    if !s.ends_with('\n') {
+                        s.push('\n');
+                    }
+                    parser.feed(s.as_str());
+                    parser.poll()
+                }
+                ReadResult::Eof => parser.finish(),
+                result @ (ReadResult::Interrupted | ReadResult::Refresh) => return Ok(result),
+            };
+
+            // N.B. Syntax errors are reported when the input is run.
+            match result {
+                brush_parser::StreamingParseResult::Complete { text, .. }
+                | brush_parser::StreamingParseResult::Error { text, .. } => {
+                    return Ok(ReadResult::Input(text));
+                }
+                brush_parser::StreamingParseResult::NeedMoreInput => {
+                    if !parser.has_pending_input() {
+                        return Ok(ReadResult::Eof);
+                    }
+
+                    params = json!({
+                        "prompt": prompt.continuation_prompt_for(parser.unterminated_construct()),
+                        "altSidePrompt": "",
+                        "continuation": true,
+                    });
+                }
+            }
+        }
+    }
+
+    fn update_history(&mut self) -> Result<(), ShellError> {
+        Ok(())
+    }
+}
+
+impl ExternalShell {
+    /// Asks the editor to read a line of input, serving any completion requests it makes
+    /// while doing so.
+    fn read_input_line(&mut self, params: Value) -> Result<ReadResult, ShellError> {
+        let shell = &mut self.shell;
+        let completion_timeout = self.completion_timeout;
+
+        let result = self
+            .editor
+            .request("readLine", params, |method, params| match method {
+                "complete" => complete(shell, params, completion_timeout),
+                _ => Err(protocol::method_not_found(method)),
+            })?;
+
+        // If the editor went away, there's no more input to be had.
+        let Some(result) = result else {
+            return Ok(ReadResult::Eof);
+        };
+
+        if let Some(line) = result.get("line").and_then(Value::as_str) {
+            Ok(ReadResult::Input(line.to_owned()))
+        } else if result.get("interrupted").and_then(Value::as_bool) == Some(true) {
+            Ok(ReadResult::Interrupted)
+        } else {
+            Ok(ReadResult::Eof)
+        }
+    }
+}
+
+/// Generates completions for a command line at the given cursor position, expressed as a
+/// byte offset into the line.
+fn complete(
+    shell: &mut brush_core::Shell,
+    params: &Value,
+    timeout: Option<std::time::Duration>,
+) -> Result<Value, (i64, String)> {
+    let line = params
+        .get("line")
+        .and_then(Value::as_str)
+        .ok_or_else(|| protocol::invalid_param("line"))?;
+    let cursor = match params.get("cursor") {
+        Some(cursor) => cursor
+            .as_u64()
+            .and_then(|cursor| usize::try_from(cursor).ok())
+            .ok_or_else(|| protocol::invalid_param("cursor"))?,
+        None => line.len(),
+    };
+
+    if !line.is_char_boundary(cursor) {
+        return Err(protocol::invalid_param("cursor"));
+    }
+
+    // N.B. The editor, not the shell, is reading from the terminal, so we mustn't watch it
+    // for further input.
+    let completions = tokio::task::block_in_place(|| {
+        tokio::runtime::Handle::current().block_on(completion::complete_async(
+            shell, line, cursor, timeout, false,
+        ))
+    });
+
+    Ok(json!({
+        "insertionIndex": completions.insertion_index,
+        "deleteCount": completions.delete_count,
+        "candidates": completions.candidates.into_iter().collect::<Vec<_>>(),
+    }))
+}
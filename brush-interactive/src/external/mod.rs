@@ -0,0 +1,5 @@
+mod external_shell;
+mod protocol;
+
+#[allow(clippy::module_name_repetitions)]
+pub use external_shell::ExternalShell;
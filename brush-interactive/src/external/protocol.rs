@@ -0,0 +1,189 @@
+//! Implements the protocol spoken with an external line editor. The shell and the editor
+//! exchange JSON-RPC 2.0 messages, one message per line, over the editor's standard input
+//! and output. The protocol is documented in `docs/reference/external-editor-protocol.md`.
+
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, ChildStdout};
+
+use serde_json::{json, Value};
+
+use crate::ShellError;
+
+/// Version of the protocol implemented by the shell. Incremented whenever a change is made
+/// that isn't backwards compatible for editors.
+const PROTOCOL_VERSION: u64 = 1;
+
+/// Error code used when a request names a method the shell doesn't implement.
+pub(crate) const METHOD_NOT_FOUND: i64 = -32601;
+/// Error code used when a request's parameters are missing or malformed.
+pub(crate) const INVALID_PARAMS: i64 = -32602;
+
+/// A connection to a running external line editor.
+pub(crate) struct EditorConnection {
+    child: Child,
+    writer: ChildStdin,
+    reader: BufReader<ChildStdout>,
+    next_request_id: u64,
+}
+
+impl EditorConnection {
+    /// Starts the external line editor and initializes the connection to it.
+    ///
+    /// # Arguments
+    ///
+    /// * `command` - The program to run, followed by its arguments.
+    pub fn start(command: &[String]) -> Result<Self, ShellError> {
+        let Some((program, args)) = command.split_first() else {
+            return Err(ShellError::ExternalEditorError(String::from(
+                "no external line editor command given",
+            )));
+        };
+
+        let mut child = std::process::Command::new(program)
+            .args(args)
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .spawn()?;
+
+        let (Some(writer), Some(reader)) = (child.stdin.take(), child.stdout.take()) else {
+            return Err(ShellError::ExternalEditorError(String::from(
+                "couldn't connect to external line editor",
+            )));
+        };
+
+        let mut connection = Self {
+            child,
+            writer,
+            reader: BufReader::new(reader),
+            next_request_id: 1,
+        };
+
+        let params = json!({
+            "protocolVersion": PROTOCOL_VERSION,
+            "shellInfo": { "name": "brush", "version": env!("CARGO_PKG_VERSION") },
+        });
+        if connection
+            .request("initialize", params, |method, _| {
+                Err(method_not_found(method))
+            })?
+            .is_none()
+        {
+            return Err(ShellError::ExternalEditorError(String::from(
+                "external line editor exited during initialization",
+            )));
+        }
+
+        Ok(connection)
+    }
+
+    /// Sends a request to the editor and waits for its response, handling any requests the
+    /// editor makes of the shell in the meantime (e.g., for completions). Returns the
+    /// request's result, or `None` if the editor closed the connection first.
+    ///
+    /// # Arguments
+    ///
+    /// * `method` - The method to invoke.
+    /// * `params` - The parameters to pass.
+    /// * `handle_request` - Handles requests made by the editor, given their method and
+    ///   parameters; returns the result or an error code and message.
+    pub fn request(
+        &mut self,
+        method: &str,
+        params: Value,
+        mut handle_request: impl FnMut(&str, &Value) -> Result<Value, (i64, String)>,
+    ) -> Result<Option<Value>, ShellError> {
+        let id = self.next_request_id;
+        self.next_request_id += 1;
+
+        self.send(&json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }))?;
+
+        let mut line = String::new();
+        loop {
+            line.clear();
+            if self.reader.read_line(&mut line)? == 0 {
+                return Ok(None);
+            }
+
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let message: Value = serde_json::from_str(line.as_str())
+                .map_err(|e| ShellError::ExternalEditorError(e.to_string()))?;
+
+            // Requests from the editor name a method; anything else is a response.
+            if let Some(method) = message.get("method").and_then(Value::as_str) {
+                let params = message.get("params").cloned().unwrap_or(Value::Null);
+                let result = handle_request(method, &params);
+
+                // Notifications (i.e., requests without an ID) don't receive responses.
+                if let Some(request_id) = message.get("id") {
+                    let response = match result {
+                        Ok(result) => {
+                            json!({ "jsonrpc": "2.0", "id": request_id, "result": result })
+                        }
+                        Err((code, message)) => json!({
+                            "jsonrpc": "2.0",
+                            "id": request_id,
+                            "error": { "code": code, "message": message },
+                        }),
+                    };
+                    self.send(&response)?;
+                }
+
+                continue;
+            }
+
+            if message.get("id").and_then(Value::as_u64) != Some(id) {
+                tracing::debug!("ignoring unexpected message from external line editor");
+                continue;
+            }
+
+            if let Some(error) = message.get("error") {
+                let error_message = error
+                    .get("message")
+                    .and_then(Value::as_str)
+                    .unwrap_or("unknown error");
+                return Err(ShellError::ExternalEditorError(error_message.to_owned()));
+            }
+
+            return Ok(Some(message.get("result").cloned().unwrap_or(Value::Null)));
+        }
+    }
+
+    fn send(&mut self, message: &Value) -> Result<(), ShellError> {
+        self.writer.write_all(message.to_string().as_bytes())?;
+        self.writer.write_all(b"\n")?;
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+impl Drop for EditorConnection {
+    fn drop(&mut self) {
+        // The editor is expected to exit once its input is closed, but we don't rely on it.
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// Returns the error for a request naming a method the shell doesn't implement.
+///
+/// # Arguments
+///
+/// * `method` - The requested method.
+pub(crate) fn method_not_found(method: &str) -> (i64, String) {
+    (METHOD_NOT_FOUND, std::format!("unhandled method: {method}"))
+}
+
+/// Returns the error for a request with a missing or malformed parameter.
+///
+/// # Arguments
+///
+/// * `name` - The name of the parameter.
+pub(crate) fn invalid_param(name: &str) -> (i64, String) {
+    (
+        INVALID_PARAMS,
+        std::format!("missing or invalid parameter: {name}"),
+    )
+}
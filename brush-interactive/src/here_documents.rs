@@ -0,0 +1,81 @@
+//! Support for entering here-documents interactively. A here-document body is literal
+//! text, so it's neither completed nor highlighted as shell syntax.
+
+use std::borrow::Cow;
+
+/// Tokenizes the given input, which may end partway through one or more
+/// here-documents. Any here-documents that are still open are closed first, so the
+/// rest of the input may be tokenized; their bodies extend to the end of the input.
+///
+/// # Arguments
+///
+/// * `input` - The input to tokenize.
+/// * `options` - Options controlling how the input is parsed.
+pub(crate) fn tokenize(
+    input: &str,
+    options: &brush_parser::ParserOptions,
+) -> Option<Vec<brush_parser::Token>> {
+    let options = options.tokenizer_options();
+
+    match brush_parser::tokenize_str_with_options(input, &options) {
+        Ok(tokens) => Some(tokens),
+        Err(brush_parser::TokenizerError::UnterminatedHereDocuments(tags, _)) => {
+            let mut closed: Cow<'_, str> = input.into();
+            if !closed.ends_with('\n') {
+                closed.to_mut().push('\n');
+            }
+
+            for tag in tags.split(", ") {
+                let closed = closed.to_mut();
+                closed.push_str(brush_parser::unquote_str(tag).as_str());
+                closed.push('\n');
+            }
+
+            brush_parser::tokenize_str_with_options(closed.as_ref(), &options).ok()
+        }
+        Err(_) => None,
+    }
+}
+
+/// Returns the indices of the tokens that hold here-document bodies. Each body token
+/// spans the body's lines and its terminating tag; it's followed by a zero-width token
+/// holding the tag.
+///
+/// # Arguments
+///
+/// * `tokens` - The tokens to search.
+pub(crate) fn body_token_indices(tokens: &[brush_parser::Token]) -> Vec<usize> {
+    tokens
+        .windows(3)
+        .enumerate()
+        .filter_map(|(i, window)| match window {
+            [brush_parser::Token::Operator(op, _), brush_parser::Token::Word(..), brush_parser::Token::Word(..)]
+                if op == "<<" || op == "<<-" =>
+            {
+                Some(i + 2)
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Returns whether the given position in the input falls within a here-document body.
+///
+/// # Arguments
+///
+/// * `input` - The input.
+/// * `pos` - The byte offset of the position in the input.
+/// * `options` - Options controlling how the input is parsed.
+pub(crate) fn is_in_body(input: &str, pos: usize, options: &brush_parser::ParserOptions) -> bool {
+    let Some(tokens) = tokenize(input, options) else {
+        return false;
+    };
+
+    let char_index = input.get(..pos).map_or(0, |prefix| prefix.chars().count());
+
+    body_token_indices(&tokens).into_iter().any(|i| {
+        let location = tokens[i].location();
+        usize::try_from(location.start.index).is_ok_and(|start| start <= char_index)
+            && usize::try_from(location.end.index).map_or(true, |end| char_index < end)
+    })
+}
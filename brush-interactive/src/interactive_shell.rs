@@ -9,6 +9,10 @@ pub enum ReadResult {
     Eof,
     /// The user interrupted the input operation.
     Interrupted,
+    /// The shell's state was changed while input was being read (e.g., its working
+    /// directory), so the prompt should be composed anew before reading resumes; any input
+    /// entered so far is preserved.
+    Refresh,
 }
 
 /// Result of an interactive execution.
@@ -31,6 +35,24 @@ pub struct InteractivePrompt {
     pub continuation_prompt: String,
 }
 
+impl InteractivePrompt {
+    /// Returns the prompt to display on a continuation line, prefixed with the name
+    /// of the construct that's awaiting termination, if known.
+    ///
+    /// # Arguments
+    ///
+    /// * `construct` - The construct left open by the input so far.
+    pub(crate) fn continuation_prompt_for(
+        &self,
+        construct: Option<&brush_parser::UnterminatedConstruct>,
+    ) -> String {
+        match construct {
+            Some(construct) => std::format!("{construct}{}", self.continuation_prompt),
+            None => self.continuation_prompt.clone(),
+        }
+    }
+}
+
 /// Represents a shell capable of taking commands from standard input.
 pub trait InteractiveShell {
     /// Returns an immutable reference to the inner shell object.
@@ -49,6 +71,45 @@ pub trait InteractiveShell {
     /// Update history, if relevant.
     fn update_history(&mut self) -> Result<(), ShellError>;
 
+    /// Composes the prompt to display before reading a command, first running the command
+    /// in `PROMPT_COMMAND` (if set). External prompt commands (in `BRUSH_PROMPT_COMMAND` and
+    /// `BRUSH_PROMPT_COMMAND_RIGHT`) are run concurrently, and their outputs reused until the
+    /// shell's state changes. Implementations may override this to compose prompts by other
+    /// means.
+    fn compose_prompt(
+        &mut self,
+    ) -> impl std::future::Future<Output = Result<InteractivePrompt, ShellError>> {
+        async {
+            let mut shell_mut = self.shell_mut();
+
+            // If there's a variable called PROMPT_COMMAND, then run it first.
+            if let Some((_, prompt_cmd)) = shell_mut.as_mut().env.get("PROMPT_COMMAND") {
+                let prompt_cmd = prompt_cmd.value().to_cow_string().to_string();
+
+                // Save (and later restore) the last exit status.
+                let prev_last_result = shell_mut.as_mut().last_exit_status;
+
+                let params = shell_mut.as_mut().default_exec_params();
+
+                shell_mut
+                    .as_mut()
+                    .run_nested_string(prompt_cmd, &params)
+                    .await?;
+                shell_mut.as_mut().last_exit_status = prev_last_result;
+            }
+
+            // Run any external prompt commands up front, so they run concurrently.
+            shell_mut.as_mut().run_prompt_commands().await?;
+
+            // Now that we've done that, compose the prompt.
+            Ok(InteractivePrompt {
+                prompt: shell_mut.as_mut().compose_prompt().await?,
+                alt_side_prompt: shell_mut.as_mut().compose_alt_side_prompt().await?,
+                continuation_prompt: shell_mut.as_mut().compose_continuation_prompt().await?,
+            })
+        }
+    }
+
     /// Runs the interactive shell loop, reading commands from standard input and writing
     /// results to standard output and standard error. Continues until the shell
     /// normally exits or until a fatal error occurs.
@@ -57,7 +118,9 @@ pub trait InteractiveShell {
     fn run_interactively(&mut self) -> impl std::future::Future<Output = Result<(), ShellError>> {
         async {
             // TODO: Consider finding a better place for this.
-            let _ = brush_core::TerminalControl::acquire()?;
+            if brush_core::Capability::TerminalControl.is_supported() {
+                let _ = brush_core::TerminalControl::acquire()?;
+            }
 
             loop {
                 let result = self.run_interactively_once().await?;
@@ -89,6 +152,8 @@ pub trait InteractiveShell {
                 writeln!(self.shell().as_ref().stderr(), "exit")?;
             }
 
+            self.shell_mut().as_mut().hang_up_jobs_if_requested();
+
             if let Err(e) = self.update_history() {
                 // N.B. This seems like the sort of thing that's worth being noisy about,
                 // but bash doesn't do that -- and probably for a reason.
@@ -104,57 +169,82 @@ pub trait InteractiveShell {
         &mut self,
     ) -> impl std::future::Future<Output = Result<InteractiveExecutionResult, ShellError>> {
         async {
-            let mut shell_mut = self.shell_mut();
+            loop {
+                // Check for any completed jobs.
+                self.shell_mut().as_mut().check_for_completed_jobs()?;
 
-            // Check for any completed jobs.
-            shell_mut.as_mut().check_for_completed_jobs()?;
+                // Pick up any change to the terminal's size.
+                self.shell_mut().as_mut().check_window_size()?;
 
-            // If there's a variable called PROMPT_COMMAND, then run it first.
-            if let Some((_, prompt_cmd)) = shell_mut.as_mut().env.get("PROMPT_COMMAND") {
-                let prompt_cmd = prompt_cmd.value().to_cow_string().to_string();
+                // Update the terminal title (if requested) while awaiting input.
+                crate::term::update_title(self.shell_mut().as_mut(), None).await;
 
-                // Save (and later restore) the last exit status.
-                let prev_last_result = shell_mut.as_mut().last_exit_status;
+                let prompt = self.compose_prompt().await?;
 
-                let params = shell_mut.as_mut().default_exec_params();
+                let result = match self.read_line(prompt)? {
+                    ReadResult::Input(read_result) => {
+                        let mut shell_mut = self.shell_mut();
 
-                shell_mut.as_mut().run_string(prompt_cmd, &params).await?;
-                shell_mut.as_mut().last_exit_status = prev_last_result;
-            }
+                        // Report any background jobs that finished while the line was being
+                        // read, if requested; we can't interrupt the line reader to do so.
+                        shell_mut.as_mut().report_job_state_changes_if_notifying()?;
 
-            // Now that we've done that, compose the prompt.
-            let prompt = InteractivePrompt {
-                prompt: shell_mut.as_mut().compose_prompt().await?,
-                alt_side_prompt: shell_mut.as_mut().compose_alt_side_prompt().await?,
-                continuation_prompt: shell_mut.as_mut().compose_continuation_prompt().await?,
-            };
+                        // Number the command (as displayed in subsequent prompts). As with
+                        // bash, the line has already entered history by the time PS0 is
+                        // displayed, but the command isn't counted until it's executed.
+                        if !read_result.trim().is_empty() {
+                            shell_mut.as_mut().history_number += 1;
 
-            drop(shell_mut);
+                            display_precmd_prompt(shell_mut.as_mut()).await?;
+                            crate::term::update_title(shell_mut.as_mut(), Some(read_result.trim()))
+                                .await;
 
-            match self.read_line(prompt)? {
-                ReadResult::Input(read_result) => {
-                    let mut shell_mut = self.shell_mut();
+                            shell_mut.as_mut().command_number += 1;
+                        }
 
-                    let precmd_prompt = shell_mut.as_mut().compose_precmd_prompt().await?;
-                    if !precmd_prompt.is_empty() {
-                        print!("{precmd_prompt}");
+                        let params = shell_mut.as_mut().default_exec_params();
+                        match shell_mut.as_mut().run_string(read_result, &params).await {
+                            Ok(result) => Ok(InteractiveExecutionResult::Executed(result)),
+                            Err(e) => Ok(InteractiveExecutionResult::Failed(e)),
+                        }
                     }
-
-                    let params = shell_mut.as_mut().default_exec_params();
-                    match shell_mut.as_mut().run_string(read_result, &params).await {
-                        Ok(result) => Ok(InteractiveExecutionResult::Executed(result)),
-                        Err(e) => Ok(InteractiveExecutionResult::Failed(e)),
+                    ReadResult::Eof => Ok(InteractiveExecutionResult::Eof),
+                    ReadResult::Interrupted => {
+                        let mut shell_mut = self.shell_mut();
+                        shell_mut.as_mut().last_exit_status = 130;
+                        Ok(InteractiveExecutionResult::Executed(
+                            brush_core::ExecutionResult::new(130),
+                        ))
                     }
-                }
-                ReadResult::Eof => Ok(InteractiveExecutionResult::Eof),
-                ReadResult::Interrupted => {
-                    let mut shell_mut = self.shell_mut();
-                    shell_mut.as_mut().last_exit_status = 130;
-                    Ok(InteractiveExecutionResult::Executed(
-                        brush_core::ExecutionResult::new(130),
-                    ))
-                }
+                    // The line editor changed the shell's state in a way that may affect
+                    // the prompt (e.g., its working directory); compose the prompt anew
+                    // and resume reading.
+                    ReadResult::Refresh => continue,
+                };
+
+                break result;
             }
         }
     }
 }
+
+/// Displays the shell's post-input, pre-command prompt (`PS0`), if set. As with bash, it's
+/// written to standard error, and only by interactive shells.
+///
+/// # Arguments
+///
+/// * `shell` - The shell about to execute a command.
+async fn display_precmd_prompt(shell: &mut brush_core::Shell) -> Result<(), ShellError> {
+    if !shell.options.interactive {
+        return Ok(());
+    }
+
+    let precmd_prompt = shell.compose_precmd_prompt().await?;
+    if !precmd_prompt.is_empty() {
+        let mut stderr = shell.stderr();
+        write!(stderr, "{precmd_prompt}")?;
+        stderr.flush()?;
+    }
+
+    Ok(())
+}
@@ -15,6 +15,8 @@ pub use options::Options;
 
 #[cfg(any(windows, unix))]
 mod completion;
+#[cfg(any(windows, unix))]
+mod here_documents;
 
 // Reedline-based shell
 #[cfg(feature = "reedline")]
@@ -28,10 +30,17 @@ mod basic;
 #[cfg(feature = "basic")]
 pub use basic::BasicShell;
 
+// External line editor shell
+#[cfg(all(feature = "external", any(windows, unix)))]
+mod external;
+#[cfg(all(feature = "external", any(windows, unix)))]
+pub use external::ExternalShell;
+
 // Minimal shell
 #[cfg(feature = "minimal")]
 mod minimal;
 #[cfg(feature = "minimal")]
 pub use minimal::MinimalShell;
 
+mod term;
 mod trace_categories;
@@ -18,7 +18,10 @@ impl MinimalShell {
     ///
     /// * `options` - Options for creating the interactive shell.
     pub async fn new(options: &crate::Options) -> Result<Self, ShellError> {
-        let shell = brush_core::Shell::new(&options.shell).await?;
+        let mut shell = brush_core::Shell::new(&options.shell).await?;
+        shell.prompt_timeout = options.prompt_timeout;
+        crate::term::set_default_title_template(&mut shell, options.term_title.as_deref())?;
+
         Ok(Self { shell })
     }
 }
@@ -37,26 +40,34 @@ impl InteractiveShell for MinimalShell {
     fn read_line(&mut self, prompt: InteractivePrompt) -> Result<ReadResult, ShellError> {
         self.display_prompt(&prompt)?;
 
-        let mut result = String::new();
+        let mut parser = brush_parser::StreamingParser::new(
+            &self.shell.program_parser_options(),
+            &brush_parser::SourceInfo::default(),
+        );
 
         loop {
-            match Self::read_input_line()? {
+            let result = match Self::read_input_line()? {
                 ReadResult::Input(s) => {
-                    result.push_str(s.as_str());
-                    if self.is_valid_input(result.as_str()) {
-                        break;
+                    parser.feed(s.as_str());
+                    parser.poll()
+                }
+                ReadResult::Eof => parser.finish(),
+                result @ (ReadResult::Interrupted | ReadResult::Refresh) => return Ok(result),
+            };
+
+            // N.B. Syntax errors are reported when the input is run.
+            match result {
+                brush_parser::StreamingParseResult::Complete { text, .. }
+                | brush_parser::StreamingParseResult::Error { text, .. } => {
+                    return Ok(ReadResult::Input(text));
+                }
+                brush_parser::StreamingParseResult::NeedMoreInput => {
+                    if !parser.has_pending_input() {
+                        return Ok(ReadResult::Eof);
                     }
                 }
-                ReadResult::Eof => break,
-                ReadResult::Interrupted => return Ok(ReadResult::Interrupted),
             }
         }
-
-        if result.is_empty() {
-            Ok(ReadResult::Eof)
-        } else {
-            Ok(ReadResult::Input(result))
-        }
     }
 
     fn update_history(&mut self) -> Result<(), ShellError> {
@@ -91,16 +102,4 @@ impl MinimalShell {
             Ok(ReadResult::Input(input))
         }
     }
-
-    fn is_valid_input(&self, input: &str) -> bool {
-        match self.shell.parse_string(input.to_owned()) {
-            Err(brush_parser::ParseError::Tokenizing { inner, position: _ })
-                if inner.is_incomplete() =>
-            {
-                false
-            }
-            Err(brush_parser::ParseError::ParsingAtEndOfInput) => false,
-            _ => true,
-        }
-    }
 }
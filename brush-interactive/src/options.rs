@@ -4,8 +4,26 @@ pub struct Options {
     pub shell: brush_core::CreateOptions,
     /// Whether to disable bracketed paste mode.
     pub disable_bracketed_paste: bool,
+    /// Whether to ask for confirmation before inserting multi-line pastes (in bracketed
+    /// paste mode); once inserted, pasted text may be edited before it's executed.
+    pub confirm_multiline_paste: bool,
     /// Whether to disable color.
     pub disable_color: bool,
     /// Whether to disable syntax highlighting.
     pub disable_highlighting: bool,
+    /// Whether to disable clipboard integration.
+    pub disable_clipboard: bool,
+    /// Optionally, the longest to wait for completions to be generated; once it elapses,
+    /// any candidates generated by then are offered.
+    pub completion_timeout: Option<std::time::Duration>,
+    /// Optionally, the longest to wait for the command substitutions in a prompt while
+    /// composing it; once it elapses, the output of any still running is replaced by a
+    /// placeholder.
+    pub prompt_timeout: Option<std::time::Duration>,
+    /// Optionally, a template for the terminal title, expanded like a prompt; used unless
+    /// `BRUSH_TERM_TITLE` is set by the shell's startup files.
+    pub term_title: Option<String>,
+    /// Program (followed by its arguments) to run as the line editor for the external input
+    /// backend.
+    pub external_editor_command: Vec<String>,
 }
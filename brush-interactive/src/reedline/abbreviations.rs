@@ -0,0 +1,70 @@
+//! Abbreviation expansion for the reedline-based input backend.
+//!
+//! Typing a space is surfaced to reedline as a host command, in the same way as the clipboard
+//! functions: reedline hands us the command's marker string (leaving the current input buffer
+//! intact), we expand any abbreviation ending at the cursor and insert the space, and then
+//! resume reading input. Abbreviations ending the line are expanded as it's accepted.
+
+/// Host command string for the space key. As with the clipboard functions, it uses a prefix
+/// that can't reasonably be typed, so it won't be confused with actual user input.
+const EXPAND_AND_INSERT_SPACE: &str = "\u{0}brush:expand-abbreviation-and-insert-space";
+
+/// Returns the reedline event to bind to the space key.
+pub(crate) fn insert_space_event() -> reedline::ReedlineEvent {
+    reedline::ReedlineEvent::ExecuteHostCommand(EXPAND_AND_INSERT_SPACE.to_owned())
+}
+
+/// Returns whether the given host command string is the one bound to the space key.
+///
+/// # Arguments
+///
+/// * `host_command` - The host command string returned by reedline.
+pub(crate) fn is_insert_space_command(host_command: &str) -> bool {
+    host_command == EXPAND_AND_INSERT_SPACE
+}
+
+/// Returns the edit commands that expand the abbreviation ending at the cursor, if any, and
+/// then insert a space.
+///
+/// # Arguments
+///
+/// * `shell` - The shell whose abbreviations are expanded.
+/// * `line` - The contents of the input buffer.
+/// * `cursor` - The byte offset of the cursor in the input buffer.
+pub(crate) fn expand_and_insert_space(
+    shell: &brush_core::Shell,
+    line: &str,
+    cursor: usize,
+) -> Vec<reedline::EditCommand> {
+    let mut commands = vec![];
+
+    if let Some((range, abbreviation)) =
+        brush_core::abbreviations::find_expandable(&shell.abbreviations, line, cursor)
+    {
+        let abbreviation_len = line[range].chars().count();
+        commands.extend((0..abbreviation_len).map(|_| reedline::EditCommand::Backspace));
+        commands.push(reedline::EditCommand::InsertString(
+            abbreviation.expansion.clone(),
+        ));
+    }
+
+    commands.push(reedline::EditCommand::InsertChar(' '));
+
+    commands
+}
+
+/// Expands the abbreviation, if any, that ends the given accepted line.
+///
+/// # Arguments
+///
+/// * `shell` - The shell whose abbreviations are expanded.
+/// * `line` - The accepted line.
+pub(crate) fn expand_at_end(shell: &brush_core::Shell, mut line: String) -> String {
+    if let Some((range, abbreviation)) =
+        brush_core::abbreviations::find_expandable(&shell.abbreviations, &line, line.len())
+    {
+        line.replace_range(range, abbreviation.expansion.as_str());
+    }
+
+    line
+}
@@ -0,0 +1,246 @@
+//! Clipboard integration for the reedline-based input backend.
+//!
+//! Clipboard operations are surfaced to reedline as host commands: when the user invokes a
+//! bound clipboard function, reedline hands us the command's marker string (leaving the
+//! current input buffer intact), we perform the operation, and then resume reading input.
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// Editing functions that interact with the system clipboard.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum ClipboardFunction {
+    /// Copies the current input line to the clipboard.
+    CopyLine,
+    /// Copies the selected text to the clipboard, or the current input line if no text is
+    /// selected.
+    CopySelection,
+    /// Inserts the contents of the clipboard at the cursor.
+    Paste,
+}
+
+impl ClipboardFunction {
+    const ALL: [ClipboardFunction; 3] = [
+        ClipboardFunction::CopyLine,
+        ClipboardFunction::CopySelection,
+        ClipboardFunction::Paste,
+    ];
+
+    /// Returns the bindable name of the function.
+    pub fn name(self) -> &'static str {
+        match self {
+            ClipboardFunction::CopyLine => "copy-line-to-clipboard",
+            ClipboardFunction::CopySelection => "copy-selection-to-clipboard",
+            ClipboardFunction::Paste => "paste-from-clipboard",
+        }
+    }
+
+    /// Looks up the function with the given bindable name, if any.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The bindable name of the function.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|f| f.name() == name)
+    }
+
+    /// Returns the reedline event that invokes the function.
+    pub fn to_event(self) -> reedline::ReedlineEvent {
+        reedline::ReedlineEvent::ExecuteHostCommand(self.host_command())
+    }
+
+    /// Looks up the function invoked by the given host command, if any.
+    ///
+    /// # Arguments
+    ///
+    /// * `host_command` - The host command string returned by reedline.
+    pub fn from_host_command(host_command: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|f| f.host_command() == host_command)
+    }
+
+    fn host_command(self) -> String {
+        // N.B. We use a prefix that can't reasonably be typed so it won't be confused with
+        // actual user input.
+        std::format!("\u{0}brush:{}", self.name())
+    }
+}
+
+/// Provides access to the clipboard, preferring the native system clipboard and falling back
+/// to OSC 52 terminal escape sequences (for copying) and an in-process buffer (for pasting).
+#[derive(Default)]
+pub(crate) struct Clipboard {
+    last_copied: Option<String>,
+}
+
+impl Clipboard {
+    /// Copies the given text to the clipboard.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The text to copy.
+    pub fn copy(&mut self, text: &str) {
+        if !native_copy(text) {
+            // Fall back to asking the terminal to do it for us.
+            let mut stderr = std::io::stderr();
+            let _ = write!(stderr, "\x1b]52;c;{}\x07", base64_encode(text.as_bytes()));
+            let _ = stderr.flush();
+        }
+
+        self.last_copied = Some(text.to_owned());
+    }
+
+    /// Retrieves the current text contents of the clipboard, if available.
+    pub fn paste(&self) -> Option<String> {
+        native_paste().or_else(|| self.last_copied.clone())
+    }
+}
+
+/// Returns the text selected in reedline's input buffer, if any.
+///
+/// Reedline doesn't expose the selection directly, so we cut it (into reedline's own cut
+/// buffer), take note of what was removed, and then paste it back in place.
+///
+/// # Arguments
+///
+/// * `reedline` - The line editor whose selection is retrieved.
+pub(crate) fn selected_text(reedline: &mut reedline::Reedline) -> Option<String> {
+    let line = reedline.current_buffer_contents().to_owned();
+
+    reedline.run_edit_commands(&[reedline::EditCommand::CutSelection]);
+    let remaining_len = reedline.current_buffer_contents().len();
+    if remaining_len == line.len() {
+        return None;
+    }
+
+    // The cursor is left where the selection started.
+    let start = reedline.current_insertion_point();
+    reedline.run_edit_commands(&[reedline::EditCommand::PasteCutBufferBefore]);
+
+    line.get(start..start + (line.len() - remaining_len))
+        .map(str::to_owned)
+}
+
+type HelperCommand = (&'static str, &'static [&'static str]);
+
+const NO_ARGS: &[&str] = &[];
+
+fn copy_helpers() -> Vec<HelperCommand> {
+    if cfg!(target_os = "macos") {
+        vec![("pbcopy", NO_ARGS)]
+    } else if cfg!(windows) {
+        vec![("clip.exe", NO_ARGS)]
+    } else {
+        let mut helpers: Vec<HelperCommand> = vec![];
+        if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+            helpers.push(("wl-copy", NO_ARGS));
+        }
+        if std::env::var_os("DISPLAY").is_some() {
+            helpers.push(("xclip", &["-selection", "clipboard"]));
+            helpers.push(("xsel", &["--clipboard", "--input"]));
+        }
+        if std::env::var_os("WSL_DISTRO_NAME").is_some() {
+            helpers.push(("clip.exe", NO_ARGS));
+        }
+        helpers
+    }
+}
+
+fn paste_helpers() -> Vec<HelperCommand> {
+    const POWERSHELL_ARGS: &[&str] = &["-NoProfile", "-Command", "Get-Clipboard"];
+
+    if cfg!(target_os = "macos") {
+        vec![("pbpaste", NO_ARGS)]
+    } else if cfg!(windows) {
+        vec![("powershell.exe", POWERSHELL_ARGS)]
+    } else {
+        let mut helpers: Vec<HelperCommand> = vec![];
+        if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+            helpers.push(("wl-paste", &["--no-newline"]));
+        }
+        if std::env::var_os("DISPLAY").is_some() {
+            helpers.push(("xclip", &["-selection", "clipboard", "-o"]));
+            helpers.push(("xsel", &["--clipboard", "--output"]));
+        }
+        if std::env::var_os("WSL_DISTRO_NAME").is_some() {
+            helpers.push(("powershell.exe", POWERSHELL_ARGS));
+        }
+        helpers
+    }
+}
+
+fn native_copy(text: &str) -> bool {
+    for (program, args) in copy_helpers() {
+        let Ok(mut child) = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+        else {
+            continue;
+        };
+
+        let wrote = child
+            .stdin
+            .take()
+            .is_some_and(|mut stdin| stdin.write_all(text.as_bytes()).is_ok());
+
+        if child.wait().is_ok_and(|status| status.success()) && wrote {
+            return true;
+        }
+    }
+
+    false
+}
+
+fn native_paste() -> Option<String> {
+    for (program, args) in paste_helpers() {
+        let Ok(output) = Command::new(program)
+            .args(args)
+            .stdin(Stdio::null())
+            .stderr(Stdio::null())
+            .output()
+        else {
+            continue;
+        };
+
+        if output.status.success() {
+            let mut text = String::from_utf8_lossy(&output.stdout).into_owned();
+
+            // Some helpers append line endings; strip a single trailing one.
+            if text.ends_with("\r\n") {
+                text.truncate(text.len() - 2);
+            } else if text.ends_with('\n') {
+                text.truncate(text.len() - 1);
+            }
+
+            return Some(text);
+        }
+    }
+
+    None
+}
+
+fn base64_encode(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let b0 = usize::from(chunk[0]);
+        let b1 = chunk.get(1).copied().map_or(0, usize::from);
+        let b2 = chunk.get(2).copied().map_or(0, usize::from);
+        let triple = (b0 << 16) | (b1 << 8) | b2;
+
+        for (i, shift) in [18, 12, 6, 0].into_iter().enumerate() {
+            if i <= chunk.len() {
+                encoded.push(char::from(ALPHABET[(triple >> shift) & 0x3F]));
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+
+    encoded
+}
@@ -1,11 +1,14 @@
-use nu_ansi_term::{Color, Style};
+use nu_ansi_term::Style;
 use std::borrow::BorrowMut;
+use std::path::Path;
 
-use super::refs;
+use super::{ls_colors, refs};
 use crate::completion;
 
 pub(crate) struct ReedlineCompleter {
     pub shell: refs::ShellRef,
+    pub color: bool,
+    pub timeout: Option<std::time::Duration>,
 }
 
 impl reedline::Completer for ReedlineCompleter {
@@ -21,16 +24,34 @@ impl ReedlineCompleter {
         let mut shell_guard = self.shell.lock().await;
         let shell = shell_guard.borrow_mut().as_mut();
 
-        let completions = completion::complete_async(shell, line, pos).await;
+        let completions = completion::complete_async(shell, line, pos, self.timeout, true).await;
         let insertion_index = completions.insertion_index;
         let delete_count = completions.delete_count;
         let options = completions.options;
 
+        // Filename completions are styled like `ls` would style them.
+        let ls_colors = (self.color && options.treat_as_filenames)
+            .then(|| ls_colors::LsColors::parse(shell.env.get_str("LS_COLORS").as_deref()));
+
         completions
             .candidates
             .into_iter()
             .map(|candidate| {
-                Self::to_suggestion(line, candidate, insertion_index, delete_count, &options)
+                let style = ls_colors
+                    .as_ref()
+                    .and_then(|ls_colors| {
+                        ls_colors.style_for_path(&shell.get_absolute_path(Path::new(&candidate)))
+                    })
+                    .unwrap_or_default();
+
+                Self::to_suggestion(
+                    line,
+                    candidate,
+                    style,
+                    insertion_index,
+                    delete_count,
+                    &options,
+                )
             })
             .collect()
     }
@@ -38,18 +59,13 @@ impl ReedlineCompleter {
     fn to_suggestion(
         line: &str,
         mut candidate: String,
+        style: Style,
         mut insertion_index: usize,
         mut delete_count: usize,
         options: &brush_core::completion::ProcessingOptions,
     ) -> reedline::Suggestion {
-        let mut style = Style::new();
-
         // Special handling for filename completions.
         if options.treat_as_filenames {
-            if candidate.ends_with(std::path::MAIN_SEPARATOR) {
-                style = style.fg(Color::Green);
-            }
-
             if insertion_index + delete_count <= line.len() {
                 let removed = &line[insertion_index..insertion_index + delete_count];
                 if let Some(last_sep_index) = removed.rfind(std::path::MAIN_SEPARATOR) {
@@ -0,0 +1,82 @@
+//! Directory history navigation for the reedline-based input backend.
+//!
+//! As with the clipboard functions, navigation is surfaced to reedline as host commands:
+//! reedline hands us the command's marker string (leaving the current input buffer intact),
+//! we change the shell's working directory, and then have the prompt composed anew before
+//! resuming reading input.
+
+/// Editing functions that navigate the shell's directory history.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum DirectoryHistoryFunction {
+    /// Changes to the previous directory in the history.
+    Previous,
+    /// Changes to the next directory in the history.
+    Next,
+}
+
+impl DirectoryHistoryFunction {
+    const ALL: [DirectoryHistoryFunction; 2] = [
+        DirectoryHistoryFunction::Previous,
+        DirectoryHistoryFunction::Next,
+    ];
+
+    /// Returns the bindable name of the function.
+    pub fn name(self) -> &'static str {
+        match self {
+            DirectoryHistoryFunction::Previous => "previous-directory",
+            DirectoryHistoryFunction::Next => "next-directory",
+        }
+    }
+
+    /// Looks up the function with the given bindable name, if any.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The bindable name of the function.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|f| f.name() == name)
+    }
+
+    /// Returns the reedline event that invokes the function.
+    pub fn to_event(self) -> reedline::ReedlineEvent {
+        reedline::ReedlineEvent::ExecuteHostCommand(self.host_command())
+    }
+
+    /// Looks up the function invoked by the given host command, if any.
+    ///
+    /// # Arguments
+    ///
+    /// * `host_command` - The host command string returned by reedline.
+    pub fn from_host_command(host_command: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|f| f.host_command() == host_command)
+    }
+
+    /// Performs the function on the given shell, returning whether its working directory
+    /// was changed.
+    ///
+    /// # Arguments
+    ///
+    /// * `shell` - The shell whose working directory is changed.
+    pub fn execute(self, shell: &mut brush_core::Shell) -> bool {
+        let offset = match self {
+            DirectoryHistoryFunction::Previous => -1,
+            DirectoryHistoryFunction::Next => 1,
+        };
+
+        match shell.navigate_directory_history(offset) {
+            Ok(dir) => dir.is_some(),
+            Err(e) => {
+                tracing::debug!("failed to navigate directory history: {e}");
+                false
+            }
+        }
+    }
+
+    fn host_command(self) -> String {
+        // N.B. We use a prefix that can't reasonably be typed so it won't be confused with
+        // actual user input.
+        std::format!("\u{0}brush:{}", self.name())
+    }
+}
@@ -1,6 +1,8 @@
+use std::borrow::Cow;
 use std::str::Chars;
 
 use super::refs;
+use crate::here_documents;
 use nu_ansi_term::{Color, Style};
 
 mod styles {
@@ -10,6 +12,10 @@ mod styles {
         Style::new().fg(Color::White)
     }
 
+    pub fn here_document() -> Style {
+        Style::new().fg(Color::Yellow)
+    }
+
     pub fn comment() -> Style {
         Style::new().fg(Color::DarkGray)
     }
@@ -73,6 +79,7 @@ mod styles {
 
 pub(crate) struct ReedlineHighlighter {
     pub shell: refs::ShellRef,
+    pub parser: std::sync::Mutex<brush_parser::IncrementalParser>,
 }
 
 impl reedline::Highlighter for ReedlineHighlighter {
@@ -81,8 +88,20 @@ impl reedline::Highlighter for ReedlineHighlighter {
             tokio::runtime::Handle::current().block_on(self.shell.lock())
         });
 
+        // N.B. The line is re-highlighted as it's edited, so we tokenize it incrementally.
+        let mut parser = self
+            .parser
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        let options = shell.parser_options();
+        let tokens: Option<Cow<'_, [brush_parser::Token]>> = match parser.tokenize(line, &options) {
+            Ok(tokens) => Some(tokens.into()),
+            // N.B. The line may end within a here-document body that's still being typed.
+            Err(_) => here_documents::tokenize(line, &options).map(Into::into),
+        };
+
         let mut styled_input = StyledInputLine::new(shell.as_ref(), line, cursor);
-        styled_input.style_and_append_program(line, 0);
+        styled_input.style_and_append_tokens(tokens.as_deref(), line, 0);
 
         styled_input.styled
     }
@@ -120,13 +139,44 @@ impl<'a> StyledInputLine<'a> {
     }
 
     fn style_and_append_program(&mut self, line: &str, global_offset: usize) {
-        #[allow(clippy::cast_sign_loss)]
-        if let Ok(tokens) = brush_parser::tokenize_str_with_options(
+        let tokens = brush_parser::tokenize_str_with_options(
             line,
             &(self.shell.parser_options().tokenizer_options()),
-        ) {
+        )
+        .ok();
+
+        self.style_and_append_tokens(tokens.as_deref(), line, global_offset);
+    }
+
+    fn style_and_append_tokens(
+        &mut self,
+        tokens: Option<&[brush_parser::Token]>,
+        line: &str,
+        global_offset: usize,
+    ) {
+        #[allow(clippy::cast_sign_loss)]
+        if let Some(tokens) = tokens {
+            // N.B. A here-document's body follows the line holding its redirection, but its
+            // tokens precede the rest of that line's tokens; we style them in source order.
+            let bodies = here_documents::body_token_indices(tokens);
+            let mut ordered: Vec<_> = tokens.iter().enumerate().collect();
+            ordered.sort_by_key(|(_, token)| token.location().start.index);
+
             let mut saw_command_token = false;
-            for token in tokens {
+            for (i, token) in ordered {
+                if bodies.contains(&i) {
+                    let location = token.location();
+                    self.append_style(
+                        styles::here_document(),
+                        global_offset + location.start.index as usize,
+                        global_offset + location.end.index as usize,
+                    );
+                    continue;
+                } else if i > 0 && bodies.contains(&(i - 1)) {
+                    // Skip the zero-width token holding the body's terminating tag.
+                    continue;
+                }
+
                 match token {
                     brush_parser::Token::Operator(_op, token_location) => {
                         self.append_style(
@@ -141,7 +191,7 @@ impl<'a> StyledInputLine<'a> {
                         {
                             let default_text_style = self.get_style_for_word(
                                 w.as_str(),
-                                &token_location,
+                                token_location,
                                 &mut saw_command_token,
                             );
 
@@ -300,8 +350,8 @@ impl<'a> StyledInputLine<'a> {
     ) -> CommandType {
         if self.shell.is_keyword(name) {
             return CommandType::Keyword;
-        } else if self.shell.aliases.contains_key(name) {
-            return CommandType::Alias;
+        } else if let Some(expansion) = self.shell.expand_command_alias(name) {
+            return self.classify_alias(expansion.as_str(), token_location);
         } else if self.shell.funcs.get(name).is_some() {
             return CommandType::Function;
         } else if self.shell.builtins.contains_key(name) {
@@ -332,4 +382,23 @@ impl<'a> StyledInputLine<'a> {
             }
         }
     }
+
+    fn classify_alias(
+        &self,
+        expansion: &str,
+        token_location: &brush_parser::TokenLocation,
+    ) -> CommandType {
+        // An alias is only flagged if the command it (ultimately) expands to can't be found.
+        // If the expansion starts with an assignment, or stopped at an alias (i.e., one that
+        // refers back to itself), there's nothing further to check.
+        let target = expansion.split_whitespace().next().unwrap_or_default();
+        if target.is_empty() || target.contains('=') || self.shell.aliases.contains_key(target) {
+            return CommandType::Alias;
+        }
+
+        match self.classify_possible_command(target, token_location) {
+            CommandType::NotFound => CommandType::NotFound,
+            _ => CommandType::Alias,
+        }
+    }
 }
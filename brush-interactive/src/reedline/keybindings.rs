@@ -0,0 +1,259 @@
+//! Key bindings made with the `bind` builtin, for the reedline-based input backend.
+//!
+//! The shell records the key sequences bound with `bind`; before reading each line of input,
+//! we translate them into key bindings that are consulted ahead of the edit mode's own. Only
+//! functions that reedline doesn't provide itself (e.g., the clipboard functions) can be
+//! bound, and only to single keys (optionally with modifiers).
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use super::{clipboard, dirhistory};
+
+type Key = (reedline::KeyModifiers, reedline::KeyCode);
+
+/// Key bindings made with `bind`, shared between the shell and the edit mode.
+pub(crate) type UserKeyBindings = Arc<Mutex<HashMap<Key, reedline::ReedlineEvent>>>;
+
+/// An edit mode that handles keys bound with `bind`, deferring all other events to an inner
+/// edit mode.
+pub(crate) struct UserKeyBindingsEditMode {
+    /// The edit mode that handles all other events.
+    pub inner: Box<dyn reedline::EditMode>,
+    /// The key bindings made with `bind`.
+    pub bindings: UserKeyBindings,
+}
+
+impl reedline::EditMode for UserKeyBindingsEditMode {
+    fn parse_event(&mut self, event: reedline::ReedlineRawEvent) -> reedline::ReedlineEvent {
+        let event: crossterm::event::Event = event.into();
+        if let crossterm::event::Event::Key(key_event) = &event {
+            if key_event.kind != crossterm::event::KeyEventKind::Release {
+                let key = normalize_key(key_event.modifiers, key_event.code);
+                if let Some(bound) = self
+                    .bindings
+                    .lock()
+                    .unwrap_or_else(std::sync::PoisonError::into_inner)
+                    .get(&key)
+                {
+                    return bound.clone();
+                }
+            }
+        }
+
+        match reedline::ReedlineRawEvent::try_from(event) {
+            Ok(event) => self.inner.parse_event(event),
+            Err(()) => reedline::ReedlineEvent::None,
+        }
+    }
+
+    fn edit_mode(&self) -> reedline::PromptEditMode {
+        self.inner.edit_mode()
+    }
+}
+
+/// Replaces the key bindings consulted by the edit mode with the ones the shell has recorded;
+/// bindings that can't be honored are reported and otherwise ignored.
+///
+/// # Arguments
+///
+/// * `bindings` - The key bindings consulted by the edit mode.
+/// * `key_bindings` - The key sequences, and names of the functions bound to them, as
+///   recorded by the shell.
+/// * `enable_clipboard` - Whether the clipboard functions are available.
+pub(crate) fn update(
+    bindings: &UserKeyBindings,
+    key_bindings: &[(String, String)],
+    enable_clipboard: bool,
+) {
+    let mut updated = HashMap::new();
+
+    for (key_seq, function) in key_bindings {
+        let Some(key) = parse_key_sequence(key_seq) else {
+            tracing::warn!("bind: {key_seq}: unsupported key sequence");
+            continue;
+        };
+
+        let Some(event) = function_event(function, enable_clipboard) else {
+            tracing::warn!("bind: {function}: unknown function name");
+            continue;
+        };
+
+        updated.insert(key, event);
+    }
+
+    *bindings
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner) = updated;
+}
+
+/// Returns the reedline event that invokes the named function, if it's one that can be bound.
+///
+/// # Arguments
+///
+/// * `name` - The bindable name of the function.
+/// * `enable_clipboard` - Whether the clipboard functions are available.
+fn function_event(name: &str, enable_clipboard: bool) -> Option<reedline::ReedlineEvent> {
+    if enable_clipboard {
+        if let Some(func) = clipboard::ClipboardFunction::from_name(name) {
+            return Some(func.to_event());
+        }
+    }
+
+    dirhistory::DirectoryHistoryFunction::from_name(name)
+        .map(dirhistory::DirectoryHistoryFunction::to_event)
+}
+
+/// Parses a key sequence in readline's notation (e.g., `\C-x`, `\M-v`, or `Control-y`) that
+/// denotes a single key, optionally with modifiers.
+///
+/// # Arguments
+///
+/// * `key_seq` - The key sequence, as given to `bind`.
+fn parse_key_sequence(key_seq: &str) -> Option<Key> {
+    let mut modifiers = reedline::KeyModifiers::NONE;
+    let mut rest = key_seq;
+
+    loop {
+        if let Some(after) = rest
+            .strip_prefix(r"\C-")
+            .or_else(|| rest.strip_prefix("Control-"))
+        {
+            modifiers |= reedline::KeyModifiers::CONTROL;
+            rest = after;
+        } else if let Some(after) = rest
+            .strip_prefix(r"\M-")
+            .or_else(|| rest.strip_prefix("Meta-"))
+        {
+            modifiers |= reedline::KeyModifiers::ALT;
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix(r"\e").filter(|after| !after.is_empty()) {
+            // An escape prefixing another key is how terminals send meta keys.
+            modifiers |= reedline::KeyModifiers::ALT;
+            rest = after;
+        } else {
+            break;
+        }
+    }
+
+    let code = match rest {
+        r"\e" | "ESC" | "Escape" => reedline::KeyCode::Esc,
+        r"\t" | "TAB" | "Tab" => reedline::KeyCode::Tab,
+        r"\n" | r"\r" | "RET" | "Return" | "LFD" | "Newline" => reedline::KeyCode::Enter,
+        r"\d" | "DEL" | "Rubout" => reedline::KeyCode::Backspace,
+        "SPC" | "Space" => reedline::KeyCode::Char(' '),
+        r"\\" => reedline::KeyCode::Char('\\'),
+        r#"\""# => reedline::KeyCode::Char('"'),
+        r"\'" => reedline::KeyCode::Char('\''),
+        _ => {
+            let mut chars = rest.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => reedline::KeyCode::Char(c),
+                _ => return None,
+            }
+        }
+    };
+
+    Some(normalize_key(modifiers, code))
+}
+
+/// Normalizes a key so that it's looked up the same way regardless of how terminals report
+/// it: letters typed with Control or Alt are matched case-insensitively, and Shift is implied
+/// by the characters it produces.
+fn normalize_key(modifiers: reedline::KeyModifiers, code: reedline::KeyCode) -> Key {
+    match code {
+        reedline::KeyCode::Char(c) => {
+            let c = if modifiers
+                .intersects(reedline::KeyModifiers::CONTROL | reedline::KeyModifiers::ALT)
+            {
+                c.to_ascii_lowercase()
+            } else {
+                c
+            };
+            (
+                modifiers.difference(reedline::KeyModifiers::SHIFT),
+                reedline::KeyCode::Char(c),
+            )
+        }
+        _ => (modifiers, code),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn key_sequence_parsing() {
+        let ctrl_alt = reedline::KeyModifiers::CONTROL | reedline::KeyModifiers::ALT;
+
+        assert_eq!(
+            parse_key_sequence(r"\C-y"),
+            Some((
+                reedline::KeyModifiers::CONTROL,
+                reedline::KeyCode::Char('y')
+            ))
+        );
+        assert_eq!(
+            parse_key_sequence(r"\M-C"),
+            Some((reedline::KeyModifiers::ALT, reedline::KeyCode::Char('c')))
+        );
+        assert_eq!(
+            parse_key_sequence(r"\ev"),
+            Some((reedline::KeyModifiers::ALT, reedline::KeyCode::Char('v')))
+        );
+        assert_eq!(
+            parse_key_sequence(r"\C-\M-c"),
+            Some((ctrl_alt, reedline::KeyCode::Char('c')))
+        );
+        assert_eq!(
+            parse_key_sequence("Meta-Control-v"),
+            Some((ctrl_alt, reedline::KeyCode::Char('v')))
+        );
+        assert_eq!(
+            parse_key_sequence("Meta-Rubout"),
+            Some((reedline::KeyModifiers::ALT, reedline::KeyCode::Backspace))
+        );
+        assert_eq!(
+            parse_key_sequence(r"\e"),
+            Some((reedline::KeyModifiers::NONE, reedline::KeyCode::Esc))
+        );
+        assert_eq!(parse_key_sequence(r"\C-x\C-c"), None);
+        assert_eq!(parse_key_sequence(""), None);
+    }
+
+    #[test]
+    fn bindable_functions() {
+        assert!(function_event("copy-selection-to-clipboard", true).is_some());
+        assert!(function_event("paste-from-clipboard", false).is_none());
+        assert!(function_event("previous-directory", false).is_some());
+        assert!(function_event("not-a-function", true).is_none());
+    }
+
+    #[test]
+    fn update_bindings() {
+        let bindings = UserKeyBindings::default();
+        update(
+            &bindings,
+            &[
+                (String::from(r"\C-y"), String::from("paste-from-clipboard")),
+                (
+                    String::from(r"\C-x\C-c"),
+                    String::from("paste-from-clipboard"),
+                ),
+                (String::from(r"\M-w"), String::from("not-a-function")),
+            ],
+            true,
+        );
+
+        let bindings = bindings.lock().unwrap();
+        assert_eq!(bindings.len(), 1);
+        assert_eq!(
+            bindings.get(&(
+                reedline::KeyModifiers::CONTROL,
+                reedline::KeyCode::Char('y')
+            )),
+            Some(&clipboard::ClipboardFunction::Paste.to_event())
+        );
+    }
+}
@@ -0,0 +1,223 @@
+//! Support for styling file names per the `LS_COLORS` environment variable, as used by
+//! `ls` and `dircolors`.
+
+use nu_ansi_term::{Color, Style};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// File styles parsed from an `LS_COLORS`-formatted specification.
+pub(crate) struct LsColors {
+    /// Styles keyed by file type indicator (e.g., `di`, `ex`).
+    types: HashMap<String, Style>,
+    /// Styles keyed by file name suffix (e.g., `.tar`), from `*.tar`-style entries.
+    suffixes: Vec<(String, Style)>,
+}
+
+impl Default for LsColors {
+    fn default() -> Self {
+        // N.B. In the absence of any specification, we only highlight directories.
+        Self {
+            types: HashMap::from([(String::from("di"), Style::new().fg(Color::Green))]),
+            suffixes: vec![],
+        }
+    }
+}
+
+impl LsColors {
+    /// Parses the given `LS_COLORS`-formatted specification; returns the default styling
+    /// if no specification is provided.
+    ///
+    /// # Arguments
+    ///
+    /// * `spec` - The specification to parse, e.g. `di=01;34:*.tar=01;31`.
+    pub fn parse(spec: Option<&str>) -> Self {
+        let Some(spec) = spec.filter(|s| !s.is_empty()) else {
+            return Self::default();
+        };
+
+        let mut types = HashMap::new();
+        let mut suffixes = vec![];
+
+        for entry in spec.split(':') {
+            let Some((key, value)) = entry.split_once('=') else {
+                continue;
+            };
+
+            // N.B. For links, "target" means to style the link as its target would be.
+            if key == "ln" && value == "target" {
+                continue;
+            }
+
+            let Some(style) = parse_sgr(value) else {
+                continue;
+            };
+
+            if let Some(suffix) = key.strip_prefix('*') {
+                suffixes.push((suffix.to_owned(), style));
+            } else {
+                types.insert(key.to_owned(), style);
+            }
+        }
+
+        Self { types, suffixes }
+    }
+
+    /// Returns the style to use for the given path, if it should be styled.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to the file.
+    pub fn style_for_path(&self, path: &Path) -> Option<Style> {
+        let metadata = path.symlink_metadata().ok()?;
+
+        if metadata.file_type().is_symlink() {
+            let target_exists = path.metadata().is_ok();
+            if !target_exists {
+                if let Some(style) = self.types.get("or") {
+                    return Some(*style);
+                }
+            }
+
+            if let Some(style) = self.types.get("ln") {
+                return Some(*style);
+            }
+
+            if !target_exists {
+                return None;
+            }
+        }
+
+        let metadata = path.metadata().ok()?;
+        let type_key = if metadata.is_dir() {
+            "di"
+        } else if let Some(key) = special_file_type_key(&metadata) {
+            key
+        } else if is_executable(&metadata) && self.types.contains_key("ex") {
+            "ex"
+        } else {
+            // Regular files are styled by name first, then by their generic type.
+            if let Some(style) = self.style_for_name(path) {
+                return Some(style);
+            }
+
+            "fi"
+        };
+
+        self.types.get(type_key).copied()
+    }
+
+    fn style_for_name(&self, path: &Path) -> Option<Style> {
+        let file_name = path.file_name()?.to_string_lossy();
+
+        // N.B. Later entries take precedence, matching the behavior of `ls`.
+        self.suffixes
+            .iter()
+            .rev()
+            .find(|(suffix, _)| file_name.ends_with(suffix.as_str()))
+            .map(|(_, style)| *style)
+    }
+}
+
+#[cfg(unix)]
+fn special_file_type_key(metadata: &std::fs::Metadata) -> Option<&'static str> {
+    use std::os::unix::fs::FileTypeExt;
+
+    let file_type = metadata.file_type();
+    if file_type.is_fifo() {
+        Some("pi")
+    } else if file_type.is_socket() {
+        Some("so")
+    } else if file_type.is_block_device() {
+        Some("bd")
+    } else if file_type.is_char_device() {
+        Some("cd")
+    } else {
+        None
+    }
+}
+
+#[cfg(not(unix))]
+fn special_file_type_key(_metadata: &std::fs::Metadata) -> Option<&'static str> {
+    None
+}
+
+#[cfg(unix)]
+fn is_executable(metadata: &std::fs::Metadata) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+
+    metadata.is_file() && metadata.permissions().mode() & 0o111 != 0
+}
+
+#[cfg(not(unix))]
+fn is_executable(_metadata: &std::fs::Metadata) -> bool {
+    false
+}
+
+/// Parses a `;`-separated sequence of SGR parameters (e.g., `01;34`) into a style.
+fn parse_sgr(value: &str) -> Option<Style> {
+    let mut style = Style::new();
+    let mut codes = value.split(';').map(|code| {
+        if code.is_empty() {
+            Some(0)
+        } else {
+            code.parse::<u8>().ok()
+        }
+    });
+
+    while let Some(code) = codes.next() {
+        match code? {
+            0 => style = Style::new(),
+            1 => style = style.bold(),
+            2 => style = style.dimmed(),
+            3 => style = style.italic(),
+            4 => style = style.underline(),
+            5 => style = style.blink(),
+            7 => style = style.reverse(),
+            8 => style = style.hidden(),
+            9 => style = style.strikethrough(),
+            code @ 30..=37 => style = style.fg(basic_color(code - 30)),
+            38 => style = style.fg(parse_extended_color(&mut codes)?),
+            code @ 40..=47 => style = style.on(basic_color(code - 40)),
+            48 => style = style.on(parse_extended_color(&mut codes)?),
+            code @ 90..=97 => style = style.fg(bright_color(code - 90)),
+            code @ 100..=107 => style = style.on(bright_color(code - 100)),
+            _ => (),
+        }
+    }
+
+    Some(style)
+}
+
+fn parse_extended_color(codes: &mut impl Iterator<Item = Option<u8>>) -> Option<Color> {
+    match codes.next()?? {
+        5 => Some(Color::Fixed(codes.next()??)),
+        2 => Some(Color::Rgb(codes.next()??, codes.next()??, codes.next()??)),
+        _ => None,
+    }
+}
+
+fn basic_color(index: u8) -> Color {
+    match index {
+        0 => Color::Black,
+        1 => Color::Red,
+        2 => Color::Green,
+        3 => Color::Yellow,
+        4 => Color::Blue,
+        5 => Color::Purple,
+        6 => Color::Cyan,
+        _ => Color::LightGray,
+    }
+}
+
+fn bright_color(index: u8) -> Color {
+    match index {
+        0 => Color::DarkGray,
+        1 => Color::LightRed,
+        2 => Color::LightGreen,
+        3 => Color::LightYellow,
+        4 => Color::LightBlue,
+        5 => Color::LightPurple,
+        6 => Color::LightCyan,
+        _ => Color::White,
+    }
+}
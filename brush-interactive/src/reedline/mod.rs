@@ -1,5 +1,11 @@
+mod abbreviations;
+mod clipboard;
 mod completer;
+mod dirhistory;
 mod highlighter;
+mod keybindings;
+mod ls_colors;
+mod paste;
 mod prompt;
 mod reedline_shell;
 mod refs;
@@ -0,0 +1,109 @@
+//! Handling of bracketed pastes for the reedline-based input backend.
+//!
+//! Reedline leaves it to the edit mode to turn a paste into edits of the input buffer, so we
+//! wrap the edit mode to sanitize pasted text before it's inserted. If requested, multi-line
+//! pastes are instead held for confirmation, surfaced to us as a host command in the same way
+//! as the clipboard functions: reedline hands us the command's marker string (leaving the
+//! current input buffer intact), we ask whether to insert the held text, and then resume
+//! reading input.
+
+use std::io::{BufRead, Write};
+use std::sync::{Arc, Mutex};
+
+/// Host command string for a multi-line paste awaiting confirmation. As with the clipboard
+/// functions, it uses a prefix that can't reasonably be typed, so it won't be confused with
+/// actual user input.
+const CONFIRM_PASTE: &str = "\u{0}brush:confirm-paste";
+
+/// Multi-line pasted text held for confirmation before it's inserted.
+pub(crate) type PendingPaste = Arc<Mutex<Option<String>>>;
+
+/// An edit mode that sanitizes pasted text, deferring all other events to an inner edit mode.
+pub(crate) struct PasteHandlingEditMode {
+    /// The edit mode that handles all other events.
+    pub inner: Box<dyn reedline::EditMode>,
+    /// Whether multi-line pastes are held for confirmation.
+    pub confirm_multiline: bool,
+    /// Where multi-line pastes are held for confirmation.
+    pub pending: PendingPaste,
+}
+
+impl reedline::EditMode for PasteHandlingEditMode {
+    fn parse_event(&mut self, event: reedline::ReedlineRawEvent) -> reedline::ReedlineEvent {
+        let event: crossterm::event::Event = event.into();
+        let crossterm::event::Event::Paste(text) = event else {
+            return match reedline::ReedlineRawEvent::try_from(event) {
+                Ok(event) => self.inner.parse_event(event),
+                Err(()) => reedline::ReedlineEvent::None,
+            };
+        };
+
+        let text = sanitize(text.as_str());
+        if text.is_empty() {
+            return reedline::ReedlineEvent::None;
+        }
+
+        if self.confirm_multiline && text.contains('\n') {
+            *self
+                .pending
+                .lock()
+                .unwrap_or_else(std::sync::PoisonError::into_inner) = Some(text);
+            return reedline::ReedlineEvent::ExecuteHostCommand(CONFIRM_PASTE.to_owned());
+        }
+
+        reedline::ReedlineEvent::Edit(vec![reedline::EditCommand::InsertString(text)])
+    }
+
+    fn edit_mode(&self) -> reedline::PromptEditMode {
+        self.inner.edit_mode()
+    }
+}
+
+/// Returns whether the given host command string is the one for a paste awaiting
+/// confirmation.
+///
+/// # Arguments
+///
+/// * `host_command` - The host command string returned by reedline.
+pub(crate) fn is_confirm_paste_command(host_command: &str) -> bool {
+    host_command == CONFIRM_PASTE
+}
+
+/// Asks whether the pending multi-line paste, if any, should be inserted; returns the text
+/// to insert if so.
+///
+/// # Arguments
+///
+/// * `pending` - Where the paste is held.
+pub(crate) fn confirm(pending: &PendingPaste) -> Option<String> {
+    let text = pending
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+        .take()?;
+
+    let line_count = text.lines().count();
+
+    // N.B. Reedline has released the terminal, so we read the reply as a regular line.
+    let mut stderr = std::io::stderr();
+    let _ = write!(stderr, "\nPaste {line_count} lines? [y/N] ");
+    let _ = stderr.flush();
+
+    let mut reply = String::new();
+    if std::io::stdin().lock().read_line(&mut reply).is_err() {
+        return None;
+    }
+
+    matches!(reply.trim(), "y" | "Y" | "yes").then_some(text)
+}
+
+/// Sanitizes pasted text: line endings are normalized, trailing newlines are removed (so the
+/// paste doesn't look like a command ready to execute), and control characters other than
+/// newlines and tabs are dropped.
+fn sanitize(text: &str) -> String {
+    text.replace("\r\n", "\n")
+        .replace('\r', "\n")
+        .trim_end_matches('\n')
+        .chars()
+        .filter(|c| matches!(c, '\n' | '\t') || !c.is_control())
+        .collect()
+}
@@ -1,20 +1,30 @@
+use std::sync::{Arc, Mutex};
+
 use crate::interactive_shell::InteractivePrompt;
 
-impl reedline::Prompt for InteractivePrompt {
+/// Adapts an [`InteractivePrompt`] for use with reedline.
+pub(crate) struct ReedlinePrompt {
+    /// The prompt.
+    pub prompt: InteractivePrompt,
+    /// The construct left open by the input, as last determined by the validator.
+    pub unterminated: Arc<Mutex<Option<brush_parser::UnterminatedConstruct>>>,
+}
+
+impl reedline::Prompt for ReedlinePrompt {
     fn render_prompt_left(&self) -> std::borrow::Cow<str> {
         // [Workaround: see https://github.com/nushell/reedline/issues/707]
         // If the prompt starts with a newline character, then there's a chance
         // that it won't be rendered correctly. For this specific case, insert
         // an extra space character before the newline.
-        if self.prompt.starts_with('\n') {
-            std::format!(" {}", self.prompt).into()
+        if self.prompt.prompt.starts_with('\n') {
+            std::format!(" {}", self.prompt.prompt).into()
         } else {
-            self.prompt.as_str().into()
+            self.prompt.prompt.as_str().into()
         }
     }
 
     fn render_prompt_right(&self) -> std::borrow::Cow<str> {
-        self.alt_side_prompt.as_str().into()
+        self.prompt.alt_side_prompt.as_str().into()
     }
 
     // N.B. For now, we don't support prompt indicators.
@@ -26,7 +36,14 @@ impl reedline::Prompt for InteractivePrompt {
     }
 
     fn render_prompt_multiline_indicator(&self) -> std::borrow::Cow<str> {
-        self.continuation_prompt.as_str().into()
+        let unterminated = self
+            .unterminated
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+
+        self.prompt
+            .continuation_prompt_for(unterminated.as_ref())
+            .into()
     }
 
     fn render_prompt_history_search_indicator(
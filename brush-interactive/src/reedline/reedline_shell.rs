@@ -1,9 +1,12 @@
 use nu_ansi_term::Color;
-use reedline::MenuBuilder;
+use reedline::{History, MenuBuilder};
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
-use super::{completer, highlighter, refs, validator};
+use super::{
+    abbreviations, clipboard, completer, dirhistory, highlighter, keybindings, paste, prompt, refs,
+    validator,
+};
 use crate::{interactive_shell::InteractivePrompt, InteractiveShell, ReadResult, ShellError};
 
 /// Represents an interactive shell capable of taking commands from standard input
@@ -11,6 +14,11 @@ use crate::{interactive_shell::InteractivePrompt, InteractiveShell, ReadResult,
 pub struct ReedlineShell {
     reedline: reedline::Reedline,
     shell: refs::ShellRef,
+    clipboard: Option<clipboard::Clipboard>,
+    user_key_bindings: keybindings::UserKeyBindings,
+    applied_key_bindings: Vec<(String, String)>,
+    unterminated: Arc<std::sync::Mutex<Option<brush_parser::UnterminatedConstruct>>>,
+    pending_paste: paste::PendingPaste,
 }
 
 const COMPLETION_MENU_NAME: &str = "completion_menu";
@@ -24,8 +32,23 @@ impl ReedlineShell {
     pub async fn new(options: &crate::Options) -> Result<ReedlineShell, ShellError> {
         // Set up shell first. Its initialization may influence how the
         // editor needs to operate.
-        let shell = brush_core::Shell::new(&options.shell).await?;
-        let history_file_path = shell.get_history_file_path();
+        let mut shell = brush_core::Shell::new(&options.shell).await?;
+        shell.prompt_timeout = options.prompt_timeout;
+        crate::term::set_default_title_template(&mut shell, options.term_title.as_deref())?;
+
+        // If we have a history file (that the shell's sandbox policy lets us write to), load
+        // it; commands entered from here on are numbered after the entries loaded from it.
+        let history = shell
+            .get_history_file_path()
+            .filter(|history_file_path| shell.check_write_allowed(history_file_path).is_ok())
+            .and_then(|history_file_path| {
+                reedline::FileBackedHistory::with_file(reedline::HISTORY_SIZE, history_file_path)
+                    .ok()
+            });
+        if let Some(history) = &history {
+            let entry_count = history.count_all().unwrap_or_default();
+            shell.history_number += usize::try_from(entry_count).unwrap_or_default();
+        }
 
         let shell_ref = Arc::new(Mutex::new(shell));
 
@@ -33,12 +56,18 @@ impl ReedlineShell {
         // hold a reference to the shell.
         let completer = completer::ReedlineCompleter {
             shell: shell_ref.clone(),
+            color: !options.disable_color,
+            timeout: options.completion_timeout,
         };
+        let unterminated = Arc::new(std::sync::Mutex::new(None));
         let validator = validator::ReedlineValidator {
             shell: shell_ref.clone(),
+            parser: std::sync::Mutex::default(),
+            unterminated: unterminated.clone(),
         };
         let highlighter = highlighter::ReedlineHighlighter {
             shell: shell_ref.clone(),
+            parser: std::sync::Mutex::default(),
         };
 
         // Set up completion menu. Set an empty marker to avoid the
@@ -58,7 +87,7 @@ impl ReedlineShell {
         );
 
         // Set up key bindings.
-        let key_bindings = compose_key_bindings(COMPLETION_MENU_NAME);
+        let key_bindings = compose_key_bindings(COMPLETION_MENU_NAME, !options.disable_clipboard);
 
         // Set up default history-based hinter.
         let mut hinter = reedline::DefaultHinter::default();
@@ -66,6 +95,19 @@ impl ReedlineShell {
             hinter = hinter.with_style(nu_ansi_term::Style::new().italic().fg(Color::DarkGray));
         }
 
+        // Sanitize pasted text (and, if requested, hold multi-line pastes for confirmation)
+        // before it's inserted. Keys bound with `bind` take precedence over the defaults.
+        let user_key_bindings = keybindings::UserKeyBindings::default();
+        let pending_paste = paste::PendingPaste::default();
+        let edit_mode = paste::PasteHandlingEditMode {
+            inner: Box::new(keybindings::UserKeyBindingsEditMode {
+                inner: Box::new(reedline::Emacs::new(key_bindings)),
+                bindings: user_key_bindings.clone(),
+            }),
+            confirm_multiline: options.confirm_multiline_paste,
+            pending: pending_paste.clone(),
+        };
+
         // Instantiate reedline with some defaults and hand it ownership of
         // the helpers.
         let mut reedline = reedline::Reedline::create()
@@ -76,27 +118,44 @@ impl ReedlineShell {
             .with_validator(Box::new(validator))
             .with_hinter(Box::new(hinter))
             .with_menu(reedline::ReedlineMenu::EngineCompleter(completion_menu))
-            .with_edit_mode(Box::new(reedline::Emacs::new(key_bindings)));
+            .with_edit_mode(Box::new(edit_mode));
 
         // If requested, apply some additional niceties.
         if !options.disable_highlighting && !options.disable_color {
             reedline = reedline.with_highlighter(Box::new(highlighter));
         }
 
-        // If we have a history file, wire it up.
-        if let Some(history_file_path) = history_file_path {
-            if let Ok(history) =
-                reedline::FileBackedHistory::with_file(reedline::HISTORY_SIZE, history_file_path)
-            {
-                reedline = reedline.with_history(Box::new(history));
-            }
+        // If we have a history, wire it up.
+        if let Some(history) = history {
+            reedline = reedline.with_history(Box::new(history));
         }
 
         Ok(ReedlineShell {
             reedline,
             shell: shell_ref,
+            clipboard: (!options.disable_clipboard).then(clipboard::Clipboard::default),
+            user_key_bindings,
+            applied_key_bindings: vec![],
+            unterminated,
+            pending_paste,
         })
     }
+
+    /// Brings the keys bound in the editor up to date with the ones bound with `bind`.
+    fn update_key_bindings(&mut self) {
+        let Ok(shell) = self.shell.try_lock() else {
+            return;
+        };
+
+        if shell.key_bindings != self.applied_key_bindings {
+            self.applied_key_bindings.clone_from(&shell.key_bindings);
+            keybindings::update(
+                &self.user_key_bindings,
+                &self.applied_key_bindings,
+                self.clipboard.is_some(),
+            );
+        }
+    }
 }
 
 impl InteractiveShell for ReedlineShell {
@@ -120,11 +179,105 @@ impl InteractiveShell for ReedlineShell {
     ///
     /// * `prompt` - The prompt to display to the user.
     fn read_line(&mut self, prompt: InteractivePrompt) -> Result<ReadResult, ShellError> {
-        match self.reedline.read_line(&prompt) {
-            Ok(reedline::Signal::Success(s)) => Ok(ReadResult::Input(s)),
-            Ok(reedline::Signal::CtrlC) => Ok(ReadResult::Interrupted),
-            Ok(reedline::Signal::CtrlD) => Ok(ReadResult::Eof),
-            Err(err) => Err(ShellError::IoError(err)),
+        *self
+            .unterminated
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner) = None;
+
+        let prompt = prompt::ReedlinePrompt {
+            prompt,
+            unterminated: self.unterminated.clone(),
+        };
+
+        self.update_key_bindings();
+
+        loop {
+            match self.reedline.read_line(&prompt) {
+                Ok(reedline::Signal::Success(s)) => {
+                    // Spaces come back to us as host commands, so that we can expand the
+                    // abbreviation (if any) that precedes them.
+                    if abbreviations::is_insert_space_command(&s) {
+                        let commands = if let Ok(shell) = self.shell.try_lock() {
+                            abbreviations::expand_and_insert_space(
+                                &shell,
+                                self.reedline.current_buffer_contents(),
+                                self.reedline.current_insertion_point(),
+                            )
+                        } else {
+                            vec![reedline::EditCommand::InsertChar(' ')]
+                        };
+                        self.reedline.run_edit_commands(&commands);
+
+                        continue;
+                    }
+
+                    // Multi-line pastes held for confirmation come back to us as host
+                    // commands; insert the text only if the user confirms.
+                    if paste::is_confirm_paste_command(&s) {
+                        if let Some(text) = paste::confirm(&self.pending_paste) {
+                            self.reedline
+                                .run_edit_commands(&[reedline::EditCommand::InsertString(text)]);
+                        }
+
+                        continue;
+                    }
+
+                    // Clipboard functions come back to us as host commands; handle them
+                    // and then resume editing the (preserved) input buffer.
+                    if let Some(clipboard) = &mut self.clipboard {
+                        if let Some(func) = clipboard::ClipboardFunction::from_host_command(&s) {
+                            match func {
+                                clipboard::ClipboardFunction::CopyLine => {
+                                    clipboard.copy(self.reedline.current_buffer_contents());
+                                }
+                                clipboard::ClipboardFunction::CopySelection => {
+                                    let text = clipboard::selected_text(&mut self.reedline)
+                                        .unwrap_or_else(|| {
+                                            self.reedline.current_buffer_contents().to_owned()
+                                        });
+                                    clipboard.copy(text.as_str());
+                                }
+                                clipboard::ClipboardFunction::Paste => {
+                                    if let Some(text) = clipboard.paste() {
+                                        self.reedline.run_edit_commands(&[
+                                            reedline::EditCommand::InsertString(text),
+                                        ]);
+                                    }
+                                }
+                            }
+
+                            continue;
+                        }
+                    }
+
+                    // Directory history navigation comes back to us as host commands too;
+                    // if the working directory changed, have the prompt composed anew,
+                    // leaving the input buffer as it was.
+                    if let Some(func) = dirhistory::DirectoryHistoryFunction::from_host_command(&s)
+                    {
+                        let changed = match self.shell.try_lock() {
+                            Ok(mut shell) => func.execute(&mut shell),
+                            Err(_) => false,
+                        };
+
+                        if changed {
+                            return Ok(ReadResult::Refresh);
+                        }
+
+                        continue;
+                    }
+
+                    let s = match self.shell.try_lock() {
+                        Ok(shell) => abbreviations::expand_at_end(&shell, s),
+                        Err(_) => s,
+                    };
+
+                    return Ok(ReadResult::Input(s));
+                }
+                Ok(reedline::Signal::CtrlC) => return Ok(ReadResult::Interrupted),
+                Ok(reedline::Signal::CtrlD) => return Ok(ReadResult::Eof),
+                Err(err) => return Err(ShellError::IoError(err)),
+            }
         }
     }
 
@@ -135,7 +288,10 @@ impl InteractiveShell for ReedlineShell {
     }
 }
 
-fn compose_key_bindings(completion_menu_name: &str) -> reedline::Keybindings {
+fn compose_key_bindings(
+    completion_menu_name: &str,
+    enable_clipboard: bool,
+) -> reedline::Keybindings {
     let mut key_bindings = reedline::default_emacs_keybindings();
 
     // Wire up tab to completion.
@@ -176,5 +332,65 @@ fn compose_key_bindings(completion_menu_name: &str) -> reedline::Keybindings {
         ]),
     );
 
+    // Expand abbreviations as spaces are typed.
+    key_bindings.add_binding(
+        reedline::KeyModifiers::NONE,
+        reedline::KeyCode::Char(' '),
+        abbreviations::insert_space_event(),
+    );
+
+    // Navigate the directory history.
+    key_bindings.add_binding(
+        reedline::KeyModifiers::ALT,
+        reedline::KeyCode::Left,
+        dirhistory::DirectoryHistoryFunction::Previous.to_event(),
+    );
+    key_bindings.add_binding(
+        reedline::KeyModifiers::ALT,
+        reedline::KeyCode::Right,
+        dirhistory::DirectoryHistoryFunction::Next.to_event(),
+    );
+
+    // Extend the selection with shifted movement keys.
+    for (key_code, command) in [
+        (
+            reedline::KeyCode::Left,
+            reedline::EditCommand::MoveLeft { select: true },
+        ),
+        (
+            reedline::KeyCode::Right,
+            reedline::EditCommand::MoveRight { select: true },
+        ),
+        (
+            reedline::KeyCode::Home,
+            reedline::EditCommand::MoveToLineStart { select: true },
+        ),
+        (
+            reedline::KeyCode::End,
+            reedline::EditCommand::MoveToLineEnd { select: true },
+        ),
+    ] {
+        key_bindings.add_binding(
+            reedline::KeyModifiers::SHIFT,
+            key_code,
+            reedline::ReedlineEvent::Edit(vec![command]),
+        );
+    }
+
+    // Add clipboard integration; other keys may be bound to the clipboard functions with
+    // `bind`.
+    if enable_clipboard {
+        key_bindings.add_binding(
+            reedline::KeyModifiers::CONTROL | reedline::KeyModifiers::ALT,
+            reedline::KeyCode::Char('c'),
+            clipboard::ClipboardFunction::CopySelection.to_event(),
+        );
+        key_bindings.add_binding(
+            reedline::KeyModifiers::CONTROL | reedline::KeyModifiers::ALT,
+            reedline::KeyCode::Char('v'),
+            clipboard::ClipboardFunction::Paste.to_event(),
+        );
+    }
+
     key_bindings
 }
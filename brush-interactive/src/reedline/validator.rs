@@ -2,6 +2,10 @@ use super::refs;
 
 pub(crate) struct ReedlineValidator {
     pub shell: refs::ShellRef,
+    pub parser: std::sync::Mutex<brush_parser::IncrementalParser>,
+    /// Receives the construct left open by incomplete input, for display in the
+    /// continuation prompt.
+    pub unterminated: std::sync::Arc<std::sync::Mutex<Option<brush_parser::UnterminatedConstruct>>>,
 }
 
 impl reedline::Validator for ReedlineValidator {
@@ -10,16 +14,28 @@ impl reedline::Validator for ReedlineValidator {
             tokio::runtime::Handle::current().block_on(self.shell.lock())
         });
 
-        match shell.parse_string(line.to_owned()) {
-            Err(brush_parser::ParseError::Tokenizing { inner, position: _ })
-                if inner.is_incomplete() =>
-            {
-                reedline::ValidationResult::Incomplete
-            }
-            Err(brush_parser::ParseError::ParsingAtEndOfInput) => {
-                reedline::ValidationResult::Incomplete
-            }
-            _ => reedline::ValidationResult::Complete,
-        }
+        let mut parser = self
+            .parser
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        let source_info = brush_parser::SourceInfo {
+            source: String::from("main"),
+        };
+
+        let (result, unterminated) =
+            match parser.parse(line, &shell.program_parser_options(), &source_info) {
+                Err(error) if error.is_incomplete() => (
+                    reedline::ValidationResult::Incomplete,
+                    parser.unterminated_construct(&error),
+                ),
+                _ => (reedline::ValidationResult::Complete, None),
+            };
+
+        *self
+            .unterminated
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner) = unterminated;
+
+        result
     }
 }
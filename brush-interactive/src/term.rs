@@ -0,0 +1,92 @@
+//! Terminal title updates.
+//!
+//! When `BRUSH_TERM_TITLE` is set, it's expanded like a prompt (so escapes like `\w` and `\h`
+//! may be used) and the result set as the terminal's title: once before each prompt is
+//! displayed, and again before each command is executed. While it's expanded for the
+//! latter, `BRUSH_TERM_TITLE_COMMAND` holds the command about to be executed.
+
+use std::io::Write;
+
+use crate::ShellError;
+
+/// Variable holding the template for the terminal title (a brush extension).
+const TERM_TITLE_VAR: &str = "BRUSH_TERM_TITLE";
+
+/// Variable holding the command about to be executed, while the terminal title template is
+/// expanded (a brush extension).
+const TERM_TITLE_COMMAND_VAR: &str = "BRUSH_TERM_TITLE_COMMAND";
+
+/// Sets the terminal title template to the given default, unless one was already set (e.g.,
+/// by the shell's startup files).
+///
+/// # Arguments
+///
+/// * `shell` - The shell whose terminal title template is set.
+/// * `template` - The default template, if any.
+pub(crate) fn set_default_title_template(
+    shell: &mut brush_core::Shell,
+    template: Option<&str>,
+) -> Result<(), ShellError> {
+    if let Some(template) = template {
+        if shell.env.get(TERM_TITLE_VAR).is_none() {
+            shell.env.set_global(
+                TERM_TITLE_VAR,
+                brush_core::ShellVariable::new(template.into()),
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Updates the terminal title from the shell's terminal title template, if one is set and
+/// the shell's standard error is a terminal. Failures are logged rather than reported, so
+/// as not to interrupt the shell.
+///
+/// # Arguments
+///
+/// * `shell` - The shell whose terminal is updated.
+/// * `command` - The command about to be executed, if any.
+pub(crate) async fn update_title(shell: &mut brush_core::Shell, command: Option<&str>) {
+    if let Err(e) = try_update_title(shell, command).await {
+        tracing::debug!("failed to update terminal title: {e}");
+    }
+}
+
+async fn try_update_title(
+    shell: &mut brush_core::Shell,
+    command: Option<&str>,
+) -> Result<(), ShellError> {
+    if !shell.options.interactive {
+        return Ok(());
+    }
+
+    let Some((_, template)) = shell.env.get(TERM_TITLE_VAR) else {
+        return Ok(());
+    };
+    let template = template.value().to_cow_string().to_string();
+
+    let mut stderr = shell.stderr();
+    if template.is_empty() || !stderr.is_term() {
+        return Ok(());
+    }
+
+    shell.env.set_global(
+        TERM_TITLE_COMMAND_VAR,
+        brush_core::ShellVariable::new(command.unwrap_or_default().into()),
+    )?;
+    let title = shell.expand_prompt_string(template).await;
+    shell.env.unset(TERM_TITLE_COMMAND_VAR)?;
+
+    write!(stderr, "{}", format_title_sequence(title?.as_str()))?;
+    stderr.flush()?;
+
+    Ok(())
+}
+
+/// Returns the OSC 0 escape sequence that sets the terminal's title (and icon name) to the
+/// given title, less any control characters it contains.
+fn format_title_sequence(title: &str) -> String {
+    let title: String = title.chars().filter(|c| !c.is_control()).collect();
+    std::format!("\x1b]0;{title}\x07")
+}
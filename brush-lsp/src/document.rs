@@ -0,0 +1,323 @@
+//! Analysis of individual shell script documents.
+
+use brush_parser::ast;
+use brush_parser::{SourcePosition, Token, TokenLocation};
+
+/// A shell script document open in the editor, along with the results of analyzing it.
+pub(crate) struct Document {
+    /// The full text of the document.
+    pub text: String,
+    /// The tokens in the document; empty if tokenization failed.
+    pub tokens: Vec<Token>,
+    /// The parsed program, if tokenization succeeded. If the document contains syntax
+    /// errors, only the commands that could be parsed are present.
+    pub program: Option<ast::Program>,
+    /// Problems found in the document.
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// The severity of a document diagnostic, using the numbering defined by the protocol.
+#[derive(Clone, Copy)]
+pub(crate) enum Severity {
+    Error = 1,
+    Warning = 2,
+    Information = 3,
+    Hint = 4,
+}
+
+/// A problem found in a document.
+pub(crate) struct Diagnostic {
+    pub location: TokenLocation,
+    pub severity: Severity,
+    pub code: Option<&'static str>,
+    pub message: String,
+}
+
+/// A function defined in a document.
+pub(crate) struct FunctionSymbol {
+    pub name: String,
+    /// Location of the whole definition.
+    pub location: TokenLocation,
+    /// Location of the function's name.
+    pub name_location: TokenLocation,
+}
+
+impl Document {
+    /// Parses and analyzes the given document text.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The text of the document.
+    pub fn analyze(text: String) -> Self {
+        let options = brush_parser::ParserOptions::default();
+        let source_info = brush_parser::SourceInfo::default();
+
+        let tokens = match brush_parser::tokenize_str_with_options(
+            text.as_str(),
+            &options.tokenizer_options(),
+        ) {
+            Ok(tokens) => tokens,
+            Err(e) => {
+                let diagnostic = Diagnostic {
+                    location: end_of_text_location(text.as_str()),
+                    severity: Severity::Error,
+                    code: None,
+                    message: e.to_string(),
+                };
+
+                return Self {
+                    text,
+                    tokens: vec![],
+                    program: None,
+                    diagnostics: vec![diagnostic],
+                };
+            }
+        };
+
+        let (program, errors) =
+            brush_parser::parse_tokens_with_recovery(&tokens, &options, &source_info);
+
+        let lint_diagnostics = brush_parser::lint::lint_program(&program, &tokens, &options);
+        let diagnostics = errors
+            .iter()
+            .map(brush_parser::lint::Diagnostic::from)
+            .chain(lint_diagnostics)
+            .map(|d| Diagnostic {
+                location: d.location.clone().unwrap_or_default(),
+                severity: match d.severity() {
+                    brush_parser::lint::Severity::Error => Severity::Error,
+                    brush_parser::lint::Severity::Warning => Severity::Warning,
+                    brush_parser::lint::Severity::Info => Severity::Information,
+                    brush_parser::lint::Severity::Style => Severity::Hint,
+                },
+                code: Some(d.rule.code()),
+                message: d.message,
+            })
+            .collect();
+
+        let program = Some(program);
+
+        Self {
+            text,
+            tokens,
+            program,
+            diagnostics,
+        }
+    }
+
+    /// Returns the functions defined in the document, in source order.
+    pub fn functions(&self) -> Vec<FunctionSymbol> {
+        let Some(program) = &self.program else {
+            return vec![];
+        };
+
+        let mut names = vec![];
+        for complete_command in &program.complete_commands {
+            collect_function_names(complete_command, &mut names);
+        }
+
+        let mut functions = vec![];
+        for (i, token) in self.tokens.iter().enumerate() {
+            let Token::Word(word, location) = token else {
+                continue;
+            };
+
+            // Look for either `function name` or `name()`.
+            let name_token = if word == "function" {
+                self.tokens.get(i + 1)
+            } else if matches!(
+                (self.tokens.get(i + 1), self.tokens.get(i + 2)),
+                (Some(Token::Operator(open, _)), Some(Token::Operator(close, _)))
+                    if open == "(" && close == ")"
+            ) {
+                Some(token)
+            } else {
+                None
+            };
+
+            let Some(Token::Word(name, name_location)) = name_token else {
+                continue;
+            };
+
+            if !names.contains(name)
+                || functions.iter().any(|f: &FunctionSymbol| {
+                    f.name_location.start.index == name_location.start.index
+                })
+            {
+                continue;
+            }
+
+            let end = program
+                .complete_command_locations
+                .iter()
+                .find(|l| {
+                    l.start.index <= location.start.index && location.start.index < l.end.index
+                })
+                .map_or_else(|| name_location.end.clone(), |l| l.end.clone());
+
+            functions.push(FunctionSymbol {
+                name: name.clone(),
+                location: TokenLocation {
+                    start: location.start.clone(),
+                    end,
+                },
+                name_location: name_location.clone(),
+            });
+        }
+
+        functions
+    }
+
+    /// Returns the word token found at the given position, if any.
+    ///
+    /// # Arguments
+    ///
+    /// * `line` - The 1-based line number.
+    /// * `column` - The 1-based column number.
+    pub fn word_at(&self, line: i32, column: i32) -> Option<(&str, &TokenLocation)> {
+        self.tokens.iter().find_map(|token| match token {
+            Token::Word(word, location)
+                if location.start.line == line
+                    && location.start.column <= column
+                    && (location.end.line > line || column <= location.end.column) =>
+            {
+                Some((word.as_str(), location))
+            }
+            _ => None,
+        })
+    }
+
+    /// Returns the text of the given line, up to the given column.
+    ///
+    /// # Arguments
+    ///
+    /// * `line` - The 0-based line index.
+    /// * `character` - The 0-based character offset within the line.
+    pub fn line_prefix(&self, line: usize, character: usize) -> Option<&str> {
+        let line_text = self.text.lines().nth(line)?;
+        let end = line_text
+            .char_indices()
+            .nth(character)
+            .map_or(line_text.len(), |(index, _)| index);
+
+        Some(&line_text[..end])
+    }
+}
+
+fn end_of_text_location(text: &str) -> TokenLocation {
+    let line = text.lines().count().max(1);
+    let column = text.lines().last().map_or(0, |l| l.chars().count()) + 1;
+
+    let position = SourcePosition {
+        index: i32::try_from(text.chars().count()).unwrap_or(i32::MAX),
+        line: i32::try_from(line).unwrap_or(i32::MAX),
+        column: i32::try_from(column).unwrap_or(i32::MAX),
+    };
+
+    TokenLocation {
+        start: position.clone(),
+        end: position,
+    }
+}
+
+fn collect_function_names(list: &ast::CompoundList, names: &mut Vec<String>) {
+    for ast::CompoundListItem(ao_list, _) in &list.0 {
+        let pipelines = std::iter::once(&ao_list.first).chain(ao_list.additional.iter().map(
+            |next| match next {
+                ast::AndOr::And(pipeline) | ast::AndOr::Or(pipeline) => pipeline,
+            },
+        ));
+
+        for command in pipelines.flat_map(|p| p.seq.iter()) {
+            match command {
+                ast::Command::Function(function_definition) => {
+                    names.push(function_definition.fname.clone());
+                    collect_compound_command_function_names(&function_definition.body.0, names);
+                }
+                ast::Command::Compound(compound_command, _) => {
+                    collect_compound_command_function_names(compound_command, names);
+                }
+                ast::Command::Simple(_) | ast::Command::ExtendedTest(_) => (),
+            }
+        }
+    }
+}
+
+fn collect_compound_command_function_names(
+    command: &ast::CompoundCommand,
+    names: &mut Vec<String>,
+) {
+    match command {
+        ast::CompoundCommand::Arithmetic(_) => (),
+        ast::CompoundCommand::ArithmeticForClause(for_clause) => {
+            collect_function_names(&for_clause.body.0, names);
+        }
+        ast::CompoundCommand::BraceGroup(ast::BraceGroupCommand(list))
+        | ast::CompoundCommand::Subshell(ast::SubshellCommand(list)) => {
+            collect_function_names(list, names);
+        }
+        ast::CompoundCommand::ForClause(for_clause) => {
+            collect_function_names(&for_clause.body.0, names);
+        }
+        ast::CompoundCommand::CaseClause(case_clause) => {
+            for cmd in case_clause.cases.iter().filter_map(|c| c.cmd.as_ref()) {
+                collect_function_names(cmd, names);
+            }
+        }
+        ast::CompoundCommand::IfClause(if_clause) => {
+            collect_function_names(&if_clause.then, names);
+            for else_clause in if_clause.elses.iter().flatten() {
+                collect_function_names(&else_clause.body, names);
+            }
+        }
+        ast::CompoundCommand::WhileClause(ast::WhileOrUntilClauseCommand(_, body))
+        | ast::CompoundCommand::UntilClause(ast::WhileOrUntilClauseCommand(_, body)) => {
+            collect_function_names(&body.0, names);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn find_functions() {
+        let document = Document::analyze(String::from(
+            "function foo {\n  echo hi\n}\nbar() { foo; }\nbar\n",
+        ));
+
+        let functions = document.functions();
+        let names: Vec<_> = functions.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, ["foo", "bar"]);
+
+        assert_eq!(functions[0].name_location.start.line, 1);
+        assert_eq!(functions[0].name_location.start.column, 10);
+        assert_eq!(functions[1].location.start.line, 4);
+    }
+
+    #[test]
+    fn parse_error_diagnostics() {
+        let document = Document::analyze(String::from("echo hi\nif then\nfoo() { :; }\nfi )\n"));
+
+        assert_eq!(document.diagnostics.len(), 2);
+        assert!(document
+            .diagnostics
+            .iter()
+            .all(|d| matches!(d.severity, Severity::Error)));
+        assert_eq!(document.diagnostics[0].location.start.line, 2);
+        assert_eq!(document.diagnostics[1].location.start.line, 4);
+
+        let functions = document.functions();
+        assert_eq!(functions.len(), 1);
+        assert_eq!(functions[0].name, "foo");
+    }
+
+    #[test]
+    fn word_at_position() {
+        let document = Document::analyze(String::from("echo hello world\n"));
+
+        assert_eq!(document.word_at(1, 8).map(|(w, _)| w), Some("hello"));
+        assert_eq!(document.line_prefix(0, 4), Some("echo"));
+    }
+}
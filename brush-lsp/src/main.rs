@@ -0,0 +1,54 @@
+//! Implements a Language Server Protocol server for shell scripts, built on `brush`.
+//! The server communicates with its client over standard input and output.
+
+#![deny(missing_docs)]
+
+mod document;
+mod server;
+mod transport;
+
+fn main() {
+    // N.B. Standard output carries protocol messages, so all logging must go to stderr.
+    tracing_subscriber::fmt()
+        .with_writer(std::io::stderr)
+        .with_max_level(tracing::Level::WARN)
+        .init();
+
+    let result = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map_err(|e| e.to_string())
+        .and_then(|runtime| runtime.block_on(run()));
+
+    let exit_code = match result {
+        Ok(code) => code,
+        Err(e) => {
+            tracing::error!("error: {e}");
+            1
+        }
+    };
+
+    std::process::exit(i32::from(exit_code));
+}
+
+async fn run() -> Result<u8, String> {
+    let options = brush_core::CreateOptions {
+        interactive: false,
+        no_profile: true,
+        no_rc: true,
+        ..brush_core::CreateOptions::default()
+    };
+
+    let shell = brush_core::Shell::new(&options)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let mut server = server::Server::new(shell);
+    let mut reader = std::io::stdin().lock();
+    let mut writer = std::io::stdout().lock();
+
+    server
+        .run(&mut reader, &mut writer)
+        .await
+        .map_err(|e| e.to_string())
+}
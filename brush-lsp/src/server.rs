@@ -0,0 +1,392 @@
+//! Implements the language server's handling of requests and notifications.
+
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
+
+use serde_json::{json, Value};
+
+use crate::document::Document;
+use crate::transport;
+
+/// Error code used when a request names a method the server doesn't implement.
+const METHOD_NOT_FOUND: i64 = -32601;
+/// Error code used when a request is received before the server has been initialized.
+const SERVER_NOT_INITIALIZED: i64 = -32002;
+
+/// A language server for shell scripts.
+pub(crate) struct Server {
+    /// Shell used to answer completion and hover queries.
+    shell: brush_core::Shell,
+    /// Documents currently open in the client, keyed by URI.
+    documents: HashMap<String, Document>,
+    /// Whether the client has initialized the server.
+    initialized: bool,
+    /// Whether the client has requested that the server shut down.
+    shutdown_requested: bool,
+}
+
+impl Server {
+    /// Creates a new server that uses the given shell to answer queries.
+    ///
+    /// # Arguments
+    ///
+    /// * `shell` - The shell to use.
+    pub fn new(shell: brush_core::Shell) -> Self {
+        Self {
+            shell,
+            documents: HashMap::new(),
+            initialized: false,
+            shutdown_requested: false,
+        }
+    }
+
+    /// Serves requests read from the given reader until the client asks the server to
+    /// exit or the input is closed. Returns the exit code the process should exit with.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - The reader to read messages from.
+    /// * `writer` - The writer to write messages to.
+    pub async fn run(
+        &mut self,
+        reader: &mut impl BufRead,
+        writer: &mut impl Write,
+    ) -> Result<u8, std::io::Error> {
+        while let Some(message) = transport::read_message(reader)? {
+            let method = message.get("method").and_then(Value::as_str).unwrap_or("");
+            let params = message.get("params").cloned().unwrap_or(Value::Null);
+
+            if method == "exit" {
+                return Ok(u8::from(!self.shutdown_requested));
+            }
+
+            if let Some(id) = message.get("id").cloned() {
+                let response = match self.handle_request(method, &params).await {
+                    Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+                    Err((code, message)) => json!({
+                        "jsonrpc": "2.0",
+                        "id": id,
+                        "error": { "code": code, "message": message },
+                    }),
+                };
+
+                transport::write_message(writer, &response)?;
+            } else {
+                for notification in self.handle_notification(method, &params) {
+                    transport::write_message(writer, &notification)?;
+                }
+            }
+        }
+
+        Ok(1)
+    }
+
+    async fn handle_request(
+        &mut self,
+        method: &str,
+        params: &Value,
+    ) -> Result<Value, (i64, String)> {
+        if method == "initialize" {
+            self.initialized = true;
+            return Ok(initialize_result());
+        } else if !self.initialized {
+            return Err((
+                SERVER_NOT_INITIALIZED,
+                String::from("server not yet initialized"),
+            ));
+        }
+
+        match method {
+            "shutdown" => {
+                self.shutdown_requested = true;
+                Ok(Value::Null)
+            }
+            "textDocument/documentSymbol" => Ok(self.document_symbols(params)),
+            "textDocument/definition" => Ok(self.definition(params)),
+            "textDocument/hover" => Ok(self.hover(params)),
+            "textDocument/completion" => Ok(self.completion(params).await),
+            _ => Err((METHOD_NOT_FOUND, std::format!("unhandled method: {method}"))),
+        }
+    }
+
+    fn handle_notification(&mut self, method: &str, params: &Value) -> Vec<Value> {
+        let Some(uri) = params
+            .pointer("/textDocument/uri")
+            .and_then(Value::as_str)
+            .map(str::to_owned)
+        else {
+            return vec![];
+        };
+
+        match method {
+            "textDocument/didOpen" => {
+                let text = params
+                    .pointer("/textDocument/text")
+                    .and_then(Value::as_str)
+                    .unwrap_or("");
+                self.update_document(uri, text.to_owned())
+            }
+            "textDocument/didChange" => {
+                // N.B. We only advertise full-document sync, so the last change holds
+                // the complete text of the document.
+                let Some(text) = params
+                    .get("contentChanges")
+                    .and_then(Value::as_array)
+                    .and_then(|changes| changes.last())
+                    .and_then(|change| change.get("text"))
+                    .and_then(Value::as_str)
+                else {
+                    return vec![];
+                };
+                self.update_document(uri, text.to_owned())
+            }
+            "textDocument/didClose" => {
+                self.documents.remove(&uri);
+                vec![publish_diagnostics(uri.as_str(), vec![])]
+            }
+            _ => vec![],
+        }
+    }
+
+    fn update_document(&mut self, uri: String, text: String) -> Vec<Value> {
+        let document = Document::analyze(text);
+
+        let diagnostics = document
+            .diagnostics
+            .iter()
+            .map(|diagnostic| {
+                let mut value = json!({
+                    "range": to_range(&diagnostic.location),
+                    "severity": diagnostic.severity as u8,
+                    "source": "brush",
+                    "message": diagnostic.message,
+                });
+
+                if let Some(code) = diagnostic.code {
+                    value["code"] = Value::from(code);
+                }
+
+                value
+            })
+            .collect();
+
+        let notification = publish_diagnostics(uri.as_str(), diagnostics);
+        self.documents.insert(uri, document);
+
+        vec![notification]
+    }
+
+    fn document_symbols(&self, params: &Value) -> Value {
+        let Some((_, document)) = self.document_for(params) else {
+            return Value::Null;
+        };
+
+        document
+            .functions()
+            .into_iter()
+            .map(|function| {
+                json!({
+                    "name": function.name,
+                    "kind": SYMBOL_KIND_FUNCTION,
+                    "range": to_range(&function.location),
+                    "selectionRange": to_range(&function.name_location),
+                })
+            })
+            .collect()
+    }
+
+    fn definition(&self, params: &Value) -> Value {
+        let Some((uri, document)) = self.document_for(params) else {
+            return Value::Null;
+        };
+        let Some((word, _)) = word_at_params_position(document, params) else {
+            return Value::Null;
+        };
+
+        document
+            .functions()
+            .into_iter()
+            .rev()
+            .find(|function| function.name == word)
+            .map_or(
+                Value::Null,
+                |function| json!({ "uri": uri, "range": to_range(&function.name_location) }),
+            )
+    }
+
+    fn hover(&self, params: &Value) -> Value {
+        let Some((_, document)) = self.document_for(params) else {
+            return Value::Null;
+        };
+        let Some((word, location)) = word_at_params_position(document, params) else {
+            return Value::Null;
+        };
+
+        let contents = if let Some(registration) = self
+            .shell
+            .builtins
+            .get(word)
+            .filter(|registration| !registration.disabled)
+        {
+            let content = |content_type| (registration.content_func)(word, content_type).ok();
+
+            let mut contents = String::new();
+            if let Some(usage) = content(brush_core::builtins::ContentType::ShortUsage) {
+                contents.push_str(std::format!("```\n{}\n```\n", usage.trim_end()).as_str());
+            }
+            if let Some(description) = content(brush_core::builtins::ContentType::ShortDescription)
+            {
+                contents.push_str(description.trim_end());
+            }
+
+            contents
+        } else if let Some(function) = document
+            .functions()
+            .into_iter()
+            .rev()
+            .find(|function| function.name == word)
+        {
+            std::format!(
+                "```sh\n{word}()\n```\nFunction defined on line {}",
+                function.location.start.line
+            )
+        } else {
+            return Value::Null;
+        };
+
+        if contents.is_empty() {
+            return Value::Null;
+        }
+
+        json!({
+            "contents": { "kind": "markdown", "value": contents },
+            "range": to_range(location),
+        })
+    }
+
+    async fn completion(&mut self, params: &Value) -> Value {
+        let Some((line, character)) = params_position(params) else {
+            return Value::Null;
+        };
+        let Some(prefix) = self
+            .document_for(params)
+            .and_then(|(_, document)| document.line_prefix(line, character))
+            .map(str::to_owned)
+        else {
+            return Value::Null;
+        };
+
+        let completions = match self
+            .shell
+            .get_completions(prefix.as_str(), prefix.len())
+            .await
+        {
+            Ok(completions) => completions,
+            Err(e) => {
+                tracing::debug!("failed to generate completions: {e}");
+                return Value::Null;
+            }
+        };
+
+        // Figure out the range of text that each completion replaces, in characters.
+        let start = prefix
+            .get(..completions.insertion_index)
+            .map_or(character, |s| s.chars().count());
+        let end = prefix
+            .get(completions.insertion_index..)
+            .and_then(|s| s.get(..completions.delete_count))
+            .map_or(start, |s| start + s.chars().count());
+        let range = json!({
+            "start": { "line": line, "character": start },
+            "end": { "line": line, "character": end },
+        });
+
+        let items: Vec<_> = completions
+            .candidates
+            .into_iter()
+            .map(|candidate| {
+                json!({
+                    "label": candidate,
+                    "textEdit": { "range": range, "newText": candidate },
+                })
+            })
+            .collect();
+
+        Value::from(items)
+    }
+
+    fn document_for(&self, params: &Value) -> Option<(&str, &Document)> {
+        let uri = params
+            .pointer("/textDocument/uri")
+            .and_then(Value::as_str)?;
+        self.documents.get(uri).map(|document| (uri, document))
+    }
+}
+
+/// Symbol kind used by the protocol for functions.
+const SYMBOL_KIND_FUNCTION: u8 = 12;
+
+fn initialize_result() -> Value {
+    json!({
+        "capabilities": {
+            // N.B. 1 indicates full-document sync.
+            "textDocumentSync": 1,
+            "documentSymbolProvider": true,
+            "definitionProvider": true,
+            "hoverProvider": true,
+            "completionProvider": {},
+        },
+        "serverInfo": {
+            "name": env!("CARGO_PKG_NAME"),
+            "version": env!("CARGO_PKG_VERSION"),
+        },
+    })
+}
+
+fn publish_diagnostics(uri: &str, diagnostics: Vec<Value>) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/publishDiagnostics",
+        "params": { "uri": uri, "diagnostics": diagnostics },
+    })
+}
+
+fn params_position(params: &Value) -> Option<(usize, usize)> {
+    let line = params.pointer("/position/line").and_then(Value::as_u64)?;
+    let character = params
+        .pointer("/position/character")
+        .and_then(Value::as_u64)?;
+
+    Some((
+        usize::try_from(line).ok()?,
+        usize::try_from(character).ok()?,
+    ))
+}
+
+fn word_at_params_position<'a>(
+    document: &'a Document,
+    params: &Value,
+) -> Option<(&'a str, &'a brush_parser::TokenLocation)> {
+    let (line, character) = params_position(params)?;
+
+    // N.B. Positions in the protocol are 0-based; the tokenizer's are 1-based.
+    document.word_at(
+        i32::try_from(line + 1).ok()?,
+        i32::try_from(character + 1).ok()?,
+    )
+}
+
+/// Converts a token location to a range as defined by the protocol.
+fn to_range(location: &brush_parser::TokenLocation) -> Value {
+    json!({
+        "start": to_position(&location.start),
+        "end": to_position(&location.end),
+    })
+}
+
+fn to_position(position: &brush_parser::SourcePosition) -> Value {
+    json!({
+        "line": (position.line - 1).max(0),
+        "character": (position.column - 1).max(0),
+    })
+}
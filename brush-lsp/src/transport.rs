@@ -0,0 +1,63 @@
+//! Implements the base protocol of the Language Server Protocol: JSON-RPC messages
+//! framed by HTTP-style headers.
+
+use std::io::{BufRead, Write};
+
+/// Reads the next message from the given reader. Returns `None` if the end of the input
+/// stream was reached.
+///
+/// # Arguments
+///
+/// * `reader` - The reader to read the message from.
+pub(crate) fn read_message(
+    reader: &mut impl BufRead,
+) -> Result<Option<serde_json::Value>, std::io::Error> {
+    let mut content_length = None;
+
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse::<usize>().ok();
+            }
+        }
+    }
+
+    let Some(content_length) = content_length else {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "message missing Content-Length header",
+        ));
+    };
+
+    let mut content = vec![0; content_length];
+    reader.read_exact(&mut content)?;
+
+    serde_json::from_slice(&content)
+        .map(Some)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+}
+
+/// Writes the given message to the given writer.
+///
+/// # Arguments
+///
+/// * `writer` - The writer to write the message to.
+/// * `message` - The message to write.
+pub(crate) fn write_message(
+    writer: &mut impl Write,
+    message: &serde_json::Value,
+) -> Result<(), std::io::Error> {
+    let content = message.to_string();
+    write!(writer, "Content-Length: {}\r\n\r\n{content}", content.len())?;
+    writer.flush()
+}
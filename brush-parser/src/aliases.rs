@@ -0,0 +1,484 @@
+//! Expansion of aliases in a sequence of tokens, ahead of parsing.
+//!
+//! An unquoted word that's the first word of a simple command is replaced with the tokens
+//! of the alias it names, if any. The first word of the replacement is itself checked for
+//! an alias, but an alias is never expanded again within its own replacement (which
+//! guarantees termination). If an alias's value ends with a blank, then the word following
+//! its replacement is also checked for an alias, even if it's not in command position. If
+//! an alias's value can't be tokenized, the resulting error is reported as a syntax error at
+//! the word that names it, much as bash reports the error that results from substituting it.
+
+use std::collections::{BTreeMap, VecDeque};
+
+use crate::error;
+use crate::parser::{self, ParserOptions, RecoveredParseError};
+use crate::tokenizer::{Token, TokenLocation, TokenizerOptions};
+
+/// The state of a `case` command whose header or patterns are being scanned.
+enum CaseState {
+    /// The word being matched, up through the `in` keyword.
+    Subject,
+    /// A pattern list, up through its closing `)`.
+    Patterns,
+    /// The commands following a pattern list.
+    Body,
+}
+
+/// A token that's yet to be scanned, or the end of an alias's replacement.
+enum Pending {
+    Token {
+        /// The token.
+        token: Token,
+        /// The names of the aliases whose replacements the token is part of.
+        expanding: Vec<String>,
+        /// For tokens that are part of an alias's replacement, the location of the word
+        /// that was replaced; this is the location we report for the token.
+        origin: Option<TokenLocation>,
+    },
+    EndOfAlias {
+        /// Whether the alias's value ended with a blank.
+        trailing_blank: bool,
+    },
+}
+
+/// Tracks where in the shell grammar the scan is, to the extent needed to identify words
+/// in command position.
+struct Scanner<'a> {
+    aliases: &'a BTreeMap<String, String>,
+    tokenizer_options: TokenizerOptions,
+    /// Whether the next word is the first word of a simple command.
+    command_position: bool,
+    /// Whether the next word should be checked for an alias regardless of its position.
+    check_next_word: bool,
+    /// The number of upcoming tokens (e.g., redirection targets) to pass over.
+    skip_tokens: usize,
+    /// The depth of parentheses within an arithmetic command or array assignment being
+    /// passed over, if any.
+    skip_parens: Option<usize>,
+    /// Whether a `[[ ... ]]` extended test is being passed over.
+    in_extended_test: bool,
+    /// The states of the `case` commands enclosing the current token.
+    cases: Vec<CaseState>,
+}
+
+/// Expands aliases in the given tokens, as described in the module docs, using the aliases
+/// defined in the given options. Tokens that come from an alias's value are reported at the
+/// location of the word they replaced.
+///
+/// # Arguments
+///
+/// * `tokens` - The tokens to expand aliases in.
+/// * `options` - The options in effect, including the aliases to expand.
+pub(crate) fn expand_aliases(
+    tokens: &[Token],
+    options: &ParserOptions,
+) -> Result<Vec<Token>, RecoveredParseError> {
+    let mut scanner = Scanner {
+        aliases: &options.aliases,
+        tokenizer_options: options.tokenizer_options(),
+        command_position: true,
+        check_next_word: false,
+        skip_tokens: 0,
+        skip_parens: None,
+        in_extended_test: false,
+        cases: vec![],
+    };
+
+    let mut pending: VecDeque<_> = tokens
+        .iter()
+        .map(|token| Pending::Token {
+            token: token.clone(),
+            expanding: vec![],
+            origin: None,
+        })
+        .collect();
+
+    let mut expanded = Vec::with_capacity(tokens.len());
+
+    while let Some(item) = pending.pop_front() {
+        let (token, expanding, origin) = match item {
+            Pending::Token {
+                token,
+                expanding,
+                origin,
+            } => (token, expanding, origin),
+            Pending::EndOfAlias { trailing_blank } => {
+                scanner.check_next_word |= trailing_blank;
+                continue;
+            }
+        };
+
+        let next = pending.iter().find_map(|item| match item {
+            Pending::Token { token, .. } => Some(token),
+            Pending::EndOfAlias { .. } => None,
+        });
+
+        if let Some((name, replacement)) = scanner.try_expand(&token, &expanding, next)? {
+            let trailing_blank = scanner.aliases[name.as_str()].ends_with([' ', '\t']);
+            let origin = origin.unwrap_or_else(|| token.location().clone());
+
+            let mut expanding = expanding;
+            expanding.push(name);
+
+            pending.push_front(Pending::EndOfAlias { trailing_blank });
+            for replacement_token in replacement.into_iter().rev() {
+                pending.push_front(Pending::Token {
+                    token: replacement_token,
+                    expanding: expanding.clone(),
+                    origin: Some(origin.clone()),
+                });
+            }
+
+            // The first word of the replacement is checked in turn.
+            scanner.check_next_word = true;
+            continue;
+        }
+
+        scanner.observe(&token, next);
+
+        expanded.push(match origin {
+            Some(origin) => relocate(token, origin),
+            None => token,
+        });
+    }
+
+    Ok(expanded)
+}
+
+impl Scanner<'_> {
+    /// If the given token is a word that should be replaced by an alias, returns the
+    /// alias's name and the tokens of its value.
+    fn try_expand(
+        &mut self,
+        token: &Token,
+        expanding: &[String],
+        next: Option<&Token>,
+    ) -> Result<Option<(String, Vec<Token>)>, RecoveredParseError> {
+        let check_next_word = std::mem::take(&mut self.check_next_word);
+
+        let Token::Word(word, location) = token else {
+            return Ok(None);
+        };
+
+        if !self.passing_over_nothing() || (!self.command_position && !check_next_word) {
+            return Ok(None);
+        }
+
+        // Quoted words are never expanded.
+        if word.contains(['\'', '"', '\\']) || is_io_number(token, next) {
+            return Ok(None);
+        }
+
+        if expanding.iter().any(|name| name == word) {
+            return Ok(None);
+        }
+
+        let Some(value) = self.aliases.get(word.as_str()) else {
+            return Ok(None);
+        };
+
+        let replacement =
+            crate::tokenizer::tokenize_str_with_options(value, &self.tokenizer_options).map_err(
+                |inner| RecoveredParseError {
+                    error: error::ParseError::Tokenizing {
+                        inner,
+                        position: Some(location.start.clone()),
+                    },
+                    location: location.clone(),
+                },
+            )?;
+
+        Ok(Some((word.clone(), replacement)))
+    }
+
+    /// Returns whether no tokens are currently being passed over without examination.
+    fn passing_over_nothing(&self) -> bool {
+        self.skip_tokens == 0
+            && self.skip_parens.is_none()
+            && !self.in_extended_test
+            && !matches!(
+                self.cases.last(),
+                Some(CaseState::Subject | CaseState::Patterns)
+            )
+    }
+
+    /// Updates the scan state to account for the given token, which won't be expanded.
+    fn observe(&mut self, token: &Token, next: Option<&Token>) {
+        if self.skip_tokens > 0 {
+            self.skip_tokens -= 1;
+            return;
+        }
+
+        if let Some(depth) = self.skip_parens.as_mut() {
+            match token.to_str() {
+                "(" if matches!(token, Token::Operator(..)) => *depth += 1,
+                ")" if matches!(token, Token::Operator(..)) => {
+                    *depth -= 1;
+                    if *depth == 0 {
+                        self.skip_parens = None;
+                    }
+                }
+                _ => (),
+            }
+            return;
+        }
+
+        if self.in_extended_test {
+            if matches!(token, Token::Word(w, _) if w == "]]") {
+                self.in_extended_test = false;
+            }
+            return;
+        }
+
+        match token {
+            Token::Word(word, _) => self.observe_word(word.as_str(), token, next),
+            Token::Operator(op, _) => self.observe_operator(op.as_str(), token, next),
+        }
+    }
+
+    fn observe_word(&mut self, word: &str, token: &Token, next: Option<&Token>) {
+        match self.cases.last_mut() {
+            Some(state @ CaseState::Subject) => {
+                if word == "in" {
+                    *state = CaseState::Patterns;
+                }
+                return;
+            }
+            Some(CaseState::Patterns) => {
+                if word == "esac" {
+                    self.cases.pop();
+                    self.command_position = false;
+                }
+                return;
+            }
+            _ => (),
+        }
+
+        if !self.command_position {
+            return;
+        }
+
+        // Redirections and assignments may precede the first word of a command.
+        if is_io_number(token, next) {
+            return;
+        }
+        if is_assignment_word(word) {
+            // Pass over the elements of array assignments (e.g., `arr=(a b)`).
+            if word.ends_with('=') && next.is_some_and(|next| is_adjacent_open_paren(token, next)) {
+                self.skip_parens = Some(0);
+            }
+            return;
+        }
+
+        match word {
+            // Reserved words that are followed by a command.
+            "if" | "then" | "else" | "elif" | "do" | "while" | "until" | "!" | "time" | "{" => (),
+            // The name of a function being defined isn't a command, but its body is.
+            "function" => self.skip_tokens = 1,
+            "[[" => {
+                self.in_extended_test = true;
+                self.command_position = false;
+            }
+            "case" => {
+                self.cases.push(CaseState::Subject);
+                self.command_position = false;
+            }
+            "esac" if matches!(self.cases.last(), Some(CaseState::Body)) => {
+                self.cases.pop();
+                self.command_position = false;
+            }
+            _ => self.command_position = false,
+        }
+    }
+
+    fn observe_operator(&mut self, op: &str, token: &Token, next: Option<&Token>) {
+        match self.cases.last_mut() {
+            Some(CaseState::Subject) => return,
+            Some(state @ CaseState::Patterns) => {
+                if op == ")" {
+                    *state = CaseState::Body;
+                    self.command_position = true;
+                }
+                return;
+            }
+            Some(state @ CaseState::Body) if matches!(op, ";;" | ";&" | ";;&") => {
+                *state = CaseState::Patterns;
+                self.command_position = false;
+                return;
+            }
+            _ => (),
+        }
+
+        match op {
+            // An arithmetic command (e.g., `(( x++ ))`) or arithmetic `for` loop.
+            "(" if next.is_some_and(|next| is_adjacent_open_paren(token, next)) => {
+                self.skip_parens = Some(1);
+                self.command_position = false;
+            }
+            ";" | ";;" | ";&" | ";;&" | "&" | "&&" | "||" | "|" | "|&" | "(" | ")" | "\n" => {
+                self.command_position = true;
+            }
+            // A here-document's tag is followed by its body and closing tag.
+            "<<" | "<<-" => self.skip_tokens = 3,
+            // Any other operator is a redirection, which is followed by its target.
+            _ => self.skip_tokens = 1,
+        }
+    }
+}
+
+/// Returns whether the given token is an I/O number (e.g., the `2` in `2>&1`).
+fn is_io_number(token: &Token, next: Option<&Token>) -> bool {
+    let Token::Word(word, location) = token else {
+        return false;
+    };
+
+    word.chars().all(|c| c.is_ascii_digit())
+        && matches!(next, Some(Token::Operator(op, next_location))
+            if (op.starts_with('<') || op.starts_with('>'))
+                && next_location.start.index == location.end.index)
+}
+
+/// Returns whether the given token is immediately followed by the given `(` operator.
+fn is_adjacent_open_paren(token: &Token, next: &Token) -> bool {
+    matches!(next, Token::Operator(op, next_location)
+        if op == "(" && next_location.start.index == token.location().end.index)
+}
+
+/// Returns whether the given word is an assignment (e.g., `x=1`, `x+=1`, or `x[0]=1`).
+fn is_assignment_word(word: &str) -> bool {
+    let Some((name, _)) = word.split_once('=') else {
+        return false;
+    };
+
+    let name = name.strip_suffix('+').unwrap_or(name);
+    let name = match name.strip_suffix(']').and_then(|name| name.split_once('[')) {
+        Some((name, _)) => name,
+        None => name,
+    };
+
+    parser::is_valid_name(name)
+}
+
+fn relocate(token: Token, location: TokenLocation) -> Token {
+    match token {
+        Token::Operator(op, _) => Token::Operator(op, location),
+        Token::Word(word, _) => Token::Word(word, location),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokenize_str;
+    use anyhow::Result;
+
+    fn expand(input: &str, aliases: &[(&str, &str)]) -> Result<String> {
+        let options = ParserOptions {
+            aliases: aliases
+                .iter()
+                .map(|(name, value)| ((*name).to_owned(), (*value).to_owned()))
+                .collect(),
+            ..ParserOptions::default()
+        };
+
+        let tokens = tokenize_str(input)?;
+        let expanded = expand_aliases(&tokens, &options).map_err(|e| e.error)?;
+
+        Ok(expanded
+            .iter()
+            .map(|token| token.to_str())
+            .collect::<Vec<_>>()
+            .join(" "))
+    }
+
+    #[test]
+    fn expand_command_words() -> Result<()> {
+        let aliases = [("ll", "ls -l")];
+
+        assert_eq!(expand("ll x", &aliases)?, "ls -l x");
+        assert_eq!(expand("echo ll", &aliases)?, "echo ll");
+        assert_eq!(expand("x=1 >f ll", &aliases)?, "x=1 > f ls -l");
+        assert_eq!(
+            expand("true && ll | ll", &aliases)?,
+            "true && ls -l | ls -l"
+        );
+        assert_eq!(
+            expand("if ll; then ll; fi", &aliases)?,
+            "if ls -l ; then ls -l ; fi"
+        );
+        assert_eq!(expand("'ll'; \\ll", &aliases)?, "'ll' ; \\ll");
+
+        Ok(())
+    }
+
+    #[test]
+    fn expand_nested_aliases_without_recursing() -> Result<()> {
+        let aliases = [("ls", "ls -F"), ("a", "b"), ("b", "a x")];
+
+        assert_eq!(expand("ls", &aliases)?, "ls -F");
+        assert_eq!(expand("a", &aliases)?, "a x");
+        assert_eq!(expand("b", &aliases)?, "b x");
+
+        Ok(())
+    }
+
+    #[test]
+    fn expand_word_after_trailing_blank() -> Result<()> {
+        let aliases = [
+            ("cmd", "echo "),
+            ("other", "replaced "),
+            ("last", "also-replaced"),
+        ];
+
+        assert_eq!(
+            expand("cmd other last last", &aliases)?,
+            "echo replaced also-replaced last"
+        );
+        assert_eq!(expand("echo other last", &aliases)?, "echo other last");
+
+        Ok(())
+    }
+
+    #[test]
+    fn skip_words_not_in_command_position() -> Result<()> {
+        let aliases = [("x", "y")];
+
+        assert_eq!(
+            expand("case x in x) x;; (x|x) x;; esac", &aliases)?,
+            "case x in x ) y ;; ( x | x ) y ;; esac"
+        );
+        assert_eq!(expand("[[ x && x ]]; x", &aliases)?, "[[ x && x ]] ; y");
+        assert_eq!(expand("a=(x x); x", &aliases)?, "a= ( x x ) ; y");
+        assert_eq!(
+            expand("for x in x; do x; done", &aliases)?,
+            "for x in x ; do y ; done"
+        );
+        assert_eq!(expand("function x { x; }", &aliases)?, "function x { y ; }");
+
+        Ok(())
+    }
+
+    #[test]
+    fn report_aliases_that_fail_to_tokenize() -> Result<()> {
+        let options = ParserOptions {
+            aliases: [(String::from("x"), String::from("echo 'a"))].into(),
+            ..ParserOptions::default()
+        };
+
+        let tokens = tokenize_str("true; x")?;
+        let Err(recovered) = expand_aliases(&tokens, &options) else {
+            panic!("expected an error");
+        };
+
+        assert!(matches!(
+            recovered.error,
+            error::ParseError::Tokenizing {
+                inner: crate::tokenizer::TokenizerError::UnterminatedSingleQuote(..),
+                ..
+            }
+        ));
+        assert_eq!(recovered.location.start.column, 7);
+
+        Ok(())
+    }
+}
@@ -8,12 +8,19 @@ use crate::tokenizer;
 const DISPLAY_INDENT: &str = "    ";
 
 /// Represents a complete shell program.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Default)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[cfg_attr(test, derive(PartialEq, Eq))]
 pub struct Program {
     /// A sequence of complete shell commands.
     pub complete_commands: Vec<CompleteCommand>,
+    /// The location in the source of each of the complete commands, in the same order
+    /// as `complete_commands`. May be empty if the program wasn't produced by parsing.
+    pub complete_command_locations: Vec<tokenizer::TokenLocation>,
+    /// Comments and whitespace trivia captured from the source, in source order. Only
+    /// populated when trivia capture is enabled in the parser's options.
+    pub trivia: Vec<AttachedTrivia>,
 }
 
 impl Display for Program {
@@ -25,6 +32,33 @@ impl Display for Program {
     }
 }
 
+/// Represents trivia captured from a program's source, along with the node it's attached to.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+pub struct AttachedTrivia {
+    /// The trivia itself.
+    pub trivia: tokenizer::Trivia,
+    /// The node the trivia is attached to.
+    pub attachment: TriviaAttachment,
+}
+
+/// Identifies where in a program a piece of trivia is attached.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum TriviaAttachment {
+    /// The trivia precedes the complete command with the given index.
+    Leading(usize),
+    /// The trivia is contained within the complete command with the given index.
+    Within(usize),
+    /// The trivia follows the complete command with the given index, on the same line.
+    Trailing(usize),
+    /// The trivia isn't attached to any complete command (e.g., it follows the last one).
+    Dangling,
+}
+
 /// Represents a complete shell command.
 pub type CompleteCommand = CompoundList;
 
@@ -34,6 +68,7 @@ pub type CompleteCommandItem = CompoundListItem;
 /// Indicates whether the preceding command is executed synchronously or asynchronously.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[cfg_attr(test, derive(PartialEq, Eq))]
 pub enum SeparatorOperator {
     /// The preceding command is executed asynchronously.
@@ -54,6 +89,7 @@ impl Display for SeparatorOperator {
 /// Represents a sequence of command pipelines connected by boolean operators.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[cfg_attr(test, derive(PartialEq, Eq))]
 pub struct AndOrList {
     /// The first command pipeline.
@@ -77,6 +113,7 @@ impl Display for AndOrList {
 /// succeeding pipeline.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[cfg_attr(test, derive(PartialEq, Eq))]
 pub enum AndOr {
     /// Boolean AND operator; the embedded pipeline is only to be executed if the
@@ -99,6 +136,7 @@ impl Display for AndOr {
 /// The type of timing requested for a pipeline.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[cfg_attr(test, derive(PartialEq, Eq))]
 pub enum PipelineTimed {
     /// The pipeline should be timed with bash-like output.
@@ -111,6 +149,7 @@ pub enum PipelineTimed {
 /// to the command that follows it.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[cfg_attr(test, derive(PartialEq, Eq))]
 pub struct Pipeline {
     /// Indicates whether the pipeline's execution should be timed with reported
@@ -121,6 +160,8 @@ pub struct Pipeline {
     pub bang: bool,
     /// The sequence of commands in the pipeline.
     pub seq: Vec<Command>,
+    /// The 1-based line number in the source at which the pipeline starts.
+    pub line: i32,
 }
 
 impl Display for Pipeline {
@@ -142,6 +183,7 @@ impl Display for Pipeline {
 /// Represents a shell command.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[cfg_attr(test, derive(PartialEq, Eq))]
 pub enum Command {
     /// A simple command, directly invoking an external command, a built-in command,
@@ -177,6 +219,7 @@ impl Display for Command {
 /// Represents a compound command, potentially made up of multiple nested commands.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[cfg_attr(test, derive(PartialEq, Eq))]
 pub enum CompoundCommand {
     /// An arithmetic command, evaluating an arithmetic expression.
@@ -229,6 +272,7 @@ impl Display for CompoundCommand {
 /// An arithmetic command, evaluating an arithmetic expression.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[cfg_attr(test, derive(PartialEq, Eq))]
 pub struct ArithmeticCommand {
     /// The raw, unparsed and unexpanded arithmetic expression.
@@ -244,6 +288,7 @@ impl Display for ArithmeticCommand {
 /// A subshell, which executes commands in a subshell.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[cfg_attr(test, derive(PartialEq, Eq))]
 pub struct SubshellCommand(pub CompoundList);
 
@@ -258,6 +303,7 @@ impl Display for SubshellCommand {
 /// A for clause, which loops over a set of values.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[cfg_attr(test, derive(PartialEq, Eq))]
 pub struct ForClauseCommand {
     /// The name of the iterator variable.
@@ -291,6 +337,7 @@ impl Display for ForClauseCommand {
 /// An arithmetic for clause, which loops until an arithmetic condition is reached.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[cfg_attr(test, derive(PartialEq, Eq))]
 pub struct ArithmeticForClauseCommand {
     /// Optionally, the initializer expression evaluated before the first iteration of the loop.
@@ -333,6 +380,7 @@ impl Display for ArithmeticForClauseCommand {
 /// pattern-based filters.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[cfg_attr(test, derive(PartialEq, Eq))]
 pub struct CaseClauseCommand {
     /// The value being matched on.
@@ -355,6 +403,7 @@ impl Display for CaseClauseCommand {
 /// A sequence of commands.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[cfg_attr(test, derive(PartialEq, Eq))]
 pub struct CompoundList(pub Vec<CompoundListItem>);
 
@@ -383,6 +432,7 @@ impl Display for CompoundList {
 /// An element of a compound command list.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[cfg_attr(test, derive(PartialEq, Eq))]
 pub struct CompoundListItem(pub AndOrList, pub SeparatorOperator);
 
@@ -397,6 +447,7 @@ impl Display for CompoundListItem {
 /// An if clause, which conditionally executes a command.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[cfg_attr(test, derive(PartialEq, Eq))]
 pub struct IfClauseCommand {
     /// The command whose execution result is inspected.
@@ -431,6 +482,7 @@ impl Display for IfClauseCommand {
 /// Represents the `else` clause of a conditional command.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[cfg_attr(test, derive(PartialEq, Eq))]
 pub struct ElseClause {
     /// If present, the condition that must be met for this `else` clause to be executed.
@@ -459,6 +511,7 @@ impl Display for ElseClause {
 /// An individual matching case item in a case clause.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[cfg_attr(test, derive(PartialEq, Eq))]
 pub struct CaseItem {
     /// The patterns that select this case branch.
@@ -491,6 +544,7 @@ impl Display for CaseItem {
 /// Describes the action to take after executing the body command of a case clause.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[cfg_attr(test, derive(PartialEq, Eq))]
 pub enum CaseItemPostAction {
     /// The containing case should be exited.
@@ -516,6 +570,7 @@ impl Display for CaseItemPostAction {
 /// A while or until clause, whose looping is controlled by a condition.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[cfg_attr(test, derive(PartialEq, Eq))]
 pub struct WhileOrUntilClauseCommand(pub CompoundList, pub DoGroupCommand);
 
@@ -528,6 +583,7 @@ impl Display for WhileOrUntilClauseCommand {
 /// Encapsulates the definition of a shell function.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[cfg_attr(test, derive(PartialEq, Eq))]
 pub struct FunctionDefinition {
     /// The name of the function.
@@ -540,15 +596,14 @@ pub struct FunctionDefinition {
 
 impl Display for FunctionDefinition {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        writeln!(f, "{} () ", self.fname)?;
-        write!(f, "{}", self.body)?;
-        Ok(())
+        write!(f, "{}", crate::format::format_function_definition(self))
     }
 }
 
 /// Encapsulates the body of a function definition.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[cfg_attr(test, derive(PartialEq, Eq))]
 pub struct FunctionBody(pub CompoundCommand, pub Option<RedirectList>);
 
@@ -566,6 +621,7 @@ impl Display for FunctionBody {
 /// A brace group, which groups commands together.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[cfg_attr(test, derive(PartialEq, Eq))]
 pub struct BraceGroupCommand(pub CompoundList);
 
@@ -583,6 +639,7 @@ impl Display for BraceGroupCommand {
 /// A do group, which groups commands together.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[cfg_attr(test, derive(PartialEq, Eq))]
 pub struct DoGroupCommand(pub CompoundList);
 
@@ -598,6 +655,7 @@ impl Display for DoGroupCommand {
 /// Represents the invocation of a simple command.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[cfg_attr(test, derive(PartialEq, Eq))]
 pub struct SimpleCommand {
     /// Optionally, a prefix to the command.
@@ -645,6 +703,7 @@ impl Display for SimpleCommand {
 /// Represents a prefix to a simple command.
 #[derive(Clone, Debug, Default)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[cfg_attr(test, derive(PartialEq, Eq))]
 pub struct CommandPrefix(pub Vec<CommandPrefixOrSuffixItem>);
 
@@ -664,6 +723,7 @@ impl Display for CommandPrefix {
 /// Represents a suffix to a simple command; a word argument, declaration, or I/O redirection.
 #[derive(Clone, Default, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[cfg_attr(test, derive(PartialEq, Eq))]
 pub struct CommandSuffix(pub Vec<CommandPrefixOrSuffixItem>);
 
@@ -683,6 +743,7 @@ impl Display for CommandSuffix {
 /// Represents the I/O direction of a process substitution.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[cfg_attr(test, derive(PartialEq, Eq))]
 pub enum ProcessSubstitutionKind {
     /// The process is read from.
@@ -703,6 +764,7 @@ impl Display for ProcessSubstitutionKind {
 /// A prefix or suffix for a simple command.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[cfg_attr(test, derive(PartialEq, Eq))]
 pub enum CommandPrefixOrSuffixItem {
     /// An I/O redirection.
@@ -731,6 +793,7 @@ impl Display for CommandPrefixOrSuffixItem {
 /// Encapsulates an assignment declaration.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[cfg_attr(test, derive(PartialEq, Eq))]
 pub struct Assignment {
     /// Name being assigned to.
@@ -754,6 +817,7 @@ impl Display for Assignment {
 /// The target of an assignment.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[cfg_attr(test, derive(PartialEq, Eq))]
 pub enum AssignmentName {
     /// A named variable.
@@ -776,6 +840,7 @@ impl Display for AssignmentName {
 /// A value being assigned to a variable.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[cfg_attr(test, derive(PartialEq, Eq))]
 pub enum AssignmentValue {
     /// A scalar (word) value.
@@ -808,6 +873,7 @@ impl Display for AssignmentValue {
 /// A list of I/O redirections to be applied to a command.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[cfg_attr(test, derive(PartialEq, Eq))]
 pub struct RedirectList(pub Vec<IoRedirect>);
 
@@ -823,6 +889,7 @@ impl Display for RedirectList {
 /// An I/O redirection.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[cfg_attr(test, derive(PartialEq, Eq))]
 pub enum IoRedirect {
     /// Redirection to a file.
@@ -891,6 +958,7 @@ impl Display for IoRedirect {
 /// Kind of file I/O redirection.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[cfg_attr(test, derive(PartialEq, Eq))]
 pub enum IoFileRedirectKind {
     /// Read (`<`).
@@ -926,6 +994,7 @@ impl Display for IoFileRedirectKind {
 /// Target for an I/O file redirection.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[cfg_attr(test, derive(PartialEq, Eq))]
 pub enum IoFileRedirectTarget {
     /// Path to a file.
@@ -952,6 +1021,7 @@ impl Display for IoFileRedirectTarget {
 /// Represents an I/O here document.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[cfg_attr(test, derive(PartialEq, Eq))]
 pub struct IoHereDocument {
     /// Whether to remove leading tabs from the here document.
@@ -966,6 +1036,7 @@ pub struct IoHereDocument {
 
 /// A (non-extended) test expression.
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum TestExpr {
     /// Always evaluates to false.
     False,
@@ -1003,6 +1074,7 @@ impl Display for TestExpr {
 /// An extended test expression.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[cfg_attr(test, derive(PartialEq, Eq))]
 pub enum ExtendedTestExpr {
     /// Logical AND operation on two nested expressions.
@@ -1047,6 +1119,7 @@ impl Display for ExtendedTestExpr {
 /// A unary predicate usable in an extended test expression.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[cfg_attr(test, derive(PartialEq, Eq))]
 pub enum UnaryPredicate {
     /// Computes if the operand is a path to an existing file.
@@ -1136,6 +1209,7 @@ impl Display for UnaryPredicate {
 /// A binary predicate usable in an extended test expression.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[cfg_attr(test, derive(PartialEq, Eq))]
 pub enum BinaryPredicate {
     /// Computes if two files refer to the same device and inode numbers.
@@ -1195,6 +1269,7 @@ impl Display for BinaryPredicate {
 /// Represents a shell word.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[cfg_attr(test, derive(PartialEq, Eq))]
 pub struct Word {
     /// Raw text of the word.
@@ -1243,6 +1318,7 @@ impl Word {
 /// Encapsulates an unparsed arithmetic expression.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[cfg_attr(test, derive(PartialEq, Eq))]
 pub struct UnexpandedArithmeticExpr {
     /// The raw text of the expression.
@@ -1257,6 +1333,7 @@ impl Display for UnexpandedArithmeticExpr {
 
 /// An arithmetic expression.
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum ArithmeticExpr {
     /// A literal integer value.
     Literal(i64),
@@ -1362,6 +1439,7 @@ impl Display for ArithmeticExpr {
 /// A binary arithmetic operator.
 #[derive(Clone, Copy, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum BinaryOperator {
     /// Exponentiation (e.g., `x ** y`).
     Power,
@@ -1435,6 +1513,7 @@ impl Display for BinaryOperator {
 /// A unary arithmetic operator.
 #[derive(Clone, Copy, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum UnaryOperator {
     /// Unary plus (e.g., `+x`).
     UnaryPlus,
@@ -1460,6 +1539,7 @@ impl Display for UnaryOperator {
 /// A unary arithmetic assignment operator.
 #[derive(Clone, Copy, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum UnaryAssignmentOperator {
     /// Prefix increment (e.g., `++x`).
     PrefixIncrement,
@@ -1485,6 +1565,7 @@ impl Display for UnaryAssignmentOperator {
 /// Identifies the target of an arithmetic assignment expression.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum ArithmeticTarget {
     /// A named variable.
     Variable(String),
@@ -0,0 +1,219 @@
+//! Identification of the construct left open by incomplete input, so that interactive
+//! shells can indicate what's expected on a continuation line.
+
+use crate::error::ParseError;
+use crate::tokenizer::{Token, TokenizerError};
+
+/// A construct that's opened but not terminated by the end of incomplete input.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum UnterminatedConstruct {
+    /// A single-quoted string.
+    SingleQuote,
+    /// A double-quoted string.
+    DoubleQuote,
+    /// A backquoted command substitution.
+    Backquote,
+    /// A `$(...)` command substitution.
+    CommandSubstitution,
+    /// A `${...}` parameter expansion.
+    ParameterExpansion,
+    /// An extended glob pattern.
+    ExtendedGlob,
+    /// An escape sequence (e.g., a backslash at the end of the line).
+    EscapeSequence,
+    /// A here document; holds the tag(s) that will terminate it.
+    HereDocument(String),
+    /// An `if` command.
+    If,
+    /// A `for` loop.
+    For,
+    /// A `select` command.
+    Select,
+    /// A `while` loop.
+    While,
+    /// An `until` loop.
+    Until,
+    /// A `case` command.
+    Case,
+    /// A `{ ... }` brace group.
+    BraceGroup,
+    /// A `( ... )` subshell.
+    Subshell,
+    /// A pipeline whose last command is missing.
+    Pipeline,
+    /// An `&&` list whose last command is missing.
+    AndList,
+    /// An `||` list whose last command is missing.
+    OrList,
+}
+
+impl UnterminatedConstruct {
+    /// Returns a short name for the construct, suitable for display in a prompt.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::SingleQuote => "quote",
+            Self::DoubleQuote => "dquote",
+            Self::Backquote => "bquote",
+            Self::CommandSubstitution => "cmdsubst",
+            Self::ParameterExpansion => "braceparam",
+            Self::ExtendedGlob => "extglob",
+            Self::EscapeSequence => "escape",
+            Self::HereDocument(_) => "heredoc",
+            Self::If => "if",
+            Self::For => "for",
+            Self::Select => "select",
+            Self::While => "while",
+            Self::Until => "until",
+            Self::Case => "case",
+            Self::BraceGroup => "cursh",
+            Self::Subshell => "subsh",
+            Self::Pipeline => "pipe",
+            Self::AndList => "cmdand",
+            Self::OrList => "cmdor",
+        }
+    }
+
+    /// Identifies the innermost construct left open by input that failed to parse
+    /// because it's incomplete. Returns `None` if the error doesn't indicate incomplete
+    /// input, or if the open construct can't be identified.
+    ///
+    /// # Arguments
+    ///
+    /// * `error` - The error that resulted from parsing the input.
+    /// * `tokens` - The tokens of the input; only consulted if the input was
+    ///   successfully tokenized.
+    pub fn from_parse_error(error: &ParseError, tokens: &[Token]) -> Option<Self> {
+        match error {
+            ParseError::Tokenizing { inner, .. } => Self::from_tokenizer_error(inner),
+            ParseError::ParsingAtEndOfInput => Self::from_tokens(tokens),
+            ParseError::ParsingNearToken(_) => None,
+        }
+    }
+
+    fn from_tokenizer_error(error: &TokenizerError) -> Option<Self> {
+        match error {
+            TokenizerError::UnterminatedSingleQuote(_) => Some(Self::SingleQuote),
+            TokenizerError::UnterminatedDoubleQuote(_) => Some(Self::DoubleQuote),
+            TokenizerError::UnterminatedBackquote(_) => Some(Self::Backquote),
+            TokenizerError::UnterminatedCommandSubstitution => Some(Self::CommandSubstitution),
+            TokenizerError::UnterminatedVariable => Some(Self::ParameterExpansion),
+            TokenizerError::UnterminatedExtendedGlob(_) => Some(Self::ExtendedGlob),
+            TokenizerError::UnterminatedEscapeSequence => Some(Self::EscapeSequence),
+            TokenizerError::UnterminatedHereDocuments(tags, _) => {
+                Some(Self::HereDocument(tags.clone()))
+            }
+            _ => None,
+        }
+    }
+
+    fn from_tokens(tokens: &[Token]) -> Option<Self> {
+        // A trailing operator awaiting its next command is the innermost construct.
+        let last = tokens
+            .iter()
+            .rev()
+            .find(|token| !matches!(token, Token::Operator(o, _) if o == "\n"));
+        if let Some(Token::Operator(o, _)) = last {
+            match o.as_str() {
+                "|" | "|&" => return Some(Self::Pipeline),
+                "&&" => return Some(Self::AndList),
+                "||" => return Some(Self::OrList),
+                _ => (),
+            }
+        }
+
+        let mut open = vec![];
+        let mut command_position = true;
+
+        for token in tokens {
+            let in_command_position = command_position;
+
+            match token {
+                Token::Operator(o, _) => {
+                    command_position = true;
+
+                    match o.as_str() {
+                        "(" => open.push(Self::Subshell),
+                        // N.B. Within a case command, a closing parenthesis ends a pattern.
+                        ")" if open.last() == Some(&Self::Subshell) => {
+                            open.pop();
+                        }
+                        _ => (),
+                    }
+                }
+                Token::Word(word, _) => {
+                    command_position = false;
+
+                    if !in_command_position {
+                        continue;
+                    }
+
+                    match word.as_str() {
+                        "if" => open.push(Self::If),
+                        "for" => open.push(Self::For),
+                        "select" => open.push(Self::Select),
+                        "while" => open.push(Self::While),
+                        "until" => open.push(Self::Until),
+                        "case" => open.push(Self::Case),
+                        "{" => {
+                            open.push(Self::BraceGroup);
+                            command_position = true;
+                        }
+                        "fi" | "done" | "esac" | "}" => {
+                            open.pop();
+                        }
+                        "then" | "else" | "elif" | "do" | "!" => command_position = true,
+                        _ => (),
+                    }
+                }
+            }
+        }
+
+        open.pop()
+    }
+}
+
+impl std::fmt::Display for UnterminatedConstruct {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{IncrementalParser, ParserOptions, SourceInfo};
+
+    fn construct_for(input: &str) -> Option<UnterminatedConstruct> {
+        let mut parser = IncrementalParser::new();
+        let error = parser
+            .parse(input, &ParserOptions::default(), &SourceInfo::default())
+            .err()?;
+        parser.unterminated_construct(&error)
+    }
+
+    #[test]
+    fn identifies_constructs() {
+        assert_eq!(
+            construct_for("echo 'hi\n"),
+            Some(UnterminatedConstruct::SingleQuote)
+        );
+        assert_eq!(
+            construct_for("cat <<EOF\nbody\n"),
+            Some(UnterminatedConstruct::HereDocument(String::from("EOF")))
+        );
+        assert_eq!(
+            construct_for("if true; then\n  while false; do :; done\n"),
+            Some(UnterminatedConstruct::If)
+        );
+        assert_eq!(
+            construct_for("case x in\n  a) (echo a) ;;\n"),
+            Some(UnterminatedConstruct::Case)
+        );
+        assert_eq!(construct_for("(\n"), Some(UnterminatedConstruct::Subshell));
+        assert_eq!(
+            construct_for("{ echo a |\n"),
+            Some(UnterminatedConstruct::Pipeline)
+        );
+        assert_eq!(construct_for("echo )\n"), None);
+    }
+}
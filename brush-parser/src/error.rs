@@ -22,6 +22,18 @@ pub enum ParseError {
     },
 }
 
+impl ParseError {
+    /// Returns whether the error indicates that the input ended before a command was
+    /// complete, such that more input could resolve it.
+    pub fn is_incomplete(&self) -> bool {
+        match self {
+            Self::ParsingAtEndOfInput => true,
+            Self::Tokenizing { inner, .. } => inner.is_incomplete(),
+            Self::ParsingNearToken(_) => false,
+        }
+    }
+}
+
 /// Represents an error that occurred while parsing a word.
 #[derive(Debug, thiserror::Error)]
 pub enum WordParseError {
@@ -56,6 +68,14 @@ pub enum TestCommandParseError {
     /// An error occurred while parsing a test command.
     #[error("failed to parse test command")]
     TestCommand(peg::error::ParseError<usize>),
+
+    /// A unary operator was expected but not found.
+    #[error("{0}: unary operator expected")]
+    UnaryOperatorExpected(String),
+
+    /// A binary operator was expected but not found.
+    #[error("{0}: binary operator expected")]
+    BinaryOperatorExpected(String),
 }
 
 pub(crate) fn convert_peg_parse_error(
@@ -0,0 +1,1311 @@
+//! Implements a source-to-source formatter (pretty-printer) for parsed shell programs, as
+//! well as the canonical formatting bash uses when displaying function definitions.
+
+use crate::ast;
+use crate::tokenizer;
+
+/// Options that control how shell programs are formatted.
+#[derive(Clone, Debug)]
+pub struct FormatOptions {
+    /// Number of spaces to use for each level of indentation.
+    pub indent_width: usize,
+    /// Preferred maximum line width; lines that would exceed this width are broken
+    /// across multiple lines where the syntax allows for it.
+    pub max_line_width: usize,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        Self {
+            indent_width: 4,
+            max_line_width: 100,
+        }
+    }
+}
+
+/// Formats the given program, returning the formatted source text. Comments are
+/// preserved if the program was parsed with trivia capture enabled; comments found
+/// within a complete command are moved to precede it.
+///
+/// # Arguments
+///
+/// * `program` - The program to format.
+/// * `options` - Options controlling formatting.
+pub fn format_program(program: &ast::Program, options: &FormatOptions) -> String {
+    let comments = || {
+        program
+            .trivia
+            .iter()
+            .filter(|t| t.trivia.kind == tokenizer::TriviaKind::Comment)
+    };
+
+    let mut formatter = Formatter::new(options);
+    for (i, complete_command) in program.complete_commands.iter().enumerate() {
+        let mut trailing_comment = None;
+        for comment in comments() {
+            match comment.attachment {
+                ast::TriviaAttachment::Leading(j) | ast::TriviaAttachment::Within(j) if j == i => {
+                    formatter.write_comment_line(comment.trivia.text.as_str());
+                }
+                ast::TriviaAttachment::Trailing(j) if j == i => {
+                    trailing_comment = Some(comment.trivia.text.as_str());
+                }
+                _ => (),
+            }
+        }
+
+        for (j, item) in complete_command.0.iter().enumerate() {
+            formatter.write_compound_list_item(item);
+            if j + 1 == complete_command.0.len() {
+                if let Some(comment) = trailing_comment {
+                    formatter.output.push(' ');
+                    formatter.output.push_str(comment);
+                }
+            }
+            formatter.end_line();
+        }
+    }
+
+    for comment in comments().filter(|t| t.attachment == ast::TriviaAttachment::Dangling) {
+        formatter.write_comment_line(comment.trivia.text.as_str());
+    }
+
+    formatter.output
+}
+
+/// Formats the given function definition in the canonical layout bash uses when
+/// displaying functions (e.g., in the output of `declare -f` or `type`). The output
+/// depends only on the structure of the definition, not on how its source was laid out.
+///
+/// # Arguments
+///
+/// * `function_definition` - The function definition to format.
+pub fn format_function_definition(function_definition: &ast::FunctionDefinition) -> String {
+    let mut formatter = CanonicalFormatter::default();
+    formatter.write_function_definition(function_definition, false);
+    formatter.output
+}
+
+/// Formats the body of a function definition (including any redirections applied to it)
+/// in the same canonical layout as `format_function_definition`.
+///
+/// # Arguments
+///
+/// * `body` - The function body to format.
+pub fn format_function_body(body: &ast::FunctionBody) -> String {
+    let mut formatter = CanonicalFormatter::default();
+    formatter.write_function_body(body);
+    formatter.output
+}
+
+struct Formatter<'a> {
+    options: &'a FormatOptions,
+    output: String,
+    indent_level: usize,
+    /// Column at which `output` starts; non-zero for scratch formatters.
+    column_offset: usize,
+    /// Whether lines may be broken to respect the maximum line width.
+    allow_wrapping: bool,
+    pending_here_docs: Vec<ast::IoHereDocument>,
+}
+
+impl<'a> Formatter<'a> {
+    fn new(options: &'a FormatOptions) -> Self {
+        Self {
+            options,
+            output: String::new(),
+            indent_level: 0,
+            column_offset: 0,
+            allow_wrapping: true,
+            pending_here_docs: vec![],
+        }
+    }
+
+    /// Creates a scratch formatter that continues from the current position of this one.
+    fn scratch(&self, allow_wrapping: bool) -> Formatter<'a> {
+        Formatter {
+            options: self.options,
+            output: String::new(),
+            indent_level: self.indent_level,
+            column_offset: self.current_column(),
+            allow_wrapping,
+            pending_here_docs: vec![],
+        }
+    }
+
+    /// Renders content using a scratch formatter.
+    fn render(&self, f: impl FnOnce(&mut Formatter<'a>)) -> Formatter<'a> {
+        let mut scratch = self.scratch(self.allow_wrapping);
+        f(&mut scratch);
+        scratch
+    }
+
+    /// Renders content using a scratch formatter that never breaks lines.
+    fn render_flat(&self, f: impl FnOnce(&mut Formatter<'a>)) -> Formatter<'a> {
+        let mut scratch = self.scratch(false);
+        f(&mut scratch);
+        scratch
+    }
+
+    /// Appends content previously rendered by a scratch formatter.
+    fn adopt(&mut self, mut rendered: Formatter<'a>) {
+        self.output.push_str(rendered.output.as_str());
+        self.pending_here_docs
+            .append(&mut rendered.pending_here_docs);
+    }
+
+    fn current_column(&self) -> usize {
+        if let Some(newline_index) = self.output.rfind('\n') {
+            self.output[newline_index + 1..].chars().count()
+        } else {
+            self.column_offset + self.output.chars().count()
+        }
+    }
+
+    /// Returns whether the given single-line text fits on the current line.
+    fn fits(&self, text: &str) -> bool {
+        !text.contains('\n')
+            && self.current_column() + text.chars().count() <= self.options.max_line_width
+    }
+
+    fn write_indent(&mut self, extra_levels: usize) {
+        let width = (self.indent_level + extra_levels) * self.options.indent_width;
+        self.output.push_str(" ".repeat(width).as_str());
+    }
+
+    fn end_line(&mut self) {
+        self.output.push('\n');
+
+        // Here-document bodies follow the line that references them.
+        for here_doc in std::mem::take(&mut self.pending_here_docs) {
+            self.output.push_str(here_doc.doc.value.as_str());
+            if !here_doc.doc.value.is_empty() && !here_doc.doc.value.ends_with('\n') {
+                self.output.push('\n');
+            }
+
+            self.output
+                .push_str(tokenizer::unquote_str(here_doc.here_end.value.as_str()).as_str());
+            self.output.push('\n');
+        }
+    }
+
+    fn write_comment_line(&mut self, comment: &str) {
+        self.write_indent(0);
+        self.output.push_str(comment);
+        self.end_line();
+    }
+
+    fn write_compound_list(&mut self, list: &ast::CompoundList) {
+        for item in &list.0 {
+            self.write_compound_list_item(item);
+            self.end_line();
+        }
+    }
+
+    fn write_compound_list_item(&mut self, item: &ast::CompoundListItem) {
+        let ast::CompoundListItem(ao_list, separator) = item;
+        self.write_indent(0);
+        self.write_and_or_list(ao_list);
+        if matches!(separator, ast::SeparatorOperator::Async) {
+            self.output.push_str(" &");
+        }
+    }
+
+    /// Tries to render the given list on a single line; returns `None` if any of its
+    /// items can't be represented without line breaks.
+    fn inline_compound_list(&self, list: &ast::CompoundList) -> Option<Formatter<'a>> {
+        let mut inline = self.scratch(false);
+        for (i, ast::CompoundListItem(ao_list, separator)) in list.0.iter().enumerate() {
+            let item = inline.render_flat(|f| f.write_and_or_list(ao_list));
+            if item.output.contains('\n') || !item.pending_here_docs.is_empty() {
+                return None;
+            }
+
+            if i > 0 {
+                inline.output.push(' ');
+            }
+
+            inline.adopt(item);
+
+            match separator {
+                ast::SeparatorOperator::Async => inline.output.push_str(" &"),
+                ast::SeparatorOperator::Sequence if i + 1 < list.0.len() => {
+                    inline.output.push(';');
+                }
+                ast::SeparatorOperator::Sequence => (),
+            }
+        }
+
+        Some(inline)
+    }
+
+    fn write_and_or_list(&mut self, ao_list: &ast::AndOrList) {
+        // First see if the whole list fits on the current line.
+        let flat = self.render_flat(|f| f.write_joined_and_or_list(ao_list));
+        if !self.allow_wrapping || self.fits(flat.output.as_str()) {
+            self.adopt(flat);
+            return;
+        }
+
+        // If there's nothing to break up, or if it spans multiple lines anyway, leave
+        // any wrapping to its constituent pipelines.
+        if ao_list.additional.is_empty() || flat.output.contains('\n') {
+            self.write_joined_and_or_list(ao_list);
+            return;
+        }
+
+        // Break before each operator, continuing the list on an indented line.
+        self.write_pipeline(&ao_list.first);
+        self.indent_level += 1;
+        for next in &ao_list.additional {
+            let (op, pipeline) = and_or_parts(next);
+            self.output.push_str(" \\\n");
+            self.write_indent(0);
+            self.output.push_str(op);
+            self.output.push(' ');
+            self.write_pipeline(pipeline);
+        }
+        self.indent_level -= 1;
+    }
+
+    fn write_joined_and_or_list(&mut self, ao_list: &ast::AndOrList) {
+        self.write_pipeline(&ao_list.first);
+        for next in &ao_list.additional {
+            let (op, pipeline) = and_or_parts(next);
+            self.output.push(' ');
+            self.output.push_str(op);
+            self.output.push(' ');
+            self.write_pipeline(pipeline);
+        }
+    }
+
+    fn write_pipeline(&mut self, pipeline: &ast::Pipeline) {
+        match pipeline.timed {
+            Some(ast::PipelineTimed::Timed) => self.output.push_str("time "),
+            Some(ast::PipelineTimed::TimedWithPosixOutput) => self.output.push_str("time -p "),
+            None => (),
+        }
+
+        if pipeline.bang {
+            self.output.push_str("! ");
+        }
+
+        let flat = self.render_flat(|f| f.write_joined_pipeline(pipeline));
+        if !self.allow_wrapping || self.fits(flat.output.as_str()) {
+            self.adopt(flat);
+            return;
+        }
+
+        if pipeline.seq.len() == 1 || flat.output.contains('\n') {
+            self.write_joined_pipeline(pipeline);
+            return;
+        }
+
+        // Break after each pipe operator; no line continuation is needed there.
+        for (i, command) in pipeline.seq.iter().enumerate() {
+            if i > 0 {
+                self.output.push_str(" |\n");
+                self.write_indent(1);
+            }
+            self.write_command(command);
+        }
+    }
+
+    fn write_joined_pipeline(&mut self, pipeline: &ast::Pipeline) {
+        for (i, command) in pipeline.seq.iter().enumerate() {
+            if i > 0 {
+                self.output.push_str(" | ");
+            }
+            self.write_command(command);
+        }
+    }
+
+    fn write_command(&mut self, command: &ast::Command) {
+        match command {
+            ast::Command::Simple(simple_command) => self.write_simple_command(simple_command),
+            ast::Command::Compound(compound_command, redirects) => {
+                self.write_compound_command(compound_command, false);
+                if let Some(redirects) = redirects {
+                    self.write_redirect_list(redirects);
+                }
+            }
+            ast::Command::Function(function_definition) => {
+                self.write_function_definition(function_definition);
+            }
+            ast::Command::ExtendedTest(expr) => {
+                self.output.push_str("[[ ");
+                self.output.push_str(expr.to_string().as_str());
+                self.output.push_str(" ]]");
+            }
+        }
+    }
+
+    fn write_simple_command(&mut self, simple_command: &ast::SimpleCommand) {
+        let prefix_items = simple_command.prefix.iter().flat_map(|p| p.0.iter());
+        let suffix_items = simple_command.suffix.iter().flat_map(|s| s.0.iter());
+
+        let mut pieces = vec![];
+        for item in prefix_items {
+            pieces.push(self.render(|f| f.write_prefix_or_suffix_item(item)));
+        }
+        if let Some(word) = &simple_command.word_or_name {
+            pieces.push(self.render(|f| f.output.push_str(word.value.as_str())));
+        }
+        for item in suffix_items {
+            pieces.push(self.render(|f| f.write_prefix_or_suffix_item(item)));
+        }
+
+        for (i, piece) in pieces.into_iter().enumerate() {
+            if i > 0 {
+                // Wrap onto a continuation line if this piece would overflow the current one.
+                if !self.allow_wrapping
+                    || piece.output.contains('\n')
+                    || self.fits(std::format!(" {}", piece.output).as_str())
+                {
+                    self.output.push(' ');
+                } else {
+                    self.output.push_str(" \\\n");
+                    self.write_indent(1);
+                }
+            }
+
+            self.adopt(piece);
+        }
+    }
+
+    fn write_prefix_or_suffix_item(&mut self, item: &ast::CommandPrefixOrSuffixItem) {
+        match item {
+            ast::CommandPrefixOrSuffixItem::IoRedirect(redirect) => self.write_redirect(redirect),
+            ast::CommandPrefixOrSuffixItem::Word(word)
+            | ast::CommandPrefixOrSuffixItem::AssignmentWord(_, word) => {
+                self.output.push_str(word.value.as_str());
+            }
+            ast::CommandPrefixOrSuffixItem::ProcessSubstitution(kind, subshell) => {
+                self.write_process_substitution(kind, subshell);
+            }
+        }
+    }
+
+    fn write_process_substitution(
+        &mut self,
+        kind: &ast::ProcessSubstitutionKind,
+        subshell: &ast::SubshellCommand,
+    ) {
+        self.output.push_str(kind.to_string().as_str());
+        self.output.push('(');
+
+        if let Some(inline) = self.inline_compound_list(&subshell.0) {
+            self.adopt(inline);
+        } else {
+            self.output.push('\n');
+            self.indent_level += 1;
+            self.write_compound_list(&subshell.0);
+            self.indent_level -= 1;
+            self.write_indent(0);
+        }
+
+        self.output.push(')');
+    }
+
+    fn write_redirect_list(&mut self, redirects: &ast::RedirectList) {
+        for redirect in &redirects.0 {
+            self.output.push(' ');
+            self.write_redirect(redirect);
+        }
+    }
+
+    fn write_redirect(&mut self, redirect: &ast::IoRedirect) {
+        match redirect {
+            ast::IoRedirect::File(fd_num, kind, target) => {
+                if let Some(fd_num) = fd_num {
+                    self.output.push_str(fd_num.to_string().as_str());
+                }
+
+                self.output.push_str(kind.to_string().as_str());
+
+                match target {
+                    ast::IoFileRedirectTarget::Filename(word) => {
+                        self.output.push_str(word.value.as_str());
+                    }
+                    ast::IoFileRedirectTarget::Fd(fd) => {
+                        self.output.push_str(fd.to_string().as_str());
+                    }
+                    ast::IoFileRedirectTarget::ProcessSubstitution(kind, subshell) => {
+                        self.output.push(' ');
+                        self.write_process_substitution(kind, subshell);
+                    }
+                }
+            }
+            ast::IoRedirect::HereDocument(fd_num, here_doc) => {
+                if let Some(fd_num) = fd_num {
+                    self.output.push_str(fd_num.to_string().as_str());
+                }
+
+                self.output.push_str("<<");
+                if here_doc.remove_tabs {
+                    self.output.push('-');
+                }
+                self.output.push_str(here_doc.here_end.value.as_str());
+
+                self.pending_here_docs.push(here_doc.clone());
+            }
+            ast::IoRedirect::HereString(fd_num, word) => {
+                if let Some(fd_num) = fd_num {
+                    self.output.push_str(fd_num.to_string().as_str());
+                }
+
+                self.output.push_str("<<< ");
+                self.output.push_str(word.value.as_str());
+            }
+            ast::IoRedirect::OutputAndError(word, append) => {
+                self.output.push_str(if *append { "&>>" } else { "&>" });
+                self.output.push_str(word.value.as_str());
+            }
+        }
+    }
+
+    fn write_function_definition(&mut self, function_definition: &ast::FunctionDefinition) {
+        let ast::FunctionBody(body, redirects) = &function_definition.body;
+
+        self.output.push_str(function_definition.fname.as_str());
+        self.output.push_str("() ");
+        self.write_compound_command(body, true);
+
+        if let Some(redirects) = redirects {
+            self.write_redirect_list(redirects);
+        }
+    }
+
+    /// Writes the given list as the indented body of a block, leaving the output positioned at
+    /// the start of the line following it.
+    fn write_block_body(&mut self, list: &ast::CompoundList) {
+        self.end_line();
+        self.indent_level += 1;
+        self.write_compound_list(list);
+        self.indent_level -= 1;
+        self.write_indent(0);
+    }
+
+    /// Writes a condition list followed by the given keyword (e.g., `then`, `do`).
+    fn write_condition(&mut self, condition: &ast::CompoundList, keyword: &str) {
+        self.output.push(' ');
+        let inline = self.inline_compound_list(condition);
+
+        if let Some(inline) = inline.filter(|i| self.fits(i.output.as_str())) {
+            self.adopt(inline);
+            self.output.push_str("; ");
+        } else if let [ast::CompoundListItem(ao_list, ast::SeparatorOperator::Sequence)] =
+            condition.0.as_slice()
+        {
+            self.write_and_or_list(ao_list);
+            self.output.push_str("; ");
+        } else {
+            self.output.pop();
+            self.write_block_body(condition);
+        }
+
+        self.output.push_str(keyword);
+    }
+
+    #[allow(clippy::too_many_lines)]
+    fn write_compound_command(&mut self, command: &ast::CompoundCommand, always_expand: bool) {
+        match command {
+            ast::CompoundCommand::Arithmetic(arithmetic) => {
+                self.output.push_str("((");
+                self.output.push_str(arithmetic.expr.value.as_str());
+                self.output.push_str("))");
+            }
+            ast::CompoundCommand::ArithmeticForClause(for_clause) => {
+                self.output.push_str("for ((");
+                let exprs = [
+                    &for_clause.initializer,
+                    &for_clause.condition,
+                    &for_clause.updater,
+                ];
+                for (i, expr) in exprs.into_iter().enumerate() {
+                    if i > 0 {
+                        self.output.push_str("; ");
+                    }
+                    if let Some(expr) = expr {
+                        self.output.push_str(expr.value.trim());
+                    }
+                }
+                self.output.push_str(")); do");
+                self.write_block_body(&for_clause.body.0);
+                self.output.push_str("done");
+            }
+            ast::CompoundCommand::BraceGroup(ast::BraceGroupCommand(list)) => {
+                if !always_expand {
+                    if let Some(inline) = self.inline_single_item(list) {
+                        self.output.push_str("{ ");
+                        self.adopt(inline);
+                        self.output.push_str("; }");
+                        return;
+                    }
+                }
+
+                self.output.push('{');
+                self.write_block_body(list);
+                self.output.push('}');
+            }
+            ast::CompoundCommand::Subshell(ast::SubshellCommand(list)) => {
+                if !always_expand {
+                    if let Some(inline) = self.inline_single_item(list) {
+                        self.output.push('(');
+                        self.adopt(inline);
+                        self.output.push(')');
+                        return;
+                    }
+                }
+
+                self.output.push('(');
+                self.write_block_body(list);
+                self.output.push(')');
+            }
+            ast::CompoundCommand::ForClause(for_clause) => {
+                self.output.push_str("for ");
+                self.output.push_str(for_clause.variable_name.as_str());
+                if let Some(values) = &for_clause.values {
+                    self.output.push_str(" in");
+                    for value in values {
+                        self.output.push(' ');
+                        self.output.push_str(value.value.as_str());
+                    }
+                }
+                self.output.push_str("; do");
+                self.write_block_body(&for_clause.body.0);
+                self.output.push_str("done");
+            }
+            ast::CompoundCommand::CaseClause(case_clause) => {
+                self.output.push_str("case ");
+                self.output.push_str(case_clause.value.value.as_str());
+                self.output.push_str(" in");
+                self.end_line();
+
+                self.indent_level += 1;
+                for case in &case_clause.cases {
+                    self.write_indent(0);
+                    let patterns: Vec<_> = case.patterns.iter().map(|p| p.value.as_str()).collect();
+                    self.output.push_str(patterns.join(" | ").as_str());
+                    self.output.push(')');
+                    self.end_line();
+
+                    self.indent_level += 1;
+                    if let Some(cmd) = &case.cmd {
+                        self.write_compound_list(cmd);
+                    }
+                    self.write_indent(0);
+                    self.output.push_str(case.post_action.to_string().as_str());
+                    self.end_line();
+                    self.indent_level -= 1;
+                }
+                self.indent_level -= 1;
+
+                self.write_indent(0);
+                self.output.push_str("esac");
+            }
+            ast::CompoundCommand::IfClause(if_clause) => {
+                self.output.push_str("if");
+                self.write_condition(&if_clause.condition, "then");
+                self.write_block_body(&if_clause.then);
+
+                for else_clause in if_clause.elses.iter().flatten() {
+                    if let Some(condition) = &else_clause.condition {
+                        self.output.push_str("elif");
+                        self.write_condition(condition, "then");
+                    } else {
+                        self.output.push_str("else");
+                    }
+                    self.write_block_body(&else_clause.body);
+                }
+
+                self.output.push_str("fi");
+            }
+            ast::CompoundCommand::WhileClause(ast::WhileOrUntilClauseCommand(condition, body)) => {
+                self.output.push_str("while");
+                self.write_condition(condition, "do");
+                self.write_block_body(&body.0);
+                self.output.push_str("done");
+            }
+            ast::CompoundCommand::UntilClause(ast::WhileOrUntilClauseCommand(condition, body)) => {
+                self.output.push_str("until");
+                self.write_condition(condition, "do");
+                self.write_block_body(&body.0);
+                self.output.push_str("done");
+            }
+        }
+    }
+
+    /// Renders a list consisting of a single, synchronous item that fits on the current line.
+    fn inline_single_item(&self, list: &ast::CompoundList) -> Option<Formatter<'a>> {
+        if list.0.len() != 1 || !matches!(list.0[0].1, ast::SeparatorOperator::Sequence) {
+            return None;
+        }
+
+        // Leave room for the surrounding delimiters.
+        let inline = self.inline_compound_list(list)?;
+        if self.fits(std::format!("{{ {}; }}", inline.output).as_str()) {
+            Some(inline)
+        } else {
+            None
+        }
+    }
+}
+
+/// Formatter that mimics the layout bash uses when displaying commands. Unlike
+/// `Formatter`, it's not configurable and never wraps lines.
+#[derive(Default)]
+struct CanonicalFormatter {
+    output: String,
+    indent_level: usize,
+    pending_here_docs: Vec<ast::IoHereDocument>,
+}
+
+impl CanonicalFormatter {
+    const INDENT: &'static str = "    ";
+
+    fn newline(&mut self) {
+        self.output.push('\n');
+        self.output
+            .push_str(Self::INDENT.repeat(self.indent_level).as_str());
+    }
+
+    /// Writes the bodies of any here-documents referenced since the last call; returns
+    /// whether any were written.
+    fn write_pending_here_docs(&mut self) -> bool {
+        if self.pending_here_docs.is_empty() {
+            return false;
+        }
+
+        for here_doc in std::mem::take(&mut self.pending_here_docs) {
+            self.output.push('\n');
+            self.output.push_str(here_doc.doc.value.as_str());
+            if !here_doc.doc.value.is_empty() && !here_doc.doc.value.ends_with('\n') {
+                self.output.push('\n');
+            }
+
+            self.output
+                .push_str(tokenizer::unquote_str(here_doc.here_end.value.as_str()).as_str());
+            self.output.push('\n');
+        }
+
+        true
+    }
+
+    fn write_function_definition(
+        &mut self,
+        function_definition: &ast::FunctionDefinition,
+        nested: bool,
+    ) {
+        if nested {
+            self.output.push_str("function ");
+        }
+        self.output.push_str(function_definition.fname.as_str());
+        self.output.push_str(" () ");
+        self.newline();
+
+        self.write_function_body(&function_definition.body);
+    }
+
+    fn write_function_body(&mut self, body: &ast::FunctionBody) {
+        let ast::FunctionBody(body, redirects) = body;
+
+        if let ast::CompoundCommand::BraceGroup(ast::BraceGroupCommand(list)) = body {
+            self.write_brace_group(list);
+        } else {
+            // Bodies that aren't brace groups are displayed wrapped in one.
+            self.output.push_str("{ ");
+            self.indent_level += 1;
+            self.newline();
+            self.write_compound_command(body);
+            self.indent_level -= 1;
+            self.newline();
+            self.output.push('}');
+        }
+
+        if let Some(redirects) = redirects {
+            self.write_redirect_list(redirects);
+        }
+    }
+
+    /// Writes the items of the given list, each on its own line. If `terminate` is
+    /// set, the last item is followed by a `;` (as in the body of an `if` or loop).
+    fn write_compound_list(&mut self, list: &ast::CompoundList, terminate: bool) {
+        for (i, ast::CompoundListItem(ao_list, separator)) in list.0.iter().enumerate() {
+            let is_async = matches!(separator, ast::SeparatorOperator::Async);
+            let is_last = i + 1 == list.0.len();
+
+            self.write_and_or_list(ao_list);
+            if is_async {
+                self.output.push_str(" &");
+            }
+
+            // N.B. A here-document body already ends its line, so no `;` is needed.
+            let wrote_here_docs = self.write_pending_here_docs();
+            let needs_semicolon = !is_async && !wrote_here_docs;
+
+            if is_last {
+                if terminate && needs_semicolon {
+                    self.output.push(';');
+                }
+            } else if is_async && !wrote_here_docs {
+                // An asynchronous command is followed by the next one on the same line.
+                self.output.push(' ');
+            } else {
+                if needs_semicolon {
+                    self.output.push(';');
+                }
+                self.newline();
+            }
+        }
+    }
+
+    /// Writes the given list as the indented body of a block, leaving the output at the
+    /// start of the line following it.
+    fn write_block_body(&mut self, list: &ast::CompoundList) {
+        self.indent_level += 1;
+        self.newline();
+        self.write_compound_list(list, true);
+        self.indent_level -= 1;
+        self.newline();
+    }
+
+    fn write_brace_group(&mut self, list: &ast::CompoundList) {
+        self.output.push_str("{ ");
+        self.indent_level += 1;
+        self.newline();
+        self.write_compound_list(list, false);
+        self.indent_level -= 1;
+        self.newline();
+        self.output.push('}');
+    }
+
+    fn write_and_or_list(&mut self, ao_list: &ast::AndOrList) {
+        self.write_pipeline(&ao_list.first);
+        for next in &ao_list.additional {
+            let (op, pipeline) = and_or_parts(next);
+            self.output.push(' ');
+            self.output.push_str(op);
+            self.output.push(' ');
+            self.write_pipeline(pipeline);
+        }
+    }
+
+    fn write_pipeline(&mut self, pipeline: &ast::Pipeline) {
+        match pipeline.timed {
+            Some(ast::PipelineTimed::Timed) => self.output.push_str("time "),
+            Some(ast::PipelineTimed::TimedWithPosixOutput) => self.output.push_str("time -p "),
+            None => (),
+        }
+
+        if pipeline.bang {
+            self.output.push_str("! ");
+        }
+
+        for (i, command) in pipeline.seq.iter().enumerate() {
+            if i > 0 {
+                self.output.push_str(" | ");
+            }
+            self.write_command(command);
+        }
+    }
+
+    fn write_command(&mut self, command: &ast::Command) {
+        match command {
+            ast::Command::Simple(simple_command) => self.write_simple_command(simple_command),
+            ast::Command::Compound(compound_command, redirects) => {
+                self.write_compound_command(compound_command);
+                if let Some(redirects) = redirects {
+                    self.write_redirect_list(redirects);
+                }
+            }
+            ast::Command::Function(function_definition) => {
+                self.write_function_definition(function_definition, true);
+            }
+            ast::Command::ExtendedTest(expr) => {
+                self.output.push_str("[[ ");
+                self.output.push_str(expr.to_string().as_str());
+                self.output.push_str(" ]]");
+            }
+        }
+    }
+
+    fn write_simple_command(&mut self, simple_command: &ast::SimpleCommand) {
+        let is_redirect = |item: &&ast::CommandPrefixOrSuffixItem| {
+            matches!(item, ast::CommandPrefixOrSuffixItem::IoRedirect(_))
+        };
+
+        let (prefix_redirects, prefix_words): (Vec<_>, Vec<_>) = simple_command
+            .prefix
+            .iter()
+            .flat_map(|p| p.0.iter())
+            .partition(is_redirect);
+        let (suffix_redirects, suffix_words): (Vec<_>, Vec<_>) = simple_command
+            .suffix
+            .iter()
+            .flat_map(|s| s.0.iter())
+            .partition(is_redirect);
+
+        // Redirections are displayed after all words, regardless of where they appeared.
+        let mut first = true;
+        for item in prefix_words {
+            self.write_item_separator(&mut first);
+            self.write_prefix_or_suffix_item(item);
+        }
+        if let Some(word) = &simple_command.word_or_name {
+            self.write_item_separator(&mut first);
+            self.output.push_str(word.value.as_str());
+        }
+        for item in suffix_words
+            .into_iter()
+            .chain(prefix_redirects)
+            .chain(suffix_redirects)
+        {
+            self.write_item_separator(&mut first);
+            self.write_prefix_or_suffix_item(item);
+        }
+    }
+
+    fn write_item_separator(&mut self, first: &mut bool) {
+        if !*first {
+            self.output.push(' ');
+        }
+        *first = false;
+    }
+
+    fn write_prefix_or_suffix_item(&mut self, item: &ast::CommandPrefixOrSuffixItem) {
+        match item {
+            ast::CommandPrefixOrSuffixItem::IoRedirect(redirect) => self.write_redirect(redirect),
+            ast::CommandPrefixOrSuffixItem::Word(word)
+            | ast::CommandPrefixOrSuffixItem::AssignmentWord(_, word) => {
+                self.output.push_str(word.value.as_str());
+            }
+            ast::CommandPrefixOrSuffixItem::ProcessSubstitution(kind, subshell) => {
+                self.write_process_substitution(kind, subshell);
+            }
+        }
+    }
+
+    fn write_process_substitution(
+        &mut self,
+        kind: &ast::ProcessSubstitutionKind,
+        subshell: &ast::SubshellCommand,
+    ) {
+        self.output.push_str(kind.to_string().as_str());
+        self.output.push('(');
+        self.write_compound_list(&subshell.0, false);
+        self.output.push(')');
+    }
+
+    fn write_redirect_list(&mut self, redirects: &ast::RedirectList) {
+        for redirect in &redirects.0 {
+            self.output.push(' ');
+            self.write_redirect(redirect);
+        }
+    }
+
+    fn write_redirect(&mut self, redirect: &ast::IoRedirect) {
+        match redirect {
+            ast::IoRedirect::File(fd_num, kind, target) => {
+                let is_dup = matches!(
+                    kind,
+                    ast::IoFileRedirectKind::DuplicateInput
+                        | ast::IoFileRedirectKind::DuplicateOutput
+                );
+                let default_fd = if matches!(
+                    kind,
+                    ast::IoFileRedirectKind::Read
+                        | ast::IoFileRedirectKind::ReadAndWrite
+                        | ast::IoFileRedirectKind::DuplicateInput
+                ) {
+                    0
+                } else {
+                    1
+                };
+
+                match target {
+                    // Closing a descriptor is always displayed as an output duplication.
+                    ast::IoFileRedirectTarget::Filename(word) if is_dup && word.value == "-" => {
+                        self.output
+                            .push_str(std::format!("{}>&-", fd_num.unwrap_or(default_fd)).as_str());
+                    }
+                    ast::IoFileRedirectTarget::Filename(word) => {
+                        if let Some(fd_num) = fd_num {
+                            self.output.push_str(fd_num.to_string().as_str());
+                        }
+                        self.output.push_str(kind.to_string().as_str());
+                        if !is_dup {
+                            self.output.push(' ');
+                        }
+                        self.output.push_str(word.value.as_str());
+                    }
+                    ast::IoFileRedirectTarget::Fd(fd) => {
+                        self.output.push_str(
+                            std::format!("{}{kind}{fd}", fd_num.unwrap_or(default_fd)).as_str(),
+                        );
+                    }
+                    ast::IoFileRedirectTarget::ProcessSubstitution(subst_kind, subshell) => {
+                        if let Some(fd_num) = fd_num {
+                            self.output.push_str(fd_num.to_string().as_str());
+                        }
+                        self.output.push_str(kind.to_string().as_str());
+                        self.output.push(' ');
+                        self.write_process_substitution(subst_kind, subshell);
+                    }
+                }
+            }
+            ast::IoRedirect::HereDocument(fd_num, here_doc) => {
+                if let Some(fd_num) = fd_num {
+                    self.output.push_str(fd_num.to_string().as_str());
+                }
+
+                self.output.push_str("<<");
+                if here_doc.remove_tabs {
+                    self.output.push('-');
+                }
+                self.output.push_str(here_doc.here_end.value.as_str());
+
+                self.pending_here_docs.push(here_doc.clone());
+            }
+            ast::IoRedirect::HereString(fd_num, word) => {
+                if let Some(fd_num) = fd_num {
+                    self.output.push_str(fd_num.to_string().as_str());
+                }
+
+                self.output.push_str("<<< ");
+                self.output.push_str(word.value.as_str());
+            }
+            ast::IoRedirect::OutputAndError(word, append) => {
+                self.output.push_str(if *append { "&>> " } else { "&> " });
+                self.output.push_str(word.value.as_str());
+            }
+        }
+    }
+
+    fn write_compound_command(&mut self, command: &ast::CompoundCommand) {
+        match command {
+            ast::CompoundCommand::Arithmetic(arithmetic) => {
+                self.output.push_str("(( ");
+                self.output.push_str(arithmetic.expr.value.trim());
+                self.output.push_str(" ))");
+            }
+            ast::CompoundCommand::ArithmeticForClause(for_clause) => {
+                // N.B. Omitted expressions are displayed as `1`.
+                let exprs: Vec<_> = [
+                    &for_clause.initializer,
+                    &for_clause.condition,
+                    &for_clause.updater,
+                ]
+                .into_iter()
+                .map(|expr| {
+                    expr.as_ref()
+                        .map(|e| e.value.trim())
+                        .filter(|e| !e.is_empty())
+                        .unwrap_or("1")
+                })
+                .collect();
+
+                self.output.push_str("for ((");
+                self.output.push_str(exprs.join("; ").as_str());
+                self.output.push_str("))");
+                self.newline();
+                self.output.push_str("do");
+                self.write_block_body(&for_clause.body.0);
+                self.output.push_str("done");
+            }
+            ast::CompoundCommand::BraceGroup(ast::BraceGroupCommand(list)) => {
+                self.write_brace_group(list);
+            }
+            ast::CompoundCommand::Subshell(ast::SubshellCommand(list)) => {
+                self.output.push_str("( ");
+                self.write_compound_list(list, false);
+                self.output.push_str(" )");
+            }
+            ast::CompoundCommand::ForClause(for_clause) => {
+                self.output.push_str("for ");
+                self.output.push_str(for_clause.variable_name.as_str());
+                self.output.push_str(" in ");
+                if let Some(values) = &for_clause.values {
+                    let values: Vec<_> = values.iter().map(|v| v.value.as_str()).collect();
+                    self.output.push_str(values.join(" ").as_str());
+                } else {
+                    self.output.push_str("\"$@\"");
+                }
+                self.output.push(';');
+                self.newline();
+                self.output.push_str("do");
+                self.write_block_body(&for_clause.body.0);
+                self.output.push_str("done");
+            }
+            ast::CompoundCommand::CaseClause(case_clause) => {
+                self.output.push_str("case ");
+                self.output.push_str(case_clause.value.value.as_str());
+                self.output.push_str(" in ");
+
+                self.indent_level += 1;
+                for case in &case_clause.cases {
+                    self.newline();
+                    let patterns: Vec<_> = case.patterns.iter().map(|p| p.value.as_str()).collect();
+                    self.output.push_str(patterns.join(" | ").as_str());
+                    self.output.push(')');
+
+                    self.indent_level += 1;
+                    match &case.cmd {
+                        Some(cmd) if !cmd.0.is_empty() => {
+                            self.newline();
+                            self.write_compound_list(cmd, false);
+                        }
+                        _ => self.output.push('\n'),
+                    }
+                    self.indent_level -= 1;
+
+                    self.newline();
+                    self.output.push_str(case.post_action.to_string().as_str());
+                }
+                self.indent_level -= 1;
+
+                self.newline();
+                self.output.push_str("esac");
+            }
+            ast::CompoundCommand::IfClause(if_clause) => {
+                let elses = if_clause.elses.as_deref().unwrap_or_default();
+                self.write_if_clause(&if_clause.condition, &if_clause.then, elses);
+            }
+            ast::CompoundCommand::WhileClause(ast::WhileOrUntilClauseCommand(condition, body)) => {
+                self.write_loop("while", condition, &body.0);
+            }
+            ast::CompoundCommand::UntilClause(ast::WhileOrUntilClauseCommand(condition, body)) => {
+                self.write_loop("until", condition, &body.0);
+            }
+        }
+    }
+
+    /// Writes an `if` clause; `elif` clauses are displayed as nested `if` clauses within
+    /// an `else` clause.
+    fn write_if_clause(
+        &mut self,
+        condition: &ast::CompoundList,
+        then: &ast::CompoundList,
+        elses: &[ast::ElseClause],
+    ) {
+        self.output.push_str("if ");
+        self.write_compound_list(condition, true);
+        self.output.push_str(" then");
+        self.write_block_body(then);
+
+        if let Some((else_clause, remaining_elses)) = elses.split_first() {
+            self.output.push_str("else");
+            if let Some(condition) = &else_clause.condition {
+                self.indent_level += 1;
+                self.newline();
+                self.write_if_clause(condition, &else_clause.body, remaining_elses);
+                self.output.push(';');
+                self.indent_level -= 1;
+                self.newline();
+            } else {
+                self.write_block_body(&else_clause.body);
+            }
+        }
+
+        self.output.push_str("fi");
+    }
+
+    fn write_loop(
+        &mut self,
+        keyword: &str,
+        condition: &ast::CompoundList,
+        body: &ast::CompoundList,
+    ) {
+        self.output.push_str(keyword);
+        self.output.push(' ');
+        self.write_compound_list(condition, true);
+        self.output.push_str(" do");
+        self.write_block_body(body);
+        self.output.push_str("done");
+    }
+}
+
+fn and_or_parts(and_or: &ast::AndOr) -> (&'static str, &ast::Pipeline) {
+    match and_or {
+        ast::AndOr::And(pipeline) => ("&&", pipeline),
+        ast::AndOr::Or(pipeline) => ("||", pipeline),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Parser, ParserOptions, SourceInfo};
+    use anyhow::Result;
+    use pretty_assertions::assert_eq;
+
+    fn format_str(input: &str, options: &FormatOptions) -> Result<String> {
+        let parser_options = ParserOptions {
+            capture_trivia: true,
+            ..ParserOptions::default()
+        };
+        let mut parser = Parser::new(
+            std::io::BufReader::new(input.as_bytes()),
+            &parser_options,
+            &SourceInfo::default(),
+        );
+        let program = parser.parse()?;
+        Ok(format_program(&program, options))
+    }
+
+    #[test]
+    fn format_compound_commands() -> Result<()> {
+        let input = r"if true;then echo hi;elif false
+then :;else echo bye;fi
+for x in a b;do echo $x;done
+while false;do :;done
+f() { echo body; }
+";
+        let expected = r"if true; then
+    echo hi
+elif false; then
+    :
+else
+    echo bye
+fi
+for x in a b; do
+    echo $x
+done
+while false; do
+    :
+done
+f() {
+    echo body
+}
+";
+
+        assert_eq!(format_str(input, &FormatOptions::default())?, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn format_case_and_groups() -> Result<()> {
+        let input = r"case $x in a|b) echo ab;; *) echo other;;esac
+{ echo grouped; } >out.txt
+( cd /tmp && ls )
+";
+        let expected = r"case $x in
+  a | b)
+    echo ab
+    ;;
+  *)
+    echo other
+    ;;
+esac
+{ echo grouped; } >out.txt
+(cd /tmp && ls)
+";
+
+        let options = FormatOptions {
+            indent_width: 2,
+            ..FormatOptions::default()
+        };
+
+        assert_eq!(format_str(input, &options)?, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn format_long_lines() -> Result<()> {
+        let input = "echo aaaa bbbb cccc && echo dddd | cat\n";
+        let expected = "echo aaaa bbbb cccc \\\n    && echo dddd |\n        cat\n";
+
+        let options = FormatOptions {
+            max_line_width: 20,
+            ..FormatOptions::default()
+        };
+
+        assert_eq!(format_str(input, &options)?, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn format_here_doc() -> Result<()> {
+        let input = "cat <<'EOF' | wc -l\nline 1\nline 2\nEOF\necho done\n";
+        let expected = "cat <<'EOF' | wc -l\nline 1\nline 2\nEOF\necho done\n";
+
+        assert_eq!(format_str(input, &FormatOptions::default())?, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn format_comments() -> Result<()> {
+        let input = r"#!/bin/bash
+# leading
+echo   hi    # trailing
+for x in a;do
+# within
+echo $x;done
+# dangling
+";
+        let expected = r"#!/bin/bash
+# leading
+echo hi # trailing
+# within
+for x in a; do
+    echo $x
+done
+# dangling
+";
+
+        assert_eq!(format_str(input, &FormatOptions::default())?, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn format_function_definition_like_bash() -> Result<()> {
+        let input = r"f() {
+  echo a; echo b &
+  if true; then echo t; elif false; then :; else echo e; fi
+  for x in 1 2; do echo $x; done
+  case $x in a|b) echo ab;; *) ;; esac
+  ( a; b ) 2>&1
+  echo x >&2 2>/dev/null
+}
+";
+        let expected = "f () \n{ \n    echo a;
+    echo b & if true; then
+        echo t;
+    else
+        if false; then
+            :;
+        else
+            echo e;
+        fi;
+    fi;
+    for x in 1 2;
+    do
+        echo $x;
+    done;
+    case $x in \n        a | b)
+            echo ab
+        ;;
+        *)
+
+        ;;
+    esac;
+    ( a;
+    b ) 2>&1;
+    echo x 1>&2 2> /dev/null
+}";
+
+        let mut parser = Parser::new(
+            std::io::BufReader::new(input.as_bytes()),
+            &ParserOptions::default(),
+            &SourceInfo::default(),
+        );
+        let program = parser.parse()?;
+        let ast::CompoundListItem(ao_list, _) = &program.complete_commands[0].0[0];
+        let ast::Command::Function(function_definition) = &ao_list.first.seq[0] else {
+            anyhow::bail!("expected function definition");
+        };
+
+        assert_eq!(format_function_definition(function_definition), expected);
+        Ok(())
+    }
+}
@@ -0,0 +1,245 @@
+//! Incremental tokenization and parsing of input that's repeatedly edited, as with a
+//! command line being typed interactively.
+
+use crate::ast;
+use crate::continuation::UnterminatedConstruct;
+use crate::error;
+use crate::parser::{self, ParserOptions, SourceInfo};
+use crate::tokenizer::{SourcePosition, Token, TokenEndReason, Tokenizer};
+
+/// Tokenizes and parses successive revisions of an input, reusing the tokens of the
+/// lines preceding the first edit instead of re-tokenizing the whole input each time.
+/// Trivia are never captured, regardless of the options used.
+#[derive(Default)]
+pub struct IncrementalParser {
+    /// Options used to tokenize the input most recently seen.
+    options: ParserOptions,
+    /// The input most recently seen.
+    input: String,
+    /// Tokens produced from the input. If tokenizing failed, these may be incomplete.
+    tokens: Vec<Token>,
+    /// Points in the input at which tokenizing can be resumed, ordered by position.
+    checkpoints: Vec<Checkpoint>,
+    /// The position within a larger input stream at which the input begins, if it doesn't
+    /// begin at the start of the stream; token locations are relative to the stream.
+    origin: Option<SourcePosition>,
+}
+
+/// A point in the input at which tokenizing can be resumed with a fresh tokenizer.
+struct Checkpoint {
+    /// The position in the input.
+    position: SourcePosition,
+    /// The number of tokens preceding the position.
+    token_count: usize,
+}
+
+impl IncrementalParser {
+    /// Creates a new parser with no prior input.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a new parser with no prior input, for input that begins at the given
+    /// position within a larger input stream.
+    ///
+    /// # Arguments
+    ///
+    /// * `origin` - The position at which the input begins.
+    pub fn new_at(origin: &SourcePosition) -> Self {
+        Self {
+            origin: Some(origin.clone()),
+            ..Self::default()
+        }
+    }
+
+    /// Returns the position within the input stream at which the input begins.
+    fn origin(&self) -> SourcePosition {
+        self.origin.clone().unwrap_or(SourcePosition {
+            index: 0,
+            line: 1,
+            column: 1,
+        })
+    }
+
+    /// Tokenizes the given input, returning its tokens.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The input to tokenize.
+    /// * `options` - Options controlling how the input is tokenized.
+    pub fn tokenize(
+        &mut self,
+        input: &str,
+        options: &ParserOptions,
+    ) -> Result<&[Token], error::ParseError> {
+        if *options != self.options {
+            self.options = options.clone();
+            self.checkpoints.clear();
+        }
+
+        // Find the last checkpoint preceding the first character that differs from the
+        // input we saw last time; everything before it is unchanged.
+        let unchanged_chars = self
+            .input
+            .chars()
+            .zip(input.chars())
+            .take_while(|(prev, next)| prev == next)
+            .count();
+        let origin = self.origin();
+        let reusable_checkpoints = self
+            .checkpoints
+            .iter()
+            .take_while(|checkpoint| {
+                usize::try_from(checkpoint.position.index - origin.index)
+                    .is_ok_and(|i| i <= unchanged_chars)
+            })
+            .count();
+        self.checkpoints.truncate(reusable_checkpoints);
+
+        let start = if let Some(checkpoint) = self.checkpoints.last() {
+            self.tokens.truncate(checkpoint.token_count);
+            checkpoint.position.clone()
+        } else {
+            self.tokens.clear();
+            origin.clone()
+        };
+
+        self.input = input.to_owned();
+
+        let remaining = usize::try_from(start.index - origin.index)
+            .ok()
+            .and_then(|i| input.char_indices().nth(i))
+            .map_or("", |(offset, _)| &input[offset..]);
+
+        let tokenizer_options = crate::tokenizer::TokenizerOptions {
+            capture_trivia: false,
+            ..options.tokenizer_options()
+        };
+
+        let mut reader = remaining.as_bytes();
+        let mut tokenizer = Tokenizer::new_at(&mut reader, &tokenizer_options, &start);
+
+        loop {
+            let result = tokenizer
+                .next_token()
+                .map_err(|e| error::ParseError::Tokenizing {
+                    inner: e,
+                    position: tokenizer.current_location(),
+                })?;
+
+            if let Some(token) = result.token {
+                let at_newline = matches!(&token, Token::Operator(o, _) if o == "\n");
+                self.tokens.push(token);
+
+                if at_newline && tokenizer.is_at_resumable_position() {
+                    if let Some(position) = tokenizer.current_location() {
+                        self.checkpoints.push(Checkpoint {
+                            position,
+                            token_count: self.tokens.len(),
+                        });
+                    }
+                }
+            }
+
+            if matches!(result.reason, TokenEndReason::EndOfInput) {
+                break;
+            }
+        }
+
+        Ok(self.tokens.as_slice())
+    }
+
+    /// Parses the given input into the abstract syntax tree (AST) of a shell program.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The input to parse.
+    /// * `options` - Options controlling how the input is parsed.
+    /// * `source_info` - Information about the source of the input.
+    pub fn parse(
+        &mut self,
+        input: &str,
+        options: &ParserOptions,
+        source_info: &SourceInfo,
+    ) -> Result<ast::Program, error::ParseError> {
+        self.tokenize(input, options)?;
+        parser::parse_tokens(&self.tokens, options, source_info)
+    }
+
+    /// Identifies the construct left open by the input most recently seen, given the
+    /// error that resulted from parsing it.
+    ///
+    /// # Arguments
+    ///
+    /// * `error` - The error that resulted from parsing the input.
+    pub fn unterminated_construct(
+        &self,
+        error: &error::ParseError,
+    ) -> Option<UnterminatedConstruct> {
+        UnterminatedConstruct::from_parse_error(error, &self.tokens)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+
+    fn token_summary(tokens: &[Token]) -> Vec<(String, i32, i32, i32)> {
+        tokens
+            .iter()
+            .map(|t| {
+                let start = &t.location().start;
+                (t.to_str().to_owned(), start.index, start.line, start.column)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn matches_full_tokenization() -> Result<()> {
+        let options = ParserOptions::default();
+        let mut parser = IncrementalParser::new();
+
+        let revisions = [
+            "if true; then\n",
+            "if true; then\n  echo 'hi\nthere'\n",
+            "if true; then\n  echo 'hi\nthere'\n  cat <<EOF\nbody\nEOF\n",
+            "if true; then\n  echo 'hi\nthere'\n  cat <<EOF\nbody\nEOF\nfi\n",
+            "if false; then\n  echo 'hi\nthere'\n  cat <<EOF\nbody\nEOF\nfi\n",
+            "if false; then\n  echo $(( 1 +\n 2 ))\nfi\n",
+        ];
+
+        for input in revisions {
+            let expected = crate::tokenize_str_with_options(input, &options.tokenizer_options())?;
+            let actual = parser.tokenize(input, &options)?;
+            assert_eq!(token_summary(actual), token_summary(&expected), "{input}");
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn reports_errors() -> Result<()> {
+        let options = ParserOptions::default();
+        let mut parser = IncrementalParser::new();
+
+        parser.tokenize("echo hi\n", &options)?;
+        assert!(matches!(
+            parser.tokenize("echo hi\necho 'x\n", &options),
+            Err(error::ParseError::Tokenizing { .. })
+        ));
+        assert!(matches!(
+            parser.parse("echo hi\nif true; then\n", &options, &SourceInfo::default()),
+            Err(error::ParseError::ParsingAtEndOfInput)
+        ));
+        assert!(parser
+            .parse(
+                "echo hi\nif true; then :; fi\n",
+                &options,
+                &SourceInfo::default()
+            )
+            .is_ok());
+
+        Ok(())
+    }
+}
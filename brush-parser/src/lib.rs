@@ -4,17 +4,31 @@
 
 pub mod arithmetic;
 pub mod ast;
+pub mod format;
+pub mod lint;
 pub mod pattern;
+pub mod plan;
 pub mod prompt;
 pub mod test_command;
 pub mod word;
 
+mod aliases;
+mod continuation;
 mod error;
+mod incremental;
 mod parser;
+mod streaming;
 mod tokenizer;
 
+pub use continuation::UnterminatedConstruct;
 pub use error::{ParseError, TestCommandParseError, WordParseError};
-pub use parser::{parse_tokens, Parser, ParserOptions, SourceInfo};
+pub use incremental::IncrementalParser;
+pub use parser::{
+    is_valid_function_name, is_valid_name, parse_tokens, parse_tokens_with_recovery, Parser,
+    ParserOptions, RecoveredParseError, SourceInfo,
+};
+pub use streaming::{StreamingParseResult, StreamingParser};
 pub use tokenizer::{
     tokenize_str, tokenize_str_with_options, unquote_str, SourcePosition, Token, TokenLocation,
+    TokenizerError, Trivia, TriviaKind,
 };
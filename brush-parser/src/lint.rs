@@ -0,0 +1,588 @@
+//! Implements static analysis (linting) of parsed shell programs.
+
+use std::fmt::Display;
+
+use crate::ast;
+use crate::tokenizer::{Token, TokenLocation};
+use crate::word::{self, WordPiece};
+use crate::ParserOptions;
+
+/// Identifies a check performed by the linter.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Rule {
+    /// The program can't be parsed.
+    SyntaxError,
+    /// An expansion is subject to word splitting and pathname expansion because it
+    /// isn't quoted.
+    UnquotedExpansion,
+    /// The exit status of a command is checked indirectly via `$?`.
+    IndirectExitStatusCheck,
+    /// A single file is piped from `cat` into another command.
+    UselessCat,
+    /// A command substitution uses the deprecated backquoted form.
+    DeprecatedBackticks,
+    /// A command can never be executed because it follows an unconditional exit.
+    UnreachableCode,
+}
+
+impl Rule {
+    /// Returns the unique code identifying the rule.
+    pub const fn code(self) -> &'static str {
+        match self {
+            Rule::SyntaxError => "BR1000",
+            Rule::UnquotedExpansion => "BR1001",
+            Rule::IndirectExitStatusCheck => "BR1002",
+            Rule::UselessCat => "BR1003",
+            Rule::DeprecatedBackticks => "BR1004",
+            Rule::UnreachableCode => "BR1005",
+        }
+    }
+
+    /// Returns the severity of diagnostics produced by the rule.
+    pub const fn severity(self) -> Severity {
+        match self {
+            Rule::SyntaxError => Severity::Error,
+            Rule::UnquotedExpansion | Rule::UnreachableCode => Severity::Warning,
+            Rule::IndirectExitStatusCheck | Rule::UselessCat | Rule::DeprecatedBackticks => {
+                Severity::Style
+            }
+        }
+    }
+}
+
+/// The severity of a diagnostic.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// A stylistic suggestion.
+    Style,
+    /// A suggestion that may improve the script.
+    Info,
+    /// A likely problem with the script.
+    Warning,
+    /// A definite problem with the script.
+    Error,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::Style => write!(f, "style"),
+            Severity::Info => write!(f, "info"),
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// A diagnostic produced by linting a shell program.
+#[derive(Clone, Debug)]
+pub struct Diagnostic {
+    /// The rule that produced the diagnostic.
+    pub rule: Rule,
+    /// A human-readable description of the problem.
+    pub message: String,
+    /// The location of the problem in the source, if known.
+    pub location: Option<TokenLocation>,
+}
+
+impl Diagnostic {
+    /// Returns the severity of the diagnostic.
+    pub const fn severity(&self) -> Severity {
+        self.rule.severity()
+    }
+}
+
+impl From<&crate::RecoveredParseError> for Diagnostic {
+    fn from(value: &crate::RecoveredParseError) -> Self {
+        let message = match &value.error {
+            crate::ParseError::ParsingNearToken(token) => {
+                std::format!("syntax error near unexpected token '{}'", token.to_str())
+            }
+            crate::ParseError::ParsingAtEndOfInput => {
+                String::from("syntax error: unexpected end of input")
+            }
+            error @ crate::ParseError::Tokenizing { .. } => error.to_string(),
+        };
+
+        Self {
+            rule: Rule::SyntaxError,
+            message,
+            location: Some(value.location.clone()),
+        }
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(location) = &self.location {
+            write!(f, "{}:{}: ", location.start.line, location.start.column)?;
+        }
+
+        write!(
+            f,
+            "{}: {} [{}]",
+            self.severity(),
+            self.message,
+            self.rule.code()
+        )
+    }
+}
+
+/// Lints the given program, returning any diagnostics in source order.
+///
+/// # Arguments
+///
+/// * `program` - The program to lint.
+/// * `tokens` - The tokens the program was parsed from; used to locate diagnostics.
+/// * `options` - The options the program was parsed with.
+pub fn lint_program(
+    program: &ast::Program,
+    tokens: &[Token],
+    options: &ParserOptions,
+) -> Vec<Diagnostic> {
+    let mut linter = Linter {
+        tokens,
+        options,
+        diagnostics: vec![],
+        command_location: None,
+        next_token_index: 0,
+    };
+
+    let mut exited = false;
+    let mut reported_unreachable = false;
+
+    for (i, complete_command) in program.complete_commands.iter().enumerate() {
+        linter.command_location = program.complete_command_locations.get(i).cloned();
+
+        if exited && !reported_unreachable {
+            if let Some(ast::CompoundListItem(ao_list, _)) = complete_command.0.first() {
+                linter.report_unreachable(ao_list);
+                reported_unreachable = true;
+            }
+        }
+
+        exited |= linter.lint_compound_list(complete_command);
+    }
+
+    let mut diagnostics = linter.diagnostics;
+    diagnostics.sort_by_key(|d| d.location.as_ref().map(|l| l.start.index));
+
+    diagnostics
+}
+
+struct Linter<'a> {
+    tokens: &'a [Token],
+    options: &'a ParserOptions,
+    diagnostics: Vec<Diagnostic>,
+    /// Location of the complete command currently being linted.
+    command_location: Option<TokenLocation>,
+    /// Index of the next token to consider when locating words.
+    next_token_index: usize,
+}
+
+impl Linter<'_> {
+    fn report(&mut self, rule: Rule, message: impl Into<String>, location: Option<TokenLocation>) {
+        self.diagnostics.push(Diagnostic {
+            rule,
+            message: message.into(),
+            location: location.or_else(|| self.command_location.clone()),
+        });
+    }
+
+    /// Finds the location of the given word in the source, searching forward from the
+    /// last word located within the current complete command.
+    fn locate_word(&mut self, word: &ast::Word) -> Option<TokenLocation> {
+        let command_location = self.command_location.as_ref()?;
+        let in_command = |token: &Token| {
+            let index = token.location().start.index;
+            index >= command_location.start.index && index < command_location.end.index
+        };
+
+        let found = (self.next_token_index..self.tokens.len())
+            .chain(0..self.next_token_index)
+            .find(|i| {
+                let token = &self.tokens[*i];
+                matches!(token, Token::Word(..))
+                    && in_command(token)
+                    && token.to_str() == word.value
+            })?;
+
+        self.next_token_index = found + 1;
+        Some(self.tokens[found].location().clone())
+    }
+
+    fn report_unreachable(&mut self, ao_list: &ast::AndOrList) {
+        let location = first_word(ao_list).and_then(|w| self.locate_word(w));
+        self.report(
+            Rule::UnreachableCode,
+            "this command is unreachable; it follows an unconditional exit",
+            location,
+        );
+    }
+
+    /// Lints the given list; returns whether the list unconditionally exits.
+    fn lint_compound_list(&mut self, list: &ast::CompoundList) -> bool {
+        let mut exited = false;
+        let mut reported_unreachable = false;
+
+        for ast::CompoundListItem(ao_list, separator) in &list.0 {
+            // N.B. We only report the first unreachable command in the list.
+            if exited && !reported_unreachable {
+                self.report_unreachable(ao_list);
+                reported_unreachable = true;
+            }
+
+            for pipeline in pipelines(ao_list) {
+                self.lint_pipeline(pipeline);
+            }
+
+            exited |= matches!(separator, ast::SeparatorOperator::Sequence)
+                && ao_list.additional.is_empty()
+                && is_unconditional_exit(&ao_list.first);
+        }
+
+        exited
+    }
+
+    fn lint_pipeline(&mut self, pipeline: &ast::Pipeline) {
+        if pipeline.seq.len() > 1 {
+            if let ast::Command::Simple(cmd) = &pipeline.seq[0] {
+                if is_useless_cat(cmd) {
+                    let location = cmd.word_or_name.as_ref().and_then(|w| self.locate_word(w));
+                    self.report(
+                        Rule::UselessCat,
+                        "useless use of cat; redirect the file into the command instead",
+                        location,
+                    );
+                }
+            }
+        }
+
+        for command in &pipeline.seq {
+            self.lint_command(command);
+        }
+    }
+
+    fn lint_command(&mut self, command: &ast::Command) {
+        match command {
+            ast::Command::Simple(simple_command) => self.lint_simple_command(simple_command),
+            ast::Command::Compound(compound_command, _) => {
+                self.lint_compound_command(compound_command);
+            }
+            ast::Command::Function(function_definition) => {
+                self.lint_compound_command(&function_definition.body.0);
+            }
+            ast::Command::ExtendedTest(_) => (),
+        }
+    }
+
+    fn lint_simple_command(&mut self, cmd: &ast::SimpleCommand) {
+        let prefix_items = cmd.prefix.iter().flat_map(|prefix| prefix.0.iter());
+        let suffix_items = cmd.suffix.iter().flat_map(|suffix| suffix.0.iter());
+
+        for item in prefix_items {
+            if let ast::CommandPrefixOrSuffixItem::AssignmentWord(_, word) = item {
+                self.lint_word(word, false);
+            }
+        }
+
+        if let Some(word) = &cmd.word_or_name {
+            self.lint_word(word, true);
+        }
+
+        for item in suffix_items {
+            match item {
+                ast::CommandPrefixOrSuffixItem::Word(word) => self.lint_word(word, true),
+                // N.B. Assignments aren't subject to word splitting.
+                ast::CommandPrefixOrSuffixItem::AssignmentWord(_, word) => {
+                    self.lint_word(word, false);
+                }
+                _ => (),
+            }
+        }
+    }
+
+    fn lint_word(&mut self, word: &ast::Word, check_quoting: bool) {
+        let Ok(pieces) = word::parse(word.value.as_str(), self.options) else {
+            return;
+        };
+
+        let mut unquoted_expansion = None;
+        let mut uses_backticks = false;
+
+        for piece in &pieces {
+            let text = word
+                .value
+                .get(piece.start_index..piece.end_index)
+                .unwrap_or_default();
+            match &piece.piece {
+                WordPiece::ParameterExpansion(_) if !expands_to_single_field(text) => {
+                    unquoted_expansion.get_or_insert(text);
+                }
+                WordPiece::CommandSubstitution(_) => {
+                    unquoted_expansion.get_or_insert(text);
+                }
+                WordPiece::BackquotedCommandSubstitution(_) => {
+                    unquoted_expansion.get_or_insert(text);
+                    uses_backticks = true;
+                }
+                WordPiece::DoubleQuotedSequence(inner) => {
+                    uses_backticks |= inner
+                        .iter()
+                        .any(|p| matches!(p.piece, WordPiece::BackquotedCommandSubstitution(_)));
+                }
+                _ => (),
+            }
+        }
+
+        if !check_quoting {
+            unquoted_expansion = None;
+        }
+
+        if unquoted_expansion.is_none() && !uses_backticks {
+            return;
+        }
+
+        let location = self.locate_word(word);
+
+        if let Some(expansion) = unquoted_expansion {
+            self.report(
+                Rule::UnquotedExpansion,
+                std::format!(
+                    "double quote {expansion} to prevent word splitting and pathname expansion"
+                ),
+                location.clone(),
+            );
+        }
+
+        if uses_backticks {
+            self.report(
+                Rule::DeprecatedBackticks,
+                "use $(...) instead of deprecated backquoted command substitution",
+                location,
+            );
+        }
+    }
+
+    fn lint_condition(&mut self, condition: &ast::CompoundList) {
+        for ast::CompoundListItem(ao_list, _) in &condition.0 {
+            for pipeline in pipelines(ao_list) {
+                let checked_word = pipeline.seq.iter().find_map(|command| match command {
+                    ast::Command::Simple(cmd) => indirect_exit_status_check(cmd),
+                    _ => None,
+                });
+
+                if let Some(word) = checked_word {
+                    let location = self.locate_word(word);
+                    self.report(
+                        Rule::IndirectExitStatusCheck,
+                        "check the exit status of the command directly instead of via $?",
+                        location,
+                    );
+                }
+            }
+        }
+
+        self.lint_compound_list(condition);
+    }
+
+    fn lint_compound_command(&mut self, command: &ast::CompoundCommand) {
+        match command {
+            ast::CompoundCommand::Arithmetic(_) => (),
+            ast::CompoundCommand::ArithmeticForClause(for_clause) => {
+                self.lint_compound_list(&for_clause.body.0);
+            }
+            ast::CompoundCommand::BraceGroup(ast::BraceGroupCommand(list))
+            | ast::CompoundCommand::Subshell(ast::SubshellCommand(list)) => {
+                self.lint_compound_list(list);
+            }
+            ast::CompoundCommand::ForClause(for_clause) => {
+                self.lint_compound_list(&for_clause.body.0);
+            }
+            ast::CompoundCommand::CaseClause(case_clause) => {
+                for case in &case_clause.cases {
+                    if let Some(cmd) = &case.cmd {
+                        self.lint_compound_list(cmd);
+                    }
+                }
+            }
+            ast::CompoundCommand::IfClause(if_clause) => {
+                self.lint_condition(&if_clause.condition);
+                self.lint_compound_list(&if_clause.then);
+                for else_clause in if_clause.elses.iter().flatten() {
+                    if let Some(condition) = &else_clause.condition {
+                        self.lint_condition(condition);
+                    }
+                    self.lint_compound_list(&else_clause.body);
+                }
+            }
+            ast::CompoundCommand::WhileClause(ast::WhileOrUntilClauseCommand(condition, body))
+            | ast::CompoundCommand::UntilClause(ast::WhileOrUntilClauseCommand(condition, body)) => {
+                self.lint_condition(condition);
+                self.lint_compound_list(&body.0);
+            }
+        }
+    }
+}
+
+/// Returns whether the given parameter expansion text always expands to a single field
+/// that's safe from word splitting (e.g., `$?` or `${#name}`).
+fn expands_to_single_field(text: &str) -> bool {
+    let name = text.strip_prefix('$').unwrap_or(text);
+    let name = name
+        .strip_prefix('{')
+        .and_then(|n| n.strip_suffix('}'))
+        .unwrap_or(name);
+
+    name.starts_with('#') || matches!(name, "?" | "$" | "!" | "-")
+}
+
+fn pipelines(ao_list: &ast::AndOrList) -> impl Iterator<Item = &ast::Pipeline> {
+    std::iter::once(&ao_list.first).chain(ao_list.additional.iter().map(|next| match next {
+        ast::AndOr::And(pipeline) | ast::AndOr::Or(pipeline) => pipeline,
+    }))
+}
+
+fn first_word(ao_list: &ast::AndOrList) -> Option<&ast::Word> {
+    match ao_list.first.seq.first()? {
+        ast::Command::Simple(cmd) => cmd.word_or_name.as_ref(),
+        _ => None,
+    }
+}
+
+fn is_unconditional_exit(pipeline: &ast::Pipeline) -> bool {
+    if pipeline.seq.len() != 1 || pipeline.bang {
+        return false;
+    }
+
+    let ast::Command::Simple(cmd) = &pipeline.seq[0] else {
+        return false;
+    };
+
+    match cmd.word_or_name.as_ref().map(|w| w.value.as_str()) {
+        Some("exit" | "return") => true,
+        // N.B. `exec` without a command only applies redirections to the current shell.
+        Some("exec") => cmd
+            .suffix
+            .iter()
+            .flat_map(|s| s.0.iter())
+            .any(|item| matches!(item, ast::CommandPrefixOrSuffixItem::Word(_))),
+        _ => false,
+    }
+}
+
+fn is_useless_cat(cmd: &ast::SimpleCommand) -> bool {
+    if cmd.word_or_name.as_ref().map_or(true, |w| w.value != "cat") || cmd.prefix.is_some() {
+        return false;
+    }
+
+    let Some(suffix) = &cmd.suffix else {
+        return false;
+    };
+
+    matches!(&suffix.0[..], [ast::CommandPrefixOrSuffixItem::Word(w)] if !w.value.starts_with('-'))
+}
+
+/// If the given command is a test of `$?` (e.g., `[ $? -ne 0 ]`), returns the word that
+/// references `$?`.
+fn indirect_exit_status_check(cmd: &ast::SimpleCommand) -> Option<&ast::Word> {
+    if !matches!(
+        cmd.word_or_name.as_ref().map(|w| w.value.as_str()),
+        Some("[" | "test")
+    ) {
+        return None;
+    }
+
+    cmd.suffix
+        .iter()
+        .flat_map(|s| s.0.iter())
+        .find_map(|item| match item {
+            ast::CommandPrefixOrSuffixItem::Word(w)
+                if matches!(w.value.as_str(), "$?" | "\"$?\"" | "${?}" | "\"${?}\"") =>
+            {
+                Some(w)
+            }
+            _ => None,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_tokens, tokenize_str, SourceInfo};
+    use anyhow::Result;
+
+    fn lint_str(input: &str) -> Result<Vec<(Rule, i32)>> {
+        let options = ParserOptions::default();
+        let tokens = tokenize_str(input)?;
+        let program = parse_tokens(&tokens, &options, &SourceInfo::default())?;
+
+        Ok(lint_program(&program, &tokens, &options)
+            .into_iter()
+            .map(|d| (d.rule, d.location.map_or(0, |l| l.start.line)))
+            .collect())
+    }
+
+    #[test]
+    fn lint_unquoted_expansions() -> Result<()> {
+        let input = r#"echo $x "$y" $? ${#z}
+rm $(ls)
+"#;
+
+        assert_eq!(
+            lint_str(input)?,
+            [(Rule::UnquotedExpansion, 1), (Rule::UnquotedExpansion, 2)]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn lint_style_issues() -> Result<()> {
+        let input = r#"cat file | grep x
+x="`date`"
+cmd
+if [ $? -ne 0 ]; then :; fi
+"#;
+
+        assert_eq!(
+            lint_str(input)?,
+            [
+                (Rule::UselessCat, 1),
+                (Rule::DeprecatedBackticks, 2),
+                (Rule::IndirectExitStatusCheck, 4),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn lint_unreachable_code() -> Result<()> {
+        let input = r"f() {
+    return 1
+    echo unreachable
+    echo also-unreachable
+}
+exit 0
+echo done
+";
+
+        assert_eq!(
+            lint_str(input)?,
+            [(Rule::UnreachableCode, 3), (Rule::UnreachableCode, 7)]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn lint_backticks() -> Result<()> {
+        assert_eq!(
+            lint_str("echo \"`date`\" `pwd`\n")?,
+            [
+                (Rule::DeprecatedBackticks, 1),
+                (Rule::UnquotedExpansion, 1),
+                (Rule::DeprecatedBackticks, 1),
+            ]
+        );
+        Ok(())
+    }
+}
@@ -1,6 +1,11 @@
+use std::collections::BTreeMap;
+
+use crate::aliases;
 use crate::ast::{self, SeparatorOperator};
 use crate::error;
-use crate::tokenizer::{Token, TokenEndReason, Tokenizer, TokenizerOptions, Tokens};
+use crate::tokenizer::{
+    Token, TokenEndReason, TokenLocation, Tokenizer, TokenizerOptions, Tokens, Trivia,
+};
 
 /// Options used to control the behavior of the parser.
 #[derive(Clone, Eq, Hash, PartialEq)]
@@ -13,6 +18,14 @@ pub struct ParserOptions {
     pub sh_mode: bool,
     /// Whether or not to perform tilde expansion.
     pub tilde_expansion: bool,
+    /// Whether or not to also perform tilde expansion following each unquoted `:` in a
+    /// word, as in the values of assignments (e.g., `PATH=~/bin:~/.local/bin`).
+    pub tilde_expansion_after_colons: bool,
+    /// Whether or not to capture comments and whitespace trivia in the parsed program.
+    pub capture_trivia: bool,
+    /// Aliases to expand in the first word of each simple command, keyed by name. Empty
+    /// if aliases shouldn't be expanded.
+    pub aliases: BTreeMap<String, String>,
 }
 
 impl Default for ParserOptions {
@@ -22,6 +35,9 @@ impl Default for ParserOptions {
             posix_mode: false,
             sh_mode: false,
             tilde_expansion: true,
+            tilde_expansion_after_colons: false,
+            capture_trivia: false,
+            aliases: BTreeMap::new(),
         }
     }
 }
@@ -33,10 +49,41 @@ impl ParserOptions {
             enable_extended_globbing: self.enable_extended_globbing,
             posix_mode: self.posix_mode,
             sh_mode: self.sh_mode,
+            capture_trivia: self.capture_trivia,
         }
     }
 }
 
+/// Returns whether the given string is a valid name for a variable (i.e., it consists of
+/// letters, digits, and underscores, and doesn't start with a digit).
+///
+/// # Arguments
+///
+/// * `s` - The string to check.
+pub fn is_valid_name(s: &str) -> bool {
+    let mut chars = s.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Returns whether the given word is a valid name for a function. In POSIX mode, function
+/// names must be valid variable names; otherwise, as in bash, any word is accepted (e.g.,
+/// `foo:bar` or `a.b`) as long as it isn't quoted and doesn't contain `$`.
+///
+/// # Arguments
+///
+/// * `word` - The word to check, as written in the source (i.e., before any quote removal).
+/// * `posix_mode` - Whether POSIX mode is enabled.
+pub fn is_valid_function_name(word: &str, posix_mode: bool) -> bool {
+    if posix_mode {
+        is_valid_name(word)
+    } else {
+        !word.is_empty() && !word.contains(['$', '\'', '"', '\\'])
+    }
+}
+
 /// Implements parsing for shell programs.
 pub struct Parser<R> {
     reader: R,
@@ -100,10 +147,87 @@ impl<R: std::io::BufRead> Parser<R> {
 
         tracing::debug!(target: "tokenize", "  => {} token(s)", tokens.len());
 
-        parse_tokens(&tokens, &self.options, &self.source_info)
+        let mut program = parse_tokens(&tokens, &self.options, &self.source_info)?;
+
+        if self.options.capture_trivia {
+            program.trivia =
+                attach_trivia(tokenizer.take_trivia(), &program.complete_command_locations);
+        }
+
+        Ok(program)
     }
 }
 
+/// Computes the source location spanned by each of the given ranges of tokens.
+///
+/// # Arguments
+///
+/// * `tokens` - The tokens that were parsed.
+/// * `extents` - The ranges of tokens to compute locations for.
+fn token_extent_locations(
+    tokens: &[Token],
+    extents: &[std::ops::Range<usize>],
+) -> Vec<TokenLocation> {
+    // N.B. We can't assume the last token has the furthest extent, since here-document
+    // bodies may come after later tokens.
+    extents
+        .iter()
+        .map(|extent| {
+            let extent_tokens = &tokens[extent.clone()];
+            let start = extent_tokens
+                .iter()
+                .map(|t| &t.location().start)
+                .min_by_key(|p| p.index);
+            let end = extent_tokens
+                .iter()
+                .map(|t| &t.location().end)
+                .max_by_key(|p| p.index);
+
+            TokenLocation {
+                start: start.cloned().unwrap_or_default(),
+                end: end.cloned().unwrap_or_default(),
+            }
+        })
+        .collect()
+}
+
+/// Associates each of the given trivia with the complete command it precedes, follows, or
+/// is contained within.
+///
+/// # Arguments
+///
+/// * `trivia` - The trivia to attach, in source order.
+/// * `locations` - The location of each complete command in the program.
+fn attach_trivia(trivia: Vec<Trivia>, locations: &[TokenLocation]) -> Vec<ast::AttachedTrivia> {
+    trivia
+        .into_iter()
+        .map(|trivia| {
+            let position = &trivia.location.start;
+
+            let attachment = if let Some(i) = locations.iter().position(|location| {
+                location.start.index <= position.index && position.index < location.end.index
+            }) {
+                ast::TriviaAttachment::Within(i)
+            } else {
+                let next = locations
+                    .iter()
+                    .position(|location| location.start.index > position.index)
+                    .unwrap_or(locations.len());
+
+                match (next.checked_sub(1), locations.get(next)) {
+                    (Some(prev), _) if locations[prev].end.line == position.line => {
+                        ast::TriviaAttachment::Trailing(prev)
+                    }
+                    (_, Some(_)) => ast::TriviaAttachment::Leading(next),
+                    _ => ast::TriviaAttachment::Dangling,
+                }
+            };
+
+            ast::AttachedTrivia { trivia, attachment }
+        })
+        .collect()
+}
+
 /// Parses a sequence of tokens into the abstract syntax tree (AST) of a shell program.
 ///
 /// # Arguments
@@ -116,23 +240,169 @@ pub fn parse_tokens(
     options: &ParserOptions,
     source_info: &SourceInfo,
 ) -> Result<ast::Program, error::ParseError> {
+    let expanded_tokens;
+    let tokens = if options.aliases.is_empty() {
+        tokens
+    } else {
+        expanded_tokens = aliases::expand_aliases(tokens, options).map_err(|e| e.error)?;
+        &expanded_tokens
+    };
+
     let parse_result = token_parser::program(&Tokens { tokens }, options, source_info);
+    let (mut program, extents) = convert_parse_result(parse_result, tokens)?;
+
+    program.complete_command_locations = token_extent_locations(tokens, &extents);
+
+    Ok(program)
+}
+
+/// A syntax error encountered while parsing with error recovery, along with the location
+/// in the source where it was encountered.
+#[derive(Debug)]
+pub struct RecoveredParseError {
+    /// The error.
+    pub error: error::ParseError,
+    /// The location of the error; for errors at the end of the input, this is the
+    /// location of the end of the last token.
+    pub location: TokenLocation,
+}
+
+/// Parses a sequence of tokens into the abstract syntax tree (AST) of a shell program,
+/// recovering from syntax errors instead of stopping at the first one. When an error is
+/// encountered, the complete commands preceding the line it occurred on are retained, the
+/// rest of that line is skipped, and parsing resumes on the next line. Returns the
+/// commands that could be parsed along with all errors encountered, in source order.
+///
+/// # Arguments
+///
+/// * `tokens` - The tokens to parse.
+/// * `options` - The options to use when parsing.
+/// * `source_info` - Information about the source of the tokens.
+pub fn parse_tokens_with_recovery(
+    tokens: &[Token],
+    options: &ParserOptions,
+    source_info: &SourceInfo,
+) -> (ast::Program, Vec<RecoveredParseError>) {
+    let expanded_tokens;
+    let tokens = if options.aliases.is_empty() {
+        tokens
+    } else {
+        expanded_tokens = match aliases::expand_aliases(tokens, options) {
+            Ok(expanded_tokens) => expanded_tokens,
+            Err(error) => return (ast::Program::default(), vec![error]),
+        };
+        expanded_tokens.as_slice()
+    };
+
+    let mut program = ast::Program::default();
+    let mut errors = vec![];
+    let mut remaining = tokens;
+
+    while !remaining.is_empty() {
+        let remaining_tokens = remaining.to_vec();
+        let error_index = match token_parser::program(
+            &Tokens {
+                tokens: &remaining_tokens,
+            },
+            options,
+            source_info,
+        ) {
+            Ok((parsed, extents)) => {
+                append_parsed(&mut program, parsed, &remaining_tokens, &extents);
+                break;
+            }
+            Err(e) => e.location,
+        };
 
-    let result = match parse_result {
+        let (error, location) = if let Some(token) = remaining.get(error_index) {
+            (
+                error::ParseError::ParsingNearToken(token.clone()),
+                token.location().clone(),
+            )
+        } else {
+            let end = remaining
+                .last()
+                .map(|t| t.location().end.clone())
+                .unwrap_or_default();
+            (
+                error::ParseError::ParsingAtEndOfInput,
+                TokenLocation {
+                    start: end.clone(),
+                    end,
+                },
+            )
+        };
+        errors.push(RecoveredParseError { error, location });
+
+        // Retain as much as we can from before the error, trying successively earlier
+        // line boundaries until we find a prefix that parses on its own.
+        let line_ends = remaining[..error_index.min(remaining.len())]
+            .iter()
+            .enumerate()
+            .filter(|(_, t)| is_newline(t))
+            .map(|(i, _)| i + 1)
+            .rev();
+
+        for line_end in line_ends {
+            let prefix_tokens = remaining[..line_end].to_vec();
+            if let Ok((parsed, extents)) = token_parser::program(
+                &Tokens {
+                    tokens: &prefix_tokens,
+                },
+                options,
+                source_info,
+            ) {
+                append_parsed(&mut program, parsed, &prefix_tokens, &extents);
+                break;
+            }
+        }
+
+        // Resynchronize at the start of the line following the error.
+        let Some(next_line_start) = remaining
+            .iter()
+            .skip(error_index)
+            .position(is_newline)
+            .map(|offset| error_index + offset + 1)
+        else {
+            break;
+        };
+
+        remaining = &remaining[next_line_start..];
+    }
+
+    (program, errors)
+}
+
+fn is_newline(token: &Token) -> bool {
+    matches!(token, Token::Operator(o, _) if o == "\n")
+}
+
+fn append_parsed(
+    program: &mut ast::Program,
+    parsed: ast::Program,
+    tokens: &[Token],
+    extents: &[std::ops::Range<usize>],
+) {
+    program.complete_commands.extend(parsed.complete_commands);
+    program
+        .complete_command_locations
+        .extend(token_extent_locations(tokens, extents));
+}
+
+fn convert_parse_result<T: std::fmt::Debug>(
+    parse_result: Result<T, peg::error::ParseError<usize>>,
+    tokens: &[Token],
+) -> Result<T, error::ParseError> {
+    match parse_result {
         Ok(program) => {
             tracing::debug!(target: "parse", "PROG: {:?}", program);
             Ok(program)
         }
         Err(parse_error) => {
             tracing::debug!(target: "parse", "Parse error: {:?}", parse_error);
-            Err(error::convert_peg_parse_error(
-                parse_error,
-                tokens.as_slice(),
-            ))
+            Err(error::convert_peg_parse_error(parse_error, tokens))
         }
-    };
-
-    result
+    }
 }
 
 impl peg::Parse for Tokens<'_> {
@@ -204,12 +474,17 @@ pub struct SourceInfo {
 
 peg::parser! {
     grammar token_parser<'a>(parser_options: &ParserOptions, source_info: &SourceInfo) for Tokens<'a> {
-        pub(crate) rule program() -> ast::Program =
-            linebreak() c:complete_commands() linebreak() { ast::Program { complete_commands: c } } /
-            linebreak() { ast::Program { complete_commands: vec![] } }
+        // N.B. Along with the program, yields the range of tokens making up each of its
+        // complete commands.
+        pub(crate) rule program() -> (ast::Program, Vec<std::ops::Range<usize>>) =
+            linebreak() c:complete_commands() linebreak() {
+                let (complete_commands, extents) = c.into_iter().unzip();
+                (ast::Program { complete_commands, ..ast::Program::default() }, extents)
+            } /
+            linebreak() { (ast::Program::default(), vec![]) }
 
-        rule complete_commands() -> Vec<ast::CompleteCommand> =
-            c:complete_command() ++ newline_list()
+        rule complete_commands() -> Vec<(ast::CompleteCommand, std::ops::Range<usize>)> =
+            (start:position!() c:complete_command() end:position!() { (c, start..end) }) ++ newline_list()
 
         rule complete_command() -> ast::CompleteCommand =
             first:and_or() remainder:(s:separator_op() l:and_or() { (s, l) })* last_sep:separator_op()? {
@@ -243,7 +518,9 @@ peg::parser! {
             specific_operator("||") { ast::AndOr::Or }
 
         rule pipeline() -> ast::Pipeline =
-            timed:pipeline_timed()? bang:bang()? seq:pipe_sequence() { ast::Pipeline { timed, bang: bang.is_some(), seq } }
+            start:&[_] timed:pipeline_timed()? bang:bang()? seq:pipe_sequence() {
+                ast::Pipeline { timed, bang: bang.is_some(), seq, line: start.location().start.line }
+            }
 
         rule pipeline_timed() -> ast::PipelineTimed =
             non_posix_extensions_enabled() specific_word("time") posix_output:specific_word("-p")? {
@@ -1029,6 +1306,49 @@ esac\
         Ok(())
     }
 
+    #[test]
+    fn parse_function_definitions_with_unusual_names() -> Result<()> {
+        let inputs = [
+            ("function foo { echo 1; }", "foo"),
+            ("function foo:bar { echo 1; }", "foo:bar"),
+            ("function a-b\n{ echo 1; }", "a-b"),
+            ("function @x() { echo 1; }", "@x"),
+            ("a.b() { echo 1; }", "a.b"),
+            ("foo:bar ()\n{ echo 1; }", "foo:bar"),
+            ("123() ( echo 1 )", "123"),
+        ];
+
+        for (input, expected_name) in inputs {
+            let tokens = tokenize_str(input)?;
+            let seq = super::token_parser::pipe_sequence(
+                &Tokens {
+                    tokens: tokens.as_slice(),
+                },
+                &ParserOptions::default(),
+                &SourceInfo::default(),
+            )?;
+            assert_eq!(seq.len(), 1);
+            assert_matches!(&seq[0], ast::Command::Function(f) if f.fname == expected_name);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn validate_function_names() {
+        for name in ["foo", "foo:bar", "a.b", "@x", "a-b", "123", "x[1]"] {
+            assert!(is_valid_function_name(name, false), "{name}");
+        }
+        for name in ["", "f$x", "'f'", "ab\"c\"", "a\\b"] {
+            assert!(!is_valid_function_name(name, false), "{name}");
+        }
+
+        assert!(is_valid_function_name("_foo1", true));
+        for name in ["foo:bar", "a.b", "1x"] {
+            assert!(!is_valid_function_name(name, true), "{name}");
+        }
+    }
+
     #[test]
     fn test_parse_program() -> Result<()> {
         let input = r#"
@@ -1050,6 +1370,7 @@ for f in A B C; do
                     first: Pipeline {
                         timed: None,
                         bang: false,
+                        line: 4,
                         seq: vec![Command::Compound(
                             CompoundCommand::ForClause(ForClauseCommand {
                                 variable_name: "f".into(),
@@ -1059,6 +1380,7 @@ for f in A B C; do
                                         first: Pipeline {
                                             timed: None,
                                             bang: false,
+                                            line: 7,
                                             seq: vec![Command::Simple(SimpleCommand {
                                                 prefix: None,
                                                 word_or_name: Some(Word::new("echo")),
@@ -1088,10 +1410,11 @@ for f in A B C; do
                 },
                 SeparatorOperator::Sequence,
             )])],
+            ..Program::default()
         };
 
         let tokens = tokenize_str(input)?;
-        let result = super::token_parser::program(
+        let (result, _) = super::token_parser::program(
             &Tokens {
                 tokens: tokens.as_slice(),
             },
@@ -1103,4 +1426,80 @@ for f in A B C; do
 
         Ok(())
     }
+
+    #[test]
+    fn parse_program_with_trivia() -> Result<()> {
+        let input = r"# leading
+echo hi # trailing
+
+for x in y; do
+    # within
+    echo $x
+done
+# dangling
+";
+
+        let options = ParserOptions {
+            capture_trivia: true,
+            ..ParserOptions::default()
+        };
+
+        let mut parser = Parser::new(input.as_bytes(), &options, &SourceInfo::default());
+        let program = parser.parse()?;
+
+        let comments: Vec<_> = program
+            .trivia
+            .iter()
+            .filter(|t| t.trivia.kind == crate::TriviaKind::Comment)
+            .map(|t| (t.trivia.text.as_str(), t.attachment))
+            .collect();
+
+        assert_eq!(
+            comments,
+            [
+                ("# leading", ast::TriviaAttachment::Leading(0)),
+                ("# trailing", ast::TriviaAttachment::Trailing(0)),
+                ("# within", ast::TriviaAttachment::Within(1)),
+                ("# dangling", ast::TriviaAttachment::Dangling),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_program_without_trivia() -> Result<()> {
+        let input = "echo hi # comment\n";
+
+        let mut parser = Parser::new(
+            input.as_bytes(),
+            &ParserOptions::default(),
+            &SourceInfo::default(),
+        );
+        let program = parser.parse()?;
+
+        assert!(program.trivia.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_with_recovery() -> Result<()> {
+        let input = "echo 1\necho )\necho 2\nif true; then\n";
+        let tokens = tokenize_str(input)?;
+
+        let (program, errors) =
+            parse_tokens_with_recovery(&tokens, &ParserOptions::default(), &SourceInfo::default());
+
+        assert_eq!(program.complete_commands.len(), 2);
+        assert_eq!(program.complete_command_locations.len(), 2);
+        assert_eq!(program.complete_command_locations[1].start.line, 3);
+
+        assert_eq!(errors.len(), 2);
+        assert_matches!(&errors[0].error, error::ParseError::ParsingNearToken(t) if t.to_str() == ")");
+        assert_eq!(errors[0].location.start.line, 2);
+        assert_matches!(errors[1].error, error::ParseError::ParsingAtEndOfInput);
+
+        Ok(())
+    }
 }
@@ -0,0 +1,659 @@
+//! Implements dry-run analysis of parsed shell programs, producing a plan of the side
+//! effects that executing a program would have: which commands it would run, which files
+//! it would write to via redirection, and which variables it would export.
+//!
+//! The analysis is purely static. Words are only expanded if their expansion doesn't depend
+//! on the state of the shell or the file system (e.g., `'a b'` or `x\ y`, but not `$HOME` or
+//! `*.txt`); other words are reported as written in the source.
+
+use std::collections::HashSet;
+use std::fmt::Display;
+
+use crate::ast;
+use crate::word::{self, WordPiece};
+use crate::{is_valid_name, ParserOptions};
+
+/// A word in a plan step.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum PlanWord {
+    /// A word whose expansion is known statically; holds the expanded word.
+    Static(String),
+    /// A word whose expansion depends on the state of the shell (e.g., because it references
+    /// a variable); holds the word as written in the source.
+    Dynamic(String),
+}
+
+impl PlanWord {
+    /// Returns the text of the word: its expansion if static, or its source otherwise.
+    pub fn as_str(&self) -> &str {
+        match self {
+            PlanWord::Static(s) | PlanWord::Dynamic(s) => s.as_str(),
+        }
+    }
+}
+
+impl Display for PlanWord {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PlanWord::Static(s) if s.is_empty() || s.contains(needs_quoting) => {
+                write!(f, "'{}'", s.replace('\'', "'\\''"))
+            }
+            PlanWord::Static(s) | PlanWord::Dynamic(s) => write!(f, "{s}"),
+        }
+    }
+}
+
+/// Identifies what a command name refers to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum CommandKind {
+    /// A builtin command.
+    Builtin,
+    /// A function defined in the program.
+    Function,
+    /// An external command (i.e., a separate program).
+    External,
+    /// A command whose name isn't known statically.
+    Unknown,
+}
+
+impl Display for CommandKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CommandKind::Builtin => write!(f, "builtin"),
+            CommandKind::Function => write!(f, "function"),
+            CommandKind::External => write!(f, "external"),
+            CommandKind::Unknown => write!(f, "unknown"),
+        }
+    }
+}
+
+/// A side effect of executing a program.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum Effect {
+    /// A command would be run.
+    RunCommand {
+        /// What the command's name refers to.
+        kind: CommandKind,
+        /// The command's name followed by its arguments.
+        argv: Vec<PlanWord>,
+    },
+    /// A file would be opened for writing via redirection.
+    WriteFile {
+        /// The path of the file.
+        path: PlanWord,
+        /// Whether the file would be appended to, rather than truncated.
+        append: bool,
+    },
+    /// A variable would be exported to the environment of commands run by the shell.
+    ExportVariable {
+        /// The name of the variable.
+        name: String,
+        /// The value assigned to the variable, if one is assigned when it's exported.
+        value: Option<PlanWord>,
+    },
+}
+
+impl Display for Effect {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Effect::RunCommand { kind, argv } => {
+                write!(f, "run {kind}:")?;
+                for word in argv {
+                    write!(f, " {word}")?;
+                }
+                Ok(())
+            }
+            Effect::WriteFile { path, append } => {
+                write!(f, "write file: {path}")?;
+                if *append {
+                    write!(f, " (append)")?;
+                }
+                Ok(())
+            }
+            Effect::ExportVariable { name, value } => {
+                write!(f, "export variable: {name}")?;
+                if let Some(value) = value {
+                    write!(f, "={value}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// A step in the plan of executing a program.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Step {
+    /// The side effect of the step.
+    pub effect: Effect,
+    /// The 1-based line number in the source of the command responsible for the step.
+    pub line: i32,
+    /// Whether the step only happens under some condition (e.g., in the body of an `if`
+    /// command, or on the right-hand side of `&&`), or may happen more than once (e.g., in
+    /// the body of a loop).
+    pub conditional: bool,
+    /// The name of the function containing the step, if any; such steps only happen when
+    /// the function is called.
+    pub function: Option<String>,
+}
+
+impl Display for Step {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.line, self.effect)?;
+        if let Some(function) = &self.function {
+            write!(f, " [in function {function}]")?;
+        }
+        if self.conditional {
+            write!(f, " [conditional]")?;
+        }
+        Ok(())
+    }
+}
+
+/// Plans the execution of the given program, returning its steps in the order they appear
+/// in the source.
+///
+/// # Arguments
+///
+/// * `program` - The program to plan.
+/// * `options` - The options the program was parsed with.
+/// * `is_builtin` - Returns whether the given command name refers to a builtin command.
+pub fn plan_program(
+    program: &ast::Program,
+    options: &ParserOptions,
+    is_builtin: impl Fn(&str) -> bool,
+) -> Vec<Step> {
+    let mut planner = Planner {
+        options,
+        steps: vec![],
+        functions: HashSet::new(),
+        line_override: None,
+        conditional: false,
+        function: None,
+    };
+
+    for complete_command in &program.complete_commands {
+        planner.plan_compound_list(complete_command);
+    }
+
+    // N.B. Functions may be called before (in source order) their definition is
+    // encountered, so we can only tell which commands refer to them once we're done.
+    let mut steps = planner.steps;
+    for step in &mut steps {
+        if let Effect::RunCommand { kind, argv } = &mut step.effect {
+            if let Some(PlanWord::Static(name)) = argv.first() {
+                *kind = if planner.functions.contains(name.as_str()) {
+                    CommandKind::Function
+                } else if is_builtin(name.as_str()) {
+                    CommandKind::Builtin
+                } else {
+                    CommandKind::External
+                };
+            }
+        }
+    }
+
+    steps
+}
+
+struct Planner<'a> {
+    options: &'a ParserOptions,
+    steps: Vec<Step>,
+    /// Names of the functions defined in the program.
+    functions: HashSet<String>,
+    /// Line number to attribute steps to, if not that of the pipeline being planned (e.g.,
+    /// while planning the contents of a command substitution).
+    line_override: Option<i32>,
+    /// Whether the steps currently being planned are conditional.
+    conditional: bool,
+    /// Name of the function whose body is currently being planned, if any.
+    function: Option<String>,
+}
+
+impl Planner<'_> {
+    fn add_step(&mut self, effect: Effect, line: i32) {
+        self.steps.push(Step {
+            effect,
+            line: self.line_override.unwrap_or(line),
+            conditional: self.conditional,
+            function: self.function.clone(),
+        });
+    }
+
+    /// Plans the given list, with its steps marked as conditional if `conditional` is set.
+    fn plan_conditional_list(&mut self, list: &ast::CompoundList, conditional: bool) {
+        let was_conditional = self.conditional;
+        self.conditional |= conditional;
+        self.plan_compound_list(list);
+        self.conditional = was_conditional;
+    }
+
+    fn plan_compound_list(&mut self, list: &ast::CompoundList) {
+        for ast::CompoundListItem(ao_list, _) in &list.0 {
+            self.plan_pipeline(&ao_list.first);
+
+            let was_conditional = self.conditional;
+            self.conditional = true;
+            for next in &ao_list.additional {
+                match next {
+                    ast::AndOr::And(pipeline) | ast::AndOr::Or(pipeline) => {
+                        self.plan_pipeline(pipeline);
+                    }
+                }
+            }
+            self.conditional = was_conditional;
+        }
+    }
+
+    fn plan_pipeline(&mut self, pipeline: &ast::Pipeline) {
+        for command in &pipeline.seq {
+            self.plan_command(command, pipeline.line);
+        }
+    }
+
+    fn plan_command(&mut self, command: &ast::Command, line: i32) {
+        match command {
+            ast::Command::Simple(simple_command) => {
+                self.plan_simple_command(simple_command, line);
+            }
+            ast::Command::Compound(compound_command, redirects) => {
+                for redirect in redirects.iter().flat_map(|r| r.0.iter()) {
+                    self.plan_redirect(redirect, line);
+                }
+                self.plan_compound_command(compound_command, line);
+            }
+            ast::Command::Function(function_definition) => {
+                self.functions.insert(function_definition.fname.clone());
+
+                let enclosing_function = self.function.replace(function_definition.fname.clone());
+                let was_conditional = std::mem::take(&mut self.conditional);
+
+                let ast::FunctionBody(body, redirects) = &function_definition.body;
+                for redirect in redirects.iter().flat_map(|r| r.0.iter()) {
+                    self.plan_redirect(redirect, line);
+                }
+                self.plan_compound_command(body, line);
+
+                self.conditional = was_conditional;
+                self.function = enclosing_function;
+            }
+            ast::Command::ExtendedTest(_) => (),
+        }
+    }
+
+    fn plan_compound_command(&mut self, command: &ast::CompoundCommand, line: i32) {
+        match command {
+            ast::CompoundCommand::Arithmetic(_) => (),
+            ast::CompoundCommand::ArithmeticForClause(for_clause) => {
+                self.plan_conditional_list(&for_clause.body.0, true);
+            }
+            ast::CompoundCommand::BraceGroup(ast::BraceGroupCommand(list))
+            | ast::CompoundCommand::Subshell(ast::SubshellCommand(list)) => {
+                self.plan_compound_list(list);
+            }
+            ast::CompoundCommand::ForClause(for_clause) => {
+                for value in for_clause.values.iter().flatten() {
+                    self.plan_word(value, line);
+                }
+                self.plan_conditional_list(&for_clause.body.0, true);
+            }
+            ast::CompoundCommand::CaseClause(case_clause) => {
+                self.plan_word(&case_clause.value, line);
+                for case in &case_clause.cases {
+                    if let Some(cmd) = &case.cmd {
+                        self.plan_conditional_list(cmd, true);
+                    }
+                }
+            }
+            ast::CompoundCommand::IfClause(if_clause) => {
+                self.plan_compound_list(&if_clause.condition);
+                self.plan_conditional_list(&if_clause.then, true);
+                for else_clause in if_clause.elses.iter().flatten() {
+                    if let Some(condition) = &else_clause.condition {
+                        self.plan_conditional_list(condition, true);
+                    }
+                    self.plan_conditional_list(&else_clause.body, true);
+                }
+            }
+            ast::CompoundCommand::WhileClause(ast::WhileOrUntilClauseCommand(condition, body))
+            | ast::CompoundCommand::UntilClause(ast::WhileOrUntilClauseCommand(condition, body)) => {
+                // N.B. The condition is evaluated at least once, but may be evaluated
+                // repeatedly.
+                self.plan_conditional_list(condition, true);
+                self.plan_conditional_list(&body.0, true);
+            }
+        }
+    }
+
+    fn plan_simple_command(&mut self, cmd: &ast::SimpleCommand, line: i32) {
+        // N.B. Assignments preceding a command name are exported to the command's
+        // environment; without a command name, they only set shell variables.
+        for item in cmd.prefix.iter().flat_map(|prefix| prefix.0.iter()) {
+            match item {
+                ast::CommandPrefixOrSuffixItem::IoRedirect(redirect) => {
+                    self.plan_redirect(redirect, line);
+                }
+                ast::CommandPrefixOrSuffixItem::AssignmentWord(assignment, word) => {
+                    self.plan_word(word, line);
+                    if cmd.word_or_name.is_some() {
+                        self.plan_exported_assignment(assignment, line);
+                    }
+                }
+                ast::CommandPrefixOrSuffixItem::Word(word) => {
+                    self.plan_word(word, line);
+                }
+                ast::CommandPrefixOrSuffixItem::ProcessSubstitution(_, subshell) => {
+                    self.plan_compound_list(&subshell.0);
+                }
+            }
+        }
+
+        let Some(name) = &cmd.word_or_name else {
+            return;
+        };
+
+        let mut argv = vec![self.plan_word(name, line)];
+        let mut assignments = vec![];
+
+        for item in cmd.suffix.iter().flat_map(|suffix| suffix.0.iter()) {
+            match item {
+                ast::CommandPrefixOrSuffixItem::IoRedirect(redirect) => {
+                    self.plan_redirect(redirect, line);
+                }
+                ast::CommandPrefixOrSuffixItem::Word(word) => {
+                    argv.push(self.plan_word(word, line));
+                }
+                ast::CommandPrefixOrSuffixItem::AssignmentWord(assignment, word) => {
+                    assignments.push((argv.len(), assignment));
+                    argv.push(self.plan_word(word, line));
+                }
+                ast::CommandPrefixOrSuffixItem::ProcessSubstitution(_, subshell) => {
+                    self.plan_compound_list(&subshell.0);
+                }
+            }
+        }
+
+        if exports_variables(argv.as_slice()) {
+            let mut assignments = assignments.into_iter().peekable();
+            for (i, word) in argv.iter().enumerate().skip(1) {
+                if let Some((_, assignment)) = assignments.next_if(|(index, _)| *index == i) {
+                    self.plan_exported_assignment(assignment, line);
+                } else if let PlanWord::Static(name) = word {
+                    if is_valid_name(name) {
+                        self.add_step(
+                            Effect::ExportVariable {
+                                name: name.clone(),
+                                value: None,
+                            },
+                            line,
+                        );
+                    }
+                }
+            }
+        }
+
+        self.add_step(
+            Effect::RunCommand {
+                kind: CommandKind::Unknown,
+                argv,
+            },
+            line,
+        );
+    }
+
+    fn plan_exported_assignment(&mut self, assignment: &ast::Assignment, line: i32) {
+        // N.B. Array variables can't be exported.
+        if let (ast::AssignmentName::VariableName(name), ast::AssignmentValue::Scalar(value)) =
+            (&assignment.name, &assignment.value)
+        {
+            self.add_step(
+                Effect::ExportVariable {
+                    name: name.clone(),
+                    value: Some(self.expand_word(value)),
+                },
+                line,
+            );
+        }
+    }
+
+    fn plan_redirect(&mut self, redirect: &ast::IoRedirect, line: i32) {
+        match redirect {
+            ast::IoRedirect::File(_, kind, ast::IoFileRedirectTarget::Filename(target)) => {
+                let append = match kind {
+                    ast::IoFileRedirectKind::Read | ast::IoFileRedirectKind::DuplicateInput => {
+                        return;
+                    }
+                    ast::IoFileRedirectKind::Append => true,
+                    ast::IoFileRedirectKind::Write
+                    | ast::IoFileRedirectKind::ReadAndWrite
+                    | ast::IoFileRedirectKind::Clobber
+                    | ast::IoFileRedirectKind::DuplicateOutput => false,
+                };
+
+                let path = self.plan_word(target, line);
+                self.add_step(Effect::WriteFile { path, append }, line);
+            }
+            ast::IoRedirect::File(_, _, ast::IoFileRedirectTarget::ProcessSubstitution(_, s)) => {
+                self.plan_compound_list(&s.0);
+            }
+            ast::IoRedirect::OutputAndError(target, append) => {
+                let path = self.plan_word(target, line);
+                self.add_step(
+                    Effect::WriteFile {
+                        path,
+                        append: *append,
+                    },
+                    line,
+                );
+            }
+            ast::IoRedirect::File(_, _, ast::IoFileRedirectTarget::Fd(_))
+            | ast::IoRedirect::HereDocument(..)
+            | ast::IoRedirect::HereString(..) => (),
+        }
+    }
+
+    /// Statically expands the given word, if possible; also plans any command
+    /// substitutions it contains.
+    fn plan_word(&mut self, word: &ast::Word, line: i32) -> PlanWord {
+        if let Ok(pieces) = word::parse(word.value.as_str(), self.options) {
+            self.plan_command_substitutions(&pieces, line);
+        }
+
+        self.expand_word(word)
+    }
+
+    /// Statically expands the given word, if possible.
+    fn expand_word(&self, word: &ast::Word) -> PlanWord {
+        let Ok(pieces) = word::parse(word.value.as_str(), self.options) else {
+            return PlanWord::Dynamic(word.value.clone());
+        };
+
+        let mut expanded = String::new();
+        if pieces
+            .iter()
+            .all(|piece| expand_static_piece(&piece.piece, false, &mut expanded))
+        {
+            PlanWord::Static(expanded)
+        } else {
+            PlanWord::Dynamic(word.value.clone())
+        }
+    }
+
+    fn plan_command_substitutions(&mut self, pieces: &[word::WordPieceWithSource], line: i32) {
+        for piece in pieces {
+            match &piece.piece {
+                WordPiece::CommandSubstitution(source)
+                | WordPiece::BackquotedCommandSubstitution(source) => {
+                    self.plan_source(source.as_str(), line);
+                }
+                WordPiece::DoubleQuotedSequence(inner) => {
+                    self.plan_command_substitutions(inner, line);
+                }
+                _ => (),
+            }
+        }
+    }
+
+    /// Plans the program in the given source (e.g., the contents of a command substitution),
+    /// attributing its steps to the given line.
+    fn plan_source(&mut self, source: &str, line: i32) {
+        let Ok(tokens) =
+            crate::tokenize_str_with_options(source, &self.options.tokenizer_options())
+        else {
+            return;
+        };
+        let Ok(program) = crate::parse_tokens(&tokens, self.options, &crate::SourceInfo::default())
+        else {
+            return;
+        };
+
+        let enclosing_line = self.line_override.replace(line);
+        for complete_command in &program.complete_commands {
+            self.plan_compound_list(complete_command);
+        }
+        self.line_override = enclosing_line;
+    }
+}
+
+/// Appends the static expansion of the given piece to `expanded`; returns false if the
+/// piece's expansion isn't known statically.
+fn expand_static_piece(piece: &WordPiece, quoted: bool, expanded: &mut String) -> bool {
+    match piece {
+        // N.B. Unquoted text may be subject to brace or pathname expansion.
+        WordPiece::Text(s) if !quoted && s.contains(['*', '?', '[', '{']) => false,
+        WordPiece::Text(s) | WordPiece::SingleQuotedText(s) => {
+            expanded.push_str(s);
+            true
+        }
+        WordPiece::EscapeSequence(s) => {
+            expanded.push_str(s.strip_prefix('\\').unwrap_or(s));
+            true
+        }
+        WordPiece::DoubleQuotedSequence(inner) => inner
+            .iter()
+            .all(|piece| expand_static_piece(&piece.piece, true, expanded)),
+        WordPiece::AnsiCQuotedText(_)
+        | WordPiece::TildePrefix(_)
+        | WordPiece::ParameterExpansion(_)
+        | WordPiece::CommandSubstitution(_)
+        | WordPiece::BackquotedCommandSubstitution(_)
+        | WordPiece::ArithmeticExpression(_) => false,
+    }
+}
+
+/// Returns whether the given command (e.g., `export x` or `declare -x x`) exports the
+/// variables named in its arguments.
+fn exports_variables(argv: &[PlanWord]) -> bool {
+    let Some(PlanWord::Static(name)) = argv.first() else {
+        return false;
+    };
+
+    let options = || {
+        argv.iter()
+            .skip(1)
+            .map(PlanWord::as_str)
+            .take_while(|arg| arg.starts_with(['-', '+']) && *arg != "--")
+    };
+
+    match name.as_str() {
+        // N.B. `export -n` removes the export attribute, and `export -f` exports functions.
+        "export" => !options().any(|opt| opt.starts_with('-') && opt.contains(['n', 'f'])),
+        "declare" | "typeset" | "local" => {
+            options().any(|opt| opt.starts_with('-') && opt.contains('x'))
+                && !options().any(|opt| opt.starts_with('-') && opt.contains('f'))
+        }
+        _ => false,
+    }
+}
+
+fn needs_quoting(c: char) -> bool {
+    !(c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | '/' | ':' | ',' | '=' | '@' | '+'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_tokens, tokenize_str, SourceInfo};
+    use anyhow::Result;
+
+    fn plan_str(input: &str) -> Result<Vec<String>> {
+        let options = ParserOptions::default();
+        let tokens = tokenize_str(input)?;
+        let program = parse_tokens(&tokens, &options, &SourceInfo::default())?;
+
+        Ok(plan_program(&program, &options, |name| {
+            matches!(name, "cd" | "declare" | "echo" | "export" | "test")
+        })
+        .iter()
+        .map(ToString::to_string)
+        .collect())
+    }
+
+    #[test]
+    fn plan_commands_and_redirects() -> Result<()> {
+        let input = r#"cd /srv/app
+curl -o 'release file.tgz' "$URL" > log.txt 2>>errors.txt
+echo *.conf
+"#;
+
+        assert_eq!(
+            plan_str(input)?,
+            [
+                "1: run builtin: cd /srv/app",
+                "2: write file: log.txt",
+                "2: write file: errors.txt (append)",
+                "2: run external: curl -o 'release file.tgz' \"$URL\"",
+                "3: run builtin: echo *.conf",
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn plan_exports() -> Result<()> {
+        let input = r"export A=1 B
+declare -x C=$(hostname)
+export -n D
+";
+
+        assert_eq!(
+            plan_str(input)?,
+            [
+                "1: export variable: A=1",
+                "1: export variable: B",
+                "1: run builtin: export A=1 B",
+                "2: run external: hostname",
+                "2: export variable: C=$(hostname)",
+                "2: run builtin: declare -x C=$(hostname)",
+                "3: run builtin: export -n D",
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn plan_conditional_steps() -> Result<()> {
+        let input = r"deploy() {
+    rsync -a out/ host:/srv
+}
+if test -d out; then deploy; fi
+make && deploy
+";
+
+        assert_eq!(
+            plan_str(input)?,
+            [
+                "2: run external: rsync -a out/ host:/srv [in function deploy]",
+                "4: run builtin: test -d out",
+                "4: run function: deploy [conditional]",
+                "5: run external: make",
+                "5: run function: deploy [conditional]",
+            ]
+        );
+        Ok(())
+    }
+}
@@ -64,7 +64,8 @@ pub enum PromptPiece {
 pub enum PromptDateFormat {
     /// A format including weekday, month, and date.
     WeekdayMonthDate,
-    /// A customer string format.
+    /// A custom `strftime`-style format; an empty format yields the locale's time
+    /// representation.
     Custom(String),
 }
 
@@ -75,6 +76,8 @@ pub enum PromptTimeFormat {
     TwelveHourAM,
     /// A twelve-hour time format (HHMMSS).
     TwelveHourHHMMSS,
+    /// A twenty-four-hour time format (HHMM).
+    TwentyFourHourHHMM,
     /// A twenty-four-hour time format (HHMMSS).
     TwentyFourHourHHMMSS,
 }
@@ -106,6 +109,7 @@ peg::parser! {
             "\\t" { PromptPiece::Time(PromptTimeFormat::TwentyFourHourHHMMSS ) } /
             "\\T" { PromptPiece::Time(PromptTimeFormat::TwelveHourHHMMSS ) } /
             "\\@" { PromptPiece::Time(PromptTimeFormat::TwelveHourAM ) } /
+            "\\A" { PromptPiece::Time(PromptTimeFormat::TwentyFourHourHHMM ) } /
             "\\u" { PromptPiece::CurrentUser } /
             "\\v" { PromptPiece::ShellVersion } /
             "\\V" { PromptPiece::ShellRelease } /
@@ -126,7 +130,7 @@ peg::parser! {
             s:$([c if c != '}']*) { s.to_owned() }
 
         rule octal_number() -> u32 =
-            s:$(['0'..='9']*<3,3>) {? u32::from_str_radix(s, 8).or(Err("invalid octal number")) }
+            s:$(['0'..='7']*<3,3>) {? u32::from_str_radix(s, 8).or(Err("invalid octal number")) }
     }
 }
 
@@ -139,3 +143,129 @@ pub fn parse(s: &str) -> Result<Vec<PromptPiece>, error::WordParseError> {
     let result = prompt_parser::prompt(s).map_err(error::WordParseError::Prompt)?;
     Ok(result)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+    use assert_matches::assert_matches;
+
+    fn parse_single(s: &str) -> Result<PromptPiece> {
+        let mut pieces = parse(s)?;
+        assert_eq!(pieces.len(), 1, "expected a single piece for {s:?}");
+        Ok(pieces.remove(0))
+    }
+
+    #[test]
+    fn parse_character_escapes() -> Result<()> {
+        assert_matches!(parse_single(r"\a")?, PromptPiece::BellCharacter);
+        assert_matches!(parse_single(r"\e")?, PromptPiece::EscapeCharacter);
+        assert_matches!(parse_single(r"\n")?, PromptPiece::Newline);
+        assert_matches!(parse_single(r"\r")?, PromptPiece::CarriageReturn);
+        assert_matches!(parse_single(r"\\")?, PromptPiece::Backslash);
+        assert_matches!(parse_single(r"\033")?, PromptPiece::AsciiCharacter(27));
+        assert_matches!(parse_single(r"\[")?, PromptPiece::StartNonPrintingSequence);
+        assert_matches!(parse_single(r"\]")?, PromptPiece::EndNonPrintingSequence);
+        Ok(())
+    }
+
+    #[test]
+    fn parse_date_and_time_escapes() -> Result<()> {
+        assert_matches!(
+            parse_single(r"\d")?,
+            PromptPiece::Date(PromptDateFormat::WeekdayMonthDate)
+        );
+        assert_matches!(
+            parse_single(r"\D{%H:%M}")?,
+            PromptPiece::Date(PromptDateFormat::Custom(f)) if f == "%H:%M"
+        );
+        assert_matches!(
+            parse_single(r"\D{}")?,
+            PromptPiece::Date(PromptDateFormat::Custom(f)) if f.is_empty()
+        );
+        assert_matches!(
+            parse_single(r"\t")?,
+            PromptPiece::Time(PromptTimeFormat::TwentyFourHourHHMMSS)
+        );
+        assert_matches!(
+            parse_single(r"\T")?,
+            PromptPiece::Time(PromptTimeFormat::TwelveHourHHMMSS)
+        );
+        assert_matches!(
+            parse_single(r"\@")?,
+            PromptPiece::Time(PromptTimeFormat::TwelveHourAM)
+        );
+        assert_matches!(
+            parse_single(r"\A")?,
+            PromptPiece::Time(PromptTimeFormat::TwentyFourHourHHMM)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn parse_shell_state_escapes() -> Result<()> {
+        assert_matches!(
+            parse_single(r"\h")?,
+            PromptPiece::Hostname {
+                only_up_to_first_dot: true
+            }
+        );
+        assert_matches!(
+            parse_single(r"\H")?,
+            PromptPiece::Hostname {
+                only_up_to_first_dot: false
+            }
+        );
+        assert_matches!(parse_single(r"\j")?, PromptPiece::NumberOfManagedJobs);
+        assert_matches!(parse_single(r"\l")?, PromptPiece::TerminalDeviceBaseName);
+        assert_matches!(parse_single(r"\s")?, PromptPiece::ShellBaseName);
+        assert_matches!(parse_single(r"\u")?, PromptPiece::CurrentUser);
+        assert_matches!(parse_single(r"\v")?, PromptPiece::ShellVersion);
+        assert_matches!(parse_single(r"\V")?, PromptPiece::ShellRelease);
+        assert_matches!(
+            parse_single(r"\w")?,
+            PromptPiece::CurrentWorkingDirectory {
+                tilde_replaced: true,
+                basename: false
+            }
+        );
+        assert_matches!(
+            parse_single(r"\W")?,
+            PromptPiece::CurrentWorkingDirectory {
+                tilde_replaced: true,
+                basename: true
+            }
+        );
+        assert_matches!(parse_single(r"\!")?, PromptPiece::CurrentHistoryNumber);
+        assert_matches!(parse_single(r"\#")?, PromptPiece::CurrentCommandNumber);
+        assert_matches!(parse_single(r"\$")?, PromptPiece::DollarOrPound);
+        Ok(())
+    }
+
+    #[test]
+    fn parse_literals() -> Result<()> {
+        assert_matches!(parse_single("abc")?, PromptPiece::Literal(s) if s == "abc");
+
+        // Unrecognized escapes, and backslashes followed by non-octal digits, are literal.
+        assert_matches!(parse_single(r"\z")?, PromptPiece::Literal(s) if s == r"\z");
+        assert_matches!(parse_single(r"\089")?, PromptPiece::Literal(s) if s == r"\089");
+
+        let pieces = parse(r"[\u@\h \W]\$ ")?;
+        assert_matches!(
+            &pieces[..],
+            [
+                PromptPiece::Literal(open),
+                PromptPiece::CurrentUser,
+                PromptPiece::Literal(at),
+                PromptPiece::Hostname { .. },
+                PromptPiece::Literal(space),
+                PromptPiece::CurrentWorkingDirectory { basename: true, .. },
+                PromptPiece::Literal(close),
+                PromptPiece::DollarOrPound,
+                PromptPiece::Literal(trailing),
+            ] if open == "[" && at == "@" && space == " " && close == "]" && trailing == " "
+        );
+
+        Ok(())
+    }
+}
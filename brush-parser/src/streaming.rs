@@ -0,0 +1,322 @@
+//! Parsing of commands from input that arrives a piece at a time, such as input read
+//! from a terminal or a pipe.
+
+use crate::ast;
+use crate::continuation::UnterminatedConstruct;
+use crate::error;
+use crate::incremental::IncrementalParser;
+use crate::parser::{ParserOptions, SourceInfo};
+use crate::tokenizer::SourcePosition;
+
+/// Parses complete programs out of input that's fed to it incrementally. Input is only
+/// considered complete at the end of a line, so a command is never parsed before all
+/// of its line has been received. The locations of parsed tokens are relative to the
+/// start of all input fed to the parser.
+pub struct StreamingParser {
+    options: ParserOptions,
+    source_info: SourceInfo,
+    /// Input that's been fed to the parser but not yet decoded, because it ends partway
+    /// through a UTF-8 sequence.
+    undecoded: Vec<u8>,
+    /// Input that's been decoded but isn't yet part of a complete program.
+    pending: String,
+    /// Parser used to parse the pending input; reuses work from previous polls.
+    parser: IncrementalParser,
+    /// The construct left open by the pending input, as of the last poll.
+    unterminated: Option<UnterminatedConstruct>,
+    /// The position, within all input fed to the parser, at which the pending input begins.
+    position: SourcePosition,
+}
+
+/// The result of polling a [`StreamingParser`].
+#[derive(Debug)]
+pub enum StreamingParseResult {
+    /// More input is needed before a complete program can be parsed.
+    NeedMoreInput,
+    /// A complete program was parsed.
+    Complete {
+        /// The program.
+        program: ast::Program,
+        /// The input the program was parsed from.
+        text: String,
+    },
+    /// The input contains a syntax error that no additional input could resolve.
+    Error {
+        /// The error.
+        error: error::ParseError,
+        /// The input that contained the error.
+        text: String,
+    },
+}
+
+impl StreamingParser {
+    /// Creates a new streaming parser.
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - Options controlling how input is parsed.
+    /// * `source_info` - Information about the source of the input.
+    pub fn new(options: &ParserOptions, source_info: &SourceInfo) -> Self {
+        Self {
+            options: options.clone(),
+            source_info: source_info.clone(),
+            undecoded: vec![],
+            pending: String::new(),
+            parser: IncrementalParser::new(),
+            unterminated: None,
+            position: SourcePosition {
+                index: 0,
+                line: 1,
+                column: 1,
+            },
+        }
+    }
+
+    /// Updates the options used to parse input that hasn't yet been returned as part of a
+    /// complete program (e.g., to reflect aliases defined by a program already returned).
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - Options controlling how input is parsed.
+    pub fn set_options(&mut self, options: &ParserOptions) {
+        self.options = options.clone();
+    }
+
+    /// Provides more input to the parser.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The input. It need not end at a line or character boundary.
+    pub fn feed(&mut self, input: impl AsRef<[u8]>) {
+        self.undecoded.extend_from_slice(input.as_ref());
+
+        loop {
+            match std::str::from_utf8(&self.undecoded) {
+                Ok(decoded) => {
+                    self.pending.push_str(decoded);
+                    self.undecoded.clear();
+                    break;
+                }
+                Err(e) => {
+                    let valid_len = e.valid_up_to();
+                    self.pending
+                        .push_str(String::from_utf8_lossy(&self.undecoded[..valid_len]).as_ref());
+
+                    // Replace invalid sequences; leave an incomplete trailing sequence to
+                    // be completed by later input.
+                    let Some(invalid_len) = e.error_len() else {
+                        self.undecoded.drain(..valid_len);
+                        break;
+                    };
+
+                    self.pending.push(char::REPLACEMENT_CHARACTER);
+                    self.undecoded.drain(..valid_len + invalid_len);
+                }
+            }
+        }
+    }
+
+    /// Returns whether there's input that hasn't yet been returned as part of a
+    /// complete program or error.
+    pub fn has_pending_input(&self) -> bool {
+        !self.pending.is_empty() || !self.undecoded.is_empty()
+    }
+
+    /// Attempts to parse a complete program from the input fed to the parser so far.
+    /// When a program (or error) is returned, the input it was parsed from is consumed.
+    pub fn poll(&mut self) -> StreamingParseResult {
+        // N.B. The tokenizer treats a line continuation at the end of its input as complete,
+        // so we wait for the line that continues it.
+        if !self.pending.ends_with('\n') || ends_with_line_continuation(self.pending.as_str()) {
+            return StreamingParseResult::NeedMoreInput;
+        }
+
+        match self
+            .parser
+            .parse(self.pending.as_str(), &self.options, &self.source_info)
+        {
+            Err(error) if error.is_incomplete() => {
+                self.unterminated = self.parser.unterminated_construct(&error);
+                StreamingParseResult::NeedMoreInput
+            }
+            result => self.consume(result),
+        }
+    }
+
+    /// Returns the construct left open by the pending input, if known, as of the last
+    /// time the parser was polled.
+    pub fn unterminated_construct(&self) -> Option<&UnterminatedConstruct> {
+        self.unterminated.as_ref()
+    }
+
+    /// Parses whatever input remains, given that no more input will be provided. Returns
+    /// [`StreamingParseResult::NeedMoreInput`] only if there's no input remaining.
+    pub fn finish(&mut self) -> StreamingParseResult {
+        if !self.undecoded.is_empty() {
+            self.pending
+                .push_str(String::from_utf8_lossy(&self.undecoded).as_ref());
+            self.undecoded.clear();
+        }
+
+        if self.pending.is_empty() {
+            return StreamingParseResult::NeedMoreInput;
+        }
+
+        let result = self
+            .parser
+            .parse(self.pending.as_str(), &self.options, &self.source_info);
+        self.consume(result)
+    }
+
+    fn consume(&mut self, result: Result<ast::Program, error::ParseError>) -> StreamingParseResult {
+        self.unterminated = None;
+        let text = std::mem::take(&mut self.pending);
+
+        // Input following what's been consumed begins where it ends.
+        for c in text.chars() {
+            self.position.index += 1;
+            if c == '\n' {
+                self.position.line += 1;
+                self.position.column = 1;
+            } else {
+                self.position.column += 1;
+            }
+        }
+        self.parser = IncrementalParser::new_at(&self.position);
+
+        match result {
+            Ok(program) => StreamingParseResult::Complete { program, text },
+            Err(error) => StreamingParseResult::Error { error, text },
+        }
+    }
+}
+
+/// Returns whether the given text ends with a line continuation, i.e., a newline preceded by
+/// an unescaped backslash.
+fn ends_with_line_continuation(text: &str) -> bool {
+    let Some(line) = text.strip_suffix('\n') else {
+        return false;
+    };
+
+    line.bytes().rev().take_while(|b| *b == b'\\').count() % 2 == 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_parser() -> StreamingParser {
+        StreamingParser::new(&ParserOptions::default(), &SourceInfo::default())
+    }
+
+    #[test]
+    fn complete_commands() {
+        let mut parser = new_parser();
+
+        parser.feed("if true; th");
+        assert!(matches!(parser.poll(), StreamingParseResult::NeedMoreInput));
+        parser.feed("en\n  echo 'a\n");
+        assert!(matches!(parser.poll(), StreamingParseResult::NeedMoreInput));
+        assert_eq!(
+            parser.unterminated_construct(),
+            Some(&UnterminatedConstruct::SingleQuote)
+        );
+        parser.feed("b'\nfi\necho");
+        assert!(matches!(parser.poll(), StreamingParseResult::NeedMoreInput));
+        parser.feed(" done\n");
+
+        let StreamingParseResult::Complete { program, text } = parser.poll() else {
+            panic!("expected a complete program");
+        };
+        assert_eq!(program.complete_commands.len(), 2);
+        assert_eq!(text, "if true; then\n  echo 'a\nb'\nfi\necho done\n");
+        assert!(!parser.has_pending_input());
+    }
+
+    #[test]
+    fn line_continuations() {
+        let mut parser = new_parser();
+
+        parser.feed("echo a \\\n");
+        assert!(matches!(parser.poll(), StreamingParseResult::NeedMoreInput));
+        parser.feed("b\n");
+        let StreamingParseResult::Complete { program, text } = parser.poll() else {
+            panic!("expected a complete program");
+        };
+        assert_eq!(program.complete_commands.len(), 1);
+        assert_eq!(text, "echo a \\\nb\n");
+
+        // An escaped backslash doesn't continue the line.
+        parser.feed("echo a \\\\\n");
+        assert!(matches!(
+            parser.poll(),
+            StreamingParseResult::Complete { .. }
+        ));
+    }
+
+    #[test]
+    fn locations_and_options_span_programs() {
+        let mut parser = new_parser();
+
+        parser.feed("echo a\nll\n");
+        assert!(matches!(
+            parser.poll(),
+            StreamingParseResult::Complete { .. }
+        ));
+
+        parser.set_options(&ParserOptions {
+            aliases: [(String::from("ll"), String::from("ls -l"))].into(),
+            ..ParserOptions::default()
+        });
+        parser.feed("\n  ll\n");
+        let StreamingParseResult::Complete { program, text } = parser.poll() else {
+            panic!("expected a complete program");
+        };
+        assert_eq!(text, "\n  ll\n");
+        assert!(program.to_string().contains("ls -l"));
+        let location = &program.complete_command_locations[0];
+        assert_eq!(
+            (
+                location.start.index,
+                location.start.line,
+                location.start.column
+            ),
+            (13, 4, 3)
+        );
+    }
+
+    #[test]
+    fn errors_and_end_of_input() {
+        let mut parser = new_parser();
+
+        parser.feed("echo )\n");
+        assert!(matches!(
+            parser.poll(),
+            StreamingParseResult::Error {
+                error: error::ParseError::ParsingNearToken(_),
+                ..
+            }
+        ));
+
+        // N.B. The two bytes of a UTF-8 sequence may arrive separately.
+        parser.feed(b"echo \xc3");
+        parser.feed(b"\xa9");
+        let StreamingParseResult::Complete { text, .. } = parser.finish() else {
+            panic!("expected a complete program");
+        };
+        assert_eq!(text, "echo \u{e9}");
+
+        parser.feed("while true; do\n");
+        assert!(matches!(
+            parser.finish(),
+            StreamingParseResult::Error {
+                error: error::ParseError::ParsingAtEndOfInput,
+                ..
+            }
+        ));
+        assert!(matches!(
+            parser.finish(),
+            StreamingParseResult::NeedMoreInput
+        ));
+    }
+}
@@ -2,53 +2,100 @@
 
 use crate::{ast, error};
 
-/// Parses a test command expression.
+/// Parses a test command expression. Per POSIX, expressions of up to 4 arguments are
+/// disambiguated by how many arguments there are (e.g., `test "$a" = "$b"` compares two
+/// strings even if `$a` is `-f`); longer expressions are parsed by operator precedence.
 ///
 /// # Arguments
 ///
 /// * `input` - The test command expression to parse, in string form.
 pub fn parse(input: &[String]) -> Result<ast::TestExpr, error::TestCommandParseError> {
     let input: Vec<_> = input.iter().map(|s| s.as_str()).collect();
+    parse_args(input.as_slice())
+}
 
-    let expr = test_command::full_expression(input.as_slice())
-        .map_err(error::TestCommandParseError::TestCommand)?;
-
-    Ok(expr)
+fn parse_args(args: &[&str]) -> Result<ast::TestExpr, error::TestCommandParseError> {
+    match args {
+        [] => Ok(ast::TestExpr::False),
+        [s] => Ok(literal(s)),
+        [first, second] => parse_two_args(first, second),
+        [first, second, third] => parse_three_args(first, second, third),
+        ["!", first, second, third] => Ok(ast::TestExpr::Not(Box::from(parse_three_args(
+            first, second, third,
+        )?))),
+        ["(", first, second, ")"] => parse_two_args(first, second),
+        _ => test_command::expression(args).map_err(error::TestCommandParseError::TestCommand),
+    }
 }
 
-peg::parser! {
-    grammar test_command<'a>() for [&'a str] {
-        pub(crate) rule full_expression() -> ast::TestExpr =
-            end() { ast::TestExpr::False } /
-            e:one_arg_expr() end() { e } /
-            e:two_arg_expr() end()  { e } /
-            e:three_arg_expr() end()  { e } /
-            e:four_arg_expr() end()  { e } /
-            expression()
-
-        rule one_arg_expr() -> ast::TestExpr =
-            [s] { ast::TestExpr::Literal(s.to_owned()) }
+fn parse_two_args(
+    first: &str,
+    second: &str,
+) -> Result<ast::TestExpr, error::TestCommandParseError> {
+    if first == "!" {
+        return Ok(ast::TestExpr::Not(Box::from(literal(second))));
+    }
 
-        rule two_arg_expr() -> ast::TestExpr =
-            ["!"] e:one_arg_expr() { ast::TestExpr::Not(Box::from(e)) } /
-            op:unary_op() [s] { ast::TestExpr::UnaryTest(op, s.to_owned()) } /
-            [_] [_] { ast::TestExpr::False }
+    match test_command::unary_op(&[first]) {
+        Ok(op) => Ok(ast::TestExpr::UnaryTest(op, second.to_owned())),
+        Err(_) => Err(error::TestCommandParseError::UnaryOperatorExpected(
+            first.to_owned(),
+        )),
+    }
+}
 
-        rule three_arg_expr() -> ast::TestExpr =
-            [left] ["-a"] [right] { ast::TestExpr::And(Box::from(ast::TestExpr::Literal(left.to_owned())), Box::from(ast::TestExpr::Literal(right.to_owned()))) } /
-            [left] ["-o"] [right] { ast::TestExpr::Or(Box::from(ast::TestExpr::Literal(left.to_owned())), Box::from(ast::TestExpr::Literal(right.to_owned()))) } /
-            [left] op:binary_op() [right] { ast::TestExpr::BinaryTest(op, left.to_owned(), right.to_owned()) } /
-            ["!"] e:two_arg_expr() { ast::TestExpr::Not(Box::from(e)) } /
-            ["("] e:one_arg_expr() [")"] { e } /
-            [_] [_] [_] { ast::TestExpr::False }
+fn parse_three_args(
+    first: &str,
+    second: &str,
+    third: &str,
+) -> Result<ast::TestExpr, error::TestCommandParseError> {
+    // N.B. A binary operator in the middle takes priority over a leading `!` or `(`.
+    match second {
+        "-a" => {
+            return Ok(ast::TestExpr::And(
+                Box::from(literal(first)),
+                Box::from(literal(third)),
+            ))
+        }
+        "-o" => {
+            return Ok(ast::TestExpr::Or(
+                Box::from(literal(first)),
+                Box::from(literal(third)),
+            ))
+        }
+        _ => (),
+    }
 
-        rule four_arg_expr() -> ast::TestExpr =
-            ["!"] e:three_arg_expr() { ast::TestExpr::Not(Box::from(e)) }
+    if let Ok(op) = test_command::binary_op(&[second]) {
+        Ok(ast::TestExpr::BinaryTest(
+            op,
+            first.to_owned(),
+            third.to_owned(),
+        ))
+    } else if first == "!" {
+        Ok(ast::TestExpr::Not(Box::from(parse_two_args(
+            second, third,
+        )?)))
+    } else if first == "(" && third == ")" {
+        Ok(literal(second))
+    } else {
+        Err(error::TestCommandParseError::BinaryOperatorExpected(
+            second.to_owned(),
+        ))
+    }
+}
 
-        rule expression() -> ast::TestExpr = precedence! {
-            left:(@) ["-a"] right:@ { ast::TestExpr::And(Box::from(left), Box::from(right)) }
+fn literal(s: &str) -> ast::TestExpr {
+    ast::TestExpr::Literal(s.to_owned())
+}
+
+peg::parser! {
+    grammar test_command<'a>() for [&'a str] {
+        pub(crate) rule expression() -> ast::TestExpr = precedence! {
             left:(@) ["-o"] right:@ { ast::TestExpr::Or(Box::from(left), Box::from(right)) }
             --
+            left:(@) ["-a"] right:@ { ast::TestExpr::And(Box::from(left), Box::from(right)) }
+            --
             ["("] e:expression() [")"] { ast::TestExpr::Parenthesized(Box::from(e)) }
             --
             ["!"] e:@ { ast::TestExpr::Not(Box::from(e)) }
@@ -60,7 +107,7 @@ peg::parser! {
             [s] { ast::TestExpr::Literal(s.to_owned()) }
         }
 
-        rule unary_op() -> ast::UnaryPredicate =
+        pub(crate) rule unary_op() -> ast::UnaryPredicate =
             ["-a"] { ast::UnaryPredicate::FileExists } /
             ["-b"] { ast::UnaryPredicate::FileExistsAndIsBlockSpecialFile } /
             ["-c"] { ast::UnaryPredicate::FileExistsAndIsCharSpecialFile } /
@@ -88,7 +135,7 @@ peg::parser! {
             ["-R"] { ast::UnaryPredicate::ShellVariableIsSetAndNameRef } /
             ["-S"] { ast::UnaryPredicate::FileExistsAndIsSocket }
 
-        rule binary_op() -> ast::BinaryPredicate =
+        pub(crate) rule binary_op() -> ast::BinaryPredicate =
             ["=="] { ast::BinaryPredicate::StringExactlyMatchesPattern } /
             ["-ef"] { ast::BinaryPredicate::FilesReferToSameDeviceAndInodeNumbers } /
             ["-eq"] { ast::BinaryPredicate::ArithmeticEqualTo } /
@@ -108,3 +155,57 @@ peg::parser! {
         rule end() = ![_]
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn disambiguates_by_arg_count() -> Result<(), error::TestCommandParseError> {
+        assert!(matches!(
+            parse_args(&["-f", "=", "-f"])?,
+            ast::TestExpr::BinaryTest(ast::BinaryPredicate::StringExactlyMatchesPattern, ..)
+        ));
+        assert!(matches!(
+            parse_args(&["!", "=", "x"])?,
+            ast::TestExpr::BinaryTest(ast::BinaryPredicate::StringExactlyMatchesPattern, ..)
+        ));
+        assert!(matches!(
+            parse_args(&["!", "-z", "x"])?,
+            ast::TestExpr::Not(inner) if matches!(*inner, ast::TestExpr::UnaryTest(..))
+        ));
+        assert!(matches!(
+            parse_args(&["(", "-n", ")"])?,
+            ast::TestExpr::Literal(s) if s == "-n"
+        ));
+        assert!(matches!(
+            parse_args(&["(", "-n", "x", ")"])?,
+            ast::TestExpr::UnaryTest(ast::UnaryPredicate::StringHasNonZeroLength, _)
+        ));
+        assert!(matches!(
+            parse_args(&["!", "a", "=", "b"])?,
+            ast::TestExpr::Not(inner) if matches!(*inner, ast::TestExpr::BinaryTest(..))
+        ));
+
+        assert!(matches!(
+            parse_args(&["a", "b"]),
+            Err(error::TestCommandParseError::UnaryOperatorExpected(_))
+        ));
+        assert!(matches!(
+            parse_args(&["a", "b", "c"]),
+            Err(error::TestCommandParseError::BinaryOperatorExpected(_))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or() -> Result<(), error::TestCommandParseError> {
+        assert!(matches!(
+            parse_args(&["a", "-o", "b", "-a", "c"])?,
+            ast::TestExpr::Or(_, right) if matches!(*right, ast::TestExpr::And(..))
+        ));
+
+        Ok(())
+    }
+}
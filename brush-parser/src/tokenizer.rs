@@ -30,6 +30,8 @@ pub(crate) enum TokenEndReason {
 /// Represents a position in a source shell script.
 #[derive(Clone, Default, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(test, derive(PartialEq, Eq))]
 pub struct SourcePosition {
     /// The 0-based index of the character in the input stream.
     pub index: i32,
@@ -48,6 +50,8 @@ impl Display for SourcePosition {
 /// Represents the location of a token in its source shell script.
 #[derive(Clone, Default, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(test, derive(PartialEq, Eq))]
 pub struct TokenLocation {
     /// The start position of the token.
     pub start: SourcePosition,
@@ -58,6 +62,7 @@ pub struct TokenLocation {
 /// Represents a token extracted from a shell script.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum Token {
     /// An operator token.
     Operator(String, TokenLocation),
@@ -83,6 +88,32 @@ impl Token {
     }
 }
 
+/// Kinds of trivia that may be captured from a shell script.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum TriviaKind {
+    /// A comment, including its leading '#'.
+    Comment,
+    /// A run of non-newline blank characters separating tokens.
+    Whitespace,
+}
+
+/// Represents a fragment of a shell script that isn't meaningful to the parser (e.g., a
+/// comment), but which may be of interest to tooling that reproduces the original source.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+pub struct Trivia {
+    /// The kind of trivia.
+    pub kind: TriviaKind,
+    /// The text of the trivia, exactly as it appeared in the source.
+    pub text: String,
+    /// The location of the trivia in the source script.
+    pub location: TokenLocation,
+}
+
 /// Encapsulates the result of tokenizing a shell script.
 #[derive(Clone, Debug)]
 pub(crate) struct TokenizeResult {
@@ -217,6 +248,8 @@ struct CrossTokenParseState {
     queued_tokens: Vec<TokenizeResult>,
     /// Are we in an arithmetic expansion?
     arithmetic_expansion: bool,
+    /// Trivia captured so far, if trivia capture is enabled.
+    trivia: Vec<Trivia>,
 }
 
 /// Options controlling how the tokenizer operates.
@@ -229,6 +262,8 @@ pub struct TokenizerOptions {
     pub posix_mode: bool,
     /// Whether or not we're running in SH emulation mode.
     pub sh_mode: bool,
+    /// Whether or not to capture comments and whitespace trivia (instead of discarding them).
+    pub capture_trivia: bool,
 }
 
 impl Default for TokenizerOptions {
@@ -237,6 +272,7 @@ impl Default for TokenizerOptions {
             enable_extended_globbing: true,
             posix_mode: false,
             sh_mode: false,
+            capture_trivia: false,
         }
     }
 }
@@ -511,19 +547,32 @@ fn cacheable_tokenize_str(
 
 impl<'a, R: ?Sized + std::io::BufRead> Tokenizer<'a, R> {
     pub fn new(reader: &'a mut R, options: &TokenizerOptions) -> Tokenizer<'a, R> {
+        let start = SourcePosition {
+            index: 0,
+            line: 1,
+            column: 1,
+        };
+
+        Self::new_at(reader, options, &start)
+    }
+
+    /// Creates a tokenizer for input that begins at the given position within a larger
+    /// input stream; the locations of tokens are relative to the start of that stream.
+    pub fn new_at(
+        reader: &'a mut R,
+        options: &TokenizerOptions,
+        start: &SourcePosition,
+    ) -> Tokenizer<'a, R> {
         Tokenizer {
             options: options.clone(),
             char_reader: reader.chars().peekable(),
             cross_state: CrossTokenParseState {
-                cursor: SourcePosition {
-                    index: 0,
-                    line: 1,
-                    column: 1,
-                },
+                cursor: start.clone(),
                 here_state: HereState::None,
                 current_here_tags: vec![],
                 queued_tokens: vec![],
                 arithmetic_expansion: false,
+                trivia: vec![],
             },
         }
     }
@@ -532,6 +581,47 @@ impl<'a, R: ?Sized + std::io::BufRead> Tokenizer<'a, R> {
         Some(self.cross_state.cursor.clone())
     }
 
+    /// Returns whether a new tokenizer started at the current position would produce the
+    /// same tokens that this one will from here on, i.e., whether we're at the start of a
+    /// line and no state carries over from earlier in the input.
+    pub fn is_at_resumable_position(&self) -> bool {
+        self.cross_state.cursor.column == 1
+            && matches!(self.cross_state.here_state, HereState::None)
+            && self.cross_state.current_here_tags.is_empty()
+            && self.cross_state.queued_tokens.is_empty()
+            && !self.cross_state.arithmetic_expansion
+    }
+
+    /// Removes and returns all trivia captured so far. Always returns an empty list
+    /// if trivia capture wasn't enabled in the tokenizer's options.
+    pub fn take_trivia(&mut self) -> Vec<Trivia> {
+        std::mem::take(&mut self.cross_state.trivia)
+    }
+
+    fn capture_trivia(&mut self, kind: TriviaKind, c: char, start: &SourcePosition) {
+        if !self.options.capture_trivia {
+            return;
+        }
+
+        // Coalesce adjacent trivia of the same kind.
+        if let Some(last) = self.cross_state.trivia.last_mut() {
+            if last.kind == kind && last.location.end.index == start.index {
+                last.text.push(c);
+                last.location.end = self.cross_state.cursor.clone();
+                return;
+            }
+        }
+
+        self.cross_state.trivia.push(Trivia {
+            kind,
+            text: c.to_string(),
+            location: TokenLocation {
+                start: start.clone(),
+                end: self.cross_state.cursor.clone(),
+            },
+        });
+    }
+
     fn next_char(&mut self) -> Result<Option<char>, TokenizerError> {
         let c = self
             .char_reader
@@ -1073,7 +1163,14 @@ impl<'a, R: ?Sized + std::io::BufRead> Tokenizer<'a, R> {
                     state.start_position.index += 1;
                 }
 
+                let start = self.cross_state.cursor.clone();
                 self.consume_char()?;
+
+                // N.B. Blanks within nested constructs (e.g., command substitutions) are
+                // part of the enclosing token, not trivia.
+                if terminating_char.is_none() {
+                    self.capture_trivia(TriviaKind::Whitespace, c, &start);
+                }
             }
             //
             // N.B. We need to remember if we were recursively called, say in a command
@@ -1086,16 +1183,20 @@ impl<'a, R: ?Sized + std::io::BufRead> Tokenizer<'a, R> {
                 state.append_char(c);
             } else if c == '#' {
                 // Consume the '#'.
+                let start = self.cross_state.cursor.clone();
                 self.consume_char()?;
+                self.capture_trivia(TriviaKind::Comment, c, &start);
 
                 let mut done = false;
                 while !done {
                     done = match self.peek_char()? {
                         Some('\n') => true,
                         None => true,
-                        _ => {
+                        Some(c) => {
                             // Consume the peeked char; it's part of the comment.
+                            let start = self.cross_state.cursor.clone();
                             self.consume_char()?;
+                            self.capture_trivia(TriviaKind::Comment, c, &start);
                             false
                         }
                     };
@@ -1293,6 +1394,34 @@ bc",
         Ok(())
     }
 
+    #[test]
+    fn tokenize_capturing_trivia() -> Result<()> {
+        let options = TokenizerOptions {
+            capture_trivia: true,
+            ..TokenizerOptions::default()
+        };
+
+        let mut reader = std::io::BufReader::new("a  $(b c) #comment".as_bytes());
+        let mut tokenizer = Tokenizer::new(&mut reader, &options);
+        while !matches!(tokenizer.next_token()?.reason, TokenEndReason::EndOfInput) {}
+
+        let trivia: Vec<_> = tokenizer
+            .take_trivia()
+            .into_iter()
+            .map(|t| (t.kind, t.text, t.location.start.column))
+            .collect();
+
+        assert_eq!(
+            trivia,
+            [
+                (TriviaKind::Whitespace, "  ".to_owned(), 2),
+                (TriviaKind::Whitespace, " ".to_owned(), 10),
+                (TriviaKind::Comment, "#comment".to_owned(), 11),
+            ]
+        );
+        Ok(())
+    }
+
     #[test]
     fn tokenize_empty_here_doc() -> Result<()> {
         let tokens = tokenize_str(
@@ -402,6 +402,9 @@ pub enum BraceExpressionMember {
         high: i64,
         /// Increment value.
         increment: i64,
+        /// Minimum width to which values are zero-padded (as when either end of the
+        /// sequence is written with leading zeros), or 0 if they aren't.
+        width: usize,
     },
     /// An inclusive character sequence.
     CharSequence {
@@ -414,6 +417,8 @@ pub enum BraceExpressionMember {
     },
     /// Text.
     Text(String),
+    /// Text combined with nested brace expressions (e.g., `a{1,2}` in `{a{1,2},b}`).
+    Compound(Vec<BraceExpressionOrText>),
 }
 
 impl BraceExpressionMember {
@@ -424,22 +429,72 @@ impl BraceExpressionMember {
                 low,
                 high,
                 increment,
-            } => Box::new(
-                (low..=high)
-                    .step_by(increment as usize)
-                    .map(|n| n.to_string()),
-            ),
+                width,
+            } => Box::new(sequence(low, high, increment).map(move |n| format!("{n:0width$}"))),
             BraceExpressionMember::CharSequence {
                 low,
                 high,
                 increment,
             } => Box::new(
-                (low..=high)
-                    .step_by(increment as usize)
-                    .map(|c| c.to_string()),
+                sequence(
+                    i64::from(u32::from(low)),
+                    i64::from(u32::from(high)),
+                    increment,
+                )
+                .filter_map(|n| u32::try_from(n).ok().and_then(char::from_u32))
+                .map(|c| c.to_string()),
             ),
             BraceExpressionMember::Text(text) => Box::new(std::iter::once(text)),
+            BraceExpressionMember::Compound(pieces) => {
+                let mut expansions = vec![String::new()];
+                for piece in pieces {
+                    let piece_expansions: Vec<_> = piece.generate().collect();
+                    expansions = expansions
+                        .iter()
+                        .flat_map(|prefix| {
+                            piece_expansions
+                                .iter()
+                                .map(move |suffix| format!("{prefix}{suffix}"))
+                        })
+                        .collect();
+                }
+                Box::new(expansions.into_iter())
+            }
+        }
+    }
+}
+
+/// Generates an inclusive sequence running from `start` to `end`, in whichever direction
+/// that is. As in bash, the sign of the increment is ignored, and an increment of 0 is
+/// treated as 1.
+fn sequence(start: i64, end: i64, increment: i64) -> impl Iterator<Item = i64> {
+    let step = increment.unsigned_abs().max(1);
+    let count = start.abs_diff(end) / step + 1;
+    let descending = end < start;
+
+    (0..count).map_while(move |i| {
+        let offset = i * step;
+        if descending {
+            start.checked_sub_unsigned(offset)
+        } else {
+            start.checked_add_unsigned(offset)
         }
+    })
+}
+
+/// Returns the width to which the values of a numeric brace sequence are zero-padded,
+/// given the text of its ends: if either is written with leading zeros, values are padded
+/// to the width of the wider end.
+fn zero_padded_width(low: &str, high: &str) -> usize {
+    let has_leading_zero = |s: &str| {
+        let digits = s.strip_prefix('-').unwrap_or(s);
+        digits.len() > 1 && digits.starts_with('0')
+    };
+
+    if has_leading_zero(low) || has_leading_zero(high) {
+        low.len().max(high.len())
+    } else {
+        0
     }
 }
 
@@ -504,15 +559,40 @@ peg::parser! {
         pub(crate) rule unexpanded_word() -> Vec<WordPieceWithSource> = word(<![_]>)
 
         rule word<T>(stop_condition: rule<T>) -> Vec<WordPieceWithSource> =
-            tilde:tilde_prefix_with_source()? pieces:word_piece_with_source(<stop_condition()>, false /*in_command*/)* {
+            tilde:tilde_prefix_with_source()? pieces:word_pieces_with_source(<stop_condition()>)* {
                 let mut all_pieces = Vec::new();
                 if let Some(tilde) = tilde {
                     all_pieces.push(tilde);
                 }
-                all_pieces.extend(pieces);
+                all_pieces.extend(pieces.into_iter().flatten());
                 all_pieces
             }
 
+        rule word_pieces_with_source<T>(stop_condition: rule<T>) -> Vec<WordPieceWithSource> =
+            tilde_after_colon_parsing_enabled() colon:colon_with_source() tilde:tilde_prefix_with_source()? {
+                let mut pieces = vec![colon];
+                pieces.extend(tilde);
+                pieces
+            } /
+            piece:word_piece_with_source(<stop_condition_or_colon(<stop_condition()>)>, false /*in_command*/) {
+                vec![piece]
+            }
+
+        // N.B. When tildes are expanded after colons, text is broken up at each colon so
+        // that any tilde prefix following it can be recognized.
+        rule stop_condition_or_colon<T>(stop_condition: rule<T>) -> () =
+            stop_condition() {} /
+            tilde_after_colon_parsing_enabled() ":" {}
+
+        rule colon_with_source() -> WordPieceWithSource =
+            start_index:position!() ":" end_index:position!() {
+                WordPieceWithSource {
+                    piece: WordPiece::Text(":".to_owned()),
+                    start_index,
+                    end_index
+                }
+            }
+
         pub(crate) rule brace_expansions() -> Option<Vec<BraceExpressionOrText>> =
             pieces:(brace_expansion_piece()+) { Some(pieces) } /
             [_]* { None }
@@ -541,20 +621,41 @@ peg::parser! {
 
         rule brace_text_list_member() -> BraceExpression =
             &[',' | '}'] { vec![BraceExpressionMember::Text(String::new())] } /
-            brace_expr() /
-            text:$(word_piece(<[',' | '}']>, false)) {
-                vec![BraceExpressionMember::Text(text.to_owned())]
+            pieces:brace_text_list_member_piece()+ {
+                let mut pieces = pieces;
+                if pieces.len() == 1 {
+                    match pieces.remove(0) {
+                        BraceExpressionOrText::Expr(members) => members,
+                        BraceExpressionOrText::Text(text) => vec![BraceExpressionMember::Text(text)],
+                    }
+                } else {
+                    vec![BraceExpressionMember::Compound(pieces)]
+                }
             }
 
+        rule brace_text_list_member_piece() -> BraceExpressionOrText =
+            expr:brace_expr() { BraceExpressionOrText::Expr(expr) } /
+            text:$(brace_text_list_member_text()+) { BraceExpressionOrText::Text(text.to_owned()) }
+
+        rule brace_text_list_member_text() -> () =
+            !"{" word_piece(<[',' | '}' | '{']>, false) {} /
+            !brace_expr() "{" {}
+
         rule brace_sequence_expr() -> BraceExpressionMember =
-            low:number() ".." high:number() increment:(".." n:number() { n })? {
-                BraceExpressionMember::NumberSequence { low, high, increment: increment.unwrap_or(1) }
+            low:number_text() ".." high:number_text() increment:(".." n:number() { n })? {?
+                Ok(BraceExpressionMember::NumberSequence {
+                    low: low.parse().or(Err("number"))?,
+                    high: high.parse().or(Err("number"))?,
+                    increment: increment.unwrap_or(1),
+                    width: zero_padded_width(low, high),
+                })
             } /
             low:character() ".." high:character() increment:(".." n:number() { n })? {
                 BraceExpressionMember::CharSequence { low, high, increment: increment.unwrap_or(1) }
             }
 
-        rule number() -> i64 = n:$(['0'..='9']+) { n.parse().unwrap() }
+        rule number() -> i64 = n:number_text() {? n.parse().or(Err("number")) }
+        rule number_text() -> &'input str = $("-"? ['0'..='9']+)
         rule character() -> char = ['a'..='z' | 'A'..='Z']
 
         // N.B. We don't bother returning the word pieces, as all users of this rule
@@ -857,6 +958,9 @@ peg::parser! {
 
         rule tilde_parsing_enabled() -> () =
             &[_] {? if parser_options.tilde_expansion { Ok(()) } else { Err("no tilde expansion") } }
+
+        rule tilde_after_colon_parsing_enabled() -> () =
+            &[_] {? if parser_options.tilde_expansion && parser_options.tilde_expansion_after_colons { Ok(()) } else { Err("no tilde expansion after colons") } }
     }
 }
 
@@ -912,6 +1016,75 @@ mod tests {
         Ok(())
     }
 
+    fn expand_braces(word: &str) -> Result<Vec<String>> {
+        let pieces = parse_brace_expansions(word, &ParserOptions::default())?
+            .ok_or_else(|| anyhow::anyhow!("no brace expansion in '{word}'"))?;
+
+        let mut expansions = vec![String::new()];
+        for piece in pieces {
+            let piece_expansions: Vec<_> = piece.generate().collect();
+            expansions = expansions
+                .iter()
+                .flat_map(|prefix| piece_expansions.iter().map(move |s| format!("{prefix}{s}")))
+                .collect();
+        }
+
+        Ok(expansions)
+    }
+
+    #[test]
+    fn expand_brace_sequences() -> Result<()> {
+        assert_eq!(expand_braces("{1..3}")?, ["1", "2", "3"]);
+        assert_eq!(expand_braces("{3..1}")?, ["3", "2", "1"]);
+        assert_eq!(expand_braces("{-2..2..2}")?, ["-2", "0", "2"]);
+        assert_eq!(expand_braces("{1..7..-3}")?, ["1", "4", "7"]);
+        assert_eq!(expand_braces("{1..2..0}")?, ["1", "2"]);
+        assert_eq!(expand_braces("{08..11}")?, ["08", "09", "10", "11"]);
+        assert_eq!(expand_braces("{-01..1}")?, ["-01", "000", "001"]);
+        assert_eq!(expand_braces("{a..e..2}")?, ["a", "c", "e"]);
+        assert_eq!(expand_braces("{c..a}")?, ["c", "b", "a"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn expand_nested_brace_lists() -> Result<()> {
+        assert_eq!(expand_braces("{a{1,2},b}")?, ["a1", "a2", "b"]);
+        assert_eq!(expand_braces("x{a\"b\",c}")?, ["xa\"b\"", "xc"]);
+        assert_eq!(expand_braces("{,{1..2}z}")?, ["", "1z", "2z"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_tilde_prefixes_after_colons() -> Result<()> {
+        let options = ParserOptions {
+            tilde_expansion_after_colons: true,
+            ..ParserOptions::default()
+        };
+
+        let parsed = super::parse("~/a:~b/c:d", &options)?;
+        assert_matches!(
+            &parsed[..],
+            [WordPieceWithSource { piece: WordPiece::TildePrefix(t1), .. },
+             WordPieceWithSource { piece: WordPiece::Text(s1), .. },
+             WordPieceWithSource { piece: WordPiece::Text(c1), .. },
+             WordPieceWithSource { piece: WordPiece::TildePrefix(t2), .. },
+             WordPieceWithSource { piece: WordPiece::Text(s2), .. },
+             WordPieceWithSource { piece: WordPiece::Text(c2), .. },
+             WordPieceWithSource { piece: WordPiece::Text(s3), .. }]
+                if t1.is_empty() && s1 == "/a" && c1 == ":" && t2 == "b" && s2 == "/c" && c2 == ":" && s3 == "d"
+        );
+
+        let parsed = super::parse("a:~", &ParserOptions::default())?;
+        assert_matches!(
+            &parsed[..],
+            [WordPieceWithSource { piece: WordPiece::Text(s), .. }] if s == "a:~"
+        );
+
+        Ok(())
+    }
+
     #[test]
     fn parse_extglob_with_embedded_parameter() -> Result<()> {
         let parsed = super::parse("+([$var])", &ParserOptions::default())?;
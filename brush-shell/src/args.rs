@@ -23,6 +23,53 @@ pub enum InputBackend {
     Reedline,
     Basic,
     Minimal,
+    External,
+}
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+pub enum LintOutputFormat {
+    /// Human-readable text, one diagnostic per line.
+    Text,
+    /// A JSON array of diagnostics.
+    Json,
+}
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+pub enum PlanOutputFormat {
+    /// Human-readable text, one step per line.
+    Text,
+    /// A JSON array of steps.
+    Json,
+}
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+pub enum AstOutputFormat {
+    /// JSON.
+    Json,
+    /// Rusty Object Notation.
+    Ron,
+}
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+pub enum ColorMode {
+    /// Never use colorized output.
+    Never,
+    /// Use colorized output when writing to a terminal.
+    Auto,
+    /// Always use colorized output.
+    Always,
+}
+
+/// Transport over which the shell serves RPC requests.
+#[cfg(any(unix, windows))]
+pub enum RpcTransport<'a> {
+    /// Standard input and output.
+    Stdio,
+    /// TCP, listening at the given address.
+    Tcp(&'a str),
+    /// A Unix socket, created at the given path.
+    #[cfg(unix)]
+    UnixSocket(&'a str),
 }
 
 /// Parsed command-line arguments for the brush shell.
@@ -78,10 +125,27 @@ pub struct CommandLineArgs {
     #[clap(long = "norc")]
     pub no_rc: bool,
 
+    /// Run as a fast, predictable script runner (e.g., in containers and CI): don't
+    /// process any profile or rc files, and disable interactive niceties such as colors,
+    /// syntax highlighting, and line editing. Also enabled by setting `BRUSH_MINIMAL=1`.
+    #[clap(long = "minimal")]
+    pub minimal: bool,
+
     /// Don't inherit environment variables from the calling process.
     #[clap(long = "noenv")]
     pub do_not_inherit_env: bool,
 
+    /// Import functions exported by the calling process (e.g., via `export -f`). Off by
+    /// default, since it lets the calling process define commands the shell runs. Also
+    /// enabled by setting `BRUSH_IMPORT_FUNCTIONS=1`.
+    #[clap(long = "import-functions")]
+    pub import_functions: bool,
+
+    /// Don't import functions exported by the calling process; this is the default, and the
+    /// option is accepted for compatibility with bash.
+    #[clap(long = "noimportfunctions", hide = true)]
+    pub do_not_import_functions: bool,
+
     /// Enable shell option.
     #[clap(short = 'O', value_name = "OPTION")]
     pub enabled_shopt_options: Vec<String>,
@@ -110,22 +174,155 @@ pub struct CommandLineArgs {
     #[clap(short = 'x')]
     pub print_commands_and_arguments: bool,
 
+    /// Maximum depth to which functions, `eval`, `source`, and command substitutions may be
+    /// nested before execution fails, guarding against runaway recursion.
+    #[clap(long = "max-recursion-depth", value_name = "DEPTH")]
+    pub max_recursion_depth: Option<usize>,
+
     /// Disable bracketed paste.
     #[clap(long = "disable-bracketed-paste")]
     pub disable_bracketed_paste: bool,
 
+    /// Ask for confirmation before inserting multi-line pastes.
+    #[clap(long = "confirm-multiline-paste")]
+    pub confirm_multiline_paste: bool,
+
     /// Disable colorized output.
     #[clap(long = "disable-color")]
     pub disable_color: bool,
 
+    /// When to use colorized output.
+    #[clap(long = "color", value_name = "WHEN", default_value = "auto")]
+    pub color: ColorMode,
+
     /// Enable syntax highlighting (experimental).
     #[clap(long = "enable-highlighting")]
     pub enable_highlighting: bool,
 
+    /// Enable clipboard integration (experimental).
+    #[clap(long = "enable-clipboard")]
+    pub enable_clipboard: bool,
+
+    /// Longest time (in milliseconds) to wait for completions to be generated; once it
+    /// elapses, any candidates generated by then are offered.
+    #[clap(long = "completion-timeout", value_name = "MILLISECONDS")]
+    pub completion_timeout_ms: Option<u64>,
+
+    /// Longest time (in milliseconds) to wait for the command substitutions in a prompt
+    /// while composing it; once it elapses, the output of any still running is replaced by
+    /// a placeholder.
+    #[clap(long = "prompt-timeout", value_name = "MILLISECONDS")]
+    pub prompt_timeout_ms: Option<u64>,
+
+    /// Command whose output is used as the prompt (e.g., from an external prompt generator);
+    /// sets `BRUSH_PROMPT_COMMAND`, overriding any value set by startup files.
+    #[clap(long = "prompt-cmd", value_name = "COMMAND")]
+    pub prompt_command: Option<String>,
+
+    /// Template for the terminal title, expanded like a prompt before each prompt and each
+    /// command (when `BRUSH_TERM_TITLE_COMMAND` holds the command); used unless
+    /// `BRUSH_TERM_TITLE` is set by startup files.
+    #[clap(long = "term-title", value_name = "TEMPLATE")]
+    pub term_title: Option<String>,
+
     /// Input backend.
     #[clap(long = "input-backend")]
     pub input_backend: Option<InputBackend>,
 
+    /// Program to delegate reading input to with the external input backend, followed by any
+    /// arguments (separated by whitespace).
+    #[clap(long = "external-editor", value_name = "COMMAND")]
+    pub external_editor: Option<String>,
+
+    /// Format the script (or standard input) and print it, instead of executing it.
+    #[clap(long = "format")]
+    pub format: bool,
+
+    /// Number of spaces per indentation level used by `--format`.
+    #[clap(long = "format-indent", value_name = "WIDTH", default_value_t = 4)]
+    pub format_indent_width: usize,
+
+    /// Preferred maximum line width used by `--format`.
+    #[clap(long = "format-width", value_name = "WIDTH", default_value_t = 100)]
+    pub format_line_width: usize,
+
+    /// Lint the script (or standard input) and report any problems found, instead of
+    /// executing it.
+    #[clap(long = "lint", conflicts_with = "format")]
+    pub lint: bool,
+
+    /// Output format used by `--lint`.
+    #[clap(long = "lint-output", value_name = "FORMAT", default_value = "text")]
+    pub lint_output: LintOutputFormat,
+
+    /// Parse the script (or standard input) and print its abstract syntax tree, instead
+    /// of executing it.
+    #[clap(long = "dump-ast", conflicts_with_all = ["format", "lint"])]
+    pub dump_ast: bool,
+
+    /// Output format used by `--dump-ast`.
+    #[clap(
+        long = "dump-ast-format",
+        value_name = "FORMAT",
+        default_value = "json"
+    )]
+    pub dump_ast_format: AstOutputFormat,
+
+    /// Analyze the script (or standard input) and print the side effects executing it would
+    /// have (commands run, files written, and variables exported), instead of executing it.
+    #[clap(long = "dry-run", conflicts_with_all = ["format", "lint", "dump_ast"])]
+    pub dry_run: bool,
+
+    /// Output format used by `--dry-run`.
+    #[clap(long = "dry-run-output", value_name = "FORMAT", default_value = "text")]
+    pub dry_run_output: PlanOutputFormat,
+
+    /// Profile the time spent in shell functions, writing the results to the given file
+    /// as folded stacks suitable for rendering as a flamegraph.
+    #[clap(long = "profile-functions", value_name = "FILE")]
+    pub profile_functions_path: Option<String>,
+
+    /// Serve RPC requests from external tools over a Unix socket created at the given
+    /// path, instead of reading commands from standard input.
+    #[cfg(unix)]
+    #[clap(
+        long = "rpc-socket",
+        value_name = "PATH",
+        conflicts_with_all = ["command", "script_path", "rpc_stdio", "rpc_listen_address"]
+    )]
+    pub rpc_socket_path: Option<String>,
+
+    /// Serve RPC requests from external tools over standard input and output, instead of
+    /// reading commands from standard input. Output from commands is sent to standard error.
+    #[cfg(any(unix, windows))]
+    #[clap(
+        long = "rpc-stdio",
+        conflicts_with_all = ["command", "script_path", "rpc_listen_address"]
+    )]
+    pub rpc_stdio: bool,
+
+    /// Serve RPC requests from external tools over TCP, listening at the given address
+    /// (e.g., `127.0.0.1:7878`), instead of reading commands from standard input. Requests
+    /// aren't authenticated, so anyone able to connect may run commands as the current
+    /// user; only loopback addresses are accepted.
+    #[cfg(any(unix, windows))]
+    #[clap(
+        long = "rpc-listen",
+        value_name = "ADDRESS",
+        conflicts_with_all = ["command", "script_path"]
+    )]
+    pub rpc_listen_address: Option<String>,
+
+    /// Append a record of each command executed (with its user, working directory,
+    /// arguments, and exit status) to the given file, as JSON lines.
+    #[clap(long = "audit-log", value_name = "FILE")]
+    pub audit_log_path: Option<String>,
+
+    /// Resume a shell session previously saved to the given file with the `save` builtin,
+    /// restoring its state before running any commands.
+    #[clap(long = "resume", value_name = "FILE")]
+    pub resume_path: Option<String>,
+
     /// Enable debug logging for classes of tracing events.
     #[clap(long = "log-enable", value_name = "EVENT")]
     pub enabled_log_events: Vec<events::TraceEvent>,
@@ -148,7 +345,7 @@ impl CommandLineArgs {
             return true;
         }
 
-        if self.command.is_some() || self.script_path.is_some() {
+        if self.command.is_some() || self.script_path.is_some() || self.is_rpc_server() {
             return false;
         }
 
@@ -158,6 +355,68 @@ impl CommandLineArgs {
 
         true
     }
+
+    /// Returns whether output written to the terminal should be colorized. This honors
+    /// `--disable-color` and `--color`; when the latter is `auto` (the default), color is
+    /// used only if standard error is a terminal, `NO_COLOR` isn't set, and the shell isn't
+    /// running in minimal mode.
+    pub fn use_color(&self) -> bool {
+        if self.disable_color {
+            return false;
+        }
+
+        match self.color {
+            ColorMode::Never => false,
+            ColorMode::Always => true,
+            ColorMode::Auto => {
+                !self.is_minimal()
+                    && std::env::var_os("NO_COLOR").is_none()
+                    && std::io::stderr().is_terminal()
+            }
+        }
+    }
+
+    /// Returns whether the shell should serve RPC requests instead of reading commands.
+    pub fn is_rpc_server(&self) -> bool {
+        #[cfg(any(unix, windows))]
+        {
+            self.rpc_transport().is_some()
+        }
+        #[cfg(not(any(unix, windows)))]
+        {
+            false
+        }
+    }
+
+    /// Returns the transport over which the shell should serve RPC requests, if any.
+    #[cfg(any(unix, windows))]
+    pub fn rpc_transport(&self) -> Option<RpcTransport<'_>> {
+        #[cfg(unix)]
+        if let Some(socket_path) = &self.rpc_socket_path {
+            return Some(RpcTransport::UnixSocket(socket_path.as_str()));
+        }
+
+        if self.rpc_stdio {
+            Some(RpcTransport::Stdio)
+        } else {
+            self.rpc_listen_address.as_deref().map(RpcTransport::Tcp)
+        }
+    }
+
+    /// Returns whether the shell should run in minimal mode, either because it was
+    /// requested on the command line or via the `BRUSH_MINIMAL` environment variable.
+    pub fn is_minimal(&self) -> bool {
+        self.minimal || std::env::var_os("BRUSH_MINIMAL").is_some_and(|value| value == "1")
+    }
+
+    /// Returns whether functions exported by the calling process should be imported, as
+    /// requested on the command line or via the `BRUSH_IMPORT_FUNCTIONS` environment
+    /// variable.
+    pub fn should_import_functions(&self) -> bool {
+        !self.do_not_import_functions
+            && (self.import_functions
+                || std::env::var_os("BRUSH_IMPORT_FUNCTIONS").is_some_and(|value| value == "1"))
+    }
 }
 
 /// Returns clap styling to be used for command-line help.
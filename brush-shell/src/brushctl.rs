@@ -1,7 +1,7 @@
 use clap::{Parser, Subcommand};
 use std::io::Write;
 
-use crate::events;
+use crate::{events, productinfo};
 
 pub(crate) fn register(shell: &mut brush_core::Shell) {
     shell.builtins.insert(
@@ -21,6 +21,12 @@ struct BrushCtlCommand {
 enum CommandGroup {
     #[clap(subcommand)]
     Events(EventsCommand),
+    #[clap(subcommand)]
+    Compat(CompatCommand),
+    #[clap(subcommand)]
+    Config(ConfigCommand),
+    #[clap(subcommand)]
+    DirHistory(DirHistoryCommand),
 }
 
 /// Commands for configuring tracing events.
@@ -42,13 +48,50 @@ enum EventsCommand {
     },
 }
 
+/// Commands for inspecting and toggling compatibility quirks.
+#[derive(Subcommand)]
+enum CompatCommand {
+    /// List known divergences from bash behavior.
+    List,
+
+    /// Enable quirk.
+    Enable {
+        /// Quirk to enable.
+        quirk: String,
+    },
+
+    /// Disable quirk.
+    Disable {
+        /// Quirk to disable.
+        quirk: String,
+    },
+}
+
+/// Commands for inspecting the shell's configuration.
+#[derive(Subcommand)]
+enum ConfigCommand {
+    /// Display the shell's configuration as JSON.
+    Show,
+}
+
+/// Commands for inspecting the shell's directory history.
+#[derive(Subcommand)]
+enum DirHistoryCommand {
+    /// List the directories in the history, from least to most recently entered, along with
+    /// their offsets from the current directory (as accepted by `cd -N`).
+    List,
+}
+
 impl brush_core::builtins::Command for BrushCtlCommand {
     async fn execute(
         &self,
-        context: brush_core::ExecutionContext<'_>,
+        mut context: brush_core::ExecutionContext<'_>,
     ) -> Result<brush_core::builtins::ExitCode, brush_core::Error> {
         match self.command_group {
             CommandGroup::Events(ref events) => events.execute(&context),
+            CommandGroup::Compat(ref compat) => compat.execute(&mut context),
+            CommandGroup::Config(ref config) => config.execute(&context),
+            CommandGroup::DirHistory(ref dir_history) => dir_history.execute(&context),
         }
     }
 }
@@ -86,3 +129,112 @@ impl EventsCommand {
         }
     }
 }
+
+impl CompatCommand {
+    fn execute(
+        &self,
+        context: &mut brush_core::ExecutionContext<'_>,
+    ) -> Result<brush_core::builtins::ExitCode, brush_core::Error> {
+        let (name, value) = match self {
+            CompatCommand::List => {
+                for quirk in brush_core::compat::quirks() {
+                    let state = if !quirk.is_toggleable() {
+                        "always"
+                    } else if quirk.is_enabled(context.shell) {
+                        "on"
+                    } else {
+                        "off"
+                    };
+
+                    writeln!(
+                        context.stdout(),
+                        "{}\t{state}\t{}",
+                        quirk.name,
+                        quirk.description
+                    )?;
+                }
+
+                return Ok(brush_core::builtins::ExitCode::Success);
+            }
+            CompatCommand::Enable { quirk } => (quirk, true),
+            CompatCommand::Disable { quirk } => (quirk, false),
+        };
+
+        let Some(quirk) = brush_core::compat::find_quirk(name.as_str()) else {
+            writeln!(
+                context.stderr(),
+                "{}: unknown quirk: {name}",
+                context.command_name
+            )?;
+            return Ok(brush_core::builtins::ExitCode::InvalidUsage);
+        };
+
+        quirk.set_enabled(context.shell, value)?;
+
+        Ok(brush_core::builtins::ExitCode::Success)
+    }
+}
+
+impl ConfigCommand {
+    fn execute(
+        &self,
+        context: &brush_core::ExecutionContext<'_>,
+    ) -> Result<brush_core::builtins::ExitCode, brush_core::Error> {
+        match self {
+            ConfigCommand::Show => {
+                let quirks: serde_json::Map<_, _> = brush_core::compat::quirks()
+                    .map(|quirk| {
+                        (
+                            quirk.name.to_owned(),
+                            serde_json::json!({
+                                "enabled": quirk.is_enabled(context.shell),
+                                "toggleable": quirk.is_toggleable(),
+                                "description": quirk.description,
+                            }),
+                        )
+                    })
+                    .collect();
+
+                let config = serde_json::json!({
+                    "version": productinfo::PRODUCT_VERSION,
+                    "quirks": quirks,
+                    "options": brush_core::compat::set_o_option_values(context.shell),
+                    "shopt": brush_core::compat::shopt_option_values(context.shell),
+                });
+
+                let formatted = serde_json::to_string_pretty(&config).map_err(|_| {
+                    brush_core::Error::Unimplemented("failed to serialize configuration")
+                })?;
+
+                writeln!(context.stdout(), "{formatted}")?;
+            }
+        }
+
+        Ok(brush_core::builtins::ExitCode::Success)
+    }
+}
+
+impl DirHistoryCommand {
+    fn execute(
+        &self,
+        context: &brush_core::ExecutionContext<'_>,
+    ) -> Result<brush_core::builtins::ExitCode, brush_core::Error> {
+        match self {
+            DirHistoryCommand::List => {
+                let history = &context.shell.directory_history;
+                let current_index = history.current_index();
+                for (i, dir) in history.entries().iter().enumerate() {
+                    let offset = match i.cmp(&current_index) {
+                        std::cmp::Ordering::Less => std::format!("-{}", current_index - i),
+                        std::cmp::Ordering::Equal => "0".to_owned(),
+                        std::cmp::Ordering::Greater => std::format!("+{}", i - current_index),
+                    };
+
+                    writeln!(context.stdout(), "{offset:>4}  {}", dir.display())?;
+                }
+            }
+        }
+
+        Ok(brush_core::builtins::ExitCode::Success)
+    }
+}
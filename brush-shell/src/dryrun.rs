@@ -0,0 +1,65 @@
+//! Implements the `--dry-run` mode of the shell.
+
+use std::io::Write;
+
+use crate::args::{CommandLineArgs, PlanOutputFormat};
+
+/// Analyzes the script identified by the command-line arguments (or standard input, if no
+/// script was provided) without executing it, writing the side effects executing it would
+/// have to standard output. Returns the exit code to be used: 0 on success, or 2 if the
+/// script couldn't be parsed.
+///
+/// # Arguments
+///
+/// * `args` - The parsed command-line arguments.
+pub(crate) fn plan_script(args: &CommandLineArgs) -> Result<u8, brush_interactive::ShellError> {
+    let (source_name, contents) = crate::format::read_script(args)?;
+
+    let parser_options = brush_parser::ParserOptions {
+        posix_mode: args.posix || args.sh_mode,
+        sh_mode: args.sh_mode,
+        ..brush_parser::ParserOptions::default()
+    };
+    let source_info = brush_parser::SourceInfo {
+        source: source_name.clone(),
+    };
+
+    let mut parser = brush_parser::Parser::new(
+        std::io::BufReader::new(contents.as_bytes()),
+        &parser_options,
+        &source_info,
+    );
+
+    let program = match parser.parse() {
+        Ok(program) => program,
+        Err(e) => {
+            tracing::error!("{source_name}: {e}");
+            return Ok(2);
+        }
+    };
+
+    let builtins = brush_core::builtins::default_builtin_names(&brush_core::CreateOptions {
+        posix: args.posix || args.sh_mode,
+        sh_mode: args.sh_mode,
+        ..brush_core::CreateOptions::default()
+    });
+
+    let steps = brush_parser::plan::plan_program(&program, &parser_options, |name| {
+        builtins.binary_search_by(|b| b.as_str().cmp(name)).is_ok()
+    });
+
+    let mut stdout = std::io::stdout().lock();
+    match args.dry_run_output {
+        PlanOutputFormat::Text => {
+            for step in &steps {
+                writeln!(stdout, "{source_name}:{step}")?;
+            }
+        }
+        PlanOutputFormat::Json => {
+            let output = serde_json::to_string_pretty(&steps).map_err(std::io::Error::other)?;
+            writeln!(stdout, "{output}")?;
+        }
+    }
+
+    Ok(0)
+}
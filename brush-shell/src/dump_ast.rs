@@ -0,0 +1,55 @@
+//! Implements the `--dump-ast` mode of the shell.
+
+use std::io::Write;
+
+use crate::args::{AstOutputFormat, CommandLineArgs};
+
+/// Parses the script identified by the command-line arguments, writing its abstract
+/// syntax tree to standard output. Source locations of each complete command and any
+/// comments are included. Returns the exit code to be used: 0 on success, or 2 if the
+/// script couldn't be parsed.
+///
+/// # Arguments
+///
+/// * `args` - The parsed command-line arguments.
+pub(crate) fn dump_ast(args: &CommandLineArgs) -> Result<u8, brush_interactive::ShellError> {
+    let (source_name, contents) = crate::format::read_script(args)?;
+
+    let parser_options = brush_parser::ParserOptions {
+        posix_mode: args.posix || args.sh_mode,
+        sh_mode: args.sh_mode,
+        capture_trivia: true,
+        ..brush_parser::ParserOptions::default()
+    };
+    let source_info = brush_parser::SourceInfo {
+        source: source_name.clone(),
+    };
+
+    let mut parser = brush_parser::Parser::new(
+        std::io::BufReader::new(contents.as_bytes()),
+        &parser_options,
+        &source_info,
+    );
+
+    let program = match parser.parse() {
+        Ok(program) => program,
+        Err(e) => {
+            tracing::error!("{source_name}: {e}");
+            return Ok(2);
+        }
+    };
+
+    let output = match args.dump_ast_format {
+        AstOutputFormat::Json => {
+            serde_json::to_string_pretty(&program).map_err(std::io::Error::other)?
+        }
+        AstOutputFormat::Ron => {
+            ron::ser::to_string_pretty(&program, ron::ser::PrettyConfig::default())
+                .map_err(std::io::Error::other)?
+        }
+    };
+
+    writeln!(std::io::stdout(), "{output}")?;
+
+    Ok(0)
+}
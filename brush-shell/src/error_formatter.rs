@@ -0,0 +1,378 @@
+//! Renders syntax errors with an excerpt of the offending source and hints for
+//! common mistakes.
+
+use std::fmt::Write;
+
+use brush_core::i18n::{self, Catalog};
+use brush_parser::{ParseError, SourcePosition, Token, TokenLocation};
+
+/// ANSI escape sequence styling the description of the error and the caret pointing at
+/// its location.
+const ERROR_STYLE: &str = "\x1b[1;31m";
+/// ANSI escape sequence styling the gutter of the source excerpt.
+const GUTTER_STYLE: &str = "\x1b[1;34m";
+/// ANSI escape sequence styling hints.
+const HINT_STYLE: &str = "\x1b[36m";
+/// ANSI escape sequence resetting the style.
+const RESET_STYLE: &str = "\x1b[0m";
+
+/// Formats syntax errors for display on the terminal.
+pub(crate) struct ErrorFormatter {
+    /// Whether to style the output with ANSI color escape sequences.
+    pub use_color: bool,
+}
+
+impl brush_core::SyntaxErrorFormatter for ErrorFormatter {
+    fn format_syntax_error(
+        &self,
+        error: &ParseError,
+        source_info: &brush_parser::SourceInfo,
+        source: &str,
+        catalog: &Catalog,
+    ) -> String {
+        render(
+            error,
+            source_info.source.as_str(),
+            source,
+            catalog,
+            self.use_color,
+        )
+    }
+}
+
+/// A region of the source to be highlighted, identified by its 1-based line and column
+/// and its width in characters.
+struct Span {
+    line: usize,
+    column: usize,
+    width: usize,
+}
+
+impl Span {
+    fn at(position: &SourcePosition) -> Self {
+        Self {
+            line: to_index(position.line),
+            column: to_index(position.column),
+            width: 1,
+        }
+    }
+
+    fn of(location: &TokenLocation) -> Self {
+        let mut span = Self::at(&location.start);
+        if location.end.line == location.start.line {
+            span.width = to_index(location.end.column)
+                .saturating_sub(span.column)
+                .max(1);
+        }
+        span
+    }
+}
+
+fn render(
+    error: &ParseError,
+    source_name: &str,
+    source: &str,
+    catalog: &Catalog,
+    use_color: bool,
+) -> String {
+    let paint = |text: &str, style: &str| {
+        if use_color {
+            std::format!("{style}{text}{RESET_STYLE}")
+        } else {
+            text.to_owned()
+        }
+    };
+
+    let (message, span) = describe(error, source, catalog);
+    let hints = hints(error, span.as_ref(), source, catalog);
+
+    let mut output = String::new();
+    if !source_name.is_empty() {
+        output.push_str(source_name);
+        output.push_str(": ");
+    }
+    output.push_str(
+        paint(
+            catalog
+                .format(&i18n::SYNTAX_ERROR_DETAIL, &[("detail", &message)])
+                .as_str(),
+            ERROR_STYLE,
+        )
+        .as_str(),
+    );
+
+    if let Some(span) = &span {
+        if let Some(line) = source.lines().nth(span.line - 1) {
+            let line_number = span.line.to_string();
+            let gutter = " ".repeat(line_number.len());
+
+            // N.B. Tabs are carried over from the source line so the caret stays aligned
+            // with the character it points at.
+            let padding: String = line
+                .chars()
+                .take(span.column - 1)
+                .map(|c| if c == '\t' { '\t' } else { ' ' })
+                .collect();
+            let name = if source_name.is_empty() {
+                "<input>"
+            } else {
+                source_name
+            };
+
+            let arrow = paint("-->", GUTTER_STYLE);
+            let bar = paint("|", GUTTER_STYLE);
+            let _ = write!(
+                output,
+                "\n{gutter}{arrow} {name}:{}:{}\n{gutter} {bar}\n{} {bar} {line}\n{gutter} {bar} {padding}{}",
+                span.line,
+                span.column,
+                paint(line_number.as_str(), GUTTER_STYLE),
+                paint("^".repeat(span.width).as_str(), ERROR_STYLE)
+            );
+        }
+    }
+
+    for hint in hints {
+        output.push_str("\n  = ");
+        output.push_str(
+            paint(
+                catalog
+                    .format(&i18n::SYNTAX_ERROR_HINT, &[("hint", &hint)])
+                    .as_str(),
+                HINT_STYLE,
+            )
+            .as_str(),
+        );
+    }
+
+    output
+}
+
+/// Describes the error, returning the region of the source it pertains to, if known.
+fn describe(error: &ParseError, source: &str, catalog: &Catalog) -> (String, Option<Span>) {
+    match error {
+        ParseError::ParsingNearToken(token) => (
+            catalog.format(
+                &i18n::SYNTAX_ERROR_UNEXPECTED_TOKEN,
+                &[("token", &token.to_str())],
+            ),
+            Some(Span::of(token.location())),
+        ),
+        ParseError::ParsingAtEndOfInput => (
+            catalog.format(&i18n::SYNTAX_ERROR_UNEXPECTED_END_OF_INPUT, &[]),
+            end_of_input_span(source),
+        ),
+        ParseError::Tokenizing { inner, position } => {
+            let span = match inner {
+                brush_parser::TokenizerError::UnterminatedSingleQuote(start)
+                | brush_parser::TokenizerError::UnterminatedDoubleQuote(start)
+                | brush_parser::TokenizerError::UnterminatedBackquote(start)
+                | brush_parser::TokenizerError::UnterminatedExtendedGlob(start) => {
+                    Some(Span::at(start))
+                }
+                _ => position.as_ref().map(Span::at),
+            };
+
+            (inner.to_string(), span)
+        }
+    }
+}
+
+/// Locates the last character of the last non-blank line of the source.
+fn end_of_input_span(source: &str) -> Option<Span> {
+    let (index, line) = source
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .last()?;
+
+    Some(Span {
+        line: index + 1,
+        column: line.trim_end().chars().count(),
+        width: 1,
+    })
+}
+
+fn hints(error: &ParseError, span: Option<&Span>, source: &str, catalog: &Catalog) -> Vec<String> {
+    let mut hints = vec![];
+
+    match error {
+        ParseError::Tokenizing { inner, .. } => {
+            let quote = match inner {
+                brush_parser::TokenizerError::UnterminatedSingleQuote(_) => Some('\''),
+                brush_parser::TokenizerError::UnterminatedDoubleQuote(_) => Some('"'),
+                brush_parser::TokenizerError::UnterminatedBackquote(_) => Some('`'),
+                _ => None,
+            };
+
+            if let Some(quote) = quote {
+                hints.push(catalog.format(
+                    &i18n::SYNTAX_ERROR_UNCLOSED_QUOTE_HINT,
+                    &[("quote", &quote)],
+                ));
+            }
+        }
+        ParseError::ParsingAtEndOfInput => {
+            if let Some(hint) = unclosed_compound_command_hint(source, catalog) {
+                hints.push(hint);
+            }
+        }
+        ParseError::ParsingNearToken(_) => (),
+    }
+
+    if let Some(line) = span.and_then(|span| source.lines().nth(span.line - 1)) {
+        let words: Vec<_> = line.split_whitespace().collect();
+        if words.contains(&"[") && words.contains(&"==") {
+            hints.push(catalog.format(&i18n::SYNTAX_ERROR_DOUBLE_EQUALS_HINT, &[]));
+        }
+    }
+
+    hints
+}
+
+/// Looks for a compound command that's opened but never closed (e.g., an `if` without
+/// a `fi`), returning a hint describing the innermost such command.
+fn unclosed_compound_command_hint(source: &str, catalog: &Catalog) -> Option<String> {
+    let tokens = brush_parser::tokenize_str(source).ok()?;
+
+    let mut open: Vec<(&str, &str, i32)> = vec![];
+    let mut command_position = true;
+
+    for token in &tokens {
+        let in_command_position = command_position;
+
+        match token {
+            Token::Operator(_, _) => command_position = true,
+            Token::Word(word, location) => {
+                command_position = false;
+
+                if !in_command_position {
+                    continue;
+                }
+
+                let word = word.as_str();
+                match word {
+                    "if" | "do" | "case" | "{" => {
+                        let closer = match word {
+                            "if" => "fi",
+                            "do" => "done",
+                            "case" => "esac",
+                            _ => "}",
+                        };
+                        open.push((word, closer, location.start.line));
+                        // N.B. The word following `case` is the value being matched.
+                        command_position = word != "case";
+                    }
+                    "fi" | "done" | "esac" | "}" => {
+                        if open.last().is_some_and(|(_, closer, _)| *closer == word) {
+                            open.pop();
+                        }
+                    }
+                    "then" | "else" | "elif" | "while" | "until" | "!" => {
+                        command_position = true;
+                    }
+                    _ => (),
+                }
+            }
+        }
+    }
+
+    let (opener, closer, line) = open.last()?;
+    Some(catalog.format(
+        &i18n::SYNTAX_ERROR_UNCLOSED_COMMAND_HINT,
+        &[("opener", opener), ("closer", closer), ("line", line)],
+    ))
+}
+
+fn to_index(value: i32) -> usize {
+    usize::try_from(value).unwrap_or(0).max(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render_str(source: &str) -> String {
+        render_str_with_catalog(source, &Catalog::default())
+    }
+
+    fn render_str_with_catalog(source: &str, catalog: &Catalog) -> String {
+        let mut parser = brush_parser::Parser::new(
+            source.as_bytes(),
+            &brush_parser::ParserOptions::default(),
+            &brush_parser::SourceInfo::default(),
+        );
+
+        match parser.parse() {
+            Ok(_) => String::new(),
+            Err(e) => render(&e, "script.sh", source, catalog, false),
+        }
+    }
+
+    #[test]
+    fn excerpt_with_caret() {
+        assert_eq!(
+            render_str("echo hi\nfi\n"),
+            "script.sh: syntax error: unexpected token `fi'\n --> script.sh:2:1\n  |\n2 | fi\n  | ^^"
+        );
+    }
+
+    #[test]
+    fn unclosed_quote() {
+        let output = render_str("echo 'hello\n");
+        assert!(output.contains("1 | echo 'hello\n  |      ^"));
+        assert!(output.contains("hint: the quote opened here is never closed"));
+    }
+
+    #[test]
+    fn missing_terminators() {
+        assert!(render_str("if true; then\n  echo hi\n")
+            .ends_with("hint: `if` on line 1 is missing a matching `fi`"));
+        assert!(render_str("while true; do\n  if true; then echo if; fi\n")
+            .ends_with("hint: `do` on line 1 is missing a matching `done`"));
+    }
+
+    #[test]
+    fn double_equals_in_test() {
+        assert!(render_str("[ \"$a\" == b ] )\n").contains("hint: `==`"));
+    }
+
+    #[test]
+    fn localized() {
+        let catalog = Catalog::parse(
+            "syntax-error-detail = Syntaxfehler: {detail}\n\
+             syntax-error-unexpected-token = unerwartetes Symbol `{token}'\n\
+             syntax-error-hint = Hinweis: {hint}\n",
+        );
+
+        let output = render_str_with_catalog("if true; then\n  echo hi\nfi fi\n", &catalog);
+        assert!(output.starts_with("script.sh: Syntaxfehler: unerwartetes Symbol `fi'"));
+
+        let output = render_str_with_catalog("echo 'hello\n", &catalog);
+        assert!(output.contains("= Hinweis: the quote opened here is never closed"));
+    }
+
+    #[test]
+    fn colored() {
+        let source = "echo hi\nfi\n";
+        let mut parser = brush_parser::Parser::new(
+            source.as_bytes(),
+            &brush_parser::ParserOptions::default(),
+            &brush_parser::SourceInfo::default(),
+        );
+        let error = parser.parse().unwrap_err();
+
+        let output = render(&error, "script.sh", source, &Catalog::default(), true);
+        assert!(output.contains("\x1b[1;31msyntax error: unexpected token `fi'\x1b[0m"));
+        assert!(output.contains("\x1b[1;31m^^\x1b[0m"));
+
+        // Without color, the output is plain text.
+        assert_eq!(strip_styles(output.as_str()), render_str(source));
+    }
+
+    fn strip_styles(s: &str) -> String {
+        [ERROR_STYLE, GUTTER_STYLE, HINT_STYLE, RESET_STYLE]
+            .iter()
+            .fold(s.to_owned(), |s, style| s.replace(style, ""))
+    }
+}
@@ -0,0 +1,69 @@
+//! Implements the `--format` mode of the shell.
+
+use std::io::{Read, Write};
+
+use crate::args::CommandLineArgs;
+
+/// Formats the script identified by the command-line arguments (or standard input, if
+/// no script was provided), writing the formatted result to standard output. Returns the
+/// exit code to be used.
+///
+/// # Arguments
+///
+/// * `args` - The parsed command-line arguments.
+pub(crate) fn format_script(args: &CommandLineArgs) -> Result<u8, brush_interactive::ShellError> {
+    let (source_name, contents) = read_script(args)?;
+
+    let parser_options = brush_parser::ParserOptions {
+        posix_mode: args.posix || args.sh_mode,
+        sh_mode: args.sh_mode,
+        capture_trivia: true,
+        ..brush_parser::ParserOptions::default()
+    };
+    let source_info = brush_parser::SourceInfo {
+        source: source_name.clone(),
+    };
+
+    let mut parser = brush_parser::Parser::new(
+        std::io::BufReader::new(contents.as_bytes()),
+        &parser_options,
+        &source_info,
+    );
+
+    let program = match parser.parse() {
+        Ok(program) => program,
+        Err(e) => {
+            tracing::error!("{source_name}: {e}");
+            return Ok(2);
+        }
+    };
+
+    let format_options = brush_parser::format::FormatOptions {
+        indent_width: args.format_indent_width,
+        max_line_width: args.format_line_width,
+    };
+
+    let formatted = brush_parser::format::format_program(&program, &format_options);
+    std::io::stdout().write_all(formatted.as_bytes())?;
+
+    Ok(0)
+}
+
+/// Reads the script identified by the command-line arguments: the `-c` command string, if
+/// one was given, or else the script file (or standard input, if no script was provided).
+/// Returns a display name for the script's source along with its contents.
+///
+/// # Arguments
+///
+/// * `args` - The parsed command-line arguments.
+pub(crate) fn read_script(args: &CommandLineArgs) -> Result<(String, String), std::io::Error> {
+    if let Some(command) = &args.command {
+        Ok((String::from("<command>"), command.clone()))
+    } else if let Some(script_path) = &args.script_path {
+        Ok((script_path.clone(), std::fs::read_to_string(script_path)?))
+    } else {
+        let mut contents = String::new();
+        std::io::stdin().read_to_string(&mut contents)?;
+        Ok((String::from("<stdin>"), contents))
+    }
+}
@@ -0,0 +1,117 @@
+//! Implements the `--lint` mode of the shell.
+
+use std::io::Write;
+
+use crate::args::{CommandLineArgs, LintOutputFormat};
+
+/// Lints the script identified by the command-line arguments (or standard input, if
+/// no script was provided), writing any diagnostics to standard output. Returns the
+/// exit code to be used: 0 if no problems were found, 1 if diagnostics were reported,
+/// or 2 if the script contains syntax errors. Parsing recovers from syntax errors, so
+/// all of them are reported, along with any diagnostics for the commands that could be
+/// parsed.
+///
+/// # Arguments
+///
+/// * `args` - The parsed command-line arguments.
+pub(crate) fn lint_script(args: &CommandLineArgs) -> Result<u8, brush_interactive::ShellError> {
+    let (source_name, contents) = crate::format::read_script(args)?;
+
+    let parser_options = brush_parser::ParserOptions {
+        posix_mode: args.posix || args.sh_mode,
+        sh_mode: args.sh_mode,
+        ..brush_parser::ParserOptions::default()
+    };
+    let source_info = brush_parser::SourceInfo {
+        source: source_name.clone(),
+    };
+
+    let tokens = match brush_parser::tokenize_str_with_options(
+        contents.as_str(),
+        &parser_options.tokenizer_options(),
+    ) {
+        Ok(tokens) => tokens,
+        Err(e) => {
+            tracing::error!("{source_name}: {e}");
+            return Ok(2);
+        }
+    };
+
+    let (program, errors) =
+        brush_parser::parse_tokens_with_recovery(&tokens, &parser_options, &source_info);
+
+    let mut diagnostics: Vec<brush_parser::lint::Diagnostic> = errors
+        .iter()
+        .map(brush_parser::lint::Diagnostic::from)
+        .collect();
+    diagnostics.extend(brush_parser::lint::lint_program(
+        &program,
+        &tokens,
+        &parser_options,
+    ));
+    diagnostics.sort_by_key(|d| d.location.as_ref().map_or(0, |l| l.start.index));
+
+    let mut stdout = std::io::stdout().lock();
+    match args.lint_output {
+        LintOutputFormat::Text => {
+            for diagnostic in &diagnostics {
+                writeln!(stdout, "{source_name}:{diagnostic}")?;
+            }
+        }
+        LintOutputFormat::Json => {
+            writeln!(stdout, "{}", to_json(source_name.as_str(), &diagnostics))?;
+        }
+    }
+
+    if !errors.is_empty() {
+        Ok(2)
+    } else {
+        Ok(u8::from(!diagnostics.is_empty()))
+    }
+}
+
+/// Renders the given diagnostics as a JSON array, using the same general layout as
+/// `shellcheck --format=json`.
+fn to_json(source_name: &str, diagnostics: &[brush_parser::lint::Diagnostic]) -> String {
+    let entries: Vec<_> = diagnostics
+        .iter()
+        .map(|diagnostic| {
+            let location = diagnostic.location.clone().unwrap_or_default();
+            std::format!(
+                r#"{{"file":{},"line":{},"endLine":{},"column":{},"endColumn":{},"level":{},"code":{},"message":{}}}"#,
+                json_string(source_name),
+                location.start.line,
+                location.end.line,
+                location.start.column,
+                location.end.column,
+                json_string(diagnostic.severity().to_string().as_str()),
+                json_string(diagnostic.rule.code()),
+                json_string(diagnostic.message.as_str()),
+            )
+        })
+        .collect();
+
+    std::format!("[{}]", entries.join(","))
+}
+
+fn json_string(s: &str) -> String {
+    let mut result = String::with_capacity(s.len() + 2);
+    result.push('"');
+
+    for c in s.chars() {
+        match c {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            '\r' => result.push_str("\\r"),
+            '\t' => result.push_str("\\t"),
+            c if u32::from(c) < 0x20 => {
+                result.push_str(std::format!("\\u{:04x}", u32::from(c)).as_str());
+            }
+            c => result.push(c),
+        }
+    }
+
+    result.push('"');
+    result
+}
@@ -4,13 +4,20 @@
 
 mod args;
 mod brushctl;
+mod dryrun;
+mod dump_ast;
+mod error_formatter;
 mod events;
+mod format;
+mod lint;
 mod productinfo;
+#[cfg(any(unix, windows))]
+mod rpc;
 mod shell_factory;
 
 use crate::args::{CommandLineArgs, InputBackend};
 use brush_interactive::InteractiveShell;
-use std::{path::Path, sync::Arc};
+use std::{io::Write, path::Path, sync::Arc};
 
 lazy_static::lazy_static! {
     static ref TRACE_EVENT_CONFIG: Arc<tokio::sync::Mutex<Option<events::TraceEventConfig>>> =
@@ -109,7 +116,12 @@ async fn run(
     cli_args: Vec<String>,
     args: CommandLineArgs,
 ) -> Result<u8, brush_interactive::ShellError> {
-    let default_backend = get_default_input_backend();
+    // N.B. Minimal mode avoids the line editor and its terminal handling.
+    let default_backend = if args.is_minimal() {
+        InputBackend::Minimal
+    } else {
+        get_default_input_backend()
+    };
 
     match args.input_backend.as_ref().unwrap_or(&default_backend) {
         InputBackend::Reedline => {
@@ -117,6 +129,9 @@ async fn run(
         }
         InputBackend::Basic => run_impl(cli_args, args, shell_factory::BasicShellFactory).await,
         InputBackend::Minimal => run_impl(cli_args, args, shell_factory::MinimalShellFactory).await,
+        InputBackend::External => {
+            run_impl(cli_args, args, shell_factory::ExternalShellFactory).await
+        }
     }
 }
 
@@ -137,10 +152,37 @@ async fn run_impl(
     *event_config = Some(events::TraceEventConfig::init(&args.enabled_log_events));
     drop(event_config);
 
+    // Formatting, linting, dumping the AST, and dry runs don't require a shell instance;
+    // handle them before creating one.
+    if args.format {
+        return format::format_script(&args);
+    }
+    if args.lint {
+        return lint::lint_script(&args);
+    }
+    if args.dump_ast {
+        return dump_ast::dump_ast(&args);
+    }
+    if args.dry_run {
+        return dryrun::plan_script(&args);
+    }
+
     // Instantiate an appropriately configured shell.
     let mut shell = instantiate_shell(&args, cli_args, factory).await?;
 
+    // Resume a previously saved session, if requested.
+    if let Some(resume_path) = &args.resume_path {
+        brush_core::savedstate::SavedState::load_from_file(Path::new(resume_path))?
+            .restore(shell.shell_mut().as_mut())?;
+    }
+
     // Handle commands.
+    #[cfg(any(unix, windows))]
+    if let Some(transport) = args.rpc_transport() {
+        rpc::serve(shell.shell_mut().as_mut(), transport).await?;
+        return Ok(shell.shell().as_ref().last_result());
+    }
+
     if let Some(command) = args.command {
         // Pass through args.
         if let Some(script_path) = args.script_path {
@@ -171,6 +213,15 @@ async fn run_impl(
         shell.run_interactively().await?;
     }
 
+    if let (Some(profile_path), Some(profiler)) = (
+        &args.profile_functions_path,
+        &shell.shell().as_ref().function_profiler,
+    ) {
+        let mut profile_file = std::io::BufWriter::new(std::fs::File::create(profile_path)?);
+        profiler.write_folded_stacks(&mut profile_file)?;
+        profile_file.flush()?;
+    }
+
     // Make sure to return the last result observed in the shell.
     let result = shell.shell().as_ref().last_result();
 
@@ -194,6 +245,24 @@ async fn instantiate_shell(
     let read_commands_from_stdin = (args.read_commands_from_stdin && args.command.is_none())
         || (args.script_path.is_none() && args.command.is_none());
     let interactive = args.is_interactive();
+    let minimal = args.is_minimal();
+
+    let mut audit_sinks: Vec<Arc<dyn brush_core::audit::AuditSink>> = vec![];
+    if let Some(audit_log_path) = &args.audit_log_path {
+        audit_sinks.push(Arc::new(brush_core::audit::JsonLinesFileSink::open(
+            Path::new(audit_log_path),
+        )?));
+    }
+
+    // Rank the directories visited in interactive sessions, for the `j` builtin.
+    let mut directory_rank_store: Option<Arc<dyn brush_core::frecency::DirectoryRankStore>> = None;
+    if interactive {
+        if let Some(home_dir) = std::env::var_os("HOME") {
+            directory_rank_store = Some(Arc::new(brush_core::frecency::FileRankStore::new(
+                Path::new(&home_dir).join(".brush_dirs"),
+            )));
+        }
+    }
 
     // Compose the options we'll use to create the shell.
     let options = brush_interactive::Options {
@@ -206,21 +275,42 @@ async fn instantiate_shell(
             login: args.login || argv0.as_ref().is_some_and(|a0| a0.starts_with('-')),
             interactive,
             no_editing: args.no_editing,
-            no_profile: args.no_profile,
-            no_rc: args.no_rc,
+            no_profile: args.no_profile || minimal,
+            no_rc: args.no_rc || minimal,
             do_not_inherit_env: args.do_not_inherit_env,
+            import_functions: args.should_import_functions(),
             posix: args.posix || args.sh_mode,
             print_commands_and_arguments: args.print_commands_and_arguments,
             read_commands_from_stdin,
+            read_commands_from_command_string: args.command.is_some(),
             shell_name: argv0,
             shell_product_display_str: Some(productinfo::get_product_display_str()),
+            product_info: Some(productinfo::get_product_info()),
             sh_mode: args.sh_mode,
             verbose: args.verbose,
             max_function_call_depth: None,
+            max_recursion_depth: args.max_recursion_depth,
+            profile_functions: args.profile_functions_path.is_some(),
+            process_container: None,
+            sandbox_policy: None,
+            audit_sinks,
+            directory_rank_store,
         },
-        disable_bracketed_paste: args.disable_bracketed_paste,
-        disable_color: args.disable_color,
-        disable_highlighting: !args.enable_highlighting,
+        disable_bracketed_paste: args.disable_bracketed_paste || minimal,
+        confirm_multiline_paste: args.confirm_multiline_paste,
+        disable_color: !args.use_color(),
+        disable_highlighting: !args.enable_highlighting || minimal,
+        disable_clipboard: !args.enable_clipboard || minimal,
+        completion_timeout: args
+            .completion_timeout_ms
+            .map(std::time::Duration::from_millis),
+        prompt_timeout: args.prompt_timeout_ms.map(std::time::Duration::from_millis),
+        term_title: args.term_title.clone(),
+        external_editor_command: args
+            .external_editor
+            .as_deref()
+            .map(|command| command.split_whitespace().map(str::to_owned).collect())
+            .unwrap_or_default(),
     };
 
     // Create the shell.
@@ -229,6 +319,20 @@ async fn instantiate_shell(
     // Register our own built-in(s) with the shell.
     brushctl::register(shell.shell_mut().as_mut());
 
+    // Describe syntax errors with an excerpt of the offending source.
+    shell.shell_mut().as_mut().syntax_error_formatter =
+        Some(Arc::new(error_formatter::ErrorFormatter {
+            use_color: args.use_color(),
+        }));
+
+    // Use the output of the requested command as the prompt, if any.
+    if let Some(prompt_command) = &args.prompt_command {
+        shell.shell_mut().as_mut().env.set_global(
+            "BRUSH_PROMPT_COMMAND",
+            brush_core::ShellVariable::new(prompt_command.as_str().into()),
+        )?;
+    }
+
     Ok(shell)
 }
 
@@ -30,3 +30,34 @@ pub(crate) fn get_product_display_str() -> String {
         "{PRODUCT_NAME} version {PRODUCT_VERSION} ({PRODUCT_GIT_VERSION}) - {PRODUCT_DISPLAY_URI}"
     )
 }
+
+/// Returns details about this product, as exposed to scripts run by the shell.
+pub(crate) fn get_product_info() -> brush_core::ProductInfo {
+    let source_version = PRODUCT_GIT_VERSION
+        .split_once(':')
+        .map_or(PRODUCT_GIT_VERSION, |(_, version)| version);
+
+    let build_profile = if cfg!(debug_assertions) {
+        "debug"
+    } else {
+        "release"
+    };
+
+    let mut features = vec![];
+    if cfg!(feature = "basic") {
+        features.push("basic".to_owned());
+    }
+    if cfg!(feature = "minimal") {
+        features.push("minimal".to_owned());
+    }
+    if cfg!(feature = "reedline") {
+        features.push("reedline".to_owned());
+    }
+
+    brush_core::ProductInfo {
+        version: PRODUCT_VERSION.to_owned(),
+        source_version: source_version.to_owned(),
+        build_profile: build_profile.to_owned(),
+        features,
+    }
+}
@@ -0,0 +1,918 @@
+//! Implements an RPC interface that lets external tools (e.g., editors and other UIs) drive
+//! a long-lived shell instance. Clients exchange JSON-RPC 2.0 messages with the shell, one
+//! message per line, over standard input and output, TCP, or a Unix socket. The protocol is
+//! documented in `docs/reference/rpc-protocol.md`.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use serde_json::{json, Value};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+
+use crate::args::RpcTransport;
+use crate::productinfo;
+
+/// Version of the RPC protocol implemented by the server. Incremented whenever a change is
+/// made that isn't backwards compatible for clients.
+const PROTOCOL_VERSION: u64 = 1;
+
+/// Methods that may be invoked once a connection has been initialized.
+const METHODS: &[&str] = &[
+    "run",
+    "complete",
+    "expand",
+    "getVariable",
+    "setVariable",
+    "listJobs",
+    "createSession",
+    "destroySession",
+    "attachSession",
+    "listSessions",
+    "exit",
+];
+
+/// ID of the session hosted by the shell the server was started with. This session always
+/// exists.
+const PRIMARY_SESSION_ID: u64 = 0;
+
+/// Error code used when a message can't be parsed.
+const PARSE_ERROR: i64 = -32700;
+/// Error code used when a request names a method the server doesn't implement.
+const METHOD_NOT_FOUND: i64 = -32601;
+/// Error code used when a request's parameters are missing or malformed.
+const INVALID_PARAMS: i64 = -32602;
+/// Error code used when the shell fails to carry out a request.
+const INTERNAL_ERROR: i64 = -32603;
+/// Error code used when a request is received before the connection has been initialized.
+const NOT_INITIALIZED: i64 = -32002;
+/// Error code used when the client requests a protocol version the server doesn't support.
+const UNSUPPORTED_PROTOCOL_VERSION: i64 = -32001;
+/// Error code used when a request names a session that doesn't exist.
+const SESSION_NOT_FOUND: i64 = -32003;
+
+/// A shell hosted by a session. Requests against the same session are carried out one at a
+/// time, while requests against different sessions may run concurrently.
+type SessionShell = Arc<tokio::sync::Mutex<brush_core::Shell>>;
+
+/// Serves RPC requests over the given transport until a client asks the server to exit.
+/// Listening transports serve each client connection concurrently.
+///
+/// # Arguments
+///
+/// * `shell` - The shell to drive.
+/// * `transport` - The transport to serve requests over.
+pub(crate) async fn serve(
+    shell: &mut brush_core::Shell,
+    transport: RpcTransport<'_>,
+) -> Result<(), std::io::Error> {
+    if matches!(transport, RpcTransport::Stdio) {
+        // Standard output carries protocol messages, so redirect the output of commands
+        // to standard error.
+        let stderr = shell.stderr();
+        shell.open_files.files.insert(1, stderr);
+    }
+
+    // The server takes ownership of the shell while it runs, so that connections can be
+    // served concurrently; the shell is handed back once the server exits.
+    let placeholder = shell.clone();
+    let server = Arc::new(Server::new(std::mem::replace(shell, placeholder)));
+
+    let result = serve_transport(&server, transport).await;
+
+    *shell = server.into_primary_shell().await;
+
+    result
+}
+
+async fn serve_transport(
+    server: &Arc<Server>,
+    transport: RpcTransport<'_>,
+) -> Result<(), std::io::Error> {
+    match transport {
+        RpcTransport::Stdio => {
+            Connection::new(server.clone())
+                .serve(tokio::io::stdin(), tokio::io::stdout())
+                .await
+        }
+        RpcTransport::Tcp(address) => {
+            // N.B. Requests aren't authenticated, so anyone able to connect could run
+            // commands as the current user. We only listen on loopback addresses, so that
+            // only local users can do so.
+            let addresses: Vec<_> = tokio::net::lookup_host(address).await?.collect();
+            if addresses.is_empty() || !addresses.iter().all(|a| a.ip().is_loopback()) {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    std::format!("refusing to listen on non-loopback address: {address}"),
+                ));
+            }
+
+            let listener = tokio::net::TcpListener::bind(addresses.as_slice()).await?;
+            let listener = &listener;
+
+            serve_connections(server, move || async move {
+                listener.accept().await.map(|(stream, _)| stream)
+            })
+            .await
+        }
+        #[cfg(unix)]
+        RpcTransport::UnixSocket(socket_path) => {
+            let listener = tokio::net::UnixListener::bind(socket_path)?;
+            let listener = &listener;
+
+            let result = serve_connections(server, move || async move {
+                listener.accept().await.map(|(stream, _)| stream)
+            })
+            .await;
+
+            // Clean up the socket; there's nothing useful to do if that fails.
+            let _ = std::fs::remove_file(socket_path);
+
+            result
+        }
+    }
+}
+
+/// Accepts client connections until a client asks the server to exit, serving each one in
+/// its own task. Any connections still open when the server exits are closed.
+///
+/// # Arguments
+///
+/// * `server` - The server to serve requests with.
+/// * `accept` - Function that waits for and accepts the next client connection.
+async fn serve_connections<S, F, Fut>(
+    server: &Arc<Server>,
+    mut accept: F,
+) -> Result<(), std::io::Error>
+where
+    S: AsyncRead + AsyncWrite + Send + 'static,
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<S, std::io::Error>>,
+{
+    let mut exit_requested = server.exit_requested.subscribe();
+    let mut connections = tokio::task::JoinSet::new();
+
+    let result = loop {
+        tokio::select! {
+            accepted = accept() => match accepted {
+                Ok(stream) => {
+                    let connection = Connection::new(server.clone());
+                    connections.spawn(connection.serve_stream(stream));
+                }
+                Err(e) => break Err(e),
+            },
+            _ = exit_requested.changed() => break Ok(()),
+        }
+
+        // Reap connections that have since closed.
+        while connections.try_join_next().is_some() {}
+    };
+
+    connections.shutdown().await;
+
+    result
+}
+
+/// Hosts the sessions shared by all of the server's client connections.
+struct Server {
+    /// Shells hosted by each session, keyed by session ID.
+    sessions: std::sync::Mutex<HashMap<u64, SessionShell>>,
+    /// Pristine copy of the primary shell, from which new sessions are created without
+    /// paying the cost of shell startup.
+    template: std::sync::Mutex<brush_core::Shell>,
+    /// ID to assign to the next session created.
+    next_session_id: AtomicU64,
+    /// Set once a client has asked the server to exit.
+    exit_requested: tokio::sync::watch::Sender<bool>,
+}
+
+impl Server {
+    fn new(shell: brush_core::Shell) -> Self {
+        let template = shell.clone();
+        let sessions =
+            HashMap::from([(PRIMARY_SESSION_ID, Arc::new(tokio::sync::Mutex::new(shell)))]);
+
+        Self {
+            sessions: std::sync::Mutex::new(sessions),
+            template: std::sync::Mutex::new(template),
+            next_session_id: AtomicU64::new(PRIMARY_SESSION_ID + 1),
+            exit_requested: tokio::sync::watch::channel(false).0,
+        }
+    }
+
+    /// Tears down the server, handing back the shell hosted by the primary session. All
+    /// connections should have been closed first; otherwise, they may still be using the
+    /// shell, and a copy of it is returned instead.
+    async fn into_primary_shell(self: Arc<Self>) -> brush_core::Shell {
+        let primary = self
+            .sessions
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .remove(&PRIMARY_SESSION_ID)
+            .unwrap();
+
+        drop(self);
+
+        match Arc::try_unwrap(primary) {
+            Ok(shell) => shell.into_inner(),
+            Err(primary) => primary.lock().await.clone(),
+        }
+    }
+
+    fn session(&self, session_id: u64) -> Result<Option<SessionShell>, (i64, String)> {
+        Ok(self.lock_sessions()?.get(&session_id).cloned())
+    }
+
+    /// Locks the map of sessions, failing if a panic while it was locked left it poisoned.
+    fn lock_sessions(
+        &self,
+    ) -> Result<std::sync::MutexGuard<'_, HashMap<u64, SessionShell>>, (i64, String)> {
+        self.sessions.lock().map_err(poisoned_lock)
+    }
+
+    fn is_exit_requested(&self) -> bool {
+        *self.exit_requested.borrow()
+    }
+}
+
+/// Services RPC requests from a single client connection.
+struct Connection {
+    /// The server hosting the sessions targeted by the client's requests.
+    server: Arc<Server>,
+    /// Session targeted by requests from the client that don't name a session.
+    attached_session_id: u64,
+    /// Whether the client has initialized its connection.
+    initialized: bool,
+}
+
+impl Connection {
+    /// Returns a new connection that's attached to the primary session and has yet to be
+    /// initialized.
+    fn new(server: Arc<Server>) -> Self {
+        Self {
+            server,
+            attached_session_id: PRIMARY_SESSION_ID,
+            initialized: false,
+        }
+    }
+
+    /// Serves requests from a client connected via the given stream.
+    ///
+    /// # Arguments
+    ///
+    /// * `stream` - The stream connected to the client.
+    async fn serve_stream(mut self, stream: impl AsyncRead + AsyncWrite) {
+        let (reader, writer) = tokio::io::split(stream);
+        if let Err(e) = self.serve(reader, writer).await {
+            tracing::warn!("RPC connection failed: {e}");
+        }
+    }
+
+    /// Serves requests read from the given reader until the client asks the server to
+    /// exit or the input is closed. Requests are carried out in the order they're read.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - The reader to read messages from.
+    /// * `writer` - The writer to write responses to.
+    async fn serve(
+        &mut self,
+        reader: impl AsyncRead + Unpin,
+        mut writer: impl AsyncWrite + Unpin,
+    ) -> Result<(), std::io::Error> {
+        let mut lines = BufReader::new(reader).lines();
+
+        while let Some(line) = lines.next_line().await? {
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let response = match serde_json::from_str::<Value>(line.as_str()) {
+                Ok(message) => self.handle_message(&message).await,
+                Err(e) => Some(error_response(&Value::Null, PARSE_ERROR, e.to_string())),
+            };
+
+            if let Some(response) = response {
+                writer.write_all(response.to_string().as_bytes()).await?;
+                writer.write_all(b"\n").await?;
+                writer.flush().await?;
+            }
+
+            if self.server.is_exit_requested() {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Handles a single message, returning the response to send (if any). Notifications
+    /// (i.e., messages without an ID) don't receive responses.
+    async fn handle_message(&mut self, message: &Value) -> Option<Value> {
+        let method = message.get("method").and_then(Value::as_str).unwrap_or("");
+        let params = message.get("params").cloned().unwrap_or(Value::Null);
+
+        let result = self.handle_request(method, &params).await;
+
+        let id = message.get("id")?;
+        let response = match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err((code, message)) => error_response(id, code, message),
+        };
+
+        Some(response)
+    }
+
+    async fn handle_request(
+        &mut self,
+        method: &str,
+        params: &Value,
+    ) -> Result<Value, (i64, String)> {
+        if method == "initialize" {
+            return self.initialize(params);
+        } else if !self.initialized {
+            return Err((
+                NOT_INITIALIZED,
+                String::from("connection not yet initialized"),
+            ));
+        }
+
+        match method {
+            "run" => self.run(params).await,
+            "complete" => self.complete(params).await,
+            "expand" => self.expand(params).await,
+            "getVariable" => self.get_variable(params).await,
+            "setVariable" => self.set_variable(params).await,
+            "listJobs" => self.list_jobs(params).await,
+            "createSession" => self.create_session(),
+            "destroySession" => self.destroy_session(params),
+            "attachSession" => self.attach_session(params),
+            "listSessions" => self.list_sessions(),
+            "exit" => {
+                self.server.exit_requested.send_replace(true);
+                Ok(Value::Null)
+            }
+            _ => Err((METHOD_NOT_FOUND, std::format!("unhandled method: {method}"))),
+        }
+    }
+
+    /// Negotiates the protocol version and capabilities for the connection. Clients may
+    /// state the protocol version they implement; the request fails if the server doesn't
+    /// implement it.
+    fn initialize(&mut self, params: &Value) -> Result<Value, (i64, String)> {
+        if let Some(version) = params.get("protocolVersion") {
+            if version.as_u64() != Some(PROTOCOL_VERSION) {
+                return Err((
+                    UNSUPPORTED_PROTOCOL_VERSION,
+                    std::format!("unsupported protocol version: {version}"),
+                ));
+            }
+        }
+
+        self.initialized = true;
+
+        Ok(json!({
+            "protocolVersion": PROTOCOL_VERSION,
+            "capabilities": {
+                "methods": METHODS,
+            },
+            "serverInfo": {
+                "name": productinfo::PRODUCT_NAME,
+                "version": productinfo::PRODUCT_VERSION,
+            },
+        }))
+    }
+
+    /// Runs a command string, returning its exit code along with what it wrote to
+    /// standard output and standard error.
+    async fn run(&mut self, params: &Value) -> Result<Value, (i64, String)> {
+        let command = string_param(params, "command")?;
+        let shell = self.session_shell(params)?;
+        let mut shell = shell.lock().await;
+
+        let exec_params = shell.default_exec_params();
+        let (result, stdout, stderr) = shell
+            .run_string_capturing_output(command.to_owned(), &exec_params)
+            .await
+            .map_err(internal_error)?;
+
+        Ok(json!({
+            "exitCode": result.exit_code,
+            "stdout": stdout,
+            "stderr": stderr,
+        }))
+    }
+
+    /// Generates completions for a command line at the given cursor position, expressed as
+    /// a byte offset into the line.
+    async fn complete(&mut self, params: &Value) -> Result<Value, (i64, String)> {
+        let line = string_param(params, "line")?;
+        let cursor = match params.get("cursor") {
+            Some(cursor) => cursor
+                .as_u64()
+                .and_then(|cursor| usize::try_from(cursor).ok())
+                .ok_or_else(|| invalid_param("cursor"))?,
+            None => line.len(),
+        };
+
+        if !line.is_char_boundary(cursor) {
+            return Err(invalid_param("cursor"));
+        }
+
+        let shell = self.session_shell(params)?;
+        let completions = shell
+            .lock()
+            .await
+            .get_completions(line, cursor)
+            .await
+            .map_err(internal_error)?;
+
+        Ok(json!({
+            "insertionIndex": completions.insertion_index,
+            "deleteCount": completions.delete_count,
+            "candidates": completions.candidates.into_iter().collect::<Vec<_>>(),
+        }))
+    }
+
+    /// Expands a word. If field splitting was requested, the resulting fields are returned;
+    /// otherwise the single expanded string is returned.
+    async fn expand(&mut self, params: &Value) -> Result<Value, (i64, String)> {
+        let word = string_param(params, "word")?;
+        let split = params
+            .get("split")
+            .and_then(Value::as_bool)
+            .unwrap_or(false);
+
+        let shell = self.session_shell(params)?;
+        let mut shell = shell.lock().await;
+
+        if split {
+            let fields = shell
+                .full_expand_and_split_string(word)
+                .await
+                .map_err(internal_error)?;
+            Ok(json!({ "fields": fields }))
+        } else {
+            let value = shell
+                .basic_expand_string(word)
+                .await
+                .map_err(internal_error)?;
+            Ok(json!({ "value": value }))
+        }
+    }
+
+    /// Retrieves the value and attributes of a variable; yields `null` if the variable
+    /// isn't set.
+    async fn get_variable(&mut self, params: &Value) -> Result<Value, (i64, String)> {
+        let name = string_param(params, "name")?;
+        let shell = self.session_shell(params)?;
+        let shell = shell.lock().await;
+
+        let Some((_, var)) = shell.env.get(name) else {
+            return Ok(Value::Null);
+        };
+
+        let value = var.value();
+        let mut result = json!({
+            "exported": var.is_exported(),
+            "readonly": var.is_readonly(),
+            "integer": var.is_treated_as_integer(),
+        });
+
+        if value.is_array() {
+            result["keys"] = Value::from(value.get_element_keys());
+            result["values"] = Value::from(value.get_element_values());
+        } else {
+            result["value"] = Value::from(value.to_cow_string().as_ref());
+        }
+
+        Ok(result)
+    }
+
+    /// Assigns a string value to a variable.
+    async fn set_variable(&mut self, params: &Value) -> Result<Value, (i64, String)> {
+        let name = string_param(params, "name")?;
+        let value = string_param(params, "value")?;
+
+        if !brush_parser::is_valid_name(name) {
+            return Err(invalid_param("name"));
+        }
+
+        self.session_shell(params)?
+            .lock()
+            .await
+            .set_var_str(name, value)
+            .map_err(internal_error)?;
+
+        Ok(Value::Null)
+    }
+
+    /// Lists the jobs managed by the shell.
+    async fn list_jobs(&mut self, params: &Value) -> Result<Value, (i64, String)> {
+        let shell = self.session_shell(params)?;
+        let jobs = shell
+            .lock()
+            .await
+            .jobs
+            .jobs
+            .iter()
+            .map(|job| {
+                json!({
+                    "id": job.id,
+                    "state": job.state.to_string(),
+                    "command": job.command_line,
+                    "annotation": job.get_annotation().to_string(),
+                    "pid": job.get_representative_pid(),
+                })
+            })
+            .collect();
+
+        Ok(jobs)
+    }
+
+    /// Creates a new session, hosting a fresh shell, and returns its ID.
+    fn create_session(&mut self) -> Result<Value, (i64, String)> {
+        let session_id = self.server.next_session_id.fetch_add(1, Ordering::Relaxed);
+        let shell = self.server.template.lock().map_err(poisoned_lock)?.clone();
+
+        self.server
+            .lock_sessions()?
+            .insert(session_id, Arc::new(tokio::sync::Mutex::new(shell)));
+
+        Ok(json!({ "sessionId": session_id }))
+    }
+
+    /// Destroys a session, discarding its shell. The primary session can't be destroyed.
+    fn destroy_session(&mut self, params: &Value) -> Result<Value, (i64, String)> {
+        let session_id = session_id_param(params)?.ok_or_else(|| invalid_param("sessionId"))?;
+
+        if session_id == PRIMARY_SESSION_ID {
+            return Err(invalid_param("sessionId"));
+        }
+
+        if self.server.lock_sessions()?.remove(&session_id).is_none() {
+            return Err(session_not_found(session_id));
+        }
+
+        if self.attached_session_id == session_id {
+            self.attached_session_id = PRIMARY_SESSION_ID;
+        }
+
+        Ok(Value::Null)
+    }
+
+    /// Attaches the client to a session, so that its subsequent requests that don't name
+    /// a session target it.
+    fn attach_session(&mut self, params: &Value) -> Result<Value, (i64, String)> {
+        let session_id = session_id_param(params)?.ok_or_else(|| invalid_param("sessionId"))?;
+
+        if self.server.session(session_id)?.is_none() {
+            return Err(session_not_found(session_id));
+        }
+
+        self.attached_session_id = session_id;
+
+        Ok(Value::Null)
+    }
+
+    /// Lists the IDs of all sessions, in ascending order.
+    fn list_sessions(&self) -> Result<Value, (i64, String)> {
+        let mut session_ids: Vec<_> = self.server.lock_sessions()?.keys().copied().collect();
+        session_ids.sort_unstable();
+
+        Ok(json!({
+            "sessionIds": session_ids,
+            "attachedSessionId": self.attached_session_id,
+        }))
+    }
+
+    /// Returns the shell targeted by a request: the one hosted by the session named in its
+    /// parameters, or else by the session the client is attached to.
+    fn session_shell(&self, params: &Value) -> Result<SessionShell, (i64, String)> {
+        let session_id = session_id_param(params)?.unwrap_or(self.attached_session_id);
+        self.server
+            .session(session_id)?
+            .ok_or_else(|| session_not_found(session_id))
+    }
+}
+
+fn error_response(id: &Value, code: i64, message: String) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": code, "message": message },
+    })
+}
+
+fn string_param<'a>(params: &'a Value, name: &str) -> Result<&'a str, (i64, String)> {
+    params
+        .get(name)
+        .and_then(Value::as_str)
+        .ok_or_else(|| invalid_param(name))
+}
+
+fn session_id_param(params: &Value) -> Result<Option<u64>, (i64, String)> {
+    params
+        .get("sessionId")
+        .map(|id| id.as_u64().ok_or_else(|| invalid_param("sessionId")))
+        .transpose()
+}
+
+fn session_not_found(session_id: u64) -> (i64, String) {
+    (
+        SESSION_NOT_FOUND,
+        std::format!("no such session: {session_id}"),
+    )
+}
+
+fn invalid_param(name: &str) -> (i64, String) {
+    (
+        INVALID_PARAMS,
+        std::format!("missing or invalid parameter: {name}"),
+    )
+}
+
+#[allow(clippy::needless_pass_by_value)]
+fn internal_error(error: brush_core::Error) -> (i64, String) {
+    (INTERNAL_ERROR, error.to_string())
+}
+
+#[allow(clippy::needless_pass_by_value)]
+fn poisoned_lock<T>(_error: std::sync::PoisonError<T>) -> (i64, String) {
+    (
+        INTERNAL_ERROR,
+        String::from("server state is unavailable after an earlier failure"),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+
+    /// Sends the given messages to a server over an in-memory stream, one per line, and
+    /// returns the responses it sends back.
+    async fn exchange(server: &Arc<Server>, messages: &[&str]) -> Result<Vec<Value>> {
+        let (client, server_stream) = tokio::io::duplex(64 * 1024);
+        let (client_reader, mut client_writer) = tokio::io::split(client);
+
+        for message in messages {
+            client_writer.write_all(message.as_bytes()).await?;
+            client_writer.write_all(b"\n").await?;
+        }
+        client_writer.shutdown().await?;
+
+        let (server_reader, server_writer) = tokio::io::split(server_stream);
+        Connection::new(server.clone())
+            .serve(server_reader, server_writer)
+            .await?;
+
+        let mut responses = vec![];
+        let mut lines = BufReader::new(client_reader).lines();
+        while let Some(line) = lines.next_line().await? {
+            responses.push(serde_json::from_str(line.as_str())?);
+        }
+
+        Ok(responses)
+    }
+
+    async fn new_server() -> Result<Arc<Server>> {
+        let shell = brush_core::Shell::new(&brush_core::CreateOptions::default()).await?;
+        Ok(Arc::new(Server::new(shell)))
+    }
+
+    const INITIALIZE: &str = r#"{"jsonrpc":"2.0","id":0,"method":"initialize"}"#;
+
+    #[tokio::test]
+    async fn request_response_framing() -> Result<()> {
+        let server = new_server().await?;
+        let responses = exchange(
+            &server,
+            &[
+                r#"{"jsonrpc":"2.0","id":"a","method":"listSessions"}"#,
+                INITIALIZE,
+                "",
+                r#"{"jsonrpc":"2.0","method":"listSessions"}"#,
+                r#"{"jsonrpc":"2.0","id":7,"method":"listSessions"}"#,
+            ],
+        )
+        .await?;
+
+        // Requests before initialization fail, notifications get no response, and each
+        // response echoes its request's ID.
+        assert_eq!(responses.len(), 3);
+        assert_eq!(responses[0]["id"], "a");
+        assert_eq!(responses[0]["error"]["code"], NOT_INITIALIZED);
+        assert_eq!(responses[1]["id"], 0);
+        assert_eq!(responses[1]["result"]["protocolVersion"], PROTOCOL_VERSION);
+        assert_eq!(responses[2]["id"], 7);
+        assert_eq!(
+            responses[2]["result"]["sessionIds"],
+            json!([PRIMARY_SESSION_ID])
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn unknown_method() -> Result<()> {
+        let server = new_server().await?;
+        let responses = exchange(
+            &server,
+            &[
+                INITIALIZE,
+                r#"{"jsonrpc":"2.0","id":1,"method":"noSuchMethod"}"#,
+            ],
+        )
+        .await?;
+
+        assert_eq!(responses[1]["id"], 1);
+        assert_eq!(responses[1]["error"]["code"], METHOD_NOT_FOUND);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn poisoned_sessions() -> Result<()> {
+        let server = new_server().await?;
+
+        // Poison the map of sessions, as a panic while it's locked would.
+        let _ = std::thread::scope(|scope| {
+            scope
+                .spawn(|| {
+                    let _sessions = server.sessions.lock();
+                    std::panic::resume_unwind(Box::new("poisoning sessions"));
+                })
+                .join()
+        });
+
+        let responses = exchange(
+            &server,
+            &[
+                INITIALIZE,
+                r#"{"jsonrpc":"2.0","id":1,"method":"listSessions"}"#,
+                r#"{"jsonrpc":"2.0","id":2,"method":"createSession"}"#,
+                r#"{"jsonrpc":"2.0","id":3,"method":"run","params":{"command":"true"}}"#,
+            ],
+        )
+        .await?;
+
+        // Requests that need the sessions fail, rather than taking down the server.
+        assert_eq!(responses.len(), 4);
+        for response in &responses[1..] {
+            assert_eq!(response["error"]["code"], INTERNAL_ERROR);
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn malformed_json() -> Result<()> {
+        let server = new_server().await?;
+        let responses = exchange(
+            &server,
+            &[INITIALIZE, r#"{"jsonrpc":"2.0","id":1,"#, INITIALIZE],
+        )
+        .await?;
+
+        // The malformed message is reported without an ID, and later messages are still
+        // served.
+        assert_eq!(responses.len(), 3);
+        assert_eq!(responses[1]["id"], Value::Null);
+        assert_eq!(responses[1]["error"]["code"], PARSE_ERROR);
+        assert_eq!(responses[2]["id"], 0);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn run_exit_code() -> Result<()> {
+        let server = new_server().await?;
+        let responses = exchange(
+            &server,
+            &[
+                INITIALIZE,
+                r#"{"jsonrpc":"2.0","id":1,"method":"run","params":{"command":"true"}}"#,
+                r#"{"jsonrpc":"2.0","id":2,"method":"run","params":{"command":"(exit 42)"}}"#,
+                r#"{"jsonrpc":"2.0","id":3,"method":"run","params":{}}"#,
+            ],
+        )
+        .await?;
+
+        assert_eq!(responses[1]["result"]["exitCode"], 0);
+        assert_eq!(responses[2]["result"]["exitCode"], 42);
+        assert_eq!(responses[3]["error"]["code"], INVALID_PARAMS);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn run_captures_output() -> Result<()> {
+        let server = new_server().await?;
+        let responses = exchange(
+            &server,
+            &[
+                INITIALIZE,
+                r#"{"jsonrpc":"2.0","id":1,"method":"run","params":{"command":"echo out; echo err >&2"}}"#,
+            ],
+        )
+        .await?;
+
+        assert_eq!(responses[1]["result"]["exitCode"], 0);
+        assert_eq!(responses[1]["result"]["stdout"], "out\n");
+        assert_eq!(responses[1]["result"]["stderr"], "err\n");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn sessions_are_shared_across_connections() -> Result<()> {
+        let server = new_server().await?;
+
+        let first = exchange(
+            &server,
+            &[
+                INITIALIZE,
+                r#"{"jsonrpc":"2.0","id":1,"method":"createSession"}"#,
+                r#"{"jsonrpc":"2.0","id":2,"method":"attachSession","params":{"sessionId":1}}"#,
+                r#"{"jsonrpc":"2.0","id":3,"method":"setVariable","params":{"name":"x","value":"1"}}"#,
+            ],
+        )
+        .await?;
+        assert_eq!(first[1]["result"]["sessionId"], 1);
+
+        // A new connection starts out attached to the primary session, but can reach the
+        // session created by the first one.
+        let second = exchange(
+            &server,
+            &[
+                INITIALIZE,
+                r#"{"jsonrpc":"2.0","id":1,"method":"listSessions"}"#,
+                r#"{"jsonrpc":"2.0","id":2,"method":"getVariable","params":{"name":"x","sessionId":1}}"#,
+            ],
+        )
+        .await?;
+        assert_eq!(second[1]["result"]["sessionIds"], json!([0, 1]));
+        assert_eq!(second[1]["result"]["attachedSessionId"], PRIMARY_SESSION_ID);
+        assert_eq!(second[2]["result"]["value"], "1");
+
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn sessions_run_concurrently() -> Result<()> {
+        let server = new_server().await?;
+        let temp_dir = assert_fs::TempDir::new()?;
+        let marker = temp_dir.path().join("marker");
+        let marker = marker.to_string_lossy();
+
+        // Start a command in one session that waits for a command in another session to
+        // run; it could never complete if requests were served one at a time.
+        let wait = json!({
+            "jsonrpc": "2.0",
+            "id": 2,
+            "method": "run",
+            "params": {
+                "command": std::format!("until [[ -e '{marker}' ]]; do sleep 0.01; done"),
+                "sessionId": 1,
+            },
+        })
+        .to_string();
+        let waiter = {
+            let server = server.clone();
+            tokio::spawn(async move {
+                exchange(
+                    &server,
+                    &[
+                        INITIALIZE,
+                        r#"{"jsonrpc":"2.0","id":1,"method":"createSession"}"#,
+                        wait.as_str(),
+                    ],
+                )
+                .await
+            })
+        };
+
+        // Make sure the session exists before the other connection signals it.
+        while server.session(1).ok().flatten().is_none() {
+            tokio::task::yield_now().await;
+        }
+
+        let signal = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "run",
+            "params": { "command": std::format!(": > '{marker}'") },
+        })
+        .to_string();
+        let responses = exchange(&server, &[INITIALIZE, signal.as_str()]).await?;
+        assert_eq!(responses[1]["result"]["exitCode"], 0);
+
+        let responses =
+            tokio::time::timeout(std::time::Duration::from_secs(30), waiter).await???;
+        assert_eq!(responses[2]["result"]["exitCode"], 0);
+
+        Ok(())
+    }
+}
@@ -121,3 +121,27 @@ impl ShellFactory for MinimalShellFactory {
         }
     }
 }
+
+pub(crate) struct ExternalShellFactory;
+
+impl ShellFactory for ExternalShellFactory {
+    #[cfg(all(feature = "external", any(windows, unix)))]
+    type ShellType = brush_interactive::ExternalShell;
+    #[cfg(any(not(feature = "external"), not(any(windows, unix))))]
+    type ShellType = StubShell;
+
+    #[allow(unused)]
+    async fn create(
+        &self,
+        options: &brush_interactive::Options,
+    ) -> Result<Self::ShellType, brush_interactive::ShellError> {
+        #[cfg(all(feature = "external", any(windows, unix)))]
+        {
+            brush_interactive::ExternalShell::new(options).await
+        }
+        #[cfg(any(not(feature = "external"), not(any(windows, unix))))]
+        {
+            Err(brush_interactive::ShellError::InputBackendNotSupported)
+        }
+    }
+}
@@ -64,15 +64,56 @@ impl TestConfig {
         })
     }
 
-    #[allow(clippy::unnecessary_wraps)]
     pub fn for_sh_testing(options: &TestOptions) -> Result<Self> {
-        // Skip rc file and profile for deterministic behavior across systems/distros.
-        Ok(Self {
-            name: String::from(SH_CONFIG_NAME),
-            oracle_shell: ShellConfig {
+        Self::for_posix_testing(
+            SH_CONFIG_NAME,
+            ShellConfig {
                 which: WhichShell::NamedShell(PathBuf::from("sh")),
                 default_args: vec![],
             },
+            options,
+        )
+    }
+
+    pub fn for_dash_testing(options: &TestOptions) -> Result<Self> {
+        Self::for_posix_testing(
+            DASH_CONFIG_NAME,
+            ShellConfig {
+                which: WhichShell::NamedShell(options.dash_path.clone()),
+                default_args: vec![],
+            },
+            options,
+        )
+    }
+
+    pub fn for_zsh_sh_testing(options: &TestOptions) -> Result<Self> {
+        // Skip zsh's startup files, and have it emulate sh from the start.
+        Self::for_posix_testing(
+            ZSH_SH_CONFIG_NAME,
+            ShellConfig {
+                which: WhichShell::NamedShell(options.zsh_path.clone()),
+                default_args: vec![
+                    String::from("--no-rcs"),
+                    String::from("--emulate"),
+                    String::from("sh"),
+                ],
+            },
+            options,
+        )
+    }
+
+    /// Returns a config that compares brush running in POSIX (sh) mode against the given
+    /// oracle shell.
+    #[allow(clippy::unnecessary_wraps)]
+    fn for_posix_testing(
+        name: &str,
+        oracle_shell: ShellConfig,
+        options: &TestOptions,
+    ) -> Result<Self> {
+        // Skip rc file and profile for deterministic behavior across systems/distros.
+        Ok(Self {
+            name: String::from(name),
+            oracle_shell,
             oracle_version_str: None,
             test_shell: ShellConfig {
                 which: WhichShell::ShellUnderTest(PathBuf::from(&options.brush_path)),
@@ -128,6 +169,12 @@ async fn cli_integration_tests(mut options: TestOptions) -> Result<()> {
     if options.should_enable_config(SH_CONFIG_NAME) {
         test_configs.push(TestConfig::for_sh_testing(&options)?);
     }
+    if options.should_enable_config(DASH_CONFIG_NAME) {
+        test_configs.push(TestConfig::for_dash_testing(&options)?);
+    }
+    if options.should_enable_config(ZSH_SH_CONFIG_NAME) {
+        test_configs.push(TestConfig::for_zsh_sh_testing(&options)?);
+    }
 
     // Generate a glob pattern to find all the YAML test case files.
     let glob_pattern = test_cases_dir
@@ -255,9 +302,165 @@ fn report_integration_test_results(
         OutputFormat::Pretty => report_integration_test_results_pretty(results, options),
         OutputFormat::Junit => report_integration_test_results_junit(results, options),
         OutputFormat::Terse => Ok(()),
+        OutputFormat::Markdown | OutputFormat::Html => {
+            report_integration_test_results_conformance(results, options)
+        }
     }
 }
 
+/// Tally of test case outcomes for one test case set under one config.
+#[derive(Clone, Copy, Default)]
+struct ConformanceTally {
+    passed: u32,
+    known_failures: u32,
+    failed: u32,
+}
+
+impl ConformanceTally {
+    fn add(&mut self, other: Self) {
+        self.passed += other.passed;
+        self.known_failures += other.known_failures;
+        self.failed += other.failed;
+    }
+
+    fn status_symbol(self) -> &'static str {
+        if self.failed > 0 {
+            "❌"
+        } else if self.known_failures > 0 {
+            "🟡"
+        } else {
+            "✅"
+        }
+    }
+
+    fn describe(self) -> String {
+        use std::fmt::Write as _;
+
+        let total = self.passed + self.known_failures + self.failed;
+        if total == 0 {
+            return String::from("—");
+        }
+
+        let mut description = format!("{} {}/{total}", self.status_symbol(), self.passed);
+        if self.known_failures > 0 {
+            let _ = write!(description, " ({} known)", self.known_failures);
+        }
+        if self.failed > 0 {
+            let _ = write!(description, " ({} failed)", self.failed);
+        }
+
+        description
+    }
+}
+
+/// Writes a conformance matrix summarizing, for each test case set (i.e., feature area) and
+/// config, how many test cases passed, are known to fail, or unexpectedly failed.
+fn report_integration_test_results_conformance(
+    results: Vec<TestCaseSetResults>,
+    options: &TestOptions,
+) -> Result<()> {
+    let mut config_names: Vec<String> = vec![];
+    let mut rows: std::collections::BTreeMap<String, HashMap<String, ConformanceTally>> =
+        std::collections::BTreeMap::new();
+    let mut totals: HashMap<String, ConformanceTally> = HashMap::new();
+
+    for result in results {
+        if !config_names.contains(&result.config_name) {
+            config_names.push(result.config_name.clone());
+        }
+
+        let tally = ConformanceTally {
+            passed: result.success_count,
+            known_failures: result.known_failure_count,
+            failed: result.fail_count,
+        };
+
+        rows.entry(result.name.unwrap_or_else(|| String::from("(unnamed)")))
+            .or_default()
+            .entry(result.config_name.clone())
+            .or_default()
+            .add(tally);
+        totals.entry(result.config_name).or_default().add(tally);
+    }
+
+    let cell = |tallies: &HashMap<String, ConformanceTally>, config_name: &String| {
+        tallies
+            .get(config_name)
+            .copied()
+            .unwrap_or_default()
+            .describe()
+    };
+
+    let mut out = std::io::stdout();
+    if matches!(options.format, OutputFormat::Html) {
+        writeln!(out, "<!DOCTYPE html>")?;
+        writeln!(out, "<html>")?;
+        writeln!(
+            out,
+            "<head><meta charset=\"utf-8\"><title>brush conformance</title></head>"
+        )?;
+        writeln!(out, "<body>")?;
+        writeln!(out, "<h1>brush conformance</h1>")?;
+        writeln!(out, "<table>")?;
+        write!(out, "<tr><th>Area</th>")?;
+        for config_name in &config_names {
+            write!(out, "<th>{}</th>", escape_html(config_name))?;
+        }
+        writeln!(out, "</tr>")?;
+        for (name, tallies) in rows
+            .iter()
+            .chain(std::iter::once((&String::from("Total"), &totals)))
+        {
+            write!(out, "<tr><td>{}</td>", escape_html(name))?;
+            for config_name in &config_names {
+                write!(
+                    out,
+                    "<td>{}</td>",
+                    escape_html(cell(tallies, config_name).as_str())
+                )?;
+            }
+            writeln!(out, "</tr>")?;
+        }
+        writeln!(out, "</table>")?;
+        writeln!(out, "</body>")?;
+        writeln!(out, "</html>")?;
+    } else {
+        writeln!(out, "# brush conformance")?;
+        writeln!(out)?;
+        writeln!(out, "| Area | {} |", config_names.join(" | "))?;
+        writeln!(out, "|------|{}", "------|".repeat(config_names.len()))?;
+        for (name, tallies) in rows
+            .iter()
+            .chain(std::iter::once((&String::from("**Total**"), &totals)))
+        {
+            let cells: Vec<_> = config_names
+                .iter()
+                .map(|config_name| cell(tallies, config_name))
+                .collect();
+            writeln!(
+                out,
+                "| {} | {} |",
+                name.replace('|', "\\|"),
+                cells.join(" | ")
+            )?;
+        }
+        writeln!(out)?;
+        writeln!(
+            out,
+            "✅ all passed; 🟡 some known to fail (e.g., known divergences); ❌ some failed"
+        )?;
+    }
+
+    Ok(())
+}
+
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
 fn report_integration_test_results_junit(
     results: Vec<TestCaseSetResults>,
     options: &TestOptions,
@@ -345,6 +548,14 @@ struct TestCase {
     pub test_files: Vec<TestFile>,
     #[serde(default)]
     pub known_failure: bool,
+    /// Configs (i.e., oracles) under which the test shell is known to diverge from the
+    /// oracle, mapped to a description of the divergence; under these configs, the case
+    /// is treated as a known failure.
+    #[serde(default)]
+    pub known_divergences: HashMap<String, String>,
+    /// If non-empty, the only configs (i.e., oracles) the case is run under
+    #[serde(default)]
+    pub configs: HashSet<String>,
     #[serde(default)]
     pub incompatible_configs: HashSet<String>,
     #[serde(default)]
@@ -452,14 +663,15 @@ impl TestCaseSet {
                     comparison: RunComparison::ignored(),
                     name: test_case.name.clone(),
                     skip: true,
-                    known_failure: test_case.known_failure,
+                    known_failure: test_case.is_known_failure(&test_config),
+                    known_divergence: None,
                 }
             };
 
             if test_case_result.skip {
                 skip_count += 1;
             } else if test_case_result.success {
-                if test_case.known_failure {
+                if test_case_result.known_failure {
                     fail_count += 1;
                 } else {
                     success_count += 1;
@@ -467,7 +679,7 @@ impl TestCaseSet {
                         test_case_result.comparison.duration.oracle;
                     success_duration_comparison.test += test_case_result.comparison.duration.test;
                 }
-            } else if test_case.known_failure {
+            } else if test_case_result.known_failure {
                 known_failure_count += 1;
             } else {
                 fail_count += 1;
@@ -512,6 +724,7 @@ struct TestCaseResult {
     pub success: bool,
     pub skip: bool,
     pub known_failure: bool,
+    pub known_divergence: Option<String>,
     pub comparison: RunComparison,
 }
 
@@ -557,6 +770,9 @@ impl TestCaseResult {
             }
         } else if self.known_failure {
             writeln!(writer, "{}", "known failure.".bright_magenta())?;
+            if let Some(divergence) = &self.known_divergence {
+                writeln!(writer, "    known divergence: {}", divergence.italic())?;
+            }
             if !options.display_known_failure_details {
                 return Ok(());
             }
@@ -765,17 +981,25 @@ impl TestCase {
             comparison,
             name: self.name.clone(),
             skip: false,
-            known_failure: self.known_failure,
+            known_failure: self.is_known_failure(test_config),
+            known_divergence: self.known_divergences.get(&test_config.name).cloned(),
         })
     }
 
+    /// Returns whether the case is expected to fail under the given config.
+    pub fn is_known_failure(&self, test_config: &TestConfig) -> bool {
+        self.known_failure || self.known_divergences.contains_key(&test_config.name)
+    }
+
     pub fn should_skip(
         &self,
         test_case_set: &TestCaseSet,
         test_config: &TestConfig,
     ) -> Result<bool> {
         // Make sure it's compatible.
-        if self.incompatible_configs.contains(&test_config.name) {
+        if self.incompatible_configs.contains(&test_config.name)
+            || (!self.configs.is_empty() && !self.configs.contains(&test_config.name))
+        {
             return Ok(true);
         }
 
@@ -1028,13 +1252,22 @@ impl TestCase {
                             duration: start_time.elapsed(),
                         });
                     }
-                } else if let Some(control_code) = line.strip_prefix("#send:") {
-                    match control_code.to_lowercase().as_str() {
-                        "ctrl+d" => p.send(expectrl::ControlCode::EndOfTransmission)?,
-                        "tab" => p.send(expectrl::ControlCode::HorizontalTabulation)?,
-                        "enter" => p.send(expectrl::ControlCode::LineFeed)?,
-                        _ => (),
+                } else if let Some(pattern) = line.strip_prefix("#expect-regex:") {
+                    if let Err(inner) = p.expect(expectrl::Regex(pattern)) {
+                        return Ok(RunResult {
+                            exit_status: ExitStatus::from_raw(1),
+                            stdout: read_expectrl_log(log).unwrap_or_default(),
+                            stderr: std::format!("failed to expect /{pattern}/: {inner}"),
+                            duration: start_time.elapsed(),
+                        });
                     }
+                } else if let Some(key) = line.strip_prefix("#send:") {
+                    let Some(bytes) = encode_key(key.trim()) else {
+                        return Err(anyhow::anyhow!("unknown key in #send directive: {key}"));
+                    };
+                    p.send(bytes)?;
+                } else if let Some(millis) = line.strip_prefix("#wait:") {
+                    std::thread::sleep(std::time::Duration::from_millis(millis.trim().parse()?));
                 } else if line.trim() == "#expect-prompt" {
                     if let Err(inner) = p.expect("test$ ") {
                         return Ok(RunResult {
@@ -1283,6 +1516,10 @@ enum OutputFormat {
     Pretty,
     Junit,
     Terse,
+    /// Conformance matrix (test case set x config), as Markdown
+    Markdown,
+    /// Conformance matrix (test case set x config), as HTML
+    Html,
 }
 
 #[derive(Clone, Parser)]
@@ -1320,6 +1557,14 @@ struct TestOptions {
     #[clap(long = "bash-path", default_value = "bash", env = "BASH_PATH")]
     pub bash_path: PathBuf,
 
+    /// Optionally specify a non-default path for dash
+    #[clap(long = "dash-path", default_value = "dash", env = "DASH_PATH")]
+    pub dash_path: PathBuf,
+
+    /// Optionally specify a non-default path for zsh
+    #[clap(long = "zsh-path", default_value = "zsh", env = "ZSH_PATH")]
+    pub zsh_path: PathBuf,
+
     /// Optionally specify a non-default path for brush
     #[clap(long = "brush-path", default_value = "", env = "BRUSH_PATH")]
     pub brush_path: String,
@@ -1352,6 +1597,8 @@ struct TestOptions {
 
 const BASH_CONFIG_NAME: &str = "bash";
 const SH_CONFIG_NAME: &str = "sh";
+const DASH_CONFIG_NAME: &str = "dash";
+const ZSH_SH_CONFIG_NAME: &str = "zsh-sh";
 
 impl TestOptions {
     pub fn should_enable_config(&self, config: &str) -> bool {
@@ -1388,6 +1635,63 @@ impl TestOptions {
     }
 }
 
+/// Returns the bytes a terminal sends for the given key, as named in a `#send:` directive
+/// (e.g., `Enter`, `Ctrl+R`, `Alt+b`, or `Up`); names are case-insensitive, except for the
+/// character following `Alt+`.
+fn encode_key(name: &str) -> Option<Vec<u8>> {
+    let lower = name.to_lowercase();
+
+    if let Some(key) = lower.strip_prefix("ctrl+") {
+        let mut chars = key.chars();
+        let (Some(c), None) = (chars.next(), chars.next()) else {
+            return None;
+        };
+
+        let code = match c {
+            'a'..='z' => c as u8 - b'a' + 1,
+            '@' | ' ' => 0,
+            '[' => 0x1b,
+            '\\' => 0x1c,
+            ']' => 0x1d,
+            '^' => 0x1e,
+            '_' | '/' => 0x1f,
+            _ => return None,
+        };
+
+        return Some(vec![code]);
+    }
+
+    if lower.starts_with("alt+") {
+        // Alt is sent as an escape prefix.
+        let key = &name["alt+".len()..];
+        let mut chars = key.chars();
+        let mut bytes = vec![0x1b];
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => bytes.extend(c.to_string().as_bytes()),
+            _ => bytes.extend(encode_key(key)?),
+        }
+
+        return Some(bytes);
+    }
+
+    let bytes: &[u8] = match lower.as_str() {
+        "enter" => b"\n",
+        "tab" => b"\t",
+        "escape" | "esc" => b"\x1b",
+        "backspace" => b"\x7f",
+        "up" => b"\x1b[A",
+        "down" => b"\x1b[B",
+        "right" => b"\x1b[C",
+        "left" => b"\x1b[D",
+        "home" => b"\x1b[H",
+        "end" => b"\x1b[F",
+        "delete" => b"\x1b[3~",
+        _ => return None,
+    };
+
+    Some(bytes.to_vec())
+}
+
 fn read_expectrl_log(log: Vec<u8>) -> Result<String> {
     let output_str = String::from_utf8(log)?;
     let output: String = output_str
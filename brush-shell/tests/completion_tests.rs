@@ -341,3 +341,152 @@ async fn complete_find_command() -> Result<()> {
 
     Ok(())
 }
+
+/// Tests completion of builtins' arguments, which works without any completion scripts.
+#[tokio::test]
+async fn complete_builtin_args_without_completion_scripts() -> Result<()> {
+    let create_options = brush_core::CreateOptions {
+        no_profile: true,
+        no_rc: true,
+        ..Default::default()
+    };
+    let mut shell = brush_core::Shell::new(&create_options).await?;
+
+    for (line, expected) in [
+        ("shopt -s nocase", vec!["nocaseglob", "nocasematch"]),
+        ("set -o pipe", vec!["pipefail"]),
+        ("brushinfo pro", vec!["process"]),
+    ] {
+        let completions = shell.get_completions(line, line.len()).await?;
+        let results: Vec<_> = completions.candidates.into_iter().collect();
+
+        assert_eq!(results, expected, "completing: {line}");
+    }
+
+    Ok(())
+}
+
+/// Tests that the words passed to completion functions are split at the characters in
+/// COMP_WORDBREAKS, as in bash.
+#[tokio::test]
+async fn complete_words_split_by_comp_wordbreaks() -> Result<()> {
+    let create_options = brush_core::CreateOptions {
+        no_profile: true,
+        no_rc: true,
+        ..Default::default()
+    };
+    let mut shell = brush_core::Shell::new(&create_options).await?;
+
+    let params = shell.default_exec_params();
+    shell
+        .run_string(
+            r#"_describe_words() { local IFS=,; COMPREPLY=("${#COMP_WORDS[@]}|$COMP_CWORD|${COMP_WORDS[*]}|$2"); }; complete -F _describe_words cmd"#
+                .to_owned(),
+            &params,
+        )
+        .await?;
+
+    let line = "cmd a=b user@host:";
+    let completions = shell.get_completions(line, line.len()).await?;
+    let results: Vec<_> = completions.candidates.into_iter().collect();
+    assert_eq!(results, ["8|7|cmd,a,=,b,user,@,host,:|"]);
+
+    shell.env.set_global(
+        "COMP_WORDBREAKS",
+        brush_core::ShellVariable::new(" \t\n".into()),
+    )?;
+
+    let completions = shell.get_completions(line, line.len()).await?;
+    let results: Vec<_> = completions.candidates.into_iter().collect();
+    assert_eq!(results, ["3|2|cmd,a=b,user@host:|user@host:"]);
+
+    Ok(())
+}
+
+/// Tests that the arguments of an alias are completed as those of the command it expands to.
+#[tokio::test]
+async fn complete_alias_args_using_expanded_command() -> Result<()> {
+    let create_options = brush_core::CreateOptions {
+        no_profile: true,
+        no_rc: true,
+        ..Default::default()
+    };
+    let mut shell = brush_core::Shell::new(&create_options).await?;
+
+    let params = shell.default_exec_params();
+    shell
+        .run_string(
+            r#"_describe_words() { local IFS=,; COMPREPLY=("${COMP_WORDS[*]}|$COMP_LINE"); }; complete -F _describe_words git"#
+                .to_owned(),
+            &params,
+        )
+        .await?;
+    shell
+        .run_string(
+            r"shopt -s expand_aliases; alias g=git gc='g commit --quiet' loop=loop".to_owned(),
+            &params,
+        )
+        .await?;
+
+    let line = "gc --am";
+    let completions = shell.get_completions(line, line.len()).await?;
+    assert_eq!(completions.insertion_index, 3);
+    assert_eq!(completions.delete_count, 4);
+    let results: Vec<_> = completions.candidates.into_iter().collect();
+    assert_eq!(results, ["git,commit,--quiet,--am|git commit --quiet --am"]);
+
+    // An alias that expands to itself must not send completion into a loop.
+    let line = "loop x";
+    shell.get_completions(line, line.len()).await?;
+
+    Ok(())
+}
+
+/// Tests that the `j` builtin, once enabled, completes and changes to the directories that
+/// have been visited most frequently and recently.
+#[tokio::test]
+async fn complete_and_jump_to_ranked_directories() -> Result<()> {
+    let temp_dir = assert_fs::TempDir::new()?;
+    temp_dir.child("projects").child("brush").create_dir_all()?;
+    temp_dir
+        .child("projects")
+        .child("brushes")
+        .create_dir_all()?;
+    let root = temp_dir.path().canonicalize()?;
+
+    let create_options = brush_core::CreateOptions {
+        no_profile: true,
+        no_rc: true,
+        directory_rank_store: Some(std::sync::Arc::new(
+            brush_core::frecency::InMemoryRankStore::default(),
+        )),
+        ..Default::default()
+    };
+    let mut shell = brush_core::Shell::new(&create_options).await?;
+    let params = shell.default_exec_params();
+
+    // N.B. The builtin is disabled by default.
+    let result = shell.run_string("enable j".to_owned(), &params).await?;
+    assert_eq!(result.exit_code, 0);
+
+    for dir in ["projects/brushes", "projects/brush", "projects/brush", ""] {
+        shell.set_working_dir(root.join(dir).as_path())?;
+    }
+
+    let line = "j bru";
+    let completions = shell.get_completions(line, line.len()).await?;
+    let results: Vec<_> = completions.candidates.into_iter().collect();
+    assert_eq!(
+        results,
+        [
+            root.join("projects/brush").to_string_lossy(),
+            root.join("projects/brushes").to_string_lossy()
+        ]
+    );
+
+    let result = shell.run_string("j proj bru".to_owned(), &params).await?;
+    assert_eq!(result.exit_code, 0);
+    assert_eq!(shell.working_dir, root.join("projects/brush"));
+
+    Ok(())
+}
@@ -90,6 +90,27 @@ fn run_in_bg_then_fg() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[test]
+fn report_completed_background_job() -> anyhow::Result<()> {
+    let mut session = start_shell_session()?;
+
+    // Run a short-lived job in the background; make sure its completion is reported
+    // before a subsequent prompt.
+    session.expect_prompt()?;
+    session.send_line("sleep 0.1 &")?;
+    session.expect_prompt()?;
+    session.send_line("sleep 1")?;
+    session
+        .expect("Done")
+        .context("Completion of background job wasn't reported")?;
+    session.expect_prompt()?;
+
+    // Exit the shell.
+    session.exit()?;
+
+    Ok(())
+}
+
 #[test]
 fn run_pipeline_interactively() -> anyhow::Result<()> {
     let mut session = start_shell_session()?;
@@ -0,0 +1,522 @@
+#![no_main]
+
+//! Differential fuzzing of script execution: generates scripts from a small subset of the
+//! shell grammar, runs them under both brush and bash (each in its own empty temporary
+//! directory, with a scrubbed environment), and flags any difference in their stdout or exit
+//! status. Scripts that diverge are saved (as plain text) to the repro directory before the
+//! failure is reported; libFuzzer's own artifact can be minimized with `cargo fuzz tmin`.
+
+use anyhow::Result;
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+use std::fmt::Write as _;
+use std::path::PathBuf;
+
+/// Maximum nesting depth of compound commands; deeper statements are rendered as `:`.
+const MAX_DEPTH: usize = 3;
+/// Maximum number of statements rendered in any one list.
+const MAX_STATEMENTS: usize = 6;
+
+const DEFAULT_TIMEOUT_IN_SECONDS: u64 = 15;
+
+#[derive(Arbitrary, Debug)]
+struct Script(Vec<Stmt>);
+
+#[derive(Arbitrary, Debug)]
+enum Stmt {
+    Echo(Vec<Word>),
+    Printf(Word, Word),
+    Assign(Var, Word),
+    Arith(Var, Var, ArithOp, i8),
+    Status(bool),
+    Not(Box<Stmt>),
+    If(Cond, Vec<Stmt>, Vec<Stmt>),
+    For(Var, Vec<Word>, Vec<Stmt>),
+    While(Var, u8, Vec<Stmt>),
+    Case(Word, Lit, Vec<Stmt>),
+    Pipeline(Box<Stmt>, Filter),
+    And(Box<Stmt>, Box<Stmt>),
+    Or(Box<Stmt>, Box<Stmt>),
+    Subshell(Vec<Stmt>),
+    Group(Vec<Stmt>),
+    CommandSubstitution(Var, Box<Stmt>),
+    Function(Vec<Stmt>),
+    CallFunction(Vec<Word>),
+    Unset(Var),
+}
+
+#[derive(Arbitrary, Debug)]
+enum Cond {
+    StringTest(Word, StringOp, Word),
+    IntTest(Var, IntOp, i8),
+    Command(Box<Stmt>),
+}
+
+#[derive(Arbitrary, Debug)]
+enum Word {
+    Lit(Lit),
+    Var(Var),
+    QuotedVar(Var),
+    Default(Var, Lit),
+    Alternative(Var, Lit),
+    Length(Var),
+    StripPrefix(Var, Lit),
+    StripSuffix(Var, Lit),
+    Arith(Var, ArithOp, i8),
+    Status,
+    Arg(u8),
+    ArgCount,
+    AllArgs,
+    Concat(Lit, Var),
+}
+
+#[derive(Arbitrary, Clone, Copy, Debug)]
+enum Var {
+    A,
+    B,
+    C,
+}
+
+#[derive(Arbitrary, Clone, Copy, Debug)]
+enum Lit {
+    Empty,
+    Foo,
+    Bar,
+    Spaced,
+    Number,
+    Negative,
+    Glob,
+    Dash,
+}
+
+#[derive(Arbitrary, Clone, Copy, Debug)]
+enum ArithOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+    Shl,
+    Lt,
+    Eq,
+    And,
+    Or,
+}
+
+#[derive(Arbitrary, Clone, Copy, Debug)]
+enum StringOp {
+    Eq,
+    Ne,
+}
+
+#[derive(Arbitrary, Clone, Copy, Debug)]
+enum IntOp {
+    Eq,
+    Ne,
+    Lt,
+    Ge,
+}
+
+#[derive(Arbitrary, Clone, Copy, Debug)]
+enum Filter {
+    Cat,
+    CountLines,
+    Upper,
+    Head,
+    Sort,
+}
+
+impl Var {
+    fn name(self) -> &'static str {
+        match self {
+            Var::A => "a",
+            Var::B => "b",
+            Var::C => "c",
+        }
+    }
+}
+
+impl Lit {
+    fn text(self) -> &'static str {
+        match self {
+            Lit::Empty => "''",
+            Lit::Foo => "foo",
+            Lit::Bar => "bar",
+            Lit::Spaced => "'x  y'",
+            Lit::Number => "42",
+            Lit::Negative => "-7",
+            Lit::Glob => "'f*'",
+            Lit::Dash => "-",
+        }
+    }
+
+    /// Returns the literal's text as it may appear unquoted within a pattern or expansion.
+    fn pattern(self) -> &'static str {
+        match self {
+            Lit::Empty => "",
+            Lit::Spaced => "x",
+            Lit::Glob => "f*",
+            lit => lit.text(),
+        }
+    }
+}
+
+impl ArithOp {
+    fn text(self) -> &'static str {
+        match self {
+            ArithOp::Add => "+",
+            ArithOp::Sub => "-",
+            ArithOp::Mul => "*",
+            // N.B. Division and modulus by zero are errors in both shells, which is fine.
+            ArithOp::Div => "/",
+            ArithOp::Mod => "%",
+            ArithOp::Shl => "<<",
+            ArithOp::Lt => "<",
+            ArithOp::Eq => "==",
+            ArithOp::And => "&&",
+            ArithOp::Or => "||",
+        }
+    }
+}
+
+impl Filter {
+    fn text(self) -> &'static str {
+        match self {
+            Filter::Cat => "cat",
+            Filter::CountLines => "wc -l",
+            Filter::Upper => "tr a-z A-Z",
+            Filter::Head => "head -n 1",
+            Filter::Sort => "sort",
+        }
+    }
+}
+
+impl Word {
+    fn render(&self, out: &mut String) {
+        let _ = match self {
+            Word::Lit(lit) => write!(out, "{}", lit.text()),
+            Word::Var(var) => write!(out, "${}", var.name()),
+            Word::QuotedVar(var) => write!(out, "\"${}\"", var.name()),
+            Word::Default(var, lit) => write!(out, "\"${{{}:-{}}}\"", var.name(), lit.pattern()),
+            Word::Alternative(var, lit) => {
+                write!(out, "\"${{{}:+{}}}\"", var.name(), lit.pattern())
+            }
+            Word::Length(var) => write!(out, "${{#{}}}", var.name()),
+            Word::StripPrefix(var, lit) => {
+                write!(out, "\"${{{}#{}}}\"", var.name(), lit.pattern())
+            }
+            Word::StripSuffix(var, lit) => {
+                write!(out, "\"${{{}%%{}}}\"", var.name(), lit.pattern())
+            }
+            Word::Arith(var, op, n) => write!(out, "$(( {} {} {n} ))", var.name(), op.text()),
+            Word::Status => write!(out, "$?"),
+            // N.B. $0 is skipped, since it names the shell.
+            Word::Arg(n) => write!(out, "\"${}\"", n % 3 + 1),
+            Word::ArgCount => write!(out, "$#"),
+            Word::AllArgs => write!(out, "\"$@\""),
+            Word::Concat(lit, var) => write!(out, "{}\"${}\"", lit.pattern(), var.name()),
+        };
+    }
+}
+
+impl Cond {
+    fn render(&self, out: &mut String, depth: usize) {
+        match self {
+            Cond::StringTest(left, op, right) => {
+                out.push_str("[ ");
+                left.render(out);
+                out.push_str(match op {
+                    StringOp::Eq => " = ",
+                    StringOp::Ne => " != ",
+                });
+                right.render(out);
+                out.push_str(" ]");
+            }
+            Cond::IntTest(var, op, n) => {
+                let op = match op {
+                    IntOp::Eq => "-eq",
+                    IntOp::Ne => "-ne",
+                    IntOp::Lt => "-lt",
+                    IntOp::Ge => "-ge",
+                };
+                let _ = write!(out, "[ \"${{{}:-0}}\" {op} {n} ]", var.name());
+            }
+            Cond::Command(stmt) => stmt.render(out, depth + 1),
+        }
+    }
+}
+
+impl Stmt {
+    fn render(&self, out: &mut String, depth: usize) {
+        if depth > MAX_DEPTH {
+            out.push(':');
+            return;
+        }
+
+        match self {
+            Stmt::Echo(words) => {
+                out.push_str("echo");
+                render_words(words, out);
+            }
+            Stmt::Printf(first, second) => {
+                // N.B. The format string is fixed so that generated words can't introduce
+                // format directives.
+                out.push_str("printf '[%s] [%s]\\n' ");
+                first.render(out);
+                out.push(' ');
+                second.render(out);
+            }
+            Stmt::Assign(var, word) => {
+                let _ = write!(out, "{}=", var.name());
+                word.render(out);
+            }
+            Stmt::Arith(target, var, op, n) => {
+                let _ = write!(
+                    out,
+                    "{}=$(( ${{{}:-0}} {} {n} ))",
+                    target.name(),
+                    var.name(),
+                    op.text()
+                );
+            }
+            Stmt::Status(success) => out.push_str(if *success { "true" } else { "false" }),
+            Stmt::Not(stmt) => {
+                out.push_str("! ");
+                stmt.render(out, depth + 1);
+            }
+            Stmt::If(cond, then_stmts, else_stmts) => {
+                out.push_str("if ");
+                cond.render(out, depth);
+                out.push_str("; then ");
+                render_list(then_stmts, out, depth + 1);
+                if !else_stmts.is_empty() {
+                    out.push_str(" else ");
+                    render_list(else_stmts, out, depth + 1);
+                }
+                out.push_str(" fi");
+            }
+            Stmt::For(var, words, body) => {
+                let _ = write!(out, "for {} in", var.name());
+                render_words(words, out);
+                out.push_str("; do ");
+                render_list(body, out, depth + 1);
+                out.push_str(" done");
+            }
+            Stmt::While(var, limit, body) => {
+                // Loops are always bounded by a counter (one per nesting level), which the body
+                // can't modify.
+                let _ = write!(
+                    out,
+                    "_n{depth}=0; while [ $_n{depth} -lt {} ]; do _n{depth}=$((_n{depth} + 1)); {}=$_n{depth}; ",
+                    limit % 4,
+                    var.name()
+                );
+                render_list(body, out, depth + 1);
+                out.push_str(" done");
+            }
+            Stmt::Case(word, lit, body) => {
+                out.push_str("case ");
+                word.render(out);
+                let _ = write!(out, " in {}) ", lit.pattern());
+                render_list(body, out, depth + 1);
+                out.push_str(" ;; *) echo nomatch ;; esac");
+            }
+            Stmt::Pipeline(stmt, filter) => {
+                stmt.render(out, depth + 1);
+                let _ = write!(out, " | {}", filter.text());
+            }
+            Stmt::And(left, right) | Stmt::Or(left, right) => {
+                out.push_str("{ ");
+                left.render(out, depth + 1);
+                out.push_str(if matches!(self, Stmt::And(..)) {
+                    "; } && { "
+                } else {
+                    "; } || { "
+                });
+                right.render(out, depth + 1);
+                out.push_str("; }");
+            }
+            Stmt::Subshell(stmts) => {
+                out.push_str("( ");
+                render_list(stmts, out, depth + 1);
+                out.push_str(" )");
+            }
+            Stmt::Group(stmts) => {
+                out.push_str("{ ");
+                render_list(stmts, out, depth + 1);
+                out.push_str(" }");
+            }
+            Stmt::CommandSubstitution(var, stmt) => {
+                let _ = write!(out, "{}=\"$(", var.name());
+                stmt.render(out, depth + 1);
+                out.push_str(")\"");
+            }
+            Stmt::Function(body) => {
+                // N.B. Calls aren't rendered within the function's body, so it can't recurse.
+                out.push_str("f() { ");
+                render_list_without_calls(body, out, depth + 1);
+                out.push_str(" }");
+            }
+            Stmt::CallFunction(words) => {
+                out.push_str("{ type f >/dev/null 2>&1 && f");
+                render_words(words, out);
+                out.push_str("; }");
+            }
+            Stmt::Unset(var) => {
+                let _ = write!(out, "unset {}", var.name());
+            }
+        }
+    }
+
+    fn calls_function(&self) -> bool {
+        match self {
+            Stmt::CallFunction(_) => true,
+            Stmt::Not(stmt) | Stmt::Pipeline(stmt, _) | Stmt::CommandSubstitution(_, stmt) => {
+                stmt.calls_function()
+            }
+            Stmt::And(left, right) | Stmt::Or(left, right) => {
+                left.calls_function() || right.calls_function()
+            }
+            Stmt::If(cond, then_stmts, else_stmts) => {
+                matches!(cond, Cond::Command(stmt) if stmt.calls_function())
+                    || then_stmts.iter().any(Stmt::calls_function)
+                    || else_stmts.iter().any(Stmt::calls_function)
+            }
+            Stmt::For(_, _, stmts)
+            | Stmt::While(_, _, stmts)
+            | Stmt::Case(_, _, stmts)
+            | Stmt::Subshell(stmts)
+            | Stmt::Group(stmts)
+            | Stmt::Function(stmts) => stmts.iter().any(Stmt::calls_function),
+            _ => false,
+        }
+    }
+}
+
+fn render_words(words: &[Word], out: &mut String) {
+    for word in words.iter().take(MAX_STATEMENTS) {
+        out.push(' ');
+        word.render(out);
+    }
+}
+
+fn render_list(stmts: &[Stmt], out: &mut String, depth: usize) {
+    if stmts.is_empty() {
+        out.push(':');
+    }
+
+    for stmt in stmts.iter().take(MAX_STATEMENTS) {
+        stmt.render(out, depth);
+        out.push(';');
+    }
+}
+
+fn render_list_without_calls(stmts: &[Stmt], out: &mut String, depth: usize) {
+    let stmts: Vec<_> = stmts.iter().filter(|s| !s.calls_function()).collect();
+    if stmts.is_empty() {
+        out.push(':');
+    }
+
+    for stmt in stmts.into_iter().take(MAX_STATEMENTS) {
+        stmt.render(out, depth);
+        out.push(';');
+    }
+}
+
+impl Script {
+    fn render(&self) -> String {
+        let mut out = String::new();
+        for stmt in self.0.iter().take(MAX_STATEMENTS * 2) {
+            stmt.render(&mut out, 0);
+            out.push('\n');
+        }
+
+        out.push_str("echo \"status: $?\"\n");
+        out
+    }
+}
+
+struct ShellOutput {
+    stdout: String,
+    exit_code: Option<i32>,
+}
+
+/// Runs the script under the given shell, in a fresh temporary directory.
+fn run_shell(program: &str, args: &[&str], script: &str) -> Result<ShellOutput> {
+    let temp_dir = std::env::temp_dir().join(std::format!(
+        "brush-fuzz-exec-{}-{}",
+        std::process::id(),
+        program.replace('/', "_")
+    ));
+    std::fs::create_dir_all(&temp_dir)?;
+
+    let mut cmd = std::process::Command::new(program);
+    cmd.args(args)
+        .env_clear()
+        .env(
+            "PATH",
+            "/usr/local/sbin:/usr/local/bin:/usr/sbin:/usr/bin:/sbin:/bin",
+        )
+        .env("LC_ALL", "C")
+        .current_dir(&temp_dir);
+
+    let mut cmd = assert_cmd::Command::from_std(cmd);
+    cmd.timeout(std::time::Duration::from_secs(DEFAULT_TIMEOUT_IN_SECONDS));
+    cmd.write_stdin(script.as_bytes());
+
+    let output = cmd.output();
+    let _ = std::fs::remove_dir_all(&temp_dir);
+    let output = output?;
+
+    Ok(ShellOutput {
+        stdout: String::from_utf8_lossy(&output.stdout).to_string(),
+        exit_code: output.status.code(),
+    })
+}
+
+/// Saves a script whose execution diverged, returning the path it was saved to.
+fn save_repro(script: &str) -> Result<PathBuf> {
+    let repro_dir = std::env::var("BRUSH_FUZZ_REPRO_DIR").map_or_else(
+        |_| PathBuf::from("artifacts/fuzz_exec/repros"),
+        PathBuf::from,
+    );
+    std::fs::create_dir_all(&repro_dir)?;
+
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    std::hash::Hash::hash(script, &mut hasher);
+    let path = repro_dir.join(std::format!(
+        "{:016x}.sh",
+        std::hash::Hasher::finish(&hasher)
+    ));
+
+    std::fs::write(&path, script)?;
+    Ok(path)
+}
+
+fn exec_and_compare(script: &str) -> Result<()> {
+    let brush_path = std::env::var("BRUSH_PATH").unwrap_or_else(|_| String::from("brush"));
+    let test_result = run_shell(brush_path.as_str(), &["--norc", "--noprofile"], script)?;
+
+    let oracle_result = run_shell("bash", &["--norc", "--noprofile"], script)?;
+
+    if test_result.stdout != oracle_result.stdout
+        || test_result.exit_code != oracle_result.exit_code
+    {
+        let repro_path = save_repro(script)?;
+        return Err(anyhow::anyhow!(
+            "Mismatched execution results (repro saved to {}):\n--- script ---\n{script}--- oracle (exit: {:?}) ---\n{}--- brush (exit: {:?}) ---\n{}",
+            repro_path.display(),
+            oracle_result.exit_code,
+            oracle_result.stdout,
+            test_result.exit_code,
+            test_result.stdout,
+        ));
+    }
+
+    Ok(())
+}
+
+fuzz_target!(|input: Script| {
+    let script = input.render();
+    exec_and_compare(script.as_str()).unwrap();
+});
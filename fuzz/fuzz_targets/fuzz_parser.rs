@@ -0,0 +1,33 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+mod shared;
+
+fuzz_target!(|input: &str| {
+    let options = brush_parser::ParserOptions::default();
+    let source_info = brush_parser::SourceInfo::default();
+
+    let _ = shared::run_with_time_limit("parsing", || {
+        let mut parser = brush_parser::Parser::new(input.as_bytes(), &options, &source_info);
+        parser.parse()
+    });
+
+    // Parsing with error recovery must report errors if and only if parsing without it fails.
+    let Ok(tokens) = brush_parser::tokenize_str(input) else {
+        return;
+    };
+
+    let result = shared::run_with_time_limit("parsing tokens", || {
+        brush_parser::parse_tokens(&tokens, &options, &source_info)
+    });
+    let (_, errors) = shared::run_with_time_limit("parsing tokens with recovery", || {
+        brush_parser::parse_tokens_with_recovery(&tokens, &options, &source_info)
+    });
+
+    assert_eq!(
+        result.is_ok(),
+        errors.is_empty(),
+        "parsing with and without recovery disagree: {result:?} vs. {errors:?}"
+    );
+});
@@ -0,0 +1,36 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+mod shared;
+
+fuzz_target!(|input: &[u8]| {
+    // Feed the input in chunks whose size is derived from the input itself, so that chunk
+    // boundaries land partway through lines and UTF-8 sequences.
+    let chunk_size = input.first().map_or(1, |b| usize::from(b % 16) + 1);
+
+    let mut parser = brush_parser::StreamingParser::new(
+        &brush_parser::ParserOptions::default(),
+        &brush_parser::SourceInfo::default(),
+    );
+
+    shared::run_with_time_limit("streaming parsing", || {
+        for chunk in input.chunks(chunk_size) {
+            parser.feed(chunk);
+            while !matches!(
+                parser.poll(),
+                brush_parser::StreamingParseResult::NeedMoreInput
+            ) {}
+        }
+
+        while !matches!(
+            parser.finish(),
+            brush_parser::StreamingParseResult::NeedMoreInput
+        ) {}
+    });
+
+    assert!(
+        !parser.has_pending_input(),
+        "streaming parser retained input after finishing"
+    );
+});
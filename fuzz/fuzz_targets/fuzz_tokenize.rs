@@ -0,0 +1,11 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+mod shared;
+
+fuzz_target!(|input: &str| {
+    // Only crashes, hangs, and pathologically slow tokenization are of interest here; the
+    // input needn't tokenize successfully.
+    let _ = shared::run_with_time_limit("tokenizing", || brush_parser::tokenize_str(input));
+});
@@ -0,0 +1,33 @@
+//! Helpers shared by fuzz targets.
+
+/// Default limit on how long processing a single input may take.
+const DEFAULT_MAX_DURATION_IN_MILLIS: u64 = 2000;
+
+/// Runs the given function, panicking if it takes longer than the configured limit (which may
+/// be overridden via `BRUSH_FUZZ_MAX_MILLIS`). This flags inputs that trigger pathologically
+/// slow (e.g., quadratic) behavior, which libFuzzer's own timeout is too coarse to catch; true
+/// hangs are still left to that timeout.
+///
+/// # Arguments
+///
+/// * `what` - Description of the work being done, for reporting.
+/// * `f` - The function to run.
+pub fn run_with_time_limit<T>(what: &str, f: impl FnOnce() -> T) -> T {
+    let max_duration = std::time::Duration::from_millis(
+        std::env::var("BRUSH_FUZZ_MAX_MILLIS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(DEFAULT_MAX_DURATION_IN_MILLIS),
+    );
+
+    let start = std::time::Instant::now();
+    let result = f();
+    let elapsed = start.elapsed();
+
+    assert!(
+        elapsed <= max_duration,
+        "{what} took {elapsed:?}, exceeding the limit of {max_duration:?}"
+    );
+
+    result
+}
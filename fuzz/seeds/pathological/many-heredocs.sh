@@ -0,0 +1,200 @@
+cat <<EOF0 <<EOF0b
+body
+EOF0
+body
+EOF0b
+cat <<EOF1 <<EOF1b
+body
+EOF1
+body
+EOF1b
+cat <<EOF2 <<EOF2b
+body
+EOF2
+body
+EOF2b
+cat <<EOF3 <<EOF3b
+body
+EOF3
+body
+EOF3b
+cat <<EOF4 <<EOF4b
+body
+EOF4
+body
+EOF4b
+cat <<EOF5 <<EOF5b
+body
+EOF5
+body
+EOF5b
+cat <<EOF6 <<EOF6b
+body
+EOF6
+body
+EOF6b
+cat <<EOF7 <<EOF7b
+body
+EOF7
+body
+EOF7b
+cat <<EOF8 <<EOF8b
+body
+EOF8
+body
+EOF8b
+cat <<EOF9 <<EOF9b
+body
+EOF9
+body
+EOF9b
+cat <<EOF10 <<EOF10b
+body
+EOF10
+body
+EOF10b
+cat <<EOF11 <<EOF11b
+body
+EOF11
+body
+EOF11b
+cat <<EOF12 <<EOF12b
+body
+EOF12
+body
+EOF12b
+cat <<EOF13 <<EOF13b
+body
+EOF13
+body
+EOF13b
+cat <<EOF14 <<EOF14b
+body
+EOF14
+body
+EOF14b
+cat <<EOF15 <<EOF15b
+body
+EOF15
+body
+EOF15b
+cat <<EOF16 <<EOF16b
+body
+EOF16
+body
+EOF16b
+cat <<EOF17 <<EOF17b
+body
+EOF17
+body
+EOF17b
+cat <<EOF18 <<EOF18b
+body
+EOF18
+body
+EOF18b
+cat <<EOF19 <<EOF19b
+body
+EOF19
+body
+EOF19b
+cat <<EOF20 <<EOF20b
+body
+EOF20
+body
+EOF20b
+cat <<EOF21 <<EOF21b
+body
+EOF21
+body
+EOF21b
+cat <<EOF22 <<EOF22b
+body
+EOF22
+body
+EOF22b
+cat <<EOF23 <<EOF23b
+body
+EOF23
+body
+EOF23b
+cat <<EOF24 <<EOF24b
+body
+EOF24
+body
+EOF24b
+cat <<EOF25 <<EOF25b
+body
+EOF25
+body
+EOF25b
+cat <<EOF26 <<EOF26b
+body
+EOF26
+body
+EOF26b
+cat <<EOF27 <<EOF27b
+body
+EOF27
+body
+EOF27b
+cat <<EOF28 <<EOF28b
+body
+EOF28
+body
+EOF28b
+cat <<EOF29 <<EOF29b
+body
+EOF29
+body
+EOF29b
+cat <<EOF30 <<EOF30b
+body
+EOF30
+body
+EOF30b
+cat <<EOF31 <<EOF31b
+body
+EOF31
+body
+EOF31b
+cat <<EOF32 <<EOF32b
+body
+EOF32
+body
+EOF32b
+cat <<EOF33 <<EOF33b
+body
+EOF33
+body
+EOF33b
+cat <<EOF34 <<EOF34b
+body
+EOF34
+body
+EOF34b
+cat <<EOF35 <<EOF35b
+body
+EOF35
+body
+EOF35b
+cat <<EOF36 <<EOF36b
+body
+EOF36
+body
+EOF36b
+cat <<EOF37 <<EOF37b
+body
+EOF37
+body
+EOF37b
+cat <<EOF38 <<EOF38b
+body
+EOF38
+body
+EOF38b
+cat <<EOF39 <<EOF39b
+body
+EOF39
+body
+EOF39b
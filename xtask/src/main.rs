@@ -15,6 +15,10 @@ enum Command {
     GenerateMan(GenerateManArgs),
     /// Generate help content in markdown format.
     GenerateMarkdown(GenerateMarkdownArgs),
+    /// Update message catalogs to reflect the shell's current set of messages.
+    UpdateCatalogs(UpdateCatalogsArgs),
+    /// Format shell scripts in place.
+    Fmt(FmtArgs),
 }
 
 #[derive(Parser)]
@@ -31,12 +35,37 @@ struct GenerateMarkdownArgs {
     output_path: PathBuf,
 }
 
+#[derive(Parser)]
+struct UpdateCatalogsArgs {
+    /// Directory containing the catalogs.
+    #[clap(long = "dir", short = 'd', default_value = "brush-core/locales")]
+    catalog_dir: PathBuf,
+}
+
+#[derive(Parser)]
+struct FmtArgs {
+    /// Check whether the scripts are already formatted, without changing them.
+    #[clap(long = "check")]
+    check: bool,
+    /// Number of spaces per indentation level.
+    #[clap(long = "indent", default_value_t = 4)]
+    indent_width: usize,
+    /// Preferred maximum line width.
+    #[clap(long = "width", default_value_t = 100)]
+    line_width: usize,
+    /// Scripts to format.
+    #[clap(required = true)]
+    paths: Vec<PathBuf>,
+}
+
 fn main() -> Result<()> {
     let args = CommandLineArgs::parse();
 
     match &args.command {
         Command::GenerateMan(gen_args) => generate_man(gen_args),
         Command::GenerateMarkdown(gen_args) => generate_markdown(gen_args),
+        Command::UpdateCatalogs(update_args) => update_catalogs(update_args),
+        Command::Fmt(fmt_args) => fmt(fmt_args),
     }
 }
 
@@ -65,3 +94,70 @@ fn generate_markdown(args: &GenerateMarkdownArgs) -> Result<()> {
 
     Ok(())
 }
+
+fn update_catalogs(args: &UpdateCatalogsArgs) -> Result<()> {
+    // Extract all messages into a template, from which catalogs for new languages
+    // can be started.
+    let template = brush_core::i18n::Catalog::default().to_catalog_text();
+    std::fs::write(args.catalog_dir.join("template.catalog"), template)?;
+
+    // Update existing catalogs, preserving their translations.
+    for entry in std::fs::read_dir(&args.catalog_dir)? {
+        let path = entry?.path();
+        if path.extension().map_or(true, |ext| ext != "catalog")
+            || path.file_stem().is_some_and(|stem| stem == "template")
+        {
+            continue;
+        }
+
+        let catalog = brush_core::i18n::Catalog::parse(std::fs::read_to_string(&path)?.as_str());
+        std::fs::write(&path, catalog.to_catalog_text())?;
+    }
+
+    Ok(())
+}
+
+fn fmt(args: &FmtArgs) -> Result<()> {
+    let parser_options = brush_parser::ParserOptions {
+        capture_trivia: true,
+        ..brush_parser::ParserOptions::default()
+    };
+    let format_options = brush_parser::format::FormatOptions {
+        indent_width: args.indent_width,
+        max_line_width: args.line_width,
+    };
+
+    let mut unformatted_count = 0;
+    for path in &args.paths {
+        let contents = std::fs::read_to_string(path)?;
+        let source_info = brush_parser::SourceInfo {
+            source: path.display().to_string(),
+        };
+
+        let program = brush_parser::Parser::new(
+            std::io::BufReader::new(contents.as_bytes()),
+            &parser_options,
+            &source_info,
+        )
+        .parse()
+        .map_err(|e| anyhow::anyhow!("{}: {e}", path.display()))?;
+
+        let formatted = brush_parser::format::format_program(&program, &format_options);
+        if formatted == contents {
+            continue;
+        }
+
+        if args.check {
+            println!("{}", path.display());
+            unformatted_count += 1;
+        } else {
+            std::fs::write(path, formatted)?;
+        }
+    }
+
+    if unformatted_count > 0 {
+        anyhow::bail!("{unformatted_count} script(s) aren't formatted");
+    }
+
+    Ok(())
+}